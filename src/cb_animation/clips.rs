@@ -0,0 +1,258 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use super::skeleton::Pose;
+use super::{blend_rotations, AnimRotation, AnimVector, BoneId};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interpolation {
+    /// Hold each keyframe's value until the next keyframe.
+    Step,
+    Linear,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    pub tick: u32,
+    pub value: T,
+}
+
+/// The keyframes that animate one bone. Each of translation, rotation and scale may be keyed separately; a bone
+/// keeps its current value for anything without keyframes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub bone: BoneId,
+    pub interpolation: Interpolation,
+    pub translations: Vec<Keyframe<AnimVector>>,
+    pub rotations: Vec<Keyframe<AnimRotation>>,
+    pub scales: Vec<Keyframe<f32>>,
+}
+
+impl Track {
+    pub fn new(bone: BoneId, interpolation: Interpolation) -> Self {
+        return Self {
+            bone: bone,
+            interpolation: interpolation,
+            translations: vec![],
+            rotations: vec![],
+            scales: vec![],
+        };
+    }
+
+    pub fn add_translation(&mut self, tick: u32, translation: AnimVector) {
+        insert_keyframe(&mut self.translations, tick, translation);
+    }
+
+    pub fn add_rotation(&mut self, tick: u32, rotation: AnimRotation) {
+        insert_keyframe(&mut self.rotations, tick, rotation);
+    }
+
+    pub fn add_scale(&mut self, tick: u32, scale: f32) {
+        insert_keyframe(&mut self.scales, tick, scale);
+    }
+
+    /// The last tick with a keyframe.
+    pub fn last_tick(&self) -> u32 {
+        let last = |ticks: Option<u32>| ticks.unwrap_or(0);
+
+        return last(self.translations.last().map(|k| k.tick))
+            .max(last(self.rotations.last().map(|k| k.tick)))
+            .max(last(self.scales.last().map(|k| k.tick)));
+    }
+}
+
+/// Keeps keyframes ordered by tick, replacing any keyframe already on the tick.
+fn insert_keyframe<T>(keyframes: &mut Vec<Keyframe<T>>, tick: u32, value: T) {
+    let keyframe = Keyframe {
+        tick: tick,
+        value: value,
+    };
+
+    match keyframes.binary_search_by_key(&tick, |k| k.tick) {
+        Ok(i) => keyframes[i] = keyframe,
+        Err(i) => keyframes.insert(i, keyframe),
+    }
+}
+
+/// The value at the time, in ticks, holding the first and last keyframes beyond either end.
+fn sample_keyframes<T: Copy>(
+    keyframes: &Vec<Keyframe<T>>,
+    time: f32,
+    interpolation: Interpolation,
+    lerp: fn(&T, &T, f32) -> T,
+) -> Option<T> {
+    let first = keyframes.first()?;
+    if time <= first.tick as f32 {
+        return Some(first.value);
+    }
+
+    // The first keyframe after the time
+    let next = match keyframes.iter().position(|k| k.tick as f32 > time) {
+        Some(next) => next,
+        None => {
+            return keyframes.last().map(|k| k.value);
+        }
+    };
+
+    let before = &keyframes[next - 1];
+    let after = &keyframes[next];
+
+    match interpolation {
+        Interpolation::Step => {
+            return Some(before.value);
+        }
+        Interpolation::Linear => {
+            let t = (time - before.tick as f32) / (after.tick - before.tick) as f32;
+            return Some(lerp(&before.value, &after.value, t));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    pub name: String,
+    pub duration_ticks: u32,
+    /// Looping clips wrap back to the start, others hold their last frame.
+    pub looping: bool,
+    pub tracks: Vec<Track>,
+}
+
+impl AnimationClip {
+    pub fn new(name: &str, duration_ticks: u32, looping: bool) -> Self {
+        return Self {
+            name: name.to_string(),
+            duration_ticks: duration_ticks,
+            looping: looping,
+            tracks: vec![],
+        };
+    }
+
+    /// Add a track, extending the clip to cover its keyframes.
+    pub fn add_track(&mut self, track: Track) {
+        self.duration_ticks = self.duration_ticks.max(track.last_tick());
+        self.tracks.push(track);
+    }
+
+    /// The time into the clip after playing for the given ticks.
+    pub fn clip_time(&self, ticks_played: f32) -> f32 {
+        if self.duration_ticks == 0 {
+            return 0.0;
+        }
+
+        let duration = self.duration_ticks as f32;
+        if self.looping {
+            return ticks_played % duration;
+        }
+
+        return ticks_played.min(duration);
+    }
+
+    pub fn is_finished(&self, ticks_played: u32) -> bool {
+        return !self.looping && ticks_played >= self.duration_ticks;
+    }
+
+    /// Write the clip's animated values into the pose after playing for the given ticks. The ticks may be fractional
+    /// when drawing between simulation ticks.
+    pub fn sample(&self, ticks_played: f32, pose: &mut Pose) {
+        let time = self.clip_time(ticks_played);
+
+        for track in self.tracks.iter() {
+            let local = &mut pose.locals[track.bone];
+
+            if let Some(translation) =
+                sample_keyframes(&track.translations, time, track.interpolation, |a, b, t| {
+                    a.lerp(b, t)
+                })
+            {
+                local.translation = translation;
+            }
+
+            if let Some(rotation) =
+                sample_keyframes(&track.rotations, time, track.interpolation, blend_rotations)
+            {
+                local.rotation = rotation;
+            }
+
+            if let Some(scale) =
+                sample_keyframes(&track.scales, time, track.interpolation, |a, b, t| {
+                    a + (b - a) * t
+                })
+            {
+                local.scale = scale;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_animation::BoneTransform;
+
+    fn sliding_clip(interpolation: Interpolation, looping: bool) -> AnimationClip {
+        let mut track = Track::new(0, interpolation);
+        track.add_translation(10, AnimVector::new(10.0, 0.0, 0.0));
+        track.add_translation(0, AnimVector::new(0.0, 0.0, 0.0));
+
+        let mut clip = AnimationClip::new("slide", 0, looping);
+        clip.add_track(track);
+        return clip;
+    }
+
+    fn pose() -> Pose {
+        return Pose {
+            locals: vec![BoneTransform::identity()],
+        };
+    }
+
+    #[test]
+    fn track_add_translation_out_of_order_keeps_keyframes_sorted() {
+        let clip = sliding_clip(Interpolation::Linear, false);
+
+        let ticks: Vec<u32> = clip.tracks[0].translations.iter().map(|k| k.tick).collect();
+
+        assert_eq!(vec![0, 10], ticks);
+        assert_eq!(10, clip.duration_ticks);
+    }
+
+    #[test]
+    fn clip_sample_linear_interpolates_between_keyframes() {
+        let clip = sliding_clip(Interpolation::Linear, false);
+        let mut pose = pose();
+
+        clip.sample(2.5, &mut pose);
+
+        assert_eq!(2.5, pose.locals[0].translation.x);
+    }
+
+    #[test]
+    fn clip_sample_step_holds_previous_keyframe() {
+        let clip = sliding_clip(Interpolation::Step, false);
+        let mut pose = pose();
+
+        clip.sample(9.0, &mut pose);
+
+        assert_eq!(0.0, pose.locals[0].translation.x);
+    }
+
+    #[test]
+    fn clip_sample_looping_wraps_to_start() {
+        let clip = sliding_clip(Interpolation::Linear, true);
+        let mut pose = pose();
+
+        clip.sample(13.0, &mut pose);
+
+        assert_eq!(3.0, pose.locals[0].translation.x);
+        assert_eq!(false, clip.is_finished(13));
+    }
+
+    #[test]
+    fn clip_sample_not_looping_holds_last_keyframe() {
+        let clip = sliding_clip(Interpolation::Linear, false);
+        let mut pose = pose();
+
+        clip.sample(13.0, &mut pose);
+
+        assert_eq!(10.0, pose.locals[0].translation.x);
+        assert_eq!(true, clip.is_finished(13));
+    }
+}
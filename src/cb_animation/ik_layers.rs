@@ -0,0 +1,164 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_inverse_kinematics;
+use cb_inverse_kinematics::{fabrik, CbMatrix, IkRig};
+
+use super::skeleton::{Pose, Skeleton};
+use super::{blend_rotations, AnimRotation, AnimVector, BoneId};
+
+/// Bends a chain of bones in a sampled pose so its last bone reaches a target, such as planting a foot on uneven
+/// ground or aiming a weapon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IkLayer {
+    chain: Vec<BoneId>,
+    /// Where the last bone of the chain should reach, in the skeleton's space.
+    pub target: AnimVector,
+    pub pole: Option<AnimVector>,
+    /// How much of the correction is applied, from 0 for the sampled pose to 1 for the solved pose.
+    pub weight: f32,
+}
+
+impl IkLayer {
+    /// Create a layer for the chain of bones, listed from its root, where each bone is the child of the one before.
+    pub fn new(
+        skeleton: &Skeleton,
+        chain: Vec<BoneId>,
+        target: AnimVector,
+    ) -> Result<Self, String> {
+        if chain.len() < 2 {
+            return Err("An IK chain needs at least two bones.".to_string());
+        }
+
+        for pair in chain.windows(2) {
+            let is_child = skeleton
+                .bone(pair[1])
+                .map_or(false, |bone| bone.parent == Some(pair[0]));
+
+            if !is_child {
+                return Err(format!(
+                    "Bone {} in the IK chain is not a child of bone {}.",
+                    pair[1], pair[0]
+                ));
+            }
+        }
+
+        return Ok(Self {
+            chain: chain,
+            target: target,
+            pole: None,
+            weight: 1.0,
+        });
+    }
+
+    pub fn chain(&self) -> &Vec<BoneId> {
+        return &self.chain;
+    }
+
+    /// Solve the chain from the pose and rotate its bones to match.
+    pub fn apply(&self, skeleton: &Skeleton, pose: &mut Pose) {
+        if self.weight <= 0.0 {
+            return;
+        }
+
+        let world = pose.world_transforms(skeleton);
+
+        let mut rig = IkRig::empty();
+        for bone in self.chain.iter() {
+            let p = world[*bone].translation;
+            rig.add_joint(CbMatrix::new(p.x, p.y, p.z), None);
+        }
+
+        rig.target = Some(CbMatrix::new(self.target.x, self.target.y, self.target.z));
+        rig.pole = self.pole.map(|p| CbMatrix::new(p.x, p.y, p.z));
+        fabrik(&mut rig);
+
+        let solved: Vec<AnimVector> = (0..self.chain.len())
+            .map(|i| {
+                let p = rig.get_child_position(i);
+                AnimVector::new(p.x, p.y, p.z)
+            })
+            .collect();
+
+        // Turn each bone so its child lands on the solved joint, working out from the root as each turn moves the
+        // bones after it
+        for i in 0..self.chain.len() - 1 {
+            let world = pose.world_transforms(skeleton);
+            let (bone, child) = (self.chain[i], self.chain[i + 1]);
+
+            let current = world[child].translation - world[bone].translation;
+            let wanted = solved[i + 1] - world[bone].translation;
+            let turn = AnimRotation::rotation_between(&current, &wanted)
+                .unwrap_or(AnimRotation::identity());
+
+            let parent_rotation = match skeleton.bone(bone).and_then(|b| b.parent) {
+                Some(parent) => world[parent].rotation,
+                None => AnimRotation::identity(),
+            };
+
+            let sampled = pose.locals[bone].rotation;
+            let corrected = parent_rotation.inverse() * turn * world[bone].rotation;
+
+            pose.locals[bone].rotation = blend_rotations(&sampled, &corrected, self.weight);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_animation::BoneTransform;
+
+    fn leg() -> Skeleton {
+        let mut skeleton = Skeleton::new();
+        let down = BoneTransform::new(
+            AnimVector::new(0.0, 0.0, -1.0),
+            AnimRotation::identity(),
+            1.0,
+        );
+
+        let hip = skeleton
+            .add_bone("hip", None, BoneTransform::identity())
+            .unwrap();
+        let knee = skeleton.add_bone("knee", Some(hip), down).unwrap();
+        skeleton.add_bone("foot", Some(knee), down).unwrap();
+
+        return skeleton;
+    }
+
+    #[test]
+    fn ik_layer_new_broken_chain_returns_err() {
+        let skeleton = leg();
+
+        let result = IkLayer::new(&skeleton, vec![0, 2], AnimVector::zeros());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ik_layer_apply_moves_end_bone_to_target() {
+        let skeleton = leg();
+        let mut pose = skeleton.rest_pose();
+        let target = AnimVector::new(0.5, 0.0, -1.5);
+        let mut layer = IkLayer::new(&skeleton, vec![0, 1, 2], target).unwrap();
+        layer.pole = Some(AnimVector::new(2.0, 0.0, -1.0));
+
+        layer.apply(&skeleton, &mut pose);
+
+        let world = pose.world_transforms(&skeleton);
+        assert!((world[2].translation - target).norm() < 0.05);
+        assert!(world[1].translation.x > 0.0);
+    }
+
+    #[test]
+    fn ik_layer_apply_no_weight_keeps_sampled_pose() {
+        let skeleton = leg();
+        let mut pose = skeleton.rest_pose();
+        let mut layer =
+            IkLayer::new(&skeleton, vec![0, 1, 2], AnimVector::new(1.0, 0.0, -1.0)).unwrap();
+        layer.weight = 0.0;
+
+        layer.apply(&skeleton, &mut pose);
+
+        assert_eq!(skeleton.rest_pose(), pose);
+    }
+}
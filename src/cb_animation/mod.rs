@@ -0,0 +1,218 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+
+pub mod clips;
+pub mod ik_layers;
+pub mod skeleton;
+pub mod state_machine;
+
+/*
+    Skeletal animation. A skeleton is a hierarchy of bones, each with a rest transform relative to its parent, and a
+    pose is a local transform for every bone. Clips hold keyframed tracks that are sampled into a pose, an animator
+    moves through the states of a state machine where each state is a tree of blended clips, and IK layers then bend
+    chains of bones in the sampled pose towards targets.
+
+    Playback is driven by simulation ticks rather than wall clock time, so every machine is in the same state on the
+    same tick. Sampling uses floats like the cosmetic IK solver, so poses are only for drawing.
+*/
+
+use crate::cb_simulation;
+use cb_simulation::TICKS_PER_SECOND;
+
+use clips::AnimationClip;
+use skeleton::Skeleton;
+use state_machine::AnimationStateMachine;
+
+pub type AnimVector = na::Vector3<f32>;
+pub type AnimRotation = na::UnitQuaternion<f32>;
+
+pub type BoneId = usize;
+pub type ClipId = usize;
+pub type StateId = usize;
+
+/// The nearest tick to a time in seconds.
+pub fn seconds_to_ticks(seconds: f32) -> u32 {
+    return (seconds * TICKS_PER_SECOND as f32).round().max(0.0) as u32;
+}
+
+/// The position, rotation and uniform scale of a bone, relative to its parent.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoneTransform {
+    pub translation: AnimVector,
+    pub rotation: AnimRotation,
+    pub scale: f32,
+}
+
+impl BoneTransform {
+    pub fn new(translation: AnimVector, rotation: AnimRotation, scale: f32) -> Self {
+        return Self {
+            translation: translation,
+            rotation: rotation,
+            scale: scale,
+        };
+    }
+
+    pub fn identity() -> Self {
+        return Self::new(AnimVector::zeros(), AnimRotation::identity(), 1.0);
+    }
+
+    /// Places a child transform, given relative to this one, into this transform's parent space.
+    pub fn then(&self, child: &BoneTransform) -> Self {
+        return Self::new(
+            self.translation + self.rotation * (child.translation * self.scale),
+            self.rotation * child.rotation,
+            self.scale * child.scale,
+        );
+    }
+
+    /// Mixes towards the other transform, where a weight of 0 is this transform and 1 is the other.
+    pub fn blend(&self, other: &BoneTransform, weight: f32) -> Self {
+        return Self::new(
+            self.translation.lerp(&other.translation, weight),
+            blend_rotations(&self.rotation, &other.rotation, weight),
+            self.scale + (other.scale - self.scale) * weight,
+        );
+    }
+
+    pub fn to_matrix(&self) -> na::Matrix4<f32> {
+        return na::Matrix4::new_translation(&self.translation)
+            * self.rotation.to_homogeneous()
+            * na::Matrix4::new_scaling(self.scale);
+    }
+}
+
+/// Normalized lerp along the shortest arc, which unlike slerp is well behaved for opposite rotations.
+pub fn blend_rotations(a: &AnimRotation, b: &AnimRotation, weight: f32) -> AnimRotation {
+    let b = if a.coords.dot(&b.coords) < 0.0 {
+        AnimRotation::new_unchecked(-b.into_inner())
+    } else {
+        *b
+    };
+
+    return a.nlerp(&b, weight);
+}
+
+/// Everything needed to animate one kind of model: its skeleton, its clips and the states that play them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationSet {
+    pub name: String,
+    pub skeleton: Skeleton,
+    pub clips: Vec<AnimationClip>,
+    pub state_machine: AnimationStateMachine,
+}
+
+impl AnimationSet {
+    pub fn new(name: &str, skeleton: Skeleton) -> Self {
+        return Self {
+            name: name.to_string(),
+            skeleton: skeleton,
+            clips: vec![],
+            state_machine: AnimationStateMachine::new(),
+        };
+    }
+
+    pub fn add_clip(&mut self, clip: AnimationClip) -> Result<ClipId, String> {
+        if let Some(track) = clip
+            .tracks
+            .iter()
+            .find(|track| track.bone >= self.skeleton.len())
+        {
+            return Err(format!(
+                "Clip '{}' animates bone {}, but '{}' only has {} bones.",
+                clip.name,
+                track.bone,
+                self.name,
+                self.skeleton.len()
+            ));
+        }
+
+        self.clips.push(clip);
+        return Ok(self.clips.len() - 1);
+    }
+
+    pub fn find_clip(&self, name: &str) -> Option<ClipId> {
+        return self.clips.iter().position(|clip| clip.name == name);
+    }
+}
+
+/// All loaded animation sets. Animation components refer to their set by index.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AnimationLibrary {
+    sets: Vec<AnimationSet>,
+}
+
+impl AnimationLibrary {
+    pub fn new() -> Self {
+        return Self { sets: vec![] };
+    }
+
+    pub fn add(&mut self, set: AnimationSet) -> Result<usize, String> {
+        if self.index_of(&set.name).is_some() {
+            return Err(format!("Animation set '{}' is already loaded.", set.name));
+        }
+
+        set.state_machine.validate(&set.clips)?;
+
+        self.sets.push(set);
+        return Ok(self.sets.len() - 1);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&AnimationSet> {
+        return self.sets.get(index);
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        return self.sets.iter().position(|set| set.name == name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bone_transform_then_rotates_and_scales_child_translation() {
+        let parent = BoneTransform::new(
+            AnimVector::new(1.0, 0.0, 0.0),
+            AnimRotation::from_axis_angle(&AnimVector::z_axis(), std::f32::consts::FRAC_PI_2),
+            2.0,
+        );
+        let child = BoneTransform::new(
+            AnimVector::new(1.0, 0.0, 0.0),
+            AnimRotation::identity(),
+            1.0,
+        );
+
+        let placed = parent.then(&child);
+
+        assert!((placed.translation - AnimVector::new(1.0, 2.0, 0.0)).norm() < 0.0001);
+        assert_eq!(2.0, placed.scale);
+    }
+
+    #[test]
+    fn blend_rotations_opposite_hemispheres_takes_shortest_arc() {
+        let a = AnimRotation::from_axis_angle(&AnimVector::z_axis(), 0.1);
+        let b = AnimRotation::new_unchecked(
+            -AnimRotation::from_axis_angle(&AnimVector::z_axis(), 0.3).into_inner(),
+        );
+
+        let halfway = blend_rotations(&a, &b, 0.5);
+
+        assert!((halfway.angle() - 0.2).abs() < 0.001);
+    }
+
+    #[test]
+    fn animation_set_add_clip_unknown_bone_returns_err() {
+        let mut skeleton = Skeleton::new();
+        skeleton
+            .add_bone("root", None, BoneTransform::identity())
+            .unwrap();
+        let mut set = AnimationSet::new("test", skeleton);
+
+        let mut clip = AnimationClip::new("bad", 10, true);
+        clip.add_track(clips::Track::new(3, clips::Interpolation::Linear));
+
+        assert!(set.add_clip(clip).is_err());
+    }
+}
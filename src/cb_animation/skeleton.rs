@@ -0,0 +1,167 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use super::{BoneId, BoneTransform};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bone {
+    pub name: String,
+    pub parent: Option<BoneId>,
+    /// The bone's transform relative to its parent when no animation plays.
+    pub rest: BoneTransform,
+}
+
+/// A hierarchy of bones. Parents are always added before their children, so walking the bones in order visits every
+/// parent first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skeleton {
+    bones: Vec<Bone>,
+}
+
+impl Skeleton {
+    pub fn new() -> Self {
+        return Self { bones: vec![] };
+    }
+
+    pub fn add_bone(
+        &mut self,
+        name: &str,
+        parent: Option<BoneId>,
+        rest: BoneTransform,
+    ) -> Result<BoneId, String> {
+        if self.find_bone(name).is_some() {
+            return Err(format!("The skeleton already has a bone named '{}'.", name));
+        }
+
+        if let Some(parent) = parent {
+            if parent >= self.bones.len() {
+                return Err(format!(
+                    "Bone '{}' has parent {}, which has not been added.",
+                    name, parent
+                ));
+            }
+        }
+
+        self.bones.push(Bone {
+            name: name.to_string(),
+            parent: parent,
+            rest: rest,
+        });
+
+        return Ok(self.bones.len() - 1);
+    }
+
+    pub fn bones(&self) -> &Vec<Bone> {
+        return &self.bones;
+    }
+
+    pub fn bone(&self, id: BoneId) -> Option<&Bone> {
+        return self.bones.get(id);
+    }
+
+    pub fn find_bone(&self, name: &str) -> Option<BoneId> {
+        return self.bones.iter().position(|bone| bone.name == name);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.bones.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bones.is_empty();
+    }
+
+    pub fn rest_pose(&self) -> Pose {
+        return Pose {
+            locals: self.bones.iter().map(|bone| bone.rest).collect(),
+        };
+    }
+}
+
+/// A transform for every bone of a skeleton, relative to each bone's parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pose {
+    pub locals: Vec<BoneTransform>,
+}
+
+impl Pose {
+    /// The transform of every bone in the skeleton's space.
+    pub fn world_transforms(&self, skeleton: &Skeleton) -> Vec<BoneTransform> {
+        let mut world: Vec<BoneTransform> = Vec::with_capacity(self.locals.len());
+
+        for (bone, local) in skeleton.bones().iter().zip(self.locals.iter()) {
+            let transform = match bone.parent {
+                Some(parent) => world[parent].then(local),
+                None => *local,
+            };
+
+            world.push(transform);
+        }
+
+        return world;
+    }
+
+    /// Mixes towards the other pose, where a weight of 0 is this pose and 1 is the other.
+    pub fn blend(&self, other: &Pose, weight: f32) -> Pose {
+        return Pose {
+            locals: self
+                .locals
+                .iter()
+                .zip(other.locals.iter())
+                .map(|(a, b)| a.blend(b, weight))
+                .collect(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_animation::{AnimRotation, AnimVector};
+
+    fn arm() -> Skeleton {
+        let mut skeleton = Skeleton::new();
+        let offset = BoneTransform::new(
+            AnimVector::new(1.0, 0.0, 0.0),
+            AnimRotation::identity(),
+            1.0,
+        );
+
+        let shoulder = skeleton
+            .add_bone("shoulder", None, BoneTransform::identity())
+            .unwrap();
+        let elbow = skeleton.add_bone("elbow", Some(shoulder), offset).unwrap();
+        skeleton.add_bone("hand", Some(elbow), offset).unwrap();
+
+        return skeleton;
+    }
+
+    #[test]
+    fn skeleton_add_bone_missing_parent_returns_err() {
+        let mut skeleton = Skeleton::new();
+
+        let result = skeleton.add_bone("orphan", Some(0), BoneTransform::identity());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn skeleton_add_bone_duplicate_name_returns_err() {
+        let mut skeleton = arm();
+
+        let result = skeleton.add_bone("hand", Some(0), BoneTransform::identity());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn pose_world_transforms_rotated_parent_moves_children() {
+        let skeleton = arm();
+        let mut pose = skeleton.rest_pose();
+        pose.locals[0].rotation =
+            AnimRotation::from_axis_angle(&AnimVector::z_axis(), std::f32::consts::FRAC_PI_2);
+
+        let world = pose.world_transforms(&skeleton);
+
+        assert!((world[2].translation - AnimVector::new(0.0, 2.0, 0.0)).norm() < 0.0001);
+    }
+}
@@ -0,0 +1,489 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use std::collections::HashMap;
+
+use super::clips::AnimationClip;
+use super::skeleton::Pose;
+use super::{AnimationSet, ClipId, StateId};
+
+/// A tree of clips mixed together by the animator's parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlendNode {
+    Clip(ClipId),
+    /// Children placed along a parameter, ordered by their position. The two children either side of the
+    /// parameter's value are mixed, such as blending walk and run cycles by speed.
+    Blend1d {
+        parameter: String,
+        children: Vec<(f32, BlendNode)>,
+    },
+}
+
+impl BlendNode {
+    pub fn blend_1d(parameter: &str, mut children: Vec<(f32, BlendNode)>) -> Self {
+        children.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        return BlendNode::Blend1d {
+            parameter: parameter.to_string(),
+            children: children,
+        };
+    }
+
+    /// Sample every clip in the tree after playing for the given ticks, on top of the rest pose.
+    pub fn sample(
+        &self,
+        ticks_played: f32,
+        clips: &Vec<AnimationClip>,
+        parameters: &HashMap<String, f32>,
+        rest: &Pose,
+    ) -> Pose {
+        match self {
+            BlendNode::Clip(clip) => {
+                let mut pose = rest.clone();
+                clips[*clip].sample(ticks_played, &mut pose);
+                return pose;
+            }
+            BlendNode::Blend1d {
+                parameter,
+                children,
+            } => {
+                let value = parameters.get(parameter).copied().unwrap_or(0.0);
+
+                // The first child placed past the value
+                let after = match children.iter().position(|(at, _)| *at > value) {
+                    Some(after) => after,
+                    None => children.len() - 1,
+                };
+
+                if after == 0 || value >= children[after].0 {
+                    return children[after]
+                        .1
+                        .sample(ticks_played, clips, parameters, rest);
+                }
+
+                let (before_at, before) = &children[after - 1];
+                let (after_at, after) = &children[after];
+                let weight = (value - before_at) / (after_at - before_at);
+
+                return before
+                    .sample(ticks_played, clips, parameters, rest)
+                    .blend(&after.sample(ticks_played, clips, parameters, rest), weight);
+            }
+        }
+    }
+
+    /// Whether every clip in the tree has played through, which is never for looping clips.
+    pub fn is_finished(&self, ticks_played: u32, clips: &Vec<AnimationClip>) -> bool {
+        match self {
+            BlendNode::Clip(clip) => clips[*clip].is_finished(ticks_played),
+            BlendNode::Blend1d { children, .. } => children
+                .iter()
+                .all(|(_, child)| child.is_finished(ticks_played, clips)),
+        }
+    }
+
+    fn validate(&self, clips: &Vec<AnimationClip>) -> Result<(), String> {
+        match self {
+            BlendNode::Clip(clip) => {
+                if *clip >= clips.len() {
+                    return Err(format!("Clip {} does not exist.", clip));
+                }
+            }
+            BlendNode::Blend1d {
+                parameter,
+                children,
+            } => {
+                if children.is_empty() {
+                    return Err(format!("The blend on '{}' has no children.", parameter));
+                }
+
+                for (_, child) in children.iter() {
+                    child.validate(clips)?;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationState {
+    pub name: String,
+    pub node: BlendNode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Above {
+        parameter: String,
+        value: f32,
+    },
+    Below {
+        parameter: String,
+        value: f32,
+    },
+    /// The current state's clips have all played through.
+    Finished,
+}
+
+impl Condition {
+    pub fn above(parameter: &str, value: f32) -> Self {
+        return Condition::Above {
+            parameter: parameter.to_string(),
+            value: value,
+        };
+    }
+
+    pub fn below(parameter: &str, value: f32) -> Self {
+        return Condition::Below {
+            parameter: parameter.to_string(),
+            value: value,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// The state the transition leaves, or any state if none.
+    pub from: Option<StateId>,
+    pub to: StateId,
+    pub condition: Condition,
+    /// How long the old state fades out over.
+    pub blend_ticks: u32,
+}
+
+/// The states of an animation set and the transitions between them. Transitions are checked in the order they were
+/// added, and the first one met is taken. The first state added is where animators start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationStateMachine {
+    pub states: Vec<AnimationState>,
+    pub transitions: Vec<Transition>,
+}
+
+impl AnimationStateMachine {
+    pub fn new() -> Self {
+        return Self {
+            states: vec![],
+            transitions: vec![],
+        };
+    }
+
+    pub fn add_state(&mut self, name: &str, node: BlendNode) -> StateId {
+        self.states.push(AnimationState {
+            name: name.to_string(),
+            node: node,
+        });
+
+        return self.states.len() - 1;
+    }
+
+    pub fn add_transition(
+        &mut self,
+        from: Option<StateId>,
+        to: StateId,
+        condition: Condition,
+        blend_ticks: u32,
+    ) {
+        self.transitions.push(Transition {
+            from: from,
+            to: to,
+            condition: condition,
+            blend_ticks: blend_ticks,
+        });
+    }
+
+    pub fn find_state(&self, name: &str) -> Option<StateId> {
+        return self.states.iter().position(|state| state.name == name);
+    }
+
+    pub fn validate(&self, clips: &Vec<AnimationClip>) -> Result<(), String> {
+        if self.states.is_empty() {
+            return Err("The state machine has no states.".to_string());
+        }
+
+        for state in self.states.iter() {
+            state
+                .node
+                .validate(clips)
+                .map_err(|e| format!("State '{}' is invalid: {}", state.name, e))?;
+        }
+
+        for transition in self.transitions.iter() {
+            let states = self.states.len();
+            if transition.to >= states || transition.from.map_or(false, |from| from >= states) {
+                return Err(format!(
+                    "A transition to state {} refers to a state that does not exist.",
+                    transition.to
+                ));
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Fade {
+    from: StateId,
+    from_ticks: u32,
+    elapsed_ticks: u32,
+    length_ticks: u32,
+}
+
+/// Plays an animation set's state machine for one entity. Gameplay systems set parameters to drive the transitions
+/// and blends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animator {
+    state: StateId,
+    /// How long the current state has played for.
+    state_ticks: u32,
+    fade: Option<Fade>,
+    parameters: HashMap<String, f32>,
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        return Self {
+            state: 0,
+            state_ticks: 0,
+            fade: None,
+            parameters: HashMap::new(),
+        };
+    }
+
+    pub fn state(&self) -> StateId {
+        return self.state;
+    }
+
+    pub fn state_ticks(&self) -> u32 {
+        return self.state_ticks;
+    }
+
+    pub fn is_fading(&self) -> bool {
+        return self.fade.is_some();
+    }
+
+    pub fn set_parameter(&mut self, parameter: &str, value: f32) {
+        self.parameters.insert(parameter.to_string(), value);
+    }
+
+    pub fn parameter(&self, parameter: &str) -> f32 {
+        return self.parameters.get(parameter).copied().unwrap_or(0.0);
+    }
+
+    /// Switch to the state, fading out of the current one over the given ticks.
+    pub fn play(&mut self, state: StateId, blend_ticks: u32) {
+        self.fade = if blend_ticks > 0 {
+            Some(Fade {
+                from: self.state,
+                from_ticks: self.state_ticks,
+                elapsed_ticks: 0,
+                length_ticks: blend_ticks,
+            })
+        } else {
+            None
+        };
+
+        self.state = state;
+        self.state_ticks = 0;
+    }
+
+    /// Advance playback by one simulation tick, then take the first transition that is met.
+    pub fn tick(&mut self, set: &AnimationSet) {
+        self.state_ticks += 1;
+
+        if let Some(fade) = &mut self.fade {
+            fade.from_ticks += 1;
+            fade.elapsed_ticks += 1;
+
+            if fade.elapsed_ticks >= fade.length_ticks {
+                self.fade = None;
+            }
+        }
+
+        let machine = &set.state_machine;
+        let transition = machine.transitions.iter().find(|transition| {
+            if transition.to == self.state
+                || transition.from.map_or(false, |from| from != self.state)
+            {
+                return false;
+            }
+
+            match &transition.condition {
+                Condition::Above { parameter, value } => self.parameter(parameter) > *value,
+                Condition::Below { parameter, value } => self.parameter(parameter) < *value,
+                Condition::Finished => machine.states[self.state]
+                    .node
+                    .is_finished(self.state_ticks, &set.clips),
+            }
+        });
+
+        if let Some(transition) = transition {
+            let (to, blend_ticks) = (transition.to, transition.blend_ticks);
+            self.play(to, blend_ticks);
+        }
+    }
+
+    /// The pose for the current tick, mixing in the state being faded out of.
+    pub fn sample(&self, set: &AnimationSet) -> Pose {
+        let rest = set.skeleton.rest_pose();
+        let states = &set.state_machine.states;
+
+        let pose = states[self.state].node.sample(
+            self.state_ticks as f32,
+            &set.clips,
+            &self.parameters,
+            &rest,
+        );
+
+        match self.fade {
+            Some(fade) => {
+                let from = states[fade.from].node.sample(
+                    fade.from_ticks as f32,
+                    &set.clips,
+                    &self.parameters,
+                    &rest,
+                );
+
+                return from.blend(&pose, fade.elapsed_ticks as f32 / fade.length_ticks as f32);
+            }
+            None => {
+                return pose;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_animation::clips::{Interpolation, Track};
+    use crate::cb_animation::skeleton::Skeleton;
+    use crate::cb_animation::{AnimVector, BoneTransform};
+
+    /// A one bone set whose clips hold the bone at x = 0 (idle), 1 (walk) and 2 (run), plus a 4 tick wave at x = 5.
+    fn set() -> AnimationSet {
+        let mut skeleton = Skeleton::new();
+        skeleton
+            .add_bone("root", None, BoneTransform::identity())
+            .unwrap();
+
+        let mut set = AnimationSet::new("test", skeleton);
+        for (name, x, ticks, looping) in [
+            ("idle", 0.0, 10, true),
+            ("walk", 1.0, 10, true),
+            ("run", 2.0, 10, true),
+            ("wave", 5.0, 4, false),
+        ]
+        .iter()
+        {
+            let mut track = Track::new(0, Interpolation::Linear);
+            track.add_translation(0, AnimVector::new(*x, 0.0, 0.0));
+            track.add_translation(*ticks, AnimVector::new(*x, 0.0, 0.0));
+
+            let mut clip = AnimationClip::new(name, 0, *looping);
+            clip.add_track(track);
+            set.add_clip(clip).unwrap();
+        }
+
+        let machine = &mut set.state_machine;
+        let idle = machine.add_state("idle", BlendNode::Clip(0));
+        let moving = machine.add_state(
+            "moving",
+            BlendNode::blend_1d(
+                "speed",
+                vec![(4.0, BlendNode::Clip(2)), (2.0, BlendNode::Clip(1))],
+            ),
+        );
+        let wave = machine.add_state("wave", BlendNode::Clip(3));
+
+        machine.add_transition(Some(idle), moving, Condition::above("speed", 0.0), 4);
+        machine.add_transition(Some(moving), idle, Condition::below("speed", 0.1), 0);
+        machine.add_transition(None, wave, Condition::above("wave", 0.0), 0);
+        machine.add_transition(Some(wave), idle, Condition::Finished, 0);
+
+        return set;
+    }
+
+    fn x(animator: &Animator, set: &AnimationSet) -> f32 {
+        return animator.sample(set).locals[0].translation.x;
+    }
+
+    #[test]
+    fn state_machine_validate_unknown_clip_returns_err() {
+        let mut machine = AnimationStateMachine::new();
+        machine.add_state("broken", BlendNode::Clip(7));
+
+        assert!(machine.validate(&vec![]).is_err());
+    }
+
+    #[test]
+    fn blend_1d_between_children_mixes_by_parameter() {
+        let set = set();
+        let mut animator = Animator::new();
+        animator.play(1, 0);
+
+        animator.set_parameter("speed", 3.0);
+        assert!((x(&animator, &set) - 1.5).abs() < 0.0001);
+
+        animator.set_parameter("speed", 9.0);
+        assert!((x(&animator, &set) - 2.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn animator_tick_condition_met_fades_into_next_state() {
+        let set = set();
+        let mut animator = Animator::new();
+        animator.set_parameter("speed", 2.0);
+
+        animator.tick(&set);
+        assert_eq!(1, animator.state());
+        assert_eq!(0.0, x(&animator, &set));
+
+        animator.tick(&set);
+        assert!((x(&animator, &set) - 0.25).abs() < 0.0001);
+
+        for _ in 0..3 {
+            animator.tick(&set);
+        }
+        assert_eq!(false, animator.is_fading());
+        assert_eq!(1.0, x(&animator, &set));
+    }
+
+    #[test]
+    fn animator_tick_finished_state_returns_to_idle() {
+        let set = set();
+        let mut animator = Animator::new();
+        animator.set_parameter("wave", 1.0);
+
+        animator.tick(&set);
+        assert_eq!(2, animator.state());
+        animator.set_parameter("wave", 0.0);
+
+        for _ in 0..3 {
+            animator.tick(&set);
+        }
+        assert_eq!(2, animator.state());
+
+        animator.tick(&set);
+        assert_eq!(0, animator.state());
+    }
+
+    #[test]
+    fn animator_same_ticks_same_pose() {
+        let set = set();
+        let mut a = Animator::new();
+        let mut b = Animator::new();
+
+        for tick in 0..30 {
+            let speed = (tick % 7) as f32;
+            a.set_parameter("speed", speed);
+            b.set_parameter("speed", speed);
+            a.tick(&set);
+            b.tick(&set);
+        }
+
+        assert_eq!(a, b);
+        assert_eq!(a.sample(&set), b.sample(&set));
+    }
+}
@@ -0,0 +1,477 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Asset manager. Assets are files under the asset root directory that are loaded once, shared through typed
+    handles and reloaded when the file changes on disk.
+
+    Handles are reference counted; the manager keeps a copy of each one until `unload_unused` drops the assets that
+    nothing else references. Loading can happen in the background, in which case the handle is empty until the load
+    finishes. Reloading swaps the asset behind every handle and bumps its version, so owners that derive data from
+    an asset, such as compiled shaders or uploaded meshes, compare versions to know when to rebuild it. If a reload
+    fails the previous asset is kept and the error is recorded on the handle.
+*/
+
+extern crate rayon;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The directory that relative asset paths are resolved against.
+pub const ASSET_ROOT: &'static str = "./src";
+
+/// How often `poll` checks the asset files for changes.
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A type that can be loaded from a file by the asset manager.
+pub trait Asset: Send + Sync + Sized + 'static {
+    fn load(path: &Path) -> Result<Self, String>;
+}
+
+struct AssetSlot<T> {
+    asset: Option<Arc<T>>,
+    loading: bool,
+    error: Option<String>,
+    version: u64,
+    modified: Option<SystemTime>,
+}
+
+impl<T: Asset> AssetSlot<T> {
+    fn new() -> Self {
+        return Self {
+            asset: None,
+            loading: false,
+            error: None,
+            version: 0,
+            modified: None,
+        };
+    }
+
+    /// Store the result of loading the asset. Failures keep the previous asset.
+    fn finish(&mut self, result: Result<T, String>, modified: Option<SystemTime>) {
+        self.loading = false;
+        self.modified = modified;
+
+        match result {
+            Ok(asset) => {
+                self.asset = Some(Arc::new(asset));
+                self.error = None;
+                self.version += 1;
+            }
+            Err(e) => {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panicking loader leaves the slot as it was, so it is still safe to use
+    return mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    return std::fs::metadata(path).and_then(|m| m.modified()).ok();
+}
+
+/// A shared reference to an asset. Cloning it adds a reference.
+pub struct Handle<T: Asset> {
+    path: PathBuf,
+    slot: Arc<Mutex<AssetSlot<T>>>,
+}
+
+impl<T: Asset> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        return Self {
+            path: self.path.clone(),
+            slot: self.slot.clone(),
+        };
+    }
+}
+
+impl<T: Asset> Handle<T> {
+    /// The resolved path of the asset's file.
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    /// The asset, or none if it has not finished loading or failed to load.
+    pub fn get(&self) -> Option<Arc<T>> {
+        return lock(&self.slot).asset.clone();
+    }
+
+    pub fn is_loading(&self) -> bool {
+        return lock(&self.slot).loading;
+    }
+
+    /// The error from the last load or reload, if it failed.
+    pub fn error(&self) -> Option<String> {
+        return lock(&self.slot).error.clone();
+    }
+
+    /// Incremented each time the asset is loaded or reloaded. Zero until it first loads.
+    pub fn version(&self) -> u64 {
+        return lock(&self.slot).version;
+    }
+
+    /// The number of handles to the asset, not counting the manager's own.
+    pub fn references(&self) -> usize {
+        return Arc::strong_count(&self.slot) - 1;
+    }
+}
+
+/// The handles of a single asset type, keyed by resolved path.
+struct AssetStore<T: Asset> {
+    slots: HashMap<PathBuf, Handle<T>>,
+}
+
+trait AnyAssetStore: Send {
+    fn reload_changed(&mut self) -> Vec<(PathBuf, Result<(), String>)>;
+    fn unload_unused(&mut self) -> usize;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Asset> AnyAssetStore for AssetStore<T> {
+    fn reload_changed(&mut self) -> Vec<(PathBuf, Result<(), String>)> {
+        let mut reloaded = vec![];
+
+        for (path, handle) in self.slots.iter() {
+            let modified = modified_at(path);
+            {
+                let slot = lock(&handle.slot);
+                if slot.loading || modified.is_none() || modified == slot.modified {
+                    continue;
+                }
+            }
+
+            let result = T::load(path);
+            let outcome = result.as_ref().map(|_| ()).map_err(|e| e.clone());
+            lock(&handle.slot).finish(result, modified);
+
+            reloaded.push((path.clone(), outcome));
+        }
+
+        return reloaded;
+    }
+
+    fn unload_unused(&mut self) -> usize {
+        let count = self.slots.len();
+        self.slots
+            .retain(|_, handle| Arc::strong_count(&handle.slot) > 1 || handle.is_loading());
+
+        return count - self.slots.len();
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        return self;
+    }
+}
+
+/// Loads and caches assets of any type from the asset root. Methods take a shared reference so the manager can be
+/// a world resource.
+pub struct AssetManager {
+    root: PathBuf,
+    stores: Mutex<HashMap<TypeId, Box<dyn AnyAssetStore>>>,
+    last_poll: Mutex<Option<Instant>>,
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        return Self::new(Path::new(ASSET_ROOT));
+    }
+}
+
+impl AssetManager {
+    pub fn new(root: &Path) -> Self {
+        return Self {
+            root: root.to_path_buf(),
+            stores: Mutex::new(HashMap::new()),
+            last_poll: Mutex::new(None),
+        };
+    }
+
+    pub fn root(&self) -> &Path {
+        return &self.root;
+    }
+
+    /// The path of an asset on disk. Relative paths are under the root, unless they already start with it.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() || path.starts_with(&self.root) {
+            return path.to_path_buf();
+        }
+
+        return self.root.join(path);
+    }
+
+    /// The cached handle for the path, creating an empty one if there is none.
+    fn handle<T: Asset>(&self, path: &Path) -> Handle<T> {
+        let path = self.resolve(path);
+
+        let mut stores = lock(&self.stores);
+        let store = stores
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(AssetStore::<T> {
+                    slots: HashMap::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut::<AssetStore<T>>()
+            .expect("Asset store registered under another type");
+
+        if let Some(handle) = store.slots.get(&path) {
+            return handle.clone();
+        }
+
+        let handle = Handle {
+            path: path.clone(),
+            slot: Arc::new(Mutex::new(AssetSlot::new())),
+        };
+        store.slots.insert(path, handle.clone());
+
+        return handle;
+    }
+
+    /// Load the asset, or return the cached handle if it is loaded or loading. Assets that previously failed to
+    /// load are tried again.
+    pub fn load<T: Asset>(&self, path: &Path) -> Result<Handle<T>, String> {
+        let handle = self.handle::<T>(path);
+
+        {
+            let mut slot = lock(&handle.slot);
+            if slot.asset.is_some() || slot.loading {
+                return Ok(handle.clone());
+            }
+
+            let modified = modified_at(&handle.path);
+            slot.finish(T::load(&handle.path), modified);
+        }
+
+        return match handle.error() {
+            Some(e) => Err(e),
+            None => Ok(handle),
+        };
+    }
+
+    /// Load the asset on another thread. The handle is empty until the load finishes; check `is_loading` and
+    /// `error` for the outcome.
+    pub fn load_in_background<T: Asset>(&self, path: &Path) -> Handle<T> {
+        let handle = self.handle::<T>(path);
+
+        {
+            let mut slot = lock(&handle.slot);
+            if slot.asset.is_some() || slot.loading {
+                return handle.clone();
+            }
+
+            slot.loading = true;
+        }
+
+        let loading = handle.clone();
+        rayon::spawn(move || {
+            let modified = modified_at(&loading.path);
+            let result = T::load(&loading.path);
+            lock(&loading.slot).finish(result, modified);
+        });
+
+        return handle;
+    }
+
+    /// Reload every asset whose file changed since it was loaded, returning the paths that were reloaded. Assets
+    /// that fail to reload keep their previous value.
+    pub fn hot_reload(&self) -> Vec<PathBuf> {
+        let mut reloaded = vec![];
+
+        let mut stores = lock(&self.stores);
+        for store in stores.values_mut() {
+            for (path, result) in store.reload_changed() {
+                match result {
+                    Ok(_) => reloaded.push(path),
+                    Err(e) => println!("Unable to reload asset {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        return reloaded;
+    }
+
+    /// Hot reload, at most once every interval so it can be called each frame.
+    pub fn poll(&self) -> Vec<PathBuf> {
+        {
+            let mut last_poll = lock(&self.last_poll);
+            let now = Instant::now();
+            if let Some(last) = *last_poll {
+                if now.duration_since(last) < HOT_RELOAD_INTERVAL {
+                    return vec![];
+                }
+            }
+
+            *last_poll = Some(now);
+        }
+
+        return self.hot_reload();
+    }
+
+    /// Drop the assets that only the manager references, returning how many were dropped.
+    pub fn unload_unused(&self) -> usize {
+        return lock(&self.stores)
+            .values_mut()
+            .map(|store| store.unload_unused())
+            .sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[derive(Debug, PartialEq)]
+    struct TextAsset {
+        text: String,
+    }
+
+    impl Asset for TextAsset {
+        fn load(path: &Path) -> Result<Self, String> {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+
+            if text.is_empty() {
+                return Err(format!("{} is empty", path.display()));
+            }
+
+            return Ok(Self { text: text });
+        }
+    }
+
+    /// A fresh asset root for the test, holding the files.
+    fn asset_root(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("cb_assets_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        for (name, text) in files.iter() {
+            fs::write(root.join(name), text).unwrap();
+        }
+
+        return root;
+    }
+
+    /// Rewrite the file and move its modified time forward, as writes within a second may share a timestamp.
+    fn modify(path: &Path, text: &str) {
+        fs::write(path, text).unwrap();
+
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    fn text(handle: &Handle<TextAsset>) -> String {
+        return handle.get().unwrap().text.clone();
+    }
+
+    #[test]
+    fn asset_manager_resolve_joins_root_once() {
+        let assets = AssetManager::new(Path::new("./src"));
+
+        assert_eq!(
+            PathBuf::from("./src/assets/monkey.dae"),
+            assets.resolve(Path::new("assets/monkey.dae"))
+        );
+        assert_eq!(
+            PathBuf::from("./src/assets/monkey.dae"),
+            assets.resolve(Path::new("./src/assets/monkey.dae"))
+        );
+    }
+
+    #[test]
+    fn asset_manager_load_caches_handles_by_path() {
+        let root = asset_root("cache", &[("a.txt", "hello")]);
+        let assets = AssetManager::new(&root);
+
+        let first = assets.load::<TextAsset>(Path::new("a.txt")).unwrap();
+        let second = assets.load::<TextAsset>(&root.join("a.txt")).unwrap();
+
+        assert_eq!("hello", text(&first));
+        assert_eq!(1, first.version());
+        assert_eq!(2, first.references());
+        assert!(Arc::ptr_eq(&first.get().unwrap(), &second.get().unwrap()));
+    }
+
+    #[test]
+    fn asset_manager_load_missing_file_returns_err() {
+        let root = asset_root("missing", &[]);
+        let assets = AssetManager::new(&root);
+
+        let actual = assets.load::<TextAsset>(Path::new("missing.txt"));
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn asset_manager_hot_reload_replaces_changed_assets() {
+        let root = asset_root("reload", &[("a.txt", "before"), ("b.txt", "other")]);
+        let assets = AssetManager::new(&root);
+        let a = assets.load::<TextAsset>(Path::new("a.txt")).unwrap();
+        let b = assets.load::<TextAsset>(Path::new("b.txt")).unwrap();
+
+        assert!(assets.hot_reload().is_empty());
+
+        modify(&root.join("a.txt"), "after");
+
+        assert_eq!(vec![root.join("a.txt")], assets.hot_reload());
+        assert_eq!("after", text(&a));
+        assert_eq!(2, a.version());
+        assert_eq!(1, b.version());
+        assert!(assets.hot_reload().is_empty());
+    }
+
+    #[test]
+    fn asset_manager_hot_reload_failure_keeps_previous_asset() {
+        let root = asset_root("reload_failure", &[("a.txt", "before")]);
+        let assets = AssetManager::new(&root);
+        let a = assets.load::<TextAsset>(Path::new("a.txt")).unwrap();
+
+        modify(&root.join("a.txt"), "");
+
+        assert!(assets.hot_reload().is_empty());
+        assert_eq!("before", text(&a));
+        assert_eq!(1, a.version());
+        assert!(a.error().is_some());
+    }
+
+    #[test]
+    fn asset_manager_load_in_background_fills_handle() {
+        let root = asset_root("background", &[("a.txt", "later")]);
+        let assets = AssetManager::new(&root);
+
+        let handle = assets.load_in_background::<TextAsset>(Path::new("a.txt"));
+        let start = Instant::now();
+        while handle.is_loading() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!("later", text(&handle));
+        assert_eq!(None, handle.error());
+    }
+
+    #[test]
+    fn asset_manager_unload_unused_drops_unreferenced_assets() {
+        let root = asset_root("unload", &[("a.txt", "kept"), ("b.txt", "dropped")]);
+        let assets = AssetManager::new(&root);
+        let a = assets.load::<TextAsset>(Path::new("a.txt")).unwrap();
+        {
+            let b = assets.load::<TextAsset>(Path::new("b.txt")).unwrap();
+            assert_eq!(1, b.references());
+        }
+
+        assert_eq!(1, assets.unload_unused());
+        assert_eq!(0, assets.unload_unused());
+        assert_eq!("kept", text(&a));
+    }
+}
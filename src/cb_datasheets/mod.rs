@@ -0,0 +1,429 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    A minimal parser for the hjson-like datasheets found in the simulation. Supports objects, arrays, unquoted and quoted
+    scalars, comma or newline separators, and '#' or '//' comments. Root braces may be omitted.
+*/
+
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataValue {
+    Object(Vec<(String, DataValue)>),
+    Array(Vec<DataValue>),
+    Scalar(String),
+}
+
+impl DataValue {
+    /// Returns the value for the given key, if this is an object and the key exists.
+    pub fn get(&self, key: &str) -> Option<&DataValue> {
+        match self {
+            DataValue::Object(members) => {
+                return members.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+            }
+            _ => {
+                return None;
+            }
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Vec<(String, DataValue)>> {
+        match self {
+            DataValue::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<DataValue>> {
+        match self {
+            DataValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            DataValue::Scalar(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        return self.as_str().and_then(|s| s.parse::<i32>().ok());
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        return self.as_str().and_then(|s| s.parse::<u32>().ok());
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        return self.as_str().and_then(|s| s.parse::<f32>().ok());
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.as_str() {
+            Some("true") => Some(true),
+            Some("false") => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Read and parse the datasheet at the given path.
+pub fn from_path(path: &Path) -> Result<DataValue, String> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(format!("unable to read '{}': {}", path.display(), e));
+        }
+    };
+
+    return parse(&source);
+}
+
+/// Parse a datasheet from a string.
+pub fn parse(source: &str) -> Result<DataValue, String> {
+    let mut parser = Parser::new(source);
+
+    parser.skip_separators();
+
+    if parser.peek() == Some('{') {
+        let value = parser.parse_value()?;
+        parser.skip_separators();
+
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected content after root object"));
+        }
+
+        return Ok(value);
+    }
+
+    return parser.parse_members(None);
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        return Self {
+            chars: source.chars().collect(),
+            index: 0,
+            line: 1,
+        };
+    }
+
+    fn error(&self, message: &str) -> String {
+        return format!("line {}: {}", self.line, message);
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.index).copied();
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        return self.chars.get(self.index + offset).copied();
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek();
+
+        if c == Some('\n') {
+            self.line += 1;
+        }
+
+        if c.is_some() {
+            self.index += 1;
+        }
+
+        return c;
+    }
+
+    fn skip_comment(&mut self) -> bool {
+        let is_comment =
+            self.peek() == Some('#') || (self.peek() == Some('/') && self.peek_at(1) == Some('/'));
+
+        if is_comment {
+            while self.peek().is_some() && self.peek() != Some('\n') {
+                self.advance();
+            }
+        }
+
+        return is_comment;
+    }
+
+    /// Skip spaces and tabs on the current line.
+    fn skip_inline_whitespace(&mut self) {
+        while self.peek() == Some(' ') || self.peek() == Some('\t') || self.peek() == Some('\r') {
+            self.advance();
+        }
+    }
+
+    /// Skip whitespace, newlines, commas and comments.
+    fn skip_separators(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() || c == ',' => {
+                    self.advance();
+                }
+                Some(_) => {
+                    if !self.skip_comment() {
+                        return;
+                    }
+                }
+                None => {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<DataValue, String> {
+        match self.peek() {
+            Some('{') => {
+                self.advance();
+                return self.parse_members(Some('}'));
+            }
+            Some('[') => {
+                self.advance();
+                return self.parse_array();
+            }
+            Some('"') => {
+                self.advance();
+                return self.parse_quoted();
+            }
+            Some(_) => {
+                return self.parse_scalar();
+            }
+            None => {
+                return Err(self.error("expected a value but found the end of the file"));
+            }
+        }
+    }
+
+    fn parse_members(&mut self, terminator: Option<char>) -> Result<DataValue, String> {
+        let mut members: Vec<(String, DataValue)> = vec![];
+
+        loop {
+            self.skip_separators();
+
+            match self.peek() {
+                None => {
+                    if terminator.is_some() {
+                        return Err(self.error("unterminated object; expected '}'"));
+                    }
+
+                    break;
+                }
+                Some(c) if Some(c) == terminator => {
+                    self.advance();
+                    break;
+                }
+                _ => {}
+            }
+
+            let key = self.parse_key()?;
+
+            if members.iter().any(|(k, _)| *k == key) {
+                return Err(self.error(&format!("duplicate key '{}'", key)));
+            }
+
+            self.skip_inline_whitespace();
+            let value = self.parse_value()?;
+
+            members.push((key, value));
+        }
+
+        return Ok(DataValue::Object(members));
+    }
+
+    fn parse_key(&mut self) -> Result<String, String> {
+        let mut key = String::new();
+
+        loop {
+            match self.peek() {
+                Some(':') => {
+                    self.advance();
+                    break;
+                }
+                Some(c)
+                    if c == '\n' || c == '{' || c == '}' || c == '[' || c == ']' || c == ',' =>
+                {
+                    return Err(self.error(&format!("expected ':' after key '{}'", key.trim())));
+                }
+                Some(c) => {
+                    key.push(c);
+                    self.advance();
+                }
+                None => {
+                    return Err(self.error(&format!("expected ':' after key '{}'", key.trim())));
+                }
+            }
+        }
+
+        let key = key.trim().to_string();
+        if key.is_empty() {
+            return Err(self.error("empty key"));
+        }
+
+        return Ok(key);
+    }
+
+    fn parse_array(&mut self) -> Result<DataValue, String> {
+        let mut values = vec![];
+
+        loop {
+            self.skip_separators();
+
+            match self.peek() {
+                None => {
+                    return Err(self.error("unterminated array; expected ']'"));
+                }
+                Some(']') => {
+                    self.advance();
+                    break;
+                }
+                _ => {
+                    values.push(self.parse_value()?);
+                }
+            }
+        }
+
+        return Ok(DataValue::Array(values));
+    }
+
+    fn parse_quoted(&mut self) -> Result<DataValue, String> {
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                Some('"') => {
+                    break;
+                }
+                Some('\\') => match self.advance() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => value.push(c),
+                    None => {
+                        return Err(self.error("unterminated string"));
+                    }
+                },
+                Some('\n') | None => {
+                    return Err(self.error("unterminated string"));
+                }
+                Some(c) => {
+                    value.push(c);
+                }
+            }
+        }
+
+        return Ok(DataValue::Scalar(value));
+    }
+
+    fn parse_scalar(&mut self) -> Result<DataValue, String> {
+        let mut value = String::new();
+
+        loop {
+            match self.peek() {
+                Some(c) if c == '\n' || c == ',' || c == '}' || c == ']' || c == '#' => {
+                    break;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.advance();
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+
+        return Ok(DataValue::Scalar(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_root_without_braces_returns_object() {
+        let actual = parse("name: infantry\nspeed: 10").unwrap();
+
+        assert_eq!(Some("infantry"), actual.get("name").unwrap().as_str());
+        assert_eq!(Some(10), actual.get("speed").unwrap().as_i32());
+    }
+
+    #[test]
+    fn parse_nested_objects_and_arrays_returns_expected() {
+        let source = "{\n  unit: { armor: 20, tags: [a, b\n c] }\n}";
+
+        let actual = parse(source).unwrap();
+        let unit = actual.get("unit").unwrap();
+
+        assert_eq!(Some(20), unit.get("armor").unwrap().as_i32());
+        assert_eq!(3, unit.get("tags").unwrap().as_array().unwrap().len());
+    }
+
+    #[test]
+    fn parse_comments_are_ignored() {
+        let source = "#// header\n{\n  title: neutral light punch # comment\n  // another\n}";
+
+        let actual = parse(source).unwrap();
+
+        assert_eq!(
+            Some("neutral light punch"),
+            actual.get("title").unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn parse_quoted_string_keeps_separators() {
+        let actual = parse("name: \"a, b # c\"").unwrap();
+
+        assert_eq!(Some("a, b # c"), actual.get("name").unwrap().as_str());
+    }
+
+    #[test]
+    fn parse_unterminated_object_returns_err() {
+        let actual = parse("{\n  a: 1\n");
+
+        assert_eq!(
+            Err("line 3: unterminated object; expected '}'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn parse_missing_colon_returns_err() {
+        let actual = parse("a 1\nb: 2");
+
+        assert_eq!(
+            Err("line 1: expected ':' after key 'a 1'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn parse_duplicate_key_returns_err() {
+        assert!(parse("a: 1\na: 2").is_err());
+    }
+
+    #[test]
+    fn as_bool_returns_expected() {
+        assert_eq!(Some(true), DataValue::Scalar("true".to_string()).as_bool());
+        assert_eq!(
+            Some(false),
+            DataValue::Scalar("false".to_string()).as_bool()
+        );
+        assert_eq!(None, DataValue::Scalar("yes".to_string()).as_bool());
+    }
+}
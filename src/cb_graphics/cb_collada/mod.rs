@@ -1,22 +1,1314 @@
 // Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+mod xml;
+
+/*
+    COLLADA import. The document's visual scene becomes the model's nodes, its joint nodes the bones of the model's
+    skeleton, and every triangles, polylist or polygons element of an instanced geometry a mesh. Skin controllers bake
+    their bind shape into the mesh and weight each vertex to its strongest joints. Animation channels become tracks in
+    one clip per animation clip, or a single clip named after the model if the document has none.
+
+    Only channels that animate a joint's whole matrix are imported, which is what Blender exports; channels on other
+    nodes are skipped. Bezier and hermite keys are played back linearly. Anything else that can't be represented is an
+    error rather than being dropped.
+*/
+
+use std::collections::HashMap;
 use std::path::Path;
 
-extern crate collada;
-use collada::*;
+extern crate nalgebra as na;
+
+use crate::cb_animation;
+use cb_animation::clips::{AnimationClip, Interpolation, Track};
+use cb_animation::skeleton::Skeleton;
+use cb_animation::{seconds_to_ticks, BoneId};
+
+use crate::cb_graphics;
+use cb_graphics::mesh::Mesh;
+use cb_graphics::models::{self, Material, Model, ModelMatrix, ModelMesh, Skin, SkinWeights};
+
+use xml::XmlNode;
+
+/// Import a COLLADA file, naming the model after the file.
+pub fn from_path(path: &Path) -> Result<Model, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("model");
+
+    return parse(&source, name)
+        .map_err(|e| format!("Unable to import '{}': {}", path.display(), e));
+}
+
+pub fn parse(source: &str, name: &str) -> Result<Model, String> {
+    let root = XmlNode::parse(source)?;
+    if root.name != "COLLADA" {
+        return Err(format!(
+            "Expected a <COLLADA> document, but found <{}>.",
+            root.name
+        ));
+    }
+
+    let mut importer = Importer::new(&root, name)?;
+    importer.import_scene()?;
+    importer.import_animations()?;
+
+    return Ok(importer.model);
+}
+
+/// Values read from a <source>, with a stride of values per element.
+struct Source {
+    values: Vec<f32>,
+    stride: usize,
+}
+
+impl Source {
+    fn get(&self, index: usize, id: &str) -> Result<&[f32], String> {
+        let start = index * self.stride;
+        if start + self.stride > self.values.len() {
+            return Err(format!(
+                "Index {} is out of range of source '{}'.",
+                index, id
+            ));
+        }
+
+        return Ok(&self.values[start..start + self.stride]);
+    }
+}
+
+struct Input<'a> {
+    semantic: &'a str,
+    id: &'a str,
+    source: Source,
+    offset: usize,
+}
+
+/// How a skin controller deforms the geometry it wraps.
+struct SkinBinding {
+    skin: usize,
+    /// Applied to the geometry before it is bound, to place it in the model's space.
+    bind_shape: ModelMatrix,
+    /// The joints and weights influencing each position of the geometry.
+    influences: Vec<Vec<(usize, f32)>>,
+}
+
+struct Importer<'a> {
+    root: &'a XmlNode,
+    ids: HashMap<&'a str, &'a XmlNode>,
+    /// Converts the document's up axis to z up.
+    up: ModelMatrix,
+    model: Model,
+    skeleton: Skeleton,
+    /// Bones by the names, ids and sids of their nodes, as skins may refer to them by any.
+    bones: HashMap<&'a str, BoneId>,
+    nodes: HashMap<&'a str, usize>,
+    materials: HashMap<&'a str, usize>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(root: &'a XmlNode, name: &str) -> Result<Self, String> {
+        let mut ids = HashMap::new();
+        root.visit(&mut |node| {
+            if let Some(id) = node.attribute("id") {
+                ids.insert(id, node);
+            }
+        });
+
+        let up = match root.find("asset/up_axis").map(|axis| axis.text.trim()) {
+            None | Some("Z_UP") => ModelMatrix::identity(),
+            Some("Y_UP") => {
+                na::Rotation3::from_axis_angle(&na::Vector3::x_axis(), std::f32::consts::FRAC_PI_2)
+                    .to_homogeneous()
+            }
+            Some("X_UP") => {
+                na::Rotation3::from_axis_angle(&na::Vector3::y_axis(), -std::f32::consts::FRAC_PI_2)
+                    .to_homogeneous()
+            }
+            Some(axis) => {
+                return Err(format!("The up axis '{}' is not X_UP, Y_UP or Z_UP.", axis));
+            }
+        };
+
+        let mut model = Model::new(name);
+        if let Some(unit) = root
+            .find("asset/unit")
+            .and_then(|unit| unit.attribute("meter"))
+        {
+            model.meters_per_unit = unit
+                .parse()
+                .map_err(|_| format!("The unit size '{}' is not a number.", unit))?;
+        }
+
+        return Ok(Self {
+            root: root,
+            ids: ids,
+            up: up,
+            model: model,
+            skeleton: Skeleton::new(),
+            bones: HashMap::new(),
+            nodes: HashMap::new(),
+            materials: HashMap::new(),
+        });
+    }
+
+    /// Find the element a url such as "#Cube-mesh" refers to.
+    fn lookup(&self, url: &str) -> Result<&'a XmlNode, String> {
+        let id = url.trim_start_matches('#');
+
+        return self
+            .ids
+            .get(id)
+            .copied()
+            .ok_or(format!("Nothing in the document has the id '{}'.", id));
+    }
+
+    fn import_scene(&mut self) -> Result<(), String> {
+        let scene = match self.root.find("scene/instance_visual_scene") {
+            Some(instance) => Some(self.lookup(instance.required_attribute("url")?)?),
+            None => self.root.find("library_visual_scenes/visual_scene"),
+        };
+
+        let scene = match scene {
+            Some(scene) => scene,
+            None => {
+                return self.import_loose_geometries();
+            }
+        };
+
+        // Instances may bind to any joint in the scene, so every node is read before them
+        let mut instances = vec![];
+        for node in scene.children_named("node") {
+            self.import_node(node, None, &mut instances)?;
+        }
+
+        for (node, instance) in instances {
+            let meshes = self.import_instance(instance)?;
+            self.model.nodes[node].meshes.extend(meshes);
+        }
+
+        if !self.skeleton.is_empty() {
+            self.model.skeleton = Some(self.skeleton.clone());
+        }
+
+        return Ok(());
+    }
+
+    /// Without a scene, each geometry in the library gets a node of its own.
+    fn import_loose_geometries(&mut self) -> Result<(), String> {
+        let geometries: Vec<&'a XmlNode> = match self.root.child("library_geometries") {
+            Some(library) => library.children_named("geometry").collect(),
+            None => vec![],
+        };
+
+        for geometry in geometries {
+            let name = geometry.attribute("id").unwrap_or("geometry");
+            let node = self.model.add_node(name, None, self.up)?;
+
+            let meshes = self.import_geometry(geometry, &HashMap::new(), None)?;
+            self.model.nodes[node].meshes.extend(meshes);
+        }
+
+        return Ok(());
+    }
+
+    fn import_node(
+        &mut self,
+        xml: &'a XmlNode,
+        parent: Option<usize>,
+        instances: &mut Vec<(usize, &'a XmlNode)>,
+    ) -> Result<(), String> {
+        let name = xml
+            .attribute("name")
+            .or(xml.attribute("id"))
+            .or(xml.attribute("sid"))
+            .unwrap_or("node");
+
+        let mut transform = node_transform(xml).map_err(|e| format!("Node '{}': {}", name, e))?;
+        if parent.is_none() {
+            transform = self.up * transform;
+        }
+
+        let index = self.model.add_node(name, parent, transform)?;
+        if let Some(id) = xml.attribute("id") {
+            self.nodes.insert(id, index);
+        }
+
+        if xml.attribute("type") == Some("JOINT") {
+            let parent_bone = self
+                .model
+                .parent_bone_node(index)
+                .and_then(|node| self.model.nodes[node].bone);
+            let rest = models::decompose(&self.model.bone_space(index, transform));
+
+            let bone = self.skeleton.add_bone(name, parent_bone, rest)?;
+            for key in [
+                xml.attribute("sid"),
+                xml.attribute("id"),
+                xml.attribute("name"),
+            ]
+            .iter()
+            {
+                if let Some(key) = key {
+                    self.bones.insert(*key, bone);
+                }
+            }
+
+            self.model.nodes[index].bone = Some(bone);
+        }
+
+        for child in xml.children.iter() {
+            match child.name.as_str() {
+                "node" => self.import_node(child, Some(index), instances)?,
+                "instance_node" => {
+                    let node = self.lookup(child.required_attribute("url")?)?;
+                    self.import_node(node, Some(index), instances)?;
+                }
+                "instance_geometry" | "instance_controller" => instances.push((index, child)),
+                _ => {}
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn import_instance(&mut self, instance: &'a XmlNode) -> Result<Vec<usize>, String> {
+        let mut bindings = HashMap::new();
+        if let Some(technique) = instance.find("bind_material/technique_common") {
+            for binding in technique.children_named("instance_material") {
+                let material = self.import_material(binding.required_attribute("target")?)?;
+                bindings.insert(binding.required_attribute("symbol")?, material);
+            }
+        }
+
+        let target = self.lookup(instance.required_attribute("url")?)?;
+
+        if instance.name == "instance_geometry" {
+            return self.import_geometry(target, &bindings, None);
+        }
+
+        return self.import_controller(target, &bindings);
+    }
+
+    fn import_material(&mut self, url: &str) -> Result<usize, String> {
+        let xml = self.lookup(url)?;
+        let id = xml.required_attribute("id")?;
+
+        if let Some(material) = self.materials.get(id) {
+            return Ok(*material);
+        }
+
+        let mut material = Material::new(xml.attribute("name").unwrap_or(id));
+
+        if let Some(instance) = xml.child("instance_effect") {
+            let effect = self.lookup(instance.required_attribute("url")?)?;
+            self.read_effect(effect, &mut material)
+                .map_err(|e| format!("Material '{}': {}", material.name, e))?;
+        }
+
+        self.model.materials.push(material);
+        let index = self.model.materials.len() - 1;
+        self.materials.insert(id, index);
+
+        return Ok(index);
+    }
+
+    fn read_effect(&self, effect: &'a XmlNode, material: &mut Material) -> Result<(), String> {
+        let profile = match effect.child("profile_COMMON") {
+            Some(profile) => profile,
+            None => {
+                return Ok(());
+            }
+        };
+
+        let diffuse = profile
+            .child("technique")
+            .and_then(|technique| {
+                technique.children.iter().find(|shading| {
+                    ["phong", "lambert", "blinn", "constant"].contains(&shading.name.as_str())
+                })
+            })
+            .and_then(|shading| shading.child("diffuse"));
+
+        let diffuse = match diffuse {
+            Some(diffuse) => diffuse,
+            None => {
+                return Ok(());
+            }
+        };
+
+        if let Some(color) = diffuse.child("color") {
+            let color = color.floats()?;
+            if color.len() < 3 {
+                return Err("The diffuse color needs at least three numbers.".to_string());
+            }
+
+            material.diffuse = [color[0], color[1], color[2], *color.get(3).unwrap_or(&1.0)];
+        }
+
+        if let Some(texture) = diffuse.child("texture") {
+            let sampler = texture.required_attribute("texture")?;
+            material.texture = Some(
+                self.texture_image(profile, sampler)
+                    .unwrap_or(sampler.to_string()),
+            );
+        }
+
+        return Ok(());
+    }
+
+    /// Follow a texture's sampler and surface parameters to the file of its image.
+    fn texture_image(&self, profile: &'a XmlNode, sampler: &str) -> Option<String> {
+        let param = |sid: &str| {
+            profile
+                .children_named("newparam")
+                .find(|param| param.attribute("sid") == Some(sid))
+        };
+
+        let image = match param(sampler) {
+            Some(sampler) => {
+                let surface = param(sampler.find("sampler2D/source")?.text.trim())?;
+                surface.find("surface/init_from")?.text.trim().to_string()
+            }
+            None => sampler.to_string(),
+        };
+
+        let image = self.lookup(&image).ok()?;
+        let file = match image.find("init_from/ref") {
+            Some(reference) => reference,
+            None => image.child("init_from")?,
+        };
+
+        return Some(file.text.trim().to_string());
+    }
+
+    fn read_source(&self, url: &str) -> Result<Source, String> {
+        let source = self.lookup(url)?;
+        let values = source.required_child("float_array")?.floats()?;
+
+        let stride = match source
+            .find("technique_common/accessor")
+            .and_then(|accessor| accessor.attribute("stride"))
+        {
+            Some(stride) => stride
+                .parse::<usize>()
+                .map_err(|_| format!("The stride '{}' is not a number.", stride))?,
+            None => 1,
+        };
+
+        if stride == 0 {
+            return Err(format!("Source '{}' has a stride of 0.", url));
+        }
+
+        return Ok(Source {
+            values: values,
+            stride: stride,
+        });
+    }
+
+    /// Names from a source's Name_array or IDREF_array.
+    fn read_names(&self, url: &str) -> Result<Vec<&'a str>, String> {
+        let source = self.lookup(url)?;
+
+        let names = match source.child("Name_array") {
+            Some(names) => names,
+            None => source.child("IDREF_array").ok_or(format!(
+                "Source '{}' has no Name_array or IDREF_array.",
+                url
+            ))?,
+        };
+
+        return Ok(names.words());
+    }
+
+    fn read_inputs(
+        &self,
+        xml: &'a XmlNode,
+        vertices: Option<&'a XmlNode>,
+    ) -> Result<Vec<Input<'a>>, String> {
+        let mut inputs = vec![];
+
+        for input in xml.children_named("input") {
+            let semantic = input.required_attribute("semantic")?;
+            let offset = match input.attribute("offset") {
+                Some(offset) => offset
+                    .parse::<usize>()
+                    .map_err(|_| format!("The input offset '{}' is not a number.", offset))?,
+                None => 0,
+            };
+
+            // Vertex inputs share an offset, and refer to the vertices element instead of a source
+            if semantic == "VERTEX" {
+                if let Some(vertices) = vertices {
+                    for vertex_input in vertices.children_named("input") {
+                        let id = vertex_input.required_attribute("source")?;
+                        inputs.push(Input {
+                            semantic: vertex_input.required_attribute("semantic")?,
+                            id: id,
+                            source: self.read_source(id)?,
+                            offset: offset,
+                        });
+                    }
+
+                    continue;
+                }
+            }
+
+            let id = input.required_attribute("source")?;
+            if semantic == "JOINT" {
+                // Joints are names rather than numbers, so only their offset is needed
+                inputs.push(Input {
+                    semantic: semantic,
+                    id: id,
+                    source: Source {
+                        values: vec![],
+                        stride: 1,
+                    },
+                    offset: offset,
+                });
+                continue;
+            }
+
+            inputs.push(Input {
+                semantic: semantic,
+                id: id,
+                source: self.read_source(id)?,
+                offset: offset,
+            });
+        }
+
+        return Ok(inputs);
+    }
+
+    fn import_controller(
+        &mut self,
+        controller: &'a XmlNode,
+        bindings: &HashMap<&'a str, usize>,
+    ) -> Result<Vec<usize>, String> {
+        let name = controller.attribute("id").unwrap_or("controller");
+
+        let binding = self
+            .read_skin(controller)
+            .map_err(|e| format!("Controller '{}': {}", name, e))?;
+
+        let geometry = self.lookup(
+            controller
+                .required_child("skin")?
+                .required_attribute("source")?,
+        )?;
+
+        return self.import_geometry(geometry, bindings, Some(&binding));
+    }
+
+    fn read_skin(&mut self, controller: &'a XmlNode) -> Result<SkinBinding, String> {
+        let skin = controller
+            .child("skin")
+            .ok_or("Only <skin> controllers are supported.")?;
+
+        let bind_shape = match skin.child("bind_shape_matrix") {
+            Some(matrix) => row_major(&expect_floats(matrix, 16)?),
+            None => ModelMatrix::identity(),
+        };
+
+        // The joints and their inverse bind matrices
+        let joints = skin.required_child("joints")?;
+        let mut names = vec![];
+        let mut inverse_bind_matrices = vec![];
+
+        for input in joints.children_named("input") {
+            let source = input.required_attribute("source")?;
+
+            match input.required_attribute("semantic")? {
+                "JOINT" => names = self.read_names(source)?,
+                "INV_BIND_MATRIX" => {
+                    inverse_bind_matrices = self
+                        .read_source(source)?
+                        .values
+                        .chunks(16)
+                        .filter(|values| values.len() == 16)
+                        .map(|values| row_major(values))
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        if names.len() != inverse_bind_matrices.len() {
+            return Err(format!(
+                "The skin has {} joints but {} inverse bind matrices.",
+                names.len(),
+                inverse_bind_matrices.len()
+            ));
+        }
+
+        let mut bones = vec![];
+        for name in names.iter() {
+            match self.bones.get(name) {
+                Some(bone) => bones.push(*bone),
+                None => {
+                    return Err(format!(
+                        "The skin binds to joint '{}', which is not in the scene.",
+                        name
+                    ));
+                }
+            }
+        }
+
+        // The joints were moved by the change of up axis, so their bind matrices must undo it
+        let from_up = self.up.try_inverse().unwrap_or(ModelMatrix::identity());
+        let joint_count = bones.len();
+
+        self.model.skins.push(Skin {
+            bones: bones,
+            inverse_bind_matrices: inverse_bind_matrices
+                .iter()
+                .map(|matrix| matrix * from_up)
+                .collect(),
+        });
+
+        // The weights of each vertex
+        let vertex_weights = skin.required_child("vertex_weights")?;
+        let inputs = self.read_inputs(vertex_weights, None)?;
+
+        let joint_offset = inputs
+            .iter()
+            .find(|input| input.semantic == "JOINT")
+            .ok_or("The vertex weights have no JOINT input.")?
+            .offset;
+        let weights = inputs
+            .iter()
+            .find(|input| input.semantic == "WEIGHT")
+            .ok_or("The vertex weights have no WEIGHT input.")?;
+        let stride = inputs.iter().map(|input| input.offset).max().unwrap_or(0) + 1;
+
+        let counts = vertex_weights.required_child("vcount")?.indices()?;
+        let values = vertex_weights.required_child("v")?.indices()?;
+
+        let mut influences = vec![];
+        let mut cursor = 0;
+        for count in counts.iter() {
+            let mut vertex = vec![];
+
+            for _ in 0..*count {
+                if cursor + stride > values.len() {
+                    return Err("The vertex weights end early.".to_string());
+                }
+
+                let joint = values[cursor + joint_offset];
+                if joint >= joint_count {
+                    return Err(format!("Vertex weight joint {} does not exist.", joint));
+                }
 
-pub struct ColladaMesh {
-    document: collada::document::ColladaDocument,
-    id: usize,
+                let weight = weights
+                    .source
+                    .get(values[cursor + weights.offset], weights.id)?[0];
+                vertex.push((joint, weight));
+
+                cursor += stride;
+            }
+
+            influences.push(vertex);
+        }
+
+        return Ok(SkinBinding {
+            skin: self.model.skins.len() - 1,
+            bind_shape: self.up * bind_shape,
+            influences: influences,
+        });
+    }
+
+    fn import_geometry(
+        &mut self,
+        geometry: &'a XmlNode,
+        bindings: &HashMap<&'a str, usize>,
+        skin: Option<&SkinBinding>,
+    ) -> Result<Vec<usize>, String> {
+        let name = geometry
+            .attribute("name")
+            .or(geometry.attribute("id"))
+            .unwrap_or("geometry");
+
+        return self
+            .import_meshes(name, geometry, bindings, skin)
+            .map_err(|e| format!("Geometry '{}': {}", name, e));
+    }
+
+    fn import_meshes(
+        &mut self,
+        name: &str,
+        geometry: &'a XmlNode,
+        bindings: &HashMap<&'a str, usize>,
+        skin: Option<&SkinBinding>,
+    ) -> Result<Vec<usize>, String> {
+        let mesh = geometry
+            .child("mesh")
+            .ok_or("Only <mesh> geometries are supported.")?;
+        let vertices = mesh.required_child("vertices")?;
+
+        let mut meshes = vec![];
+        for primitive in mesh.children.iter() {
+            match primitive.name.as_str() {
+                "triangles" | "polylist" | "polygons" => {
+                    let material = self.primitive_material(primitive, bindings)?;
+                    let model_mesh =
+                        self.import_primitive(name, primitive, vertices, material, skin)?;
+
+                    self.model.meshes.push(model_mesh);
+                    meshes.push(self.model.meshes.len() - 1);
+                }
+                "lines" | "linestrips" | "tristrips" | "trifans" => {
+                    return Err(format!(
+                        "<{}> primitives are not supported.",
+                        primitive.name
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        return Ok(meshes);
+    }
+
+    /// The material bound to a primitive's symbol, or the material with that id.
+    fn primitive_material(
+        &mut self,
+        primitive: &'a XmlNode,
+        bindings: &HashMap<&'a str, usize>,
+    ) -> Result<Option<usize>, String> {
+        let symbol = match primitive.attribute("material") {
+            Some(symbol) => symbol,
+            None => {
+                return Ok(None);
+            }
+        };
+
+        if let Some(material) = bindings.get(symbol) {
+            return Ok(Some(*material));
+        }
+
+        if self.ids.contains_key(symbol) {
+            return self.import_material(symbol).map(|material| Some(material));
+        }
+
+        return Ok(None);
+    }
+
+    fn import_primitive(
+        &self,
+        name: &str,
+        primitive: &'a XmlNode,
+        vertices: &'a XmlNode,
+        material: Option<usize>,
+        skin: Option<&SkinBinding>,
+    ) -> Result<ModelMesh, String> {
+        let inputs = self.read_inputs(primitive, Some(vertices))?;
+        let stride = inputs.iter().map(|input| input.offset).max().unwrap_or(0) + 1;
+
+        let input = |semantic: &str| inputs.iter().find(|input| input.semantic == semantic);
+        let position = input("POSITION").ok_or("The primitive has no POSITION input.")?;
+        let normal = input("NORMAL");
+        let color = input("COLOR");
+        let uv = input("TEXCOORD");
+
+        // The number of corners of each polygon, and the indices of all of them
+        let mut corners: Vec<usize> = vec![];
+        let mut indices: Vec<usize> = vec![];
+
+        for p in primitive.children_named("p") {
+            let polygon = p.indices()?;
+            if primitive.name == "polygons" {
+                corners.push(polygon.len() / stride);
+            }
+
+            indices.extend(polygon);
+        }
+
+        match primitive.name.as_str() {
+            "triangles" => corners = vec![3; indices.len() / stride / 3],
+            "polylist" => {
+                if let Some(vcount) = primitive.child("vcount") {
+                    corners = vcount.indices()?;
+                }
+            }
+            _ => {}
+        }
+
+        if corners.iter().sum::<usize>() * stride > indices.len() {
+            return Err("The primitive has fewer indices than its polygons need.".to_string());
+        }
+
+        let default_color = match material {
+            Some(material) => self.model.materials[material].diffuse,
+            None => models::DEFAULT_COLOR,
+        };
+
+        let bake = match skin {
+            Some(skin) => skin.bind_shape,
+            None => ModelMatrix::identity(),
+        };
+
+        let mut mesh = Mesh::new(3, vec![], vec![], 3, vec![], 3, vec![], 0);
+        let mut position_indices: Vec<usize> = vec![];
+        let mut unique: HashMap<&[usize], i32> = HashMap::new();
+        let mut cursor = 0;
+
+        for count in corners.iter() {
+            let mut polygon: Vec<i32> = vec![];
+
+            for _ in 0..*count {
+                let corner = &indices[cursor..cursor + stride];
+                cursor += stride;
+
+                if let Some(vertex) = unique.get(corner) {
+                    polygon.push(*vertex);
+                    continue;
+                }
+
+                let p = position.source.get(corner[position.offset], position.id)?;
+                let p = bake.transform_point(&na::Point3::new(p[0], p[1], p[2]));
+                mesh.vertices.extend(&[p.x, p.y, p.z]);
+                position_indices.push(corner[position.offset]);
+
+                if let Some(normal) = normal {
+                    let n = normal.source.get(corner[normal.offset], normal.id)?;
+                    let n = bake
+                        .transform_vector(&na::Vector3::new(n[0], n[1], n[2]))
+                        .normalize();
+                    mesh.normals.extend(&[n.x, n.y, n.z]);
+                }
+
+                match color {
+                    Some(color) => {
+                        let c = color.source.get(corner[color.offset], color.id)?;
+                        if c.len() < 3 {
+                            return Err(format!("Colors in '{}' need three values.", color.id));
+                        }
+
+                        mesh.colors.extend(&c[0..3]);
+                    }
+                    None => mesh.colors.extend(&default_color[0..3]),
+                }
+
+                if let Some(uv) = uv {
+                    let t = uv.source.get(corner[uv.offset], uv.id)?;
+                    if t.len() < 2 {
+                        return Err(format!(
+                            "Texture coordinates in '{}' need two values.",
+                            uv.id
+                        ));
+                    }
+
+                    mesh.uvs.extend(&t[0..2]);
+                }
+
+                let vertex = position_indices.len() as i32 - 1;
+                unique.insert(corner, vertex);
+                polygon.push(vertex);
+            }
+
+            // Fan each polygon into triangles
+            for i in 1..polygon.len().saturating_sub(1) {
+                mesh.indices
+                    .extend(&[polygon[0], polygon[i], polygon[i + 1]]);
+            }
+        }
+
+        if normal.is_none() {
+            mesh.normals = models::smooth_normals(&mesh);
+        }
+
+        let skin = match skin {
+            Some(skin) => {
+                let mut weights = SkinWeights {
+                    skin: skin.skin,
+                    joints: vec![],
+                    weights: vec![],
+                };
+
+                for position in position_indices.iter() {
+                    let mut influences = skin
+                        .influences
+                        .get(*position)
+                        .cloned()
+                        .ok_or(format!("Position {} has no skin weights.", position))?;
+
+                    let (joints, vertex_weights) = SkinWeights::strongest(&mut influences);
+                    weights.joints.push(joints);
+                    weights.weights.push(vertex_weights);
+                }
+
+                Some(weights)
+            }
+            None => None,
+        };
+
+        return Ok(ModelMesh {
+            name: name.to_string(),
+            mesh: mesh,
+            material: material,
+            skin: skin,
+        });
+    }
+
+    fn import_animations(&mut self) -> Result<(), String> {
+        let library = match self.root.child("library_animations") {
+            Some(library) => library,
+            None => {
+                return Ok(());
+            }
+        };
+
+        let clips: Vec<&'a XmlNode> = match self.root.child("library_animation_clips") {
+            Some(clips) => clips.children_named("animation_clip").collect(),
+            None => vec![],
+        };
+
+        // Without clips, every animation plays together
+        if clips.is_empty() {
+            let mut clip = AnimationClip::new(&self.model.name, 0, true);
+            for animation in library.children_named("animation") {
+                self.add_channels(animation, 0.0, &mut clip)?;
+            }
+
+            if !clip.tracks.is_empty() {
+                self.model.clips.push(clip);
+            }
+
+            return Ok(());
+        }
+
+        for xml in clips {
+            let name = xml
+                .attribute("name")
+                .or(xml.attribute("id"))
+                .unwrap_or("clip");
+
+            let seconds = |attribute: &str| -> Result<f32, String> {
+                match xml.attribute(attribute) {
+                    Some(value) => value.parse::<f32>().map_err(|_| {
+                        format!(
+                            "Clip '{}' has the {} '{}', which is not a number.",
+                            name, attribute, value
+                        )
+                    }),
+                    None => Ok(0.0),
+                }
+            };
+
+            let start = seconds("start")?;
+            let end = seconds("end")?;
+
+            let mut clip = AnimationClip::new(name, seconds_to_ticks(end - start), true);
+            for instance in xml.children_named("instance_animation") {
+                let animation = self.lookup(instance.required_attribute("url")?)?;
+                self.add_channels(animation, start, &mut clip)?;
+            }
+
+            self.model.clips.push(clip);
+        }
+
+        return Ok(());
+    }
+
+    fn add_channels(
+        &self,
+        animation: &'a XmlNode,
+        start: f32,
+        clip: &mut AnimationClip,
+    ) -> Result<(), String> {
+        for channel in animation.children_named("channel") {
+            if let Some(track) = self.import_channel(channel, start)? {
+                clip.add_track(track);
+            }
+        }
+
+        for nested in animation.children_named("animation") {
+            self.add_channels(nested, start, clip)?;
+        }
+
+        return Ok(());
+    }
+
+    /// Convert a channel into a track, starting at the time in seconds. Channels on nodes that aren't joints return
+    /// none.
+    fn import_channel(&self, channel: &'a XmlNode, start: f32) -> Result<Option<Track>, String> {
+        let target = channel.required_attribute("target")?;
+
+        let (node_id, element) = match target.find('/') {
+            Some(split) => (&target[..split], &target[split + 1..]),
+            None => {
+                return Err(format!("The animation target '{}' is malformed.", target));
+            }
+        };
+
+        let node = *self.nodes.get(node_id).ok_or(format!(
+            "The animation target '{}' refers to a node that is not in the scene.",
+            target
+        ))?;
+
+        let bone = match self.model.nodes[node].bone {
+            Some(bone) => bone,
+            None => {
+                return Ok(None);
+            }
+        };
+
+        let animates_matrix = self.lookup(node_id)?.children.iter().any(|transform| {
+            transform.name == "matrix" && transform.attribute("sid") == Some(element)
+        });
+
+        if !animates_matrix {
+            return Err(format!(
+                "The animation target '{}' is part of a transform, but only whole joint matrices are supported.",
+                target
+            ));
+        }
+
+        let sampler = self.lookup(channel.required_attribute("source")?)?;
+        let mut times = vec![];
+        let mut outputs = vec![];
+        let mut interpolation = Interpolation::Linear;
+
+        for input in sampler.children_named("input") {
+            let source = input.required_attribute("source")?;
+
+            match input.required_attribute("semantic")? {
+                "INPUT" => times = self.read_source(source)?.values,
+                "OUTPUT" => outputs = self.read_source(source)?.values,
+                "INTERPOLATION" => {
+                    if self.read_names(source)?.iter().all(|name| *name == "STEP") {
+                        interpolation = Interpolation::Step;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if outputs.len() != times.len() * 16 {
+            return Err(format!(
+                "The animation of '{}' has {} keys but {} values, where each key needs 16.",
+                target,
+                times.len(),
+                outputs.len()
+            ));
+        }
+
+        let is_root = self.model.nodes[node].parent.is_none();
+        let mut track = Track::new(bone, interpolation);
+
+        for (time, values) in times.iter().zip(outputs.chunks(16)) {
+            if *time < start {
+                continue;
+            }
+
+            let mut matrix = row_major(values);
+            if is_root {
+                matrix = self.up * matrix;
+            }
+
+            let transform = models::decompose(&self.model.bone_space(node, matrix));
+            let tick = seconds_to_ticks(time - start);
+
+            track.add_translation(tick, transform.translation);
+            track.add_rotation(tick, transform.rotation);
+            track.add_scale(tick, transform.scale);
+        }
+
+        return Ok(Some(track));
+    }
+}
+
+/// COLLADA writes matrices a row at a time.
+fn row_major(values: &[f32]) -> ModelMatrix {
+    return ModelMatrix::from_row_slice(values);
+}
+
+fn expect_floats(xml: &XmlNode, count: usize) -> Result<Vec<f32>, String> {
+    let values = xml.floats()?;
+    if values.len() != count {
+        return Err(format!(
+            "<{}> should have {} numbers, but has {}.",
+            xml.name,
+            count,
+            values.len()
+        ));
+    }
+
+    return Ok(values);
 }
 
-impl ColladaMesh {
-    pub fn from_collada(id: usize, path: &Path) -> Self {
-        let doc = collada::document::ColladaDocument::from_path(path).unwrap();
+/// Combine a node's transform elements, in order.
+fn node_transform(xml: &XmlNode) -> Result<ModelMatrix, String> {
+    let mut transform = ModelMatrix::identity();
 
-        let mut c = Self {
-            id: id,
-            document: doc,
+    for element in xml.children.iter() {
+        let step = match element.name.as_str() {
+            "matrix" => row_major(&expect_floats(element, 16)?),
+            "translate" => {
+                let v = expect_floats(element, 3)?;
+                ModelMatrix::new_translation(&na::Vector3::new(v[0], v[1], v[2]))
+            }
+            "rotate" => {
+                let v = expect_floats(element, 4)?;
+                let axis = na::Vector3::new(v[0], v[1], v[2]);
+                match na::Unit::try_new(axis, 0.0) {
+                    Some(axis) => {
+                        na::Rotation3::from_axis_angle(&axis, v[3].to_radians()).to_homogeneous()
+                    }
+                    None => ModelMatrix::identity(),
+                }
+            }
+            "scale" => {
+                let v = expect_floats(element, 3)?;
+                ModelMatrix::new_nonuniform_scaling(&na::Vector3::new(v[0], v[1], v[2]))
+            }
+            "lookat" | "skew" => {
+                return Err(format!("<{}> transforms are not supported.", element.name));
+            }
+            _ => {
+                continue;
+            }
         };
-        return c;
+
+        transform = transform * step;
+    }
+
+    return Ok(transform);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_simulation::TICKS_PER_SECOND;
+
+    /// A y up quad split into two triangles, with colors and texture coordinates.
+    const QUAD: &'static str = r##"<?xml version="1.0" encoding="utf-8"?>
+<COLLADA xmlns="http://www.collada.org/2005/11/COLLADASchema" version="1.4.1">
+  <asset><unit meter="0.01"/><up_axis>Y_UP</up_axis></asset>
+  <library_effects>
+    <effect id="red-effect"><profile_COMMON><technique sid="common"><lambert>
+      <diffuse><color>1 0 0 1</color></diffuse>
+    </lambert></technique></profile_COMMON></effect>
+  </library_effects>
+  <library_materials>
+    <material id="red" name="Red"><instance_effect url="#red-effect"/></material>
+  </library_materials>
+  <library_geometries>
+    <geometry id="quad" name="Quad"><mesh>
+      <source id="quad-positions">
+        <float_array id="quad-positions-array" count="12">0 0 0 1 0 0 1 1 0 0 1 0</float_array>
+        <technique_common><accessor source="#quad-positions-array" count="4" stride="3"/></technique_common>
+      </source>
+      <source id="quad-uvs">
+        <float_array id="quad-uvs-array" count="8">0 0 1 0 1 1 0 1</float_array>
+        <technique_common><accessor source="#quad-uvs-array" count="4" stride="2"/></technique_common>
+      </source>
+      <vertices id="quad-vertices"><input semantic="POSITION" source="#quad-positions"/></vertices>
+      <polylist material="red-material" count="1">
+        <input semantic="VERTEX" source="#quad-vertices" offset="0"/>
+        <input semantic="TEXCOORD" source="#quad-uvs" offset="1" set="0"/>
+        <vcount>4</vcount>
+        <p>0 0 1 1 2 2 3 3</p>
+      </polylist>
+    </mesh></geometry>
+  </library_geometries>
+  <library_visual_scenes>
+    <visual_scene id="scene">
+      <node id="Quad" name="Quad">
+        <translate>0 2 0</translate>
+        <instance_geometry url="#quad">
+          <bind_material><technique_common>
+            <instance_material symbol="red-material" target="#red"/>
+          </technique_common></bind_material>
+        </instance_geometry>
+      </node>
+    </visual_scene>
+  </library_visual_scenes>
+  <scene><instance_visual_scene url="#scene"/></scene>
+</COLLADA>"##;
+
+    /// A two bone arm skinning a single triangle, with the elbow bending over one second.
+    const ARM: &'static str = r##"<?xml version="1.0" encoding="utf-8"?>
+<COLLADA xmlns="http://www.collada.org/2005/11/COLLADASchema" version="1.4.1">
+  <asset><up_axis>Z_UP</up_axis></asset>
+  <library_animations>
+    <animation id="elbow-anim">
+      <source id="elbow-times"><float_array id="elbow-times-array" count="2">0 1</float_array></source>
+      <source id="elbow-poses">
+        <float_array id="elbow-poses-array" count="32">1 0 0 1 0 1 0 0 0 0 1 0 0 0 0 1 0 -1 0 1 1 0 0 0 0 0 1 0 0 0 0 1</float_array>
+        <technique_common><accessor source="#elbow-poses-array" count="2" stride="16"/></technique_common>
+      </source>
+      <source id="elbow-interpolation"><Name_array id="elbow-interpolation-array" count="2">LINEAR LINEAR</Name_array></source>
+      <sampler id="elbow-sampler">
+        <input semantic="INPUT" source="#elbow-times"/>
+        <input semantic="OUTPUT" source="#elbow-poses"/>
+        <input semantic="INTERPOLATION" source="#elbow-interpolation"/>
+      </sampler>
+      <channel source="#elbow-sampler" target="Elbow/transform"/>
+    </animation>
+  </library_animations>
+  <library_controllers>
+    <controller id="arm-skin"><skin source="#triangle">
+      <bind_shape_matrix>1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1</bind_shape_matrix>
+      <source id="arm-joints"><Name_array id="arm-joints-array" count="2">Shoulder Elbow</Name_array></source>
+      <source id="arm-bind-poses">
+        <float_array id="arm-bind-poses-array" count="32">1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1 1 0 0 -1 0 1 0 0 0 0 1 0 0 0 0 1</float_array>
+        <technique_common><accessor source="#arm-bind-poses-array" count="2" stride="16"/></technique_common>
+      </source>
+      <source id="arm-weights"><float_array id="arm-weights-array" count="3">1 0.25 0.75</float_array></source>
+      <joints>
+        <input semantic="JOINT" source="#arm-joints"/>
+        <input semantic="INV_BIND_MATRIX" source="#arm-bind-poses"/>
+      </joints>
+      <vertex_weights count="3">
+        <input semantic="JOINT" source="#arm-joints" offset="0"/>
+        <input semantic="WEIGHT" source="#arm-weights" offset="1"/>
+        <vcount>1 2 1</vcount>
+        <v>0 0 0 1 1 2 1 0</v>
+      </vertex_weights>
+    </skin></controller>
+  </library_controllers>
+  <library_geometries>
+    <geometry id="triangle"><mesh>
+      <source id="triangle-positions">
+        <float_array id="triangle-positions-array" count="9">0 0 0 1 0 0 2 0 0.5</float_array>
+        <technique_common><accessor source="#triangle-positions-array" count="3" stride="3"/></technique_common>
+      </source>
+      <vertices id="triangle-vertices"><input semantic="POSITION" source="#triangle-positions"/></vertices>
+      <triangles count="1">
+        <input semantic="VERTEX" source="#triangle-vertices" offset="0"/>
+        <p>0 1 2</p>
+      </triangles>
+    </mesh></geometry>
+  </library_geometries>
+  <library_visual_scenes>
+    <visual_scene id="scene">
+      <node id="Armature" name="Armature">
+        <node id="Shoulder" name="Shoulder" sid="Shoulder" type="JOINT">
+          <matrix sid="transform">1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1</matrix>
+          <node id="Elbow" name="Elbow" sid="Elbow" type="JOINT">
+            <matrix sid="transform">1 0 0 1 0 1 0 0 0 0 1 0 0 0 0 1</matrix>
+          </node>
+        </node>
+      </node>
+      <node id="Arm" name="Arm">
+        <instance_controller url="#arm-skin"><skeleton>#Shoulder</skeleton></instance_controller>
+      </node>
+    </visual_scene>
+  </library_visual_scenes>
+  <scene><instance_visual_scene url="#scene"/></scene>
+</COLLADA>"##;
+
+    #[test]
+    fn parse_polylist_fans_polygons_into_triangles() {
+        let model = parse(QUAD, "quad").unwrap();
+
+        assert_eq!(1, model.meshes.len());
+        let mesh = &model.meshes[0].mesh;
+        assert_eq!(vec![0, 1, 2, 0, 2, 3], mesh.indices);
+        assert_eq!(12, mesh.vertices.len());
+        assert_eq!(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0], mesh.uvs);
+        assert_eq!(12, mesh.normals.len());
+    }
+
+    #[test]
+    fn parse_bound_material_colors_vertices() {
+        let model = parse(QUAD, "quad").unwrap();
+
+        assert_eq!(Some(0), model.meshes[0].material);
+        assert_eq!("Red", model.materials[0].name);
+        assert_eq!(
+            vec![1.0, 0.0, 0.0],
+            model.meshes[0].mesh.colors[0..3].to_vec()
+        );
+        assert_eq!(0.01, model.meters_per_unit);
+    }
+
+    #[test]
+    fn parse_y_up_converts_node_to_z_up() {
+        let model = parse(QUAD, "quad").unwrap();
+
+        let world = model.world_transforms();
+        let origin = world[0].transform_point(&na::Point3::origin());
+
+        assert!((origin - na::Point3::new(0.0, 0.0, 2.0)).norm() < 0.0001);
+        assert_eq!(vec![0], model.nodes[0].meshes);
+    }
+
+    #[test]
+    fn parse_joints_build_skeleton() {
+        let model = parse(ARM, "arm").unwrap();
+
+        let skeleton = model.skeleton.unwrap();
+        assert_eq!(2, skeleton.len());
+        assert_eq!(Some(0), skeleton.bones()[1].parent);
+        assert_eq!(1.0, skeleton.bones()[1].rest.translation.x);
+    }
+
+    #[test]
+    fn parse_skin_weights_each_vertex() {
+        let model = parse(ARM, "arm").unwrap();
+
+        let skin = model.meshes[0].skin.as_ref().unwrap();
+        assert_eq!(vec![0, 1], model.skins[0].bones);
+        assert_eq!([1, 0, 0, 0], skin.joints[1]);
+        assert_eq!([0.75, 0.25, 0.0, 0.0], skin.weights[1]);
+        assert_eq!(-1.0, model.skins[0].inverse_bind_matrices[1][(0, 3)]);
+    }
+
+    #[test]
+    fn parse_matrix_channel_becomes_clip() {
+        let model = parse(ARM, "arm").unwrap();
+
+        assert_eq!(1, model.clips.len());
+        let clip = &model.clips[0];
+        assert_eq!("arm", clip.name);
+        assert_eq!(TICKS_PER_SECOND, clip.duration_ticks);
+        assert_eq!(1, clip.tracks[0].bone);
+
+        let set = model.animation_set().unwrap();
+        let mut pose = set.skeleton.rest_pose();
+        set.clips[0].sample(TICKS_PER_SECOND as f32 / 2.0, &mut pose);
+        let world = pose.world_transforms(&set.skeleton);
+        assert!((world[1].rotation.angle() - std::f32::consts::FRAC_PI_4).abs() < 0.001);
+    }
+
+    #[test]
+    fn parse_unsupported_primitive_returns_err() {
+        let source = QUAD
+            .replace("<polylist", "<lines")
+            .replace("</polylist>", "</lines>");
+
+        let result = parse(&source, "quad");
+
+        assert!(result.unwrap_err().contains("<lines>"));
+    }
+
+    #[test]
+    fn parse_index_out_of_range_returns_err() {
+        let source = QUAD.replace("<p>0 0 1 1 2 2 3 3</p>", "<p>0 0 1 1 2 2 9 3</p>");
+
+        let result = parse(&source, "quad");
+
+        assert!(result.unwrap_err().contains("out of range"));
+    }
+
+    #[test]
+    fn parse_not_collada_returns_err() {
+        assert!(parse("<gltf></gltf>", "model").is_err());
+    }
+
+    #[test]
+    fn from_path_monkey_imports_mesh() {
+        let model = from_path(Path::new("./src/assets/monkey.dae")).unwrap();
+
+        assert_eq!(1, model.meshes.len());
+        assert!(model.meshes[0].mesh.indices.len() > 0);
+        assert_eq!(
+            model.meshes[0].mesh.vertices.len(),
+            model.meshes[0].mesh.normals.len()
+        );
     }
 }
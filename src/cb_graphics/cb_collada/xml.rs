@@ -0,0 +1,168 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate xml;
+use xml::reader::{EventReader, XmlEvent};
+
+/// An element of an XML document, read entirely into memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlNode {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+    pub text: String,
+}
+
+impl XmlNode {
+    fn new(name: String, attributes: Vec<(String, String)>) -> Self {
+        return Self {
+            name: name,
+            attributes: attributes,
+            children: vec![],
+            text: String::new(),
+        };
+    }
+
+    /// Parse a document, returning its root element. Namespaces are dropped from names.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut open: Vec<XmlNode> = vec![];
+
+        for event in EventReader::from_str(source) {
+            let event = event.map_err(|e| format!("Malformed XML: {}", e))?;
+
+            match event {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let attributes = attributes
+                        .into_iter()
+                        .map(|a| (a.name.local_name, a.value))
+                        .collect();
+
+                    open.push(XmlNode::new(name.local_name, attributes));
+                }
+                XmlEvent::EndElement { .. } => {
+                    let node = open.pop().ok_or("Malformed XML: unbalanced elements.")?;
+
+                    match open.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => {
+                            return Ok(node);
+                        }
+                    }
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(node) = open.last_mut() {
+                        node.text.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return Err("Malformed XML: the document has no root element.".to_string());
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        return self
+            .attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    /// The attribute, or an error naming the element it is missing from.
+    pub fn required_attribute(&self, name: &str) -> Result<&str, String> {
+        return self.attribute(name).ok_or(format!(
+            "<{}> is missing its '{}' attribute.",
+            self.name, name
+        ));
+    }
+
+    pub fn child(&self, name: &str) -> Option<&XmlNode> {
+        return self.children.iter().find(|child| child.name == name);
+    }
+
+    /// The child, or an error naming the element it is missing from.
+    pub fn required_child(&self, name: &str) -> Result<&XmlNode, String> {
+        return self
+            .child(name)
+            .ok_or(format!("<{}> is missing its <{}>.", self.name, name));
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> {
+        return self.children.iter().filter(move |child| child.name == name);
+    }
+
+    /// Follow a path of child names, such as "technique_common/accessor".
+    pub fn find(&self, path: &str) -> Option<&XmlNode> {
+        let mut node = self;
+        for name in path.split('/') {
+            node = node.child(name)?;
+        }
+
+        return Some(node);
+    }
+
+    /// Visit this element and everything beneath it, parents first.
+    pub fn visit<'a>(&'a self, visitor: &mut dyn FnMut(&'a XmlNode)) {
+        visitor(self);
+        for child in self.children.iter() {
+            child.visit(visitor);
+        }
+    }
+
+    pub fn words(&self) -> Vec<&str> {
+        return self.text.split_whitespace().collect();
+    }
+
+    pub fn floats(&self) -> Result<Vec<f32>, String> {
+        return self
+            .words()
+            .iter()
+            .map(|word| {
+                word.parse::<f32>()
+                    .map_err(|_| format!("<{}> has '{}', which is not a number.", self.name, word))
+            })
+            .collect();
+    }
+
+    pub fn indices(&self) -> Result<Vec<usize>, String> {
+        return self
+            .words()
+            .iter()
+            .map(|word| {
+                word.parse::<usize>()
+                    .map_err(|_| format!("<{}> has '{}', which is not an index.", self.name, word))
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_node_parse_builds_tree_with_text_and_attributes() {
+        let root =
+            XmlNode::parse("<a xmlns=\"x\"><b id=\"first\">1 2.5 3</b><b id=\"second\"/></a>")
+                .unwrap();
+
+        assert_eq!("a", root.name);
+        assert_eq!(2, root.children_named("b").count());
+        assert_eq!(Some("first"), root.children[0].attribute("id"));
+        assert_eq!(vec![1.0, 2.5, 3.0], root.children[0].floats().unwrap());
+    }
+
+    #[test]
+    fn xml_node_parse_unclosed_element_returns_err() {
+        assert!(XmlNode::parse("<a><b></a>").is_err());
+    }
+
+    #[test]
+    fn xml_node_floats_not_a_number_returns_err() {
+        let root = XmlNode::parse("<a>1 two 3</a>").unwrap();
+
+        assert!(root.floats().is_err());
+    }
+}
@@ -0,0 +1,397 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/// A JSON value, read entirely into memory. Objects keep their members in the order they were written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+
+        let value = parser.value()?;
+
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("the document continues after its value"));
+        }
+
+        return Ok(value);
+    }
+
+    /// The member of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => {
+                return members.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+            }
+            _ => {
+                return None;
+            }
+        }
+    }
+
+    /// The member, or an error naming it.
+    pub fn required(&self, key: &str) -> Result<&Json, String> {
+        return self.get(key).ok_or(format!("'{}' is missing.", key));
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Json::Number(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    /// Whole, non negative numbers, such as indices and counts.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// The array member, or nothing if there is no such member.
+    pub fn array(&self, key: &str) -> Result<&[Json], String> {
+        match self.get(key) {
+            Some(value) => {
+                return value
+                    .as_array()
+                    .map(|values| values.as_slice())
+                    .ok_or(format!("'{}' is not an array.", key));
+            }
+            None => {
+                return Ok(&[]);
+            }
+        }
+    }
+
+    pub fn string(&self, key: &str) -> Result<Option<&str>, String> {
+        return self.member(key, "a string", |value| value.as_str());
+    }
+
+    pub fn index(&self, key: &str) -> Result<Option<usize>, String> {
+        return self.member(key, "an index", |value| value.as_usize());
+    }
+
+    pub fn required_index(&self, key: &str) -> Result<usize, String> {
+        return self.index(key)?.ok_or(format!("'{}' is missing.", key));
+    }
+
+    /// An array of numbers with exactly the given length.
+    pub fn numbers(&self, key: &str, count: usize) -> Result<Option<Vec<f32>>, String> {
+        let values = match self.get(key) {
+            Some(values) => values,
+            None => {
+                return Ok(None);
+            }
+        };
+
+        let numbers: Option<Vec<f32>> = values
+            .as_array()
+            .and_then(|values| values.iter().map(|value| value.as_f32()).collect());
+
+        match numbers {
+            Some(numbers) if numbers.len() == count => {
+                return Ok(Some(numbers));
+            }
+            _ => {
+                return Err(format!(
+                    "'{}' should be an array of {} numbers.",
+                    key, count
+                ));
+            }
+        }
+    }
+
+    fn member<'a, T>(
+        &'a self,
+        key: &str,
+        expected: &str,
+        convert: fn(&'a Json) -> Option<T>,
+    ) -> Result<Option<T>, String> {
+        match self.get(key) {
+            Some(value) => {
+                return convert(value)
+                    .map(|value| Some(value))
+                    .ok_or(format!("'{}' is not {}.", key, expected));
+            }
+            None => {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        return format!(
+            "Malformed JSON at character {}: {}.",
+            self.position, message
+        );
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.chars.get(self.position) {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        let c = self
+            .peek()
+            .ok_or(self.error("the document ends unexpectedly"))?;
+        self.position += 1;
+
+        return Ok(c);
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.next()? != expected {
+            self.position -= 1;
+            return Err(self.error(&format!("expected '{}'", expected)));
+        }
+
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("the document ends unexpectedly")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.next()? != expected {
+                return Err(self.error(&format!("expected '{}'", word)));
+            }
+        }
+
+        return Ok(value);
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E') {
+                break;
+            }
+
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        return text
+            .parse::<f64>()
+            .map(|value| Json::Number(value))
+            .map_err(|_| self.error(&format!("'{}' is not a number", text)));
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut value = String::new();
+        loop {
+            match self.next()? {
+                '"' => {
+                    return Ok(value);
+                }
+                '\\' => match self.next()? {
+                    '"' => value.push('"'),
+                    '\\' => value.push('\\'),
+                    '/' => value.push('/'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'u' => {
+                        let mut code = self.code_unit()?;
+
+                        // Characters outside the basic plane are written as a surrogate pair
+                        if code >= 0xD800 && code < 0xDC00 {
+                            if self.next()? != '\\' || self.next()? != 'u' {
+                                return Err(self.error("expected the rest of a surrogate pair"));
+                            }
+
+                            let low = self.code_unit()?;
+                            code = 0x10000
+                                + ((code - 0xD800) << 10)
+                                + (low.wrapping_sub(0xDC00) & 0x3FF);
+                        }
+
+                        value.push(
+                            std::char::from_u32(code)
+                                .ok_or(self.error("the string has an invalid escape"))?,
+                        );
+                    }
+                    _ => {
+                        return Err(self.error("the string has an invalid escape"));
+                    }
+                },
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn code_unit(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()?
+                .to_digit(16)
+                .ok_or(self.error("expected four hex digits"))?;
+            code = code * 16 + digit;
+        }
+
+        return Ok(code);
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => {}
+                ']' => {
+                    return Ok(Json::Array(values));
+                }
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("expected ',' or ']'"));
+                }
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => {}
+                '}' => {
+                    return Ok(Json::Object(members));
+                }
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("expected ',' or '}'"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_parse_builds_nested_values() {
+        let json =
+            Json::parse(r#"{ "a": [1, -2.5e1, true, null], "b": { "c": "d\"eé" } }"#).unwrap();
+
+        let a = json.get("a").unwrap().as_array().unwrap();
+        assert_eq!(Some(1), a[0].as_usize());
+        assert_eq!(Some(-25.0), a[1].as_f32());
+        assert_eq!(Some(true), a[2].as_bool());
+        assert_eq!(Json::Null, a[3]);
+        assert_eq!(
+            Some("d\"eé"),
+            json.get("b").unwrap().get("c").unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn json_parse_unclosed_array_returns_err() {
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("{\"a\": 1} 2").is_err());
+    }
+
+    #[test]
+    fn json_index_wrong_type_returns_err() {
+        let json = Json::parse(r#"{ "count": -1, "name": "x" }"#).unwrap();
+
+        assert!(json.index("count").is_err());
+        assert_eq!(Ok(None), json.index("missing"));
+        assert_eq!(Ok(Some("x")), json.string("name"));
+    }
+}
@@ -0,0 +1,1453 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+mod json;
+
+/*
+    glTF 2.0 import, from either a .gltf document or a .glb container. The scene's nodes become the model's nodes, the
+    nodes that any skin uses as joints the bones of the model's skeleton, and every primitive of an instanced mesh a
+    mesh. Buffers may be embedded as base64 data uris, stored in the .glb's binary chunk or in files beside the model.
+
+    glTF is y up and in meters, so root nodes are turned z up like COLLADA's y up documents, and skinned meshes have
+    the same conversion baked into their vertices. Texture coordinates are flipped to start at the bottom of the image.
+    Animations become one clip each, with every keyframe of a joint's translation, rotation and scale channels
+    combined so joints placed beneath other nodes still animate correctly. Cubic spline keys are played back linearly.
+    Morph targets, sparse accessors, points, lines and required extensions are errors rather than being dropped.
+*/
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+extern crate nalgebra as na;
+
+use crate::cb_animation;
+use cb_animation::clips::{AnimationClip, Interpolation, Track};
+use cb_animation::skeleton::Skeleton;
+use cb_animation::{blend_rotations, seconds_to_ticks, AnimRotation, AnimVector};
+
+use crate::cb_graphics;
+use cb_graphics::mesh::Mesh;
+use cb_graphics::models::{self, Material, Model, ModelMatrix, ModelMesh, Skin, SkinWeights};
+
+use json::Json;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+/// Import a .gltf or .glb file, naming the model after the file.
+pub fn from_path(path: &Path) -> Result<Model, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("model");
+
+    let directory = path.parent().unwrap_or(Path::new("."));
+
+    return parse(&bytes, name, directory)
+        .map_err(|e| format!("Unable to import '{}': {}", path.display(), e));
+}
+
+/// Import a .gltf document or .glb container. Buffers in other files are read relative to the directory.
+pub fn parse(bytes: &[u8], name: &str, directory: &Path) -> Result<Model, String> {
+    let (document, binary) = if bytes.starts_with(b"glTF") {
+        read_glb(bytes)?
+    } else {
+        let document =
+            std::str::from_utf8(bytes).map_err(|_| "The document is not UTF-8.".to_string())?;
+        (document, None)
+    };
+
+    let root = Json::parse(document)?;
+
+    let version = root
+        .required("asset")?
+        .string("version")?
+        .ok_or("The asset has no version.")?;
+    if !version.starts_with("2.") {
+        return Err(format!(
+            "Expected a glTF 2.0 document, but found version {}.",
+            version
+        ));
+    }
+
+    if let Some(extension) = root.array("extensionsRequired")?.first() {
+        return Err(format!(
+            "The document requires the extension {}, which is not supported.",
+            extension.as_str().unwrap_or("?")
+        ));
+    }
+
+    let buffers = read_buffers(&root, binary, directory)?;
+
+    let mut importer = Importer::new(&root, buffers, name);
+    importer.import_materials()?;
+    importer.import_scene()?;
+    importer.import_animations()?;
+
+    return Ok(importer.model);
+}
+
+/// Split a .glb into its JSON document and binary chunk.
+fn read_glb(bytes: &[u8]) -> Result<(&str, Option<&[u8]>), String> {
+    let word = |offset: usize| -> Result<u32, String> {
+        return bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or("The binary file ends early.".to_string());
+    };
+
+    if word(0)? != GLB_MAGIC {
+        return Err("The binary file does not start with 'glTF'.".to_string());
+    }
+
+    let version = word(4)?;
+    if version != 2 {
+        return Err(format!("Binary glTF version {} is not supported.", version));
+    }
+
+    let length = (word(8)? as usize).min(bytes.len());
+    let mut document = None;
+    let mut binary = None;
+    let mut offset = 12;
+
+    while offset + 8 <= length {
+        let chunk_length = word(offset)? as usize;
+        let chunk_type = word(offset + 4)?;
+
+        let start = offset + 8;
+        let end = start + chunk_length;
+        if end > length {
+            return Err("A chunk runs past the end of the binary file.".to_string());
+        }
+
+        match chunk_type {
+            GLB_JSON_CHUNK if document.is_none() => document = Some(&bytes[start..end]),
+            GLB_BIN_CHUNK if binary.is_none() => binary = Some(&bytes[start..end]),
+            _ => {}
+        }
+
+        offset = end;
+    }
+
+    let document = document.ok_or("The binary file has no JSON chunk.")?;
+    let document =
+        std::str::from_utf8(document).map_err(|_| "The JSON chunk is not UTF-8.".to_string())?;
+
+    return Ok((document, binary));
+}
+
+fn read_buffers(
+    root: &Json,
+    binary: Option<&[u8]>,
+    directory: &Path,
+) -> Result<Vec<Vec<u8>>, String> {
+    let mut buffers = vec![];
+
+    for (i, buffer) in root.array("buffers")?.iter().enumerate() {
+        let length = buffer
+            .required_index("byteLength")
+            .map_err(|e| format!("Buffer {}: {}", i, e))?;
+
+        let data = match buffer.string("uri")? {
+            Some(uri) if uri.starts_with("data:") => {
+                let split = uri
+                    .find(";base64,")
+                    .ok_or(format!("Buffer {}'s data uri is not base64.", i))?;
+
+                decode_base64(&uri[split + 8..]).map_err(|e| format!("Buffer {}: {}", i, e))?
+            }
+            Some(uri) => {
+                let path = directory.join(decode_uri(uri));
+                std::fs::read(&path)
+                    .map_err(|e| format!("Unable to read buffer '{}': {}", path.display(), e))?
+            }
+            None => match binary {
+                Some(binary) if i == 0 => binary.to_vec(),
+                _ => {
+                    return Err(format!(
+                        "Buffer {} has no uri and is not the binary chunk.",
+                        i
+                    ));
+                }
+            },
+        };
+
+        if data.len() < length {
+            return Err(format!(
+                "Buffer {} should be {} bytes, but is {}.",
+                i,
+                length,
+                data.len()
+            ));
+        }
+
+        buffers.push(data);
+    }
+
+    return Ok(buffers);
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => {
+                break;
+            }
+            _ => {
+                return Err(format!("'{}' is not a base64 character.", c as char));
+            }
+        };
+
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+
+    return Ok(bytes);
+}
+
+/// Undo the percent escapes in a uri, such as "%20" for spaces.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = match (bytes[i], uri.get(i + 1..i + 3)) {
+            (b'%', Some(hex)) => u8::from_str_radix(hex, 16).ok(),
+            _ => None,
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    return String::from_utf8_lossy(&decoded).to_string();
+}
+
+/// The item at the index of one of the document's top level arrays.
+fn item<'a>(root: &'a Json, key: &str, index: usize) -> Result<&'a Json, String> {
+    return root
+        .array(key)?
+        .get(index)
+        .ok_or(format!("There is no item {} in '{}'.", index, key));
+}
+
+fn indices(values: &[Json], key: &str) -> Result<Vec<usize>, String> {
+    return values
+        .iter()
+        .map(|value| {
+            value
+                .as_usize()
+                .ok_or(format!("'{}' should only hold indices.", key))
+        })
+        .collect();
+}
+
+/// The values an accessor reads, with a number of components per element.
+struct Accessor {
+    values: Vec<f64>,
+    components: usize,
+}
+
+impl Accessor {
+    fn count(&self) -> usize {
+        return self.values.len() / self.components;
+    }
+
+    fn element(&self, index: usize) -> Vec<f32> {
+        let start = index * self.components;
+        return self.values[start..start + self.components]
+            .iter()
+            .map(|value| *value as f32)
+            .collect();
+    }
+
+    fn indices(&self) -> Vec<usize> {
+        return self.values.iter().map(|value| *value as usize).collect();
+    }
+
+    fn expect(&self, name: &str, components: &[usize], count: usize) -> Result<(), String> {
+        if !components.contains(&self.components) {
+            return Err(format!(
+                "{} has {} components per element, which is not supported.",
+                name, self.components
+            ));
+        }
+
+        if self.count() != count {
+            return Err(format!(
+                "{} has {} elements, but {} were expected.",
+                name,
+                self.count(),
+                count
+            ));
+        }
+
+        return Ok(());
+    }
+}
+
+/// Read one component of an accessor's element, scaling normalized integers to between -1 and 1.
+fn read_component(bytes: &[u8], component_type: usize, normalized: bool) -> f64 {
+    let (value, max) = match component_type {
+        5120 => (bytes[0] as i8 as f64, 127.0),
+        5121 => (bytes[0] as f64, 255.0),
+        5122 => (i16::from_le_bytes([bytes[0], bytes[1]]) as f64, 32767.0),
+        5123 => (u16::from_le_bytes([bytes[0], bytes[1]]) as f64, 65535.0),
+        5125 => (
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            4294967295.0,
+        ),
+        _ => (
+            f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            1.0,
+        ),
+    };
+
+    if normalized {
+        return (value / max).max(-1.0);
+    }
+
+    return value;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ChannelPath {
+    Translation,
+    Rotation,
+    Scale,
+}
+
+/// An animation sampler, read for a channel.
+struct Channel {
+    path: ChannelPath,
+    interpolation: Interpolation,
+    times: Vec<f32>,
+    values: Vec<Vec<f32>>,
+}
+
+impl Channel {
+    /// The keyframe values on either side of the time in seconds, and how far between them it is.
+    fn keys(&self, time: f32) -> (&[f32], &[f32], f32) {
+        let last = self.times.len() - 1;
+        let next = match self.times.iter().position(|t| *t > time) {
+            Some(next) => next,
+            None => {
+                return (&self.values[last], &self.values[last], 0.0);
+            }
+        };
+
+        if next == 0 || self.interpolation == Interpolation::Step {
+            let key = next.saturating_sub(1);
+            return (&self.values[key], &self.values[key], 0.0);
+        }
+
+        let (before, after) = (self.times[next - 1], self.times[next]);
+        return (
+            &self.values[next - 1],
+            &self.values[next],
+            (time - before) / (after - before),
+        );
+    }
+}
+
+/// A node's translation, rotation and scale.
+fn node_trs(json: &Json) -> Result<(AnimVector, AnimRotation, AnimVector), String> {
+    let translation = match json.numbers("translation", 3)? {
+        Some(t) => AnimVector::new(t[0], t[1], t[2]),
+        None => AnimVector::zeros(),
+    };
+
+    let rotation = match json.numbers("rotation", 4)? {
+        Some(r) => to_rotation(&r),
+        None => AnimRotation::identity(),
+    };
+
+    let scale = match json.numbers("scale", 3)? {
+        Some(s) => AnimVector::new(s[0], s[1], s[2]),
+        None => AnimVector::new(1.0, 1.0, 1.0),
+    };
+
+    return Ok((translation, rotation, scale));
+}
+
+/// glTF writes quaternions with w last.
+fn to_rotation(values: &[f32]) -> AnimRotation {
+    return AnimRotation::from_quaternion(na::Quaternion::new(
+        values[3], values[0], values[1], values[2],
+    ));
+}
+
+fn trs_matrix(
+    translation: &AnimVector,
+    rotation: &AnimRotation,
+    scale: &AnimVector,
+) -> ModelMatrix {
+    return ModelMatrix::new_translation(translation)
+        * rotation.to_homogeneous()
+        * ModelMatrix::new_nonuniform_scaling(scale);
+}
+
+fn node_transform(json: &Json) -> Result<ModelMatrix, String> {
+    if let Some(matrix) = json.numbers("matrix", 16)? {
+        return Ok(ModelMatrix::from_column_slice(&matrix));
+    }
+
+    let (translation, rotation, scale) = node_trs(json)?;
+    return Ok(trs_matrix(&translation, &rotation, &scale));
+}
+
+struct Importer<'a> {
+    root: &'a Json,
+    buffers: Vec<Vec<u8>>,
+    /// Converts glTF's y up to z up.
+    up: ModelMatrix,
+    model: Model,
+    skeleton: Skeleton,
+    /// The model node each of the document's nodes became.
+    nodes: HashMap<usize, usize>,
+    /// The document's nodes that are joints of a skin.
+    joints: HashSet<usize>,
+    /// The meshes imported for each of the document's meshes, per skin it was instanced with.
+    meshes: HashMap<(usize, Option<usize>), Vec<usize>>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(root: &'a Json, buffers: Vec<Vec<u8>>, name: &str) -> Self {
+        return Self {
+            root: root,
+            buffers: buffers,
+            up: na::Rotation3::from_axis_angle(&na::Vector3::x_axis(), std::f32::consts::FRAC_PI_2)
+                .to_homogeneous(),
+            model: Model::new(name),
+            skeleton: Skeleton::new(),
+            nodes: HashMap::new(),
+            joints: HashSet::new(),
+            meshes: HashMap::new(),
+        };
+    }
+
+    fn read_accessor(&self, index: usize) -> Result<Accessor, String> {
+        let accessor = item(self.root, "accessors", index)?;
+
+        return self
+            .read_accessor_values(accessor)
+            .map_err(|e| format!("Accessor {}: {}", index, e));
+    }
+
+    fn read_accessor_values(&self, accessor: &Json) -> Result<Accessor, String> {
+        if accessor.get("sparse").is_some() {
+            return Err("Sparse accessors are not supported.".to_string());
+        }
+
+        let count = accessor.required_index("count")?;
+        let components = match accessor.string("type")?.ok_or("'type' is missing.")? {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" | "MAT2" => 4,
+            "MAT3" => 9,
+            "MAT4" => 16,
+            kind => {
+                return Err(format!("The type '{}' is not supported.", kind));
+            }
+        };
+
+        let component_type = accessor.required_index("componentType")?;
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => {
+                return Err(format!(
+                    "The component type {} is not supported.",
+                    component_type
+                ));
+            }
+        };
+
+        let normalized = accessor
+            .get("normalized")
+            .and_then(|normalized| normalized.as_bool())
+            .unwrap_or(false);
+
+        // Accessors without a buffer view are all zeros
+        let view_index = match accessor.index("bufferView")? {
+            Some(view) => view,
+            None => {
+                return Ok(Accessor {
+                    values: vec![0.0; count * components],
+                    components: components,
+                });
+            }
+        };
+
+        let view = item(self.root, "bufferViews", view_index)?;
+        let buffer = self
+            .buffers
+            .get(view.required_index("buffer")?)
+            .ok_or(format!(
+                "Buffer view {} refers to a missing buffer.",
+                view_index
+            ))?;
+
+        let view_offset = view.index("byteOffset")?.unwrap_or(0);
+        let view_length = view.required_index("byteLength")?;
+        if view_offset + view_length > buffer.len() {
+            return Err(format!(
+                "Buffer view {} runs past the end of its buffer.",
+                view_index
+            ));
+        }
+
+        let data = &buffer[view_offset..view_offset + view_length];
+        let element_size = components * size;
+        let stride = view.index("byteStride")?.unwrap_or(element_size);
+        let offset = accessor.index("byteOffset")?.unwrap_or(0);
+
+        if count > 0 && offset + stride * (count - 1) + element_size > data.len() {
+            return Err(format!(
+                "The accessor reads past the end of buffer view {}.",
+                view_index
+            ));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            for c in 0..components {
+                let at = offset + i * stride + c * size;
+                values.push(read_component(
+                    &data[at..at + size],
+                    component_type,
+                    normalized,
+                ));
+            }
+        }
+
+        return Ok(Accessor {
+            values: values,
+            components: components,
+        });
+    }
+
+    fn import_materials(&mut self) -> Result<(), String> {
+        for (i, json) in self.root.array("materials")?.iter().enumerate() {
+            let material = self
+                .read_material(i, json)
+                .map_err(|e| format!("Material {}: {}", i, e))?;
+
+            self.model.materials.push(material);
+        }
+
+        return Ok(());
+    }
+
+    fn read_material(&self, index: usize, json: &'a Json) -> Result<Material, String> {
+        let default_name = format!("material {}", index);
+        let mut material = Material::new(json.string("name")?.unwrap_or(&default_name));
+
+        if let Some(pbr) = json.get("pbrMetallicRoughness") {
+            if let Some(color) = pbr.numbers("baseColorFactor", 4)? {
+                material.diffuse = [color[0], color[1], color[2], color[3]];
+            }
+
+            if let Some(texture) = pbr.get("baseColorTexture") {
+                material.texture = self.texture_image(texture.required_index("index")?)?;
+            }
+        }
+
+        return Ok(material);
+    }
+
+    /// The file of a texture's image. Images embedded in the document have none.
+    fn texture_image(&self, texture: usize) -> Result<Option<String>, String> {
+        let source = match item(self.root, "textures", texture)?.index("source")? {
+            Some(source) => source,
+            None => {
+                return Ok(None);
+            }
+        };
+
+        match item(self.root, "images", source)?.string("uri")? {
+            Some(uri) if !uri.starts_with("data:") => {
+                return Ok(Some(decode_uri(uri)));
+            }
+            _ => {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// The nodes at the top of the scene, or every node that isn't a child if the document has no scenes.
+    fn scene_roots(&self) -> Result<Vec<usize>, String> {
+        let scene = match self.root.index("scene")? {
+            Some(scene) => Some(scene),
+            None if !self.root.array("scenes")?.is_empty() => Some(0),
+            None => None,
+        };
+
+        if let Some(scene) = scene {
+            return indices(item(self.root, "scenes", scene)?.array("nodes")?, "nodes");
+        }
+
+        let nodes = self.root.array("nodes")?;
+        let mut children = HashSet::new();
+        for node in nodes.iter() {
+            children.extend(indices(node.array("children")?, "children")?);
+        }
+
+        return Ok((0..nodes.len()).filter(|n| !children.contains(n)).collect());
+    }
+
+    fn import_scene(&mut self) -> Result<(), String> {
+        for skin in self.root.array("skins")? {
+            self.joints
+                .extend(indices(skin.array("joints")?, "joints")?);
+        }
+
+        // Skinned meshes may bind to any joint in the scene, so every node is read before them
+        let mut instances = vec![];
+        for node in self.scene_roots()? {
+            self.import_node(node, None, &mut instances)?;
+        }
+
+        if !self.skeleton.is_empty() {
+            self.model.skeleton = Some(self.skeleton.clone());
+        }
+
+        self.import_skins()?;
+
+        for (node, index) in instances {
+            let meshes = self.import_instance(index)?;
+            self.model.nodes[node].meshes.extend(meshes);
+        }
+
+        return Ok(());
+    }
+
+    fn import_node(
+        &mut self,
+        index: usize,
+        parent: Option<usize>,
+        instances: &mut Vec<(usize, usize)>,
+    ) -> Result<(), String> {
+        if self.nodes.contains_key(&index) {
+            return Err(format!(
+                "Node {} appears more than once in the scene.",
+                index
+            ));
+        }
+
+        let json = item(self.root, "nodes", index)?;
+        let default_name = format!("node {}", index);
+        let name = json.string("name")?.unwrap_or(&default_name);
+
+        let mut transform = node_transform(json).map_err(|e| format!("Node '{}': {}", name, e))?;
+        if parent.is_none() {
+            transform = self.up * transform;
+        }
+
+        let node = self.model.add_node(name, parent, transform)?;
+        self.nodes.insert(index, node);
+
+        if self.joints.contains(&index) {
+            let parent_bone = self
+                .model
+                .parent_bone_node(node)
+                .and_then(|parent| self.model.nodes[parent].bone);
+            let rest = models::decompose(&self.model.bone_space(node, transform));
+
+            let bone = self.skeleton.add_bone(name, parent_bone, rest)?;
+            self.model.nodes[node].bone = Some(bone);
+        }
+
+        if json.get("mesh").is_some() {
+            instances.push((node, index));
+        }
+
+        for child in indices(json.array("children")?, "children")? {
+            self.import_node(child, Some(node), instances)?;
+        }
+
+        return Ok(());
+    }
+
+    fn import_skins(&mut self) -> Result<(), String> {
+        for (i, json) in self.root.array("skins")?.iter().enumerate() {
+            let skin = self
+                .read_skin(json)
+                .map_err(|e| format!("Skin {}: {}", i, e))?;
+
+            self.model.skins.push(skin);
+        }
+
+        return Ok(());
+    }
+
+    fn read_skin(&self, json: &'a Json) -> Result<Skin, String> {
+        let mut bones = vec![];
+        for joint in indices(json.array("joints")?, "joints")? {
+            let bone = self
+                .nodes
+                .get(&joint)
+                .and_then(|node| self.model.nodes[*node].bone)
+                .ok_or(format!("Joint node {} is not in the scene.", joint))?;
+
+            bones.push(bone);
+        }
+
+        // The joints were moved by the change of up axis, so their bind matrices must undo it
+        let from_up = self.up.try_inverse().unwrap_or(ModelMatrix::identity());
+
+        let inverse_bind_matrices = match json.index("inverseBindMatrices")? {
+            Some(accessor) => {
+                let matrices = self.read_accessor(accessor)?;
+                matrices.expect("The inverse bind matrices", &[16], bones.len())?;
+
+                (0..bones.len())
+                    .map(|i| ModelMatrix::from_column_slice(&matrices.element(i)) * from_up)
+                    .collect()
+            }
+            None => vec![from_up; bones.len()],
+        };
+
+        return Ok(Skin {
+            bones: bones,
+            inverse_bind_matrices: inverse_bind_matrices,
+        });
+    }
+
+    fn import_instance(&mut self, index: usize) -> Result<Vec<usize>, String> {
+        let json = item(self.root, "nodes", index)?;
+        let mesh = json.required_index("mesh")?;
+
+        let skin = json.index("skin")?;
+        if let Some(skin) = skin {
+            if skin >= self.model.skins.len() {
+                return Err(format!(
+                    "Node {} uses skin {}, which does not exist.",
+                    index, skin
+                ));
+            }
+        }
+
+        if let Some(meshes) = self.meshes.get(&(mesh, skin)) {
+            return Ok(meshes.clone());
+        }
+
+        let json = item(self.root, "meshes", mesh)?;
+        let default_name = format!("mesh {}", mesh);
+        let name = json.string("name")?.unwrap_or(&default_name);
+
+        let mut meshes = vec![];
+        for (i, primitive) in json.array("primitives")?.iter().enumerate() {
+            let model_mesh = self
+                .import_primitive(name, primitive, skin)
+                .map_err(|e| format!("Mesh '{}' primitive {}: {}", name, i, e))?;
+
+            self.model.meshes.push(model_mesh);
+            meshes.push(self.model.meshes.len() - 1);
+        }
+
+        self.meshes.insert((mesh, skin), meshes.clone());
+
+        return Ok(meshes);
+    }
+
+    fn import_primitive(
+        &self,
+        name: &str,
+        primitive: &'a Json,
+        skin: Option<usize>,
+    ) -> Result<ModelMesh, String> {
+        if !primitive.array("targets")?.is_empty() {
+            return Err("Morph targets are not supported.".to_string());
+        }
+
+        let attributes = primitive.required("attributes")?;
+        let attribute = |semantic: &str| -> Result<Option<Accessor>, String> {
+            match attributes.index(semantic)? {
+                Some(accessor) => Ok(Some(self.read_accessor(accessor)?)),
+                None => Ok(None),
+            }
+        };
+
+        let positions = attribute("POSITION")?.ok_or("The primitive has no POSITION attribute.")?;
+        let count = positions.count();
+        positions.expect("POSITION", &[3], count)?;
+
+        let normals = attribute("NORMAL")?;
+        let colors = attribute("COLOR_0")?;
+        let uvs = attribute("TEXCOORD_0")?;
+
+        for (semantic, accessor, components) in [
+            ("NORMAL", &normals, &[3][..]),
+            ("COLOR_0", &colors, &[3, 4][..]),
+            ("TEXCOORD_0", &uvs, &[2][..]),
+        ]
+        .iter()
+        {
+            if let Some(accessor) = accessor {
+                accessor.expect(semantic, components, count)?;
+            }
+        }
+
+        let material = primitive.index("material")?;
+        if let Some(material) = material {
+            if material >= self.model.materials.len() {
+                return Err(format!("Material {} does not exist.", material));
+            }
+        }
+
+        let vertex_indices = match primitive.index("indices")? {
+            Some(accessor) => {
+                let accessor = self.read_accessor(accessor)?;
+                accessor.expect("The indices", &[1], accessor.count())?;
+                accessor.indices()
+            }
+            None => (0..count).collect(),
+        };
+
+        if let Some(index) = vertex_indices.iter().find(|index| **index >= count) {
+            return Err(format!(
+                "Index {} is out of range of the primitive's {} vertices.",
+                index, count
+            ));
+        }
+
+        // Strips and fans are unrolled into a list of triangles
+        let mut triangles: Vec<usize> = vec![];
+        match primitive.index("mode")?.unwrap_or(4) {
+            4 => triangles = vertex_indices[..vertex_indices.len() / 3 * 3].to_vec(),
+            5 => {
+                for i in 0..vertex_indices.len().saturating_sub(2) {
+                    let (a, b) = if i % 2 == 0 { (i, i + 1) } else { (i + 1, i) };
+                    triangles.extend(&[
+                        vertex_indices[a],
+                        vertex_indices[b],
+                        vertex_indices[i + 2],
+                    ]);
+                }
+            }
+            6 => {
+                for i in 1..vertex_indices.len().saturating_sub(1) {
+                    triangles.extend(&[
+                        vertex_indices[0],
+                        vertex_indices[i],
+                        vertex_indices[i + 1],
+                    ]);
+                }
+            }
+            mode => {
+                return Err(format!(
+                    "Mode {} draws points or lines, which are not supported.",
+                    mode
+                ));
+            }
+        }
+
+        let default_color = match material {
+            Some(material) => self.model.materials[material].diffuse,
+            None => models::DEFAULT_COLOR,
+        };
+
+        let bake = match skin {
+            Some(_) => self.up,
+            None => ModelMatrix::identity(),
+        };
+
+        let mut mesh = Mesh::new(3, vec![], vec![], 3, vec![], 3, vec![], 0);
+
+        for i in 0..count {
+            let p = positions.element(i);
+            let p = bake.transform_point(&na::Point3::new(p[0], p[1], p[2]));
+            mesh.vertices.extend(&[p.x, p.y, p.z]);
+
+            if let Some(normals) = &normals {
+                let n = normals.element(i);
+                let n = bake
+                    .transform_vector(&na::Vector3::new(n[0], n[1], n[2]))
+                    .normalize();
+                mesh.normals.extend(&[n.x, n.y, n.z]);
+            }
+
+            match &colors {
+                Some(colors) => mesh.colors.extend(&colors.element(i)[0..3]),
+                None => mesh.colors.extend(&default_color[0..3]),
+            }
+
+            if let Some(uvs) = &uvs {
+                let t = uvs.element(i);
+                mesh.uvs.extend(&[t[0], 1.0 - t[1]]);
+            }
+        }
+
+        mesh.indices = triangles.iter().map(|i| *i as i32).collect();
+
+        if normals.is_none() {
+            mesh.normals = models::smooth_normals(&mesh);
+        }
+
+        let skin = match skin {
+            Some(skin) => {
+                let joints = attribute("JOINTS_0")?
+                    .ok_or("The primitive is skinned, but has no JOINTS_0 attribute.")?;
+                let vertex_weights = attribute("WEIGHTS_0")?
+                    .ok_or("The primitive is skinned, but has no WEIGHTS_0 attribute.")?;
+                joints.expect("JOINTS_0", &[4], count)?;
+                vertex_weights.expect("WEIGHTS_0", &[4], count)?;
+
+                let joint_count = self.model.skins[skin].bones.len();
+                let mut weights = SkinWeights {
+                    skin: skin,
+                    joints: vec![],
+                    weights: vec![],
+                };
+
+                for i in 0..count {
+                    let mut influences = vec![];
+                    for (joint, weight) in joints
+                        .element(i)
+                        .iter()
+                        .zip(vertex_weights.element(i).iter())
+                    {
+                        if *weight <= 0.0 {
+                            continue;
+                        }
+
+                        let joint = *joint as usize;
+                        if joint >= joint_count {
+                            return Err(format!("Vertex weight joint {} does not exist.", joint));
+                        }
+
+                        influences.push((joint, *weight));
+                    }
+
+                    let (joints, vertex_weights) = SkinWeights::strongest(&mut influences);
+                    weights.joints.push(joints);
+                    weights.weights.push(vertex_weights);
+                }
+
+                Some(weights)
+            }
+            None => None,
+        };
+
+        return Ok(ModelMesh {
+            name: name.to_string(),
+            mesh: mesh,
+            material: material,
+            skin: skin,
+        });
+    }
+
+    fn import_animations(&mut self) -> Result<(), String> {
+        for (i, json) in self.root.array("animations")?.iter().enumerate() {
+            let default_name = format!("animation {}", i);
+            let name = json.string("name")?.unwrap_or(&default_name);
+
+            let clip = self
+                .import_animation(name, json)
+                .map_err(|e| format!("Animation '{}': {}", name, e))?;
+
+            if !clip.tracks.is_empty() {
+                self.model.clips.push(clip);
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Convert an animation into a clip. Channels on nodes that aren't joints are skipped.
+    fn import_animation(&self, name: &str, json: &'a Json) -> Result<AnimationClip, String> {
+        let samplers = json.array("samplers")?;
+
+        // The channels of each animated joint, in the document's node order
+        let mut joints: BTreeMap<usize, Vec<Channel>> = BTreeMap::new();
+
+        for channel in json.array("channels")? {
+            let target = channel.required("target")?;
+            let node = match target.index("node")? {
+                Some(node) => node,
+                None => {
+                    continue;
+                }
+            };
+
+            let path = match target
+                .string("path")?
+                .ok_or("A channel's target has no path.")?
+            {
+                "translation" => ChannelPath::Translation,
+                "rotation" => ChannelPath::Rotation,
+                "scale" => ChannelPath::Scale,
+                path => {
+                    return Err(format!("Animating a node's {} is not supported.", path));
+                }
+            };
+
+            let is_joint = self
+                .nodes
+                .get(&node)
+                .map(|model_node| self.model.nodes[*model_node].bone.is_some())
+                .ok_or(format!(
+                    "A channel targets node {}, which is not in the scene.",
+                    node
+                ))?;
+
+            if !is_joint {
+                continue;
+            }
+
+            let sampler = samplers
+                .get(channel.required_index("sampler")?)
+                .ok_or("A channel refers to a sampler that does not exist.")?;
+
+            joints
+                .entry(node)
+                .or_insert(vec![])
+                .push(self.read_sampler(sampler, path)?);
+        }
+
+        let mut clip = AnimationClip::new(name, 0, true);
+
+        for (node, channels) in joints {
+            let json = item(self.root, "nodes", node)?;
+            if json.get("matrix").is_some() {
+                return Err(format!(
+                    "Node {} is animated, but its transform is a matrix.",
+                    node
+                ));
+            }
+
+            let model_node = self.nodes[&node];
+            let bone = match self.model.nodes[model_node].bone {
+                Some(bone) => bone,
+                None => {
+                    continue;
+                }
+            };
+
+            let interpolation = if channels
+                .iter()
+                .all(|channel| channel.interpolation == Interpolation::Step)
+            {
+                Interpolation::Step
+            } else {
+                Interpolation::Linear
+            };
+
+            let mut times: Vec<f32> = channels
+                .iter()
+                .flat_map(|channel| channel.times.iter().copied())
+                .collect();
+            times.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            times.dedup();
+
+            let (rest_translation, rest_rotation, rest_scale) = node_trs(json)?;
+            let is_root = self.model.nodes[model_node].parent.is_none();
+            let mut track = Track::new(bone, interpolation);
+
+            // Channels only key part of the node's transform, so the rest comes from the other channels or the node
+            for time in times {
+                let mut translation = rest_translation;
+                let mut rotation = rest_rotation;
+                let mut scale = rest_scale;
+
+                for channel in channels.iter() {
+                    let (before, after, t) = channel.keys(time);
+
+                    match channel.path {
+                        ChannelPath::Translation => {
+                            translation = AnimVector::from_column_slice(before)
+                                .lerp(&AnimVector::from_column_slice(after), t);
+                        }
+                        ChannelPath::Rotation => {
+                            rotation =
+                                blend_rotations(&to_rotation(before), &to_rotation(after), t);
+                        }
+                        ChannelPath::Scale => {
+                            scale = AnimVector::from_column_slice(before)
+                                .lerp(&AnimVector::from_column_slice(after), t);
+                        }
+                    }
+                }
+
+                let mut matrix = trs_matrix(&translation, &rotation, &scale);
+                if is_root {
+                    matrix = self.up * matrix;
+                }
+
+                let transform = models::decompose(&self.model.bone_space(model_node, matrix));
+                let tick = seconds_to_ticks(time);
+
+                track.add_translation(tick, transform.translation);
+                track.add_rotation(tick, transform.rotation);
+                track.add_scale(tick, transform.scale);
+            }
+
+            clip.add_track(track);
+        }
+
+        return Ok(clip);
+    }
+
+    fn read_sampler(&self, sampler: &'a Json, path: ChannelPath) -> Result<Channel, String> {
+        let times = self.read_accessor(sampler.required_index("input")?)?;
+        times.expect("The sampler's input", &[1], times.count())?;
+
+        let outputs = self.read_accessor(sampler.required_index("output")?)?;
+        let components = match path {
+            ChannelPath::Rotation => 4,
+            _ => 3,
+        };
+
+        // Cubic splines store an in tangent, value and out tangent for every key
+        let (interpolation, stride) = match sampler.string("interpolation")?.unwrap_or("LINEAR") {
+            "LINEAR" => (Interpolation::Linear, 1),
+            "STEP" => (Interpolation::Step, 1),
+            "CUBICSPLINE" => (Interpolation::Linear, 3),
+            interpolation => {
+                return Err(format!(
+                    "The interpolation '{}' is not supported.",
+                    interpolation
+                ));
+            }
+        };
+
+        outputs.expect(
+            "The sampler's output",
+            &[components],
+            times.count() * stride,
+        )?;
+        if times.count() == 0 {
+            return Err("The sampler has no keys.".to_string());
+        }
+
+        return Ok(Channel {
+            path: path,
+            interpolation: interpolation,
+            times: (0..times.count()).map(|i| times.element(i)[0]).collect(),
+            values: (0..times.count())
+                .map(|i| outputs.element(i * stride + stride / 2))
+                .collect(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_simulation::TICKS_PER_SECOND;
+
+    /// A triangle skinned to a two joint arm with the elbow turning over one second, and a red quad above it. The
+    /// buffer views and buffer are filled in by `arm`.
+    const ARM: &'static str = r#"{
+  "asset": { "version": "2.0" },
+  "scene": 0,
+  "scenes": [{ "nodes": [0, 3, 4] }],
+  "nodes": [
+    { "name": "Armature", "children": [1] },
+    { "name": "Shoulder", "children": [2] },
+    { "name": "Elbow", "translation": [1, 0, 0] },
+    { "name": "Arm", "mesh": 0, "skin": 0 },
+    { "name": "Quad", "mesh": 1, "translation": [0, 2, 0] }
+  ],
+  "meshes": [
+    { "name": "Triangle", "primitives": [{ "attributes": { "POSITION": 0, "JOINTS_0": 2, "WEIGHTS_0": 3 }, "indices": 1 }] },
+    { "name": "Quad", "primitives": [{ "attributes": { "POSITION": 7, "TEXCOORD_0": 8 }, "indices": 9, "material": 0, "mode": 4 }] }
+  ],
+  "materials": [{ "name": "Red", "pbrMetallicRoughness": { "baseColorFactor": [1, 0, 0, 1] } }],
+  "skins": [{ "joints": [1, 2], "inverseBindMatrices": 4 }],
+  "animations": [{
+    "name": "Bend",
+    "samplers": [{ "input": 5, "output": 6 }],
+    "channels": [{ "sampler": 0, "target": { "node": 2, "path": "rotation" } }]
+  }],
+  "accessors": [
+    { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+    { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" },
+    { "bufferView": 2, "componentType": 5121, "count": 3, "type": "VEC4" },
+    { "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC4" },
+    { "bufferView": 4, "componentType": 5126, "count": 2, "type": "MAT4" },
+    { "bufferView": 5, "componentType": 5126, "count": 2, "type": "SCALAR" },
+    { "bufferView": 6, "componentType": 5126, "count": 2, "type": "VEC4" },
+    { "bufferView": 7, "componentType": 5126, "count": 4, "type": "VEC3" },
+    { "bufferView": 8, "componentType": 5126, "count": 4, "type": "VEC2" },
+    { "bufferView": 9, "componentType": 5123, "count": 6, "type": "SCALAR" }
+  ],
+  "bufferViews": [VIEWS],
+  "buffers": [BUFFER]
+}"#;
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        return values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+    }
+
+    fn shorts(values: &[u16]) -> Vec<u8> {
+        return values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+    }
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let bits = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    text.push(alphabet[(bits >> (18 - i * 6) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+
+        return text;
+    }
+
+    /// The ARM document and its buffer, either embedded as a data uri or left for a .glb's binary chunk.
+    fn arm(embed: bool) -> (String, Vec<u8>) {
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        let mut ibm = vec![
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ];
+        ibm.extend(&[
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0,
+        ]);
+
+        let views = vec![
+            floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 2.0, 0.5, 0.0]),
+            shorts(&[0, 1, 2]),
+            vec![0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0],
+            floats(&[1.0, 0.0, 0.0, 0.0, 0.25, 0.75, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0]),
+            floats(&ibm),
+            floats(&[0.0, 1.0]),
+            floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, s, s]),
+            floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]),
+            floats(&[0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]),
+            shorts(&[0, 1, 2, 0, 2, 3]),
+        ];
+
+        let mut buffer = vec![];
+        let mut json_views = vec![];
+        for view in views {
+            while buffer.len() % 4 != 0 {
+                buffer.push(0);
+            }
+
+            json_views.push(format!(
+                "{{ \"buffer\": 0, \"byteOffset\": {}, \"byteLength\": {} }}",
+                buffer.len(),
+                view.len()
+            ));
+            buffer.extend(view);
+        }
+
+        let json_buffer = if embed {
+            format!(
+                "{{ \"byteLength\": {}, \"uri\": \"data:application/octet-stream;base64,{}\" }}",
+                buffer.len(),
+                encode_base64(&buffer)
+            )
+        } else {
+            format!("{{ \"byteLength\": {} }}", buffer.len())
+        };
+
+        let document = ARM
+            .replace("VIEWS", &json_views.join(", "))
+            .replace("BUFFER", &json_buffer);
+
+        return (document, buffer);
+    }
+
+    fn parse_arm(document: &str) -> Result<Model, String> {
+        return parse(document.as_bytes(), "arm", Path::new("."));
+    }
+
+    #[test]
+    fn parse_indexed_primitive_builds_mesh() {
+        let model = parse_arm(&arm(true).0).unwrap();
+
+        let mesh = &model.meshes[1].mesh;
+        assert_eq!(vec![0, 1, 2, 0, 2, 3], mesh.indices);
+        assert_eq!(12, mesh.vertices.len());
+        assert_eq!(12, mesh.normals.len());
+        assert_eq!(vec![0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0], mesh.uvs);
+    }
+
+    #[test]
+    fn parse_material_colors_vertices() {
+        let model = parse_arm(&arm(true).0).unwrap();
+
+        assert_eq!(Some(0), model.meshes[1].material);
+        assert_eq!("Red", model.materials[0].name);
+        assert_eq!(
+            vec![1.0, 0.0, 0.0],
+            model.meshes[1].mesh.colors[0..3].to_vec()
+        );
+    }
+
+    #[test]
+    fn parse_y_up_converts_node_to_z_up() {
+        let model = parse_arm(&arm(true).0).unwrap();
+
+        let world = model.world_transforms();
+        let quad = model.nodes.iter().position(|n| n.name == "Quad").unwrap();
+        let origin = world[quad].transform_point(&na::Point3::origin());
+
+        assert!((origin - na::Point3::new(0.0, 0.0, 2.0)).norm() < 0.0001);
+        assert_eq!(vec![1], model.nodes[quad].meshes);
+    }
+
+    #[test]
+    fn parse_skin_joints_build_skeleton() {
+        let model = parse_arm(&arm(true).0).unwrap();
+
+        let skeleton = model.skeleton.unwrap();
+        assert_eq!(2, skeleton.len());
+        assert_eq!(Some(0), skeleton.bones()[1].parent);
+        assert_eq!(1.0, skeleton.bones()[1].rest.translation.x);
+    }
+
+    #[test]
+    fn parse_skin_weights_each_vertex() {
+        let model = parse_arm(&arm(true).0).unwrap();
+
+        let skin = model.meshes[0].skin.as_ref().unwrap();
+        assert_eq!(vec![0, 1], model.skins[0].bones);
+        assert_eq!([1, 0, 0, 0], skin.joints[1]);
+        assert_eq!([0.75, 0.25, 0.0, 0.0], skin.weights[1]);
+        assert!((model.skins[0].inverse_bind_matrices[1][(0, 3)] + 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn parse_rotation_channel_becomes_clip() {
+        let model = parse_arm(&arm(true).0).unwrap();
+
+        assert_eq!(1, model.clips.len());
+        let clip = &model.clips[0];
+        assert_eq!("Bend", clip.name);
+        assert_eq!(TICKS_PER_SECOND, clip.duration_ticks);
+        assert_eq!(1, clip.tracks[0].bone);
+
+        let set = model.animation_set().unwrap();
+        let mut pose = set.skeleton.rest_pose();
+        set.clips[0].sample(TICKS_PER_SECOND as f32 / 2.0, &mut pose);
+        assert!((pose.locals[1].rotation.angle() - std::f32::consts::FRAC_PI_4).abs() < 0.001);
+        assert!((pose.locals[1].translation.x - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn parse_glb_reads_binary_chunk() {
+        let (document, mut buffer) = arm(false);
+        let mut document = document.into_bytes();
+        while document.len() % 4 != 0 {
+            document.push(b' ');
+        }
+        while buffer.len() % 4 != 0 {
+            buffer.push(0);
+        }
+
+        let mut glb = vec![];
+        glb.extend(b"glTF");
+        glb.extend(&2u32.to_le_bytes());
+        glb.extend(&(12 + 8 + document.len() as u32 + 8 + buffer.len() as u32).to_le_bytes());
+        glb.extend(&(document.len() as u32).to_le_bytes());
+        glb.extend(&GLB_JSON_CHUNK.to_le_bytes());
+        glb.extend(&document);
+        glb.extend(&(buffer.len() as u32).to_le_bytes());
+        glb.extend(&GLB_BIN_CHUNK.to_le_bytes());
+        glb.extend(&buffer);
+
+        let model = parse(&glb, "arm", Path::new(".")).unwrap();
+
+        assert_eq!(2, model.meshes.len());
+        assert_eq!(1, model.clips.len());
+    }
+
+    #[test]
+    fn parse_points_mode_returns_err() {
+        let document = arm(true).0.replace("\"mode\": 4", "\"mode\": 0");
+
+        let result = parse_arm(&document);
+
+        assert!(result.unwrap_err().contains("points or lines"));
+    }
+
+    #[test]
+    fn parse_accessor_past_buffer_returns_err() {
+        let document = arm(true).0.replace(
+            "\"count\": 4, \"type\": \"VEC3\"",
+            "\"count\": 40, \"type\": \"VEC3\"",
+        );
+
+        let result = parse_arm(&document);
+
+        assert!(result.unwrap_err().contains("past the end"));
+    }
+
+    #[test]
+    fn parse_required_extension_returns_err() {
+        let document = arm(true).0.replacen(
+            "\"scene\": 0,",
+            "\"scene\": 0, \"extensionsRequired\": [\"KHR_draco_mesh_compression\"],",
+            1,
+        );
+
+        let result = parse_arm(&document);
+
+        assert!(result.unwrap_err().contains("KHR_draco_mesh_compression"));
+    }
+
+    #[test]
+    fn parse_not_gltf_2_returns_err() {
+        assert!(parse_arm("{ \"asset\": { \"version\": \"1.0\" } }").is_err());
+        assert!(parse_arm("<COLLADA></COLLADA>").is_err());
+    }
+
+    #[test]
+    fn decode_base64_round_trips() {
+        let bytes = vec![0, 1, 2, 250, 251, 252, 253];
+
+        assert_eq!(bytes, decode_base64(&encode_base64(&bytes)).unwrap());
+        assert!(decode_base64("ab$").is_err());
+    }
+}
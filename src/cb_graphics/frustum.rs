@@ -0,0 +1,190 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+
+use crate::cb_graphics;
+use cb_graphics::mesh::Mesh;
+use cb_graphics::CbCamera;
+
+/// An axis aligned bounding box.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub min: na::Vector3<f32>,
+    pub max: na::Vector3<f32>,
+}
+
+impl Bounds {
+    pub fn new(min: na::Vector3<f32>, max: na::Vector3<f32>) -> Self {
+        return Self { min: min, max: max };
+    }
+
+    /// The box around the mesh's vertices, or none if it has no vertices.
+    pub fn from_mesh(mesh: &Mesh) -> Option<Self> {
+        if mesh.vertex_size < 3 {
+            return None;
+        }
+
+        let mut vertices = mesh
+            .vertices
+            .chunks(mesh.vertex_size)
+            .filter(|v| v.len() >= 3)
+            .map(|v| na::Vector3::new(v[0], v[1], v[2]));
+
+        let first = vertices.next()?;
+        let mut bounds = Self::new(first, first);
+        for vertex in vertices {
+            bounds.min = bounds.min.zip_map(&vertex, f32::min);
+            bounds.max = bounds.max.zip_map(&vertex, f32::max);
+        }
+
+        return Some(bounds);
+    }
+
+    /// The box around this one once it has been transformed.
+    pub fn transformed(&self, transform: &na::Matrix4<f32>) -> Self {
+        let mut corners = (0..8).map(|i| {
+            let corner = na::Point3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+
+            return transform.transform_point(&corner).coords;
+        });
+
+        let first = corners.next().unwrap();
+        let mut bounds = Self::new(first, first);
+        for corner in corners {
+            bounds.min = bounds.min.zip_map(&corner, f32::min);
+            bounds.max = bounds.max.zip_map(&corner, f32::max);
+        }
+
+        return bounds;
+    }
+}
+
+/// The volume a camera can see, as six planes facing inwards.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frustum {
+    /// Each plane is (normal, distance), so points inside have a non-negative dot product with (x, y, z, 1).
+    planes: [na::Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a projection * view matrix.
+    pub fn from_matrix(proj_view: &na::Matrix4<f32>) -> Self {
+        let row = |i: usize| proj_view.row(i).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        return Self {
+            planes: [w + x, w - x, w + y, w - y, w + z, w - z],
+        };
+    }
+
+    pub fn from_camera(camera: &CbCamera) -> Self {
+        let (proj, view) = camera.proj_view();
+
+        return Self::from_matrix(&(proj * view));
+    }
+
+    /// Whether any of the box may be visible. Boxes near the frustum's corners may pass without being visible.
+    pub fn intersects(&self, bounds: &Bounds) -> bool {
+        for plane in self.planes.iter() {
+            // The corner furthest along the plane's normal is the last one to leave
+            let furthest = na::Vector3::new(
+                if plane.x >= 0.0 {
+                    bounds.max.x
+                } else {
+                    bounds.min.x
+                },
+                if plane.y >= 0.0 {
+                    bounds.max.y
+                } else {
+                    bounds.min.y
+                },
+                if plane.z >= 0.0 {
+                    bounds.max.z
+                } else {
+                    bounds.min.z
+                },
+            );
+
+            if plane.xyz().dot(&furthest) + plane.w < 0.0 {
+                return false;
+            }
+        }
+
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box(center: (f32, f32, f32)) -> Bounds {
+        let center = na::Vector3::new(center.0, center.1, center.2);
+        let half = na::Vector3::new(0.5, 0.5, 0.5);
+
+        return Bounds::new(center - half, center + half);
+    }
+
+    fn camera() -> CbCamera {
+        let mut camera = CbCamera::new(640.0, 480.0);
+        camera.pos_x = 0.0;
+        camera.pos_y = 0.0;
+        camera.pos_z = 10.0;
+        camera.target_x = 0.0;
+        camera.target_y = 0.0;
+        camera.target_z = 0.0;
+
+        return camera;
+    }
+
+    #[test]
+    fn frustum_intersects_boxes_in_front_of_camera() {
+        let frustum = Frustum::from_camera(&camera());
+
+        assert!(frustum.intersects(&unit_box((0.0, 0.0, 0.0))));
+        assert!(frustum.intersects(&unit_box((2.0, -2.0, 5.0))));
+    }
+
+    #[test]
+    fn frustum_rejects_boxes_behind_and_beside_camera() {
+        let frustum = Frustum::from_camera(&camera());
+
+        assert_eq!(false, frustum.intersects(&unit_box((0.0, 0.0, 20.0))));
+        assert_eq!(false, frustum.intersects(&unit_box((100.0, 0.0, 0.0))));
+        assert_eq!(false, frustum.intersects(&unit_box((0.0, 0.0, -2000.0))));
+    }
+
+    #[test]
+    fn frustum_intersects_boxes_crossing_its_edge() {
+        let frustum = Frustum::from_camera(&camera());
+
+        let wide = Bounds::new(
+            na::Vector3::new(-100.0, -0.5, -0.5),
+            na::Vector3::new(100.0, 0.5, 0.5),
+        );
+
+        assert!(frustum.intersects(&wide));
+    }
+
+    #[test]
+    fn bounds_transformed_contains_moved_corners() {
+        let transform = na::Matrix4::new_translation(&na::Vector3::new(1.0, 2.0, 3.0))
+            * na::Matrix4::new_rotation(na::Vector3::z() * std::f32::consts::FRAC_PI_2);
+
+        let bounds = unit_box((1.0, 0.0, 0.0)).transformed(&transform);
+
+        assert!((bounds.min - na::Vector3::new(0.5, 2.5, 2.5)).norm() < 0.0001);
+        assert!((bounds.max - na::Vector3::new(1.5, 3.5, 3.5)).norm() < 0.0001);
+    }
+
+    #[test]
+    fn bounds_from_empty_mesh_returns_none() {
+        let mesh = Mesh::new(3, vec![], vec![], 3, vec![], 3, vec![], 0);
+
+        assert_eq!(None, Bounds::from_mesh(&mesh));
+    }
+}
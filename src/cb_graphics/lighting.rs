@@ -0,0 +1,428 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    How a scene is lit. Each map has a sun, shining from a direction over everything, an ambient term that reaches
+    surfaces no light faces, and optionally fog that fades distant surfaces into a color. These come from the map's
+    datasheet. Point lights come from entities with light components, and are gathered into the render list each frame.
+
+    Surfaces are lit by the sum of the ambient, the sun and every point light in range, times their material color.
+    The sun's share is kept apart so renderers can shadow it: they draw the scene's depth from the sun into a shadow
+    map with `sun_matrix`, and leave out the sun's light wherever something nearer to the sun covers a surface.
+*/
+
+extern crate nalgebra as na;
+use na::{Isometry3, Matrix4, Orthographic3, Point3, Vector3};
+
+use std::path::Path;
+
+use crate::cb_assets;
+use cb_assets::Asset;
+
+use crate::cb_datasheets;
+use cb_datasheets::DataValue;
+
+use crate::cb_graphics;
+use cb_graphics::frustum::Bounds;
+
+/// The most point lights a renderer has to light a surface with. Renderers that are limited use the nearest.
+pub const MAX_POINT_LIGHTS: usize = 8;
+
+/// A light infinitely far away, shining the same way everywhere.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sun {
+    /// Points towards the sun, in world space. Doesn't have to be normalized.
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub shadows: bool,
+}
+
+/// A light that shines in every direction from a position, fading out until its radius.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointLight {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub radius: f32,
+}
+
+/// Fades surfaces into the color between the start and end distances from the camera, in world units.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Fog {
+    pub color: [f32; 3],
+    pub start: f32,
+    pub end: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lighting {
+    pub sun: Sun,
+    /// How much light reaches surfaces that face away from every light, from 0 to 1.
+    pub ambient: f32,
+    pub fog: Option<Fog>,
+    pub point_lights: Vec<PointLight>,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        return Self {
+            sun: Sun {
+                direction: [0.3, 0.5, 0.8],
+                color: [1.0, 1.0, 1.0],
+                shadows: false,
+            },
+            ambient: 0.3,
+            fog: None,
+            point_lights: vec![],
+        };
+    }
+}
+
+impl Lighting {
+    /// Load the lighting of the map datasheet at the given path.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let root = cb_datasheets::from_path(path)?;
+
+        return Self::from_data(&root).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    /// Parse the lighting of a map datasheet string.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root = cb_datasheets::parse(source)?;
+
+        return Self::from_data(&root);
+    }
+
+    fn from_data(root: &DataValue) -> Result<Self, String> {
+        let mut lighting = Self::default();
+
+        // Maps without a lighting block are lit like the editor
+        let data = match root.get("lighting") {
+            Some(data) => data,
+            None => {
+                return Ok(lighting);
+            }
+        };
+
+        if let Some(sun) = data.get("sun") {
+            if let Some(direction) = sun.get("direction") {
+                lighting.sun.direction = read_vector(direction, "sun direction")?;
+                if Vector3::from(lighting.sun.direction).norm() == 0.0 {
+                    return Err("'sun direction' must not be zero".to_string());
+                }
+            }
+
+            if let Some(color) = sun.get("color") {
+                lighting.sun.color = read_color(color, "sun color")?;
+            }
+
+            if let Some(shadows) = sun.get("shadows") {
+                lighting.sun.shadows = match shadows.as_bool() {
+                    Some(shadows) => shadows,
+                    None => {
+                        return Err("'sun shadows' must be true or false".to_string());
+                    }
+                };
+            }
+        }
+
+        if let Some(ambient) = data.get("ambient") {
+            lighting.ambient = match ambient.as_f32() {
+                Some(a) if a >= 0.0 && a <= 1.0 => a,
+                _ => {
+                    return Err("'ambient' must be a number from 0 to 1".to_string());
+                }
+            };
+        }
+
+        if let Some(fog) = data.get("fog") {
+            let read_distance = |field: &str| -> Result<f32, String> {
+                match fog.get(field).and_then(|d| d.as_f32()) {
+                    Some(d) if d >= 0.0 => Ok(d),
+                    _ => Err(format!("fog requires a non-negative '{}'", field)),
+                }
+            };
+
+            let color = match fog.get("color") {
+                Some(color) => read_color(color, "fog color")?,
+                None => {
+                    return Err("fog requires a 'color'".to_string());
+                }
+            };
+            let start = read_distance("start")?;
+            let end = read_distance("end")?;
+
+            if end <= start {
+                return Err("fog 'end' must be further than its 'start'".to_string());
+            }
+
+            lighting.fog = Some(Fog {
+                color: color,
+                start: start,
+                end: end,
+            });
+        }
+
+        return Ok(lighting);
+    }
+
+    /// The normalized direction towards the sun.
+    pub fn sun_direction(&self) -> Vector3<f32> {
+        return Vector3::from(self.sun.direction)
+            .try_normalize(0.0)
+            .unwrap_or(Vector3::z());
+    }
+
+    /// The light reaching a point with the normal, as (ambient and point lights, sun). The sun's is kept apart so it
+    /// can be shadowed.
+    pub fn light(
+        &self,
+        position: &Vector3<f32>,
+        normal: &Vector3<f32>,
+    ) -> (Vector3<f32>, Vector3<f32>) {
+        let normal = normal.try_normalize(0.0).unwrap_or(Vector3::z());
+
+        // Whatever the ambient doesn't light, the sun does where it faces it
+        let facing_sun = normal.dot(&self.sun_direction()).max(0.0);
+        let sun = Vector3::from(self.sun.color) * (1.0 - self.ambient) * facing_sun;
+
+        let mut light = Vector3::new(self.ambient, self.ambient, self.ambient);
+        for point_light in self.point_lights.iter() {
+            let to_light = Vector3::from(point_light.position) - position;
+            let distance = to_light.norm();
+            if distance >= point_light.radius {
+                continue;
+            }
+
+            let falloff = (1.0 - distance / point_light.radius).powi(2);
+            let facing = match to_light.try_normalize(0.0) {
+                Some(direction) => normal.dot(&direction).max(0.0),
+                None => 1.0,
+            };
+
+            light += Vector3::from(point_light.color) * facing * falloff;
+        }
+
+        return (light, sun);
+    }
+
+    /// How much a surface at the distance from the camera is hidden by fog, from 0 to 1.
+    pub fn fog_amount(&self, distance: f32) -> f32 {
+        return match self.fog {
+            Some(fog) => ((distance - fog.start) / (fog.end - fog.start))
+                .max(0.0)
+                .min(1.0),
+            None => 0.0,
+        };
+    }
+
+    /// The point lights nearest to the position, at most the count of them, nearest first.
+    pub fn nearest_point_lights(&self, position: &Vector3<f32>, count: usize) -> Vec<PointLight> {
+        let distance = |light: &PointLight| (Vector3::from(light.position) - position).norm();
+
+        let mut lights = self.point_lights.clone();
+        lights.sort_by(|a, b| {
+            distance(a)
+                .partial_cmp(&distance(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        lights.truncate(count);
+
+        return lights;
+    }
+
+    /// The projection * view matrix of the sun looking at everything within the bounds, for its shadow map.
+    pub fn sun_matrix(&self, bounds: &Bounds) -> Matrix4<f32> {
+        let center = (bounds.min + bounds.max) / 2.0;
+        let radius = ((bounds.max - bounds.min).norm() / 2.0).max(0.001);
+        let direction = self.sun_direction();
+
+        // Looking straight down, z can't be up
+        let up = if direction.z.abs() > 0.99 {
+            Vector3::y()
+        } else {
+            Vector3::z()
+        };
+
+        let eye = center + direction * radius * 2.0;
+        let view = Isometry3::look_at_rh(&Point3::from(eye), &Point3::from(center), &up);
+        let projection = Orthographic3::new(-radius, radius, -radius, radius, radius, radius * 3.0);
+
+        return projection.to_homogeneous() * view.to_homogeneous();
+    }
+}
+
+impl Asset for Lighting {
+    fn load(path: &Path) -> Result<Self, String> {
+        return Self::from_path(path);
+    }
+}
+
+fn read_vector(value: &DataValue, name: &str) -> Result<[f32; 3], String> {
+    let values: Vec<f32> = match value.as_array() {
+        Some(values) => values.iter().filter_map(|v| v.as_f32()).collect(),
+        None => vec![],
+    };
+
+    if values.len() != 3 || value.as_array().map(|v| v.len()) != Some(3) {
+        return Err(format!("'{}' must be an array of 3 numbers", name));
+    }
+
+    return Ok([values[0], values[1], values[2]]);
+}
+
+fn read_color(value: &DataValue, name: &str) -> Result<[f32; 3], String> {
+    let color = read_vector(value, name)?;
+
+    if color.iter().any(|c| *c < 0.0) {
+        return Err(format!("'{}' must not be negative", name));
+    }
+
+    return Ok(color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(expected: f32, actual: f32) -> bool {
+        return (expected - actual).abs() < 0.0001;
+    }
+
+    #[test]
+    fn light_default_sun_facing_surface_is_fully_lit() {
+        let lighting = Lighting::default();
+
+        let (light, sun) = lighting.light(&Vector3::zeros(), &lighting.sun_direction());
+
+        assert!(close(1.0, light.x + sun.x));
+        assert!(close(0.3, light.x));
+    }
+
+    #[test]
+    fn light_surface_facing_away_gets_ambient() {
+        let lighting = Lighting::default();
+
+        let (light, sun) = lighting.light(&Vector3::zeros(), &-lighting.sun_direction());
+
+        assert_eq!(Vector3::zeros(), sun);
+        assert!(close(0.3, light.y));
+    }
+
+    #[test]
+    fn light_point_light_fades_out_at_radius() {
+        let mut lighting = Lighting::default();
+        lighting.point_lights.push(PointLight {
+            position: [0.0, 0.0, 2.0],
+            color: [1.0, 0.0, 0.0],
+            radius: 4.0,
+        });
+        let up = Vector3::z();
+
+        let (below, _) = lighting.light(&Vector3::zeros(), &up);
+        let (outside, _) = lighting.light(&Vector3::new(0.0, 0.0, -3.0), &up);
+
+        assert!(close(0.3 + 0.25, below.x));
+        assert!(close(0.3, below.y));
+        assert!(close(0.3, outside.x));
+    }
+
+    #[test]
+    fn fog_amount_ramps_between_start_and_end() {
+        let mut lighting = Lighting::default();
+        assert_eq!(0.0, lighting.fog_amount(100.0));
+
+        lighting.fog = Some(Fog {
+            color: [0.5, 0.5, 0.5],
+            start: 10.0,
+            end: 20.0,
+        });
+
+        assert_eq!(0.0, lighting.fog_amount(5.0));
+        assert!(close(0.5, lighting.fog_amount(15.0)));
+        assert_eq!(1.0, lighting.fog_amount(25.0));
+    }
+
+    #[test]
+    fn nearest_point_lights_are_sorted_and_limited() {
+        let mut lighting = Lighting::default();
+        for x in [5.0, 1.0, 3.0].iter() {
+            lighting.point_lights.push(PointLight {
+                position: [*x, 0.0, 0.0],
+                color: [1.0, 1.0, 1.0],
+                radius: 1.0,
+            });
+        }
+
+        let actual = lighting.nearest_point_lights(&Vector3::zeros(), 2);
+
+        assert_eq!(2, actual.len());
+        assert_eq!(1.0, actual[0].position[0]);
+        assert_eq!(3.0, actual[1].position[0]);
+    }
+
+    #[test]
+    fn sun_matrix_sees_whole_bounds() {
+        let lighting = Lighting::default();
+        let bounds = Bounds::new(Vector3::new(-2.0, 0.0, 0.0), Vector3::new(6.0, 4.0, 1.0));
+
+        let matrix = lighting.sun_matrix(&bounds);
+
+        for corner in [bounds.min, bounds.max].iter() {
+            let clip = matrix.transform_point(&Point3::from(*corner));
+            assert!(clip.coords.iter().all(|c| c.abs() <= 1.0));
+        }
+
+        // Nearer to the sun is nearer in the shadow map
+        let high = matrix.transform_point(&Point3::new(2.0, 2.0, 1.0));
+        let low = matrix.transform_point(&Point3::new(2.0, 2.0, 0.0));
+        assert!(high.z < low.z);
+    }
+
+    #[test]
+    fn parse_map_lighting_returns_expected() {
+        let source = "lighting: {\n  sun: { direction: [0, 0, 1], color: [1, 0.5, 0.5], shadows: true }\n  ambient: 0.2\n  fog: { color: [0.8, 0.8, 0.9], start: 10, end: 40.5 }\n}";
+
+        let actual = Lighting::parse(source).unwrap();
+
+        assert_eq!([0.0, 0.0, 1.0], actual.sun.direction);
+        assert_eq!([1.0, 0.5, 0.5], actual.sun.color);
+        assert!(actual.sun.shadows);
+        assert_eq!(0.2, actual.ambient);
+        assert_eq!(40.5, actual.fog.unwrap().end);
+    }
+
+    #[test]
+    fn from_path_map_datasheets_load() {
+        for map in ["skirmish", "arena", "stage"].iter() {
+            let path = format!("./src/cb_simulation/datasheets/maps/{}.hjson", map);
+
+            assert!(Lighting::from_path(Path::new(&path)).is_ok(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn parse_map_without_lighting_returns_default() {
+        let actual = Lighting::parse("name: empty").unwrap();
+
+        assert_eq!(Lighting::default(), actual);
+    }
+
+    #[test]
+    fn parse_invalid_lighting_returns_err() {
+        let fog = Lighting::parse("lighting: { fog: { color: [1, 1, 1], start: 10, end: 5 } }");
+        let color = Lighting::parse("lighting: { sun: { color: [1, 1] } }");
+        let ambient = Lighting::parse("lighting: { ambient: 2 }");
+
+        assert_eq!(
+            Err("fog 'end' must be further than its 'start'".to_string()),
+            fog
+        );
+        assert_eq!(
+            Err("'sun color' must be an array of 3 numbers".to_string()),
+            color
+        );
+        assert_eq!(
+            Err("'ambient' must be a number from 0 to 1".to_string()),
+            ambient
+        );
+    }
+}
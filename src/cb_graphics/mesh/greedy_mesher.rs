@@ -8,6 +8,7 @@ use crate::cb_graphics::mesh;
 use mesh::Mesh;
 
 use crate::cb_voxels;
+use cb_voxels::palette::VoxelPalette;
 use cb_voxels::*;
 
 pub fn calculate_greedy_mesh(
@@ -17,6 +18,7 @@ pub fn calculate_greedy_mesh(
     chunk_z_offset: usize,
     frame: usize,
     chunk_size: usize,
+    palette: &VoxelPalette,
 ) -> Mesh {
     const SOUTH: usize = 0;
     const NORTH: usize = 1;
@@ -238,6 +240,7 @@ pub fn calculate_greedy_mesh(
                                     mask[n].unwrap(),
                                     backface,
                                     frame,
+                                    palette,
                                 );
 
                                 meshes.push(quad);
@@ -292,7 +295,7 @@ fn get_voxel_face(
     /*
     NOTE: THIS PART IS BUGGY AND DOESN"T WORK
     */
-    
+
     let check_for_obscured = false;
     if check_for_obscured {
         /*
@@ -351,6 +354,7 @@ fn get_quad(
     voxel: VoxelFace,
     backface: bool,
     generated_at_frame: usize,
+    palette: &VoxelPalette,
 ) -> Mesh {
     const VALUES_IN_VERTEX: usize = 3;
     let vertices;
@@ -394,41 +398,13 @@ fn get_quad(
     let vertices: Vec<f32> = vertices.iter().map(|n| n * VOXEL_SIZE).collect();
     let indices: Vec<i32> = indices;
 
-    // Colors
-    const COLOR_CAPACITY: usize = 9;
+    // Colors, one for each corner of the quad
     const COLOR_VERTEX_SIZE: usize = 3;
 
-    let mut colors;
-    {
-        colors = Vec::with_capacity(COLOR_CAPACITY);
-        let mut i = 0;
-        while i < COLOR_CAPACITY {
-            match voxel.vf_type {
-                VOXEL_TYPE_DEFAULT => {
-                    colors.push(1.0);
-                    colors.push(0.0);
-                    colors.push(0.0);
-                }
-                VOXEL_TYPE_DIRT => {
-                    colors.push(0.23);
-                    colors.push(0.168);
-                    colors.push(0.086);
-                }
-                VOXEL_TYPE_GRASS => {
-                    colors.push(0.0);
-                    colors.push(1.0);
-                    colors.push(0.0);
-                }
-                _ => {
-                    colors.push(0.0);
-                    colors.push(0.0);
-                    colors.push(0.0);
-                }
-            }
-
-            i += COLOR_VERTEX_SIZE;
-        }
-    }
+    let color = palette.color(voxel.vf_type);
+    let colors: Vec<f32> = (0..vertices.len() / VALUES_IN_VERTEX)
+        .flat_map(|_| color.to_vec())
+        .collect();
 
     // Normals
     const NORMAL_VERTEX_SIZE: usize = 3;
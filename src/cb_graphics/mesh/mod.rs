@@ -16,6 +16,8 @@ pub struct Mesh {
     pub color_vertex_size: usize,
     pub normals: Vec<f32>,
     pub normal_vertex_size: usize,
+    /// Texture coordinates, two per vertex. Empty for meshes without textures.
+    pub uvs: Vec<f32>,
     pub generated_at_frame: usize,
 }
 
@@ -38,6 +40,7 @@ impl Mesh {
             colors: colors,
             normal_vertex_size: normal_vertex_size,
             normals: normals,
+            uvs: vec![],
             generated_at_frame: generated_at_frame,
         };
     }
@@ -76,6 +79,7 @@ impl Mesh {
                 mesh.vertices.append(&mut m.vertices.clone());
                 mesh.colors.append(&mut m.colors.clone());
                 mesh.normals.append(&mut m.normals.clone());
+                mesh.uvs.append(&mut m.uvs.clone());
 
                 // do tricky shit with indices
                 let mut mapped_indices = m.indices.iter().map(|i| i + offset).collect();
@@ -18,6 +18,7 @@ use super::greedy_mesher::calculate_greedy_mesh;
 use super::*;
 
 use crate::cb_voxels;
+use cb_voxels::voxel_model::VoxelModel;
 use cb_voxels::*;
 
 pub struct VoxelMeshWrapper {
@@ -90,6 +91,7 @@ impl VoxelMesher {
                                 zchunk,
                                 frame,
                                 CHUNK_SIZE / 1,
+                                &chunk_manager.palette,
                             );
 
                             //TODO: scale voxels based on chunks?
@@ -107,3 +109,49 @@ impl VoxelMesher {
         self.first_frame = false;
     }
 }
+
+/// Mesh a standalone voxel model with its own palette, a chunk at a time like the map.
+pub fn mesh_voxel_model(model: &VoxelModel, frame: usize) -> Mesh {
+    let (width, depth, height) = model.chunk_counts();
+
+    let mut meshes = vec![];
+    for x in 0..width {
+        for y in 0..depth {
+            for z in 0..height {
+                meshes.push(calculate_greedy_mesh(
+                    &model.chunk(x, y, z).voxels,
+                    x,
+                    y,
+                    z,
+                    frame,
+                    CHUNK_SIZE,
+                    &model.palette,
+                ));
+            }
+        }
+    }
+
+    return Mesh::merge(&meshes, frame);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cb_voxels::palette::VoxelPalette;
+
+    #[test]
+    fn mesh_voxel_model_colors_vertices_from_palette() {
+        let mut palette = VoxelPalette::empty();
+        let voxel_type = palette.add([255, 0, 255]).unwrap();
+
+        let mut model = VoxelModel::new((CHUNK_SIZE + 1, 1, 1), palette);
+        model.set_voxel(CHUNK_SIZE, 0, 0, (true, true, voxel_type, 0), 0);
+
+        let mesh = mesh_voxel_model(&model, 4);
+
+        assert_eq!(false, mesh.is_empty());
+        assert_eq!(mesh.vertices.len(), mesh.colors.len());
+        assert_eq!(vec![1.0, 0.0, 1.0], mesh.colors[0..3].to_vec());
+        assert!(mesh.vertices.chunks(3).all(|v| v[0] >= CHUNK_SIZE as f32));
+    }
+}
@@ -5,13 +5,24 @@ use specs::prelude::*;
 
 extern crate gl;
 extern crate sdl2;
+
+extern crate nalgebra as na;
+use na::{Isometry3, Perspective3, Point3, Vector3};
 use sdl2::video::GLProfile;
 
 pub mod open_gl_backend;
 use open_gl_backend::OpenGlBackend;
 
 pub mod cb_collada;
+pub mod cb_gltf;
+pub mod frustum;
+pub mod lighting;
 pub mod mesh;
+pub mod models;
+pub mod render_list;
+pub mod renderer;
+pub mod scene_renderer;
+pub mod software_renderer;
 pub mod sprites;
 mod systems;
 
@@ -22,8 +33,22 @@ use crate::cb_menu::gfx;
 use gfx::{Color, Palette};
 
 use crate::cb_simulation;
+use cb_simulation::cameras::CbActiveCamera;
+use cb_simulation::components::gfx_components::CameraComponent;
 use cb_simulation::CbGameState;
 
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use crate::cb_assets;
+use cb_assets::AssetManager;
+
+use renderer::Renderer;
+use scene_renderer::CbSceneRenderer;
+
+/// The model placed at the origin of every world.
+pub const DEFAULT_MODEL_PATH: &'static str = "assets/monkey.dae";
+
 pub use systems::gfx_build_dispatcher;
 
 /// A class that is meant to congregate all platform specific code that interacts with the logic layers, so that it can easily be refactored/swapped out later on.
@@ -48,6 +73,7 @@ impl<'a> Sdl2HardwareInterface<'a> {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct CbCamera {
     pub orthographic_view: bool,
 
@@ -59,6 +85,10 @@ pub struct CbCamera {
     pub target_y: f32,
     pub target_z: f32,
 
+    pub up_x: f32,
+    pub up_y: f32,
+    pub up_z: f32,
+
     pub roll: f32,
     pub pitch: f32,
     pub yaw: f32,
@@ -67,7 +97,75 @@ pub struct CbCamera {
     pub window_height: f32,
 }
 impl CbCamera {
-    fn new(window_width: f32, window_height: f32) -> Self {
+    /// The projection and view matrices the camera draws with.
+    pub fn proj_view(&self) -> (na::Matrix4<f32>, na::Matrix4<f32>) {
+        let proj;
+        let view;
+        {
+            let horizontal_angle = 3.14;
+            let vertical_angle = 0.0;
+            let mouse_speed = 0.005; // configure to user variable?
+
+            let delta_time = 1.0; //TODO: figure out
+                                  /*
+                                                                let horizontal_angle = horizontal_angle
+                                                                    + mouse_speed * delta_time * (self.window_width / 2.0 - self.cursor_x);
+                                                                let vertical_angle = vertical_angle
+                                                                    + mouse_speed * delta_time * (self.window_height / 2.0 - self.cursor_y);
+
+                                                                let mouse_target = Point3::new(
+                                                                    vertical_angle.cos() * horizontal_angle.sin(),
+                                                                    vertical_angle.sin(),
+                                                                    vertical_angle.cos() * horizontal_angle.cos(),
+                                                                );
+
+                                                                let target = Point3::new(
+                                      mouse_target.x + self.pos_x,
+                                      mouse_target.y + self.pos_y,
+                                      mouse_target.z + self.pos_z,
+                                  );
+                                                        */
+
+            //TODO: need to figure out camera pitch, yaw, roll
+            let target = Point3::new(self.target_x, self.target_y, self.target_z);
+
+            let eye = Point3::new(self.pos_x, self.pos_y, self.pos_z);
+            let up = Vector3::new(self.up_x, self.up_y, self.up_z);
+            view = Isometry3::look_at_rh(&eye, &target, &up);
+
+            if self.orthographic_view {
+                const BOUNDS: f32 = 22.0;
+
+                let horizontal_bounds = (self.window_width / self.window_height) * BOUNDS; // Ensure it's properly scaled for the aspect ratio
+                let vertical_bounds = BOUNDS;
+
+                let ortho = na::Orthographic3::new(
+                    -horizontal_bounds,
+                    horizontal_bounds,
+                    -vertical_bounds,
+                    vertical_bounds,
+                    0.01,
+                    10000.0,
+                );
+
+                proj = Perspective3::from_matrix_unchecked(*ortho.as_matrix());
+            } else {
+                proj = Perspective3::new(
+                    (self.window_width / self.window_height), // Ensure it's properly scaled for the aspect ratio
+                    3.14 / 2.0,
+                    0.1,
+                    1000.0,
+                );
+            }
+        }
+
+        let proj = *proj.as_matrix();
+        let view = view.to_homogeneous();
+
+        return (proj, view);
+    }
+
+    pub fn new(window_width: f32, window_height: f32) -> Self {
         return Self {
             orthographic_view: false,
 
@@ -78,6 +176,10 @@ impl CbCamera {
             target_y: 0.0,
             target_z: 0.0,
 
+            up_x: 0.0,
+            up_y: 1.0,
+            up_z: 0.0,
+
             roll: 0.0,
             pitch: 0.0,
             yaw: 0.0,
@@ -85,6 +187,38 @@ impl CbCamera {
             window_height: window_height,
         };
     }
+
+    /// The view of the camera component, the alpha of the way from its previous tick's pose to its current one.
+    pub fn from_component(
+        camera: &CameraComponent,
+        alpha: f32,
+        window_width: f32,
+        window_height: f32,
+    ) -> Self {
+        let lerp = |previous: GameUnit, current: GameUnit| {
+            let previous = render_list::to_world_units(previous);
+            let current = render_list::to_world_units(current);
+
+            return previous + (current - previous) * alpha;
+        };
+
+        let mut view = Self::new(window_width, window_height);
+        view.orthographic_view = camera.orthographic_view;
+
+        view.pos_x = lerp(camera.previous_position.x, camera.position.x);
+        view.pos_y = lerp(camera.previous_position.y, camera.position.y);
+        view.pos_z = lerp(camera.previous_position.z, camera.position.z);
+
+        view.target_x = lerp(camera.previous_target.x, camera.target.x);
+        view.target_y = lerp(camera.previous_target.y, camera.target.y);
+        view.target_z = lerp(camera.previous_target.z, camera.target.z);
+
+        view.up_x = camera.up.x.to_num();
+        view.up_y = camera.up.y.to_num();
+        view.up_z = camera.up.z.to_num();
+
+        return view;
+    }
 }
 
 #[allow(dead_code)]
@@ -93,7 +227,6 @@ pub struct CbGfx {
     window_height: i32,
     sdl_context: sdl2::Sdl,
     event_pump: sdl2::EventPump,
-    window: sdl2::video::Window,
     video_subsystem: sdl2::VideoSubsystem,
     editor_window: sdl2::render::Canvas<sdl2::video::Window>,
     pub editor_gui_env: cb_menu::GuiEnvironment,
@@ -107,12 +240,13 @@ pub struct CbGfx {
 
     gl_context: sdl2::video::GLContext, // Need this to keep the OpenGL context active
     gl_backend: OpenGlBackend,
+    scene_renderer: CbSceneRenderer,
     camera: CbCamera,
     pub reset_cursor: bool,
 }
 
 impl<'a> CbGfx {
-    pub fn new() -> Self {
+    pub fn new(assets: &AssetManager) -> Self {
         let sdl_context = sdl2::init().unwrap();
         let event_pump = sdl_context.event_pump().unwrap();
 
@@ -141,7 +275,9 @@ impl<'a> CbGfx {
         debug_assert_eq!(gl_attr.context_profile(), GLProfile::Core);
         debug_assert_eq!(gl_attr.context_version(), (3, 2));
 
-        let gl_backend = OpenGlBackend::new();
+        let gl_backend = OpenGlBackend::new(assets, window);
+
+        let scene_renderer = CbSceneRenderer::new();
 
         let (editor_width, editor_height) = (640, 480);
         let mut editor_window = video_subsystem
@@ -165,7 +301,6 @@ impl<'a> CbGfx {
             window_height: window_height as i32,
             sdl_context: sdl_context,
             event_pump: event_pump,
-            window: window,
             main_window_id: main_window_id,
             editor_window_id: editor_window_id,
             editor_window: editor_canvas,
@@ -178,6 +313,7 @@ impl<'a> CbGfx {
             editor_visible: true,
             gl_context: ctx,
             gl_backend: gl_backend,
+            scene_renderer: scene_renderer,
             camera: CbCamera::new(window_width as f32, window_height as f32),
         };
     }
@@ -314,32 +450,27 @@ impl<'a> CbGfx {
         return &mut self.camera;
     }
 
-    pub fn render(&mut self, game_state: &CbGameState, world: &World, frame: usize) {
-        const SIZE_SCALING_FACTOR: f32 = 100.0;
-        const DEGREES_SCALING_FACTOR: f32 = 10.0;
-
-        let camera_components =
-            world.read_storage::<cb_simulation::components::gfx_components::CameraComponent>();
-
-        // NOTE: There really should only be one active camera at a time perhaps?
-        for camera in (&camera_components).join() {
-            self.camera.orthographic_view = camera.camera_orthographic_view;
-
-            self.camera.pitch = (camera.camera_pitch as f32) / DEGREES_SCALING_FACTOR;
-            self.camera.roll = (camera.camera_roll as f32) / DEGREES_SCALING_FACTOR;
-            self.camera.yaw = (camera.camera_yaw as f32) / DEGREES_SCALING_FACTOR;
-
-            self.camera.pos_x = (camera.camera_pos_x as f32) / SIZE_SCALING_FACTOR;
-            self.camera.pos_y = (camera.camera_pos_y as f32) / SIZE_SCALING_FACTOR;
-            self.camera.pos_z = (camera.camera_pos_z as f32) / SIZE_SCALING_FACTOR;
-
-            self.camera.target_x = (camera.camera_target_x as f32) / SIZE_SCALING_FACTOR;
-            self.camera.target_y = (camera.camera_target_y as f32) / SIZE_SCALING_FACTOR;
-            self.camera.target_z = (camera.camera_target_z as f32) / SIZE_SCALING_FACTOR;
+    /// Draw the world from the active camera. The alpha is how far the frame is between the previous tick and the
+    /// current one, from 0 to 1.
+    pub fn render(&mut self, game_state: &CbGameState, world: &World, frame: usize, alpha: f32) {
+        // Without an active camera the last view is kept
+        {
+            let active_camera = world.read_resource::<CbActiveCamera>();
+            let cameras = world.read_storage::<CameraComponent>();
+
+            if let Some(camera) = active_camera.get().and_then(|entity| cameras.get(entity)) {
+                self.camera = CbCamera::from_component(
+                    camera,
+                    alpha,
+                    self.window_width as f32,
+                    self.window_height as f32,
+                );
+            }
         }
 
-        OpenGlBackend::render(&mut self.gl_backend, &self.camera, game_state, world, frame);
-        self.window.gl_swap_window();
+        self.scene_renderer
+            .render(&mut self.gl_backend, &self.camera, world, frame);
+        self.gl_backend.present();
 
         // Draw GUI editor window
         self.render_editor_window(&game_state, &world, frame);
@@ -358,37 +489,29 @@ impl<'a> CbGfx {
                     world.read_storage::<cb_simulation::components::ik_components::IkComponent>();
 
                 for ik in (&ik_components).join() {
-                    let rig = &ik.rig;
-
-                    if rig.is_valid_rig() {
-                        let len = rig.joints.len() - 1;
-
-                        for i in 0..len {
-                            let mut joint1 = rig.get_child_position(i);
-                            let mut joint2 = rig.get_child_position(i + 1);
-
-                            joint1 += rig.position;
-                            joint2 += rig.position;
-
-                            canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
-
-                            let joint_rect_width = 6;
-                            canvas
-                                .draw_rect(sdl2::rect::Rect::new(
-                                    (joint1.x as i32) - joint_rect_width,
-                                    (joint1.y as i32) - joint_rect_width,
-                                    joint_rect_width as u32,
-                                    joint_rect_width as u32,
-                                ))
-                                .unwrap();
-
-                            canvas
-                                .draw_line(
-                                    (joint1.x as i32, joint1.y as i32),
-                                    (joint2.x as i32, joint2.y as i32),
-                                )
-                                .unwrap();
-                        }
+                    let joints = ik.joint_positions();
+
+                    for pair in joints.windows(2) {
+                        let (joint1, joint2) = (pair[0], pair[1]);
+
+                        canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
+
+                        let joint_rect_width = 6;
+                        canvas
+                            .draw_rect(sdl2::rect::Rect::new(
+                                (joint1.x as i32) - joint_rect_width,
+                                (joint1.y as i32) - joint_rect_width,
+                                joint_rect_width as u32,
+                                joint_rect_width as u32,
+                            ))
+                            .unwrap();
+
+                        canvas
+                            .draw_line(
+                                (joint1.x as i32, joint1.y as i32),
+                                (joint2.x as i32, joint2.y as i32),
+                            )
+                            .unwrap();
                     }
                 }
             }
@@ -422,37 +545,102 @@ impl<'a> CbGfx {
 
             // Render editor GUI
             let draw_calls = self.editor_gui_env.draw();
-            for draw_call in draw_calls.iter() {
-                match draw_call {
-                    cb_menu::CbMenuDrawVirtualMachine::WireframeRect(position, color) => {
-                        canvas.set_draw_color(sdl2::pixels::Color::RGB(color.r, color.g, color.b));
-                        canvas
-                            .draw_rect(sdl2::rect::Rect::new(
-                                (position.x as i32),
-                                position.y as i32,
-                                (position.width) as u32,
-                                (position.height) as u32,
-                            ))
-                            .unwrap();
-                    }
-                    cb_menu::CbMenuDrawVirtualMachine::FilledRect(position, color) => {
-                        canvas.set_draw_color(sdl2::pixels::Color::RGB(color.r, color.g, color.b));
-                        canvas
-                            .fill_rect(sdl2::rect::Rect::new(
-                                (position.x as i32),
-                                position.y as i32,
-                                (position.width) as u32,
-                                (position.height) as u32,
-                            ))
-                            .unwrap();
-                    }
-                    cb_menu::CbMenuDrawVirtualMachine::Text(position, color, value) => {
-                        println!("gonna draw text: {}", value);
-                    }
-                }
+            for sprite in menu_sprites(&draw_calls).iter() {
+                let channel = |value: f32| (value * 255.0).round() as u8;
+                canvas.set_draw_color(sdl2::pixels::Color::RGB(
+                    channel(sprite.color[0]),
+                    channel(sprite.color[1]),
+                    channel(sprite.color[2]),
+                ));
+                canvas
+                    .fill_rect(sdl2::rect::Rect::new(
+                        sprite.position[0] as i32,
+                        sprite.position[1] as i32,
+                        sprite.size[0] as u32,
+                        sprite.size[1] as u32,
+                    ))
+                    .unwrap();
             }
 
             canvas.present();
         }
     }
 }
+
+/// The sprites that draw the menu. Wireframes become a sprite for each side. Text is not drawn yet.
+pub fn menu_sprites(draw_calls: &[cb_menu::CbMenuDrawVirtualMachine]) -> Vec<renderer::Sprite> {
+    let sprite = |x: usize, y: usize, width: usize, height: usize, color: &Color| {
+        return renderer::Sprite::new(
+            [x as f32, y as f32],
+            [width as f32, height as f32],
+            [
+                color.r as f32 / 255.0,
+                color.g as f32 / 255.0,
+                color.b as f32 / 255.0,
+            ],
+        );
+    };
+
+    let mut sprites = vec![];
+    for draw_call in draw_calls.iter() {
+        match draw_call {
+            cb_menu::CbMenuDrawVirtualMachine::WireframeRect(position, color) => {
+                let (x, y, width, height) =
+                    (position.x, position.y, position.width, position.height);
+                if width == 0 || height == 0 {
+                    continue;
+                }
+
+                sprites.push(sprite(x, y, width, 1, color));
+                sprites.push(sprite(x, y + height - 1, width, 1, color));
+                sprites.push(sprite(x, y, 1, height, color));
+                sprites.push(sprite(x + width - 1, y, 1, height, color));
+            }
+            cb_menu::CbMenuDrawVirtualMachine::FilledRect(position, color) => {
+                sprites.push(sprite(
+                    position.x,
+                    position.y,
+                    position.width,
+                    position.height,
+                    color,
+                ));
+            }
+            cb_menu::CbMenuDrawVirtualMachine::Text(_, _, value) => {
+                println!("gonna draw text: {}", value);
+            }
+        }
+    }
+
+    return sprites;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cb_simulation::components::gfx_components::CameraController;
+    use cb_system::Coordinate3d;
+
+    #[test]
+    fn camera_from_component_moves_between_ticks() {
+        let voxels = |x: i32| GameUnit::from_num(x * crate::cb_voxels::VOXEL_GAME_SIZE);
+
+        let mut camera = CameraComponent::new(CameraController::Fixed);
+        camera.snap_to(
+            Coordinate3d::new(voxels(0), voxels(0), voxels(10)),
+            Coordinate3d::zero(),
+        );
+        camera.position.x = voxels(4);
+        camera.target.y = voxels(2);
+
+        let view = CbCamera::from_component(&camera, 0.25, 640.0, 480.0);
+
+        assert_eq!((1.0, 0.0, 10.0), (view.pos_x, view.pos_y, view.pos_z));
+        assert_eq!(
+            (0.0, 0.5, 0.0),
+            (view.target_x, view.target_y, view.target_z)
+        );
+        assert_eq!((0.0, 0.0, 1.0), (view.up_x, view.up_y, view.up_z));
+        assert_eq!(camera.orthographic_view, view.orthographic_view);
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+
+/*
+    Models imported from files, in one representation whatever format they came from. A model is a hierarchy of nodes,
+    each placing any number of meshes relative to its parent. Nodes that are joints are also the bones of the model's
+    skeleton, and skinned meshes follow those bones instead of their node, with every vertex weighted to up to four of
+    a skin's bones. Models are converted to be z up like the rest of the game, but keep the units they were authored in.
+*/
+
+use crate::cb_animation;
+use cb_animation::clips::AnimationClip;
+use cb_animation::skeleton::Skeleton;
+use cb_animation::state_machine::BlendNode;
+use cb_animation::{AnimRotation, AnimVector, AnimationSet, BoneId, BoneTransform};
+
+use std::path::Path;
+
+use crate::cb_assets::Asset;
+
+use crate::cb_graphics;
+use cb_graphics::mesh::Mesh;
+use cb_graphics::{cb_collada, cb_gltf};
+
+pub type ModelMatrix = na::Matrix4<f32>;
+
+/// The most bones that may move a single vertex.
+pub const MAX_VERTEX_WEIGHTS: usize = 4;
+
+/// The color of vertices without colors or a material.
+pub const DEFAULT_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub diffuse: [f32; 4],
+    /// The diffuse texture's image file, relative to the model.
+    pub texture: Option<String>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        return Self {
+            name: name.to_string(),
+            diffuse: DEFAULT_COLOR,
+            texture: None,
+        };
+    }
+}
+
+/// The skeleton's bones that skinned meshes are bound to, with the inverse of each bone's transform when bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skin {
+    pub bones: Vec<BoneId>,
+    pub inverse_bind_matrices: Vec<ModelMatrix>,
+}
+
+/// The bones moving each vertex of a mesh, as indices into the skin's bones, with weights that add up to one. Unused
+/// influences have a weight of zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinWeights {
+    pub skin: usize,
+    pub joints: Vec<[usize; MAX_VERTEX_WEIGHTS]>,
+    pub weights: Vec<[f32; MAX_VERTEX_WEIGHTS]>,
+}
+
+impl SkinWeights {
+    /// Keep the heaviest influences on a vertex and scale their weights to add up to one.
+    pub fn strongest(
+        influences: &mut Vec<(usize, f32)>,
+    ) -> ([usize; MAX_VERTEX_WEIGHTS], [f32; MAX_VERTEX_WEIGHTS]) {
+        influences.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        influences.truncate(MAX_VERTEX_WEIGHTS);
+
+        let total: f32 = influences.iter().map(|(_, weight)| weight).sum();
+
+        let mut joints = [0; MAX_VERTEX_WEIGHTS];
+        let mut weights = [0.0; MAX_VERTEX_WEIGHTS];
+        for (i, (joint, weight)) in influences.iter().enumerate() {
+            joints[i] = *joint;
+            weights[i] = if total > 0.0 { weight / total } else { 0.0 };
+        }
+
+        return (joints, weights);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelMesh {
+    pub name: String,
+    pub mesh: Mesh,
+    pub material: Option<usize>,
+    pub skin: Option<SkinWeights>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelNode {
+    pub name: String,
+    pub parent: Option<usize>,
+    /// The node's transform relative to its parent.
+    pub transform: ModelMatrix,
+    pub meshes: Vec<usize>,
+    /// The skeleton's bone, if the node is a joint.
+    pub bone: Option<BoneId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Model {
+    pub name: String,
+    /// Parents always come before their children.
+    pub nodes: Vec<ModelNode>,
+    pub meshes: Vec<ModelMesh>,
+    pub materials: Vec<Material>,
+    pub skeleton: Option<Skeleton>,
+    pub skins: Vec<Skin>,
+    pub clips: Vec<AnimationClip>,
+    /// The size of one of the model's units.
+    pub meters_per_unit: f32,
+}
+
+impl Model {
+    pub fn new(name: &str) -> Self {
+        return Self {
+            name: name.to_string(),
+            nodes: vec![],
+            meshes: vec![],
+            materials: vec![],
+            skeleton: None,
+            skins: vec![],
+            clips: vec![],
+            meters_per_unit: 1.0,
+        };
+    }
+
+    /// Add a node, returning its index.
+    pub fn add_node(
+        &mut self,
+        name: &str,
+        parent: Option<usize>,
+        transform: ModelMatrix,
+    ) -> Result<usize, String> {
+        if let Some(parent) = parent {
+            if parent >= self.nodes.len() {
+                return Err(format!(
+                    "Node '{}' has parent {}, which has not been added.",
+                    name, parent
+                ));
+            }
+        }
+
+        self.nodes.push(ModelNode {
+            name: name.to_string(),
+            parent: parent,
+            transform: transform,
+            meshes: vec![],
+            bone: None,
+        });
+
+        return Ok(self.nodes.len() - 1);
+    }
+
+    /// The transform of every node in the model's space.
+    pub fn world_transforms(&self) -> Vec<ModelMatrix> {
+        let mut world: Vec<ModelMatrix> = Vec::with_capacity(self.nodes.len());
+
+        for node in self.nodes.iter() {
+            let transform = match node.parent {
+                Some(parent) => world[parent] * node.transform,
+                None => node.transform,
+            };
+
+            world.push(transform);
+        }
+
+        return world;
+    }
+
+    /// A node's transform in the model's space.
+    pub fn node_world(&self, node: usize) -> ModelMatrix {
+        let mut world = self.nodes[node].transform;
+        let mut parent = self.nodes[node].parent;
+
+        while let Some(p) = parent {
+            world = self.nodes[p].transform * world;
+            parent = self.nodes[p].parent;
+        }
+
+        return world;
+    }
+
+    /// The nearest node above that is a joint.
+    pub fn parent_bone_node(&self, node: usize) -> Option<usize> {
+        let mut parent = self.nodes[node].parent;
+        while let Some(p) = parent {
+            if self.nodes[p].bone.is_some() {
+                return Some(p);
+            }
+
+            parent = self.nodes[p].parent;
+        }
+
+        return None;
+    }
+
+    /// Convert a joint node's transform to be relative to its parent bone. Root bones are placed in the model's
+    /// space, so the skeleton holds any transforms of the nodes above it.
+    pub fn bone_space(&self, node: usize, transform: ModelMatrix) -> ModelMatrix {
+        let parent_world = match self.nodes[node].parent {
+            Some(parent) => self.node_world(parent),
+            None => ModelMatrix::identity(),
+        };
+
+        let parent_bone_world = match self.parent_bone_node(node) {
+            Some(parent_bone) => self.node_world(parent_bone),
+            None => ModelMatrix::identity(),
+        };
+
+        let to_bone = parent_bone_world
+            .try_inverse()
+            .unwrap_or(ModelMatrix::identity());
+
+        return to_bone * parent_world * transform;
+    }
+
+    /// An animation set for the model's skeleton, with a looping state for every clip.
+    pub fn animation_set(&self) -> Result<AnimationSet, String> {
+        let skeleton = match &self.skeleton {
+            Some(skeleton) => skeleton.clone(),
+            None => {
+                return Err(format!("Model '{}' has no skeleton to animate.", self.name));
+            }
+        };
+
+        let mut set = AnimationSet::new(&self.name, skeleton);
+        for clip in self.clips.iter() {
+            let id = set.add_clip(clip.clone())?;
+            set.state_machine.add_state(&clip.name, BlendNode::Clip(id));
+        }
+
+        return Ok(set);
+    }
+}
+
+/// Import a model, choosing the importer by the file's extension.
+pub fn from_path(path: &Path) -> Result<Model, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("dae") => cb_collada::from_path(path),
+        Some("gltf") | Some("glb") => cb_gltf::from_path(path),
+        _ => Err(format!(
+            "'{}' is not a COLLADA (.dae) or glTF (.gltf, .glb) model.",
+            path.display()
+        )),
+    }
+}
+
+impl Asset for Model {
+    fn load(path: &Path) -> Result<Self, String> {
+        return from_path(path);
+    }
+}
+
+/// Split a matrix into a bone transform. Skewed or unevenly scaled matrices lose their skew and average their scale.
+pub fn decompose(matrix: &ModelMatrix) -> BoneTransform {
+    let translation = AnimVector::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+
+    let mut rotation: na::Matrix3<f32> = matrix.fixed_slice::<na::U3, na::U3>(0, 0).into_owned();
+    let scales = [
+        rotation.column(0).norm(),
+        rotation.column(1).norm(),
+        rotation.column(2).norm(),
+    ];
+
+    for (i, scale) in scales.iter().enumerate() {
+        if *scale > 0.0 {
+            let mut column = rotation.column_mut(i);
+            column /= *scale;
+        }
+    }
+
+    // A mirrored matrix is a rotation with a negative scale
+    let mut scale = (scales[0] + scales[1] + scales[2]) / 3.0;
+    if rotation.determinant() < 0.0 {
+        rotation = -rotation;
+        scale = -scale;
+    }
+
+    return BoneTransform::new(translation, AnimRotation::from_matrix(&rotation), scale);
+}
+
+/// Average the normals of the triangles around each vertex, for meshes imported without normals.
+pub fn smooth_normals(mesh: &Mesh) -> Vec<f32> {
+    let mut normals = vec![na::Vector3::<f32>::zeros(); mesh.vertices.len() / 3];
+    let vertex = |i: i32| {
+        let i = i as usize * 3;
+        na::Vector3::new(mesh.vertices[i], mesh.vertices[i + 1], mesh.vertices[i + 2])
+    };
+
+    for triangle in mesh.indices.chunks(3) {
+        let (a, b, c) = (triangle[0], triangle[1], triangle[2]);
+        let normal = (vertex(b) - vertex(a)).cross(&(vertex(c) - vertex(a)));
+
+        for i in triangle.iter() {
+            normals[*i as usize] += normal;
+        }
+    }
+
+    return normals
+        .iter()
+        .flat_map(|n| {
+            let n = n.try_normalize(0.0).unwrap_or(na::Vector3::z());
+            vec![n.x, n.y, n.z]
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompose_matches_composed_transform() {
+        let transform = BoneTransform::new(
+            AnimVector::new(1.0, -2.0, 3.0),
+            AnimRotation::from_axis_angle(&AnimVector::y_axis(), 0.7),
+            2.0,
+        );
+
+        let decomposed = decompose(&transform.to_matrix());
+
+        assert!((decomposed.translation - transform.translation).norm() < 0.0001);
+        assert!(decomposed.rotation.angle_to(&transform.rotation) < 0.0001);
+        assert!((decomposed.scale - 2.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn skin_weights_strongest_keeps_four_and_normalizes() {
+        let mut influences = vec![(0, 0.1), (1, 0.4), (2, 0.2), (3, 0.2), (4, 0.1)];
+
+        let (joints, weights) = SkinWeights::strongest(&mut influences);
+
+        assert_eq!(1, joints[0]);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 0.0001);
+        assert_eq!(false, joints.contains(&0) && joints.contains(&4));
+    }
+
+    #[test]
+    fn model_world_transforms_children_follow_parents() {
+        let mut model = Model::new("test");
+        let parent = model
+            .add_node(
+                "parent",
+                None,
+                ModelMatrix::new_translation(&AnimVector::new(1.0, 0.0, 0.0)),
+            )
+            .unwrap();
+        model
+            .add_node(
+                "child",
+                Some(parent),
+                ModelMatrix::new_translation(&AnimVector::new(0.0, 2.0, 0.0)),
+            )
+            .unwrap();
+
+        let world = model.world_transforms();
+
+        assert_eq!(1.0, world[1][(0, 3)]);
+        assert_eq!(2.0, world[1][(1, 3)]);
+    }
+
+    #[test]
+    fn from_path_unknown_extension_returns_err() {
+        let result = from_path(Path::new("./src/assets/chr_sword.vox"));
+
+        assert!(result.unwrap_err().contains("chr_sword.vox"));
+    }
+}
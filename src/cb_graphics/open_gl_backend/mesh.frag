@@ -1,30 +1,81 @@
 #version 330 core
 
-uniform vec3 cbLightPos;  
+// Matches lighting::MAX_POINT_LIGHTS
+#define MAX_POINT_LIGHTS 8
+
+// How much further from the sun than the shadow map a fragment has to be to be shadowed, in depth from 0 to 1
+#define SHADOW_BIAS 0.005
+
+uniform vec3 cbMaterialColor;
+
+uniform vec3 cbSunDirection;
+uniform vec3 cbSunColor;
+uniform float cbAmbient;
+
+uniform bool cbShadowsEnabled;
+uniform sampler2D cbShadowMap;
+
+uniform int cbPointLightCount;
+uniform vec3 cbPointLightPositions[MAX_POINT_LIGHTS];
+uniform vec3 cbPointLightColors[MAX_POINT_LIGHTS];
+uniform float cbPointLightRadii[MAX_POINT_LIGHTS];
+
+uniform bool cbFogEnabled;
+uniform vec3 cbFogColor;
+uniform float cbFogStart;
+uniform float cbFogEnd;
+
+uniform vec3 cbEyePos;
 
 in vec3 cbMeshVertexColor;
 in vec3 cbFragPos;
 in vec3 cbNormal;
+in vec4 cbFragPosLightSpace;
 
 out vec4 FragColor;
 
-void main()
+// 1 where something nearer to the sun covers the fragment, 0 otherwise
+float sunShadow()
 {
-    vec3 lightColor = vec3(1.0, 0.96078431372, 0.85098039215);
-
-    vec3 lightDir = normalize(cbLightPos - cbFragPos);  
+    vec3 projected = cbFragPosLightSpace.xyz / cbFragPosLightSpace.w * 0.5 + 0.5;
+    if (any(lessThan(projected, vec3(0.0))) || any(greaterThan(projected, vec3(1.0))))
+    {
+        return 0.0;
+    }
 
-    float ambientStrength = 0.1;
-    vec3 ambient = ambientStrength * lightColor;
+    float closest = texture(cbShadowMap, projected.xy).r;
+    return projected.z - SHADOW_BIAS > closest ? 1.0 : 0.0;
+}
 
+void main()
+{
     vec3 norm = normalize(cbNormal);
 
-    float diff = max(dot(norm, lightDir), 0.0);
-    vec3 diffuse = diff * lightColor;
-    
+    vec3 light = vec3(cbAmbient);
+    for (int i = 0; i < cbPointLightCount; i++)
+    {
+        vec3 toLight = cbPointLightPositions[i] - cbFragPos;
+        float dist = length(toLight);
+        if (dist < cbPointLightRadii[i])
+        {
+            float falloff = pow(1.0 - dist / cbPointLightRadii[i], 2.0);
+            light += cbPointLightColors[i] * max(dot(norm, normalize(toLight)), 0.0) * falloff;
+        }
+    }
+
+    vec3 sun = cbSunColor * (1.0 - cbAmbient) * max(dot(norm, normalize(cbSunDirection)), 0.0);
+    if (cbShadowsEnabled)
+    {
+        sun *= 1.0 - sunShadow();
+    }
 
+    vec3 result = (light + sun) * cbMeshVertexColor * cbMaterialColor;
 
-    vec3 result = (ambient + diffuse) * cbMeshVertexColor;
+    if (cbFogEnabled)
+    {
+        float amount = clamp((distance(cbFragPos, cbEyePos) - cbFogStart) / (cbFogEnd - cbFogStart), 0.0, 1.0);
+        result = mix(result, cbFogColor, amount);
+    }
 
     FragColor = vec4(result, 1.0);
-}
\ No newline at end of file
+}
@@ -5,15 +5,21 @@ layout (location = 1) in vec3 myColor;
 layout (location = 2) in vec3 myNormal;
 
 uniform mat4 MVP;
+uniform mat4 cbModel;
+uniform mat4 cbLightSpace;
 
 out vec3 cbMeshVertexColor;
 out vec3 cbFragPos;
 out vec3 cbNormal;
+out vec4 cbFragPosLightSpace;
 
 void main()
 {
-    cbFragPos = vec3(MVP * vec4(myPosition, 1));
+    vec4 worldPos = cbModel * vec4(myPosition, 1);
+
+    cbFragPos = vec3(worldPos);
+    cbFragPosLightSpace = cbLightSpace * worldPos;
     gl_Position = MVP * vec4(myPosition, 1);
-    cbMeshVertexColor = myColor;    
-    cbNormal = myNormal;    
+    cbMeshVertexColor = myColor;
+    cbNormal = mat3(transpose(inverse(cbModel))) * myNormal;
 }
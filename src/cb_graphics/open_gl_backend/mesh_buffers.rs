@@ -0,0 +1,123 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate gl;
+
+use crate::cb_graphics::mesh::Mesh;
+
+/// The vertex array and buffers a mesh was uploaded to.
+pub struct MeshBuffers {
+    pub vao: gl::types::GLuint,
+    pub vbo: gl::types::GLuint,
+    pub ebo: gl::types::GLuint,
+    pub color_buff: gl::types::GLuint,
+    pub normal_buff: gl::types::GLuint,
+    pub indices_count: usize,
+    visible: bool,
+}
+
+impl MeshBuffers {
+    pub fn new(mesh: &Mesh) -> Self {
+        let mut buffers = Self {
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+            color_buff: 0,
+            normal_buff: 0,
+            indices_count: 0,
+            visible: false,
+        };
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut buffers.vao);
+            gl::GenBuffers(1, &mut buffers.vbo);
+            gl::GenBuffers(1, &mut buffers.ebo);
+            gl::GenBuffers(1, &mut buffers.color_buff);
+            gl::GenBuffers(1, &mut buffers.normal_buff);
+        }
+
+        buffers.fill(mesh);
+
+        return buffers;
+    }
+
+    /// Replace the buffers' contents with the mesh.
+    pub fn fill(&mut self, mesh: &Mesh) {
+        self.indices_count = mesh.indices.len();
+        self.visible = !mesh.is_empty();
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+
+            // Vertices
+            buffer_attribute(0, self.vbo, &mesh.vertices, mesh.vertex_size);
+
+            // Indices
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (mesh.indices.len() * std::mem::size_of::<i32>()) as gl::types::GLsizeiptr,
+                mesh.indices.as_ptr() as *const gl::types::GLvoid,
+                gl::STATIC_DRAW,
+            );
+
+            // Colors
+            buffer_attribute(1, self.color_buff, &mesh.colors, mesh.color_vertex_size);
+
+            // Normals
+            buffer_attribute(2, self.normal_buff, &mesh.normals, mesh.normal_vertex_size);
+
+            gl::BindVertexArray(0);
+        }
+    }
+
+    pub fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawElements(
+                gl::TRIANGLES,
+                self.indices_count as i32,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+            gl::BindVertexArray(0);
+        }
+    }
+
+    pub fn delete(self) {
+        let buffers = [self.vbo, self.ebo, self.color_buff, self.normal_buff];
+
+        unsafe {
+            gl::DeleteBuffers(buffers.len() as i32, buffers.as_ptr());
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Upload the values to the buffer and point the vertex attribute at them. The vertex array must be bound.
+unsafe fn buffer_attribute(
+    attribute: gl::types::GLuint,
+    buffer: gl::types::GLuint,
+    values: &Vec<f32>,
+    size: usize,
+) {
+    gl::EnableVertexAttribArray(attribute);
+    gl::BindBuffer(gl::ARRAY_BUFFER, buffer);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        (values.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+        values.as_ptr() as *const gl::types::GLvoid,
+        gl::STATIC_DRAW,
+    );
+    gl::VertexAttribPointer(
+        attribute,
+        size as gl::types::GLint,
+        gl::FLOAT,
+        gl::FALSE,
+        (size * std::mem::size_of::<f32>()) as gl::types::GLint,
+        std::ptr::null(),
+    );
+}
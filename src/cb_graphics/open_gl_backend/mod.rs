@@ -1,88 +1,134 @@
 // Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
 
 extern crate gl;
-use std::ffi::CString;
 
 extern crate nalgebra as na;
-use na::{Isometry3, Perspective3, Point3, Vector3};
 
 use crate::cb_graphics;
-use crate::cb_simulation;
-use cb_simulation::{components, CbGameState};
 
-use crate::cb_voxels;
+mod mesh_buffers;
+use mesh_buffers::MeshBuffers;
 
-mod r_collada_render;
-use r_collada_render::CbColladaRenderer;
-mod r_voxel_render;
+mod shadow_framebuffer;
+use shadow_framebuffer::ShadowFramebuffer;
 
 pub mod render_gl;
 
-use cb_graphics::cb_collada;
-use cb_graphics::mesh;
+use cb_graphics::frustum::Bounds;
+use cb_graphics::lighting::{self, Lighting};
+use cb_graphics::mesh::Mesh;
+use cb_graphics::renderer::{Material, MeshId, Renderer, Sprite};
 use cb_graphics::sprites::{CbSpriteRenderer, SpriteRenderer};
-use std::path::Path;
-extern crate specs;
-use specs::prelude::*;
-
-pub struct MeshBuffers {
-    pub vao: gl::types::GLuint,
-    pub vbo: gl::types::GLuint,
-    pub ebo: gl::types::GLuint,
-    visible: bool,
-    pub color_buff: gl::types::GLuint,
-    pub normal_buff: gl::types::GLuint,
-    pub last_calculated_frame: usize,
-    pub indices_count: usize,
+
+use crate::cb_assets;
+use cb_assets::AssetManager;
+
+const MESH_VERTEX_SHADER_PATH: &'static str = "cb_graphics/open_gl_backend/mesh.vert";
+const MESH_FRAGMENT_SHADER_PATH: &'static str = "cb_graphics/open_gl_backend/mesh.frag";
+const SHADOW_VERTEX_SHADER_PATH: &'static str = "cb_graphics/open_gl_backend/shadow.vert";
+const SHADOW_FRAGMENT_SHADER_PATH: &'static str = "cb_graphics/open_gl_backend/shadow.frag";
+
+/// The width and height of the sun's shadow map, in texels.
+const SHADOW_MAP_SIZE: i32 = 2048;
+
+/// The texture unit the mesh shader samples the shadow map from.
+const SHADOW_MAP_UNIT: u32 = 0;
+
+/// The locations of the mesh shader's uniforms.
+struct MeshUniforms {
+    mvp: i32,
+    model: i32,
+    material_color: i32,
+    sun_direction: i32,
+    sun_color: i32,
+    ambient: i32,
+    shadows_enabled: i32,
+    shadow_map: i32,
+    light_space: i32,
+    point_light_count: i32,
+    point_light_positions: i32,
+    point_light_colors: i32,
+    point_light_radii: i32,
+    fog_enabled: i32,
+    fog_color: i32,
+    fog_start: i32,
+    fog_end: i32,
+    eye: i32,
+}
+
+impl MeshUniforms {
+    fn locate(program: &render_gl::ShaderProgram) -> Self {
+        return Self {
+            mvp: program.uniform_location("MVP"),
+            model: program.uniform_location("cbModel"),
+            material_color: program.uniform_location("cbMaterialColor"),
+            sun_direction: program.uniform_location("cbSunDirection"),
+            sun_color: program.uniform_location("cbSunColor"),
+            ambient: program.uniform_location("cbAmbient"),
+            shadows_enabled: program.uniform_location("cbShadowsEnabled"),
+            shadow_map: program.uniform_location("cbShadowMap"),
+            light_space: program.uniform_location("cbLightSpace"),
+            point_light_count: program.uniform_location("cbPointLightCount"),
+            point_light_positions: program.uniform_location("cbPointLightPositions"),
+            point_light_colors: program.uniform_location("cbPointLightColors"),
+            point_light_radii: program.uniform_location("cbPointLightRadii"),
+            fog_enabled: program.uniform_location("cbFogEnabled"),
+            fog_color: program.uniform_location("cbFogColor"),
+            fog_start: program.uniform_location("cbFogStart"),
+            fog_end: program.uniform_location("cbFogEnd"),
+            eye: program.uniform_location("cbEyePos"),
+        };
+    }
 }
 
+/// Draws to the game window with OpenGL. Meshes are drawn when the sprites are or the frame is presented, first
+/// into the sun's shadow map and then lit into the window.
 pub struct OpenGlBackend {
-    basic_mesh_program: render_gl::Program,
-    chunk_mesh_buffers: Vec<MeshBuffers>,
+    window: sdl2::video::Window,
+    basic_mesh_program: render_gl::ShaderProgram,
+    shadow_program: render_gl::ShaderProgram,
     sprite_renderer: CbSpriteRenderer,
-    mvp_id: i32,
-    light_id: i32,
-    frame: usize,
-    voxel_mesher: cb_graphics::mesh::voxel_mesher::VoxelMesher,
-    collada_renderer: CbColladaRenderer,
+    shadow_framebuffer: ShadowFramebuffer,
+    /// The buffers of each uploaded mesh and the bounds its shadow is fitted by, or none if it was deleted.
+    meshes: Vec<Option<(MeshBuffers, Option<Bounds>)>>,
+    mesh_uniforms: MeshUniforms,
+    shadow_mvp_id: i32,
+    /// The meshes drawn since the last flush, as (id, transform, material).
+    queued: Vec<(MeshId, na::Matrix4<f32>, Material)>,
+    lighting: Lighting,
+    proj_view: na::Matrix4<f32>,
+    eye: na::Vector3<f32>,
+    window_size: (f32, f32),
 }
 
 impl OpenGlBackend {
-    pub fn new() -> Self {
-        // Collada renderer
-        let mut collada_renderer = CbColladaRenderer::new();
-        collada_renderer.load_collada(&Path::new("./src/assets/monkey.dae"));
-
+    /// Create the backend for a window whose OpenGL context is current.
+    pub fn new(assets: &AssetManager, window: sdl2::video::Window) -> Self {
         // Basic mesh program
-        let mesh_program;
-        {
-            let vert_shader = render_gl::Shader::from_vert_source(
-                &CString::new(include_str!("mesh.vert")).unwrap(),
-            )
-            .unwrap();
-
-            let frag_shader = render_gl::Shader::from_frag_source(
-                &CString::new(include_str!("mesh.frag")).unwrap(),
-            )
-            .unwrap();
-            mesh_program = render_gl::Program::from_shaders(&[vert_shader, frag_shader]).unwrap();
-        }
+        let mesh_program = match render_gl::ShaderProgram::load(
+            assets,
+            MESH_VERTEX_SHADER_PATH,
+            MESH_FRAGMENT_SHADER_PATH,
+        ) {
+            Ok(program) => program,
+            Err(e) => panic!("Unable to load mesh shaders: {}", e),
+        };
 
-        mesh_program.set_used();
+        // Shadow map program
+        let shadow_program = match render_gl::ShaderProgram::load(
+            assets,
+            SHADOW_VERTEX_SHADER_PATH,
+            SHADOW_FRAGMENT_SHADER_PATH,
+        ) {
+            Ok(program) => program,
+            Err(e) => panic!("Unable to load shadow shaders: {}", e),
+        };
 
-        // MVP uniform
-        let mvp_str = &CString::new("MVP").unwrap();
-        let mvp_id;
-        unsafe {
-            mvp_id = gl::GetUniformLocation(mesh_program.id(), mvp_str.as_ptr());
-        }
+        mesh_program.set_used();
 
-        // Light uniform
-        let light_str = &CString::new("cbLightPos").unwrap();
-        let light_id;
-        unsafe {
-            light_id = gl::GetUniformLocation(mesh_program.id(), light_str.as_ptr());
-        }
+        // Uniforms
+        let mesh_uniforms = MeshUniforms::locate(&mesh_program);
+        let shadow_mvp_id = shadow_program.uniform_location("cbLightMVP");
 
         // Backface culling
         unsafe {
@@ -94,148 +140,259 @@ impl OpenGlBackend {
             gl::Enable(gl::DEPTH_TEST);
         }
 
-        return Self {
-            sprite_renderer: CbSpriteRenderer::new(),
+        let (width, height) = window.size();
+
+        let backend = Self {
+            window: window,
+            sprite_renderer: CbSpriteRenderer::new(assets),
             basic_mesh_program: mesh_program,
-            chunk_mesh_buffers: r_voxel_render::init_voxel_mesh_buffers(),
-            mvp_id: mvp_id,
-            light_id: light_id,
-            frame: 0,
-            voxel_mesher: cb_graphics::mesh::voxel_mesher::VoxelMesher::new(),
-            collada_renderer: collada_renderer,
+            shadow_program: shadow_program,
+            shadow_framebuffer: ShadowFramebuffer::new(SHADOW_MAP_SIZE),
+            meshes: vec![],
+            mesh_uniforms: mesh_uniforms,
+            shadow_mvp_id: shadow_mvp_id,
+            queued: vec![],
+            lighting: Lighting::default(),
+            proj_view: na::Matrix4::identity(),
+            eye: na::Vector3::zeros(),
+            window_size: (width as f32, height as f32),
         };
+        backend.clear();
+
+        return backend;
     }
 
-    pub fn render(
-        renderer: &mut Self,
-        camera: &cb_graphics::CbCamera,
-        game_state: &CbGameState,
-        world: &World,
-        frame: usize,
-    ) {
+    fn clear(&self) {
         unsafe {
             gl::ClearColor(1.0, 1.0, 1.0, 0.0);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
+    }
 
-        // Draw sprites
-        {
-            renderer.sprite_renderer.batch();
-            renderer.sprite_renderer.render();
+    /// Pick up reloaded shaders.
+    fn update_shaders(&mut self) {
+        if self.basic_mesh_program.update() {
+            self.mesh_uniforms = MeshUniforms::locate(&self.basic_mesh_program);
+        }
+
+        if self.shadow_program.update() {
+            self.shadow_mvp_id = self.shadow_program.uniform_location("cbLightMVP");
+        }
+
+        self.sprite_renderer.update();
+    }
+
+    /// Draw the queued meshes, after drawing them into the sun's shadow map if it casts shadows.
+    fn flush(&mut self) {
+        let queued = std::mem::replace(&mut self.queued, vec![]);
+        if queued.is_empty() {
+            return;
         }
 
-        // Draw collada
+        let sun_matrix = if self.lighting.sun.shadows {
+            self.bounds(&queued)
+                .map(|bounds| self.lighting.sun_matrix(&bounds))
+        } else {
+            None
+        };
+
+        // Shadow pass; both faces are drawn, so thin and open meshes still cast shadows
+        if let Some(sun_matrix) = sun_matrix {
+            self.shadow_framebuffer.bind();
+            self.shadow_program.set_used();
+
+            unsafe {
+                gl::Disable(gl::CULL_FACE);
+            }
+
+            for (id, transform, _) in queued.iter() {
+                if let Some(Some((buffers, _))) = self.meshes.get(*id) {
+                    let mvp = sun_matrix * transform;
+
+                    unsafe {
+                        gl::UniformMatrix4fv(self.shadow_mvp_id, 1, gl::FALSE, mvp.as_ptr());
+                    }
+
+                    buffers.draw();
+                }
+            }
+
+            unsafe {
+                gl::Enable(gl::CULL_FACE);
+            }
+
+            self.shadow_framebuffer.unbind(self.window_size);
+        }
+
+        // Lit pass
+        self.basic_mesh_program.set_used();
+        self.set_lighting_uniforms(sun_matrix);
+
+        for (id, transform, material) in queued.iter() {
+            let buffers = match self.meshes.get(*id) {
+                Some(Some((buffers, _))) => buffers,
+                _ => continue,
+            };
+
+            // Set MVP
+            {
+                let mvp = self.proj_view * transform;
+
+                unsafe {
+                    gl::UniformMatrix4fv(self.mesh_uniforms.mvp, 1, gl::FALSE, mvp.as_ptr());
+                    gl::UniformMatrix4fv(
+                        self.mesh_uniforms.model,
+                        1,
+                        gl::FALSE,
+                        transform.as_ptr(),
+                    );
+                    gl::Uniform3fv(
+                        self.mesh_uniforms.material_color,
+                        1,
+                        material.color.as_ptr(),
+                    );
+                }
+            }
+
+            buffers.draw();
+        }
+    }
+
+    /// The box around every queued mesh, or none if none of them have vertices.
+    fn bounds(&self, queued: &[(MeshId, na::Matrix4<f32>, Material)]) -> Option<Bounds> {
+        return queued
+            .iter()
+            .filter_map(|(id, transform, _)| match self.meshes.get(*id) {
+                Some(Some((_, Some(bounds)))) => Some(bounds.transformed(transform)),
+                _ => None,
+            })
+            .fold(None, |all: Option<Bounds>, bounds| match all {
+                Some(all) => Some(Bounds::new(
+                    all.min.zip_map(&bounds.min, f32::min),
+                    all.max.zip_map(&bounds.max, f32::max),
+                )),
+                None => Some(bounds),
+            });
+    }
+
+    /// Set the uniforms every mesh in the frame is lit by. The sun matrix is that of the shadow map, if one was drawn.
+    fn set_lighting_uniforms(&self, sun_matrix: Option<na::Matrix4<f32>>) {
+        let uniforms = &self.mesh_uniforms;
+        let lighting = &self.lighting;
+
+        // Sun
         {
-            renderer
-                .collada_renderer
-                .draw(renderer.mvp_id, camera, frame);
+            let direction = lighting.sun_direction();
+
+            unsafe {
+                gl::Uniform3fv(uniforms.sun_direction, 1, direction.as_ptr());
+                gl::Uniform3fv(uniforms.sun_color, 1, lighting.sun.color.as_ptr());
+                gl::Uniform1f(uniforms.ambient, lighting.ambient);
+            }
         }
 
-        let draw_voxels = true;
+        // Shadows
+        {
+            let light_space = sun_matrix.unwrap_or(na::Matrix4::identity());
+            self.shadow_framebuffer.bind_texture(SHADOW_MAP_UNIT);
 
-        // Draw voxels
-        if draw_voxels {
-            let voxel_components =
-                world.read_storage::<components::voxel_components::VoxelComponent>();
+            unsafe {
+                gl::Uniform1i(uniforms.shadows_enabled, sun_matrix.is_some() as i32);
+                gl::Uniform1i(uniforms.shadow_map, SHADOW_MAP_UNIT as i32);
+                gl::UniformMatrix4fv(uniforms.light_space, 1, gl::FALSE, light_space.as_ptr());
+            }
+        }
 
-            // First mesh them
-            for voxel in (&voxel_components).join() {
-                renderer
-                    .voxel_mesher
-                    .mesh(&voxel.chunk_manager, frame, camera);
+        // Point lights, nearest to the camera first as there are only so many the shader takes
+        {
+            let point_lights = lighting.nearest_point_lights(&self.eye, lighting::MAX_POINT_LIGHTS);
+            let positions: Vec<f32> = point_lights
+                .iter()
+                .flat_map(|l| l.position.to_vec())
+                .collect();
+            let colors: Vec<f32> = point_lights.iter().flat_map(|l| l.color.to_vec()).collect();
+            let radii: Vec<f32> = point_lights.iter().map(|l| l.radius).collect();
+            let count = point_lights.len() as i32;
+
+            unsafe {
+                gl::Uniform1i(uniforms.point_light_count, count);
+                if count > 0 {
+                    gl::Uniform3fv(uniforms.point_light_positions, count, positions.as_ptr());
+                    gl::Uniform3fv(uniforms.point_light_colors, count, colors.as_ptr());
+                    gl::Uniform1fv(uniforms.point_light_radii, count, radii.as_ptr());
+                }
             }
+        }
 
-            renderer.basic_mesh_program.set_used();
-            r_voxel_render::draw_voxel_meshes(renderer, camera, frame);
+        // Fog
+        {
+            unsafe {
+                gl::Uniform3fv(uniforms.eye, 1, self.eye.as_ptr());
+                gl::Uniform1i(uniforms.fog_enabled, lighting.fog.is_some() as i32);
+
+                if let Some(fog) = lighting.fog {
+                    gl::Uniform3fv(uniforms.fog_color, 1, fog.color.as_ptr());
+                    gl::Uniform1f(uniforms.fog_start, fog.start);
+                    gl::Uniform1f(uniforms.fog_end, fog.end);
+                }
+            }
         }
-        renderer.frame += 1;
     }
 }
 
-type CbProjection = cb_graphics::open_gl_backend::na::Matrix<
-    f32,
-    cb_graphics::open_gl_backend::na::U4,
-    cb_graphics::open_gl_backend::na::U4,
-    cb_graphics::open_gl_backend::na::ArrayStorage<
-        f32,
-        cb_graphics::open_gl_backend::na::U4,
-        cb_graphics::open_gl_backend::na::U4,
-    >,
->;
-
-type CbView = cb_graphics::open_gl_backend::na::Matrix<
-    f32,
-    cb_graphics::open_gl_backend::na::U4,
-    cb_graphics::open_gl_backend::na::U4,
-    cb_graphics::open_gl_backend::na::ArrayStorage<
-        f32,
-        cb_graphics::open_gl_backend::na::U4,
-        cb_graphics::open_gl_backend::na::U4,
-    >,
->;
-
-fn get_proj_view(camera: &cb_graphics::CbCamera) -> (CbProjection, CbView) {
-    let proj;
-    let view;
-    {
-        let horizontal_angle = 3.14;
-        let vertical_angle = 0.0;
-        let mouse_speed = 0.005; // configure to user variable?
-
-        let delta_time = 1.0; //TODO: figure out
-                              /*
-                                                            let horizontal_angle = horizontal_angle
-                                                                + mouse_speed * delta_time * (camera.window_width / 2.0 - camera.cursor_x);
-                                                            let vertical_angle = vertical_angle
-                                                                + mouse_speed * delta_time * (camera.window_height / 2.0 - camera.cursor_y);
-
-                                                            let mouse_target = Point3::new(
-                                                                vertical_angle.cos() * horizontal_angle.sin(),
-                                                                vertical_angle.sin(),
-                                                                vertical_angle.cos() * horizontal_angle.cos(),
-                                                            );
-
-                                                            let target = Point3::new(
-                                  mouse_target.x + camera.pos_x,
-                                  mouse_target.y + camera.pos_y,
-                                  mouse_target.z + camera.pos_z,
-                              );
-                                                    */
-
-        //TODO: need to figure out camera pitch, yaw, roll
-        let target = Point3::new(camera.target_x, camera.target_y, camera.target_z);
-
-        let eye = Point3::new(camera.pos_x, camera.pos_y, camera.pos_z);
-        view = Isometry3::look_at_rh(&eye, &target, &Vector3::y());
-
-        if camera.orthographic_view {
-            const BOUNDS: f32 = 22.0;
-
-            let horizontal_bounds = (camera.window_width / camera.window_height) * BOUNDS; // Ensure it's properly scaled for the aspect ratio
-            let vertical_bounds = BOUNDS;
-
-            let ortho = na::Orthographic3::new(
-                -horizontal_bounds,
-                horizontal_bounds,
-                -vertical_bounds,
-                vertical_bounds,
-                0.01,
-                10000.0,
-            );
-
-            proj = Perspective3::from_matrix_unchecked(*ortho.as_matrix());
-        } else {
-            proj = Perspective3::new(
-                (camera.window_width / camera.window_height), // Ensure it's properly scaled for the aspect ratio
-                3.14 / 2.0,
-                0.1,
-                1000.0,
-            );
+impl Renderer for OpenGlBackend {
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshId {
+        self.meshes
+            .push(Some((MeshBuffers::new(mesh), Bounds::from_mesh(mesh))));
+
+        return self.meshes.len() - 1;
+    }
+
+    fn update_mesh(&mut self, id: MeshId, mesh: &Mesh) {
+        if let Some(Some((buffers, bounds))) = self.meshes.get_mut(id) {
+            buffers.fill(mesh);
+            *bounds = Bounds::from_mesh(mesh);
+        }
+    }
+
+    fn delete_mesh(&mut self, id: MeshId) {
+        // Queued draws of the mesh are skipped
+        if let Some((buffers, _)) = self.meshes.get_mut(id).and_then(|mesh| mesh.take()) {
+            buffers.delete();
         }
     }
 
-    let proj: CbProjection = *proj.as_matrix();
-    let view = view.to_homogeneous();
+    fn set_camera(&mut self, camera: &cb_graphics::CbCamera) {
+        // Meshes drawn so far are seen from the camera they were drawn with
+        self.flush();
+
+        let (proj, view) = camera.proj_view();
+        self.proj_view = proj * view;
+        self.eye = na::Vector3::new(camera.pos_x, camera.pos_y, camera.pos_z);
+    }
 
-    return (proj, view);
+    fn set_lighting(&mut self, lighting: &Lighting) {
+        self.flush();
+
+        self.lighting = lighting.clone();
+    }
+
+    fn draw_mesh(&mut self, id: MeshId, transform: &na::Matrix4<f32>, material: &Material) {
+        self.queued.push((id, *transform, *material));
+    }
+
+    fn draw_sprites(&mut self, sprites: &[Sprite]) {
+        self.flush();
+
+        self.sprite_renderer.render(sprites, self.window_size);
+    }
+
+    fn present(&mut self) {
+        self.flush();
+
+        self.window.gl_swap_window();
+
+        self.update_shaders();
+        self.clear();
+    }
 }
@@ -3,6 +3,10 @@
 extern crate gl;
 
 use std::ffi::{CStr, CString};
+use std::path::Path;
+
+use crate::cb_assets;
+use cb_assets::{Asset, AssetManager, Handle};
 
 pub struct Program {
     id: gl::types::GLuint,
@@ -75,6 +79,100 @@ impl Drop for Program {
     }
 }
 
+/// A program built from shader assets, which is rebuilt when either shader is reloaded.
+pub struct ShaderProgram {
+    vert: Handle<ShaderSource>,
+    frag: Handle<ShaderSource>,
+    versions: (u64, u64),
+    program: Program,
+}
+
+impl ShaderProgram {
+    pub fn load(assets: &AssetManager, vert_path: &str, frag_path: &str) -> Result<Self, String> {
+        let vert = assets.load::<ShaderSource>(Path::new(vert_path))?;
+        let frag = assets.load::<ShaderSource>(Path::new(frag_path))?;
+        let program = Self::link(&vert, &frag)?;
+
+        return Ok(Self {
+            versions: (vert.version(), frag.version()),
+            vert: vert,
+            frag: frag,
+            program: program,
+        });
+    }
+
+    fn link(vert: &Handle<ShaderSource>, frag: &Handle<ShaderSource>) -> Result<Program, String> {
+        let source = |handle: &Handle<ShaderSource>| {
+            return handle
+                .get()
+                .ok_or_else(|| format!("Shader {} is not loaded", handle.path().display()));
+        };
+
+        let vert_shader = Shader::from_vert_source(source(vert)?.source())
+            .map_err(|e| format!("{}: {}", vert.path().display(), e))?;
+        let frag_shader = Shader::from_frag_source(source(frag)?.source())
+            .map_err(|e| format!("{}: {}", frag.path().display(), e))?;
+
+        return Program::from_shaders(&[vert_shader, frag_shader]);
+    }
+
+    /// Rebuild the program if a shader was reloaded, returning whether it was. Shaders that fail to compile keep the
+    /// previous program, so uniform locations only need to be looked up again when this returns true.
+    pub fn update(&mut self) -> bool {
+        let versions = (self.vert.version(), self.frag.version());
+        if versions == self.versions {
+            return false;
+        }
+
+        self.versions = versions;
+        match Self::link(&self.vert, &self.frag) {
+            Ok(program) => {
+                self.program = program;
+                return true;
+            }
+            Err(e) => {
+                println!("Unable to rebuild shader program: {}", e);
+                return false;
+            }
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        return &self.program;
+    }
+
+    pub fn set_used(&self) {
+        self.program.set_used();
+    }
+
+    pub fn uniform_location(&self, name: &str) -> i32 {
+        let name = CString::new(name).unwrap();
+        return unsafe { gl::GetUniformLocation(self.program.id(), name.as_ptr()) };
+    }
+}
+
+/// The text of a GLSL shader, loaded as an asset so it can be edited while the game runs.
+pub struct ShaderSource {
+    source: CString,
+}
+
+impl ShaderSource {
+    pub fn source(&self) -> &CStr {
+        return &self.source;
+    }
+}
+
+impl Asset for ShaderSource {
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read shader {}: {}", path.display(), e))?;
+        let source = CString::new(text)
+            .map_err(|_| format!("Shader {} contains a nul character", path.display()))?;
+
+        return Ok(Self { source: source });
+    }
+}
+
 pub struct Shader {
     id: gl::types::GLuint,
 }
@@ -0,0 +1,10 @@
+#version 330 core
+
+layout (location = 0) in vec3 myPosition;
+
+uniform mat4 cbLightMVP;
+
+void main()
+{
+    gl_Position = cbLightMVP * vec4(myPosition, 1);
+}
@@ -0,0 +1,112 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate gl;
+
+/// A depth texture the scene is drawn into from the sun, for the mesh shader to look up shadows in.
+pub struct ShadowFramebuffer {
+    fbo: gl::types::GLuint,
+    texture: gl::types::GLuint,
+    size: i32,
+}
+
+impl ShadowFramebuffer {
+    /// Create a square shadow map of the size, in texels.
+    pub fn new(size: i32) -> Self {
+        let mut fbo = 0;
+        let mut texture = 0;
+
+        // Outside of the map is as far as it goes, so nothing there is shadowed
+        let border = [1.0 as f32, 1.0, 1.0, 1.0];
+
+        unsafe {
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::DEPTH_COMPONENT as gl::types::GLint,
+                size,
+                size,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::NEAREST as gl::types::GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MAG_FILTER,
+                gl::NEAREST as gl::types::GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_BORDER as gl::types::GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_BORDER as gl::types::GLint,
+            );
+            gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                texture,
+                0,
+            );
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        return Self {
+            fbo: fbo,
+            texture: texture,
+            size: size,
+        };
+    }
+
+    /// Draw into the shadow map, clearing what was there.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.size, self.size);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Go back to drawing to the window.
+    pub fn unbind(&self, window_size: (f32, f32)) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, window_size.0 as i32, window_size.1 as i32);
+        }
+    }
+
+    /// Sample the shadow map from the texture unit.
+    pub fn bind_texture(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+        }
+    }
+}
+
+impl Drop for ShadowFramebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+
+use crate::cb_assets;
+use cb_assets::Handle;
+
+use crate::cb_graphics;
+use cb_graphics::lighting::Lighting;
+use cb_graphics::models::Model;
+use cb_graphics::renderer::Material;
+
+use crate::cb_simulation;
+use cb_simulation::components::gfx_components::TransformState;
+use cb_simulation::components::physics_components::TransformComponent;
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use crate::cb_voxels;
+
+use std::f32::consts::PI;
+
+/// A model to draw this frame.
+#[derive(Clone)]
+pub struct RenderItem {
+    pub model: Handle<Model>,
+    /// From the model's space to the world.
+    pub transform: na::Matrix4<f32>,
+    pub material: Material,
+}
+
+/// Everything the renderable entities want drawn this frame, gathered by the render list system.
+#[derive(Clone, Default)]
+pub struct RenderList {
+    pub items: Vec<RenderItem>,
+    /// The transform of the voxel terrain, or none if there is no terrain.
+    pub terrain: Option<na::Matrix4<f32>>,
+    /// The map's lighting, with the point lights of the entities that give off light.
+    pub lighting: Lighting,
+}
+
+impl RenderList {
+    pub fn new() -> Self {
+        return Self {
+            items: vec![],
+            terrain: None,
+            lighting: Lighting::default(),
+        };
+    }
+}
+
+/// Convert a game unit to world space, where a voxel is VOXEL_SIZE across.
+pub fn to_world_units(value: GameUnit) -> f32 {
+    return value.to_num::<f32>() / cb_voxels::VOXEL_GAME_SIZE as f32 * cb_voxels::VOXEL_SIZE;
+}
+
+/// The world space transform of an entity standing at the height, in game units.
+/// The rotation turns about the up axis by x then tilts about the entity's x axis by y, in radians. The scale's x
+/// stretches across the ground and y stretches the height.
+pub fn transform_matrix(transform: &TransformComponent, height: GameUnit) -> na::Matrix4<f32> {
+    let position = na::Vector3::new(
+        to_world_units(transform.world_position.x),
+        to_world_units(transform.world_position.y),
+        to_world_units(height),
+    );
+
+    return matrix(
+        position,
+        transform.rotation.x.to_num(),
+        transform.rotation.y.to_num(),
+        transform.scale.x.to_num(),
+        transform.scale.y.to_num(),
+    );
+}
+
+/// The world space transform of an entity the alpha of the way from its previous tick's state to its current one.
+/// Rotations turn the shortest way around.
+pub fn interpolated_transform_matrix(
+    previous: &TransformState,
+    current: &TransformState,
+    alpha: f32,
+) -> na::Matrix4<f32> {
+    let lerp = |previous: f32, current: f32| previous + (current - previous) * alpha;
+    let lerp_units = |previous: GameUnit, current: GameUnit| {
+        lerp(to_world_units(previous), to_world_units(current))
+    };
+    let lerp_angle = |previous: GameUnit, current: GameUnit| {
+        let (previous, current) = (previous.to_num::<f32>(), current.to_num::<f32>());
+        let turn = (current - previous + PI).rem_euclid(2.0 * PI) - PI;
+
+        return previous + turn * alpha;
+    };
+
+    let position = na::Vector3::new(
+        lerp_units(previous.world_position.x, current.world_position.x),
+        lerp_units(previous.world_position.y, current.world_position.y),
+        lerp_units(previous.height, current.height),
+    );
+
+    return matrix(
+        position,
+        lerp_angle(previous.rotation.x, current.rotation.x),
+        lerp_angle(previous.rotation.y, current.rotation.y),
+        lerp(previous.scale.x.to_num(), current.scale.x.to_num()),
+        lerp(previous.scale.y.to_num(), current.scale.y.to_num()),
+    );
+}
+
+fn matrix(
+    position: na::Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+    ground_scale: f32,
+    height_scale: f32,
+) -> na::Matrix4<f32> {
+    let yaw = na::Rotation3::from_axis_angle(&na::Vector3::z_axis(), yaw);
+    let pitch = na::Rotation3::from_axis_angle(&na::Vector3::x_axis(), pitch);
+    let scale = na::Vector3::new(ground_scale, ground_scale, height_scale);
+
+    return na::Matrix4::new_translation(&position)
+        * (yaw * pitch).to_homogeneous()
+        * na::Matrix4::new_nonuniform_scaling(&scale);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_matrix_converts_game_units_to_voxels() {
+        let mut transform = TransformComponent::new();
+        transform.world_position.x = GameUnit::from_num(cb_voxels::VOXEL_GAME_SIZE * 2);
+        transform.world_position.y = GameUnit::from_num(cb_voxels::VOXEL_GAME_SIZE / 2);
+
+        let matrix = transform_matrix(&transform, GameUnit::from_num(cb_voxels::VOXEL_GAME_SIZE));
+        let origin = matrix.transform_point(&na::Point3::origin());
+
+        assert!((origin.coords - na::Vector3::new(2.0, 0.5, 1.0)).norm() < 0.001);
+    }
+
+    #[test]
+    fn transform_matrix_rotates_then_scales() {
+        let mut transform = TransformComponent::new();
+        transform.rotation.x = GameUnit::from_num(std::f32::consts::FRAC_PI_2);
+        transform.scale.x = GameUnit::from_num(2);
+        transform.scale.y = GameUnit::from_num(3);
+
+        let matrix = transform_matrix(&transform, GameUnit::from_num(0));
+
+        let forward = matrix.transform_vector(&na::Vector3::x());
+        let up = matrix.transform_vector(&na::Vector3::z());
+
+        assert!((forward - na::Vector3::new(0.0, 2.0, 0.0)).norm() < 0.01);
+        assert!((up - na::Vector3::new(0.0, 0.0, 3.0)).norm() < 0.01);
+    }
+
+    fn state(x: i32, yaw: f32) -> TransformState {
+        let mut rotation = cb_system::Coordinate2d::zero();
+        rotation.x = GameUnit::from_num(yaw);
+
+        return TransformState {
+            world_position: cb_system::Coordinate2d::new(
+                GameUnit::from_num(x * cb_voxels::VOXEL_GAME_SIZE),
+                GameUnit::from_num(0),
+            ),
+            height: GameUnit::from_num(0),
+            rotation: rotation,
+            scale: cb_system::Coordinate2d::one(),
+        };
+    }
+
+    #[test]
+    fn interpolated_transform_matrix_moves_between_ticks() {
+        let (previous, current) = (state(2, 0.0), state(6, 0.0));
+
+        let origin = |alpha: f32| {
+            interpolated_transform_matrix(&previous, &current, alpha)
+                .transform_point(&na::Point3::origin())
+                .coords
+        };
+
+        assert!((origin(0.0) - na::Vector3::new(2.0, 0.0, 0.0)).norm() < 0.001);
+        assert!((origin(0.25) - na::Vector3::new(3.0, 0.0, 0.0)).norm() < 0.001);
+        assert!((origin(1.0) - na::Vector3::new(6.0, 0.0, 0.0)).norm() < 0.001);
+    }
+
+    #[test]
+    fn interpolated_transform_matrix_turns_shortest_way() {
+        // From just short of a full turn to just past none, through zero rather than back around
+        let (previous, current) = (state(0, 2.0 * PI - 0.1), state(0, 0.1));
+
+        let matrix = interpolated_transform_matrix(&previous, &current, 0.5);
+        let forward = matrix.transform_vector(&na::Vector3::x());
+
+        assert!((forward - na::Vector3::x()).norm() < 0.01);
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+pub mod recording;
+
+/*
+    The interface between what is drawn and how it is drawn. The scene renderer decides which meshes and sprites make
+    up a frame, while a renderer owns the platform resources they're drawn with. The OpenGL backend draws to the game
+    window; the recording renderer keeps a list of the calls it was given so frames can be checked without a GPU.
+
+    A frame is the calls between two presents: set the camera and lighting, draw meshes and sprites, then present.
+    Meshes are uploaded once and drawn by id each frame until they're deleted. Renderers may hold on to the meshes
+    drawn until the sprites or present, as shadows need every mesh before any of them can be lit.
+*/
+
+extern crate nalgebra as na;
+
+use crate::cb_graphics;
+use cb_graphics::lighting::Lighting;
+use cb_graphics::mesh::Mesh;
+use cb_graphics::CbCamera;
+
+/// The id of a mesh that was uploaded to a renderer.
+pub type MeshId = usize;
+
+/// The surface a mesh is drawn with.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Material {
+    /// Multiplied with the mesh's vertex colors.
+    pub color: [f32; 3],
+}
+
+impl Material {
+    pub fn new(color: [f32; 3]) -> Self {
+        return Self { color: color };
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        return Self::new([1.0, 1.0, 1.0]);
+    }
+}
+
+/// A flat colored rectangle drawn over the scene, in pixels from the top left of the window.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprite {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [f32; 3],
+}
+
+impl Sprite {
+    pub fn new(position: [f32; 2], size: [f32; 2], color: [f32; 3]) -> Self {
+        return Self {
+            position: position,
+            size: size,
+            color: color,
+        };
+    }
+}
+
+pub trait Renderer {
+    /// Store the mesh for drawing, returning its id.
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshId;
+    /// Replace the contents of an uploaded mesh.
+    fn update_mesh(&mut self, id: MeshId, mesh: &Mesh);
+    fn delete_mesh(&mut self, id: MeshId);
+    /// The camera meshes are drawn from until it is set again.
+    fn set_camera(&mut self, camera: &CbCamera);
+    /// The lighting meshes are drawn with until it is set again.
+    fn set_lighting(&mut self, lighting: &Lighting);
+    /// Draw an uploaded mesh, with the transform from the mesh's space to the world.
+    fn draw_mesh(&mut self, id: MeshId, transform: &na::Matrix4<f32>, material: &Material);
+    fn draw_sprites(&mut self, sprites: &[Sprite]);
+    /// Finish the frame and show it. The next draw starts a new frame.
+    fn present(&mut self);
+}
@@ -0,0 +1,165 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use super::*;
+
+/// A call made to a renderer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCall {
+    UploadMesh(MeshId),
+    UpdateMesh(MeshId),
+    DeleteMesh(MeshId),
+    SetCamera(CbCamera),
+    SetLighting(Lighting),
+    DrawMesh(MeshId, na::Matrix4<f32>, Material),
+    DrawSprites(Vec<Sprite>),
+    Present,
+}
+
+/// A headless renderer that records the calls it is given, for checking what a frame draws without a GPU.
+pub struct RecordingRenderer {
+    /// Every call since the renderer was created, in order.
+    pub calls: Vec<DrawCall>,
+    /// The contents of each uploaded mesh, or none if it was deleted.
+    pub meshes: Vec<Option<Mesh>>,
+    pub frames_presented: usize,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        return Self {
+            calls: vec![],
+            meshes: vec![],
+            frames_presented: 0,
+        };
+    }
+
+    /// The calls made since the last present, or during the last frame if it was just presented.
+    pub fn last_frame(&self) -> &[DrawCall] {
+        let mut end = self.calls.len();
+        if self.calls.last() == Some(&DrawCall::Present) {
+            end -= 1;
+        }
+
+        let start = self.calls[..end]
+            .iter()
+            .rposition(|call| *call == DrawCall::Present)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+
+        return &self.calls[start..end];
+    }
+
+    /// The meshes drawn during the last frame, with their transforms and materials.
+    pub fn drawn_meshes(&self) -> Vec<(MeshId, na::Matrix4<f32>, Material)> {
+        return self
+            .last_frame()
+            .iter()
+            .filter_map(|call| match call {
+                DrawCall::DrawMesh(id, transform, material) => Some((*id, *transform, *material)),
+                _ => None,
+            })
+            .collect();
+    }
+
+    /// The contents of the mesh. Panics if it was never uploaded or has been deleted, as the caller has a stale id.
+    pub fn mesh(&self, id: MeshId) -> &Mesh {
+        return match self.meshes.get(id) {
+            Some(Some(mesh)) => mesh,
+            _ => panic!("Mesh {} is not uploaded", id),
+        };
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshId {
+        self.meshes.push(Some(mesh.clone()));
+        let id = self.meshes.len() - 1;
+
+        self.calls.push(DrawCall::UploadMesh(id));
+        return id;
+    }
+
+    fn update_mesh(&mut self, id: MeshId, mesh: &Mesh) {
+        self.meshes[id] = Some(mesh.clone());
+        self.calls.push(DrawCall::UpdateMesh(id));
+    }
+
+    fn delete_mesh(&mut self, id: MeshId) {
+        self.meshes[id] = None;
+        self.calls.push(DrawCall::DeleteMesh(id));
+    }
+
+    fn set_camera(&mut self, camera: &CbCamera) {
+        self.calls.push(DrawCall::SetCamera(*camera));
+    }
+
+    fn set_lighting(&mut self, lighting: &Lighting) {
+        self.calls.push(DrawCall::SetLighting(lighting.clone()));
+    }
+
+    fn draw_mesh(&mut self, id: MeshId, transform: &na::Matrix4<f32>, material: &Material) {
+        self.calls
+            .push(DrawCall::DrawMesh(id, *transform, *material));
+    }
+
+    fn draw_sprites(&mut self, sprites: &[Sprite]) {
+        self.calls.push(DrawCall::DrawSprites(sprites.to_vec()));
+    }
+
+    fn present(&mut self) {
+        self.calls.push(DrawCall::Present);
+        self.frames_presented += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Mesh {
+        return Mesh::new(
+            3,
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            vec![0, 1, 2],
+            3,
+            vec![1.0; 9],
+            3,
+            vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            0,
+        );
+    }
+
+    #[test]
+    fn recording_renderer_last_frame_returns_calls_since_present() {
+        let mut renderer = RecordingRenderer::new();
+        let id = renderer.upload_mesh(&triangle());
+        renderer.draw_mesh(id, &na::Matrix4::identity(), &Material::default());
+        renderer.present();
+
+        let transform = na::Matrix4::new_translation(&na::Vector3::new(1.0, 2.0, 3.0));
+        let material = Material::new([0.5, 0.5, 0.5]);
+        renderer.draw_mesh(id, &transform, &material);
+        renderer.draw_sprites(&[Sprite::new([1.0, 2.0], [3.0, 4.0], [1.0, 0.0, 0.0])]);
+        renderer.present();
+
+        assert_eq!(2, renderer.frames_presented);
+        assert_eq!(2, renderer.last_frame().len());
+        assert_eq!(vec![(id, transform, material)], renderer.drawn_meshes());
+    }
+
+    #[test]
+    fn recording_renderer_update_mesh_replaces_contents() {
+        let mut renderer = RecordingRenderer::new();
+        let id = renderer.upload_mesh(&triangle());
+
+        let mut updated = triangle();
+        updated.indices = vec![2, 1, 0];
+        renderer.update_mesh(id, &updated);
+
+        assert_eq!(&updated, renderer.mesh(id));
+        assert_eq!(
+            vec![DrawCall::UploadMesh(id), DrawCall::UpdateMesh(id)],
+            renderer.calls
+        );
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_graphics;
+use cb_graphics::frustum::{Bounds, Frustum};
+use cb_graphics::mesh::voxel_mesher::VoxelMesher;
+use cb_graphics::render_list::{RenderItem, RenderList};
+use cb_graphics::renderer::{Material, MeshId, Renderer};
+use cb_graphics::CbCamera;
+
+use crate::cb_simulation;
+use cb_simulation::components::voxel_components::VoxelComponent;
+
+use crate::cb_voxels;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A mesh that was uploaded, with the bounds it's culled by. Empty meshes have no bounds.
+#[derive(Copy, Clone)]
+struct UploadedMesh {
+    id: MeshId,
+    bounds: Option<Bounds>,
+}
+
+impl UploadedMesh {
+    fn visible(&self, transform: &na::Matrix4<f32>, frustum: &Frustum) -> bool {
+        return match self.bounds {
+            Some(bounds) => frustum.intersects(&bounds.transformed(transform)),
+            None => false,
+        };
+    }
+}
+
+/// The meshes a model asset was uploaded as, as of the version that was uploaded.
+struct ModelInstance {
+    version: u64,
+    meshes: Vec<UploadedMesh>,
+}
+
+/// Draws the world through a renderer: the models in the render list, then the voxel terrain a chunk at a time.
+/// Meshes are only uploaded again when they change, and those outside the camera's view are skipped.
+pub struct CbSceneRenderer {
+    voxel_mesher: VoxelMesher,
+    /// The uploaded mesh of each chunk and the frame it was generated at.
+    chunk_meshes: Vec<Option<(UploadedMesh, usize)>>,
+    /// Keyed by the path of the model's asset, so entities sharing a model share its meshes.
+    models: HashMap<PathBuf, ModelInstance>,
+    culled: usize,
+}
+
+impl CbSceneRenderer {
+    pub fn new() -> Self {
+        return Self {
+            voxel_mesher: VoxelMesher::new(),
+            chunk_meshes: vec![None; cb_voxels::CHUNKS_CUBED],
+            models: HashMap::new(),
+            culled: 0,
+        };
+    }
+
+    /// The number of meshes left out of the last frame by frustum culling.
+    pub fn culled(&self) -> usize {
+        return self.culled;
+    }
+
+    /// Draw a frame of the world's render list from the camera. The caller presents it.
+    pub fn render<R: Renderer>(
+        &mut self,
+        renderer: &mut R,
+        camera: &CbCamera,
+        world: &World,
+        frame: usize,
+    ) {
+        renderer.set_camera(camera);
+
+        let frustum = Frustum::from_camera(camera);
+        let render_list = world.read_resource::<RenderList>();
+        renderer.set_lighting(&render_list.lighting);
+        self.culled = 0;
+
+        self.draw_models(renderer, &render_list.items, &frustum);
+
+        if let Some(terrain) = render_list.terrain {
+            self.draw_voxels(renderer, camera, world, &terrain, &frustum, frame);
+        }
+    }
+
+    fn draw_models<R: Renderer>(
+        &mut self,
+        renderer: &mut R,
+        items: &[RenderItem],
+        frustum: &Frustum,
+    ) {
+        // Free the meshes of models nothing draws anymore
+        self.models.retain(|path, instance| {
+            let used = items.iter().any(|item| item.model.path() == path.as_path());
+            if !used {
+                for mesh in instance.meshes.iter() {
+                    renderer.delete_mesh(mesh.id);
+                }
+            }
+
+            return used;
+        });
+
+        for item in items.iter() {
+            let model = match item.model.get() {
+                Some(model) => model,
+                None => continue,
+            };
+
+            let instance = self
+                .models
+                .entry(item.model.path().to_path_buf())
+                .or_insert_with(|| ModelInstance {
+                    version: 0,
+                    meshes: vec![],
+                });
+
+            // Upload models that finished loading or were reloaded
+            let version = item.model.version();
+            if version != instance.version {
+                for mesh in instance.meshes.drain(..) {
+                    renderer.delete_mesh(mesh.id);
+                }
+
+                instance.meshes = model
+                    .meshes
+                    .iter()
+                    .map(|model_mesh| UploadedMesh {
+                        id: renderer.upload_mesh(&model_mesh.mesh),
+                        bounds: Bounds::from_mesh(&model_mesh.mesh),
+                    })
+                    .collect();
+                instance.version = version;
+            }
+
+            // Skinned meshes are drawn in their bind pose until there is skinning on the GPU
+            let world_transforms = model.world_transforms();
+            for (node, node_transform) in model.nodes.iter().zip(world_transforms.iter()) {
+                let transform = item.transform * node_transform;
+
+                for mesh in node.meshes.iter() {
+                    let mesh = instance.meshes[*mesh];
+                    if mesh.visible(&transform, frustum) {
+                        renderer.draw_mesh(mesh.id, &transform, &item.material);
+                    } else {
+                        self.culled += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_voxels<R: Renderer>(
+        &mut self,
+        renderer: &mut R,
+        camera: &CbCamera,
+        world: &World,
+        transform: &na::Matrix4<f32>,
+        frustum: &Frustum,
+        frame: usize,
+    ) {
+        let voxel_components = world.read_storage::<VoxelComponent>();
+
+        // First mesh them
+        for voxel in (&voxel_components).join() {
+            self.voxel_mesher.mesh(&voxel.chunk_manager, frame, camera);
+        }
+
+        // Chunk meshes are offset within the terrain, so they share its transform
+        for (uploaded, wrapper) in self
+            .chunk_meshes
+            .iter_mut()
+            .zip(self.voxel_mesher.meshes.iter())
+        {
+            let mesh = &wrapper.mesh;
+
+            let chunk = match *uploaded {
+                Some((chunk, generated_at)) => {
+                    if generated_at == mesh.generated_at_frame {
+                        chunk
+                    } else {
+                        renderer.update_mesh(chunk.id, mesh);
+
+                        UploadedMesh {
+                            id: chunk.id,
+                            bounds: Bounds::from_mesh(mesh),
+                        }
+                    }
+                }
+                None => UploadedMesh {
+                    id: renderer.upload_mesh(mesh),
+                    bounds: Bounds::from_mesh(mesh),
+                },
+            };
+            *uploaded = Some((chunk, mesh.generated_at_frame));
+
+            if mesh.is_empty() {
+                continue;
+            }
+
+            if chunk.visible(transform, frustum) {
+                renderer.draw_mesh(chunk.id, transform, &Material::default());
+            } else {
+                self.culled += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cb_assets;
+    use cb_assets::AssetManager;
+
+    use cb_graphics::lighting::Lighting;
+    use cb_graphics::renderer::recording::{DrawCall, RecordingRenderer};
+    use cb_simulation::components::voxel_components::VoxelComponentsLinker;
+    use cb_simulation::components::ComponentLinker;
+
+    use std::path::Path;
+
+    fn voxel_world(terrain: Option<na::Matrix4<f32>>) -> World {
+        let mut world = World::new();
+        VoxelComponentsLinker::register_components(&mut world);
+        world.create_entity().with(VoxelComponent::new()).build();
+
+        let mut render_list = RenderList::new();
+        render_list.terrain = terrain;
+        world.insert(render_list);
+
+        return world;
+    }
+
+    fn uploads(renderer: &RecordingRenderer) -> usize {
+        return renderer
+            .calls
+            .iter()
+            .filter(|call| match call {
+                DrawCall::UploadMesh(_) => true,
+                _ => false,
+            })
+            .count();
+    }
+
+    #[test]
+    fn scene_renderer_uploads_chunks_once_and_draws_them_each_frame() {
+        let world = voxel_world(Some(na::Matrix4::identity()));
+        let camera = CbCamera::new(640.0, 480.0);
+        let mut renderer = RecordingRenderer::new();
+        let mut scene = CbSceneRenderer::new();
+
+        scene.render(&mut renderer, &camera, &world, 0);
+        renderer.present();
+        let drawn = renderer.drawn_meshes().len();
+
+        scene.render(&mut renderer, &camera, &world, 1);
+        renderer.present();
+
+        assert_eq!(cb_voxels::CHUNKS_CUBED, uploads(&renderer));
+        assert!(drawn > 0);
+        assert_eq!(drawn, renderer.drawn_meshes().len());
+        assert_eq!(DrawCall::SetCamera(camera), renderer.last_frame()[0]);
+        assert_eq!(
+            DrawCall::SetLighting(Lighting::default()),
+            renderer.last_frame()[1]
+        );
+    }
+
+    #[test]
+    fn scene_renderer_without_terrain_draws_nothing() {
+        let world = voxel_world(None);
+        let mut renderer = RecordingRenderer::new();
+
+        CbSceneRenderer::new().render(&mut renderer, &CbCamera::new(640.0, 480.0), &world, 0);
+
+        assert!(renderer.drawn_meshes().is_empty());
+    }
+
+    #[test]
+    fn scene_renderer_culls_terrain_outside_of_view() {
+        let camera = CbCamera::new(640.0, 480.0);
+        let mut renderer = RecordingRenderer::new();
+        let mut scene = CbSceneRenderer::new();
+
+        // Far behind the camera
+        let transform = na::Matrix4::new_translation(&na::Vector3::new(-5000.0, 0.0, 0.0));
+        scene.render(&mut renderer, &camera, &voxel_world(Some(transform)), 0);
+
+        assert!(renderer.drawn_meshes().is_empty());
+        assert!(scene.culled() > 0);
+    }
+
+    #[test]
+    fn scene_renderer_draws_render_list_models_at_their_transforms() {
+        let assets = AssetManager::new(Path::new(cb_assets::ASSET_ROOT));
+        let model = assets
+            .load(Path::new(cb_graphics::DEFAULT_MODEL_PATH))
+            .unwrap();
+
+        let mut world = voxel_world(None);
+        let in_view = na::Matrix4::new_translation(&na::Vector3::new(1.0, 0.0, 0.0));
+        let out_of_view = na::Matrix4::new_translation(&na::Vector3::new(5000.0, 0.0, 0.0));
+        world.insert(RenderList {
+            items: vec![
+                RenderItem {
+                    model: model.clone(),
+                    transform: in_view,
+                    material: Material::new([1.0, 0.0, 0.0]),
+                },
+                RenderItem {
+                    model: model.clone(),
+                    transform: out_of_view,
+                    material: Material::default(),
+                },
+            ],
+            terrain: None,
+            lighting: Lighting::default(),
+        });
+
+        let mut renderer = RecordingRenderer::new();
+        let mut scene = CbSceneRenderer::new();
+        scene.render(&mut renderer, &CbCamera::new(640.0, 480.0), &world, 0);
+
+        let mesh_count = model.get().unwrap().meshes.len();
+        let drawn = renderer.drawn_meshes();
+
+        // Both entities share the model's meshes
+        assert_eq!(mesh_count, uploads(&renderer));
+        assert!(!drawn.is_empty());
+        assert!(scene.culled() > 0);
+        for (_, transform, material) in drawn.iter() {
+            let origin = (transform * na::Vector4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+            assert!(origin.x < 100.0);
+            assert_eq!(Material::new([1.0, 0.0, 0.0]), *material);
+        }
+    }
+
+    #[test]
+    fn scene_renderer_deletes_models_no_longer_drawn() {
+        let assets = AssetManager::new(Path::new(cb_assets::ASSET_ROOT));
+        let model = assets
+            .load(Path::new(cb_graphics::DEFAULT_MODEL_PATH))
+            .unwrap();
+
+        let mut world = voxel_world(None);
+        world.insert(RenderList {
+            items: vec![RenderItem {
+                model: model,
+                transform: na::Matrix4::identity(),
+                material: Material::default(),
+            }],
+            terrain: None,
+            lighting: Lighting::default(),
+        });
+
+        let camera = CbCamera::new(640.0, 480.0);
+        let mut renderer = RecordingRenderer::new();
+        let mut scene = CbSceneRenderer::new();
+        scene.render(&mut renderer, &camera, &world, 0);
+
+        world.insert(RenderList::new());
+        scene.render(&mut renderer, &camera, &world, 1);
+
+        assert!(renderer.meshes.iter().all(|mesh| mesh.is_none()));
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use std::path::Path;
+
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// The most bytes a stored deflate block can hold.
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// An RGB image, with rows from the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    /// An image filled with the color.
+    pub fn new(width: usize, height: usize, color: [u8; 3]) -> Self {
+        return Self {
+            width: width,
+            height: height,
+            pixels: vec![color; width * height],
+        };
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        return self.pixels[x + y * self.width];
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: [u8; 3]) {
+        self.pixels[x + y * self.width] = color;
+    }
+
+    /// The number of pixels where a channel differs from the other image's by more than the tolerance.
+    pub fn count_differences(&self, other: &Image, tolerance: u8) -> Result<usize, String> {
+        if self.width != other.width || self.height != other.height {
+            return Err(format!(
+                "A {}x{} image can't be compared to a {}x{} one.",
+                self.width, self.height, other.width, other.height
+            ));
+        }
+
+        let differs = |a: &[u8; 3], b: &[u8; 3]| {
+            return (0..3).any(|i| (a[i] as i32 - b[i] as i32).abs() > tolerance as i32);
+        };
+
+        return Ok(self
+            .pixels
+            .iter()
+            .zip(other.pixels.iter())
+            .filter(|(a, b)| differs(a, b))
+            .count());
+    }
+
+    /// Save as a PNG or binary PPM, depending on the extension.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes = match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => self.to_png(),
+            Some("ppm") => self.to_ppm(),
+            _ => {
+                return Err(format!(
+                    "Unable to save '{}': images are saved as .png or .ppm.",
+                    path.display()
+                ))
+            }
+        };
+
+        return std::fs::write(path, bytes)
+            .map_err(|e| format!("Unable to save '{}': {}", path.display(), e));
+    }
+
+    /// Load a binary PPM.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+
+        return Self::from_ppm(&bytes)
+            .map_err(|e| format!("Unable to load '{}': {}", path.display(), e));
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in self.pixels.iter() {
+            bytes.extend_from_slice(pixel);
+        }
+
+        return bytes;
+    }
+
+    /// Parse a binary PPM with 8 bit channels.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, String> {
+        // The header is the magic number, width, height and max value, separated by whitespace and comments
+        let mut fields = vec![];
+        let mut i = 0;
+        while fields.len() < 4 {
+            match bytes.get(i) {
+                None => return Err("The header ends early.".to_string()),
+                Some(b'#') => {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+                Some(c) if c.is_ascii_whitespace() => i += 1,
+                Some(_) => {
+                    let start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                        i += 1;
+                    }
+                    fields.push(String::from_utf8_lossy(&bytes[start..i]).to_string());
+                }
+            }
+        }
+        // A single whitespace character separates the header from the pixels
+        i += 1;
+
+        if fields[0] != "P6" {
+            return Err(format!(
+                "Only binary PPMs (P6) are supported, not {}.",
+                fields[0]
+            ));
+        }
+
+        let number = |field: &String| {
+            return field
+                .parse::<usize>()
+                .map_err(|_| format!("'{}' is not a number.", field));
+        };
+        let (width, height, max_value) = (
+            number(&fields[1])?,
+            number(&fields[2])?,
+            number(&fields[3])?,
+        );
+        if max_value != 255 {
+            return Err(format!(
+                "Only 8 bit channels are supported, not a max of {}.",
+                max_value
+            ));
+        }
+
+        let data = &bytes[i.min(bytes.len())..];
+        if data.len() < width * height * 3 {
+            return Err(format!(
+                "A {}x{} image needs {} bytes of pixels, but there are {}.",
+                width,
+                height,
+                width * height * 3,
+                data.len()
+            ));
+        }
+
+        return Ok(Self {
+            width: width,
+            height: height,
+            pixels: data
+                .chunks(3)
+                .take(width * height)
+                .map(|c| [c[0], c[1], c[2]])
+                .collect(),
+        });
+    }
+
+    /// Encode as an uncompressed PNG. Deflate's stored blocks keep the encoder simple at the cost of file size.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut bytes = PNG_SIGNATURE.to_vec();
+
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bit RGB, with the default compression, filtering and no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_png_chunk(&mut bytes, b"IHDR", &header);
+
+        // Each row starts with its filter type, which is none
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            for pixel in row.iter() {
+                raw.extend_from_slice(pixel);
+            }
+        }
+        write_png_chunk(&mut bytes, b"IDAT", &zlib_stored(&raw));
+
+        write_png_chunk(&mut bytes, b"IEND", &[]);
+
+        return bytes;
+    }
+}
+
+fn write_png_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = bytes.len();
+    bytes.extend_from_slice(kind);
+    bytes.extend_from_slice(data);
+
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wrap the data in a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary
+    let mut bytes = vec![0x78, 0x01];
+
+    let blocks: Vec<&[u8]> = if data.is_empty() {
+        vec![data]
+    } else {
+        data.chunks(MAX_STORED_BLOCK).collect()
+    };
+
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i == blocks.len() - 1;
+        bytes.push(if last { 1 } else { 0 });
+
+        let len = block.len() as u16;
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&(!len).to_le_bytes());
+        bytes.extend_from_slice(block);
+    }
+
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+
+    return bytes;
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    return !crc;
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data.iter() {
+        a = (a + *byte as u32) % MODULO;
+        b = (b + a) % MODULO;
+    }
+
+    return (b << 16) | a;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard() -> Image {
+        let mut image = Image::new(3, 2, [255, 255, 255]);
+        image.set_pixel(0, 0, [10, 20, 30]);
+        image.set_pixel(2, 1, [200, 100, 0]);
+
+        return image;
+    }
+
+    #[test]
+    fn image_ppm_round_trips() {
+        let image = checkerboard();
+
+        assert_eq!(Ok(image.clone()), Image::from_ppm(&image.to_ppm()));
+    }
+
+    #[test]
+    fn image_from_ppm_skips_comments() {
+        let mut bytes = b"P6\n# made by hand\n1 1\n255\n".to_vec();
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        let image = Image::from_ppm(&bytes).unwrap();
+
+        assert_eq!([1, 2, 3], image.pixel(0, 0));
+    }
+
+    #[test]
+    fn image_from_ppm_short_data_returns_err() {
+        let actual = Image::from_ppm(b"P6 2 2 255 abc");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn image_to_png_writes_chunks_with_checksums() {
+        let png = checkerboard().to_png();
+
+        assert_eq!(&PNG_SIGNATURE[..], &png[..8]);
+        assert_eq!(b"IHDR", &png[12..16]);
+        // IEND has no data, so its CRC is always the same
+        assert_eq!(
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82][..],
+            &png[png.len() - 12..]
+        );
+    }
+
+    #[test]
+    fn adler32_matches_reference() {
+        assert_eq!(0x11E6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn image_count_differences_uses_tolerance() {
+        let image = checkerboard();
+        let mut other = image.clone();
+        other.set_pixel(1, 0, [250, 255, 255]);
+        other.set_pixel(1, 1, [100, 255, 255]);
+
+        assert_eq!(Ok(1), image.count_differences(&other, 5));
+        assert!(image
+            .count_differences(&Image::new(1, 1, [0, 0, 0]), 0)
+            .is_err());
+    }
+}
@@ -0,0 +1,837 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+pub mod image;
+pub mod shadow_map;
+
+/*
+    A renderer that rasterizes on the CPU, so frames can be drawn and checked where there is no GPU. It follows the
+    OpenGL backend's conventions: counter-clockwise triangles face the camera, back faces are culled, depth is tested
+    with less than, and sprites are drawn over the scene without depth.
+
+    Triangles are clipped against the near plane, then drawn with perspective correct interpolation. Meshes are lit
+    by the scene's lighting, either once per triangle (flat) or once per vertex with the colors interpolated
+    (Gouraud). Shadows and fog are worked out for each pixel from its world position.
+
+    Meshes aren't drawn until the sprites or the present, as the sun's shadow map needs every mesh in the frame.
+*/
+
+extern crate nalgebra as na;
+use na::{Matrix4, Vector3, Vector4};
+
+use crate::cb_graphics;
+use cb_graphics::frustum::Bounds;
+use cb_graphics::lighting::Lighting;
+use cb_graphics::mesh::Mesh;
+use cb_graphics::renderer::{Material, MeshId, Renderer, Sprite};
+use cb_graphics::CbCamera;
+
+use image::Image;
+use shadow_map::ShadowMap;
+
+/// The color the frame is cleared to, matching the OpenGL backend.
+const CLEAR_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+/// The width and height of the sun's shadow map, in texels.
+const SHADOW_MAP_SIZE: usize = 256;
+
+/// Points closer to the camera than this in clip space are clipped, to avoid dividing by zero.
+const NEAR_W: f32 = 0.0001;
+
+/// How lighting is calculated across a triangle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Shading {
+    /// A single color for the whole triangle, lit by its face normal.
+    Flat,
+    /// Each vertex is lit by its normal, and the colors are interpolated across the triangle.
+    Gouraud,
+}
+
+/// A vertex after it has been transformed and lit. The sun's light is kept apart from the rest so it can be
+/// shadowed.
+#[derive(Debug, Copy, Clone)]
+struct ClipVertex {
+    position: Vector4<f32>,
+    world: Vector3<f32>,
+    color: Vector3<f32>,
+    sun: Vector3<f32>,
+}
+
+pub struct SoftwareRenderer {
+    width: usize,
+    height: usize,
+    pub shading: Shading,
+    color_buffer: Vec<Vector3<f32>>,
+    depth_buffer: Vec<f32>,
+    meshes: Vec<Option<Mesh>>,
+    proj_view: Matrix4<f32>,
+    eye: Vector3<f32>,
+    lighting: Lighting,
+    /// The meshes drawn since the last flush, as (id, transform, material).
+    queued: Vec<(MeshId, Matrix4<f32>, Material)>,
+    shadow_map: Option<ShadowMap>,
+    /// The last frame that was presented.
+    frame: Image,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: usize, height: usize, shading: Shading) -> Self {
+        let mut renderer = Self {
+            width: width,
+            height: height,
+            shading: shading,
+            color_buffer: vec![],
+            depth_buffer: vec![],
+            meshes: vec![],
+            proj_view: Matrix4::identity(),
+            eye: Vector3::zeros(),
+            lighting: Lighting::default(),
+            queued: vec![],
+            shadow_map: None,
+            frame: Image::new(width, height, to_rgb(&Vector3::from(CLEAR_COLOR))),
+        };
+        renderer.clear();
+
+        return renderer;
+    }
+
+    /// The last frame that was presented.
+    pub fn frame(&self) -> &Image {
+        return &self.frame;
+    }
+
+    fn clear(&mut self) {
+        self.color_buffer = vec![Vector3::from(CLEAR_COLOR); self.width * self.height];
+        self.depth_buffer = vec![std::f32::INFINITY; self.width * self.height];
+    }
+
+    /// Transform and light the vertex. The normal is in world space.
+    fn clip_vertex(
+        &self,
+        transform: &Matrix4<f32>,
+        position: Vector3<f32>,
+        color: Vector3<f32>,
+        normal: Option<Vector3<f32>>,
+        material: &Material,
+    ) -> ClipVertex {
+        let world = transform * position.push(1.0);
+        let color = color.component_mul(&Vector3::from(material.color));
+
+        // Vertices without a normal aren't lit
+        let (light, sun) = match normal {
+            Some(normal) => self.lighting.light(&world.xyz(), &normal),
+            None => (Vector3::new(1.0, 1.0, 1.0), Vector3::zeros()),
+        };
+
+        return ClipVertex {
+            position: self.proj_view * world,
+            world: world.xyz(),
+            color: color.component_mul(&light),
+            sun: color.component_mul(&sun),
+        };
+    }
+
+    /// Draw the queued meshes, after drawing them into the sun's shadow map if it casts shadows.
+    fn flush(&mut self) {
+        let queued = std::mem::replace(&mut self.queued, vec![]);
+        if queued.is_empty() {
+            return;
+        }
+
+        self.shadow_map = if self.lighting.sun.shadows {
+            self.render_shadow_map(&queued)
+        } else {
+            None
+        };
+
+        for (id, transform, material) in queued.iter() {
+            self.draw_queued(*id, transform, material);
+        }
+    }
+
+    /// The depth of the meshes from the sun, fitted around them. None if there is nothing to cast a shadow.
+    fn render_shadow_map(&self, queued: &[(MeshId, Matrix4<f32>, Material)]) -> Option<ShadowMap> {
+        let meshes: Vec<(&Mesh, &Matrix4<f32>)> = queued
+            .iter()
+            .filter_map(|(id, transform, _)| match self.meshes.get(*id) {
+                Some(Some(mesh)) => Some((mesh, transform)),
+                _ => None,
+            })
+            .collect();
+
+        let bounds = meshes
+            .iter()
+            .filter_map(|(mesh, transform)| {
+                Bounds::from_mesh(mesh).map(|bounds| bounds.transformed(transform))
+            })
+            .fold(None, |all: Option<Bounds>, bounds| match all {
+                Some(all) => Some(Bounds::new(
+                    all.min.zip_map(&bounds.min, f32::min),
+                    all.max.zip_map(&bounds.max, f32::max),
+                )),
+                None => Some(bounds),
+            })?;
+
+        let mut shadow_map = ShadowMap::new(self.lighting.sun_matrix(&bounds), SHADOW_MAP_SIZE);
+        for (mesh, transform) in meshes.iter() {
+            for triangle in mesh.indices.chunks(3) {
+                if triangle.len() < 3 {
+                    break;
+                }
+
+                let mut corners = [Vector3::zeros(); 3];
+                for (i, index) in triangle.iter().enumerate() {
+                    let position = attribute(&mesh.vertices, mesh.vertex_size, *index as usize)
+                        .unwrap_or(Vector3::zeros());
+                    corners[i] = (*transform * position.push(1.0)).xyz();
+                }
+
+                shadow_map.draw_triangle(&corners);
+            }
+        }
+
+        return Some(shadow_map);
+    }
+
+    fn draw_queued(&mut self, id: MeshId, transform: &Matrix4<f32>, material: &Material) {
+        let mesh = match self.meshes.get(id) {
+            Some(Some(mesh)) => mesh.clone(),
+            _ => return,
+        };
+
+        for triangle in mesh.indices.chunks(3) {
+            if triangle.len() < 3 {
+                break;
+            }
+
+            let mut positions = [Vector3::zeros(); 3];
+            for (i, index) in triangle.iter().enumerate() {
+                positions[i] = attribute(&mesh.vertices, mesh.vertex_size, *index as usize)
+                    .unwrap_or(Vector3::zeros());
+            }
+
+            let face_normal = {
+                let world = |p: &Vector3<f32>| (transform * p.push(1.0)).xyz();
+                let (a, b, c) = (
+                    world(&positions[0]),
+                    world(&positions[1]),
+                    world(&positions[2]),
+                );
+                (b - a).cross(&(c - a)).try_normalize(std::f32::EPSILON)
+            };
+
+            let mut vertices = [ClipVertex {
+                position: Vector4::zeros(),
+                world: Vector3::zeros(),
+                color: Vector3::zeros(),
+                sun: Vector3::zeros(),
+            }; 3];
+            for (i, index) in triangle.iter().enumerate() {
+                let index = *index as usize;
+
+                let color = attribute(&mesh.colors, mesh.color_vertex_size, index)
+                    .unwrap_or(Vector3::new(1.0, 1.0, 1.0));
+                let normal = match self.shading {
+                    Shading::Flat => face_normal,
+                    Shading::Gouraud => attribute(&mesh.normals, mesh.normal_vertex_size, index)
+                        .and_then(|n| {
+                            transform
+                                .transform_vector(&n)
+                                .try_normalize(std::f32::EPSILON)
+                        })
+                        .or(face_normal),
+                };
+
+                vertices[i] = self.clip_vertex(transform, positions[i], color, normal, material);
+            }
+
+            self.draw_triangle(vertices);
+        }
+    }
+
+    fn draw_triangle(&mut self, vertices: [ClipVertex; 3]) {
+        for triangle in clip_near(vertices).iter() {
+            self.rasterize(triangle);
+        }
+    }
+
+    fn rasterize(&mut self, vertices: &[ClipVertex; 3]) {
+        // Perspective divide, keeping 1/w to interpolate with
+        let mut screen = [(0.0, 0.0, 0.0, 0.0); 3];
+        for (i, vertex) in vertices.iter().enumerate() {
+            let inverse_w = 1.0 / vertex.position.w;
+            let ndc = vertex.position.xyz() * inverse_w;
+
+            screen[i] = (
+                (ndc.x + 1.0) * 0.5 * self.width as f32,
+                (1.0 - ndc.y) * 0.5 * self.height as f32,
+                ndc.z,
+                inverse_w,
+            );
+        }
+
+        // Rows go down the screen, so counter-clockwise triangles have a negative area
+        let edge = |a: (f32, f32, f32, f32), b: (f32, f32, f32, f32), x: f32, y: f32| {
+            return (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0);
+        };
+        let area = edge(screen[0], screen[1], screen[2].0, screen[2].1);
+        if area >= 0.0 {
+            return;
+        }
+
+        let min_x = screen
+            .iter()
+            .map(|s| s.0)
+            .fold(std::f32::INFINITY, f32::min);
+        let max_x = screen
+            .iter()
+            .map(|s| s.0)
+            .fold(std::f32::NEG_INFINITY, f32::max);
+        let min_y = screen
+            .iter()
+            .map(|s| s.1)
+            .fold(std::f32::INFINITY, f32::min);
+        let max_y = screen
+            .iter()
+            .map(|s| s.1)
+            .fold(std::f32::NEG_INFINITY, f32::max);
+
+        let clamp = |value: f32, size: usize| value.max(0.0).min(size as f32) as usize;
+        let (x0, x1) = (
+            clamp(min_x.floor(), self.width),
+            clamp(max_x.ceil(), self.width),
+        );
+        let (y0, y1) = (
+            clamp(min_y.floor(), self.height),
+            clamp(max_y.ceil(), self.height),
+        );
+
+        let flat_color = (vertices[0].color + vertices[1].color + vertices[2].color) / 3.0;
+        let flat_sun = (vertices[0].sun + vertices[1].sun + vertices[2].sun) / 3.0;
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                // Barycentric weights; each is opposite the vertex it weighs
+                let w0 = edge(screen[1], screen[2], px, py) / area;
+                let w1 = edge(screen[2], screen[0], px, py) / area;
+                let w2 = edge(screen[0], screen[1], px, py) / area;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+
+                let depth = w0 * screen[0].2 + w1 * screen[1].2 + w2 * screen[2].2;
+                let index = x + y * self.width;
+                if depth < -1.0 || depth > 1.0 || depth >= self.depth_buffer[index] {
+                    continue;
+                }
+
+                let (p0, p1, p2) = (w0 * screen[0].3, w1 * screen[1].3, w2 * screen[2].3);
+                let interpolate = |a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>| {
+                    (a * p0 + b * p1 + c * p2) / (p0 + p1 + p2)
+                };
+
+                let (color, sun) = match self.shading {
+                    Shading::Flat => (flat_color, flat_sun),
+                    Shading::Gouraud => (
+                        interpolate(vertices[0].color, vertices[1].color, vertices[2].color),
+                        interpolate(vertices[0].sun, vertices[1].sun, vertices[2].sun),
+                    ),
+                };
+                let world = interpolate(vertices[0].world, vertices[1].world, vertices[2].world);
+
+                let shadowed = match &self.shadow_map {
+                    Some(shadow_map) => shadow_map.shadowed(&world),
+                    None => false,
+                };
+                let mut color = if shadowed { color } else { color + sun };
+
+                if let Some(fog) = self.lighting.fog {
+                    let amount = self.lighting.fog_amount((world - self.eye).norm());
+                    color = color * (1.0 - amount) + Vector3::from(fog.color) * amount;
+                }
+
+                self.depth_buffer[index] = depth;
+                self.color_buffer[index] = color;
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn upload_mesh(&mut self, mesh: &Mesh) -> MeshId {
+        self.meshes.push(Some(mesh.clone()));
+
+        return self.meshes.len() - 1;
+    }
+
+    fn update_mesh(&mut self, id: MeshId, mesh: &Mesh) {
+        self.meshes[id] = Some(mesh.clone());
+    }
+
+    fn delete_mesh(&mut self, id: MeshId) {
+        self.meshes[id] = None;
+    }
+
+    fn set_camera(&mut self, camera: &CbCamera) {
+        // Meshes drawn so far are seen from the camera they were drawn with
+        self.flush();
+
+        let (proj, view) = camera.proj_view();
+        self.proj_view = proj * view;
+        self.eye = Vector3::new(camera.pos_x, camera.pos_y, camera.pos_z);
+    }
+
+    fn set_lighting(&mut self, lighting: &Lighting) {
+        self.flush();
+
+        self.lighting = lighting.clone();
+    }
+
+    fn draw_mesh(&mut self, id: MeshId, transform: &Matrix4<f32>, material: &Material) {
+        self.queued.push((id, *transform, *material));
+    }
+
+    fn draw_sprites(&mut self, sprites: &[Sprite]) {
+        self.flush();
+
+        for sprite in sprites.iter() {
+            let clamp = |value: f32, size: usize| value.round().max(0.0).min(size as f32) as usize;
+
+            let (x0, x1) = (
+                clamp(sprite.position[0], self.width),
+                clamp(sprite.position[0] + sprite.size[0], self.width),
+            );
+            let (y0, y1) = (
+                clamp(sprite.position[1], self.height),
+                clamp(sprite.position[1] + sprite.size[1], self.height),
+            );
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    self.color_buffer[x + y * self.width] = Vector3::from(sprite.color);
+                }
+            }
+        }
+    }
+
+    fn present(&mut self) {
+        self.flush();
+
+        let mut frame = Image::new(self.width, self.height, [0, 0, 0]);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                frame.set_pixel(x, y, to_rgb(&self.color_buffer[x + y * self.width]));
+            }
+        }
+
+        self.frame = frame;
+        self.clear();
+    }
+}
+
+fn to_rgb(color: &Vector3<f32>) -> [u8; 3] {
+    let channel = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+
+    return [channel(color.x), channel(color.y), channel(color.z)];
+}
+
+/// The vertex's attribute, padded out to three components. None if the mesh doesn't have it.
+fn attribute(values: &Vec<f32>, size: usize, index: usize) -> Option<Vector3<f32>> {
+    if size == 0 || values.len() < (index + 1) * size {
+        return None;
+    }
+
+    let get = |i: usize| {
+        if i < size {
+            values[index * size + i]
+        } else {
+            0.0
+        }
+    };
+    return Some(Vector3::new(get(0), get(1), get(2)));
+}
+
+/// Split the triangle where it crosses the near plane, keeping the part in front of the camera.
+fn clip_near(vertices: [ClipVertex; 3]) -> Vec<[ClipVertex; 3]> {
+    let inside = |v: &ClipVertex| v.position.w > NEAR_W;
+
+    let mut polygon = vec![];
+    for i in 0..3 {
+        let (a, b) = (vertices[i], vertices[(i + 1) % 3]);
+
+        if inside(&a) {
+            polygon.push(a);
+        }
+
+        if inside(&a) != inside(&b) {
+            let t = (NEAR_W - a.position.w) / (b.position.w - a.position.w);
+            polygon.push(ClipVertex {
+                position: a.position + (b.position - a.position) * t,
+                world: a.world + (b.world - a.world) * t,
+                color: a.color + (b.color - a.color) * t,
+                sun: a.sun + (b.sun - a.sun) * t,
+            });
+        }
+    }
+
+    // The clipped polygon is convex, so it fans out from its first vertex
+    return (1..polygon.len().saturating_sub(1))
+        .map(|i| [polygon[0], polygon[i], polygon[i + 1]])
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    extern crate specs;
+    use specs::prelude::*;
+
+    use crate::cb_menu;
+    use crate::cb_menu::EditorComponent;
+    use crate::cb_simulation::components::voxel_components::{
+        VoxelComponent, VoxelComponentsLinker,
+    };
+    use crate::cb_simulation::components::ComponentLinker;
+    use crate::cb_voxels;
+    use cb_graphics::lighting::{Fog, PointLight};
+    use cb_graphics::render_list::RenderList;
+    use cb_graphics::scene_renderer::CbSceneRenderer;
+
+    const GOLDEN_PATH: &'static str = "./src/cb_graphics/software_renderer/golden";
+
+    /// Set to write the rendered images as the new golden images instead of comparing against them.
+    const UPDATE_GOLDEN_VAR: &'static str = "CB_UPDATE_GOLDEN";
+
+    /// Compare the frame to its golden image, allowing for small differences in floating point math. New golden images
+    /// are only written when updating, to be checked by eye and committed.
+    fn assert_golden(name: &str, frame: &Image) {
+        let path = PathBuf::from(GOLDEN_PATH).join(format!("{}.ppm", name));
+
+        if std::env::var(UPDATE_GOLDEN_VAR).is_ok() {
+            frame.save(&path).unwrap();
+        }
+
+        if !path.exists() {
+            let actual = std::env::temp_dir().join(format!("{}_actual.png", name));
+            frame.save(&actual).unwrap();
+
+            panic!(
+                "{} is missing; the frame was saved to {}. Set {} to write it.",
+                path.display(),
+                actual.display(),
+                UPDATE_GOLDEN_VAR
+            );
+        }
+
+        let golden = Image::from_path(&path).unwrap();
+        let differences = frame.count_differences(&golden, 2).unwrap();
+        if differences > frame.width() * frame.height() / 500 {
+            let actual = std::env::temp_dir().join(format!("{}_actual.png", name));
+            frame.save(&actual).unwrap();
+
+            panic!(
+                "{} pixels differ from {}; the frame was saved to {}. Set {} to update it.",
+                differences,
+                path.display(),
+                actual.display(),
+                UPDATE_GOLDEN_VAR
+            );
+        }
+    }
+
+    fn square(color: [f32; 3]) -> Mesh {
+        return Mesh::new(
+            3,
+            vec![
+                -1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0, 1.0, 0.0, -1.0, 1.0, 0.0,
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+            3,
+            [color; 4].concat(),
+            3,
+            [[0.0, 0.0, 1.0]; 4].concat(),
+            0,
+        );
+    }
+
+    /// A camera looking down at the origin from above.
+    fn overhead_camera(orthographic: bool) -> CbCamera {
+        let mut camera = CbCamera::new(64.0, 48.0);
+        camera.orthographic_view = orthographic;
+        camera.pos_x = 0.0;
+        camera.pos_y = 0.0;
+        camera.pos_z = 5.0;
+        camera.target_z = 0.0;
+
+        return camera;
+    }
+
+    fn render_square(camera: &CbCamera, transform: Matrix4<f32>) -> Image {
+        let mut renderer = SoftwareRenderer::new(64, 48, Shading::Flat);
+        let id = renderer.upload_mesh(&square([1.0, 0.0, 0.0]));
+
+        renderer.set_camera(camera);
+        renderer.draw_mesh(id, &transform, &Material::default());
+        renderer.present();
+
+        return renderer.frame().clone();
+    }
+
+    fn is_white(pixel: [u8; 3]) -> bool {
+        return pixel == [255, 255, 255];
+    }
+
+    /// A red square floating a unit above a larger blue one, lit by the lighting.
+    fn render_floating_square(lighting: &Lighting) -> Image {
+        let mut renderer = SoftwareRenderer::new(64, 48, Shading::Flat);
+        let red = renderer.upload_mesh(&square([1.0, 0.0, 0.0]));
+        let blue = renderer.upload_mesh(&square([0.0, 0.0, 1.0]));
+
+        renderer.set_camera(&overhead_camera(false));
+        renderer.set_lighting(lighting);
+        let floating = Matrix4::new_translation(&Vector3::new(0.0, 0.0, 1.0))
+            * Matrix4::new_nonuniform_scaling(&Vector3::repeat(0.5));
+        renderer.draw_mesh(red, &floating, &Material::default());
+        let ground = Matrix4::new_nonuniform_scaling(&Vector3::repeat(4.0));
+        renderer.draw_mesh(blue, &ground, &Material::default());
+        renderer.present();
+
+        return renderer.frame().clone();
+    }
+
+    /// The pixel the world position is drawn at by the overhead camera.
+    fn overhead_pixel(position: Vector3<f32>) -> (usize, usize) {
+        let (proj, view) = overhead_camera(false).proj_view();
+        let ndc = (proj * view).transform_point(&na::Point3::from(position));
+
+        return (
+            ((ndc.x + 1.0) * 0.5 * 64.0) as usize,
+            ((1.0 - ndc.y) * 0.5 * 48.0) as usize,
+        );
+    }
+
+    #[test]
+    fn software_renderer_shadows_surfaces_the_sun_is_blocked_from() {
+        let mut lighting = Lighting::default();
+        lighting.sun.direction = [1.0, 0.0, 1.0];
+        lighting.sun.shadows = true;
+
+        let frame = render_floating_square(&lighting);
+
+        // The shadow falls a unit away from the sun
+        let (x, y) = overhead_pixel(Vector3::new(-1.0, 0.0, 0.0));
+        let (lit_x, lit_y) = overhead_pixel(Vector3::new(1.0, 0.0, 0.0));
+        assert!(frame.pixel(x, y)[2] < 100);
+        assert!(frame.pixel(lit_x, lit_y)[2] > 180);
+
+        // Without shadows, it's lit
+        lighting.sun.shadows = false;
+        let frame = render_floating_square(&lighting);
+        assert_eq!(frame.pixel(lit_x, lit_y), frame.pixel(x, y));
+    }
+
+    #[test]
+    fn software_renderer_fog_hides_distant_surfaces() {
+        let mut lighting = Lighting::default();
+        lighting.fog = Some(Fog {
+            color: [0.0, 1.0, 0.0],
+            start: 1.0,
+            end: 2.0,
+        });
+
+        let frame = render_floating_square(&lighting);
+
+        // The camera is further than the fog's end from everything
+        let (x, y) = overhead_pixel(Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!([0, 255, 0], frame.pixel(x, y));
+        assert_eq!([0, 255, 0], frame.pixel(32, 24));
+    }
+
+    #[test]
+    fn software_renderer_draws_facing_triangles_in_front_of_camera() {
+        let frame = render_square(&overhead_camera(false), Matrix4::identity());
+
+        let center = frame.pixel(32, 24);
+        assert!(center[0] > 200 && center[1] == 0 && center[2] == 0);
+        assert!(is_white(frame.pixel(0, 0)));
+    }
+
+    #[test]
+    fn software_renderer_culls_back_faces() {
+        let flipped = Matrix4::from_euler_angles(std::f32::consts::PI, 0.0, 0.0);
+        let frame = render_square(&overhead_camera(false), flipped);
+
+        assert!(is_white(frame.pixel(32, 24)));
+    }
+
+    #[test]
+    fn software_renderer_clips_triangles_behind_camera() {
+        let mut camera = overhead_camera(false);
+        camera.pos_z = 0.5;
+        let huge = Matrix4::new_scaling(1000.0);
+
+        let frame = render_square(&camera, huge);
+
+        assert!(!is_white(frame.pixel(0, 0)));
+        assert!(!is_white(frame.pixel(63, 47)));
+    }
+
+    #[test]
+    fn software_renderer_depth_test_keeps_nearest() {
+        let mut renderer = SoftwareRenderer::new(64, 48, Shading::Flat);
+        let red = renderer.upload_mesh(&square([1.0, 0.0, 0.0]));
+        let blue = renderer.upload_mesh(&square([0.0, 0.0, 1.0]));
+
+        renderer.set_camera(&overhead_camera(false));
+        let raised = Matrix4::new_translation(&Vector3::new(0.0, 0.0, 1.0));
+        renderer.draw_mesh(red, &raised, &Material::default());
+        renderer.draw_mesh(blue, &Matrix4::identity(), &Material::default());
+        renderer.present();
+
+        let center = renderer.frame().pixel(32, 24);
+        assert!(center[0] > 200 && center[2] == 0);
+    }
+
+    #[test]
+    fn software_renderer_draws_sprites_over_scene() {
+        let mut renderer = SoftwareRenderer::new(64, 48, Shading::Flat);
+        let id = renderer.upload_mesh(&square([1.0, 0.0, 0.0]));
+
+        renderer.set_camera(&overhead_camera(false));
+        renderer.draw_mesh(id, &Matrix4::identity(), &Material::default());
+        renderer.draw_sprites(&[Sprite::new([30.0, 20.0], [4.0, 4.0], [0.0, 1.0, 0.0])]);
+        renderer.present();
+
+        assert_eq!([0, 255, 0], renderer.frame().pixel(31, 21));
+        assert_eq!([0, 255, 0], renderer.frame().pixel(33, 23));
+        assert!(renderer.frame().pixel(34, 24) != [0, 255, 0]);
+    }
+
+    #[test]
+    fn software_renderer_present_clears_for_next_frame() {
+        let mut renderer = SoftwareRenderer::new(8, 8, Shading::Flat);
+        renderer.draw_sprites(&[Sprite::new([0.0, 0.0], [8.0, 8.0], [0.0, 0.0, 0.0])]);
+        renderer.present();
+        renderer.present();
+
+        assert!(is_white(renderer.frame().pixel(4, 4)));
+    }
+
+    /// A few columns and a wall of terrain, with both of the map's colors.
+    fn voxel_scene(lighting: Lighting) -> World {
+        let mut world = World::new();
+        VoxelComponentsLinker::register_components(&mut world);
+
+        let mut voxel = VoxelComponent::new();
+        voxel.chunk_manager = cb_voxels::CbChunkManager::empty(0);
+        let terrain = &mut voxel.chunk_manager;
+        for x in 0..8 {
+            for y in 0..8 {
+                terrain.add_top_voxel(x, y, cb_voxels::VOXEL_TYPE_GRASS, 0);
+            }
+        }
+        for x in 2..6 {
+            for _ in 0..3 {
+                terrain.add_top_voxel(x, 5, cb_voxels::VOXEL_TYPE_DIRT, 0);
+            }
+        }
+        terrain.add_top_voxel(1, 1, cb_voxels::VOXEL_TYPE_DEFAULT, 0);
+
+        world.create_entity().with(voxel).build();
+
+        let mut render_list = RenderList::new();
+        render_list.terrain = Some(na::Matrix4::identity());
+        render_list.lighting = lighting;
+        world.insert(render_list);
+
+        return world;
+    }
+
+    fn render_voxel_scene(camera: &CbCamera, shading: Shading, lighting: Lighting) -> Image {
+        let world = voxel_scene(lighting);
+        let mut renderer = SoftwareRenderer::new(160, 120, shading);
+        let mut scene = CbSceneRenderer::new();
+
+        scene.render(&mut renderer, camera, &world, 0);
+        renderer.present();
+
+        return renderer.frame().clone();
+    }
+
+    fn voxel_camera() -> CbCamera {
+        let mut camera = CbCamera::new(160.0, 120.0);
+        camera.pos_x = 0.0;
+        camera.pos_y = -2.0;
+        camera.pos_z = 6.0;
+        camera.target_x = 4.0;
+        camera.target_y = 4.0;
+        camera.target_z = 1.0;
+
+        return camera;
+    }
+
+    #[test]
+    fn golden_voxel_scene_perspective_gouraud() {
+        let frame = render_voxel_scene(&voxel_camera(), Shading::Gouraud, Lighting::default());
+
+        assert_golden("voxel_scene_perspective_gouraud", &frame);
+    }
+
+    #[test]
+    fn golden_voxel_scene_orthographic_flat() {
+        let mut camera = voxel_camera();
+        camera.orthographic_view = true;
+
+        let frame = render_voxel_scene(&camera, Shading::Flat, Lighting::default());
+
+        assert_golden("voxel_scene_orthographic_flat", &frame);
+    }
+
+    #[test]
+    fn golden_voxel_scene_sun_shadows() {
+        let mut lighting = Lighting::default();
+        lighting.sun.shadows = true;
+
+        let frame = render_voxel_scene(&voxel_camera(), Shading::Gouraud, lighting);
+
+        assert_golden("voxel_scene_sun_shadows", &frame);
+    }
+
+    #[test]
+    fn golden_voxel_scene_point_lights_and_fog() {
+        let mut lighting = Lighting::default();
+        lighting.ambient = 0.1;
+        lighting.sun.color = [0.3, 0.3, 0.4];
+        lighting.point_lights.push(PointLight {
+            position: [2.0, 2.0, 2.0],
+            color: [1.0, 0.3, 0.1],
+            radius: 4.0,
+        });
+        lighting.fog = Some(Fog {
+            color: [0.6, 0.6, 0.7],
+            start: 4.0,
+            end: 12.0,
+        });
+
+        let frame = render_voxel_scene(&voxel_camera(), Shading::Gouraud, lighting);
+
+        assert_golden("voxel_scene_point_lights_and_fog", &frame);
+    }
+
+    #[test]
+    fn golden_voxel_editor() {
+        let mut gui = cb_menu::GuiEnvironment::new(160, 120);
+        let mut voxel = VoxelComponent::new();
+        gui.add_form(voxel.init_editor());
+
+        let mut renderer = SoftwareRenderer::new(160, 120, Shading::Flat);
+        renderer.draw_sprites(&cb_graphics::menu_sprites(&gui.draw()));
+        renderer.present();
+
+        assert_golden("voxel_editor", renderer.frame());
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+use na::{Matrix4, Point3, Vector3};
+
+/// How much further from the sun than the shadow map a surface has to be to be shadowed, in normalized depth. Keeps
+/// surfaces from shadowing themselves where the map's texels are coarser than the surface.
+const SHADOW_BIAS: f32 = 0.01;
+
+/// The depth of the scene as seen from the sun, to tell which surfaces something else blocks it from.
+pub struct ShadowMap {
+    /// The sun's projection * view.
+    matrix: Matrix4<f32>,
+    size: usize,
+    depth: Vec<f32>,
+}
+
+impl ShadowMap {
+    pub fn new(matrix: Matrix4<f32>, size: usize) -> Self {
+        return Self {
+            matrix: matrix,
+            size: size,
+            depth: vec![std::f32::INFINITY; size * size],
+        };
+    }
+
+    /// Where the world space position falls in the map, as (x, y, depth).
+    fn project(&self, position: &Vector3<f32>) -> (f32, f32, f32) {
+        let ndc = self.matrix.transform_point(&Point3::from(*position));
+
+        return (
+            (ndc.x + 1.0) * 0.5 * self.size as f32,
+            (1.0 - ndc.y) * 0.5 * self.size as f32,
+            ndc.z,
+        );
+    }
+
+    /// Draw the depth of a triangle with corners in world space. Both faces are drawn, so thin and open meshes still
+    /// cast shadows.
+    pub fn draw_triangle(&mut self, corners: &[Vector3<f32>; 3]) {
+        let screen = [
+            self.project(&corners[0]),
+            self.project(&corners[1]),
+            self.project(&corners[2]),
+        ];
+
+        let edge = |a: (f32, f32, f32), b: (f32, f32, f32), x: f32, y: f32| {
+            return (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0);
+        };
+        let area = edge(screen[0], screen[1], screen[2].0, screen[2].1);
+        if area == 0.0 {
+            return;
+        }
+
+        let clamp = |value: f32| value.max(0.0).min(self.size as f32) as usize;
+        let min = |f: fn(&(f32, f32, f32)) -> f32| {
+            screen.iter().map(f).fold(std::f32::INFINITY, f32::min)
+        };
+        let max = |f: fn(&(f32, f32, f32)) -> f32| {
+            screen.iter().map(f).fold(std::f32::NEG_INFINITY, f32::max)
+        };
+        let (x0, x1) = (clamp(min(|s| s.0).floor()), clamp(max(|s| s.0).ceil()));
+        let (y0, y1) = (clamp(min(|s| s.1).floor()), clamp(max(|s| s.1).ceil()));
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                // Dividing by the signed area makes the weights positive inside whichever way the triangle faces
+                let w0 = edge(screen[1], screen[2], px, py) / area;
+                let w1 = edge(screen[2], screen[0], px, py) / area;
+                let w2 = edge(screen[0], screen[1], px, py) / area;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+
+                let depth = w0 * screen[0].2 + w1 * screen[1].2 + w2 * screen[2].2;
+                let index = x + y * self.size;
+                if depth < self.depth[index] {
+                    self.depth[index] = depth;
+                }
+            }
+        }
+    }
+
+    /// Whether something nearer to the sun covers the world space position. Positions outside the map aren't.
+    pub fn shadowed(&self, position: &Vector3<f32>) -> bool {
+        let (x, y, depth) = self.project(position);
+        if x < 0.0 || y < 0.0 || x >= self.size as f32 || y >= self.size as f32 {
+            return false;
+        }
+
+        return depth > self.depth[x as usize + y as usize * self.size] + SHADOW_BIAS;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cb_graphics;
+    use cb_graphics::frustum::Bounds;
+    use cb_graphics::lighting::Lighting;
+
+    #[test]
+    fn shadow_map_shadows_only_what_is_behind_a_triangle() {
+        let mut lighting = Lighting::default();
+        lighting.sun.direction = [0.0, 0.0, 1.0];
+        let bounds = Bounds::new(Vector3::new(-2.0, -2.0, 0.0), Vector3::new(2.0, 2.0, 2.0));
+        let mut shadow_map = ShadowMap::new(lighting.sun_matrix(&bounds), 64);
+
+        shadow_map.draw_triangle(&[
+            Vector3::new(-1.0, -1.0, 1.0),
+            Vector3::new(1.0, -1.0, 1.0),
+            Vector3::new(-1.0, 1.0, 1.0),
+        ]);
+
+        assert!(shadow_map.shadowed(&Vector3::new(-0.5, -0.5, 0.0)));
+        // On the triangle itself, above it, and beside it
+        assert!(!shadow_map.shadowed(&Vector3::new(-0.5, -0.5, 1.0)));
+        assert!(!shadow_map.shadowed(&Vector3::new(-0.5, -0.5, 1.5)));
+        assert!(!shadow_map.shadowed(&Vector3::new(0.9, 0.9, 0.0)));
+        assert!(!shadow_map.shadowed(&Vector3::new(10.0, 0.0, 0.0)));
+    }
+}
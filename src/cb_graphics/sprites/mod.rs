@@ -2,14 +2,25 @@ use std::mem;
 extern crate gl;
 use super::*;
 use open_gl_backend::render_gl;
-use std::ffi::CString;
+
+use crate::cb_assets;
+use cb_assets::AssetManager;
+
+use super::renderer::Sprite;
+
 extern crate nalgebra as na;
 use na::{Isometry3, Perspective3, Point3, Vector3};
 
+const SPRITE_VERTEX_SHADER_PATH: &'static str = "cb_graphics/sprites/sprites.vert";
+const SPRITE_FRAGMENT_SHADER_PATH: &'static str = "cb_graphics/sprites/sprites.frag";
+
 pub trait SpriteRenderer {
-    fn new() -> Self;
+    fn new(assets: &AssetManager) -> Self;
+    /// Pick up reloaded shaders.
+    fn update(&mut self);
     fn batch(&mut self);
-    fn render(&self);
+    /// Draw the sprites over the scene, for a window of the size in pixels.
+    fn render(&self, sprites: &[Sprite], window_size: (f32, f32));
 }
 
 pub struct CbSpriteRenderer {
@@ -17,37 +28,25 @@ pub struct CbSpriteRenderer {
     vbo: gl::types::GLuint,
     model_uniform_id: i32,
     color_uniform_id: i32,
-    sprite_program: open_gl_backend::render_gl::Program,
+    projection_uniform_id: i32,
+    sprite_program: render_gl::ShaderProgram,
 }
 
 impl SpriteRenderer for CbSpriteRenderer {
-    fn new() -> Self {
-        let sprite_program;
-        {
-            let vert_shader = render_gl::Shader::from_vert_source(
-                &CString::new(include_str!("sprites.vert")).unwrap(),
-            )
-            .unwrap();
-
-            let frag_shader = render_gl::Shader::from_frag_source(
-                &CString::new(include_str!("sprites.frag")).unwrap(),
-            )
-            .unwrap();
-            sprite_program = render_gl::Program::from_shaders(&[vert_shader, frag_shader]).unwrap();
-            sprite_program.set_used();
-        }
-
-        let model_str = &CString::new("model").unwrap();
-        let model_uniform_id;
-        unsafe {
-            model_uniform_id = gl::GetUniformLocation(sprite_program.id(), model_str.as_ptr());
-        }
+    fn new(assets: &AssetManager) -> Self {
+        let sprite_program = match render_gl::ShaderProgram::load(
+            assets,
+            SPRITE_VERTEX_SHADER_PATH,
+            SPRITE_FRAGMENT_SHADER_PATH,
+        ) {
+            Ok(program) => program,
+            Err(e) => panic!("Unable to load sprite shaders: {}", e),
+        };
+        sprite_program.set_used();
 
-        let color_str = &CString::new("spriteColor").unwrap();
-        let color_uniform_id;
-        unsafe {
-            color_uniform_id = gl::GetUniformLocation(sprite_program.id(), color_str.as_ptr());
-        }
+        let model_uniform_id = sprite_program.uniform_location("model");
+        let color_uniform_id = sprite_program.uniform_location("spriteColor");
+        let projection_uniform_id = sprite_program.uniform_location("projection");
 
         let mut vao: gl::types::GLuint = 0;
         let mut vbo: gl::types::GLuint = 0;
@@ -92,26 +91,61 @@ impl SpriteRenderer for CbSpriteRenderer {
             sprite_program: sprite_program,
             model_uniform_id: model_uniform_id,
             color_uniform_id: color_uniform_id,
+            projection_uniform_id: projection_uniform_id,
         };
     }
 
+    fn update(&mut self) {
+        if self.sprite_program.update() {
+            self.model_uniform_id = self.sprite_program.uniform_location("model");
+            self.color_uniform_id = self.sprite_program.uniform_location("spriteColor");
+            self.projection_uniform_id = self.sprite_program.uniform_location("projection");
+        }
+    }
+
     fn batch(&mut self) {}
-    fn render(&self) {
+    fn render(&self, sprites: &[Sprite], window_size: (f32, f32)) {
         // for reference....
         // https://learnopengl.com/In-Practice/2D-Game/Rendering-Sprites
 
+        if sprites.is_empty() {
+            return;
+        }
+
         self.sprite_program.set_used();
 
-        let x = 10; // replace with component stuff
-        let y = 10; // replace with component stuff
-        let color = Vector3::new(1 as f32, 1 as f32, 1.0);
+        // Pixels from the top left of the window
+        let projection = na::Orthographic3::new(0.0, window_size.0, window_size.1, 0.0, -1.0, 1.0);
+        unsafe {
+            gl::UniformMatrix4fv(
+                self.projection_uniform_id,
+                1,
+                gl::FALSE,
+                projection.as_matrix().as_ptr(),
+            );
+        }
+
+        for sprite in sprites.iter() {
+            self.render_sprite(sprite);
+        }
+    }
+}
+
+impl CbSpriteRenderer {
+    fn render_sprite(&self, sprite: &Sprite) {
+        let color = Vector3::new(sprite.color[0], sprite.color[1], sprite.color[2]);
 
-        let model_pos = Vector3::new(x as f32, y as f32, 0.0);
+        let model_pos = Vector3::new(sprite.position[0], sprite.position[1], 0.0);
         let model_pos = Isometry3::new(model_pos, na::zero());
 
-        //TODO: rotation, scaling
+        //TODO: rotation
 
-        let model = model_pos.to_homogeneous() * na::Matrix4::identity();
+        let model = model_pos.to_homogeneous()
+            * na::Matrix4::new_nonuniform_scaling(&Vector3::new(
+                sprite.size[0],
+                sprite.size[1],
+                1.0,
+            ));
 
         unsafe {
             gl::UniformMatrix4fv(self.model_uniform_id, 1, gl::FALSE, model.as_ptr());
@@ -1,10 +1,12 @@
 extern crate specs;
 use specs::prelude::*;
 
+mod render_list_system;
 mod sprite_render_system;
 
 pub fn gfx_build_dispatcher<'a, 'b>() -> specs::Dispatcher<'a, 'b> {
     return DispatcherBuilder::new()
+        .with(render_list_system::RenderListSystem, "render list", &[])
         .with(
             sprite_render_system::SpriteRenderSystem,
             "sprite render system",
@@ -0,0 +1,292 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate nalgebra as na;
+
+use crate::cb_graphics;
+use cb_graphics::lighting::{Lighting, PointLight};
+use cb_graphics::render_list::{
+    interpolated_transform_matrix, to_world_units, transform_matrix, RenderItem, RenderList,
+};
+
+use crate::cb_simulation;
+use cb_simulation::components::{gfx_components, physics_components, voxel_components};
+use cb_simulation::tick_clock::CbFrameAlpha;
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use specs::prelude::*;
+
+/// Gather the renderable entities and their transforms into the render list for the frame, along with the map's
+/// lighting and the entities' lights.
+pub struct RenderListSystem;
+
+impl<'a> System<'a> for RenderListSystem {
+    type SystemData = (
+        Write<'a, RenderList>,
+        Read<'a, CbFrameAlpha>,
+        Read<'a, Lighting>,
+        ReadStorage<'a, gfx_components::RenderComponent>,
+        ReadStorage<'a, gfx_components::LightComponent>,
+        ReadStorage<'a, gfx_components::InterpolationComponent>,
+        ReadStorage<'a, physics_components::TransformComponent>,
+        ReadStorage<'a, physics_components::RigidBodyComponent>,
+        ReadStorage<'a, voxel_components::VoxelComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut render_list,
+            alpha,
+            lighting,
+            render_components,
+            lights,
+            interpolations,
+            transforms,
+            rigid_bodies,
+            voxels,
+        ): Self::SystemData,
+    ) {
+        render_list.items.clear();
+
+        for (render, transform, rigid_body, interpolation) in (
+            &render_components,
+            &transforms,
+            rigid_bodies.maybe(),
+            interpolations.maybe(),
+        )
+            .join()
+        {
+            if !render.visible {
+                continue;
+            }
+
+            render_list.items.push(RenderItem {
+                model: render.model.clone(),
+                transform: entity_matrix(transform, rigid_body, interpolation, alpha.get()),
+                material: render.material,
+            });
+        }
+
+        render_list.lighting = (*lighting).clone();
+        for (light, transform, rigid_body, interpolation) in (
+            &lights,
+            &transforms,
+            rigid_bodies.maybe(),
+            interpolations.maybe(),
+        )
+            .join()
+        {
+            let matrix = entity_matrix(transform, rigid_body, interpolation, alpha.get());
+            let mut position = matrix.transform_point(&na::Point3::origin()).coords;
+            position.z += to_world_units(light.height);
+
+            render_list.lighting.point_lights.push(PointLight {
+                position: position.into(),
+                color: light.color,
+                radius: to_world_units(light.radius),
+            });
+        }
+
+        let zero = GameUnit::from_num(0);
+
+        // Terrain without a transform stays at the origin
+        render_list.terrain = (&voxels, transforms.maybe())
+            .join()
+            .next()
+            .map(|(_, transform)| match transform {
+                Some(transform) => transform_matrix(transform, zero),
+                None => na::Matrix4::identity(),
+            });
+    }
+}
+
+/// Where to draw an entity. Transforms are flat, so entities with a rigid body are drawn at its height, and entities
+/// with a history are drawn between ticks.
+fn entity_matrix(
+    transform: &physics_components::TransformComponent,
+    rigid_body: Option<&physics_components::RigidBodyComponent>,
+    interpolation: Option<&gfx_components::InterpolationComponent>,
+    alpha: f32,
+) -> na::Matrix4<f32> {
+    return match interpolation {
+        Some(interpolation) => {
+            interpolated_transform_matrix(&interpolation.previous, &interpolation.current, alpha)
+        }
+        None => {
+            let height = rigid_body
+                .map(|r| r.body.position.z)
+                .unwrap_or(GameUnit::from_num(0));
+            transform_matrix(transform, height)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cb_assets;
+    use cb_assets::AssetManager;
+
+    use cb_simulation::components::ComponentLinker;
+
+    use std::path::Path;
+
+    fn world() -> World {
+        let mut world = World::new();
+        gfx_components::GfxComponentsLinker::register_components(&mut world);
+        physics_components::PhysicsComponentsLinker::register_components(&mut world);
+        voxel_components::VoxelComponentsLinker::register_components(&mut world);
+        world.insert(RenderList::new());
+        world.insert(CbFrameAlpha::new(0.0));
+        world.insert(Lighting::default());
+
+        return world;
+    }
+
+    fn model() -> cb_assets::Handle<cb_graphics::models::Model> {
+        // The model never needs to load; only its handle is gathered
+        return AssetManager::new(Path::new("./missing"))
+            .load_in_background(Path::new("model.dae"));
+    }
+
+    #[test]
+    fn render_list_system_gathers_visible_entities_with_transforms() {
+        let mut world = world();
+
+        let mut transform = physics_components::TransformComponent::new();
+        transform.world_position.x = GameUnit::from_num(600);
+        world
+            .create_entity()
+            .with(transform)
+            .with(gfx_components::RenderComponent::new(model()))
+            .build();
+
+        let mut hidden = gfx_components::RenderComponent::new(model());
+        hidden.visible = false;
+        world
+            .create_entity()
+            .with(physics_components::TransformComponent::new())
+            .with(hidden)
+            .build();
+
+        // Without a transform there is nowhere to draw it
+        world
+            .create_entity()
+            .with(gfx_components::RenderComponent::new(model()))
+            .build();
+
+        RenderListSystem.run_now(&world);
+
+        let render_list = world.read_resource::<RenderList>();
+        assert_eq!(1, render_list.items.len());
+        assert_eq!(
+            na::Vector3::new(2.0, 0.0, 0.0),
+            render_list.items[0]
+                .transform
+                .transform_point(&na::Point3::origin())
+                .coords
+        );
+        assert_eq!(None, render_list.terrain);
+    }
+
+    #[test]
+    fn render_list_system_draws_entities_between_ticks() {
+        let mut world = world();
+
+        let mut previous = physics_components::TransformComponent::new();
+        previous.world_position.x = GameUnit::from_num(600);
+        let state =
+            |transform: &physics_components::TransformComponent| gfx_components::TransformState {
+                world_position: transform.world_position,
+                height: GameUnit::from_num(0),
+                rotation: transform.rotation,
+                scale: transform.scale,
+            };
+
+        let mut interpolation = gfx_components::InterpolationComponent::new(state(&previous));
+        let mut current = physics_components::TransformComponent::new();
+        current.world_position.x = GameUnit::from_num(1200);
+        interpolation.push(state(&current));
+
+        world
+            .create_entity()
+            .with(current)
+            .with(interpolation)
+            .with(gfx_components::RenderComponent::new(model()))
+            .build();
+        world.insert(CbFrameAlpha::new(0.5));
+
+        RenderListSystem.run_now(&world);
+
+        let render_list = world.read_resource::<RenderList>();
+        assert_eq!(
+            na::Vector3::new(3.0, 0.0, 0.0),
+            render_list.items[0]
+                .transform
+                .transform_point(&na::Point3::origin())
+                .coords
+        );
+    }
+
+    #[test]
+    fn render_list_system_adds_entity_lights_to_map_lighting() {
+        let mut world = world();
+        let mut map_lighting = Lighting::default();
+        map_lighting.ambient = 0.1;
+        world.insert(map_lighting);
+
+        let mut transform = physics_components::TransformComponent::new();
+        transform.world_position.x = GameUnit::from_num(600);
+        world
+            .create_entity()
+            .with(transform)
+            .with(gfx_components::LightComponent::new(
+                [1.0, 0.5, 0.0],
+                GameUnit::from_num(1500),
+                GameUnit::from_num(300),
+            ))
+            .build();
+
+        RenderListSystem.run_now(&world);
+        RenderListSystem.run_now(&world);
+
+        let render_list = world.read_resource::<RenderList>();
+        assert_eq!(0.1, render_list.lighting.ambient);
+        assert_eq!(
+            vec![PointLight {
+                position: [2.0, 0.0, 1.0],
+                color: [1.0, 0.5, 0.0],
+                radius: 5.0,
+            }],
+            render_list.lighting.point_lights
+        );
+    }
+
+    #[test]
+    fn render_list_system_places_terrain_at_its_transform() {
+        let mut world = world();
+
+        let mut transform = physics_components::TransformComponent::new();
+        transform.world_position.y = GameUnit::from_num(300);
+        world
+            .create_entity()
+            .with(voxel_components::VoxelComponent::new())
+            .with(transform)
+            .build();
+
+        RenderListSystem.run_now(&world);
+        RenderListSystem.run_now(&world);
+
+        let render_list = world.read_resource::<RenderList>();
+        assert!(render_list.items.is_empty());
+        assert_eq!(
+            Some(na::Matrix4::new_translation(&na::Vector3::new(
+                0.0, 1.0, 0.0
+            ))),
+            render_list.terrain
+        );
+    }
+}
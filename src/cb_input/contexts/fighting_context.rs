@@ -23,6 +23,20 @@ pub fn fighting_context_from_bytes() {
                        */
 }
 
+pub fn new_fighting_context() -> CbInputContexts {
+    return CbInputContexts::FightingContext {
+        networked: Networked::On,
+        up: State::Off,
+        down: State::Off,
+        left: State::Off,
+        right: State::Off,
+        punch_light: Press::NotPressed,
+        punch_heavy: Press::NotPressed,
+        kick_light: Press::NotPressed,
+        kick_heavy: Press::NotPressed,
+    };
+}
+
 pub fn get_fighting_context_from_keys(
     events: &Vec<sdl2::event::Event>,
     previous_context: &CbInputContexts,
@@ -43,15 +57,21 @@ pub fn get_fighting_context_from_keys(
     match previous_context {
         CbInputContexts::FightingContext {
             networked: _,
-            up: _,
-            down: _,
-            left: _,
-            right: _,
+            up,
+            down,
+            left,
+            right,
             punch_light: _,
             punch_heavy: _,
             kick_light: _,
             kick_heavy: _,
         } => {
+            // Held directions stay on until they are released
+            new_up = *up;
+            new_down = *down;
+            new_left = *left;
+            new_right = *right;
+
             // TODO: make this a configurable thing
             // State keycodes
             let up_keycodes = vec![Keycode::Space];
@@ -146,18 +166,8 @@ pub fn get_fighting_context_from_keys(
                 }
             }
 
-            // SOCD cleaning, using standards where L + R = neutral, U + D = U
-            {
-                if new_left == State::On && new_right == State::On {
-                    new_left = State::Off;
-                    new_right = State::Off;
-                }
-
-                if new_down == State::On && new_up == State::On {
-                    new_down = State::Off;
-                    new_up = State::On;
-                }
-            }
+            // SOCD cleaning is done by the simulation when it reads the inputs, so the raw held directions are sent and
+            // clients cannot send illegal combinations
         }
         _ => {}
     }
@@ -12,6 +12,7 @@ use super::*;
 use input_type::{Press, Range, State};
 
 pub mod fighting_context;
+pub mod rts_command_context;
 pub mod rts_context;
 pub mod shooter_context;
 pub mod voxel_editor_context;
@@ -60,6 +61,8 @@ pub enum CbInputContexts {
         move_right: State,
         look_x: Range,
         look_y: Range,
+        fire: Press,
+        fire_secondary: Press,
     },
     VoxelEditorContext {
         networked: Networked,
@@ -75,9 +78,19 @@ pub enum CbInputContexts {
         rotate_camera_down: Press,
         rotate_camera_left: Press,
         rotate_camera_right: Press,
+        pan_camera_forward: Press,
+        pan_camera_backward: Press,
+        pan_camera_left: Press,
+        pan_camera_right: Press,
+        zoom_camera_in: Press,
+        zoom_camera_out: Press,
         add_voxel: Press,
         remove_voxel: Press,
     },
+    RtsCommandContext {
+        networked: Networked,
+        commands: [rts_command_context::CbRtsCommand; rts_command_context::MAX_RTS_COMMANDS],
+    },
 }
 
 pub type ContextId = u8;
@@ -96,6 +109,7 @@ pub const FIGHTING_CONTEXT_ID: ContextId = EMPTY_CONTENTS + 1;
 pub const RTS_CONTEXT_ID: ContextId = FIGHTING_CONTEXT_ID + 1;
 pub const SHOOTER_CONTEXT_ID: ContextId = RTS_CONTEXT_ID + 1;
 pub const VOXEL_EDITOR_CONTEXT_ID: ContextId = SHOOTER_CONTEXT_ID + 1;
+pub const RTS_COMMAND_CONTEXT_ID: ContextId = VOXEL_EDITOR_CONTEXT_ID + 1;
 
 //END NOTE
 
@@ -136,6 +150,8 @@ pub fn get_context_id_from_context(context: CbInputContexts) -> ContextId {
             move_right: _,
             look_x: _,
             look_y: _,
+            fire: _,
+            fire_secondary: _,
         } => SHOOTER_CONTEXT_ID,
         CbInputContexts::VoxelEditorContext {
             networked: _,
@@ -151,9 +167,19 @@ pub fn get_context_id_from_context(context: CbInputContexts) -> ContextId {
             rotate_camera_down: _,
             rotate_camera_left: _,
             rotate_camera_right: _,
+            pan_camera_forward: _,
+            pan_camera_backward: _,
+            pan_camera_left: _,
+            pan_camera_right: _,
+            zoom_camera_in: _,
+            zoom_camera_out: _,
             add_voxel: _,
             remove_voxel: _,
         } => VOXEL_EDITOR_CONTEXT_ID,
+        CbInputContexts::RtsCommandContext {
+            networked: _,
+            commands: _,
+        } => RTS_COMMAND_CONTEXT_ID,
     }
 }
 
@@ -216,8 +242,22 @@ impl CbContextManager {
         return None;
     }
 
+    /// Add the context, replacing any context of the same type. If all slots are taken the context is dropped.
     pub fn add_context(&mut self, context: CbInputContexts) {
-        self.contexts[0] = Some(context);
+        let context_id = get_context_id_from_context(context);
+
+        let existing = self
+            .contexts
+            .iter()
+            .position(|c| c.is_some() && get_context_id_from_context(c.unwrap()) == context_id);
+        let free = self.contexts.iter().position(|c| c.is_none());
+
+        match existing.or(free) {
+            Some(i) => {
+                self.contexts[i] = Some(context);
+            }
+            None => {}
+        }
     }
 
     pub fn to_bits(&self) -> Vec<u8> {
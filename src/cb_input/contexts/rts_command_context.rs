@@ -0,0 +1,421 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use super::*;
+
+/// The most commands a player may issue in a single frame.
+pub const MAX_RTS_COMMANDS: usize = 4;
+
+/// A network friendly reference to a unit; the index of its entity. Entities are created in the same order on every peer.
+pub type UnitId = u32;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CbAbilityTarget {
+    None,
+    /// A position in game units.
+    Point {
+        x: i32,
+        y: i32,
+    },
+    Unit(UnitId),
+}
+
+/// Orders issued to units, as opposed to the raw button presses of the RtsContext.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CbRtsCommand {
+    None,
+    Harvest {
+        worker: UnitId,
+        node: UnitId,
+    },
+    Train {
+        structure: UnitId,
+        unit_type: usize,
+    },
+    CancelTraining {
+        structure: UnitId,
+    },
+    /// What the RtsContext's activate_ability resolves to; uses the ability in the selected unit's given slot.
+    ActivateAbility {
+        unit: UnitId,
+        slot: u8,
+        target: CbAbilityTarget,
+    },
+    /// Positions are in game units.
+    Move {
+        unit: UnitId,
+        x: i32,
+        y: i32,
+    },
+    AttackMove {
+        unit: UnitId,
+        x: i32,
+        y: i32,
+    },
+    /// Mark the issuing player as ready, or not, while the match is in the lobby.
+    SetReady {
+        ready: bool,
+    },
+}
+
+pub fn new_rts_command_context() -> CbInputContexts {
+    return CbInputContexts::RtsCommandContext {
+        networked: Networked::On,
+        commands: [CbRtsCommand::None; MAX_RTS_COMMANDS],
+    };
+}
+
+/// Add the command to the first free slot of the context. Returns false if the context is full.
+pub fn push_rts_command(context: &mut CbInputContexts, command: CbRtsCommand) -> bool {
+    match context {
+        CbInputContexts::RtsCommandContext {
+            networked: _,
+            commands,
+        } => {
+            for slot in commands.iter_mut() {
+                if *slot == CbRtsCommand::None {
+                    *slot = command;
+                    return true;
+                }
+            }
+
+            return false;
+        }
+        _ => {
+            return false;
+        }
+    }
+}
+
+/// Returns the commands in the given context, skipping empty slots.
+pub fn get_rts_commands(context: &CbInputContexts) -> Vec<CbRtsCommand> {
+    match context {
+        CbInputContexts::RtsCommandContext {
+            networked: _,
+            commands,
+        } => {
+            return commands
+                .iter()
+                .filter(|c| **c != CbRtsCommand::None)
+                .copied()
+                .collect();
+        }
+        _ => {
+            return vec![];
+        }
+    }
+}
+
+/// The slot of the ability the RtsContext's activate_ability uses.
+pub const ACTIVATE_ABILITY_SLOT: u8 = 0;
+
+/// A unit under the local player's cursor.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CbRtsTargetUnit {
+    pub id: UnitId,
+    /// Whether the local player owns the unit.
+    pub owned: bool,
+    pub is_resource_node: bool,
+}
+
+/// What the local player's RTS presses act on, looked up from the world each frame.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct CbRtsTarget {
+    /// Whether the match is waiting in the lobby for players to be ready.
+    pub in_lobby: bool,
+    /// Where the cursor points on the ground, in game units.
+    pub point: Option<(i32, i32)>,
+    pub unit: Option<CbRtsTargetUnit>,
+}
+
+/// Resolve the presses of the RtsContext into commands for the selected unit, updating the selection.
+/// In the lobby, select readies the player up and cancel takes it back.
+pub fn get_rts_command_context_from_rts_context(
+    rts_context: &CbInputContexts,
+    target: &CbRtsTarget,
+    selected_unit: &mut Option<UnitId>,
+) -> CbInputContexts {
+    let mut context = new_rts_command_context();
+
+    let (select, target_press, cancel, move_unit, attack_move_unit, activate_ability) =
+        match rts_context {
+            CbInputContexts::RtsContext {
+                select,
+                target,
+                cancel,
+                move_unit,
+                attack_move_unit,
+                activate_ability,
+                ..
+            } => (
+                *select == Press::Pressed,
+                *target == Press::Pressed,
+                *cancel == Press::Pressed,
+                *move_unit == Press::Pressed,
+                *attack_move_unit == Press::Pressed,
+                *activate_ability == Press::Pressed,
+            ),
+            _ => {
+                return context;
+            }
+        };
+
+    if target.in_lobby {
+        if select {
+            push_rts_command(&mut context, CbRtsCommand::SetReady { ready: true });
+        } else if cancel {
+            push_rts_command(&mut context, CbRtsCommand::SetReady { ready: false });
+        }
+
+        return context;
+    }
+
+    if select {
+        *selected_unit = target.unit.filter(|u| u.owned).map(|u| u.id);
+    }
+
+    if cancel {
+        *selected_unit = None;
+    }
+
+    let unit = match *selected_unit {
+        Some(unit) => unit,
+        None => {
+            return context;
+        }
+    };
+
+    if target_press {
+        // Workers harvest the nodes they're sent to; anything else moves there
+        let command = match (target.unit, target.point) {
+            (Some(node), _) if node.is_resource_node => Some(CbRtsCommand::Harvest {
+                worker: unit,
+                node: node.id,
+            }),
+            (_, Some((x, y))) => Some(CbRtsCommand::Move {
+                unit: unit,
+                x: x,
+                y: y,
+            }),
+            _ => None,
+        };
+
+        if let Some(command) = command {
+            push_rts_command(&mut context, command);
+        }
+    }
+
+    if let Some((x, y)) = target.point {
+        if move_unit {
+            push_rts_command(
+                &mut context,
+                CbRtsCommand::Move {
+                    unit: unit,
+                    x: x,
+                    y: y,
+                },
+            );
+        }
+
+        if attack_move_unit {
+            push_rts_command(
+                &mut context,
+                CbRtsCommand::AttackMove {
+                    unit: unit,
+                    x: x,
+                    y: y,
+                },
+            );
+        }
+    }
+
+    if activate_ability {
+        let ability_target = match (target.unit, target.point) {
+            (Some(target_unit), _) => CbAbilityTarget::Unit(target_unit.id),
+            (None, Some((x, y))) => CbAbilityTarget::Point { x: x, y: y },
+            (None, None) => CbAbilityTarget::None,
+        };
+
+        push_rts_command(
+            &mut context,
+            CbRtsCommand::ActivateAbility {
+                unit: unit,
+                slot: ACTIVATE_ABILITY_SLOT,
+                target: ability_target,
+            },
+        );
+    }
+
+    return context;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presses(select: bool, target: bool, activate_ability: bool) -> CbInputContexts {
+        let press = |pressed: bool| {
+            if pressed {
+                Press::Pressed
+            } else {
+                Press::NotPressed
+            }
+        };
+
+        return CbInputContexts::RtsContext {
+            networked: Networked::On,
+            select: press(select),
+            target: press(target),
+            cancel: Press::NotPressed,
+            move_unit: Press::NotPressed,
+            attack_move_unit: Press::NotPressed,
+            activate_ability: press(activate_ability),
+            cursor_x: Range::default(),
+            cursor_y: Range::default(),
+        };
+    }
+
+    fn unit(id: UnitId, owned: bool, is_resource_node: bool) -> Option<CbRtsTargetUnit> {
+        return Some(CbRtsTargetUnit {
+            id: id,
+            owned: owned,
+            is_resource_node: is_resource_node,
+        });
+    }
+
+    #[test]
+    fn get_rts_command_context_select_only_selects_owned_units() {
+        let mut selected = None;
+        let mut target = CbRtsTarget::default();
+
+        target.unit = unit(3, true, false);
+        let context = get_rts_command_context_from_rts_context(
+            &presses(true, false, false),
+            &target,
+            &mut selected,
+        );
+        assert_eq!(Some(3), selected);
+        assert!(get_rts_commands(&context).is_empty());
+
+        target.unit = unit(4, false, false);
+        get_rts_command_context_from_rts_context(
+            &presses(true, false, false),
+            &target,
+            &mut selected,
+        );
+        assert_eq!(None, selected);
+    }
+
+    #[test]
+    fn get_rts_command_context_target_moves_or_harvests() {
+        let mut selected = Some(3);
+        let mut target = CbRtsTarget {
+            in_lobby: false,
+            point: Some((600, 900)),
+            unit: None,
+        };
+
+        let context = get_rts_command_context_from_rts_context(
+            &presses(false, true, false),
+            &target,
+            &mut selected,
+        );
+        assert_eq!(
+            vec![CbRtsCommand::Move {
+                unit: 3,
+                x: 600,
+                y: 900
+            }],
+            get_rts_commands(&context)
+        );
+
+        target.unit = unit(7, false, true);
+        let context = get_rts_command_context_from_rts_context(
+            &presses(false, true, false),
+            &target,
+            &mut selected,
+        );
+        assert_eq!(
+            vec![CbRtsCommand::Harvest { worker: 3, node: 7 }],
+            get_rts_commands(&context)
+        );
+    }
+
+    #[test]
+    fn get_rts_command_context_activate_ability_targets_unit_then_point() {
+        let mut selected = Some(3);
+        let mut target = CbRtsTarget {
+            in_lobby: false,
+            point: Some((600, 900)),
+            unit: unit(8, false, false),
+        };
+
+        let context = get_rts_command_context_from_rts_context(
+            &presses(false, false, true),
+            &target,
+            &mut selected,
+        );
+        assert_eq!(
+            vec![CbRtsCommand::ActivateAbility {
+                unit: 3,
+                slot: ACTIVATE_ABILITY_SLOT,
+                target: CbAbilityTarget::Unit(8)
+            }],
+            get_rts_commands(&context)
+        );
+
+        target.unit = None;
+        let context = get_rts_command_context_from_rts_context(
+            &presses(false, false, true),
+            &target,
+            &mut selected,
+        );
+        assert_eq!(
+            vec![CbRtsCommand::ActivateAbility {
+                unit: 3,
+                slot: ACTIVATE_ABILITY_SLOT,
+                target: CbAbilityTarget::Point { x: 600, y: 900 }
+            }],
+            get_rts_commands(&context)
+        );
+    }
+
+    #[test]
+    fn get_rts_command_context_lobby_select_sets_ready() {
+        let mut selected = None;
+        let target = CbRtsTarget {
+            in_lobby: true,
+            point: None,
+            unit: unit(3, true, false),
+        };
+
+        let context = get_rts_command_context_from_rts_context(
+            &presses(true, false, false),
+            &target,
+            &mut selected,
+        );
+
+        assert_eq!(
+            vec![CbRtsCommand::SetReady { ready: true }],
+            get_rts_commands(&context)
+        );
+        assert_eq!(None, selected);
+    }
+
+    #[test]
+    fn get_rts_command_context_without_selection_issues_nothing() {
+        let mut selected = None;
+        let target = CbRtsTarget {
+            in_lobby: false,
+            point: Some((600, 900)),
+            unit: None,
+        };
+
+        let context = get_rts_command_context_from_rts_context(
+            &presses(false, true, true),
+            &target,
+            &mut selected,
+        );
+
+        assert!(get_rts_commands(&context).is_empty());
+    }
+}
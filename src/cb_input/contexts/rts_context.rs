@@ -10,9 +10,88 @@ pub fn rts_context_from_bytes() {
     unimplemented!();
 }
 
-pub fn get_rts_movement_context_from_keys(
-    events: &Vec<sdl2::event::Event>,
-    previous_context: &CbInputContexts,
-) -> CbInputContexts {
-    unimplemented!();
+pub fn new_rts_context() -> CbInputContexts {
+    return CbInputContexts::RtsContext {
+        networked: Networked::On,
+        select: Press::NotPressed,
+        target: Press::NotPressed,
+        cancel: Press::NotPressed,
+        move_unit: Press::NotPressed,
+        attack_move_unit: Press::NotPressed,
+        activate_ability: Press::NotPressed,
+        cursor_x: Range::default(),
+        cursor_y: Range::default(),
+    };
+}
+
+pub fn get_rts_context_from_keys(hardware: &Sdl2HardwareInterface) -> CbInputContexts {
+    let_mut_for![
+        (
+            new_select,
+            new_target,
+            new_cancel,
+            new_move_unit,
+            new_attack_move_unit,
+            new_activate_ability
+        ),
+        Press,
+        Press::NotPressed
+    ];
+
+    for event in hardware.events {
+        match event {
+            Event::MouseButtonDown {
+                mouse_btn: sdl2::mouse::MouseButton::Left,
+                ..
+            } => {
+                new_select = Press::Pressed;
+            }
+            Event::MouseButtonDown {
+                mouse_btn: sdl2::mouse::MouseButton::Right,
+                ..
+            } => {
+                new_target = Press::Pressed;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => {
+                new_cancel = Press::Pressed;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::M),
+                ..
+            } => {
+                new_move_unit = Press::Pressed;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::A),
+                ..
+            } => {
+                new_attack_move_unit = Press::Pressed;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::R),
+                ..
+            } => {
+                new_activate_ability = Press::Pressed;
+            }
+            _ => {}
+        }
+    }
+
+    // The cursor also scrolls the camera when it nears the window's edges
+    let (cursor_x, cursor_y) = get_normalized_cursor_coordinates(hardware);
+
+    return CbInputContexts::RtsContext {
+        networked: Networked::On,
+        select: new_select,
+        target: new_target,
+        cancel: new_cancel,
+        move_unit: new_move_unit,
+        attack_move_unit: new_attack_move_unit,
+        activate_ability: new_activate_ability,
+        cursor_x: cursor_x,
+        cursor_y: cursor_y,
+    };
 }
@@ -5,7 +5,7 @@ use super::*;
 use crate::cb_graphics;
 use cb_graphics::Sdl2HardwareInterface;
 
-fn new_shooter_context() -> CbInputContexts {
+pub fn new_shooter_context() -> CbInputContexts {
     return CbInputContexts::ShooterContext {
         networked: Networked::On,
         jump: Press::NotPressed,
@@ -18,6 +18,8 @@ fn new_shooter_context() -> CbInputContexts {
         move_right: State::Off,
         look_x: Range::default(),
         look_y: Range::default(),
+        fire: Press::NotPressed,
+        fire_secondary: Press::NotPressed,
     };
 }
 
@@ -42,6 +44,8 @@ pub fn get_shooter_context_from_keys(
     }
 
     let mut new_jump = Press::NotPressed;
+    let mut new_fire = Press::NotPressed;
+    let mut new_fire_secondary = Press::NotPressed;
 
     let_mut_for![
         (
@@ -57,27 +61,32 @@ pub fn get_shooter_context_from_keys(
         State::Off
     ];
 
-    let mut look_x = Range::default();
-    let mut look_y = Range::default();
-
     // Apply key events
     {
         match ctx {
             CbInputContexts::ShooterContext {
                 networked: _,
                 jump: _,
-                crouching: _,
-                running: _,
-                prone: _,
-                move_forward: _,
-                move_backward: _,
-                move_left: _,
-                move_right: _,
-                look_x: ctx_look_x,
-                look_y: ctx_look_y,
+                crouching,
+                running,
+                prone,
+                move_forward,
+                move_backward,
+                move_left,
+                move_right,
+                look_x: _,
+                look_y: _,
+                fire: _,
+                fire_secondary: _,
             } => {
-                look_x = ctx_look_x;
-                look_y = ctx_look_y;
+                // Held keys stay on until they are released
+                new_crouching = crouching;
+                new_running = running;
+                new_prone = prone;
+                new_move_forward = move_forward;
+                new_move_backward = move_backward;
+                new_move_left = move_left;
+                new_move_right = move_right;
 
                 for event in hardware.events {
                     match event {
@@ -95,6 +104,18 @@ pub fn get_shooter_context_from_keys(
                         } => {
                             new_jump = Press::Pressed;
                         }
+                        Event::MouseButtonDown {
+                            mouse_btn: sdl2::mouse::MouseButton::Left,
+                            ..
+                        } => {
+                            new_fire = Press::Pressed;
+                        }
+                        Event::MouseButtonDown {
+                            mouse_btn: sdl2::mouse::MouseButton::Right,
+                            ..
+                        } => {
+                            new_fire_secondary = Press::Pressed;
+                        }
                         // States - on
                         Event::KeyDown {
                             keycode: Some(Keycode::W),
@@ -189,8 +210,8 @@ pub fn get_shooter_context_from_keys(
         }
     }
 
-    // Now apply cursor movements
-    let (cursor_x, cursor_y) = get_normalized_cursor_coordinates(hardware);
+    // Now apply cursor movements; the cursor is recentered each frame, so its offset from the center is how far to look
+    let (look_x, look_y) = get_normalized_cursor_coordinates(hardware);
 
     return CbInputContexts::ShooterContext {
         networked: Networked::On,
@@ -204,5 +225,7 @@ pub fn get_shooter_context_from_keys(
         move_right: new_move_right,
         look_x: look_x,
         look_y: look_y,
+        fire: new_fire,
+        fire_secondary: new_fire_secondary,
     };
 }
@@ -18,6 +18,12 @@ fn new_voxel_editor_context() -> CbInputContexts {
         rotate_camera_down: Press::NotPressed,
         rotate_camera_left: Press::NotPressed,
         rotate_camera_right: Press::NotPressed,
+        pan_camera_forward: Press::NotPressed,
+        pan_camera_backward: Press::NotPressed,
+        pan_camera_left: Press::NotPressed,
+        pan_camera_right: Press::NotPressed,
+        zoom_camera_in: Press::NotPressed,
+        zoom_camera_out: Press::NotPressed,
         add_voxel: Press::NotPressed,
         remove_voxel: Press::NotPressed,
     };
@@ -62,6 +68,13 @@ pub fn get_voxel_editor_context_from_keys(
     let rotate_camera_left_keys = vec![Keycode::Left];
     let rotate_camera_right_keys = vec![Keycode::Right];
 
+    let pan_camera_forward_keys = vec![Keycode::W];
+    let pan_camera_backward_keys = vec![Keycode::S];
+    let pan_camera_left_keys = vec![Keycode::A];
+    let pan_camera_right_keys = vec![Keycode::D];
+    let zoom_camera_in_keys = vec![Keycode::Equals];
+    let zoom_camera_out_keys = vec![Keycode::Minus];
+
     let add_voxel_keys = vec![Keycode::E];
     let remove_voxel_keys = vec![Keycode::Q];
 
@@ -77,6 +90,12 @@ pub fn get_voxel_editor_context_from_keys(
             rotate_camera_down,
             rotate_camera_left,
             rotate_camera_right,
+            pan_camera_forward,
+            pan_camera_backward,
+            pan_camera_left,
+            pan_camera_right,
+            zoom_camera_in,
+            zoom_camera_out,
             add_voxel,
             remove_voxel
         ),
@@ -101,6 +120,12 @@ pub fn get_voxel_editor_context_from_keys(
                 rotate_camera_down: ctx_rotate_camera_down,
                 rotate_camera_left: ctx_rotate_camera_left,
                 rotate_camera_right: ctx_rotate_camera_right,
+                pan_camera_forward: ctx_pan_camera_forward,
+                pan_camera_backward: ctx_pan_camera_backward,
+                pan_camera_left: ctx_pan_camera_left,
+                pan_camera_right: ctx_pan_camera_right,
+                zoom_camera_in: ctx_zoom_camera_in,
+                zoom_camera_out: ctx_zoom_camera_out,
                 add_voxel: ctx_add_voxel,
                 remove_voxel: ctx_remove_voxel,
             } => {
@@ -142,10 +167,49 @@ pub fn get_voxel_editor_context_from_keys(
                                     &rotate_camera_right_keys,
                                 );
 
+                                get_press_from_keys(
+                                    &mut pan_camera_forward,
+                                    keycode,
+                                    &pan_camera_forward_keys,
+                                );
+                                get_press_from_keys(
+                                    &mut pan_camera_backward,
+                                    keycode,
+                                    &pan_camera_backward_keys,
+                                );
+                                get_press_from_keys(
+                                    &mut pan_camera_left,
+                                    keycode,
+                                    &pan_camera_left_keys,
+                                );
+                                get_press_from_keys(
+                                    &mut pan_camera_right,
+                                    keycode,
+                                    &pan_camera_right_keys,
+                                );
+                                get_press_from_keys(
+                                    &mut zoom_camera_in,
+                                    keycode,
+                                    &zoom_camera_in_keys,
+                                );
+                                get_press_from_keys(
+                                    &mut zoom_camera_out,
+                                    keycode,
+                                    &zoom_camera_out_keys,
+                                );
+
                                 get_press_from_keys(&mut add_voxel, keycode, &add_voxel_keys);
                                 get_press_from_keys(&mut remove_voxel, keycode, &remove_voxel_keys);
                             }
                         }
+                        // Scrolling up zooms in
+                        Event::MouseWheel { y, .. } => {
+                            if *y > 0 {
+                                zoom_camera_in = Press::Pressed;
+                            } else if *y < 0 {
+                                zoom_camera_out = Press::Pressed;
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -170,6 +234,12 @@ pub fn get_voxel_editor_context_from_keys(
         rotate_camera_down: rotate_camera_down,
         rotate_camera_left: rotate_camera_left,
         rotate_camera_right: rotate_camera_right,
+        pan_camera_forward: pan_camera_forward,
+        pan_camera_backward: pan_camera_backward,
+        pan_camera_left: pan_camera_left,
+        pan_camera_right: pan_camera_right,
+        zoom_camera_in: zoom_camera_in,
+        zoom_camera_out: zoom_camera_out,
         add_voxel: add_voxel,
         remove_voxel: remove_voxel,
     };
@@ -10,8 +10,10 @@ pub mod input_type;
 use input_type::{Press, Range, State};
 
 pub mod contexts;
+use contexts::rts_command_context::{CbRtsTarget, UnitId};
 use contexts::{
-    CbContextManager, CbInputContexts, ContextId, SHOOTER_CONTEXT_ID, VOXEL_EDITOR_CONTEXT_ID,
+    CbContextManager, CbInputContexts, ContextId, FIGHTING_CONTEXT_ID, RTS_COMMAND_CONTEXT_ID,
+    RTS_CONTEXT_ID, SHOOTER_CONTEXT_ID, VOXEL_EDITOR_CONTEXT_ID,
 };
 
 pub mod cb_input;
@@ -23,6 +25,9 @@ use cb_graphics::Sdl2HardwareInterface;
 pub struct CbInputContextManager {
     active_contexts: Vec<ContextId>,
     previous_context: Option<CbContextManager>,
+    /// The unit the local player's RTS commands go to. Only the commands are networked, not the selection.
+    selected_unit: Option<UnitId>,
+    rts_target: CbRtsTarget,
 }
 
 impl CbInputContextManager {
@@ -30,6 +35,8 @@ impl CbInputContextManager {
         return Self {
             previous_context: None,
             active_contexts: vec![],
+            selected_unit: None,
+            rts_target: CbRtsTarget::default(),
         };
     }
 
@@ -45,7 +52,17 @@ impl CbInputContextManager {
         self.active_contexts.retain(|i| *i != context_id);
     }
 
-    pub fn get_rmercury_inputs(&mut self, input_interface: &Sdl2HardwareInterface) -> CbGameInput {
+    /// Set what is under the local player's cursor, for the RTS command context to act on.
+    pub fn set_rts_target(&mut self, target: CbRtsTarget) {
+        self.rts_target = target;
+    }
+
+    /// Build the local player's inputs for the current frame from the active contexts.
+    pub fn get_rmercury_inputs(
+        &mut self,
+        local_player_id: usize,
+        input_interface: &Sdl2HardwareInterface,
+    ) -> CbGameInput {
         let mut ctx_mgr = contexts::CbContextManager::new();
 
         for active_context in self.active_contexts.iter() {
@@ -58,6 +75,42 @@ impl CbInputContextManager {
 
                 ctx_mgr.add_context(shooter_context);
             }
+            // FIGHTING CONTEXT
+            else if *active_context == FIGHTING_CONTEXT_ID {
+                let previous = self
+                    .previous_context
+                    .and_then(|c| c.get_context(FIGHTING_CONTEXT_ID))
+                    .unwrap_or(contexts::fighting_context::new_fighting_context());
+
+                let fighting_context = contexts::fighting_context::get_fighting_context_from_keys(
+                    input_interface.events,
+                    &previous,
+                );
+
+                ctx_mgr.add_context(fighting_context);
+            }
+            // RTS CONTEXT
+            else if *active_context == RTS_CONTEXT_ID {
+                let rts_context =
+                    contexts::rts_context::get_rts_context_from_keys(&input_interface);
+
+                ctx_mgr.add_context(rts_context);
+            }
+            // RTS COMMAND CONTEXT
+            else if *active_context == RTS_COMMAND_CONTEXT_ID {
+                let rts_context = ctx_mgr.get_context(RTS_CONTEXT_ID).unwrap_or_else(|| {
+                    contexts::rts_context::get_rts_context_from_keys(&input_interface)
+                });
+
+                let rts_command_context =
+                    contexts::rts_command_context::get_rts_command_context_from_rts_context(
+                        &rts_context,
+                        &self.rts_target,
+                        &mut self.selected_unit,
+                    );
+
+                ctx_mgr.add_context(rts_command_context);
+            }
             // VOXEL EDITOR CONTEXT
             else if *active_context == VOXEL_EDITOR_CONTEXT_ID {
                 let voxel_editor_context =
@@ -72,7 +125,7 @@ impl CbInputContextManager {
 
         self.previous_context = Some(ctx_mgr);
 
-        let game_input = CbGameInput::new(1, ctx_mgr);
+        let game_input = CbGameInput::new(local_player_id, ctx_mgr);
 
         return game_input;
     }
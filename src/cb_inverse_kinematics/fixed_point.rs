@@ -0,0 +1,629 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    The same FABRIK solver as the float version, done entirely in fixed point so every machine poses a rig identically.
+    Used for rigs that affect the simulation, such as hitboxes, which must match between peers and replay exactly in
+    rollback. Positions are in game units; the math is done on the raw bits of the fixed point values so products never
+    overflow, square roots are integer square roots and angles are in tenths of a degree through the lookup tables in
+    cb_math. Every solve stops after at most MAX_ITERATIONS passes.
+*/
+
+use crate::cb_math;
+use cb_math::{atan2, from_bits, length, normalize, sin_cos, to_bits, FInt, ONE};
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use super::MAX_ITERATIONS;
+
+/// How close, in game units, every end effector must be to its target for a solve to finish early.
+pub const FIXED_SOLVE_TOLERANCE: i32 = 1;
+
+/// How far a straight chain is bent towards its pole, as a percentage of the bone length.
+const POLE_NUDGE_PERCENT: i64 = 5;
+
+type Bits = [i64; 3];
+
+/// Limits how the bone leaving a joint may turn relative to the bone entering it. Angles are in tenths of a degree.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FixedJointConstraint {
+    /// The bone only swings around the axis, which is in the rig's space, between the angles measured from the
+    /// entering bone. Positive angles are counter clockwise around the axis.
+    Hinge {
+        axis: Coordinate3d,
+        min_angle: i32,
+        max_angle: i32,
+    },
+    /// The bone stays within a cone around the entering bone.
+    BallSocket { max_angle: i32 },
+}
+
+#[derive(Clone)]
+pub struct FixedIkRig {
+    /// Where the end effector, the last joint of the chain, should reach.
+    pub target: Option<Coordinate3d>,
+    /// Interior joints of the chain bend towards the pole.
+    pub pole: Option<Coordinate3d>,
+    pub joints: Vec<FixedChildTypes>,
+    /// The constraint on the bone leaving each joint, if any.
+    constraints: Vec<Option<FixedJointConstraint>>,
+    pub joint_distances: Vec<GameUnit>,
+    /// The direction constraints on the root joint are measured from.
+    pub root_direction: Coordinate3d,
+    /// Where the rig is placed; joints and targets are relative to it.
+    pub position: Coordinate3d,
+}
+
+#[derive(Clone)]
+pub enum FixedChildTypes {
+    Joint(Coordinate3d),
+    SubChain(FixedIkRig),
+}
+
+fn get_child_position(child: &FixedChildTypes) -> Coordinate3d {
+    match child {
+        FixedChildTypes::Joint(pos) => *pos,
+        FixedChildTypes::SubChain(chain) => get_child_position(&chain.joints[0]),
+    }
+}
+
+fn set_child_position(child: &mut FixedChildTypes, position: Coordinate3d) {
+    match child {
+        FixedChildTypes::Joint(p) => {
+            *p = position;
+        }
+        FixedChildTypes::SubChain(sub_chain) => {
+            set_child_position(&mut sub_chain.joints[0], position);
+        }
+    };
+}
+
+impl FixedIkRig {
+    pub fn new() -> Self {
+        return Self {
+            target: None,
+            pole: None,
+            joints: vec![],
+            constraints: vec![],
+            joint_distances: vec![],
+            root_direction: Coordinate3d::new(
+                GameUnit::from_num(1),
+                GameUnit::from_num(0),
+                GameUnit::from_num(0),
+            ),
+            position: Coordinate3d::zero(),
+        };
+    }
+
+    pub fn get_child_position(&self, i: usize) -> Coordinate3d {
+        return get_child_position(&self.joints[i]);
+    }
+
+    /// Returns whether the current rig is a valid rig or not. A valid rig has at least 2 joints.
+    pub fn is_valid_rig(&self) -> bool {
+        return self.joints.len() >= 2;
+    }
+
+    /// Add a new joint to the IK rig. Calculates the distance for later use.
+    pub fn add_joint(
+        &mut self,
+        joint_position: Coordinate3d,
+        constraint: Option<FixedJointConstraint>,
+    ) {
+        self.add_child(FixedChildTypes::Joint(joint_position), constraint);
+    }
+
+    /// Add a branch to the IK rig. The sub chain's first joint becomes the rig's next joint.
+    pub fn add_sub_chain(
+        &mut self,
+        sub_chain: FixedIkRig,
+        constraint: Option<FixedJointConstraint>,
+    ) {
+        self.add_child(FixedChildTypes::SubChain(sub_chain), constraint);
+    }
+
+    fn add_child(&mut self, child: FixedChildTypes, constraint: Option<FixedJointConstraint>) {
+        if let Some(last) = self.joints.last() {
+            let distance = length(sub(
+                to_bits(get_child_position(&child)),
+                to_bits(get_child_position(last)),
+            ));
+            self.joint_distances
+                .push(GameUnit::from_bits(distance as i32));
+        }
+
+        self.joints.push(child);
+        self.constraints.push(constraint);
+    }
+
+    /// Move every joint of the rig, including its sub chains, by the offset.
+    pub fn translate(&mut self, offset: Coordinate3d) {
+        for child in self.joints.iter_mut() {
+            match child {
+                FixedChildTypes::Joint(p) => {
+                    p.x += offset.x;
+                    p.y += offset.y;
+                    p.z += offset.z;
+                }
+                FixedChildTypes::SubChain(sub_chain) => sub_chain.translate(offset),
+            }
+        }
+    }
+
+    /// Returns whether the chain or any of its sub chains has a target to reach.
+    pub fn has_end_effector(&self) -> bool {
+        return self.target.is_some()
+            || self.joints.iter().any(|child| match child {
+                FixedChildTypes::SubChain(sub_chain) => sub_chain.has_end_effector(),
+                FixedChildTypes::Joint(_) => false,
+            });
+    }
+
+    /// The furthest any end effector is from its target.
+    pub fn end_effector_error(&self) -> GameUnit {
+        return GameUnit::from_bits(self.error_bits() as i32);
+    }
+
+    fn error_bits(&self) -> i64 {
+        let mut error = match (self.target, self.joints.last()) {
+            (Some(target), Some(last)) => {
+                length(sub(to_bits(target), to_bits(get_child_position(last))))
+            }
+            _ => 0,
+        };
+
+        for child in self.joints.iter() {
+            if let FixedChildTypes::SubChain(sub_chain) = child {
+                error = error.max(sub_chain.error_bits());
+            }
+        }
+
+        return error;
+    }
+}
+
+pub fn fixed_fabrik(rig: &mut FixedIkRig) {
+    if !rig.is_valid_rig() || !rig.has_end_effector() {
+        return;
+    }
+
+    if let FixedChildTypes::SubChain(_) = rig.joints[0] {
+        panic!("Unable to have a subchain as the first child joint!")
+    }
+
+    let base = to_bits(rig.get_child_position(0));
+    let root_direction = normalize(to_bits(rig.root_direction)).unwrap_or([ONE, 0, 0]);
+
+    for _ in 0..MAX_ITERATIONS {
+        if rig.error_bits() <= FIXED_SOLVE_TOLERANCE as i64 * ONE {
+            return;
+        }
+
+        forward_reach(rig);
+        apply_poles(rig);
+        backward_reach(rig, base, root_direction);
+    }
+}
+
+/// Stage 1: move each end effector onto its target, then move every joint back towards the root so that it is the
+/// bone's length from the joint after it. Branch points go to the average of where their branches want them.
+fn forward_reach(rig: &mut FixedIkRig) {
+    let n = rig.joints.len();
+
+    if let Some(target) = rig.target {
+        set_child_position(&mut rig.joints[n - 1], target);
+    }
+
+    // Whether an end effector is further along the chain, pulling on the joints before it
+    let mut pulled = rig.target.is_some();
+
+    if let FixedChildTypes::SubChain(sub_chain) = &mut rig.joints[n - 1] {
+        if sub_chain.has_end_effector() {
+            forward_reach(sub_chain);
+            pulled = true;
+        }
+    }
+
+    for i in (0..n - 1).rev() {
+        let mut wanted: Vec<Bits> = vec![];
+
+        if pulled {
+            let next = to_bits(rig.get_child_position(i + 1));
+            let current = to_bits(rig.get_child_position(i));
+            let bone = rig.joint_distances[i].to_bits() as i64;
+
+            wanted.push(add(next, set_length(sub(current, next), bone)));
+        }
+
+        if let FixedChildTypes::SubChain(sub_chain) = &mut rig.joints[i] {
+            if sub_chain.has_end_effector() {
+                forward_reach(sub_chain);
+                wanted.push(to_bits(sub_chain.get_child_position(0)));
+                pulled = true;
+            }
+        }
+
+        if !wanted.is_empty() {
+            let count = wanted.len() as i64;
+            let sum = wanted.iter().fold([0, 0, 0], |sum, p| add(sum, *p));
+            let average = [sum[0] / count, sum[1] / count, sum[2] / count];
+
+            set_child_position(&mut rig.joints[i], from_bits(average));
+        }
+    }
+}
+
+/// Stage 2: put the root back at its base, then move every joint out from it so that it is the bone's length from the
+/// joint before it, turning each bone to within its joint's constraint.
+fn backward_reach(rig: &mut FixedIkRig, base: Bits, entering_direction: Bits) {
+    set_child_position(&mut rig.joints[0], from_bits(base));
+
+    let mut entering_direction = entering_direction;
+
+    for i in 0..rig.joints.len() - 1 {
+        let current = to_bits(rig.get_child_position(i));
+        let offset = sub(to_bits(rig.get_child_position(i + 1)), current);
+        let bone = rig.joint_distances[i].to_bits() as i64;
+
+        let next = match &rig.constraints[i] {
+            Some(constraint) => {
+                let direction = normalize(offset).unwrap_or(entering_direction);
+                let direction = apply_constraint(constraint, entering_direction, direction);
+
+                add(current, scale(direction, bone, ONE))
+            }
+            None => add(current, set_length(offset, bone)),
+        };
+        set_child_position(&mut rig.joints[i + 1], from_bits(next));
+
+        let direction = normalize(sub(next, current)).unwrap_or(entering_direction);
+
+        if let FixedChildTypes::SubChain(sub_chain) = &mut rig.joints[i + 1] {
+            backward_reach(sub_chain, next, direction);
+        }
+
+        entering_direction = direction;
+    }
+}
+
+/// Swing every interior joint around the line between its neighbours until it faces its chain's pole. Bone lengths and
+/// every other joint are left as they were.
+fn apply_poles(rig: &mut FixedIkRig) {
+    let n = rig.joints.len();
+
+    if let Some(pole) = rig.pole {
+        let pole = to_bits(pole);
+
+        for i in 1..n - 1 {
+            let before = to_bits(rig.get_child_position(i - 1));
+            let after = to_bits(rig.get_child_position(i + 1));
+            let joint = to_bits(rig.get_child_position(i));
+
+            let axis = match normalize(sub(after, before)) {
+                Some(axis) => axis,
+                None => {
+                    continue;
+                }
+            };
+            let offset = sub(joint, before);
+            let along = scale(axis, dot(offset, axis), ONE);
+
+            let to_pole = sub(pole, before);
+            let towards_pole = match normalize(sub(to_pole, scale(axis, dot(to_pole, axis), ONE))) {
+                Some(towards_pole) => towards_pole,
+                None => {
+                    continue;
+                }
+            };
+
+            // A straight chain has no bend to swing, so it is nudged towards the pole and later passes bend it further
+            let nudge = rig.joint_distances[i - 1].to_bits() as i64 * POLE_NUDGE_PERCENT / 100;
+            let bend = length(sub(offset, along)).max(nudge);
+
+            let moved = add(add(before, along), scale(towards_pole, bend, ONE));
+            set_child_position(&mut rig.joints[i], from_bits(moved));
+        }
+    }
+
+    for child in rig.joints.iter_mut() {
+        if let FixedChildTypes::SubChain(sub_chain) = child {
+            apply_poles(sub_chain);
+        }
+    }
+}
+
+/// Returns the bone direction turned as little as possible to satisfy the constraint. Both directions are unit length.
+fn apply_constraint(constraint: &FixedJointConstraint, entering: Bits, bone: Bits) -> Bits {
+    match constraint {
+        FixedJointConstraint::BallSocket { max_angle } => {
+            let (sin, cos) = sin_cos(*max_angle);
+
+            if dot(entering, bone) >= cos.to_bits() as i64 {
+                return bone;
+            }
+
+            let sideways =
+                perpendicular_part(bone, entering).unwrap_or_else(|| any_perpendicular(entering));
+
+            return add(
+                scale(entering, cos.to_bits() as i64, ONE),
+                scale(sideways, sin.to_bits() as i64, ONE),
+            );
+        }
+        FixedJointConstraint::Hinge {
+            axis,
+            min_angle,
+            max_angle,
+        } => {
+            let axis = normalize(to_bits(*axis)).unwrap_or([0, 0, ONE]);
+
+            // The angles are measured from the entering bone, flattened onto the hinge's plane
+            let reference =
+                perpendicular_part(entering, axis).unwrap_or_else(|| any_perpendicular(axis));
+            let flattened = perpendicular_part(bone, axis).unwrap_or(reference);
+
+            let angle = atan2(
+                FInt::from_bits(dot(axis, cross(reference, flattened)) as i32),
+                FInt::from_bits(dot(reference, flattened) as i32),
+            );
+            let (sin, cos) = sin_cos(angle.max(*min_angle).min(*max_angle));
+
+            return add(
+                scale(reference, cos.to_bits() as i64, ONE),
+                scale(cross(axis, reference), sin.to_bits() as i64, ONE),
+            );
+        }
+    }
+}
+
+fn add(a: Bits, b: Bits) -> Bits {
+    return [a[0] + b[0], a[1] + b[1], a[2] + b[2]];
+}
+
+fn sub(a: Bits, b: Bits) -> Bits {
+    return [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+}
+
+/// Multiply by the fraction. Each component is rounded towards zero.
+fn scale(v: Bits, numerator: i64, denominator: i64) -> Bits {
+    return [
+        v[0] * numerator / denominator,
+        v[1] * numerator / denominator,
+        v[2] * numerator / denominator,
+    ];
+}
+
+fn dot(a: Bits, b: Bits) -> i64 {
+    return (a[0] * b[0] + a[1] * b[1] + a[2] * b[2]) / ONE;
+}
+
+fn cross(a: Bits, b: Bits) -> Bits {
+    return [
+        (a[1] * b[2] - a[2] * b[1]) / ONE,
+        (a[2] * b[0] - a[0] * b[2]) / ONE,
+        (a[0] * b[1] - a[1] * b[0]) / ONE,
+    ];
+}
+
+/// The vector scaled to the given length. Zero length vectors point along the x axis.
+fn set_length(v: Bits, new_length: i64) -> Bits {
+    let current = length(v);
+
+    if current == 0 {
+        return [new_length, 0, 0];
+    }
+
+    return scale(v, new_length, current);
+}
+
+/// The unit length part of the vector perpendicular to the unit length axis, if there is any.
+fn perpendicular_part(v: Bits, axis: Bits) -> Option<Bits> {
+    return normalize(sub(v, scale(axis, dot(v, axis), ONE)));
+}
+
+fn any_perpendicular(v: Bits) -> Bits {
+    let other = if v[0].abs() < ONE * 9 / 10 {
+        [ONE, 0, 0]
+    } else {
+        [0, ONE, 0]
+    };
+
+    return normalize(cross(v, other)).unwrap_or([0, 0, ONE]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cb_inverse_kinematics::{fabrik, CbMatrix, IkRig};
+
+    /// Bone length, in game units, of the test chains.
+    const BONE: i32 = 300;
+
+    fn at(x: i32, y: i32, z: i32) -> Coordinate3d {
+        return Coordinate3d::new(
+            GameUnit::from_num(x),
+            GameUnit::from_num(y),
+            GameUnit::from_num(z),
+        );
+    }
+
+    /// A straight chain along the x axis.
+    fn straight_chain(joints: i32, constraint: Option<FixedJointConstraint>) -> FixedIkRig {
+        let mut rig = FixedIkRig::new();
+
+        for i in 0..joints {
+            rig.add_joint(at(i * BONE, 0, 0), constraint);
+        }
+
+        return rig;
+    }
+
+    fn tolerance() -> GameUnit {
+        return GameUnit::from_num(FIXED_SOLVE_TOLERANCE);
+    }
+
+    fn assert_bone_lengths_kept(rig: &FixedIkRig) {
+        for i in 0..rig.joints.len() - 1 {
+            let bone = sub(
+                to_bits(rig.get_child_position(i + 1)),
+                to_bits(rig.get_child_position(i)),
+            );
+            let error = (length(bone) - rig.joint_distances[i].to_bits() as i64).abs();
+
+            assert!(error <= ONE, "bone {} is off by {} bits", i, error);
+        }
+    }
+
+    fn positions(rig: &FixedIkRig) -> Vec<Coordinate3d> {
+        return (0..rig.joints.len())
+            .map(|i| rig.get_child_position(i))
+            .collect();
+    }
+
+    #[test]
+    fn fixed_fabrik_reachable_target_converges_within_tolerance() {
+        let mut rig = straight_chain(4, None);
+        rig.target = Some(at(300, 450, 300));
+
+        fixed_fabrik(&mut rig);
+
+        assert!(rig.end_effector_error() <= tolerance());
+        assert_eq!(at(0, 0, 0), rig.get_child_position(0));
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fixed_fabrik_unreachable_target_finishes_stretched_towards_it() {
+        let mut rig = straight_chain(4, None);
+        rig.target = Some(at(0, 0, 9000));
+
+        fixed_fabrik(&mut rig);
+
+        let end = rig.get_child_position(3);
+        assert!((end.z - GameUnit::from_num(3 * BONE)).abs() <= tolerance());
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fixed_fabrik_same_rig_solves_to_identical_bits() {
+        let mut first =
+            straight_chain(5, Some(FixedJointConstraint::BallSocket { max_angle: 600 }));
+        first.target = Some(at(-200, 700, 350));
+        first.pole = Some(at(300, 300, 1000));
+        let mut second = first.clone();
+
+        fixed_fabrik(&mut first);
+        fixed_fabrik(&mut second);
+
+        assert_eq!(positions(&first), positions(&second));
+    }
+
+    #[test]
+    fn fixed_fabrik_matches_float_solver() {
+        let mut fixed = straight_chain(4, None);
+        fixed.target = Some(at(300, 450, 300));
+
+        let mut float = IkRig::empty();
+        for i in 0..4 {
+            float.add_joint(CbMatrix::new((i * BONE) as f32, 0.0, 0.0), None);
+        }
+        float.target = Some(CbMatrix::new(300.0, 450.0, 300.0));
+
+        fixed_fabrik(&mut fixed);
+        fabrik(&mut float);
+
+        for i in 0..4 {
+            let (a, b) = (fixed.get_child_position(i), float.get_child_position(i));
+            let difference = CbMatrix::new(a.x.to_num(), a.y.to_num(), a.z.to_num()) - b;
+
+            assert!(
+                difference.norm() < 5.0,
+                "joint {} differs by {}",
+                i,
+                difference.norm()
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_fabrik_sub_chains_reach_every_target() {
+        let mut arm = FixedIkRig::new();
+        arm.add_joint(at(0, 0, BONE), None);
+        arm.add_joint(at(BONE, 0, BONE), None);
+        arm.add_joint(at(2 * BONE, 0, BONE), None);
+        arm.target = Some(at(450, 150, 360));
+
+        let mut rig = FixedIkRig::new();
+        rig.add_joint(at(0, 0, 0), None);
+        rig.add_sub_chain(arm, None);
+        rig.add_joint(at(-BONE, 0, BONE), None);
+        rig.add_joint(at(-2 * BONE, 0, BONE), None);
+        rig.target = Some(at(-450, -150, 390));
+
+        fixed_fabrik(&mut rig);
+
+        assert!(rig.end_effector_error() <= tolerance());
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fixed_fabrik_ball_socket_limits_bend() {
+        let max_angle = 300;
+        let mut rig = straight_chain(3, Some(FixedJointConstraint::BallSocket { max_angle }));
+        rig.target = Some(at(0, BONE, 0));
+
+        fixed_fabrik(&mut rig);
+
+        let (_, cos) = sin_cos(max_angle);
+        let first = normalize(to_bits(rig.get_child_position(1))).unwrap();
+        let second = normalize(sub(
+            to_bits(rig.get_child_position(2)),
+            to_bits(rig.get_child_position(1)),
+        ))
+        .unwrap();
+
+        // A few bits of slack for rounding
+        assert!(dot([ONE, 0, 0], first) >= cos.to_bits() as i64 - 8);
+        assert!(dot(first, second) >= cos.to_bits() as i64 - 8);
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fixed_fabrik_hinge_keeps_bone_in_plane() {
+        let hinge = FixedJointConstraint::Hinge {
+            axis: at(0, 0, 1),
+            min_angle: 0,
+            max_angle: 900,
+        };
+        let mut rig = FixedIkRig::new();
+        rig.add_joint(at(0, 0, 0), None);
+        rig.add_joint(at(BONE, 0, 0), Some(hinge));
+        rig.add_joint(at(2 * BONE, 0, 0), None);
+        rig.target = Some(at(60, 360, 240));
+
+        fixed_fabrik(&mut rig);
+
+        let forearm = sub(
+            to_bits(rig.get_child_position(2)),
+            to_bits(rig.get_child_position(1)),
+        );
+        assert!(forearm[2].abs() <= ONE);
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fixed_fabrik_pole_target_chooses_bend_direction() {
+        let mut up = straight_chain(3, None);
+        up.target = Some(at(450, 0, 0));
+        up.pole = Some(at(225, 0, 300));
+        let mut down = up.clone();
+        down.pole = Some(at(225, 0, -300));
+
+        fixed_fabrik(&mut up);
+        fixed_fabrik(&mut down);
+
+        assert!(up.end_effector_error() <= tolerance());
+        assert!(up.get_child_position(1).z > 150);
+        assert!(down.get_child_position(1).z < -150);
+    }
+}
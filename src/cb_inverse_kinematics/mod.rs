@@ -1,28 +1,59 @@
 extern crate nalgebra as na;
-use na::Vector2;
 
-use crate::cb_math::sqrt_f32;
+pub mod fixed_point;
 
-extern crate fixed;
-use fixed::types::I24F8;
-use fixed::FixedI32;
+/*
+    FABRIK inverse kinematics, based on: http://www.andreasaristidou.com/publications/papers/FABRIK.pdf
 
-#[derive(Clone)]
-struct Rotor {}
+    A rig is a chain of joints starting at its root. Any joint after the root may instead be a sub chain, whose first
+    joint is the branch point, so a rig forms a tree with an end effector at the tip of every chain that has a target.
+    Each iteration reaches forwards from every end effector to the root, averaging the positions the branches want for
+    each branch point, then reaches backwards from the root, applying the joint constraints on the way out. Pole targets
+    twist each chain so that it bends towards its pole.
+
+    This solver uses floats, so poses may differ slightly between machines; it is only for cosmetic rigs. Rigs that
+    affect the simulation use the fixed point solver in fixed_point instead.
+*/
 
 type Tnum = f32;
-pub type CbMatrix = na::Vector2<Tnum>; // Note: using a self aliased type here for 2d/3d implementations. First, get 2d working, then can easily export that to use 3d. Change to use fixed point.
+pub type CbMatrix = na::Vector3<Tnum>;
 
 pub type CbRotationMatrix = na::Vector1<Tnum>;
 
-const SOLVE_TOLERANCE: f32 = 0.01;
+pub const SOLVE_TOLERANCE: f32 = 0.01;
+
+/// Most forward and backward passes done in a single solve, so unreachable targets still finish.
+pub const MAX_ITERATIONS: usize = 32;
+
+/// How far a straight chain is bent towards its pole, as a fraction of the bone length.
+const POLE_NUDGE: f32 = 0.05;
+
+/// Limits how the bone leaving a joint may turn relative to the bone entering it. Angles are in radians.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum JointConstraint {
+    /// The bone only swings around the axis, which is in the rig's space, between the angles measured from the
+    /// entering bone. Positive angles are counter clockwise around the axis.
+    Hinge {
+        axis: CbMatrix,
+        min_angle: Tnum,
+        max_angle: Tnum,
+    },
+    /// The bone stays within a cone around the entering bone.
+    BallSocket { max_angle: Tnum },
+}
 
 #[derive(Clone)]
 pub struct IkRig {
+    /// Where the end effector, the last joint of the chain, should reach.
     pub target: Option<CbMatrix>,
+    /// Interior joints of the chain bend towards the pole.
+    pub pole: Option<CbMatrix>,
     pub joints: Vec<ChildTypes>,
-    rotors: Vec<Option<Rotor>>,
+    /// The constraint on the bone leaving each joint, if any.
+    constraints: Vec<Option<JointConstraint>>,
     pub joint_distances: Vec<Tnum>, // Will be of size N-1, where N is number of positions (since it's distances per joint pairs)
+    /// The direction constraints on the root joint are measured from.
+    pub root_direction: CbMatrix,
     pub position: CbMatrix,
 }
 
@@ -51,22 +82,29 @@ fn set_child_position(child: &mut ChildTypes, position: CbMatrix) {
 }
 
 impl IkRig {
+    /// A straight six joint arm, used by the editor.
     pub fn new() -> Self {
-        let mut rig = Self {
-            target: None,
-            joints: vec![],
-            rotors: vec![],
-            joint_distances: vec![],
-            position: CbMatrix::new(0.0, 0.0),
-        };
+        let mut rig = Self::empty();
 
         for i in 0..6 {
-            rig.add_joint(CbMatrix::new(60.0 * i as f32, 0.0), None);
+            rig.add_joint(CbMatrix::new(60.0 * i as f32, 0.0, 0.0), None);
         }
 
         return rig;
     }
 
+    pub fn empty() -> Self {
+        return Self {
+            target: None,
+            pole: None,
+            joints: vec![],
+            constraints: vec![],
+            joint_distances: vec![],
+            root_direction: CbMatrix::x(),
+            position: CbMatrix::new(0.0, 0.0, 0.0),
+        };
+    }
+
     pub fn get_child_position(&self, i: usize) -> CbMatrix {
         return get_child_position(&self.joints[i]);
     }
@@ -79,26 +117,55 @@ impl IkRig {
     }
 
     /// Add a new joint to the IK rig. Calculates the distance for later use.
-    fn add_joint(&mut self, joint_position: CbMatrix, rotor: Option<Rotor>) {
-        let last = self.joints.last();
+    pub fn add_joint(&mut self, joint_position: CbMatrix, constraint: Option<JointConstraint>) {
+        self.add_child(ChildTypes::Joint(joint_position), constraint);
+    }
 
-        if last.is_some() {
-            let last = last.unwrap();
-            let last = get_child_position(last);
+    /// Add a branch to the IK rig. The sub chain's first joint becomes the rig's next joint.
+    pub fn add_sub_chain(&mut self, sub_chain: IkRig, constraint: Option<JointConstraint>) {
+        self.add_child(ChildTypes::SubChain(sub_chain), constraint);
+    }
 
-            let distance = distance(last, joint_position);
+    fn add_child(&mut self, child: ChildTypes, constraint: Option<JointConstraint>) {
+        if let Some(last) = self.joints.last() {
+            let distance = distance(get_child_position(last), get_child_position(&child));
             self.joint_distances.push(distance);
         }
 
-        self.joints.push(ChildTypes::Joint(joint_position));
-        self.rotors.push(rotor); //NOTE: not sure if a rotor is between two joints, or each joint has a rotor
+        self.joints.push(child);
+        self.constraints.push(constraint);
+    }
+
+    /// Returns whether the chain or any of its sub chains has a target to reach.
+    pub fn has_end_effector(&self) -> bool {
+        return self.target.is_some()
+            || self.joints.iter().any(|child| match child {
+                ChildTypes::SubChain(sub_chain) => sub_chain.has_end_effector(),
+                ChildTypes::Joint(_) => false,
+            });
+    }
+
+    /// The furthest any end effector is from its target.
+    pub fn end_effector_error(&self) -> Tnum {
+        let mut error = match (self.target, self.joints.last()) {
+            (Some(target), Some(last)) => distance(get_child_position(last), target),
+            _ => 0.0,
+        };
+
+        for child in self.joints.iter() {
+            if let ChildTypes::SubChain(sub_chain) = child {
+                error = error.max(sub_chain.end_effector_error());
+            }
+        }
+
+        return error;
     }
 }
 
 pub fn fabrik(rig: &mut IkRig) {
     // Boundary condition checks
     {
-        if rig.target.is_none() || rig.joints.is_empty() || !rig.is_valid_rig() {
+        if rig.joints.is_empty() || !rig.is_valid_rig() || !rig.has_end_effector() {
             return;
         }
 
@@ -112,209 +179,355 @@ pub fn fabrik(rig: &mut IkRig) {
         }
     }
 
-    let target_position = rig.target.unwrap();
-    /*
-        Right now just going for single IK chain with single end effectors, will update it as time goes
-    */
+    let base = rig.get_child_position(0);
+    let root_direction = rig.root_direction;
+
+    // Unreachable targets are not special cased; repeated passes straighten the chain towards them, within the
+    // constraints, until the iterations run out.
+    for _ in 0..MAX_ITERATIONS {
+        if rig.end_effector_error() <= SOLVE_TOLERANCE {
+            return;
+        }
+
+        forward_reach(rig);
+        apply_poles(rig);
+        backward_reach(rig, base, root_direction);
+    }
+}
 
+/// Stage 1: move each end effector onto its target, then move every joint back towards the root so that it is the
+/// bone's length from the joint after it. Branch points go to the average of where their branches want them.
+fn forward_reach(rig: &mut IkRig) {
     let n = rig.joints.len();
-    // Note, in the algorithm, it indexes from 1 to N. Rust, however, indexes from 0..N, so not much actually changes. Just pointing it out. May be a few instances where the index
-    // in code looks different from the algorithm; this is expected.
-
-    // Base algorithm found on: http://www.andreasaristidou.com/publications/papers/FABRIK.pdf
-
-    //1.1 Distance between root and target
-    let dist = abs(distance(
-        get_child_position(&rig.joints[0]),
-        target_position,
-    )); // 1.2
-        //1.3 Check whether target is in reach
-    if dist > sum(&rig.joint_distances) {
-        // 1.4
-        //1.5 Target is unreachable
-        for i in 0..(n - 1) {
-            // Execute algorithm 1, finding the new position
-            {
-                // 1.6
-                // 1.7 Find the distance ri between target t and joint position pi
-                let ri = abs(distance(
-                    target_position,
-                    get_child_position(&rig.joints[i]),
-                )); //1.8
-                let lambda_i = rig.joint_distances[i] / ri; //1.9
-                                                            //1.10 Find new joint positions pi
-
-                let new_pos = (1.0 - lambda_i) * get_child_position(&rig.joints[i])
-                    + lambda_i * target_position;
-                set_child_position(&mut rig.joints[i + 1], new_pos);
-            }
-            // Execute algorithm 2
-            {
-                rig.joints[i] = apply_orientational_constraints(
-                    &rig.joints[i + 1], // NOTE: not sure if this is the proper indexing or if backwards
-                    &rig.joints[i], // NOTE: not sure if this is the proper indexing or if backwards
-                    &rig.rotors[i], //NOTE: not sure if a rotor is between two joints, or each joint has a rotor
-                );
-            }
 
-            //TODO: Execute algorithm 3
-            {
-                apply_rotational_constraints();
+    if let Some(target) = rig.target {
+        set_child_position(&mut rig.joints[n - 1], target);
+    }
+
+    // Whether an end effector is further along the chain, pulling on the joints before it
+    let mut pulled = rig.target.is_some();
+
+    if let ChildTypes::SubChain(sub_chain) = &mut rig.joints[n - 1] {
+        if sub_chain.has_end_effector() {
+            forward_reach(sub_chain);
+            pulled = true;
+        }
+    }
+
+    for i in (0..n - 1).rev() {
+        let mut wanted = vec![];
+
+        if pulled {
+            let next = rig.get_child_position(i + 1);
+            let current = rig.get_child_position(i);
+
+            wanted.push(next + direction(next, current) * rig.joint_distances[i]);
+        }
+
+        if let ChildTypes::SubChain(sub_chain) = &mut rig.joints[i] {
+            if sub_chain.has_end_effector() {
+                forward_reach(sub_chain);
+                wanted.push(sub_chain.get_child_position(0));
+                pulled = true;
             }
+        }
 
-            //1.11
-        } // 1.12
-    } else {
-        //1.13
-        //1.14 target is reachable, set b as the initial position of p0
-        let b = get_child_position(&rig.joints[0]); // 1.15
-                                                    //1.16 Check whether distance between end effector pn and the target is greater than a tolerance
-        let mut diff_a = abs(distance(
-            get_child_position(&rig.joints[n - 1]),
-            target_position,
-        )); //1.17
-        while diff_a > SOLVE_TOLERANCE {
-            //1.18
-            //1.19 Stage 1: Forward reaching
-            //1.20 Set end effector pn as target t
-            set_child_position(&mut rig.joints[n - 1], target_position); //1.21
-
-            //note: skip the last value, as it's being handled elsewhere
-            for i in (0..n - 1).rev() {
-                // Execute algorithm 1, finding the new position
-                {
-                    //1.22
-                    //1.23 Find the distance ri between the new joint position pi+1 and the joint pi
-                    let ri = abs(distance(
-                        get_child_position(&rig.joints[i + 1]),
-                        get_child_position(&rig.joints[i]),
-                    )); //1.24
-                    let lambda_i = rig.joint_distances[i] / ri; //1.25
-                                                                //1.26 Find the new joint positions pi
-
-                    let new_pos = (1.0 - lambda_i) * get_child_position(&rig.joints[i + 1])
-                        + lambda_i * get_child_position(&rig.joints[i]);
-                    set_child_position(&mut rig.joints[i], new_pos);
-                    //1.27
-                }
-                // Execute algorithm 2
-                {
-                    rig.joints[i] = apply_orientational_constraints(
-                        &rig.joints[i + 1], // NOTE: not sure if this is the proper indexing or if backwards
-                        &rig.joints[i], // NOTE: not sure if this is the proper indexing or if backwards
-                        &rig.rotors[i], //NOTE: not sure if a rotor is between two joints, or each joint has a rotor
-                    );
-                }
-
-                //TODO: Execute algorithm 3
-                {
-                    apply_rotational_constraints();
-                }
-            } // 1.28
-              //1.29 Stage 2: Backwards reaching
-              //1.30 Set the root p0 it's initial position
-
-            set_child_position(&mut rig.joints[0], b); //1.31
-
-            for i in 0..n - 1 {
-                // Execute algorithm 1, finding the new position
-                {
-                    //1.32
-                    //1.33 Find the distance ri between the new joint position pi and the joint pi+1
-                    let ri = abs(distance(
-                        get_child_position(&rig.joints[i + 1]),
-                        get_child_position(&rig.joints[i]),
-                    )); //1.34
-                    let lambda_i = rig.joint_distances[i] / ri; //1.35
-                                                                //1.36 Find the new joint positions pi
-
-                    let new_pos = (1.0 - lambda_i) * get_child_position(&rig.joints[i])
-                        + lambda_i * get_child_position(&rig.joints[i + 1]);
-                    set_child_position(&mut rig.joints[i + 1], new_pos);
-                }
-
-                // Execute algorithm 2
-                {
-                    rig.joints[i] = apply_orientational_constraints(
-                        &rig.joints[i + 1], // NOTE: not sure if this is the proper indexing or if backwards
-                        &rig.joints[i], // NOTE: not sure if this is the proper indexing or if backwards
-                        &rig.rotors[i], //NOTE: not sure if a rotor is between two joints, or each joint has a rotor
-                    );
-                }
-
-                //TODO: Execute algorithm 3
-                {
-                    apply_rotational_constraints();
-                }
-
-                //1.37
-            } //1.38
-            diff_a = abs(distance(
-                get_child_position(&rig.joints[n - 1]),
-                target_position,
-            )); // 1.39
-        } //1.40
-    } //1.41
+        if !wanted.is_empty() {
+            let sum = wanted.iter().fold(CbMatrix::zeros(), |sum, p| sum + p);
+            set_child_position(&mut rig.joints[i], sum / wanted.len() as Tnum);
+        }
+    }
 }
 
-fn apply_orientational_constraints(
-    joint_pi: &ChildTypes,
-    joint_pi_minus_1: &ChildTypes,
-    r: &Option<Rotor>,
-) -> ChildTypes {
-    /*
-        Fabrik Algorithm 2
-    */
-
-    // 2.1 Check whether the rotor R is within the motion range bounds
-    let within_the_bounds = true; //TODO solve
-    if within_the_bounds {
-        // 2.2
-        //2.3 do nothing and exit
-        return joint_pi_minus_1.clone();
-    } else {
-        //2.4
-        //2.5 reorient the joint pi-1 in such a way that the rotor will be within the limits
+/// Stage 2: put the root back at its base, then move every joint out from it so that it is the bone's length from the
+/// joint before it, turning each bone to within its joint's constraint.
+fn backward_reach(rig: &mut IkRig, base: CbMatrix, entering_direction: CbMatrix) {
+    set_child_position(&mut rig.joints[0], base);
+
+    let mut entering_direction = entering_direction;
+
+    for i in 0..rig.joints.len() - 1 {
+        let current = rig.get_child_position(i);
+        let mut bone = direction(current, rig.get_child_position(i + 1));
+
+        if let Some(constraint) = &rig.constraints[i] {
+            bone = apply_constraint(constraint, entering_direction, bone);
+        }
+
+        let next = current + bone * rig.joint_distances[i];
+        set_child_position(&mut rig.joints[i + 1], next);
+
+        if let ChildTypes::SubChain(sub_chain) = &mut rig.joints[i + 1] {
+            backward_reach(sub_chain, next, bone);
+        }
+
+        entering_direction = bone;
+    }
+}
+
+/// Swing every interior joint around the line between its neighbours until it faces its chain's pole. Bone lengths and
+/// every other joint are left as they were.
+fn apply_poles(rig: &mut IkRig) {
+    let n = rig.joints.len();
+
+    if let Some(pole) = rig.pole {
+        for i in 1..n - 1 {
+            let before = rig.get_child_position(i - 1);
+            let after = rig.get_child_position(i + 1);
+            let joint = rig.get_child_position(i);
+
+            let axis = direction(before, after);
+            let offset = joint - before;
+            let along = axis * offset.dot(&axis);
+
+            let bend = offset - along;
+            let towards_pole = (pole - before) - axis * (pole - before).dot(&axis);
+
+            if towards_pole.norm() <= Tnum::EPSILON {
+                continue;
+            }
+
+            // A straight chain has no bend to swing, so it is nudged towards the pole and later passes bend it further
+            let bend = bend.norm().max(rig.joint_distances[i - 1] * POLE_NUDGE);
 
-        let mut modified_pi_minus_1 = joint_pi_minus_1.clone();
+            let moved = before + along + towards_pole.normalize() * bend;
+            set_child_position(&mut rig.joints[i], moved);
+        }
+    }
 
-        return modified_pi_minus_1;
-    } // 2.6
+    for child in rig.joints.iter_mut() {
+        if let ChildTypes::SubChain(sub_chain) = child {
+            apply_poles(sub_chain);
+        }
+    }
 }
 
-fn apply_rotational_constraints() {
-    /*
-        Fabrik Algorithm 3
-    */
+/// Returns the bone direction turned as little as possible to satisfy the constraint. Both directions are unit length.
+fn apply_constraint(constraint: &JointConstraint, entering: CbMatrix, bone: CbMatrix) -> CbMatrix {
+    match constraint {
+        JointConstraint::BallSocket { max_angle } => {
+            let cos = entering.dot(&bone).max(-1.0).min(1.0);
+
+            if cos.acos() <= *max_angle {
+                return bone;
+            }
+
+            let sideways =
+                perpendicular_part(bone, entering).unwrap_or_else(|| any_perpendicular(entering));
+
+            return entering * max_angle.cos() + sideways * max_angle.sin();
+        }
+        JointConstraint::Hinge {
+            axis,
+            min_angle,
+            max_angle,
+        } => {
+            let axis = axis.normalize();
+
+            // The angles are measured from the entering bone, flattened onto the hinge's plane
+            let reference = match perpendicular_part(entering, axis) {
+                Some(reference) => reference,
+                None => any_perpendicular(axis),
+            };
+            let flattened = match perpendicular_part(bone, axis) {
+                Some(flattened) => flattened,
+                None => reference,
+            };
+
+            let angle = axis
+                .dot(&reference.cross(&flattened))
+                .atan2(reference.dot(&flattened));
+            let angle = angle.max(*min_angle).min(*max_angle);
+
+            return reference * angle.cos() + axis.cross(&reference) * angle.sin();
+        }
+    }
 }
 
-fn sum(values: &Vec<Tnum>) -> Tnum {
-    let mut value = 0.0;
-    for v in values.iter() {
-        value += v;
+/// The unit length part of the vector perpendicular to the unit length axis, if there is any.
+fn perpendicular_part(v: CbMatrix, axis: CbMatrix) -> Option<CbMatrix> {
+    let perpendicular = v - axis * v.dot(&axis);
+
+    if perpendicular.norm() <= Tnum::EPSILON {
+        return None;
     }
 
-    return value;
+    return Some(perpendicular.normalize());
 }
 
-fn square(value: Tnum) -> Tnum {
-    return value * value;
+fn any_perpendicular(v: CbMatrix) -> CbMatrix {
+    let other = if v.x.abs() < 0.9 {
+        CbMatrix::x()
+    } else {
+        CbMatrix::y()
+    };
+
+    return v.cross(&other).normalize();
 }
 
-fn sqrt(value: Tnum) -> Tnum {
-    return value.sqrt();
+/// The unit direction from a to b. Coincident points keep whatever direction the x axis gives.
+fn direction(a: CbMatrix, b: CbMatrix) -> CbMatrix {
+    let d = b - a;
+    let length = d.norm();
+
+    if length <= Tnum::EPSILON {
+        return CbMatrix::x();
+    }
+
+    return d / length;
 }
 
 fn distance(a: CbMatrix, b: CbMatrix) -> Tnum {
-    let d_squared = square(a.x - b.x) + square(a.y - b.y);
-
-    return sqrt(d_squared);
+    return (a - b).norm();
 }
 
-fn abs(value: Tnum) -> Tnum {
-    if value < 0.0 {
-        return -value;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A straight chain along the x axis with bones of length 1.
+    fn straight_chain(joints: usize, constraint: Option<JointConstraint>) -> IkRig {
+        let mut rig = IkRig::empty();
+
+        for i in 0..joints {
+            rig.add_joint(CbMatrix::new(i as f32, 0.0, 0.0), constraint);
+        }
+
+        return rig;
+    }
+
+    fn assert_bone_lengths_kept(rig: &IkRig) {
+        for i in 0..rig.joints.len() - 1 {
+            let length = distance(rig.get_child_position(i), rig.get_child_position(i + 1));
+            assert!(
+                (length - rig.joint_distances[i]).abs() < SOLVE_TOLERANCE,
+                "bone {} is {} long",
+                i,
+                length
+            );
+        }
+    }
+
+    fn bone(rig: &IkRig, i: usize) -> CbMatrix {
+        return direction(rig.get_child_position(i), rig.get_child_position(i + 1));
+    }
+
+    #[test]
+    fn fabrik_reachable_target_converges_within_tolerance() {
+        let mut rig = straight_chain(4, None);
+        rig.target = Some(CbMatrix::new(1.0, 1.5, 1.0));
+
+        fabrik(&mut rig);
+
+        assert!(rig.end_effector_error() <= SOLVE_TOLERANCE);
+        assert_eq!(CbMatrix::new(0.0, 0.0, 0.0), rig.get_child_position(0));
+        assert_bone_lengths_kept(&rig);
     }
 
-    return value;
+    #[test]
+    fn fabrik_unreachable_target_stretches_chain_towards_it() {
+        let mut rig = straight_chain(4, None);
+        rig.target = Some(CbMatrix::new(0.0, 0.0, 10.0));
+
+        fabrik(&mut rig);
+
+        let end = rig.get_child_position(3);
+        assert!(distance(end, CbMatrix::new(0.0, 0.0, 3.0)) < SOLVE_TOLERANCE);
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fabrik_sub_chains_reach_every_target() {
+        let mut arm = IkRig::empty();
+        arm.add_joint(CbMatrix::new(0.0, 0.0, 1.0), None);
+        arm.add_joint(CbMatrix::new(1.0, 0.0, 1.0), None);
+        arm.add_joint(CbMatrix::new(2.0, 0.0, 1.0), None);
+        arm.target = Some(CbMatrix::new(1.5, 0.5, 1.2));
+
+        let mut rig = IkRig::empty();
+        rig.add_joint(CbMatrix::new(0.0, 0.0, 0.0), None);
+        rig.add_sub_chain(arm, None);
+        rig.add_joint(CbMatrix::new(-1.0, 0.0, 1.0), None);
+        rig.add_joint(CbMatrix::new(-2.0, 0.0, 1.0), None);
+        rig.target = Some(CbMatrix::new(-1.5, -0.5, 1.3));
+
+        fabrik(&mut rig);
+
+        assert!(rig.end_effector_error() <= SOLVE_TOLERANCE);
+        assert_bone_lengths_kept(&rig);
+        match &rig.joints[1] {
+            ChildTypes::SubChain(arm) => assert_bone_lengths_kept(arm),
+            ChildTypes::Joint(_) => panic!("expected the arm to stay a sub chain"),
+        }
+    }
+
+    #[test]
+    fn fabrik_ball_socket_limits_bend() {
+        let max_angle = 0.5;
+        let mut rig = straight_chain(3, Some(JointConstraint::BallSocket { max_angle }));
+        rig.target = Some(CbMatrix::new(0.0, 1.0, 0.0));
+
+        fabrik(&mut rig);
+
+        for i in 0..2 {
+            let entering = if i == 0 {
+                rig.root_direction
+            } else {
+                bone(&rig, i - 1)
+            };
+            let angle = entering.dot(&bone(&rig, i)).min(1.0).acos();
+            assert!(
+                angle <= max_angle + SOLVE_TOLERANCE,
+                "joint {} bent {}",
+                i,
+                angle
+            );
+        }
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fabrik_hinge_keeps_bone_in_plane_and_within_limits() {
+        let hinge = JointConstraint::Hinge {
+            axis: CbMatrix::z(),
+            min_angle: 0.0,
+            max_angle: std::f32::consts::FRAC_PI_2,
+        };
+        let mut rig = IkRig::empty();
+        rig.add_joint(CbMatrix::new(0.0, 0.0, 0.0), None);
+        rig.add_joint(CbMatrix::new(1.0, 0.0, 0.0), Some(hinge));
+        rig.add_joint(CbMatrix::new(2.0, 0.0, 0.0), None);
+        rig.target = Some(CbMatrix::new(0.2, 1.2, 0.8));
+
+        fabrik(&mut rig);
+
+        let forearm = bone(&rig, 1);
+        let angle = bone(&rig, 0)
+            .cross(&forearm)
+            .z
+            .atan2(bone(&rig, 0).dot(&forearm));
+        assert!(forearm.z.abs() < SOLVE_TOLERANCE);
+        assert!(
+            angle >= -SOLVE_TOLERANCE && angle <= std::f32::consts::FRAC_PI_2 + SOLVE_TOLERANCE
+        );
+        assert_bone_lengths_kept(&rig);
+    }
+
+    #[test]
+    fn fabrik_pole_target_chooses_bend_direction() {
+        let mut up = straight_chain(3, None);
+        up.target = Some(CbMatrix::new(1.5, 0.0, 0.0));
+        up.pole = Some(CbMatrix::new(0.75, 0.0, 1.0));
+        let mut down = up.clone();
+        down.pole = Some(CbMatrix::new(0.75, 0.0, -1.0));
+
+        fabrik(&mut up);
+        fabrik(&mut down);
+
+        assert!(up.end_effector_error() <= SOLVE_TOLERANCE);
+        assert!(down.end_effector_error() <= SOLVE_TOLERANCE);
+        assert!(up.get_child_position(1).z > 0.5);
+        assert!(down.get_child_position(1).z < -0.5);
+        assert!(up.get_child_position(1).y.abs() < SOLVE_TOLERANCE);
+    }
 }
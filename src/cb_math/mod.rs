@@ -9,6 +9,9 @@ use fixed::types::{I20F12, U20F12};
 pub type FInt = I20F12;
 pub type FUint = U20F12;
 
+use crate::cb_system;
+use cb_system::Coordinate3d;
+
 /// Returns the number raised to the power
 pub fn pow(num: usize, pow: usize) -> usize {
     let mut value = 1;
@@ -35,6 +38,139 @@ pub fn pow_i32(num: i32, pow: usize) -> i32 {
     return value;
 }
 
+/// Integer square root, rounded down. Deterministic replacement for float square roots.
+pub fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method, starting from an estimate that is always above the root
+    let mut x = value / 2 + 1;
+    let mut y = (x + value / x) / 2;
+
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+
+    return x;
+}
+
+/// The raw bits of one FInt. Vectors of raw bits are of unit length when their length is this.
+pub const ONE: i64 = 1 << 12;
+
+/// The raw bits of the coordinate, widened so they can be multiplied without overflowing.
+pub fn to_bits(c: Coordinate3d) -> [i64; 3] {
+    return [
+        c.x.to_bits() as i64,
+        c.y.to_bits() as i64,
+        c.z.to_bits() as i64,
+    ];
+}
+
+pub fn from_bits(bits: [i64; 3]) -> Coordinate3d {
+    return Coordinate3d::new(
+        FInt::from_bits(bits[0] as i32),
+        FInt::from_bits(bits[1] as i32),
+        FInt::from_bits(bits[2] as i32),
+    );
+}
+
+/// The length of the vector of raw bits, rounded down.
+pub fn length(v: [i64; 3]) -> i64 {
+    return isqrt((v[0] * v[0] + v[1] * v[1] + v[2] * v[2]) as u64) as i64;
+}
+
+/// The vector of raw bits scaled to unit length, rounding each component towards zero. Zero vectors have no direction.
+pub fn normalize(v: [i64; 3]) -> Option<[i64; 3]> {
+    let length = length(v);
+
+    if length == 0 {
+        return None;
+    }
+
+    return Some([
+        v[0] * ONE / length,
+        v[1] * ONE / length,
+        v[2] * ONE / length,
+    ]);
+}
+
+/// Sine of each whole degree from 0 to 90, scaled by 4096 to match the fractional bits of FInt.
+const SIN_TABLE: [i32; 91] = [
+    0, 71, 143, 214, 286, 357, 428, 499, 570, 641, 711, 782, 852, 921, 991, 1060, 1129, 1198, 1266,
+    1334, 1401, 1468, 1534, 1600, 1666, 1731, 1796, 1860, 1923, 1986, 2048, 2110, 2171, 2231, 2290,
+    2349, 2408, 2465, 2522, 2578, 2633, 2687, 2741, 2793, 2845, 2896, 2946, 2996, 3044, 3091, 3138,
+    3183, 3228, 3271, 3314, 3355, 3396, 3435, 3474, 3511, 3547, 3582, 3617, 3650, 3681, 3712, 3742,
+    3770, 3798, 3824, 3849, 3873, 3896, 3917, 3937, 3956, 3974, 3991, 4006, 4021, 4034, 4046, 4056,
+    4065, 4074, 4080, 4086, 4090, 4094, 4095, 4096,
+];
+
+/// Sine of a whole or fractional degree in the first quadrant, in tenths of a degree, scaled by 4096.
+fn sin_quadrant(tenths: i32) -> i32 {
+    let degree = (tenths / 10) as usize;
+    let remainder = tenths % 10;
+
+    if degree >= 90 {
+        return SIN_TABLE[90];
+    }
+
+    // Linearly interpolate between whole degrees
+    let (a, b) = (SIN_TABLE[degree], SIN_TABLE[degree + 1]);
+    return a + (b - a) * remainder / 10;
+}
+
+/// Deterministic sine and cosine of an angle given in tenths of a degree, using a lookup table instead of floats.
+pub fn sin_cos(angle_tenths: i32) -> (FInt, FInt) {
+    let angle = angle_tenths.rem_euclid(3600);
+
+    let sin = match angle {
+        0..=899 => sin_quadrant(angle),
+        900..=1799 => sin_quadrant(1800 - angle),
+        1800..=2699 => -sin_quadrant(angle - 1800),
+        _ => -sin_quadrant(3600 - angle),
+    };
+
+    let cos_angle = (angle + 900).rem_euclid(3600);
+    let cos = match cos_angle {
+        0..=899 => sin_quadrant(cos_angle),
+        900..=1799 => sin_quadrant(1800 - cos_angle),
+        1800..=2699 => -sin_quadrant(cos_angle - 1800),
+        _ => -sin_quadrant(3600 - cos_angle),
+    };
+
+    return (FInt::from_bits(sin), FInt::from_bits(cos));
+}
+
+/// Deterministic angle of the point from the x axis, in tenths of a degree between -1800 and 1800.
+pub fn atan2(y: FInt, x: FInt) -> i32 {
+    let (y, x) = (y.to_bits() as i64, x.to_bits() as i64);
+
+    if x == 0 && y == 0 {
+        return 0;
+    }
+
+    // Binary search for the first quadrant angle whose tangent is |y| / |x|, comparing without dividing
+    let (mut low, mut high) = (0, 900);
+    while low < high {
+        let mid = (low + high) / 2;
+        let (sin, cos) = sin_cos(mid);
+
+        if sin.to_bits() as i64 * x.abs() < cos.to_bits() as i64 * y.abs() {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    match (x >= 0, y >= 0) {
+        (true, true) => low,
+        (false, true) => 1800 - low,
+        (false, false) => low - 1800,
+        (true, false) => -low,
+    }
+}
+
 pub fn index_1d_to_3d(index: usize, x_max: usize, y_max: usize) -> (usize, usize, usize) {
     let mut i = index;
 
@@ -121,4 +257,57 @@ mod tests {
     fn pow_num3_pow3_returns27() {
         assert_eq!(27, pow(3, 3));
     }
+
+    // atan2 tests
+    #[test]
+    fn atan2_each_quadrant_returns_expected_tenths() {
+        let (one, half) = (FInt::from_num(1), FInt::from_num(0.5));
+
+        assert_eq!(450, atan2(one, one));
+        assert_eq!(1350, atan2(one, -one));
+        assert_eq!(-1350, atan2(-one, -one));
+        assert_eq!(-450, atan2(-one, one));
+        assert_eq!(900, atan2(one, FInt::from_num(0)));
+        assert_eq!(0, atan2(FInt::from_num(0), FInt::from_num(0)));
+        assert_eq!(true, (atan2(half, FInt::from_num(0.866)) - 300).abs() <= 1);
+    }
+
+    // isqrt tests
+    #[test]
+    fn isqrt_small_values_return_expected() {
+        assert_eq!(0, isqrt(0));
+        assert_eq!(1, isqrt(1));
+        assert_eq!(1, isqrt(3));
+        assert_eq!(2, isqrt(4));
+        assert_eq!(3, isqrt(15));
+    }
+
+    #[test]
+    fn isqrt_large_value_rounds_down() {
+        assert_eq!(1_000_000, isqrt(1_000_000_000_000));
+        assert_eq!(999_999, isqrt(999_999_999_999));
+        assert_eq!(4_294_967_295, isqrt(u64::MAX));
+    }
+
+    // sin_cos tests
+    #[test]
+    fn sin_cos_quadrant_boundaries_return_exact_values() {
+        let one = FInt::from_num(1);
+        let zero = FInt::from_num(0);
+
+        assert_eq!((zero, one), sin_cos(0));
+        assert_eq!((one, zero), sin_cos(900));
+        assert_eq!((zero, -one), sin_cos(1800));
+        assert_eq!((-one, zero), sin_cos(2700));
+        assert_eq!(sin_cos(450), sin_cos(450 + 3600));
+        assert_eq!(sin_cos(-900), sin_cos(2700));
+    }
+
+    #[test]
+    fn sin_cos_30_degrees_is_within_a_bit_of_one_half() {
+        let (sin, cos) = sin_cos(300);
+
+        assert_eq!(2048, sin.to_bits());
+        assert!((cos.to_bits() - 3547).abs() <= 1);
+    }
 }
@@ -0,0 +1,880 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Abilities are described entirely by data; the ability system interprets targeting, costs and effects so that new
+    abilities only require a datasheet entry. The rules for using them live here, apart from the world, so they can be
+    checked on their own.
+*/
+
+use std::path::Path;
+
+extern crate specs;
+use specs::Entity;
+
+use crate::cb_datasheets;
+use cb_datasheets::DataValue;
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::CbAbilityTarget;
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, GameUnit};
+
+use super::components::ability_components::AbilitiesComponent;
+use super::fog_of_war;
+use super::unit_definitions::{UnitComponentDefinition, UnitDefinitions};
+
+pub const ABILITY_DEFINITIONS_PATH: &'static str = "./src/cb_simulation/datasheets/abilities.hjson";
+
+/// The index of an ability in the AbilityDefinitions.
+pub type AbilityId = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AbilityTargeting {
+    /// Applied at the caster's position.
+    SelfTarget,
+    Point,
+    Unit,
+    /// Applied automatically every cooldown.
+    Passive,
+}
+
+/// What an ability does when it is used. Radii of 0 only affect the targeted unit, or the caster.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbilityEffect {
+    /// Damages units not allied with the caster.
+    Damage {
+        amount: u32,
+        radius: u32,
+    },
+    /// Heals units allied with the caster.
+    Heal {
+        amount: u32,
+        radius: u32,
+    },
+    Spawn {
+        unit: String,
+        count: u32,
+    },
+    /// Removes the top voxel of each column within the radius, in voxels.
+    VoxelDig {
+        radius: u32,
+    },
+    /// Adds a voxel on top of each column within the radius, in voxels.
+    VoxelBuild {
+        radius: u32,
+        voxel_type: u8,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbilityDefinition {
+    pub name: String,
+    pub targeting: AbilityTargeting,
+    /// The furthest a target may be from the caster, in game units.
+    pub range: u32,
+    pub cooldown_ticks: u32,
+    pub energy_cost: u32,
+    pub effects: Vec<AbilityEffect>,
+}
+
+/// The set of abilities units may have. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AbilityDefinitions {
+    definitions: Vec<AbilityDefinition>,
+}
+
+impl AbilityDefinitions {
+    pub fn new() -> Self {
+        return Self {
+            definitions: vec![],
+        };
+    }
+
+    /// Load and validate the ability definitions at the given path.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let root = cb_datasheets::from_path(path)?;
+
+        return Self::from_data(&root).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    /// Parse and validate ability definitions from a datasheet string.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root = cb_datasheets::parse(source)?;
+
+        return Self::from_data(&root);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AbilityDefinition> {
+        return self.definitions.iter().find(|d| d.name == name);
+    }
+
+    pub fn get_by_id(&self, ability: AbilityId) -> Option<&AbilityDefinition> {
+        return self.definitions.get(ability);
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<AbilityId> {
+        return self.definitions.iter().position(|d| d.name == name);
+    }
+
+    /// Check that every unit referenced by an ability, and every ability referenced by a unit, exists.
+    pub fn validate(&self, units: &UnitDefinitions) -> Result<(), String> {
+        for definition in self.definitions.iter() {
+            for effect in definition.effects.iter() {
+                if let AbilityEffect::Spawn { unit, count: _ } = effect {
+                    if units.get(unit).is_none() {
+                        return Err(format!(
+                            "ability '{}': spawns unknown unit '{}'",
+                            definition.name, unit
+                        ));
+                    }
+                }
+            }
+        }
+
+        for unit in units.names().iter() {
+            for component in units.get(unit).unwrap().components.iter() {
+                if let UnitComponentDefinition::Abilities {
+                    max_energy: _,
+                    energy_regen: _,
+                    names,
+                } = component
+                {
+                    for name in names.iter() {
+                        if self.get(name).is_none() {
+                            return Err(format!("unit '{}': unknown ability '{}'", unit, name));
+                        }
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn from_data(root: &DataValue) -> Result<Self, String> {
+        let abilities = match root.get("abilities").and_then(|a| a.as_array()) {
+            Some(abilities) => abilities,
+            None => {
+                return Err("expected an 'abilities' array".to_string());
+            }
+        };
+
+        let mut definitions = Self::new();
+
+        for ability in abilities.iter() {
+            let definition = parse_ability(ability)?;
+
+            if definitions.get(&definition.name).is_some() {
+                return Err(format!("duplicate ability '{}'", definition.name));
+            }
+
+            definitions.definitions.push(definition);
+        }
+
+        return Ok(definitions);
+    }
+}
+
+/// Tick down the unit's cooldowns and regain energy if it's due. Passive abilities whose cooldowns are over are
+/// returned, with their cooldowns started again.
+pub fn tick_abilities(
+    unit_abilities: &mut AbilitiesComponent,
+    definitions: &AbilityDefinitions,
+    regen_energy: bool,
+) -> Vec<AbilityId> {
+    let mut passives = vec![];
+
+    if regen_energy {
+        unit_abilities.energy = unit_abilities
+            .energy
+            .saturating_add(unit_abilities.energy_regen)
+            .min(unit_abilities.max_energy);
+    }
+
+    for slot in unit_abilities.slots.iter_mut() {
+        slot.cooldown_remaining = slot.cooldown_remaining.saturating_sub(1);
+
+        let definition = match definitions.get_by_id(slot.ability) {
+            Some(d) => d,
+            None => {
+                continue;
+            }
+        };
+
+        if definition.targeting == AbilityTargeting::Passive && slot.cooldown_remaining == 0 {
+            slot.cooldown_remaining = definition.cooldown_ticks;
+            passives.push(slot.ability);
+        }
+    }
+
+    return passives;
+}
+
+/// Use the ability in the unit's slot on the target, paying its energy and starting its cooldown. A targeted unit is
+/// given by where it is. Returns the ability and where its effects are centered, or None if the ability isn't ready,
+/// can't be afforded, can't be used on the target or the target is out of range.
+pub fn try_activate(
+    unit_abilities: &mut AbilitiesComponent,
+    slot: usize,
+    definitions: &AbilityDefinitions,
+    caster_position: Coordinate2d,
+    target: CbAbilityTarget,
+    target_unit_position: Option<Coordinate2d>,
+) -> Option<(AbilityId, Coordinate2d)> {
+    let ability_slot = *unit_abilities.slots.get(slot)?;
+    let definition = definitions.get_by_id(ability_slot.ability)?;
+
+    if ability_slot.cooldown_remaining > 0 || unit_abilities.energy < definition.energy_cost {
+        return None;
+    }
+
+    let center = match (definition.targeting, target) {
+        (AbilityTargeting::SelfTarget, _) => caster_position,
+        // Points too far out for a game unit can't be in range
+        (AbilityTargeting::Point, CbAbilityTarget::Point { x, y }) => {
+            Coordinate2d::checked_from_num(x, y)?
+        }
+        // Point abilities can be aimed at where a unit is
+        (AbilityTargeting::Point, CbAbilityTarget::Unit(_))
+        | (AbilityTargeting::Unit, CbAbilityTarget::Unit(_)) => target_unit_position?,
+        _ => {
+            return None;
+        }
+    };
+
+    let range = definition.range as i64;
+    if definition.targeting != AbilityTargeting::SelfTarget
+        && caster_position.distance_squared(&center) > range * range
+    {
+        return None;
+    }
+
+    unit_abilities.energy -= definition.energy_cost;
+    unit_abilities.slots[slot].cooldown_remaining = definition.cooldown_ticks;
+
+    return Some((ability_slot.ability, center));
+}
+
+/// The units, as (unit, position), within the radius of the center, or just the target when the radius is 0.
+pub fn units_in_radius(
+    units: &[(Entity, Coordinate2d)],
+    center: Coordinate2d,
+    radius: u32,
+    target: Option<Entity>,
+) -> Vec<Entity> {
+    if radius == 0 {
+        return target.into_iter().collect();
+    }
+
+    let radius_squared = radius as i64 * radius as i64;
+
+    return units
+        .iter()
+        .filter(|(_, position)| position.distance_squared(&center) <= radius_squared)
+        .map(|(unit, _)| *unit)
+        .collect();
+}
+
+/// Whether the effect changes the hit points of a unit, given whether it is allied with the caster. Damage only hurts
+/// units that aren't allies and heals only help allies.
+pub fn affects_hit_points(effect: &AbilityEffect, allied: bool) -> bool {
+    match effect {
+        AbilityEffect::Damage {
+            amount: _,
+            radius: _,
+        } => {
+            return !allied;
+        }
+        AbilityEffect::Heal {
+            amount: _,
+            radius: _,
+        } => {
+            return allied;
+        }
+        _ => {
+            return false;
+        }
+    }
+}
+
+/// The voxel columns within the radius, in voxels, of the center that are on a map of the width.
+pub fn voxel_columns(center: Coordinate2d, radius: u32, width: i32) -> Vec<(usize, usize)> {
+    let (cx, cy) = fog_of_war::world_to_cell(center.x.to_num::<i32>(), center.y.to_num::<i32>());
+    let r = radius as i32;
+
+    let mut columns = vec![];
+    for x in (cx - r)..=(cx + r) {
+        for y in (cy - r)..=(cy + r) {
+            let in_radius = (x - cx) * (x - cx) + (y - cy) * (y - cy) <= r * r;
+            let in_bounds = x >= 0 && y >= 0 && x < width && y < width;
+
+            if in_radius && in_bounds {
+                columns.push((x as usize, y as usize));
+            }
+        }
+    }
+
+    return columns;
+}
+
+fn parse_ability(ability: &DataValue) -> Result<AbilityDefinition, String> {
+    let name = match ability.get("name").and_then(|n| n.as_str()) {
+        Some(name) => name.to_string(),
+        None => {
+            return Err("each ability requires a 'name'".to_string());
+        }
+    };
+
+    let context = format!("ability '{}'", name);
+
+    check_fields(
+        &context,
+        ability,
+        &[
+            "name",
+            "targeting",
+            "range",
+            "cooldown_ticks",
+            "energy_cost",
+            "effects",
+        ],
+    )?;
+
+    let targeting = match ability.get("targeting").and_then(|t| t.as_str()) {
+        Some("self") => AbilityTargeting::SelfTarget,
+        Some("point") => AbilityTargeting::Point,
+        Some("unit") => AbilityTargeting::Unit,
+        Some("passive") => AbilityTargeting::Passive,
+        other => {
+            return Err(format!(
+                "{}: 'targeting' must be one of self, point, unit or passive, found '{}'",
+                context,
+                other.unwrap_or("<none>")
+            ));
+        }
+    };
+
+    let effects = match ability.get("effects").and_then(|e| e.as_array()) {
+        Some(effects) => effects,
+        None => {
+            return Err(format!("{}: expected an 'effects' array", context));
+        }
+    };
+
+    let mut definition = AbilityDefinition {
+        name: name.clone(),
+        targeting: targeting,
+        range: read_u32_or(&context, ability, "range", 0)?,
+        cooldown_ticks: read_u32_or(&context, ability, "cooldown_ticks", 0)?,
+        energy_cost: read_u32_or(&context, ability, "energy_cost", 0)?,
+        effects: vec![],
+    };
+
+    for effect in effects.iter() {
+        definition.effects.push(parse_effect(&context, effect)?);
+    }
+
+    return Ok(definition);
+}
+
+fn parse_effect(context: &str, effect: &DataValue) -> Result<AbilityEffect, String> {
+    let effect_type = match effect.get("type").and_then(|t| t.as_str()) {
+        Some(t) => t,
+        None => {
+            return Err(format!("{}: each effect requires a 'type'", context));
+        }
+    };
+
+    let context = format!("{} effect '{}'", context, effect_type);
+
+    match effect_type {
+        "damage" | "heal" => {
+            check_fields(&context, effect, &["type", "amount", "radius"])?;
+
+            let amount = read_u32(&context, effect, "amount")?;
+            let radius = read_u32_or(&context, effect, "radius", 0)?;
+
+            if effect_type == "damage" {
+                return Ok(AbilityEffect::Damage {
+                    amount: amount,
+                    radius: radius,
+                });
+            }
+
+            return Ok(AbilityEffect::Heal {
+                amount: amount,
+                radius: radius,
+            });
+        }
+        "spawn" => {
+            check_fields(&context, effect, &["type", "unit", "count"])?;
+
+            let unit = match effect.get("unit").and_then(|u| u.as_str()) {
+                Some(unit) => unit.to_string(),
+                None => {
+                    return Err(format!("{}: is missing field 'unit'", context));
+                }
+            };
+
+            return Ok(AbilityEffect::Spawn {
+                unit: unit,
+                count: read_u32_or(&context, effect, "count", 1)?,
+            });
+        }
+        "voxel_dig" => {
+            check_fields(&context, effect, &["type", "radius"])?;
+
+            return Ok(AbilityEffect::VoxelDig {
+                radius: read_u32_or(&context, effect, "radius", 0)?,
+            });
+        }
+        "voxel_build" => {
+            check_fields(&context, effect, &["type", "radius", "voxel_type"])?;
+
+            let voxel_type = read_u32(&context, effect, "voxel_type")?;
+            if voxel_type > u8::MAX as u32 {
+                return Err(format!(
+                    "{}: field 'voxel_type' must be at most {}",
+                    context,
+                    u8::MAX
+                ));
+            }
+
+            return Ok(AbilityEffect::VoxelBuild {
+                radius: read_u32_or(&context, effect, "radius", 0)?,
+                voxel_type: voxel_type as u8,
+            });
+        }
+        _ => {
+            return Err(format!(
+                "{}: unknown type; expected one of damage, heal, spawn, voxel_dig or voxel_build",
+                context
+            ));
+        }
+    }
+}
+
+fn check_fields(context: &str, value: &DataValue, fields: &[&str]) -> Result<(), String> {
+    let members = match value.as_object() {
+        Some(m) => m,
+        None => {
+            return Err(format!("{}: must be an object", context));
+        }
+    };
+
+    for (key, _) in members.iter() {
+        if !fields.iter().any(|f| f == key) {
+            return Err(format!("{}: unknown field '{}'", context, key));
+        }
+    }
+
+    return Ok(());
+}
+
+fn read_u32(context: &str, value: &DataValue, field: &str) -> Result<u32, String> {
+    let raw = match value.get(field) {
+        Some(raw) => raw,
+        None => {
+            return Err(format!("{}: is missing field '{}'", context, field));
+        }
+    };
+
+    match raw.as_u32() {
+        Some(v) => {
+            return Ok(v);
+        }
+        None => {
+            return Err(format!(
+                "{}: field '{}' must be a non-negative integer, found '{}'",
+                context,
+                field,
+                raw.as_str().unwrap_or("<non-scalar>")
+            ));
+        }
+    }
+}
+
+fn read_u32_or(context: &str, value: &DataValue, field: &str, default: u32) -> Result<u32, String> {
+    if value.get(field).is_none() {
+        return Ok(default);
+    }
+
+    return read_u32(context, value, field);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ability_definitions_parse_point_ability_returns_expected() {
+        let source = "abilities: [
+            {
+                name: grenade
+                targeting: point
+                range: 1800
+                cooldown_ticks: 240
+                energy_cost: 25
+                effects: [
+                    { type: damage, amount: 8, radius: 600 }
+                    { type: voxel_dig, radius: 1 }
+                ]
+            }
+        ]";
+
+        let actual = AbilityDefinitions::parse(source).unwrap();
+
+        assert_eq!(
+            &AbilityDefinition {
+                name: "grenade".to_string(),
+                targeting: AbilityTargeting::Point,
+                range: 1800,
+                cooldown_ticks: 240,
+                energy_cost: 25,
+                effects: vec![
+                    AbilityEffect::Damage {
+                        amount: 8,
+                        radius: 600
+                    },
+                    AbilityEffect::VoxelDig { radius: 1 }
+                ]
+            },
+            actual.get("grenade").unwrap()
+        );
+    }
+
+    #[test]
+    fn ability_definitions_parse_optional_fields_use_defaults() {
+        let source = "abilities: [ { name: regen, targeting: passive, effects: [ { type: heal, amount: 1 } ] } ]";
+
+        let actual = AbilityDefinitions::parse(source).unwrap();
+        let regen = actual.get("regen").unwrap();
+
+        assert_eq!(0, regen.energy_cost);
+        assert_eq!(
+            vec![AbilityEffect::Heal {
+                amount: 1,
+                radius: 0
+            }],
+            regen.effects
+        );
+    }
+
+    #[test]
+    fn ability_definitions_parse_unknown_targeting_returns_err() {
+        let source = "abilities: [ { name: a, targeting: area, effects: [] } ]";
+
+        let actual = AbilityDefinitions::parse(source);
+
+        assert_eq!(
+            Err("ability 'a': 'targeting' must be one of self, point, unit or passive, found 'area'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn ability_definitions_parse_unknown_effect_returns_err() {
+        let source = "abilities: [ { name: a, targeting: self, effects: [ { type: teleport } ] } ]";
+
+        let actual = AbilityDefinitions::parse(source);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn ability_definitions_validate_unknown_spawn_unit_returns_err() {
+        let source =
+            "abilities: [ { name: a, targeting: self, effects: [ { type: spawn, unit: tank } ] } ]";
+        let abilities = AbilityDefinitions::parse(source).unwrap();
+
+        let actual = abilities.validate(&UnitDefinitions::new());
+
+        assert_eq!(
+            Err("ability 'a': spawns unknown unit 'tank'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn ability_definitions_from_path_shipped_datasheet_is_valid() {
+        let abilities = AbilityDefinitions::from_path(Path::new(ABILITY_DEFINITIONS_PATH));
+        assert!(abilities.is_ok(), "{:?}", abilities.err());
+
+        let units = UnitDefinitions::from_path(Path::new(
+            crate::cb_simulation::unit_definitions::UNIT_DEFINITIONS_PATH,
+        ))
+        .unwrap();
+
+        let actual = abilities.unwrap().validate(&units);
+
+        assert!(actual.is_ok(), "{:?}", actual.err());
+    }
+
+    const ABILITIES: &'static str = "abilities: [
+        { name: bolt, targeting: unit, range: 1000, cooldown_ticks: 3, energy_cost: 20, effects: [ { type: damage, amount: 5 } ] }
+        { name: grenade, targeting: point, range: 1000, cooldown_ticks: 3, effects: [ { type: voxel_dig, radius: 1 } ] }
+        { name: shield, targeting: self, cooldown_ticks: 3, effects: [ { type: heal, amount: 5 } ] }
+        { name: aura, targeting: passive, cooldown_ticks: 2, effects: [ { type: heal, amount: 1 } ] }
+    ]";
+
+    fn at(x: i32, y: i32) -> Coordinate2d {
+        return Coordinate2d::new(GameUnit::from_num(x), GameUnit::from_num(y));
+    }
+
+    fn unit_abilities(definitions: &AbilityDefinitions, names: &[&str]) -> AbilitiesComponent {
+        let slots = names
+            .iter()
+            .map(
+                |name| crate::cb_simulation::components::ability_components::AbilitySlot {
+                    ability: definitions.id_of(name).unwrap(),
+                    cooldown_remaining: 0,
+                },
+            )
+            .collect();
+
+        return AbilitiesComponent::new(100, 10, slots);
+    }
+
+    #[test]
+    fn tick_abilities_regens_energy_up_to_max() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["bolt"]);
+        unit.energy = 85;
+
+        tick_abilities(&mut unit, &definitions, false);
+        assert_eq!(85, unit.energy);
+
+        tick_abilities(&mut unit, &definitions, true);
+        assert_eq!(95, unit.energy);
+
+        tick_abilities(&mut unit, &definitions, true);
+        assert_eq!(100, unit.energy);
+    }
+
+    #[test]
+    fn tick_abilities_counts_down_cooldowns_and_fires_passives() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["bolt", "aura"]);
+        unit.slots[0].cooldown_remaining = 2;
+        unit.slots[1].cooldown_remaining = 2;
+
+        assert!(tick_abilities(&mut unit, &definitions, false).is_empty());
+        assert_eq!(1, unit.slots[0].cooldown_remaining);
+
+        let fired = tick_abilities(&mut unit, &definitions, false);
+
+        assert_eq!(vec![definitions.id_of("aura").unwrap()], fired);
+        assert_eq!(0, unit.slots[0].cooldown_remaining);
+        assert_eq!(2, unit.slots[1].cooldown_remaining);
+    }
+
+    #[test]
+    fn try_activate_pays_energy_and_starts_cooldown() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["bolt"]);
+
+        let actual = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Unit(7),
+            Some(at(600, 0)),
+        );
+
+        assert_eq!(
+            Some((definitions.id_of("bolt").unwrap(), at(600, 0))),
+            actual
+        );
+        assert_eq!(80, unit.energy);
+        assert_eq!(3, unit.slots[0].cooldown_remaining);
+
+        // Still cooling down
+        let actual = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Unit(7),
+            Some(at(600, 0)),
+        );
+        assert_eq!(None, actual);
+        assert_eq!(80, unit.energy);
+    }
+
+    #[test]
+    fn try_activate_without_energy_returns_none() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["bolt"]);
+        unit.energy = 19;
+
+        let actual = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Unit(7),
+            Some(at(600, 0)),
+        );
+
+        assert_eq!(None, actual);
+        assert_eq!(0, unit.slots[0].cooldown_remaining);
+    }
+
+    #[test]
+    fn try_activate_out_of_range_returns_none() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["bolt", "grenade", "shield"]);
+
+        let bolt = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Unit(7),
+            Some(at(800, 800)),
+        );
+        let grenade = try_activate(
+            &mut unit,
+            1,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Point { x: 0, y: 1001 },
+            None,
+        );
+        // Self abilities ignore range
+        let shield = try_activate(
+            &mut unit,
+            2,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Point { x: 0, y: 5000 },
+            None,
+        );
+
+        assert_eq!(None, bolt);
+        assert_eq!(None, grenade);
+        assert_eq!(
+            Some((definitions.id_of("shield").unwrap(), at(0, 0))),
+            shield
+        );
+        assert_eq!(100, unit.energy);
+    }
+
+    #[test]
+    fn try_activate_point_outside_game_unit_range_returns_none() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["grenade"]);
+
+        let actual = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Point {
+                x: i32::MAX,
+                y: i32::MIN,
+            },
+            None,
+        );
+
+        assert_eq!(None, actual);
+        assert_eq!(0, unit.slots[0].cooldown_remaining);
+    }
+
+    #[test]
+    fn try_activate_requires_matching_target() {
+        let definitions = AbilityDefinitions::parse(ABILITIES).unwrap();
+        let mut unit = unit_abilities(&definitions, &["bolt", "grenade", "aura"]);
+
+        let bolt_at_point = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Point { x: 10, y: 0 },
+            None,
+        );
+        let bolt_at_missing_unit = try_activate(
+            &mut unit,
+            0,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Unit(7),
+            None,
+        );
+        let passive = try_activate(
+            &mut unit,
+            2,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::None,
+            None,
+        );
+        let grenade_at_unit = try_activate(
+            &mut unit,
+            1,
+            &definitions,
+            at(0, 0),
+            CbAbilityTarget::Unit(7),
+            Some(at(300, 0)),
+        );
+
+        assert_eq!(None, bolt_at_point);
+        assert_eq!(None, bolt_at_missing_unit);
+        assert_eq!(None, passive);
+        assert_eq!(
+            Some((definitions.id_of("grenade").unwrap(), at(300, 0))),
+            grenade_at_unit
+        );
+    }
+
+    #[test]
+    fn units_in_radius_returns_target_or_units_within_radius() {
+        use specs::{Builder, World, WorldExt};
+
+        let mut world = World::new();
+        let (a, b, c) = (
+            world.create_entity().build(),
+            world.create_entity().build(),
+            world.create_entity().build(),
+        );
+        let units = vec![(a, at(0, 0)), (b, at(300, 400)), (c, at(301, 400))];
+
+        assert_eq!(vec![c], units_in_radius(&units, at(0, 0), 0, Some(c)));
+        assert_eq!(vec![a, b], units_in_radius(&units, at(0, 0), 500, Some(c)));
+    }
+
+    #[test]
+    fn affects_hit_points_damages_enemies_and_heals_allies() {
+        let damage = AbilityEffect::Damage {
+            amount: 5,
+            radius: 0,
+        };
+        let heal = AbilityEffect::Heal {
+            amount: 5,
+            radius: 0,
+        };
+        let spawn = AbilityEffect::Spawn {
+            unit: "worker".to_string(),
+            count: 1,
+        };
+
+        assert!(affects_hit_points(&damage, false));
+        assert!(!affects_hit_points(&damage, true));
+        assert!(affects_hit_points(&heal, true));
+        assert!(!affects_hit_points(&heal, false));
+        assert!(!affects_hit_points(&spawn, false));
+    }
+
+    #[test]
+    fn voxel_columns_stays_within_radius_and_map() {
+        let voxel = crate::cb_voxels::VOXEL_GAME_SIZE;
+        let center = at(voxel / 2, voxel * 5 + voxel / 2);
+
+        let actual = voxel_columns(center, 1, 10);
+
+        assert_eq!(vec![(0, 4), (0, 5), (0, 6), (1, 5)], actual);
+    }
+}
@@ -0,0 +1,324 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Actors remember the inputs their player sent over the last few ticks in a fixed size ring, so storage never grows.
+    The actor input system records one frame per tick, even when nothing was sent, so 'frames ago' is always measured
+    in ticks. Movement systems read the latest frame each tick, while action systems consume button presses so that a
+    single press only ever triggers a single action, even when it was buffered for a few ticks.
+*/
+
+use crate::cb_input;
+use cb_input::contexts::{CbContextManager, CbInputContexts, ContextId};
+use cb_input::CbGameInput;
+
+/// How many ticks of input each actor remembers.
+pub const ACTOR_INPUT_FRAMES: usize = 16;
+
+/// Identifies what a press is used for, so one button being consumed does not consume another sent on the same tick.
+/// Each mode numbers its own actions, below 32.
+pub type ActionId = u8;
+
+/// A fixed size history of values, newest first. Pushing past the capacity forgets the oldest value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InputHistory<T: Copy, const N: usize> {
+    frames: [T; N],
+    /// Index of the latest frame.
+    head: usize,
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> InputHistory<T, N> {
+    /// Create an empty history; the value only fills the unused slots.
+    pub fn new(empty: T) -> Self {
+        return Self {
+            frames: [empty; N],
+            head: 0,
+            len: 0,
+        };
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.head = (self.head + 1) % N;
+        self.frames[self.head] = value;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Returns the value from the given number of frames ago, where 0 is the latest.
+    pub fn get(&self, frames_ago: usize) -> Option<&T> {
+        if frames_ago >= self.len {
+            return None;
+        }
+
+        return Some(&self.frames[self.index(frames_ago)]);
+    }
+
+    pub fn get_mut(&mut self, frames_ago: usize) -> Option<&mut T> {
+        if frames_ago >= self.len {
+            return None;
+        }
+
+        let index = self.index(frames_ago);
+
+        return Some(&mut self.frames[index]);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    fn index(&self, frames_ago: usize) -> usize {
+        return (self.head + N - frames_ago) % N;
+    }
+}
+
+/// The contexts an actor's player sent on a single tick.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ActorFrame {
+    pub frame: usize,
+    pub contexts: CbContextManager,
+    /// Bit per action id, set once an action system has used the press.
+    consumed: u32,
+}
+
+impl ActorFrame {
+    fn new(frame: usize) -> Self {
+        return Self {
+            frame: frame,
+            contexts: CbContextManager::new(),
+            consumed: 0,
+        };
+    }
+
+    fn is_consumed(&self, action: ActionId) -> bool {
+        return self.consumed & action_bit(action) != 0;
+    }
+}
+
+fn action_bit(action: ActionId) -> u32 {
+    return 1 << (action % 32);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ActorController {
+    frames: InputHistory<ActorFrame, ACTOR_INPUT_FRAMES>,
+}
+
+impl ActorController {
+    pub fn new() -> Self {
+        return Self {
+            frames: InputHistory::new(ActorFrame::new(0)),
+        };
+    }
+
+    /// Record the tick's inputs. Should be called once every tick, with whatever the player sent; later contexts of
+    /// the same type replace earlier ones.
+    pub fn push(&mut self, frame: usize, inputs: &[&CbGameInput]) {
+        let mut actor_frame = ActorFrame::new(frame);
+
+        for input in inputs.iter() {
+            for context in input.context_manager.get_contexts().iter() {
+                if let Some(context) = context {
+                    actor_frame.contexts.add_context(*context);
+                }
+            }
+        }
+
+        self.frames.push(actor_frame);
+    }
+
+    /// Returns the recorded tick from the given number of ticks ago, where 0 is the current tick.
+    pub fn get(&self, frames_ago: usize) -> Option<&ActorFrame> {
+        return self.frames.get(frames_ago);
+    }
+
+    /// The context the player sent this tick, if any. Used by systems that react to held inputs every tick.
+    pub fn current(&self, context_id: ContextId) -> Option<CbInputContexts> {
+        return self.context(0, context_id);
+    }
+
+    /// The context the player sent the given number of ticks ago, if any.
+    pub fn context(&self, frames_ago: usize, context_id: ContextId) -> Option<CbInputContexts> {
+        return self
+            .frames
+            .get(frames_ago)
+            .and_then(|f| f.contexts.get_context(context_id));
+    }
+
+    /// Returns how many ticks ago the most recent unconsumed press of the action was, looking back no more than the
+    /// given number of ticks. A tick counts as a press when the predicate returns true for its context.
+    pub fn pressed_within<F>(
+        &self,
+        context_id: ContextId,
+        action: ActionId,
+        frames: usize,
+        pressed: F,
+    ) -> Option<usize>
+    where
+        F: Fn(&CbInputContexts) -> bool,
+    {
+        return (0..frames.min(self.frames.len())).find(|frames_ago| {
+            let frame = self.frames.get(*frames_ago).unwrap();
+
+            return !frame.is_consumed(action)
+                && frame
+                    .contexts
+                    .get_context(context_id)
+                    .map_or(false, |c| pressed(&c));
+        });
+    }
+
+    pub fn was_pressed_within<F>(
+        &self,
+        context_id: ContextId,
+        action: ActionId,
+        frames: usize,
+        pressed: F,
+    ) -> bool
+    where
+        F: Fn(&CbInputContexts) -> bool,
+    {
+        return self
+            .pressed_within(context_id, action, frames, pressed)
+            .is_some();
+    }
+
+    /// Like was_pressed_within, but marks the press as used so it will not trigger anything else.
+    pub fn consume_pressed_within<F>(
+        &mut self,
+        context_id: ContextId,
+        action: ActionId,
+        frames: usize,
+        pressed: F,
+    ) -> bool
+    where
+        F: Fn(&CbInputContexts) -> bool,
+    {
+        match self.pressed_within(context_id, action, frames, pressed) {
+            Some(frames_ago) => {
+                let frame = self.frames.get_mut(frames_ago).unwrap();
+                frame.consumed |= action_bit(action);
+
+                return true;
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cb_input::contexts::SHOOTER_CONTEXT_ID;
+    use cb_input::input_type::Press;
+
+    const FIRE: ActionId = 0;
+
+    fn shooter_input(fire: Press) -> CbGameInput {
+        let mut context = cb_input::contexts::shooter_context::new_shooter_context();
+        if let CbInputContexts::ShooterContext { fire: f, .. } = &mut context {
+            *f = fire;
+        }
+
+        let mut context_manager = CbContextManager::new();
+        context_manager.add_context(context);
+
+        return CbGameInput::new(0, context_manager);
+    }
+
+    fn fired(context: &CbInputContexts) -> bool {
+        match context {
+            CbInputContexts::ShooterContext { fire, .. } => *fire == Press::Pressed,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn input_history_is_bounded_and_newest_first() {
+        let mut history: InputHistory<usize, 4> = InputHistory::new(0);
+
+        for i in 1..=10 {
+            history.push(i);
+        }
+
+        assert_eq!(4, history.len());
+        assert_eq!(Some(&10), history.get(0));
+        assert_eq!(Some(&7), history.get(3));
+        assert_eq!(None, history.get(4));
+    }
+
+    #[test]
+    fn actor_controller_remembers_only_recent_frames() {
+        let mut controller = ActorController::new();
+        let input = shooter_input(Press::NotPressed);
+
+        for frame in 0..ACTOR_INPUT_FRAMES * 3 {
+            controller.push(frame, &[&input]);
+        }
+
+        assert_eq!(ACTOR_INPUT_FRAMES * 3 - 1, controller.get(0).unwrap().frame);
+        assert_eq!(None, controller.get(ACTOR_INPUT_FRAMES));
+    }
+
+    #[test]
+    fn actor_controller_ticks_without_input_have_no_context() {
+        let mut controller = ActorController::new();
+        controller.push(0, &[&shooter_input(Press::NotPressed)]);
+        controller.push(1, &[]);
+
+        assert_eq!(None, controller.current(SHOOTER_CONTEXT_ID));
+        assert_eq!(true, controller.context(1, SHOOTER_CONTEXT_ID).is_some());
+    }
+
+    #[test]
+    fn actor_controller_was_pressed_within_frames() {
+        let mut controller = ActorController::new();
+        controller.push(0, &[&shooter_input(Press::Pressed)]);
+        for frame in 1..4 {
+            controller.push(frame, &[&shooter_input(Press::NotPressed)]);
+        }
+
+        assert_eq!(
+            Some(3),
+            controller.pressed_within(SHOOTER_CONTEXT_ID, FIRE, 5, fired)
+        );
+        assert_eq!(
+            false,
+            controller.was_pressed_within(SHOOTER_CONTEXT_ID, FIRE, 3, fired)
+        );
+    }
+
+    #[test]
+    fn actor_controller_consumed_press_only_triggers_once() {
+        let mut controller = ActorController::new();
+        controller.push(0, &[&shooter_input(Press::Pressed)]);
+        controller.push(1, &[&shooter_input(Press::NotPressed)]);
+
+        assert_eq!(
+            true,
+            controller.consume_pressed_within(SHOOTER_CONTEXT_ID, FIRE, 5, fired)
+        );
+        assert_eq!(
+            false,
+            controller.consume_pressed_within(SHOOTER_CONTEXT_ID, FIRE, 5, fired)
+        );
+    }
+
+    #[test]
+    fn actor_controller_consuming_an_action_leaves_others_on_the_same_tick() {
+        let mut controller = ActorController::new();
+        controller.push(0, &[&shooter_input(Press::Pressed)]);
+
+        controller.consume_pressed_within(SHOOTER_CONTEXT_ID, FIRE, 5, fired);
+
+        assert_eq!(
+            true,
+            controller.was_pressed_within(SHOOTER_CONTEXT_ID, FIRE + 1, 5, fired)
+        );
+    }
+}
@@ -0,0 +1,701 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Computer controlled players. Each AI reads what its team can see of the world and issues the same commands a person
+    would, packaged as a CbGameInput for its player. AI state lives in the world and decisions only use integer math on
+    the simulation state, so every peer produces the same inputs and rollbacks replay them exactly.
+*/
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, GameUnit};
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::{
+    new_rts_command_context, push_rts_command, CbRtsCommand, UnitId, MAX_RTS_COMMANDS,
+};
+use cb_input::contexts::CbContextManager;
+use cb_input::CbGameInput;
+
+use crate::cb_voxels;
+
+use super::economy::{ResourceType, Resources};
+use super::fog_of_war::FOG_GRID_SIZE;
+use super::unit_definitions::{UnitDefinitions, UnitTypeId};
+
+/// How often, in ticks, an AI reconsiders what to do.
+pub const AI_THINK_TICKS: u32 = 15;
+
+/// The units an AI trains, in order, before repeating the last entry.
+pub const DEFAULT_BUILD_ORDER: [&'static str; 8] = [
+    "worker", "worker", "infantry", "worker", "scout", "infantry", "infantry", "infantry",
+];
+
+/// How many combat units an AI gathers before sending its first attack wave.
+const FIRST_WAVE_SIZE: usize = 3;
+
+/// How many more units each following wave waits for.
+const WAVE_SIZE_GROWTH: usize = 2;
+
+const MAX_WAVE_SIZE: usize = 12;
+
+/// A unit the AI's player owns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiUnit {
+    pub id: UnitId,
+    pub position: Coordinate2d,
+    pub move_speed: u32,
+    pub can_attack: bool,
+    pub is_worker: bool,
+    /// Whether the unit has no harvest, move or attack order.
+    pub is_idle: bool,
+    /// Where the unit is currently attack moving to, if anywhere.
+    pub attack_target: Option<Coordinate2d>,
+    pub produces: Vec<UnitTypeId>,
+    pub production_full: bool,
+}
+
+impl AiUnit {
+    fn is_structure(&self) -> bool {
+        return self.move_speed == 0;
+    }
+
+    fn is_army(&self) -> bool {
+        return self.can_attack && !self.is_worker && !self.is_structure();
+    }
+}
+
+/// What an AI knows about the world: its own units, and whatever its team can currently see.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AiView {
+    pub units: Vec<AiUnit>,
+    /// Visible enemies, as (unit, position, is a structure).
+    pub visible_enemies: Vec<(UnitId, Coordinate2d, bool)>,
+    /// Visible resource nodes, as (unit, resource, position).
+    pub resource_nodes: Vec<(UnitId, ResourceType, Coordinate2d)>,
+    pub stockpile: Resources,
+    pub supply_available: u32,
+    /// Whether the team has ever seen each scouting waypoint, in the order of scouting_waypoints().
+    pub explored_waypoints: Vec<bool>,
+}
+
+/// Points spread across the map for scouts to visit, in fog cells.
+pub fn scouting_waypoints() -> Vec<(i32, i32)> {
+    let far = FOG_GRID_SIZE as i32 - 1;
+    let mid = far / 2;
+
+    return vec![
+        (far, far),
+        (mid, mid),
+        (far, 0),
+        (0, far),
+        (mid, far),
+        (far, mid),
+        (0, 0),
+    ];
+}
+
+fn cell_center(cell: (i32, i32)) -> Coordinate2d {
+    let voxel = cb_voxels::VOXEL_GAME_SIZE;
+
+    return Coordinate2d::new(
+        GameUnit::from_num(cell.0 * voxel + voxel / 2),
+        GameUnit::from_num(cell.1 * voxel + voxel / 2),
+    );
+}
+
+/// The nearest visible node of the resource to the position; ties go to the first found.
+fn nearest_node(view: &AiView, position: Coordinate2d, resource: ResourceType) -> Option<UnitId> {
+    let mut nearest: Option<(i64, UnitId)> = None;
+
+    for (node, node_resource, node_position) in view.resource_nodes.iter() {
+        if *node_resource != resource {
+            continue;
+        }
+
+        let distance = position.distance_squared(node_position);
+        if nearest.is_none() || distance < nearest.unwrap().0 {
+            nearest = Some((distance, *node));
+        }
+    }
+
+    return nearest.map(|(_, node)| node);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiPlayer {
+    player_id: usize,
+    build_order: Vec<UnitTypeId>,
+    build_step: usize,
+    scout: Option<UnitId>,
+    crystal_worker: Option<UnitId>,
+    next_waypoint: usize,
+    enemy_base: Option<Coordinate2d>,
+    wave_size: usize,
+    attacking: bool,
+}
+
+impl AiPlayer {
+    /// Create an AI for the player, following the build order. Unknown unit types are skipped.
+    pub fn new(player_id: usize, build_order: &[&str], definitions: &UnitDefinitions) -> Self {
+        return Self {
+            player_id: player_id,
+            build_order: build_order
+                .iter()
+                .filter_map(|name| definitions.id_of(name))
+                .collect(),
+            build_step: 0,
+            scout: None,
+            crystal_worker: None,
+            next_waypoint: 0,
+            enemy_base: None,
+            wave_size: FIRST_WAVE_SIZE,
+            attacking: false,
+        };
+    }
+
+    pub fn player_id(&self) -> usize {
+        return self.player_id;
+    }
+
+    /// Decide on this tick's commands, most important first. Only as many as fit in a frame are decided on, so whatever
+    /// doesn't fit is left for a later tick instead of being remembered as done.
+    pub fn think(&mut self, view: &AiView, definitions: &UnitDefinitions) -> Vec<CbRtsCommand> {
+        let mut commands = vec![];
+
+        self.remember_enemy_base(view);
+
+        if let Some(command) = self.train(view, definitions) {
+            commands.push(command);
+        }
+
+        // Gather crystal while the next unit is waiting on it
+        let needs_crystal = self
+            .next_unit_cost(definitions)
+            .map_or(false, |cost| view.stockpile.crystal < cost.crystal);
+
+        if needs_crystal {
+            if let Some(command) = self.gather_crystal(view) {
+                commands.push(command);
+            }
+        } else {
+            self.crystal_worker = None;
+        }
+
+        // Idle workers and attackers that don't fit are still idle or off target next time
+        commands.append(&mut self.harvest(view));
+        commands.truncate(MAX_RTS_COMMANDS);
+
+        if commands.len() < MAX_RTS_COMMANDS {
+            if let Some(command) = self.scout(view) {
+                commands.push(command);
+            }
+        }
+
+        commands.append(&mut self.attack(view));
+        commands.truncate(MAX_RTS_COMMANDS);
+
+        return commands;
+    }
+
+    fn remember_enemy_base(&mut self, view: &AiView) {
+        if self.enemy_base.is_some() {
+            return;
+        }
+
+        self.enemy_base = view
+            .visible_enemies
+            .iter()
+            .find(|(_, _, is_structure)| *is_structure)
+            .map(|(_, position, _)| *position);
+    }
+
+    fn next_unit(&self) -> Option<UnitTypeId> {
+        let last = self.build_order.len().checked_sub(1)?;
+
+        return Some(self.build_order[self.build_step.min(last)]);
+    }
+
+    fn next_unit_cost(&self, definitions: &UnitDefinitions) -> Option<Resources> {
+        let unit_type = self.next_unit()?;
+
+        return definitions.get_by_id(unit_type).map(|d| d.cost.resources);
+    }
+
+    /// Train the next unit in the build order once it can be afforded.
+    fn train(&mut self, view: &AiView, definitions: &UnitDefinitions) -> Option<CbRtsCommand> {
+        let unit_type = self.next_unit()?;
+        let cost = &definitions.get_by_id(unit_type)?.cost;
+
+        if !view.stockpile.can_afford(&cost.resources) || view.supply_available < cost.supply {
+            return None;
+        }
+
+        let structure = view
+            .units
+            .iter()
+            .find(|u| u.produces.contains(&unit_type) && !u.production_full)?;
+
+        self.build_step += 1;
+
+        return Some(CbRtsCommand::Train {
+            structure: structure.id,
+            unit_type: unit_type,
+        });
+    }
+
+    /// Send idle workers to the nearest ore, or crystal if there is no ore in sight.
+    fn harvest(&self, view: &AiView) -> Vec<CbRtsCommand> {
+        let mut commands = vec![];
+
+        let idle_workers = view
+            .units
+            .iter()
+            .filter(|u| u.is_worker && u.is_idle && Some(u.id) != self.crystal_worker);
+
+        for worker in idle_workers {
+            let node = nearest_node(view, worker.position, ResourceType::Ore).or(nearest_node(
+                view,
+                worker.position,
+                ResourceType::Crystal,
+            ));
+
+            if let Some(node) = node {
+                commands.push(CbRtsCommand::Harvest {
+                    worker: worker.id,
+                    node: node,
+                });
+            }
+        }
+
+        return commands;
+    }
+
+    /// Dedicate a worker, preferably an idle one, to gathering crystal.
+    fn gather_crystal(&mut self, view: &AiView) -> Option<CbRtsCommand> {
+        let assigned = self.crystal_worker.map_or(false, |id| {
+            view.units.iter().any(|u| u.id == id && !u.is_idle)
+        });
+
+        if assigned {
+            return None;
+        }
+
+        let worker = view
+            .units
+            .iter()
+            .find(|u| u.is_worker && u.is_idle)
+            .or(view.units.iter().find(|u| u.is_worker))?;
+        let node = nearest_node(view, worker.position, ResourceType::Crystal)?;
+
+        self.crystal_worker = Some(worker.id);
+
+        return Some(CbRtsCommand::Harvest {
+            worker: worker.id,
+            node: node,
+        });
+    }
+
+    /// Keep the fastest combat unit visiting unexplored waypoints until the enemy base is found.
+    fn scout(&mut self, view: &AiView) -> Option<CbRtsCommand> {
+        if self.enemy_base.is_some() {
+            self.scout = None;
+            return None;
+        }
+
+        let scout = match self
+            .scout
+            .and_then(|id| view.units.iter().find(|u| u.id == id))
+        {
+            Some(scout) => scout,
+            None => {
+                let mut fastest: Option<&AiUnit> = None;
+                for unit in view.units.iter().filter(|u| u.is_army()) {
+                    if fastest.is_none() || unit.move_speed > fastest.unwrap().move_speed {
+                        fastest = Some(unit);
+                    }
+                }
+
+                let scout = fastest?;
+                self.scout = Some(scout.id);
+                self.next_waypoint = 0;
+
+                // Newly picked scouts start moving right away
+                return self.next_scouting_command(scout, view);
+            }
+        };
+
+        if !scout.is_idle {
+            return None;
+        }
+
+        self.next_waypoint += 1;
+
+        return self.next_scouting_command(scout, view);
+    }
+
+    fn next_scouting_command(&mut self, scout: &AiUnit, view: &AiView) -> Option<CbRtsCommand> {
+        let waypoints = scouting_waypoints();
+
+        // Skip waypoints the team has already seen, falling back to cycling through all of them
+        let unexplored = (0..waypoints.len())
+            .map(|i| (self.next_waypoint + i) % waypoints.len())
+            .find(|i| !view.explored_waypoints.get(*i).copied().unwrap_or(false));
+
+        self.next_waypoint = unexplored.unwrap_or(self.next_waypoint % waypoints.len());
+
+        let target = cell_center(waypoints[self.next_waypoint]);
+
+        return Some(CbRtsCommand::Move {
+            unit: scout.id,
+            x: target.x.to_num::<i32>(),
+            y: target.y.to_num::<i32>(),
+        });
+    }
+
+    /// Gather combat units until the wave is large enough, then attack move them all towards the enemy.
+    fn attack(&mut self, view: &AiView) -> Vec<CbRtsCommand> {
+        let army: Vec<&AiUnit> = view
+            .units
+            .iter()
+            .filter(|u| u.is_army() && Some(u.id) != self.scout)
+            .collect();
+
+        if self.attacking && army.is_empty() {
+            // The wave was wiped out; the next one needs to be larger
+            self.attacking = false;
+            self.wave_size = (self.wave_size + WAVE_SIZE_GROWTH).min(MAX_WAVE_SIZE);
+        }
+
+        if !self.attacking && army.len() >= self.wave_size {
+            self.attacking = true;
+        }
+
+        if !self.attacking {
+            return vec![];
+        }
+
+        // Head for the nearest visible enemy, then the enemy base, then wherever has not been explored yet
+        let target = view
+            .visible_enemies
+            .first()
+            .map(|(_, position, _)| *position)
+            .or(self.enemy_base)
+            .unwrap_or_else(|| {
+                let waypoints = scouting_waypoints();
+                let waypoint = (0..waypoints.len())
+                    .find(|i| !view.explored_waypoints.get(*i).copied().unwrap_or(false))
+                    .unwrap_or(0);
+
+                cell_center(waypoints[waypoint])
+            });
+
+        return army
+            .iter()
+            .filter(|u| u.attack_target != Some(target))
+            .map(|u| CbRtsCommand::AttackMove {
+                unit: u.id,
+                x: target.x.to_num::<i32>(),
+                y: target.y.to_num::<i32>(),
+            })
+            .collect();
+    }
+}
+
+/// The computer controlled players in the match, and the inputs they produced this tick. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CbAiPlayers {
+    pub players: Vec<AiPlayer>,
+    pub pending_inputs: Vec<CbGameInput>,
+}
+
+impl CbAiPlayers {
+    pub fn new() -> Self {
+        return Self {
+            players: vec![],
+            pending_inputs: vec![],
+        };
+    }
+
+    /// Have the computer control the player, replacing any AI already controlling it.
+    pub fn add_player(&mut self, player: AiPlayer) {
+        self.players.retain(|p| p.player_id != player.player_id);
+        self.players.push(player);
+        self.players.sort_by_key(|p| p.player_id);
+    }
+
+    pub fn is_ai_player(&self, player_id: usize) -> bool {
+        return self.players.iter().any(|p| p.player_id == player_id);
+    }
+}
+
+/// Package the commands into the input a player would send. Commands past what fits in a frame are dropped.
+pub fn build_input(player_id: usize, commands: &Vec<CbRtsCommand>) -> CbGameInput {
+    let mut context = new_rts_command_context();
+
+    for command in commands.iter() {
+        if !push_rts_command(&mut context, *command) {
+            break;
+        }
+    }
+
+    let mut context_manager = CbContextManager::new();
+    context_manager.add_context(context);
+
+    return CbGameInput::new(player_id, context_manager);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNITS: &'static str = "units: [
+        {
+            name: worker
+            cost: { ore: 50, crystal: 0, supply: 1, build_ticks: 60 }
+            components: { move_speed: { value: 20 } }
+        }
+        {
+            name: infantry
+            cost: { ore: 50, crystal: 0, supply: 1, build_ticks: 60 }
+            components: { move_speed: { value: 10 } }
+        }
+        {
+            name: headquarters
+            components: { production: { queue_size: 5, units: [worker, infantry] } }
+        }
+    ]";
+
+    fn at(x: i32, y: i32) -> Coordinate2d {
+        return Coordinate2d::new(GameUnit::from_num(x), GameUnit::from_num(y));
+    }
+
+    fn unit(id: UnitId, position: Coordinate2d) -> AiUnit {
+        return AiUnit {
+            id: id,
+            position: position,
+            move_speed: 10,
+            can_attack: true,
+            is_worker: false,
+            is_idle: true,
+            attack_target: None,
+            produces: vec![],
+            production_full: false,
+        };
+    }
+
+    fn headquarters(definitions: &UnitDefinitions) -> AiUnit {
+        let mut hq = unit(0, at(0, 0));
+        hq.move_speed = 0;
+        hq.can_attack = false;
+        hq.produces = vec![
+            definitions.id_of("worker").unwrap(),
+            definitions.id_of("infantry").unwrap(),
+        ];
+
+        return hq;
+    }
+
+    #[test]
+    fn ai_player_think_trains_build_order_when_affordable() {
+        let definitions = UnitDefinitions::parse(UNITS).unwrap();
+        let mut ai = AiPlayer::new(1, &["worker", "infantry"], &definitions);
+        let mut view = AiView::default();
+        view.units.push(headquarters(&definitions));
+        view.supply_available = 10;
+
+        assert_eq!(Vec::<CbRtsCommand>::new(), ai.think(&view, &definitions));
+
+        view.stockpile = Resources::new(100, 0);
+        let first = ai.think(&view, &definitions);
+        let second = ai.think(&view, &definitions);
+        let third = ai.think(&view, &definitions);
+
+        let worker = definitions.id_of("worker").unwrap();
+        let infantry = definitions.id_of("infantry").unwrap();
+        assert_eq!(
+            vec![CbRtsCommand::Train {
+                structure: 0,
+                unit_type: worker
+            }],
+            first
+        );
+        assert_eq!(
+            vec![CbRtsCommand::Train {
+                structure: 0,
+                unit_type: infantry
+            }],
+            second
+        );
+        assert_eq!(second, third);
+    }
+
+    #[test]
+    fn ai_player_think_sends_idle_workers_to_nearest_ore() {
+        let definitions = UnitDefinitions::parse(UNITS).unwrap();
+        let mut ai = AiPlayer::new(1, &[], &definitions);
+        let mut worker = unit(4, at(0, 0));
+        worker.is_worker = true;
+        worker.can_attack = false;
+
+        let view = AiView {
+            units: vec![worker],
+            resource_nodes: vec![
+                (7, ResourceType::Crystal, at(100, 0)),
+                (8, ResourceType::Ore, at(900, 0)),
+                (9, ResourceType::Ore, at(600, 0)),
+            ],
+            ..AiView::default()
+        };
+
+        let actual = ai.think(&view, &definitions);
+
+        assert_eq!(vec![CbRtsCommand::Harvest { worker: 4, node: 9 }], actual);
+    }
+
+    #[test]
+    fn ai_player_think_sends_worker_to_crystal_when_next_unit_needs_it() {
+        let units = UNITS.replace(
+            "cost: { ore: 50, crystal: 0, supply: 1, build_ticks: 60 }\n            components: { move_speed: { value: 10 } }",
+            "cost: { ore: 50, crystal: 10, supply: 1, build_ticks: 60 }\n            components: { move_speed: { value: 10 } }",
+        );
+        let definitions = UnitDefinitions::parse(&units).unwrap();
+        let mut ai = AiPlayer::new(1, &["infantry"], &definitions);
+        let mut worker = unit(4, at(0, 0));
+        worker.is_worker = true;
+        worker.can_attack = false;
+        worker.is_idle = false;
+
+        let view = AiView {
+            units: vec![worker],
+            resource_nodes: vec![
+                (8, ResourceType::Ore, at(100, 0)),
+                (9, ResourceType::Crystal, at(900, 0)),
+            ],
+            ..AiView::default()
+        };
+
+        let actual = ai.think(&view, &definitions);
+
+        assert_eq!(vec![CbRtsCommand::Harvest { worker: 4, node: 9 }], actual);
+    }
+
+    #[test]
+    fn ai_player_think_scouts_first_unexplored_waypoint() {
+        let definitions = UnitDefinitions::parse(UNITS).unwrap();
+        let mut ai = AiPlayer::new(1, &[], &definitions);
+        let mut explored = vec![false; scouting_waypoints().len()];
+        explored[0] = true;
+
+        let view = AiView {
+            units: vec![unit(2, at(0, 0))],
+            explored_waypoints: explored,
+            ..AiView::default()
+        };
+
+        let actual = ai.think(&view, &definitions);
+
+        let expected = cell_center(scouting_waypoints()[1]);
+        assert_eq!(
+            vec![CbRtsCommand::Move {
+                unit: 2,
+                x: expected.x.to_num::<i32>(),
+                y: expected.y.to_num::<i32>()
+            }],
+            actual
+        );
+    }
+
+    #[test]
+    fn ai_player_think_attacks_enemy_base_once_wave_is_ready() {
+        let definitions = UnitDefinitions::parse(UNITS).unwrap();
+        let mut ai = AiPlayer::new(1, &[], &definitions);
+        let enemy_base = at(4000, 4000);
+        let mut view = AiView {
+            units: vec![unit(1, at(0, 0)), unit(2, at(0, 0))],
+            visible_enemies: vec![(20, enemy_base, true)],
+            ..AiView::default()
+        };
+
+        assert_eq!(Vec::<CbRtsCommand>::new(), ai.think(&view, &definitions));
+
+        view.units.push(unit(3, at(0, 0)));
+        view.units[0].attack_target = Some(enemy_base);
+        let actual = ai.think(&view, &definitions);
+
+        assert_eq!(
+            vec![
+                CbRtsCommand::AttackMove {
+                    unit: 2,
+                    x: 4000,
+                    y: 4000
+                },
+                CbRtsCommand::AttackMove {
+                    unit: 3,
+                    x: 4000,
+                    y: 4000
+                }
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn ai_player_think_with_full_frame_keeps_scouting_for_later() {
+        let definitions = UnitDefinitions::parse(UNITS).unwrap();
+        let mut ai = AiPlayer::new(1, &[], &definitions);
+        let mut view = AiView {
+            units: vec![unit(2, at(0, 0))],
+            resource_nodes: vec![(9, ResourceType::Ore, at(600, 0))],
+            explored_waypoints: vec![false; scouting_waypoints().len()],
+            ..AiView::default()
+        };
+
+        for id in 10..(10 + MAX_RTS_COMMANDS as UnitId + 1) {
+            let mut worker = unit(id, at(0, 0));
+            worker.is_worker = true;
+            worker.can_attack = false;
+            view.units.push(worker);
+        }
+
+        let first = ai.think(&view, &definitions);
+
+        assert_eq!(MAX_RTS_COMMANDS, first.len());
+        assert!(first.iter().all(|c| match c {
+            CbRtsCommand::Harvest { worker: _, node: _ } => true,
+            _ => false,
+        }));
+
+        // Once the workers are busy, the scout starts at the first waypoint rather than skipping it
+        for unit in view.units.iter_mut().filter(|u| u.is_worker) {
+            unit.is_idle = false;
+        }
+        let second = ai.think(&view, &definitions);
+
+        let expected = cell_center(scouting_waypoints()[0]);
+        assert_eq!(
+            vec![CbRtsCommand::Move {
+                unit: 2,
+                x: expected.x.to_num::<i32>(),
+                y: expected.y.to_num::<i32>()
+            }],
+            second
+        );
+    }
+
+    #[test]
+    fn build_input_drops_commands_past_frame_limit() {
+        let commands = vec![CbRtsCommand::SetReady { ready: true }; 10];
+
+        let actual = build_input(3, &commands);
+
+        let context = actual
+            .context_manager
+            .get_context(cb_input::contexts::RTS_COMMAND_CONTEXT_ID)
+            .unwrap();
+        assert_eq!(3, actual.player_id);
+        assert_eq!(
+            cb_input::contexts::rts_command_context::MAX_RTS_COMMANDS,
+            cb_input::contexts::rts_command_context::get_rts_commands(&context).len()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, Coordinate3d, GameUnit};
+
+use crate::cb_math;
+use cb_math::FUint;
+
+use crate::cb_simulation::fighting::fighter_definitions::FighterDefinitions;
+use crate::cb_simulation::fighting::Facing;
+
+use crate::cb_simulation::components::{
+    actor_components, character_components, fighting_components, gfx_components,
+    ownership_components, physics_components,
+};
+use actor_components::ActorComponent;
+use character_components::HitPointsComponent;
+use fighting_components::FighterComponent;
+use gfx_components::{CameraComponent, CameraController, SpriteComponent};
+use ownership_components::OwnershipComponent;
+use physics_components::TransformComponent;
+
+/// Camera placement for a side view of the stage, in game units, as (height, distance).
+const SIDE_CAMERA: (i32, i32) = (1200, 7500);
+
+/// Create a player's fighter at the position along the stage, in game units.
+pub fn new_fighter(
+    world: &mut specs::World,
+    player_id: usize,
+    fighter: &str,
+    x: i32,
+    facing: Facing,
+) -> Result<Entity, String> {
+    let (index, health) = {
+        let definitions = world.read_resource::<FighterDefinitions>();
+
+        let index = match definitions.index_of(fighter) {
+            Some(index) => index,
+            None => {
+                return Err(format!("unknown fighter '{}'", fighter));
+            }
+        };
+
+        (index, definitions.get(index).unwrap().health)
+    };
+
+    let mut transform = TransformComponent::new();
+    transform.world_position = Coordinate2d::new(GameUnit::from_num(x), GameUnit::from_num(0));
+
+    let health = FUint::from_num(health);
+
+    return Ok(world
+        .create_entity()
+        .with(ActorComponent::new(player_id))
+        .with(OwnershipComponent::new(player_id))
+        .with(HitPointsComponent::new(health, health))
+        .with(FighterComponent::new(index, GameUnit::from_num(x), facing))
+        .with(transform)
+        .with(SpriteComponent::new())
+        .build());
+}
+
+/// Create a camera looking at the stage from the side.
+pub fn new_side_camera(world: &mut specs::World) -> Entity {
+    let (height, distance) = SIDE_CAMERA;
+
+    let (zero, height, distance) = (
+        GameUnit::from_num(0),
+        GameUnit::from_num(height),
+        GameUnit::from_num(distance),
+    );
+
+    // The stage is drawn standing up, so y is up
+    let mut camera = CameraComponent::new(CameraController::Fixed);
+    camera.up = Coordinate3d::new(zero, GameUnit::from_num(1), zero);
+    camera.snap_to(
+        Coordinate3d::new(zero, height, distance),
+        Coordinate3d::new(zero, height, zero),
+    );
+
+    return world.create_entity().with(camera).build();
+}
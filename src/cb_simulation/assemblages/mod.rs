@@ -1,3 +1,5 @@
 // Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+pub mod fighting_assemblages;
 pub mod rts_assemblages;
+pub mod shooter_assemblages;
 pub mod voxel_editor_assemblages;
@@ -3,45 +3,299 @@
 extern crate specs;
 use specs::prelude::*;
 
-use crate::cb_math;
-use cb_math::FUint;
+use crate::cb_system;
+use cb_system::{Coordinate2d, Coordinate3d, GameUnit};
+
+use crate::cb_simulation::unit_definitions;
+use unit_definitions::{UnitComponentDefinition, UnitDefinitions};
+
+use crate::cb_simulation::abilities::{AbilityDefinitions, AbilityTargeting};
+
+use crate::cb_simulation::economy;
+use economy::{CbSpawnQueue, ResourceType};
+
+use crate::cb_animation;
+use cb_animation::AnimationLibrary;
+
+use crate::cb_simulation::unit_animations;
+
+use crate::cb_simulation::locomotion;
+use locomotion::{Gait, Legs};
+
+use crate::cb_simulation::components::ability_components;
+use ability_components::{AbilitiesComponent, AbilitySlot};
+
+use crate::cb_simulation::components::animation_components;
+use animation_components::AnimationComponent;
+
+use crate::cb_simulation::components::actor_components;
+use actor_components::ActorComponent;
+
+use crate::cb_simulation::components::ownership_components;
+use ownership_components::OwnershipComponent;
 
 use crate::cb_simulation::components::character_components;
 use character_components::{
-    ArmorComponent, HitPointsComponent, MoveSpeedComponent, RangedAttackComponent,
+    ArmorComponent, HitPointsComponent, MoveSpeedComponent, RangedAttackComponent, SightComponent,
     UnitBaseComponent,
 };
 
+use crate::cb_simulation::components::economy_components;
+use economy_components::{
+    DropOffComponent, ProductionComponent, ResourceNodeComponent, SupplyComponent,
+    SupplyProviderComponent, WorkerComponent,
+};
+
+use crate::cb_simulation::components::match_components;
+use match_components::ObjectiveComponent;
+
+use crate::cb_simulation::components::order_components;
+use order_components::UnitOrderComponent;
+
 use crate::cb_simulation::components::physics_components;
 use physics_components::{TransformComponent, VelocityComponent};
 
 use crate::cb_simulation::components::gfx_components;
-use gfx_components::SpriteComponent;
+use gfx_components::{CameraComponent, CameraController, SpriteComponent};
+
+use crate::cb_simulation::components::ik_components;
+use ik_components::LegsComponent;
+
+/// Spawn a unit of the given type, as described in the world's UnitDefinitions, at the position for the player.
+pub fn new_unit(
+    world: &mut specs::World,
+    unit_type: &str,
+    position: Coordinate2d,
+    player_id: usize,
+) -> Result<Entity, String> {
+    let definition;
+    let produces;
+    {
+        let definitions = world.read_resource::<UnitDefinitions>();
+
+        definition = match definitions.get(unit_type) {
+            Some(d) => d.clone(),
+            None => {
+                return Err(format!(
+                    "unknown unit type '{}'; expected one of {:?}",
+                    unit_type,
+                    definitions.names()
+                ));
+            }
+        };
+
+        // Production refers to units by name in the datasheet, but by id in the simulation
+        produces = definition
+            .components
+            .iter()
+            .map(|c| match c {
+                UnitComponentDefinition::Production {
+                    queue_size: _,
+                    units,
+                } => units.iter().filter_map(|u| definitions.id_of(u)).collect(),
+                _ => vec![],
+            })
+            .flatten()
+            .collect::<Vec<unit_definitions::UnitTypeId>>();
+    }
 
-pub fn new_unit(world: &mut specs::World) {
-    // RTS components
-    let armor = ArmorComponent::new(FUint::from_num(20), FUint::from_num(20));
-    let hit_points = HitPointsComponent::new(FUint::from_num(20), FUint::from_num(20));
-    let move_speed = MoveSpeedComponent::new(FUint::from_num(10));
-    let ranged_atk =
-        RangedAttackComponent::new(FUint::from_num(1), FUint::from_num(20), FUint::from_num(1));
-    let base = UnitBaseComponent::new(FUint::from_num(100));
+    // Abilities are likewise referred to by id
+    let mut ability_slots = vec![];
+    {
+        let abilities = world.read_resource::<AbilityDefinitions>();
+
+        for component in definition.components.iter() {
+            if let UnitComponentDefinition::Abilities {
+                max_energy: _,
+                energy_regen: _,
+                names,
+            } = component
+            {
+                for name in names.iter() {
+                    let ability = match abilities.id_of(name) {
+                        Some(ability) => ability,
+                        None => {
+                            return Err(format!(
+                                "unit '{}': unknown ability '{}'",
+                                unit_type, name
+                            ));
+                        }
+                    };
+
+                    let ability_definition = abilities.get_by_id(ability).unwrap();
+                    let cooldown_remaining =
+                        if ability_definition.targeting == AbilityTargeting::Passive {
+                            ability_definition.cooldown_ticks
+                        } else {
+                            0
+                        };
+
+                    ability_slots.push(AbilitySlot {
+                        ability: ability,
+                        cooldown_remaining: cooldown_remaining,
+                    });
+                }
+            }
+        }
+    }
+
+    // Units with legs walk with the walker's animations, when they are loaded
+    let walker_set = match world.try_fetch::<AnimationLibrary>() {
+        Some(library) => library.index_of(unit_animations::WALKER_SET),
+        None => None,
+    };
 
     // Physics components
-    let transform = TransformComponent::new();
+    let mut transform = TransformComponent::new();
+    transform.world_position = position;
     let velocity = VelocityComponent::new();
 
-    // Gfx Components
-    let sprite = SpriteComponent::new();
-
-    world
+    let mut builder = world
         .create_entity()
-        .with(armor)
-        .with(move_speed)
-        .with(ranged_atk)
-        .with(base)
         .with(transform)
         .with(velocity)
-        .with(sprite)
+        .with(ActorComponent::new(player_id))
+        .with(OwnershipComponent::new(player_id))
+        .with(UnitOrderComponent::new());
+
+    if definition.cost.supply > 0 {
+        builder = builder.with(SupplyComponent::new(definition.cost.supply));
+    }
+
+    // RTS + Gfx components
+    for component in definition.components.iter() {
+        builder = match component {
+            UnitComponentDefinition::Armor { value, max } => {
+                builder.with(ArmorComponent::new(*value, *max))
+            }
+            UnitComponentDefinition::HitPoints { value, max } => {
+                builder.with(HitPointsComponent::new(*value, *max))
+            }
+            UnitComponentDefinition::MoveSpeed { value } => {
+                builder.with(MoveSpeedComponent::new(*value))
+            }
+            UnitComponentDefinition::RangedAttack {
+                rate_of_fire,
+                range,
+                damage,
+            } => builder.with(RangedAttackComponent::new(*rate_of_fire, *range, *damage)),
+            UnitComponentDefinition::UnitBase { base_size } => {
+                builder.with(UnitBaseComponent::new(*base_size))
+            }
+            UnitComponentDefinition::Sight { range } => builder.with(SightComponent::new(*range)),
+            UnitComponentDefinition::Worker {
+                carry_capacity,
+                gather_ticks,
+            } => builder.with(WorkerComponent::new(*carry_capacity, *gather_ticks)),
+            UnitComponentDefinition::DropOff => builder.with(DropOffComponent::new()),
+            UnitComponentDefinition::Production {
+                queue_size,
+                units: _,
+            } => builder.with(ProductionComponent::new(*queue_size, produces.clone())),
+            UnitComponentDefinition::SupplyProvider { amount } => {
+                builder.with(SupplyProviderComponent::new(*amount))
+            }
+            UnitComponentDefinition::Abilities {
+                max_energy,
+                energy_regen,
+                names: _,
+            } => builder.with(AbilitiesComponent::new(
+                *max_energy,
+                *energy_regen,
+                ability_slots.clone(),
+            )),
+            UnitComponentDefinition::Sprite => builder.with(SpriteComponent::new()),
+            UnitComponentDefinition::Legs {
+                count,
+                length,
+                hip_height,
+                stance_width,
+            } => {
+                let builder = builder.with(LegsComponent::new(Legs::new(
+                    Gait::Alternating,
+                    *count as usize,
+                    GameUnit::from_num(*length),
+                    GameUnit::from_num(*hip_height),
+                    GameUnit::from_num(*stance_width),
+                )));
+
+                match walker_set {
+                    Some(set) => builder.with(AnimationComponent::new(set)),
+                    None => builder,
+                }
+            }
+        };
+    }
+
+    return Ok(builder.build());
+}
+
+/// Spawn a harvestable resource node at the position.
+pub fn new_resource_node(
+    world: &mut specs::World,
+    resource: ResourceType,
+    amount: u32,
+    position: Coordinate2d,
+) -> Entity {
+    let mut transform = TransformComponent::new();
+    transform.world_position = position;
+
+    return world
+        .create_entity()
+        .with(transform)
+        .with(ResourceNodeComponent::new(resource, amount))
+        .with(SpriteComponent::new())
+        .build();
+}
+
+/// Place an objective that teams may capture by holding the area around it.
+pub fn new_objective(world: &mut specs::World, position: Coordinate2d, radius: GameUnit) -> Entity {
+    let mut transform = TransformComponent::new();
+    transform.world_position = position;
+
+    return world
+        .create_entity()
+        .with(transform)
+        .with(ObjectiveComponent::new(radius))
+        .with(SpriteComponent::new())
         .build();
 }
+
+/// Create a camera looking down on the map around the focus, from the height in game units.
+pub fn new_top_down_camera(
+    world: &mut specs::World,
+    focus: Coordinate2d,
+    height: GameUnit,
+) -> Entity {
+    let mut camera = CameraComponent::new(CameraController::TopDown { height: height });
+    camera.focus = Coordinate3d::new(focus.x, focus.y, GameUnit::from_num(0));
+
+    return world.create_entity().with(camera).build();
+}
+
+/// Spawn all units requested by the simulation systems this tick, in the order they were requested.
+pub fn spawn_queued_units(world: &mut specs::World) {
+    let requests;
+    {
+        let mut spawn_queue = world.write_resource::<CbSpawnQueue>();
+        requests = std::mem::replace(&mut spawn_queue.requests, vec![]);
+    }
+
+    for request in requests.iter() {
+        let name = match world
+            .read_resource::<UnitDefinitions>()
+            .get_by_id(request.unit_type)
+        {
+            Some(definition) => definition.name.clone(),
+            None => {
+                println!("Unable to spawn unknown unit type {}", request.unit_type);
+                continue;
+            }
+        };
+
+        match new_unit(world, &name, request.position, request.player_id) {
+            Ok(_) => {}
+            Err(e) => println!("Unable to spawn unit: {}", e),
+        }
+    }
+}
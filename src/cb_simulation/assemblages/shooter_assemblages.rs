@@ -0,0 +1,186 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, Coordinate3d, GameUnit};
+
+use crate::cb_math;
+use cb_math::FUint;
+
+use crate::cb_voxels;
+
+use crate::cb_simulation;
+use cb_simulation::projectiles::ProjectileDefinition;
+use cb_simulation::rigid_body::{Collider, RigidBody};
+
+use crate::cb_simulation::components::{
+    actor_components, character_components, gfx_components, ownership_components,
+    physics_components, shooter_components, voxel_components,
+};
+use actor_components::ActorComponent;
+use character_components::HitPointsComponent;
+use gfx_components::{CameraComponent, CameraController, LightComponent};
+use ownership_components::OwnershipComponent;
+use physics_components::{RigidBodyComponent, TransformComponent};
+use shooter_components::{CharacterControllerComponent, Weapon, WeaponComponent};
+use voxel_components::VoxelComponent;
+
+/// Height of the arena floor, in voxels.
+const ARENA_FLOOR_HEIGHT: usize = 2;
+
+const CHARACTER_HIT_POINTS: u32 = 100;
+
+/// Damage per shot, range in voxels and ticks between shots of a character's rifle.
+const RIFLE: (u32, i32, u32) = (25, 30, 10);
+
+/// Damage, speed in game units per tick, fuse in ticks, blast radius in game units and ticks between shots of a
+/// character's grenade launcher.
+const GRENADE_LAUNCHER: (u32, i32, u32, i32, u32) = (60, 150, 120, 450, 60);
+
+/// Half the size of a crate, in game units, and its mass in kilograms.
+const CRATE: (i32, u32) = (150, 20);
+
+/// The color of a lamp's light, and its radius and height above the arena floor in voxels.
+const LAMP: ([f32; 3], i32, i32) = ([1.0, 0.7, 0.4], 6, 2);
+
+/// Create the voxel terrain for a shooter match: a flat floor with walls to take cover behind and ledges to step onto.
+pub fn new_arena(world: &mut specs::World) -> Entity {
+    let mut voxel = VoxelComponent::new();
+    let terrain = &mut voxel.chunk_manager;
+    let width = terrain.get_voxel_width();
+
+    for x in 0..width {
+        for y in 0..width {
+            while terrain.get_column_height(x, y) > ARENA_FLOOR_HEIGHT {
+                terrain.remove_top_voxel(x, y, 0);
+            }
+        }
+    }
+
+    // Walls across the middle, with a gap to run through
+    let middle = width / 2;
+    for i in 3..width - 3 {
+        if i == middle {
+            continue;
+        }
+
+        for _ in 0..3 {
+            terrain.add_top_voxel(i, middle, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        }
+    }
+
+    // Single voxel ledges near each corner
+    for (x, y) in [(2, middle - 2), (width - 3, middle + 2)].iter() {
+        terrain.add_top_voxel(*x, *y, cb_voxels::VOXEL_TYPE_GRASS, 0);
+    }
+
+    return world
+        .create_entity()
+        .with(voxel)
+        .with(TransformComponent::new())
+        .build();
+}
+
+/// Create a camera that sees through the local player's character.
+pub fn new_first_person_camera(world: &mut specs::World) -> Entity {
+    return world
+        .create_entity()
+        .with(CameraComponent::new(CameraController::FirstPerson))
+        .build();
+}
+
+/// Create a player's character, standing on the arena floor at the voxel and facing the yaw, in tenths of a degree.
+pub fn new_character(
+    world: &mut specs::World,
+    player_id: usize,
+    voxel_x: i32,
+    voxel_y: i32,
+    yaw: i32,
+) -> Entity {
+    let voxel = cb_voxels::VOXEL_GAME_SIZE;
+    let position = Coordinate3d::new(
+        GameUnit::from_num(voxel_x * voxel + voxel / 2),
+        GameUnit::from_num(voxel_y * voxel + voxel / 2),
+        GameUnit::from_num(ARENA_FLOOR_HEIGHT as i32 * voxel),
+    );
+
+    let mut controller = CharacterControllerComponent::new(position);
+    controller.state.yaw = yaw;
+
+    let mut transform = TransformComponent::new();
+    transform.world_position = Coordinate2d::new(position.x, position.y);
+
+    let hit_points = FUint::from_num(CHARACTER_HIT_POINTS);
+    let (damage, range, cooldown) = RIFLE;
+    let rifle = Weapon::new(
+        ProjectileDefinition::hitscan(FUint::from_num(damage), GameUnit::from_num(range * voxel)),
+        cooldown,
+    );
+
+    let (damage, speed, fuse, blast_radius, cooldown) = GRENADE_LAUNCHER;
+    let grenade_launcher = Weapon::new(
+        ProjectileDefinition::ballistic(FUint::from_num(damage), GameUnit::from_num(speed), fuse)
+            .with_blast(GameUnit::from_num(blast_radius)),
+        cooldown,
+    );
+
+    return world
+        .create_entity()
+        .with(ActorComponent::new(player_id))
+        .with(OwnershipComponent::new(player_id))
+        .with(HitPointsComponent::new(hit_points, hit_points))
+        .with(transform)
+        .with(controller)
+        .with(WeaponComponent::new(rifle, Some(grenade_launcher)))
+        .build();
+}
+
+/// Create a crate that drops from the given height, in voxels, onto the arena at the voxel.
+pub fn new_crate(world: &mut specs::World, voxel_x: i32, voxel_y: i32, height: i32) -> Entity {
+    let voxel = cb_voxels::VOXEL_GAME_SIZE;
+    let (half_size, mass) = CRATE;
+    let position = Coordinate3d::new(
+        GameUnit::from_num(voxel_x * voxel + voxel / 2),
+        GameUnit::from_num(voxel_y * voxel + voxel / 2),
+        GameUnit::from_num(height * voxel + half_size),
+    );
+    let half_size = GameUnit::from_num(half_size);
+    let collider = Collider::Aabb {
+        half_extents: Coordinate3d::new(half_size, half_size, half_size),
+    };
+
+    let mut transform = TransformComponent::new();
+    transform.world_position = Coordinate2d::new(position.x, position.y);
+
+    return world
+        .create_entity()
+        .with(transform)
+        .with(RigidBodyComponent::new(RigidBody::new(
+            position, collider, mass,
+        )))
+        .build();
+}
+
+/// Create a lamp that lights the arena around the voxel.
+pub fn new_lamp(world: &mut specs::World, voxel_x: i32, voxel_y: i32) -> Entity {
+    let voxel = cb_voxels::VOXEL_GAME_SIZE;
+    let (color, radius, height) = LAMP;
+
+    let mut transform = TransformComponent::new();
+    transform.world_position = Coordinate2d::new(
+        GameUnit::from_num(voxel_x * voxel + voxel / 2),
+        GameUnit::from_num(voxel_y * voxel + voxel / 2),
+    );
+
+    return world
+        .create_entity()
+        .with(transform)
+        .with(LightComponent::new(
+            color,
+            GameUnit::from_num(radius * voxel),
+            GameUnit::from_num((ARENA_FLOOR_HEIGHT as i32 + height) * voxel),
+        ))
+        .build();
+}
@@ -1,18 +1,44 @@
 use crate::cb_simulation::components;
 use components::{gfx_components, physics_components, voxel_components};
-use gfx_components::CameraComponent;
+use gfx_components::{CameraComponent, CameraController};
 use physics_components::TransformComponent;
 use voxel_components::VoxelComponent;
 
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+
 extern crate specs;
 use specs::prelude::*;
 
+/// Starting view of the editor's camera, as (yaw, pitch) in tenths of a degree and distance in voxels.
+const EDITOR_CAMERA: (i32, i32, i32) = (-450, 450, 20);
+
 pub fn new(world: &mut World) {
     world
         .create_entity()
         .with(VoxelComponent::new())
         .with(TransformComponent::new())
-        .with(CameraComponent::new())
+        .with(editor_camera(Coordinate3d::zero()))
         .with(components::editor_components::EditableComponent::new(false))
         .build();
 }
+
+/// Create the editor's camera, orbiting the focus.
+pub fn new_editor_camera(world: &mut World, focus: Coordinate3d) -> Entity {
+    return world.create_entity().with(editor_camera(focus)).build();
+}
+
+fn editor_camera(focus: Coordinate3d) -> CameraComponent {
+    let (yaw, pitch, distance) = EDITOR_CAMERA;
+
+    let mut camera = CameraComponent::new(CameraController::Orbit {
+        yaw: yaw,
+        pitch: pitch,
+        distance: GameUnit::from_num(distance * cb_voxels::VOXEL_GAME_SIZE),
+    });
+    camera.focus = focus;
+
+    return camera;
+}
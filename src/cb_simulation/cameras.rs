@@ -0,0 +1,309 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Cameras are entities with a camera component, whose controller decides how it moves each tick. The editor's camera
+    orbits, pans and zooms around a focus point, the RTS camera looks down on the map and scrolls when the cursor nears
+    the window's edges, and first person cameras see through the local player's character. Only the active camera
+    takes input and is drawn from.
+
+    Cameras are moved with the same fixed point math as everything else, once per tick. Each keeps its pose from the
+    previous tick so the renderer can move smoothly between the two.
+*/
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_input;
+use cb_input::input_type::Range;
+
+use crate::cb_math;
+use cb_math::sin_cos;
+
+use crate::cb_simulation;
+use cb_simulation::character_controller::CharacterState;
+use cb_simulation::components::gfx_components::{CameraComponent, CameraController};
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+
+/// How far a press turns an orbiting camera, in tenths of a degree.
+pub const ORBIT_STEP: i32 = 150;
+
+/// Orbiting cameras stop short of looking straight up or down, where their up axis would flip.
+pub const MAX_ORBIT_PITCH: i32 = 850;
+
+/// Each zoom moves an orbiting camera this percentage of its distance to or from the focus.
+pub const ZOOM_PERCENT: i32 = 20;
+
+pub const MIN_ORBIT_DISTANCE: i32 = 2 * cb_voxels::VOXEL_GAME_SIZE;
+pub const MAX_ORBIT_DISTANCE: i32 = 100 * cb_voxels::VOXEL_GAME_SIZE;
+
+/// How far a press pans an orbiting camera, in game units.
+pub const PAN_STEP: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+/// How close the cursor must be to the window's edge to scroll, as a percentage of the window.
+pub const EDGE_SCROLL_MARGIN_PERCENT: i32 = 3;
+
+/// How far an edge scrolling camera moves each tick, in game units.
+pub const EDGE_SCROLL_SPEED: i32 = 60;
+
+/// The angle top down cameras look down at, in tenths of a degree below the horizon.
+pub const TOP_DOWN_PITCH: i32 = 600;
+
+/// How far ahead of the eye first person cameras look, in game units.
+const FIRST_PERSON_TARGET_DISTANCE: i32 = 1000;
+
+/// The camera the world is drawn from. Stored as a world resource.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct CbActiveCamera {
+    entity: Option<Entity>,
+}
+
+impl CbActiveCamera {
+    pub fn new(entity: Option<Entity>) -> Self {
+        return Self { entity: entity };
+    }
+
+    pub fn get(&self) -> Option<Entity> {
+        return self.entity;
+    }
+
+    pub fn set(&mut self, entity: Option<Entity>) {
+        self.entity = entity;
+    }
+}
+
+/// Make the editor's camera active, or the game's if the editor's already is.
+/// NOTE: this changes the world outside of a tick, so it's only meant for development.
+pub fn switch_editor_camera(world: &World) {
+    let entities = world.entities();
+    let cameras = world.read_storage::<CameraComponent>();
+    let mut active_camera = world.write_resource::<CbActiveCamera>();
+
+    let is_editor = |camera: &CameraComponent| match camera.controller {
+        CameraController::Orbit { .. } => true,
+        _ => false,
+    };
+
+    let editing = match active_camera.get().and_then(|entity| cameras.get(entity)) {
+        Some(camera) => is_editor(camera),
+        None => false,
+    };
+
+    let next = (&entities, &cameras)
+        .join()
+        .find(|(_, camera)| is_editor(camera) != editing)
+        .map(|(entity, _)| entity);
+
+    if next.is_some() {
+        active_camera.set(next);
+    }
+}
+
+/// Where a camera orbiting the focus is. A yaw of zero is along the x axis and a positive pitch is above the focus.
+pub fn orbit_position(
+    focus: Coordinate3d,
+    yaw: i32,
+    pitch: i32,
+    distance: GameUnit,
+) -> Coordinate3d {
+    let (sin_yaw, cos_yaw) = sin_cos(yaw);
+    let (sin_pitch, cos_pitch) = sin_cos(pitch);
+
+    return Coordinate3d::new(
+        focus.x + distance * cos_pitch * cos_yaw,
+        focus.y + distance * cos_pitch * sin_yaw,
+        focus.z + distance * sin_pitch,
+    );
+}
+
+/// The orbit distance after zooming in by the steps, or out if they're negative.
+pub fn zoom(distance: GameUnit, steps: i32) -> GameUnit {
+    let mut distance = distance;
+    for _ in 0..steps.abs() {
+        // Divide first, as the distance times the percent may not fit
+        let change = distance / 100 * ZOOM_PERCENT;
+        if steps > 0 {
+            distance -= change;
+        } else {
+            distance += change;
+        }
+    }
+
+    return distance.clamp(
+        GameUnit::from_num(MIN_ORBIT_DISTANCE),
+        GameUnit::from_num(MAX_ORBIT_DISTANCE),
+    );
+}
+
+/// Move the focus of a camera at the yaw across the ground, by steps forwards and to the right as seen by the camera.
+pub fn pan(focus: Coordinate3d, yaw: i32, forward: i32, right: i32) -> Coordinate3d {
+    // The camera looks back towards the focus, against its yaw
+    let (sin_yaw, cos_yaw) = sin_cos(yaw);
+    let (forward, right) = (
+        GameUnit::from_num(forward * PAN_STEP),
+        GameUnit::from_num(right * PAN_STEP),
+    );
+
+    return Coordinate3d::new(
+        focus.x - cos_yaw * forward - sin_yaw * right,
+        focus.y - sin_yaw * forward + cos_yaw * right,
+        focus.z,
+    );
+}
+
+/// The direction to scroll in when the cursor is near the window's edges, as (right, up) on the screen.
+pub fn edge_scroll(cursor_x: Range, cursor_y: Range) -> (i32, i32) {
+    let direction = |cursor: Range| {
+        let margin = (cursor.max() - cursor.min()) * EDGE_SCROLL_MARGIN_PERCENT / 100;
+
+        if cursor.value() >= cursor.max() - margin {
+            return 1;
+        } else if cursor.value() <= cursor.min() + margin {
+            return -1;
+        }
+
+        return 0;
+    };
+
+    // The cursor's y goes down the screen
+    return (direction(cursor_x), -direction(cursor_y));
+}
+
+/// Where a top down camera looking at the focus from the height is. It looks north, along the y axis.
+pub fn top_down_position(focus: Coordinate3d, height: GameUnit) -> Coordinate3d {
+    let (sin_pitch, cos_pitch) = sin_cos(TOP_DOWN_PITCH);
+
+    return Coordinate3d::new(
+        focus.x,
+        focus.y - height * cos_pitch / sin_pitch,
+        focus.z + height,
+    );
+}
+
+/// The eye and target of a camera seeing through the character's eyes.
+pub fn first_person_pose(state: &CharacterState) -> (Coordinate3d, Coordinate3d) {
+    let eye = state.eye_position();
+    let direction = state.look_direction();
+    let distance = GameUnit::from_num(FIRST_PERSON_TARGET_DISTANCE);
+
+    let target = Coordinate3d::new(
+        eye.x + direction.x * distance,
+        eye.y + direction.y * distance,
+        eye.z + direction.z * distance,
+    );
+
+    return (eye, target);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units(value: i32) -> GameUnit {
+        return GameUnit::from_num(value);
+    }
+
+    fn close(a: Coordinate3d, b: Coordinate3d) -> bool {
+        let tolerance = units(2);
+
+        return (a.x - b.x).abs() <= tolerance
+            && (a.y - b.y).abs() <= tolerance
+            && (a.z - b.z).abs() <= tolerance;
+    }
+
+    #[test]
+    fn orbit_position_circles_focus() {
+        let focus = Coordinate3d::new(units(100), units(200), units(0));
+
+        assert!(close(
+            Coordinate3d::new(units(1100), units(200), units(0)),
+            orbit_position(focus, 0, 0, units(1000))
+        ));
+        assert!(close(
+            Coordinate3d::new(units(100), units(1200), units(0)),
+            orbit_position(focus, 900, 0, units(1000))
+        ));
+        assert!(close(
+            Coordinate3d::new(units(100), units(200), units(1000)),
+            orbit_position(focus, 0, 900, units(1000))
+        ));
+    }
+
+    #[test]
+    fn zoom_moves_by_percent_within_limits() {
+        assert_eq!(units(800), zoom(units(1000), 1));
+        assert_eq!(units(1200), zoom(units(1000), -1));
+        assert_eq!(units(MIN_ORBIT_DISTANCE), zoom(units(1000), 20));
+        assert_eq!(
+            units(MAX_ORBIT_DISTANCE),
+            zoom(units(MAX_ORBIT_DISTANCE), -1)
+        );
+    }
+
+    #[test]
+    fn pan_moves_relative_to_camera() {
+        // A camera along the x axis looks towards -x, with +y to its right
+        let focus = Coordinate3d::zero();
+
+        assert!(close(
+            Coordinate3d::new(units(-PAN_STEP), units(0), units(0)),
+            pan(focus, 0, 1, 0)
+        ));
+        assert!(close(
+            Coordinate3d::new(units(0), units(PAN_STEP), units(0)),
+            pan(focus, 0, 0, 1)
+        ));
+    }
+
+    #[test]
+    fn edge_scroll_only_near_edges() {
+        let at = |x: i32, y: i32| edge_scroll(Range::new(x, 0, 1000), Range::new(y, 0, 1000));
+
+        assert_eq!((0, 0), at(500, 500));
+        assert_eq!((1, 0), at(999, 500));
+        assert_eq!((-1, 0), at(0, 500));
+        assert_eq!((0, 1), at(500, 0));
+        assert_eq!((-1, -1), at(5, 995));
+    }
+
+    #[test]
+    fn top_down_position_looks_north_at_pitch() {
+        let focus = Coordinate3d::new(units(1000), units(1000), units(0));
+
+        let position = top_down_position(focus, units(3000));
+
+        assert_eq!(focus.x, position.x);
+        assert_eq!(units(3000), position.z);
+        // Tan of 60 degrees is about 1.732
+        assert!((position.y - units(1000 - 1732)).abs() <= units(5));
+    }
+
+    #[test]
+    fn active_camera_switches_between_editor_and_game() {
+        let mut world = World::new();
+        world.register::<CameraComponent>();
+
+        let game = world
+            .create_entity()
+            .with(CameraComponent::new(CameraController::Fixed))
+            .build();
+        let editor = world
+            .create_entity()
+            .with(CameraComponent::new(CameraController::Orbit {
+                yaw: 0,
+                pitch: 0,
+                distance: units(1000),
+            }))
+            .build();
+        world.insert(CbActiveCamera::new(Some(game)));
+
+        switch_editor_camera(&world);
+        assert_eq!(Some(editor), world.read_resource::<CbActiveCamera>().get());
+
+        switch_editor_camera(&world);
+        assert_eq!(Some(game), world.read_resource::<CbActiveCamera>().get());
+    }
+}
@@ -0,0 +1,430 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    First person character movement over voxel terrain. Characters are upright boxes that move one axis at a time,
+    stepping up single voxel ledges, falling under gravity and changing stance when there is room to. Everything is in
+    fixed point game units so movement is identical on every peer.
+*/
+
+use crate::cb_math;
+use cb_math::sin_cos;
+
+use crate::cb_simulation;
+use cb_simulation::rigid_body::GRAVITY_MILLIS;
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+use cb_voxels::CbChunkManager;
+
+/// Half the width of a character's collision box, in game units.
+pub const CHARACTER_RADIUS: i32 = 250;
+
+/// How far below the top of the character its eyes are, in game units.
+const EYE_OFFSET: i32 = 150;
+
+/// Upwards speed when jumping, in game units per tick.
+const JUMP_SPEED: i32 = 55;
+
+/// Fastest a character can fall, in game units per tick. Kept under a voxel so falls never skip through the ground.
+const MAX_FALL_SPEED: i32 = 250;
+
+/// The highest ledge a character walks up without jumping, in game units.
+const STEP_HEIGHT: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+/// How far up or down a character may look, in tenths of a degree.
+pub const MAX_PITCH: i32 = 890;
+
+/// Roughly one over the square root of two, scaled by 4096, to keep diagonal movement at the same speed.
+const DIAGONAL_SCALE_BITS: i32 = 2896;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stance {
+    Standing,
+    Crouching,
+    Prone,
+}
+
+impl Stance {
+    /// Height of the collision box, in game units.
+    pub fn height(&self) -> i32 {
+        match self {
+            Stance::Standing => 1800,
+            Stance::Crouching => 1200,
+            Stance::Prone => 500,
+        }
+    }
+
+    /// Movement speed, in game units per tick.
+    fn speed(&self, running: bool) -> i32 {
+        match self {
+            Stance::Standing if running => 50,
+            Stance::Standing => 25,
+            Stance::Crouching => 12,
+            Stance::Prone => 6,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CharacterState {
+    /// The center of the character's feet.
+    pub position: Coordinate3d,
+    pub vertical_velocity: GameUnit,
+    pub on_ground: bool,
+    pub stance: Stance,
+    /// Heading in tenths of a degree, counter clockwise from the x axis.
+    pub yaw: i32,
+    /// Tenths of a degree above the horizon.
+    pub pitch: i32,
+}
+
+impl CharacterState {
+    pub fn new(position: Coordinate3d) -> Self {
+        return Self {
+            position: position,
+            vertical_velocity: GameUnit::from_num(0),
+            on_ground: false,
+            stance: Stance::Standing,
+            yaw: 0,
+            pitch: 0,
+        };
+    }
+
+    pub fn eye_position(&self) -> Coordinate3d {
+        let mut eye = self.position;
+        eye.z += GameUnit::from_num(self.stance.height() - EYE_OFFSET);
+
+        return eye;
+    }
+
+    /// The unit length direction the character is looking in.
+    pub fn look_direction(&self) -> Coordinate3d {
+        let (sin_yaw, cos_yaw) = sin_cos(self.yaw);
+        let (sin_pitch, cos_pitch) = sin_cos(self.pitch);
+
+        return Coordinate3d::new(cos_pitch * cos_yaw, cos_pitch * sin_yaw, sin_pitch);
+    }
+
+    /// The corners of the character's collision box.
+    pub fn bounds(&self) -> (Coordinate3d, Coordinate3d) {
+        let radius = GameUnit::from_num(CHARACTER_RADIUS);
+        let height = GameUnit::from_num(self.stance.height());
+        let p = self.position;
+
+        return (
+            Coordinate3d::new(p.x - radius, p.y - radius, p.z),
+            Coordinate3d::new(p.x + radius, p.y + radius, p.z + height),
+        );
+    }
+}
+
+/// What the character is trying to do this tick.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CharacterIntent {
+    /// 1 for forwards, -1 for backwards.
+    pub forward: i32,
+    /// 1 for right, -1 for left.
+    pub strafe: i32,
+    pub running: bool,
+    pub jump: bool,
+    pub stance: Stance,
+    /// Change in yaw and pitch, in tenths of a degree.
+    pub look_yaw: i32,
+    pub look_pitch: i32,
+}
+
+impl Default for CharacterIntent {
+    fn default() -> Self {
+        return Self {
+            forward: 0,
+            strafe: 0,
+            running: false,
+            jump: false,
+            stance: Stance::Standing,
+            look_yaw: 0,
+            look_pitch: 0,
+        };
+    }
+}
+
+/// Returns whether a character of the given stance at the position would overlap the terrain or fall out of the map.
+fn collides(terrain: &CbChunkManager, position: Coordinate3d, stance: Stance) -> bool {
+    let (x, y, z) = (
+        position.x.to_num::<i32>(),
+        position.y.to_num::<i32>(),
+        position.z.to_num::<i32>(),
+    );
+
+    if z < 0 {
+        return true;
+    }
+
+    return terrain.box_collides(
+        (x - CHARACTER_RADIUS, y - CHARACTER_RADIUS, z),
+        (
+            x + CHARACTER_RADIUS,
+            y + CHARACTER_RADIUS,
+            z + stance.height(),
+        ),
+    );
+}
+
+/// Move horizontally, stepping up onto a ledge if blocked while on the ground.
+fn try_move(state: &mut CharacterState, terrain: &CbChunkManager, dx: GameUnit, dy: GameUnit) {
+    let mut moved = state.position;
+    moved.x += dx;
+    moved.y += dy;
+
+    // Keep the character inside the map
+    let map_size = (terrain.get_voxel_width() as i32) * cb_voxels::VOXEL_GAME_SIZE;
+    let min = GameUnit::from_num(CHARACTER_RADIUS);
+    let max = GameUnit::from_num(map_size - CHARACTER_RADIUS);
+    moved.x = moved.x.max(min).min(max);
+    moved.y = moved.y.max(min).min(max);
+
+    if !collides(terrain, moved, state.stance) {
+        state.position = moved;
+        return;
+    }
+
+    if state.on_ground {
+        let mut stepped = moved;
+        stepped.z += GameUnit::from_num(STEP_HEIGHT);
+
+        if !collides(terrain, stepped, state.stance) {
+            state.position = stepped;
+        }
+    }
+}
+
+/// Advance the character by one tick.
+pub fn step(state: &mut CharacterState, intent: &CharacterIntent, terrain: &CbChunkManager) {
+    // Look
+    state.yaw = (state.yaw + intent.look_yaw).rem_euclid(3600);
+    state.pitch = (state.pitch + intent.look_pitch)
+        .max(-MAX_PITCH)
+        .min(MAX_PITCH);
+
+    // Stance; getting up requires room overhead
+    if intent.stance != state.stance {
+        let taller = intent.stance.height() > state.stance.height();
+
+        if !taller || !collides(terrain, state.position, intent.stance) {
+            state.stance = intent.stance;
+        }
+    }
+
+    // Walking
+    {
+        let forward = intent.forward.max(-1).min(1);
+        let strafe = intent.strafe.max(-1).min(1);
+
+        if forward != 0 || strafe != 0 {
+            let (sin_yaw, cos_yaw) = sin_cos(state.yaw);
+
+            let mut speed = GameUnit::from_num(state.stance.speed(intent.running));
+            if forward != 0 && strafe != 0 {
+                speed = speed * GameUnit::from_bits(DIAGONAL_SCALE_BITS);
+            }
+
+            let forward = GameUnit::from_num(forward);
+            let strafe = GameUnit::from_num(strafe);
+
+            // Right of the heading is (sin, -cos)
+            let dx = (cos_yaw * forward + sin_yaw * strafe) * speed;
+            let dy = (sin_yaw * forward - cos_yaw * strafe) * speed;
+
+            try_move(state, terrain, dx, GameUnit::from_num(0));
+            try_move(state, terrain, GameUnit::from_num(0), dy);
+        }
+    }
+
+    // Jumping and falling
+    {
+        if intent.jump && state.on_ground && state.stance == Stance::Standing {
+            state.vertical_velocity = GameUnit::from_num(JUMP_SPEED);
+        }
+
+        state.vertical_velocity -= GameUnit::from_num(GRAVITY_MILLIS) / 1000;
+        state.vertical_velocity = state
+            .vertical_velocity
+            .max(GameUnit::from_num(-MAX_FALL_SPEED));
+
+        let mut moved = state.position;
+        moved.z += state.vertical_velocity;
+
+        if !collides(terrain, moved, state.stance) {
+            state.position = moved;
+            state.on_ground = false;
+        } else if state.vertical_velocity < 0 {
+            // Land on top of the voxel layer that was fallen into
+            let voxel = cb_voxels::VOXEL_GAME_SIZE;
+            let mut landed = state.position;
+            landed.z =
+                GameUnit::from_num(state.position.z.to_num::<i32>().div_euclid(voxel) * voxel);
+
+            if !collides(terrain, landed, state.stance) {
+                state.position = landed;
+            }
+
+            state.vertical_velocity = GameUnit::from_num(0);
+            state.on_ground = true;
+        } else {
+            // Hit the ceiling
+            state.vertical_velocity = GameUnit::from_num(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cb_voxels::{coordinate, flat_map};
+
+    const VOXEL: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+    fn standing_on_flat_map(terrain: &CbChunkManager, x: i32, y: i32) -> CharacterState {
+        let mut state = CharacterState::new(coordinate(x, y, 2 * VOXEL));
+        step(&mut state, &CharacterIntent::default(), terrain);
+
+        return state;
+    }
+
+    #[test]
+    fn step_falling_character_lands_on_terrain() {
+        let terrain = flat_map(2);
+        let mut state = CharacterState::new(coordinate(1500, 1500, 2000));
+
+        for _ in 0..120 {
+            step(&mut state, &CharacterIntent::default(), &terrain);
+        }
+
+        assert_eq!(true, state.on_ground);
+        assert_eq!(GameUnit::from_num(2 * VOXEL), state.position.z);
+    }
+
+    #[test]
+    fn step_forward_moves_along_heading() {
+        let terrain = flat_map(2);
+        let mut state = standing_on_flat_map(&terrain, 1500, 1500);
+        state.yaw = 900;
+        let intent = CharacterIntent {
+            forward: 1,
+            ..CharacterIntent::default()
+        };
+
+        step(&mut state, &intent, &terrain);
+
+        assert_eq!(GameUnit::from_num(1500), state.position.x);
+        assert_eq!(GameUnit::from_num(1525), state.position.y);
+    }
+
+    #[test]
+    fn step_into_single_voxel_ledge_steps_up() {
+        let mut terrain = flat_map(2);
+        for y in 0..terrain.get_voxel_width() {
+            terrain.add_top_voxel(6, y, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        }
+        let mut state = standing_on_flat_map(&terrain, 6 * VOXEL - CHARACTER_RADIUS - 10, 1500);
+        let intent = CharacterIntent {
+            forward: 1,
+            ..CharacterIntent::default()
+        };
+
+        for _ in 0..4 {
+            step(&mut state, &intent, &terrain);
+        }
+
+        assert_eq!(GameUnit::from_num(3 * VOXEL), state.position.z);
+    }
+
+    #[test]
+    fn step_into_wall_two_voxels_high_is_blocked() {
+        let mut terrain = flat_map(2);
+        for y in 0..terrain.get_voxel_width() {
+            terrain.add_top_voxel(6, y, cb_voxels::VOXEL_TYPE_DIRT, 0);
+            terrain.add_top_voxel(6, y, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        }
+        let mut state = standing_on_flat_map(&terrain, 6 * VOXEL - CHARACTER_RADIUS - 10, 1500);
+        let intent = CharacterIntent {
+            forward: 1,
+            ..CharacterIntent::default()
+        };
+
+        for _ in 0..4 {
+            step(&mut state, &intent, &terrain);
+        }
+
+        assert_eq!(
+            GameUnit::from_num(6 * VOXEL - CHARACTER_RADIUS - 10),
+            state.position.x
+        );
+        assert_eq!(GameUnit::from_num(2 * VOXEL), state.position.z);
+    }
+
+    #[test]
+    fn step_jump_leaves_ground_then_lands() {
+        let terrain = flat_map(2);
+        let mut state = standing_on_flat_map(&terrain, 1500, 1500);
+        let jump = CharacterIntent {
+            jump: true,
+            ..CharacterIntent::default()
+        };
+
+        step(&mut state, &jump, &terrain);
+        assert_eq!(false, state.on_ground);
+        assert!(state.position.z > GameUnit::from_num(2 * VOXEL));
+
+        for _ in 0..60 {
+            step(&mut state, &CharacterIntent::default(), &terrain);
+        }
+        assert_eq!(true, state.on_ground);
+        assert_eq!(GameUnit::from_num(2 * VOXEL), state.position.z);
+    }
+
+    #[test]
+    fn step_stand_up_under_low_ceiling_stays_crouched() {
+        let mut terrain = flat_map(2);
+        let mut state = standing_on_flat_map(&terrain, 1500, 1500);
+        let ceiling = 2 + Stance::Crouching.height() as usize / VOXEL as usize + 1;
+        for x in 3..7 {
+            for y in 3..7 {
+                *terrain.get_voxel_mut(x, y, ceiling, 0) =
+                    (true, true, cb_voxels::VOXEL_TYPE_DIRT, 0);
+            }
+        }
+        let crouch = CharacterIntent {
+            stance: Stance::Crouching,
+            ..CharacterIntent::default()
+        };
+        state.stance = Stance::Crouching;
+
+        step(&mut state, &crouch, &terrain);
+        step(&mut state, &CharacterIntent::default(), &terrain);
+
+        assert_eq!(Stance::Crouching, state.stance);
+    }
+
+    #[test]
+    fn bounds_surround_character_from_feet_to_head() {
+        let mut state = CharacterState::new(coordinate(3000, 1000, 600));
+        state.stance = Stance::Crouching;
+
+        let (min, max) = state.bounds();
+
+        assert_eq!(
+            coordinate(3000 - CHARACTER_RADIUS, 1000 - CHARACTER_RADIUS, 600),
+            min
+        );
+        assert_eq!(
+            coordinate(
+                3000 + CHARACTER_RADIUS,
+                1000 + CHARACTER_RADIUS,
+                600 + Stance::Crouching.height()
+            ),
+            max
+        );
+    }
+}
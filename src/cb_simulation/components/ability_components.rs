@@ -0,0 +1,37 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_simulation;
+use cb_simulation::abilities::AbilityId;
+
+use super::ComponentLinker;
+
+init_components![AbilityComponentsLinker, (AbilitiesComponent)];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AbilitySlot {
+    pub ability: AbilityId,
+    pub cooldown_remaining: u32,
+}
+
+pub struct AbilitiesComponent {
+    pub energy: u32,
+    pub max_energy: u32,
+    /// Energy regained every ENERGY_REGEN_TICKS.
+    pub energy_regen: u32,
+    pub slots: Vec<AbilitySlot>,
+}
+
+impl AbilitiesComponent {
+    /// Units start with full energy and all abilities ready, except passives which wait their first cooldown.
+    pub fn new(max_energy: u32, energy_regen: u32, slots: Vec<AbilitySlot>) -> Self {
+        return Self {
+            energy: max_energy,
+            max_energy: max_energy,
+            energy_regen: energy_regen,
+            slots: slots,
+        };
+    }
+}
@@ -4,7 +4,7 @@ extern crate specs;
 use specs::prelude::*;
 
 use crate::cb_simulation;
-use cb_simulation::CbWorldInputs;
+use cb_simulation::actor_controller::ActorController;
 
 use super::ComponentLinker;
 
@@ -12,14 +12,15 @@ init_components![ActorComponentsLinker, (ActorComponent)];
 
 pub struct ActorComponent {
     pub player_id: usize,
-    pub inputs: CbWorldInputs,
+    /// The player's inputs over the last few ticks.
+    pub controller: ActorController,
 }
 
 impl ActorComponent {
-    pub fn new() -> Self {
+    pub fn new(player_id: usize) -> Self {
         return Self {
-            player_id: 1, //NOTE: this is a bug, need to figure out some way to increment player ids?
-            inputs: vec![],
+            player_id: player_id,
+            controller: ActorController::new(),
         };
     }
 }
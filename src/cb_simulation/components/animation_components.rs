@@ -0,0 +1,41 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_animation;
+use cb_animation::ik_layers::IkLayer;
+use cb_animation::skeleton::Pose;
+use cb_animation::state_machine::Animator;
+
+use crate::cb_system;
+use cb_system::Coordinate2d;
+
+use super::ComponentLinker;
+
+init_components![AnimationComponentsLinker, (AnimationComponent)];
+
+/// Skeletal animation played by the simulation's ticks.
+pub struct AnimationComponent {
+    /// The index of the animation set in the AnimationLibrary.
+    pub set: usize,
+    pub animator: Animator,
+    /// Corrections applied in order on top of the sampled pose.
+    pub ik_layers: Vec<IkLayer>,
+    /// The pose for the latest tick, for drawing.
+    pub pose: Option<Pose>,
+    /// Where the entity's transform was on the last tick, to measure its speed by.
+    pub previous_position: Option<Coordinate2d>,
+}
+
+impl AnimationComponent {
+    pub fn new(set: usize) -> Self {
+        return Self {
+            set: set,
+            animator: Animator::new(),
+            ik_layers: vec![],
+            pose: None,
+            previous_position: None,
+        };
+    }
+}
@@ -16,14 +16,18 @@ init_components![
         ArmorComponent,
         UnitBaseComponent,
         MoveSpeedComponent,
-        RangedAttackComponent
+        RangedAttackComponent,
+        SightComponent
     )
 ];
 
+/// Rate of fire is in shots per second; range is in game units.
 pub struct RangedAttackComponent {
     rate_of_fire: FUint,
     range: FUint,
     damage: FUint,
+    /// Ticks until the unit may fire again.
+    pub cooldown_remaining: u32,
 }
 
 impl RangedAttackComponent {
@@ -32,8 +36,21 @@ impl RangedAttackComponent {
             rate_of_fire: rate_of_fire,
             range: range,
             damage: damage,
+            cooldown_remaining: 0,
         };
     }
+
+    pub fn rate_of_fire(&self) -> FUint {
+        return self.rate_of_fire;
+    }
+
+    pub fn range(&self) -> FUint {
+        return self.range;
+    }
+
+    pub fn damage(&self) -> FUint {
+        return self.damage;
+    }
 }
 
 pub struct HitPointsComponent {
@@ -48,6 +65,26 @@ impl HitPointsComponent {
             max: max,
         };
     }
+
+    pub fn value(&self) -> FUint {
+        return self.value;
+    }
+
+    pub fn max(&self) -> FUint {
+        return self.max;
+    }
+
+    pub fn apply_damage(&mut self, amount: FUint) {
+        self.value = self.value.saturating_sub(amount);
+    }
+
+    pub fn heal(&mut self, amount: FUint) {
+        self.value = self.value.saturating_add(amount).min(self.max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        return self.value == FUint::from_num(0);
+    }
 }
 
 pub struct ArmorComponent {
@@ -85,3 +122,14 @@ impl MoveSpeedComponent {
         return Self { value: value };
     }
 }
+
+/// How far the unit can see, in game units.
+pub struct SightComponent {
+    pub range: FUint,
+}
+
+impl SightComponent {
+    pub fn new(range: FUint) -> Self {
+        return Self { range: range };
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_simulation;
+use cb_simulation::economy::{ResourceType, Resources};
+use cb_simulation::unit_definitions::UnitTypeId;
+
+use super::ComponentLinker;
+
+init_components![
+    EconomyComponentsLinker,
+    (
+        ResourceNodeComponent,
+        WorkerComponent,
+        DropOffComponent,
+        ProductionComponent,
+        SupplyComponent,
+        SupplyProviderComponent
+    )
+];
+
+/// A harvestable deposit on the map.
+pub struct ResourceNodeComponent {
+    pub resource: ResourceType,
+    pub remaining: u32,
+}
+
+impl ResourceNodeComponent {
+    pub fn new(resource: ResourceType, amount: u32) -> Self {
+        return Self {
+            resource: resource,
+            remaining: amount,
+        };
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HarvestOrder {
+    Idle,
+    Harvesting { node: Entity },
+    Returning { node: Entity },
+}
+
+pub struct WorkerComponent {
+    pub carry_capacity: u32,
+    pub gather_ticks: u32,
+    pub carrying: Option<(ResourceType, u32)>,
+    pub order: HarvestOrder,
+    pub gather_progress: u32,
+}
+
+impl WorkerComponent {
+    pub fn new(carry_capacity: u32, gather_ticks: u32) -> Self {
+        return Self {
+            carry_capacity: carry_capacity,
+            gather_ticks: gather_ticks,
+            carrying: None,
+            order: HarvestOrder::Idle,
+            gather_progress: 0,
+        };
+    }
+
+    pub fn carried_amount(&self) -> u32 {
+        match self.carrying {
+            Some((_, amount)) => amount,
+            None => 0,
+        }
+    }
+}
+
+/// Workers may return resources to this entity.
+pub struct DropOffComponent {}
+
+impl DropOffComponent {
+    pub fn new() -> Self {
+        return Self {};
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct QueuedUnit {
+    pub unit_type: UnitTypeId,
+    pub remaining_ticks: u32,
+    /// What was spent to queue the unit, refunded if cancelled.
+    pub cost: Resources,
+    pub supply: u32,
+}
+
+pub struct ProductionComponent {
+    pub queue_size: u32,
+    pub produces: Vec<UnitTypeId>,
+    pub queue: Vec<QueuedUnit>,
+}
+
+impl ProductionComponent {
+    pub fn new(queue_size: u32, produces: Vec<UnitTypeId>) -> Self {
+        return Self {
+            queue_size: queue_size,
+            produces: produces,
+            queue: vec![],
+        };
+    }
+
+    pub fn is_full(&self) -> bool {
+        return self.queue.len() as u32 >= self.queue_size;
+    }
+}
+
+/// The supply the unit takes up while alive.
+pub struct SupplyComponent {
+    pub amount: u32,
+}
+
+impl SupplyComponent {
+    pub fn new(amount: u32) -> Self {
+        return Self { amount: amount };
+    }
+}
+
+/// The supply the entity provides to its owner.
+pub struct SupplyProviderComponent {
+    pub amount: u32,
+}
+
+impl SupplyProviderComponent {
+    pub fn new(amount: u32) -> Self {
+        return Self { amount: amount };
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_simulation;
+use cb_simulation::fighting::{Facing, FighterState};
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use super::ComponentLinker;
+
+init_components![FightingComponentsLinker, (FighterComponent)];
+
+/// A fighter controlled by its actor's fighting inputs.
+pub struct FighterComponent {
+    /// The index of the fighter in the FighterDefinitions.
+    pub fighter: usize,
+    pub state: FighterState,
+}
+
+impl FighterComponent {
+    pub fn new(fighter: usize, x: GameUnit, facing: Facing) -> Self {
+        return Self {
+            fighter: fighter,
+            state: FighterState::new(x, facing),
+        };
+    }
+}
@@ -3,11 +3,28 @@
 extern crate specs;
 use specs::prelude::*;
 
+use crate::cb_assets;
+use cb_assets::Handle;
+
+use crate::cb_graphics;
+use cb_graphics::models::Model;
+use cb_graphics::renderer::Material;
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, Coordinate3d, GameUnit};
+
 use super::ComponentLinker;
 
 init_components![
     GfxComponentsLinker,
-    (CameraComponent, SpriteComponent, SpriteRenderComponent)
+    (
+        CameraComponent,
+        SpriteComponent,
+        SpriteRenderComponent,
+        RenderComponent,
+        InterpolationComponent,
+        LightComponent
+    )
 ];
 
 pub struct SpriteComponent {}
@@ -27,37 +44,133 @@ impl SpriteRenderComponent {
     }
 }
 
-pub struct CameraComponent {
-    pub camera_orthographic_view: bool,
+/// Draws a model asset at the entity's transform.
+pub struct RenderComponent {
+    pub model: Handle<Model>,
+    pub material: Material,
+    pub visible: bool,
+}
+
+impl RenderComponent {
+    pub fn new(model: Handle<Model>) -> Self {
+        return Self {
+            model: model,
+            material: Material::default(),
+            visible: true,
+        };
+    }
+}
 
-    //NOTE: Maybe this seems wrong and should instead use the normal position components?
-    pub camera_pos_x: i32,
-    pub camera_pos_y: i32,
-    pub camera_pos_z: i32,
+/// A point light above the entity's transform. The radius and height are in game units.
+pub struct LightComponent {
+    pub color: [f32; 3],
+    pub radius: GameUnit,
+    pub height: GameUnit,
+}
+
+impl LightComponent {
+    pub fn new(color: [f32; 3], radius: GameUnit, height: GameUnit) -> Self {
+        return Self {
+            color: color,
+            radius: radius,
+            height: height,
+        };
+    }
+}
 
-    pub camera_target_x: i32,
-    pub camera_target_y: i32,
-    pub camera_target_z: i32,
+/// Where an entity was at the end of a tick, in game units. The height is that of the entity's rigid body, if any.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TransformState {
+    pub world_position: Coordinate2d,
+    pub height: GameUnit,
+    pub rotation: Coordinate2d,
+    pub scale: Coordinate2d,
+}
 
-    pub camera_pitch: i32,
-    pub camera_yaw: i32,
-    pub camera_roll: i32,
+/// The transforms of the previous and current ticks, so the renderer can draw entities between them.
+pub struct InterpolationComponent {
+    pub previous: TransformState,
+    pub current: TransformState,
+}
+
+impl InterpolationComponent {
+    /// Start at the state, without moving from anywhere.
+    pub fn new(state: TransformState) -> Self {
+        return Self {
+            previous: state,
+            current: state,
+        };
+    }
+
+    /// Record the state of a new tick, keeping the current one as the previous.
+    pub fn push(&mut self, state: TransformState) {
+        self.previous = self.current;
+        self.current = state;
+    }
+}
+
+/// How a camera is moved each tick.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CameraController {
+    /// Stays where it was placed.
+    Fixed,
+    /// Circles the focus, for the editor. Yaw and pitch are in tenths of a degree and the distance is in game units.
+    Orbit {
+        yaw: i32,
+        pitch: i32,
+        distance: GameUnit,
+    },
+    /// Looks down on the focus from the height, in game units. Scrolls when the cursor nears the window's edges.
+    TopDown { height: GameUnit },
+    /// Sees through the eyes of the local player's character.
+    FirstPerson,
+}
+
+/// A view of the world. Positions are in game units, and the pose of the previous tick is kept so the renderer
+/// can move smoothly between ticks.
+pub struct CameraComponent {
+    pub controller: CameraController,
+    pub orthographic_view: bool,
+    /// The point orbiting and top down cameras move around.
+    pub focus: Coordinate3d,
+
+    pub position: Coordinate3d,
+    pub target: Coordinate3d,
+    pub up: Coordinate3d,
+
+    pub previous_position: Coordinate3d,
+    pub previous_target: Coordinate3d,
 }
 
 impl CameraComponent {
-    pub fn new() -> Self {
+    pub fn new(controller: CameraController) -> Self {
+        let zero = GameUnit::from_num(0);
+        let one = GameUnit::from_num(1);
+
         return Self {
-            camera_orthographic_view: false,
-            camera_pos_x: 0,
-            camera_pos_y: 0,
-            camera_pos_z: 0,
-
-            camera_target_x: 0,
-            camera_target_y: 0,
-            camera_target_z: 0,
-            camera_pitch: 0,
-            camera_yaw: 0,
-            camera_roll: 0,
+            controller: controller,
+            orthographic_view: false,
+            focus: Coordinate3d::zero(),
+
+            position: Coordinate3d::zero(),
+            target: Coordinate3d::zero(),
+            up: Coordinate3d::new(zero, zero, one),
+
+            previous_position: Coordinate3d::zero(),
+            previous_target: Coordinate3d::zero(),
         };
     }
+
+    /// Keep the current pose as the previous one; call before moving the camera for a tick.
+    pub fn begin_tick(&mut self) {
+        self.previous_position = self.position;
+        self.previous_target = self.target;
+    }
+
+    /// Move to the pose without moving smoothly from the previous one.
+    pub fn snap_to(&mut self, position: Coordinate3d, target: Coordinate3d) {
+        self.position = position;
+        self.target = target;
+        self.begin_tick();
+    }
 }
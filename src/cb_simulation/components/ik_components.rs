@@ -5,24 +5,136 @@
 extern crate specs;
 use specs::prelude::*;
 
-use crate::cb_math;
-use cb_math::cb_range::CbNormalizedRange;
-
 use crate::cb_inverse_kinematics;
-use cb_inverse_kinematics::IkRig;
+use cb_inverse_kinematics::fixed_point::{fixed_fabrik, FixedIkRig};
+use cb_inverse_kinematics::{fabrik, CbMatrix, IkRig};
+
+use crate::cb_simulation;
+use cb_simulation::locomotion::Legs;
 
-use crate::cb_menu;
+use crate::cb_system;
+use cb_system::Coordinate3d;
 
 use super::ComponentLinker;
 
-init_components![IkComponentsLinker, (IkComponent)];
+init_components![IkComponentsLinker, (IkComponent, LegsComponent)];
+
+/// Which solver poses the rig. Cosmetic rigs solve in floats, which may differ between machines, so only deterministic
+/// rigs may affect the simulation, such as by moving hitboxes.
+pub enum IkSolver {
+    Cosmetic(IkRig),
+    Deterministic(FixedIkRig),
+}
 
 pub struct IkComponent {
-    pub rig: IkRig,
+    pub solver: IkSolver,
 }
 
 impl IkComponent {
     pub fn new() -> Self {
-        return Self { rig: IkRig::new() };
+        return Self::cosmetic(IkRig::new());
+    }
+
+    pub fn cosmetic(rig: IkRig) -> Self {
+        return Self {
+            solver: IkSolver::Cosmetic(rig),
+        };
+    }
+
+    pub fn deterministic(rig: FixedIkRig) -> Self {
+        return Self {
+            solver: IkSolver::Deterministic(rig),
+        };
+    }
+
+    /// Set the target of the rig's main chain, in the rig's space.
+    pub fn set_target(&mut self, target: Coordinate3d) {
+        match &mut self.solver {
+            IkSolver::Cosmetic(rig) => {
+                rig.target = Some(CbMatrix::new(
+                    target.x.to_num(),
+                    target.y.to_num(),
+                    target.z.to_num(),
+                ));
+            }
+            IkSolver::Deterministic(rig) => {
+                rig.target = Some(target);
+            }
+        }
+    }
+
+    pub fn solve(&mut self) {
+        match &mut self.solver {
+            IkSolver::Cosmetic(rig) => fabrik(rig),
+            IkSolver::Deterministic(rig) => fixed_fabrik(rig),
+        }
+    }
+
+    /// Positions of the main chain's joints, offset by the rig's position, for drawing.
+    pub fn joint_positions(&self) -> Vec<CbMatrix> {
+        match &self.solver {
+            IkSolver::Cosmetic(rig) => {
+                return (0..rig.joints.len())
+                    .map(|i| rig.get_child_position(i) + rig.position)
+                    .collect();
+            }
+            IkSolver::Deterministic(rig) => {
+                return (0..rig.joints.len())
+                    .map(|i| {
+                        let p = rig.get_child_position(i);
+                        CbMatrix::new(
+                            (p.x + rig.position.x).to_num(),
+                            (p.y + rig.position.y).to_num(),
+                            (p.z + rig.position.z).to_num(),
+                        )
+                    })
+                    .collect();
+            }
+        }
+    }
+}
+
+/// Legs that walk procedurally under a unit as it moves.
+pub struct LegsComponent {
+    pub legs: Legs,
+}
+
+impl LegsComponent {
+    pub fn new(legs: Legs) -> Self {
+        return Self { legs: legs };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cb_system::GameUnit;
+
+    #[test]
+    fn joint_positions_offset_by_rig_position_for_both_solvers() {
+        let at = |x: i32, y: i32| {
+            Coordinate3d::new(
+                GameUnit::from_num(x),
+                GameUnit::from_num(y),
+                GameUnit::from_num(0),
+            )
+        };
+
+        let mut cosmetic_rig = IkRig::empty();
+        cosmetic_rig.add_joint(CbMatrix::new(0.0, 0.0, 0.0), None);
+        cosmetic_rig.add_joint(CbMatrix::new(10.0, 0.0, 0.0), None);
+        cosmetic_rig.position = CbMatrix::new(100.0, 200.0, 0.0);
+
+        let mut deterministic_rig = FixedIkRig::new();
+        deterministic_rig.add_joint(at(0, 0), None);
+        deterministic_rig.add_joint(at(10, 0), None);
+        deterministic_rig.position = at(100, 200);
+
+        let cosmetic = IkComponent::cosmetic(cosmetic_rig).joint_positions();
+        let deterministic = IkComponent::deterministic(deterministic_rig).joint_positions();
+
+        assert_eq!(cosmetic, deterministic);
+        assert_eq!(CbMatrix::new(110.0, 200.0, 0.0), deterministic[1]);
     }
 }
@@ -0,0 +1,22 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use super::ComponentLinker;
+
+init_components![MatchComponentsLinker, (ObjectiveComponent)];
+
+/// A point on the map that a team controls while only its units, or its allies', are within the radius.
+pub struct ObjectiveComponent {
+    pub radius: GameUnit,
+}
+
+impl ObjectiveComponent {
+    pub fn new(radius: GameUnit) -> Self {
+        return Self { radius: radius };
+    }
+}
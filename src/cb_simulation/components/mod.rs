@@ -10,14 +10,23 @@ use cb_math::cb_range::CbNormalizedRange;
 
 use crate::cb_menu;
 
+pub mod ability_components;
 pub mod actor_components;
+pub mod animation_components;
 pub mod audio;
 pub mod character_components;
+pub mod economy_components;
 pub mod editor_components;
+pub mod fighting_components;
 pub mod gfx_components;
 pub mod ik_components;
+pub mod match_components;
 pub mod menu_components;
+pub mod order_components;
+pub mod ownership_components;
 pub mod physics_components;
+pub mod projectile_components;
+pub mod shooter_components;
 pub mod voxel_components;
 
 /// A simple trait for linking components to the world
@@ -0,0 +1,34 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_system;
+use cb_system::Coordinate2d;
+
+use super::ComponentLinker;
+
+init_components![OrderComponentsLinker, (UnitOrderComponent)];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnitOrder {
+    /// Hold position, attacking enemies that come within range.
+    Idle,
+    /// Move to the target, ignoring enemies.
+    Move { target: Coordinate2d },
+    /// Move to the target, stopping to attack enemies within range along the way.
+    AttackMove { target: Coordinate2d },
+}
+
+/// The movement and combat order the unit is carrying out.
+pub struct UnitOrderComponent {
+    pub order: UnitOrder,
+}
+
+impl UnitOrderComponent {
+    pub fn new() -> Self {
+        return Self {
+            order: UnitOrder::Idle,
+        };
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use super::ComponentLinker;
+
+init_components![OwnershipComponentsLinker, (OwnershipComponent)];
+
+/// The player that controls the entity. Teams and alliances are looked up through the CbTeams resource.
+pub struct OwnershipComponent {
+    pub player_id: usize,
+}
+
+impl OwnershipComponent {
+    pub fn new(player_id: usize) -> Self {
+        return Self {
+            player_id: player_id,
+        };
+    }
+}
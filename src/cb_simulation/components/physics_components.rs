@@ -3,6 +3,9 @@
 extern crate specs;
 use specs::prelude::*;
 
+use crate::cb_simulation;
+use cb_simulation::rigid_body::RigidBody;
+
 use crate::cb_system;
 use cb_system::Coordinate2d;
 
@@ -13,7 +16,7 @@ use super::ComponentLinker;
 
 init_components![
     PhysicsComponentsLinker,
-    (VelocityComponent, TransformComponent)
+    (VelocityComponent, TransformComponent, RigidBodyComponent)
 ];
 
 pub struct VelocityComponent(Coordinate2d);
@@ -41,3 +44,14 @@ impl TransformComponent {
         };
     }
 }
+
+/// A body moved by the rigid body simulation, which keeps the transform's position in sync with it.
+pub struct RigidBodyComponent {
+    pub body: RigidBody,
+}
+
+impl RigidBodyComponent {
+    pub fn new(body: RigidBody) -> Self {
+        return Self { body: body };
+    }
+}
@@ -0,0 +1,26 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_simulation;
+use cb_simulation::projectiles::Projectile;
+
+use super::ComponentLinker;
+
+init_components![ProjectileComponentsLinker, (ProjectileComponent)];
+
+/// A projectile in flight, fired by one of the player's characters or units.
+pub struct ProjectileComponent {
+    pub projectile: Projectile,
+    pub player_id: usize,
+}
+
+impl ProjectileComponent {
+    pub fn new(projectile: Projectile, player_id: usize) -> Self {
+        return Self {
+            projectile: projectile,
+            player_id: player_id,
+        };
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+extern crate specs;
+use specs::prelude::*;
+
+use crate::cb_simulation;
+use cb_simulation::character_controller::CharacterState;
+use cb_simulation::projectiles::ProjectileDefinition;
+
+use crate::cb_system;
+use cb_system::Coordinate3d;
+
+use super::ComponentLinker;
+
+init_components![
+    ShooterComponentsLinker,
+    (CharacterControllerComponent, WeaponComponent)
+];
+
+/// A first person character moved by its actor's shooter inputs.
+pub struct CharacterControllerComponent {
+    pub state: CharacterState,
+}
+
+impl CharacterControllerComponent {
+    pub fn new(position: Coordinate3d) -> Self {
+        return Self {
+            state: CharacterState::new(position),
+        };
+    }
+}
+
+/// A weapon that fires projectiles, with a number of ticks between shots.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Weapon {
+    definition: ProjectileDefinition,
+    cooldown_ticks: u32,
+    /// Ticks until the weapon may fire again.
+    pub cooldown_remaining: u32,
+}
+
+impl Weapon {
+    pub fn new(definition: ProjectileDefinition, cooldown_ticks: u32) -> Self {
+        return Self {
+            definition: definition,
+            cooldown_ticks: cooldown_ticks,
+            cooldown_remaining: 0,
+        };
+    }
+
+    pub fn definition(&self) -> ProjectileDefinition {
+        return self.definition;
+    }
+
+    pub fn cooldown_ticks(&self) -> u32 {
+        return self.cooldown_ticks;
+    }
+}
+
+/// The weapons a character carries, fired with the primary and secondary fire inputs.
+pub struct WeaponComponent {
+    pub primary: Weapon,
+    pub secondary: Option<Weapon>,
+}
+
+impl WeaponComponent {
+    pub fn new(primary: Weapon, secondary: Option<Weapon>) -> Self {
+        return Self {
+            primary: primary,
+            secondary: secondary,
+        };
+    }
+}
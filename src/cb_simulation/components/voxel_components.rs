@@ -9,6 +9,10 @@ use crate::cb_patterns;
 use cb_patterns::command::MacroCommand;
 
 use crate::cb_voxels;
+use cb_voxels::vox::VoxFile;
+
+use crate::cb_assets;
+use cb_assets::Handle;
 
 use crate::cb_math::cb_range::CbNormalizedRange;
 
@@ -23,6 +27,8 @@ init_components![VoxelComponentsLinker, (VoxelComponent)];
 pub struct VoxelComponent {
     pub chunk_manager: cb_voxels::CbChunkManager,
     pub editor: VoxelEditor,
+    /// The asset the terrain was loaded from, if any, and the version of it that was loaded.
+    map: Option<(Handle<VoxFile>, u64)>,
 }
 
 #[derive(Clone)]
@@ -195,10 +201,96 @@ impl VoxelComponent {
         return Self {
             editor: VoxelEditor::new(),
             chunk_manager: cb_voxels::CbChunkManager::new(),
+            map: None,
         };
     }
+
+    /// Terrain from the first model of a voxel map asset. It is loaded again whenever the asset is reloaded.
+    pub fn from_map(map: Handle<VoxFile>, frame: usize) -> Result<Self, String> {
+        let mut voxel = Self::new();
+        voxel.map = Some((map, 0));
+        voxel.reload_map(frame)?;
+
+        return Ok(voxel);
+    }
+
+    /// Replace the terrain if its map asset changed since it was loaded, returning whether it did.
+    pub fn reload_map(&mut self, frame: usize) -> Result<bool, String> {
+        let (map, loaded_version) = match &mut self.map {
+            Some(map) => map,
+            None => return Ok(false),
+        };
+
+        let version = map.version();
+        if version == *loaded_version {
+            return Ok(false);
+        }
+
+        let file = map
+            .get()
+            .ok_or_else(|| format!("Voxel map {} is not loaded", map.path().display()))?;
+
+        let mut chunk_manager = cb_voxels::CbChunkManager::empty(frame);
+        file.load_into(0, &mut chunk_manager, (0, 0, 0), frame)?;
+
+        self.chunk_manager = chunk_manager;
+        *loaded_version = version;
+
+        return Ok(true);
+    }
 }
 
 pub struct VoxelComponentController {
     command_stack: MacroCommand,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cb_assets::AssetManager;
+    use cb_voxels::vox::VoxModel;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    fn save_map(path: &Path, voxels: Vec<((u8, u8, u8), u8)>) {
+        let mut file = VoxFile::new();
+        file.models.push(VoxModel {
+            size: (4, 4, 4),
+            voxels: voxels,
+        });
+        file.save(path).unwrap();
+
+        // Writes within a second may share a modified time
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[test]
+    fn voxel_component_reload_map_replaces_terrain_when_asset_changes() {
+        let root = std::env::temp_dir().join(format!("cb_voxel_map_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("map.vox");
+        save_map(&path, vec![((1, 2, 0), 1)]);
+
+        let assets = AssetManager::new(&root);
+        let map = assets.load::<VoxFile>(Path::new("map.vox")).unwrap();
+        let mut voxel = VoxelComponent::from_map(map, 3).unwrap();
+
+        assert_eq!(1, voxel.chunk_manager.get_column_height(1, 2));
+        assert_eq!(0, voxel.chunk_manager.get_column_height(0, 0));
+        assert_eq!(Ok(false), voxel.reload_map(4));
+
+        save_map(&path, vec![((0, 0, 0), 1), ((0, 0, 1), 1)]);
+        assets.hot_reload();
+
+        assert_eq!(Ok(true), voxel.reload_map(5));
+        assert_eq!(0, voxel.chunk_manager.get_column_height(1, 2));
+        assert_eq!(2, voxel.chunk_manager.get_column_height(0, 0));
+        assert_eq!(5, voxel.chunk_manager.chunk_array[1][1][1].frame_updated_at);
+    }
+}
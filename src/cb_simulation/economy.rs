@@ -0,0 +1,254 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Per player stockpiles and supply. Amounts are whole numbers so that every peer agrees on what can be afforded.
+*/
+
+use crate::cb_system;
+use cb_system::Coordinate2d;
+
+use super::unit_definitions::UnitTypeId;
+
+/// The most supply a player may ever have, regardless of how many supply providers they own.
+pub const MAX_SUPPLY: u32 = 200;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ResourceType {
+    Ore,
+    Crystal,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Resources {
+    pub ore: u32,
+    pub crystal: u32,
+}
+
+impl Resources {
+    pub fn new(ore: u32, crystal: u32) -> Self {
+        return Self {
+            ore: ore,
+            crystal: crystal,
+        };
+    }
+
+    pub fn zero() -> Self {
+        return Self::new(0, 0);
+    }
+
+    pub fn get(&self, resource: ResourceType) -> u32 {
+        match resource {
+            ResourceType::Ore => self.ore,
+            ResourceType::Crystal => self.crystal,
+        }
+    }
+
+    pub fn add(&mut self, resource: ResourceType, amount: u32) {
+        match resource {
+            ResourceType::Ore => self.ore = self.ore.saturating_add(amount),
+            ResourceType::Crystal => self.crystal = self.crystal.saturating_add(amount),
+        }
+    }
+
+    pub fn can_afford(&self, cost: &Resources) -> bool {
+        return self.ore >= cost.ore && self.crystal >= cost.crystal;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerEconomy {
+    pub player_id: usize,
+    pub stockpile: Resources,
+    pub supply_used: u32,
+    pub supply_cap: u32,
+}
+
+impl PlayerEconomy {
+    pub fn new(player_id: usize, stockpile: Resources) -> Self {
+        return Self {
+            player_id: player_id,
+            stockpile: stockpile,
+            supply_used: 0,
+            supply_cap: 0,
+        };
+    }
+
+    pub fn supply_available(&self) -> u32 {
+        return self.supply_cap.saturating_sub(self.supply_used);
+    }
+}
+
+/// The stockpiles and supply of every player. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CbEconomy {
+    players: Vec<PlayerEconomy>,
+}
+
+impl CbEconomy {
+    pub fn new() -> Self {
+        return Self { players: vec![] };
+    }
+
+    pub fn add_player(&mut self, player_id: usize, starting_resources: Resources) {
+        self.players.retain(|p| p.player_id != player_id);
+        self.players
+            .push(PlayerEconomy::new(player_id, starting_resources));
+        self.players.sort_by_key(|p| p.player_id);
+    }
+
+    pub fn player(&self, player_id: usize) -> Option<&PlayerEconomy> {
+        return self.players.iter().find(|p| p.player_id == player_id);
+    }
+
+    pub fn player_mut(&mut self, player_id: usize) -> Option<&mut PlayerEconomy> {
+        return self.players.iter_mut().find(|p| p.player_id == player_id);
+    }
+
+    pub fn players(&self) -> &Vec<PlayerEconomy> {
+        return &self.players;
+    }
+
+    pub fn deposit(&mut self, player_id: usize, resource: ResourceType, amount: u32) {
+        if let Some(player) = self.player_mut(player_id) {
+            player.stockpile.add(resource, amount);
+        }
+    }
+
+    /// Spend the resources and reserve the supply if the player has enough of both. Returns whether it was spent.
+    pub fn try_spend(&mut self, player_id: usize, cost: &Resources, supply: u32) -> bool {
+        let player = match self.player_mut(player_id) {
+            Some(p) => p,
+            None => {
+                return false;
+            }
+        };
+
+        if !player.stockpile.can_afford(cost) || player.supply_available() < supply {
+            return false;
+        }
+
+        player.stockpile.ore -= cost.ore;
+        player.stockpile.crystal -= cost.crystal;
+        player.supply_used += supply;
+
+        return true;
+    }
+
+    /// Return previously spent resources and reserved supply to the player.
+    pub fn refund(&mut self, player_id: usize, cost: &Resources, supply: u32) {
+        if let Some(player) = self.player_mut(player_id) {
+            player.stockpile.add(ResourceType::Ore, cost.ore);
+            player.stockpile.add(ResourceType::Crystal, cost.crystal);
+            player.supply_used = player.supply_used.saturating_sub(supply);
+        }
+    }
+
+    /// Reset supply for the tick; supply used and provided are recounted from the world each tick.
+    pub fn reset_supply(&mut self) {
+        for player in self.players.iter_mut() {
+            player.supply_used = 0;
+            player.supply_cap = 0;
+        }
+    }
+
+    pub fn add_supply_used(&mut self, player_id: usize, amount: u32) {
+        if let Some(player) = self.player_mut(player_id) {
+            player.supply_used = player.supply_used.saturating_add(amount);
+        }
+    }
+
+    pub fn add_supply_cap(&mut self, player_id: usize, amount: u32) {
+        if let Some(player) = self.player_mut(player_id) {
+            player.supply_cap = player.supply_cap.saturating_add(amount).min(MAX_SUPPLY);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnRequest {
+    pub unit_type: UnitTypeId,
+    pub position: Coordinate2d,
+    pub player_id: usize,
+}
+
+/// Units that systems have requested be spawned. Drained after the simulation systems run, as spawning requires the world.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CbSpawnQueue {
+    pub requests: Vec<SpawnRequest>,
+}
+
+impl CbSpawnQueue {
+    pub fn new() -> Self {
+        return Self { requests: vec![] };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_spend_affordable_deducts_resources_and_reserves_supply() {
+        let mut economy = CbEconomy::new();
+        economy.add_player(1, Resources::new(100, 10));
+        economy.add_supply_cap(1, 10);
+
+        assert_eq!(true, economy.try_spend(1, &Resources::new(50, 10), 2));
+
+        let player = economy.player(1).unwrap();
+        assert_eq!(Resources::new(50, 0), player.stockpile);
+        assert_eq!(2, player.supply_used);
+    }
+
+    #[test]
+    fn try_spend_not_enough_resources_returns_false() {
+        let mut economy = CbEconomy::new();
+        economy.add_player(1, Resources::new(40, 0));
+        economy.add_supply_cap(1, 10);
+
+        assert_eq!(false, economy.try_spend(1, &Resources::new(50, 0), 1));
+        assert_eq!(Resources::new(40, 0), economy.player(1).unwrap().stockpile);
+    }
+
+    #[test]
+    fn try_spend_not_enough_supply_returns_false() {
+        let mut economy = CbEconomy::new();
+        economy.add_player(1, Resources::new(100, 0));
+        economy.add_supply_cap(1, 1);
+        economy.add_supply_used(1, 1);
+
+        assert_eq!(false, economy.try_spend(1, &Resources::new(50, 0), 1));
+    }
+
+    #[test]
+    fn try_spend_unknown_player_returns_false() {
+        let mut economy = CbEconomy::new();
+
+        assert_eq!(false, economy.try_spend(3, &Resources::zero(), 0));
+    }
+
+    #[test]
+    fn refund_returns_resources_and_supply() {
+        let mut economy = CbEconomy::new();
+        economy.add_player(1, Resources::new(100, 0));
+        economy.add_supply_cap(1, 10);
+        economy.try_spend(1, &Resources::new(60, 0), 2);
+
+        economy.refund(1, &Resources::new(60, 0), 2);
+
+        let player = economy.player(1).unwrap();
+        assert_eq!(Resources::new(100, 0), player.stockpile);
+        assert_eq!(0, player.supply_used);
+    }
+
+    #[test]
+    fn add_supply_cap_is_limited_to_max_supply() {
+        let mut economy = CbEconomy::new();
+        economy.add_player(1, Resources::zero());
+
+        economy.add_supply_cap(1, MAX_SUPPLY);
+        economy.add_supply_cap(1, 10);
+
+        assert_eq!(MAX_SUPPLY, economy.player(1).unwrap().supply_cap);
+    }
+}
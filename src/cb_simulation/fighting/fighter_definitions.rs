@@ -0,0 +1,623 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Fighters are described by datasheets in the fighters folder. Each move lists the inputs that perform it, its frame
+    data and the moves it may be cancelled into. Every frame data entry lasts frame_duration frames; entries before the
+    first with hit boxes are the move's startup, those with hit boxes are active and those after are recovery.
+*/
+
+use std::path::Path;
+
+use crate::cb_datasheets;
+use cb_datasheets::DataValue;
+
+use super::input_buffer::{Button, Direction, InputStep};
+
+pub const FIGHTERS_PATH: &'static str = "./src/cb_simulation/datasheets/fighters";
+
+/// The id of the move fighters return to when they are not doing anything else. Every fighter must have it.
+pub const IDLE_MOVE: MoveId = 0;
+
+/// How many frames a move's input string may take to enter, when the datasheet does not say.
+const DEFAULT_INPUT_WINDOW: u32 = 15;
+
+pub type MoveId = usize;
+
+/// A box relative to the fighter's feet, in game units. X increases in the direction the fighter faces.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FighterBox {
+    pub start_x: i32,
+    pub start_y: i32,
+    pub end_x: i32,
+    pub end_y: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameData {
+    pub frame_duration: u32,
+    pub damage: u32,
+    /// Frames of hitstun dealt on hit.
+    pub stun_frames: u32,
+    /// How many frames sooner the attacker may act than the defender, after the move is blocked or hits.
+    pub advantage_on_block: i32,
+    pub advantage_on_hit: i32,
+    pub is_cancel_window: bool,
+    pub hit_boxes: Vec<FighterBox>,
+    /// When empty, the idle move's hurt and push boxes are used.
+    pub hurt_boxes: Vec<FighterBox>,
+    pub push_boxes: Vec<FighterBox>,
+    pub grab_boxes: Vec<FighterBox>,
+    pub throw_boxes: Vec<FighterBox>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveDefinition {
+    pub id: MoveId,
+    pub title: String,
+    /// Moves with no input string are only entered by transitions.
+    pub input_string: Vec<InputStep>,
+    pub input_window: u32,
+    pub transition_when_complete: MoveId,
+    /// Upwards speed given when the move starts, in game units per frame. Moves that jump last until landing.
+    pub jump_velocity: i32,
+    pub frame_data: Vec<FrameData>,
+    pub cancels: Vec<MoveId>,
+}
+
+impl MoveDefinition {
+    pub fn total_frames(&self) -> u32 {
+        return self.frame_data.iter().map(|f| f.frame_duration).sum();
+    }
+
+    /// Returns the frame data for the given frame of the move.
+    pub fn frame_at(&self, frame: u32) -> Option<&FrameData> {
+        let mut start = 0;
+
+        for data in self.frame_data.iter() {
+            if frame < start + data.frame_duration {
+                return Some(data);
+            }
+
+            start += data.frame_duration;
+        }
+
+        return None;
+    }
+
+    pub fn startup_frames(&self) -> u32 {
+        return self
+            .frame_data
+            .iter()
+            .take_while(|f| f.hit_boxes.is_empty())
+            .map(|f| f.frame_duration)
+            .sum();
+    }
+
+    pub fn active_frames(&self) -> u32 {
+        return self
+            .frame_data
+            .iter()
+            .filter(|f| !f.hit_boxes.is_empty())
+            .map(|f| f.frame_duration)
+            .sum();
+    }
+
+    pub fn recovery_frames(&self) -> u32 {
+        let attacks = self.active_frames() > 0;
+
+        if !attacks {
+            return 0;
+        }
+
+        return self.total_frames() - self.startup_frames() - self.active_frames();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FighterDefinition {
+    pub id: String,
+    pub version: String,
+    pub name: String,
+    pub health: u32,
+    /// Speeds are in game units per frame, gravity in game units per frame per frame.
+    pub walk_speed: i32,
+    pub back_walk_speed: i32,
+    pub gravity: i32,
+    pub moves: Vec<MoveDefinition>,
+}
+
+impl FighterDefinition {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let root = cb_datasheets::from_path(path)?;
+
+        return parse_fighter(&root).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root = cb_datasheets::parse(source)?;
+
+        return parse_fighter(&root);
+    }
+
+    pub fn get_move(&self, id: MoveId) -> Option<&MoveDefinition> {
+        return self.moves.iter().find(|m| m.id == id);
+    }
+
+    pub fn idle(&self) -> &MoveDefinition {
+        return self.get_move(IDLE_MOVE).unwrap();
+    }
+}
+
+/// Every fighter that may be picked. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FighterDefinitions {
+    definitions: Vec<FighterDefinition>,
+}
+
+impl FighterDefinitions {
+    pub fn new() -> Self {
+        return Self {
+            definitions: vec![],
+        };
+    }
+
+    /// Load every fighter datasheet in the folder. Fighters are ordered by file name, so indexes match across peers.
+    pub fn from_directory(path: &Path) -> Result<Self, String> {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(format!("unable to read '{}': {}", path.display(), e));
+            }
+        };
+
+        let mut paths: Vec<std::path::PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |e| e == "hjson"))
+            .collect();
+        paths.sort();
+
+        let mut definitions = Self::new();
+        for path in paths.iter() {
+            definitions.add(FighterDefinition::from_path(path)?)?;
+        }
+
+        return Ok(definitions);
+    }
+
+    pub fn add(&mut self, definition: FighterDefinition) -> Result<usize, String> {
+        if self.definitions.iter().any(|d| d.id == definition.id) {
+            return Err(format!("duplicate fighter '{}'", definition.id));
+        }
+
+        self.definitions.push(definition);
+
+        return Ok(self.definitions.len() - 1);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&FighterDefinition> {
+        return self.definitions.get(index);
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        return self.definitions.iter().position(|d| d.name == name);
+    }
+}
+
+fn parse_fighter(root: &DataValue) -> Result<FighterDefinition, String> {
+    check_fields(
+        "fighter",
+        root,
+        &[
+            "id",
+            "version",
+            "name",
+            "health",
+            "walk_speed",
+            "back_walk_speed",
+            "gravity",
+            "state_data",
+        ],
+    )?;
+
+    let name = read_str("fighter", root, "name")?;
+    let context = format!("fighter '{}'", name);
+
+    let states = match root.get("state_data").and_then(|s| s.as_array()) {
+        Some(states) => states,
+        None => {
+            return Err(format!("{}: expected a 'state_data' array", context));
+        }
+    };
+
+    let mut definition = FighterDefinition {
+        id: read_str(&context, root, "id")?,
+        version: read_str(&context, root, "version")?,
+        name: name,
+        health: read_i32(&context, root, "health")?.max(1) as u32,
+        walk_speed: read_i32(&context, root, "walk_speed")?,
+        back_walk_speed: read_i32(&context, root, "back_walk_speed")?,
+        gravity: read_i32(&context, root, "gravity")?,
+        moves: vec![],
+    };
+
+    for state in states.iter() {
+        let fighter_move = parse_move(&context, state)?;
+
+        if definition.get_move(fighter_move.id).is_some() {
+            return Err(format!(
+                "{}: duplicate move id {}",
+                context, fighter_move.id
+            ));
+        }
+
+        definition.moves.push(fighter_move);
+    }
+
+    if definition.get_move(IDLE_MOVE).is_none() {
+        return Err(format!(
+            "{}: requires an idle move with id {}",
+            context, IDLE_MOVE
+        ));
+    }
+
+    // Transitions and cancels may only reference moves that exist
+    for fighter_move in definition.moves.iter() {
+        let references = std::iter::once(&fighter_move.transition_when_complete)
+            .chain(fighter_move.cancels.iter());
+
+        for id in references {
+            if definition.get_move(*id).is_none() {
+                return Err(format!(
+                    "{}: move '{}' references unknown move {}",
+                    context, fighter_move.title, id
+                ));
+            }
+        }
+    }
+
+    return Ok(definition);
+}
+
+fn parse_move(context: &str, state: &DataValue) -> Result<MoveDefinition, String> {
+    let title = read_str(context, state, "title")?;
+    let context = format!("{} move '{}'", context, title);
+
+    check_fields(
+        &context,
+        state,
+        &[
+            "id",
+            "title",
+            "input_string",
+            "input_window",
+            "transition_when_complete",
+            "jump_velocity",
+            "frame_data",
+            "cancels",
+        ],
+    )?;
+
+    let mut input_string = vec![];
+    if let Some(steps) = state.get("input_string") {
+        let steps = match steps.as_array() {
+            Some(steps) => steps,
+            None => {
+                return Err(format!("{}: 'input_string' must be an array", context));
+            }
+        };
+
+        for step in steps.iter() {
+            input_string.push(parse_input_step(&context, step)?);
+        }
+    }
+
+    let frames = match state.get("frame_data").and_then(|f| f.as_array()) {
+        Some(frames) => frames,
+        None => {
+            return Err(format!("{}: expected a 'frame_data' array", context));
+        }
+    };
+
+    let mut frame_data = vec![];
+    for frame in frames.iter() {
+        frame_data.push(parse_frame_data(&context, frame)?);
+    }
+
+    if frame_data.iter().all(|f| f.frame_duration == 0) {
+        return Err(format!("{}: must last at least one frame", context));
+    }
+
+    let mut cancels = vec![];
+    if let Some(ids) = state.get("cancels") {
+        for id in ids.as_array().unwrap_or(&vec![]).iter() {
+            match id.as_u32() {
+                Some(id) => cancels.push(id as MoveId),
+                None => {
+                    return Err(format!("{}: 'cancels' must be a list of move ids", context));
+                }
+            }
+        }
+    }
+
+    return Ok(MoveDefinition {
+        id: read_i32(&context, state, "id")?.max(0) as MoveId,
+        title: title,
+        input_string: input_string,
+        input_window: read_i32_or(&context, state, "input_window", DEFAULT_INPUT_WINDOW as i32)?
+            .max(0) as u32,
+        transition_when_complete: read_i32(&context, state, "transition_when_complete")?.max(0)
+            as MoveId,
+        jump_velocity: read_i32_or(&context, state, "jump_velocity", 0)?,
+        frame_data: frame_data,
+        cancels: cancels,
+    });
+}
+
+fn parse_input_step(context: &str, step: &DataValue) -> Result<InputStep, String> {
+    check_fields(context, step, &["inputs", "hold_frames"])?;
+
+    let inputs = match step.get("inputs").and_then(|i| i.as_array()) {
+        Some(inputs) => inputs,
+        None => {
+            return Err(format!(
+                "{}: each input step requires an 'inputs' array",
+                context
+            ));
+        }
+    };
+
+    let mut input_step = InputStep {
+        directions: vec![],
+        buttons: vec![],
+        hold_frames: read_i32_or(context, step, "hold_frames", 0)?.max(0) as u32,
+    };
+
+    for input in inputs.iter() {
+        let name = input.as_str().unwrap_or("<non-scalar>");
+
+        if let Some(direction) = Direction::from_name(name) {
+            input_step.directions.push(direction);
+        } else if let Some(button) = Button::from_name(name) {
+            input_step.buttons.push(button);
+        } else {
+            return Err(format!("{}: unknown input '{}'", context, name));
+        }
+    }
+
+    return Ok(input_step);
+}
+
+fn parse_frame_data(context: &str, frame: &DataValue) -> Result<FrameData, String> {
+    check_fields(
+        context,
+        frame,
+        &[
+            "frame_duration",
+            "damage",
+            "stun_frames",
+            "advantage_on_block",
+            "advantage_on_hit",
+            "is_cancel_window",
+            "hit_boxes",
+            "hurt_boxes",
+            "push_boxes",
+            "grab_boxes",
+            "throw_boxes",
+        ],
+    )?;
+
+    let is_cancel_window = match frame.get("is_cancel_window") {
+        Some(value) => match value.as_bool() {
+            Some(b) => b,
+            None => {
+                return Err(format!(
+                    "{}: 'is_cancel_window' must be true or false",
+                    context
+                ));
+            }
+        },
+        None => false,
+    };
+
+    return Ok(FrameData {
+        frame_duration: read_i32(context, frame, "frame_duration")?.max(0) as u32,
+        damage: read_i32_or(context, frame, "damage", 0)?.max(0) as u32,
+        stun_frames: read_i32_or(context, frame, "stun_frames", 0)?.max(0) as u32,
+        advantage_on_block: read_i32_or(context, frame, "advantage_on_block", 0)?,
+        advantage_on_hit: read_i32_or(context, frame, "advantage_on_hit", 0)?,
+        is_cancel_window: is_cancel_window,
+        hit_boxes: parse_boxes(context, frame, "hit_boxes")?,
+        hurt_boxes: parse_boxes(context, frame, "hurt_boxes")?,
+        push_boxes: parse_boxes(context, frame, "push_boxes")?,
+        grab_boxes: parse_boxes(context, frame, "grab_boxes")?,
+        throw_boxes: parse_boxes(context, frame, "throw_boxes")?,
+    });
+}
+
+fn parse_boxes(context: &str, frame: &DataValue, field: &str) -> Result<Vec<FighterBox>, String> {
+    let boxes = match frame.get(field) {
+        Some(boxes) => match boxes.as_array() {
+            Some(boxes) => boxes,
+            None => {
+                return Err(format!("{}: '{}' must be an array", context, field));
+            }
+        },
+        None => {
+            return Ok(vec![]);
+        }
+    };
+
+    let mut parsed = vec![];
+    for b in boxes.iter() {
+        check_fields(context, b, &["start_x", "start_y", "end_x", "end_y"])?;
+
+        let fighter_box = FighterBox {
+            start_x: read_i32(context, b, "start_x")?,
+            start_y: read_i32(context, b, "start_y")?,
+            end_x: read_i32(context, b, "end_x")?,
+            end_y: read_i32(context, b, "end_y")?,
+        };
+
+        if fighter_box.end_x <= fighter_box.start_x || fighter_box.end_y <= fighter_box.start_y {
+            return Err(format!(
+                "{}: '{}' must end after they start",
+                context, field
+            ));
+        }
+
+        parsed.push(fighter_box);
+    }
+
+    return Ok(parsed);
+}
+
+fn check_fields(context: &str, value: &DataValue, fields: &[&str]) -> Result<(), String> {
+    let members = match value.as_object() {
+        Some(m) => m,
+        None => {
+            return Err(format!("{}: must be an object", context));
+        }
+    };
+
+    for (key, _) in members.iter() {
+        if !fields.iter().any(|f| f == key) {
+            return Err(format!("{}: unknown field '{}'", context, key));
+        }
+    }
+
+    return Ok(());
+}
+
+fn read_str(context: &str, value: &DataValue, field: &str) -> Result<String, String> {
+    match value.get(field).and_then(|v| v.as_str()) {
+        Some(s) => {
+            return Ok(s.trim().to_string());
+        }
+        None => {
+            return Err(format!("{}: is missing field '{}'", context, field));
+        }
+    }
+}
+
+fn read_i32(context: &str, value: &DataValue, field: &str) -> Result<i32, String> {
+    let raw = match value.get(field) {
+        Some(raw) => raw,
+        None => {
+            return Err(format!("{}: is missing field '{}'", context, field));
+        }
+    };
+
+    match raw.as_i32() {
+        Some(v) => {
+            return Ok(v);
+        }
+        None => {
+            return Err(format!(
+                "{}: field '{}' must be an integer, found '{}'",
+                context,
+                field,
+                raw.as_str().unwrap_or("<non-scalar>")
+            ));
+        }
+    }
+}
+
+fn read_i32_or(context: &str, value: &DataValue, field: &str, default: i32) -> Result<i32, String> {
+    if value.get(field).is_none() {
+        return Ok(default);
+    }
+
+    return read_i32(context, value, field);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL_FIGHTER: &'static str = "
+        id: test
+        version: 1
+        name: Tester
+        health: 100
+        walk_speed: 10
+        back_walk_speed: 8
+        gravity: 2
+        state_data: [
+            {
+                id: 0
+                title: idle
+                transition_when_complete: 0
+                frame_data: [
+                    { frame_duration: 1, is_cancel_window: true, hurt_boxes: [{ start_x: -100, start_y: 0, end_x: 100, end_y: 1000 }] }
+                ]
+                cancels: [1]
+            }
+            {
+                id: 1
+                title: jab
+                input_string: [{ inputs: [light_punch] }]
+                transition_when_complete: 0
+                frame_data: [
+                    { frame_duration: 3 }
+                    { frame_duration: 2, damage: 10, stun_frames: 8, hit_boxes: [{ start_x: 0, start_y: 500, end_x: 300, end_y: 700 }] }
+                    { frame_duration: 6 }
+                ]
+            }
+        ]
+    ";
+
+    #[test]
+    fn fighter_definition_parse_minimal_returns_frame_data_phases() {
+        let fighter = FighterDefinition::parse(MINIMAL_FIGHTER).unwrap();
+        let jab = fighter.get_move(1).unwrap();
+
+        assert_eq!("Tester", fighter.name);
+        assert_eq!(vec![Button::LightPunch], jab.input_string[0].buttons);
+        assert_eq!(DEFAULT_INPUT_WINDOW, jab.input_window);
+        assert_eq!(11, jab.total_frames());
+        assert_eq!(3, jab.startup_frames());
+        assert_eq!(2, jab.active_frames());
+        assert_eq!(6, jab.recovery_frames());
+        assert_eq!(10, jab.frame_at(4).unwrap().damage);
+        assert_eq!(None, jab.frame_at(11));
+    }
+
+    #[test]
+    fn fighter_definition_parse_unknown_cancel_returns_err() {
+        let source = MINIMAL_FIGHTER.replace("cancels: [1]", "cancels: [7]");
+
+        let actual = FighterDefinition::parse(&source);
+
+        assert_eq!(
+            Err("fighter 'Tester': move 'idle' references unknown move 7".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn fighter_definition_parse_unknown_input_returns_err() {
+        let source = MINIMAL_FIGHTER.replace("inputs: [light_punch]", "inputs: [medium_punch]");
+
+        let actual = FighterDefinition::parse(&source);
+
+        assert_eq!(
+            Err("fighter 'Tester' move 'jab': unknown input 'medium_punch'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn fighter_definitions_from_directory_loads_faceless_one() {
+        let definitions = FighterDefinitions::from_directory(Path::new(FIGHTERS_PATH)).unwrap();
+
+        let index = definitions.index_of("The Faceless One").unwrap();
+        let fighter = definitions.get(index).unwrap();
+
+        assert!(fighter
+            .moves
+            .iter()
+            .any(|m| m.title == "neutral light punch"));
+        assert!(fighter.idle().frame_data[0].hurt_boxes.len() > 0);
+    }
+}
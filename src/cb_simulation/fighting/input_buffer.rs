@@ -0,0 +1,364 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    A short history of each fighter's inputs, used to recognize motion inputs such as quarter circles, dragon punches
+    and charge moves. Directions are stored relative to the way the fighter was facing, so motions read the same on
+    either side of the stage.
+*/
+
+use crate::cb_simulation;
+use cb_simulation::actor_controller::InputHistory;
+
+/// How many frames of input each fighter remembers. Long enough for the slowest charge move.
+pub const INPUT_BUFFER_FRAMES: usize = 64;
+
+/// Directions in numpad notation, relative to the fighter's facing.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    DownBack,
+    Down,
+    DownForward,
+    Back,
+    Neutral,
+    Forward,
+    UpBack,
+    Up,
+    UpForward,
+}
+
+impl Direction {
+    /// Build a direction from its axes; 1 is forward or up, -1 is back or down.
+    pub fn from_axes(horizontal: i32, vertical: i32) -> Self {
+        match (horizontal.signum(), vertical.signum()) {
+            (-1, -1) => Direction::DownBack,
+            (0, -1) => Direction::Down,
+            (1, -1) => Direction::DownForward,
+            (-1, 0) => Direction::Back,
+            (1, 0) => Direction::Forward,
+            (-1, 1) => Direction::UpBack,
+            (0, 1) => Direction::Up,
+            (1, 1) => Direction::UpForward,
+            _ => Direction::Neutral,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "down_back" => Some(Direction::DownBack),
+            "down" => Some(Direction::Down),
+            "down_forward" => Some(Direction::DownForward),
+            "back" => Some(Direction::Back),
+            "neutral" => Some(Direction::Neutral),
+            "forward" => Some(Direction::Forward),
+            "up_back" => Some(Direction::UpBack),
+            "up" => Some(Direction::Up),
+            "up_forward" => Some(Direction::UpForward),
+            _ => None,
+        }
+    }
+
+    /// Returns the direction's axes; 1 is forward or up, -1 is back or down.
+    pub fn axes(&self) -> (i32, i32) {
+        match self {
+            Direction::DownBack => (-1, -1),
+            Direction::Down => (0, -1),
+            Direction::DownForward => (1, -1),
+            Direction::Back => (-1, 0),
+            Direction::Neutral => (0, 0),
+            Direction::Forward => (1, 0),
+            Direction::UpBack => (-1, 1),
+            Direction::Up => (0, 1),
+            Direction::UpForward => (1, 1),
+        }
+    }
+
+    /// Returns whether every axis this direction uses is also held in the other, e.g. down back contains back.
+    pub fn is_held_in(&self, other: Direction) -> bool {
+        let (h, v) = self.axes();
+        let (other_h, other_v) = other.axes();
+
+        return (h == 0 || h == other_h) && (v == 0 || v == other_v);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Button {
+    LightPunch,
+    HeavyPunch,
+    LightKick,
+    HeavyKick,
+}
+
+impl Button {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "light_punch" => Some(Button::LightPunch),
+            "heavy_punch" => Some(Button::HeavyPunch),
+            "light_kick" => Some(Button::LightKick),
+            "heavy_kick" => Some(Button::HeavyKick),
+            _ => None,
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        match self {
+            Button::LightPunch => 1,
+            Button::HeavyPunch => 1 << 1,
+            Button::LightKick => 1 << 2,
+            Button::HeavyKick => 1 << 3,
+        }
+    }
+}
+
+/// The direction held and buttons pressed by a fighter on a single frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FightingInput {
+    pub direction: Direction,
+    buttons: u8,
+}
+
+impl FightingInput {
+    pub fn new(direction: Direction, buttons: &[Button]) -> Self {
+        return Self {
+            direction: direction,
+            buttons: buttons.iter().fold(0, |bits, b| bits | b.bit()),
+        };
+    }
+
+    pub fn neutral() -> Self {
+        return Self::new(Direction::Neutral, &[]);
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        return self.buttons & button.bit() != 0;
+    }
+}
+
+/// One step of a move's input string. The step is matched on a frame when any of its directions is held and all of its
+/// buttons are pressed. Steps with hold frames are charges; their direction only needs to be contained in what is held,
+/// for that many frames in a row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputStep {
+    pub directions: Vec<Direction>,
+    pub buttons: Vec<Button>,
+    pub hold_frames: u32,
+}
+
+impl InputStep {
+    fn matches(&self, input: &FightingInput) -> bool {
+        let direction_matches = self.directions.is_empty()
+            || self.directions.iter().any(|d| {
+                if self.hold_frames > 0 {
+                    d.is_held_in(input.direction)
+                } else {
+                    *d == input.direction
+                }
+            });
+
+        return direction_matches && self.buttons.iter().all(|b| input.is_pressed(*b));
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InputBuffer {
+    frames: InputHistory<FightingInput, INPUT_BUFFER_FRAMES>,
+}
+
+impl InputBuffer {
+    pub fn new() -> Self {
+        return Self {
+            frames: InputHistory::new(FightingInput::neutral()),
+        };
+    }
+
+    pub fn push(&mut self, input: FightingInput) {
+        self.frames.push(input);
+    }
+
+    /// Returns the input from the given number of frames ago, where 0 is the latest.
+    pub fn get(&self, frames_ago: usize) -> Option<&FightingInput> {
+        return self.frames.get(frames_ago);
+    }
+
+    pub fn latest(&self) -> FightingInput {
+        return self.get(0).copied().unwrap_or(FightingInput::neutral());
+    }
+
+    /// Returns whether the steps were entered in order, ending on the latest frame and starting no more than the window
+    /// frames ago. Charges may begin before the window, as long as they are released within it.
+    pub fn matches(&self, steps: &[InputStep], window: u32) -> bool {
+        let (last, earlier) = match steps.split_last() {
+            Some(split) => split,
+            None => {
+                return false;
+            }
+        };
+
+        // The final step must be on the latest frame, so moves come out on the frame their button is pressed
+        if !self.step_matches_at(last, 0) {
+            return false;
+        }
+
+        let mut frame = 0;
+
+        for step in earlier.iter().rev() {
+            let found = (frame + 1..=window as usize).find(|f| self.step_matches_at(step, *f));
+
+            match found {
+                Some(f) => {
+                    frame = f;
+                }
+                None => {
+                    return false;
+                }
+            }
+        }
+
+        return true;
+    }
+
+    fn step_matches_at(&self, step: &InputStep, frames_ago: usize) -> bool {
+        let held = (step.hold_frames as usize).max(1);
+
+        return (frames_ago..frames_ago + held).all(|f| match self.get(f) {
+            Some(input) => step.matches(input),
+            None => false,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(directions: &[Direction], buttons: &[Button], hold_frames: u32) -> InputStep {
+        return InputStep {
+            directions: directions.to_vec(),
+            buttons: buttons.to_vec(),
+            hold_frames: hold_frames,
+        };
+    }
+
+    fn quarter_circle_forward() -> Vec<InputStep> {
+        return vec![
+            step(&[Direction::Down], &[], 0),
+            step(&[Direction::DownForward], &[], 0),
+            step(&[Direction::Forward], &[Button::HeavyPunch], 0),
+        ];
+    }
+
+    fn push_directions(buffer: &mut InputBuffer, directions: &[Direction]) {
+        for direction in directions.iter() {
+            buffer.push(FightingInput::new(*direction, &[]));
+        }
+    }
+
+    #[test]
+    fn input_buffer_get_wraps_around_and_forgets_oldest() {
+        let mut buffer = InputBuffer::new();
+
+        for _ in 0..INPUT_BUFFER_FRAMES + 5 {
+            buffer.push(FightingInput::neutral());
+        }
+        buffer.push(FightingInput::new(Direction::Up, &[]));
+
+        assert_eq!(Direction::Up, buffer.get(0).unwrap().direction);
+        assert_eq!(Direction::Neutral, buffer.get(1).unwrap().direction);
+        assert_eq!(None, buffer.get(INPUT_BUFFER_FRAMES));
+    }
+
+    #[test]
+    fn input_buffer_matches_quarter_circle_forward_within_window() {
+        let mut buffer = InputBuffer::new();
+        push_directions(
+            &mut buffer,
+            &[
+                Direction::Neutral,
+                Direction::Down,
+                Direction::Down,
+                Direction::DownForward,
+            ],
+        );
+        buffer.push(FightingInput::new(
+            Direction::Forward,
+            &[Button::HeavyPunch],
+        ));
+
+        assert_eq!(true, buffer.matches(&quarter_circle_forward(), 12));
+    }
+
+    #[test]
+    fn input_buffer_quarter_circle_too_slow_does_not_match() {
+        let mut buffer = InputBuffer::new();
+        push_directions(&mut buffer, &[Direction::Down, Direction::DownForward]);
+        push_directions(&mut buffer, &[Direction::Forward; 12]);
+        buffer.push(FightingInput::new(
+            Direction::Forward,
+            &[Button::HeavyPunch],
+        ));
+
+        assert_eq!(false, buffer.matches(&quarter_circle_forward(), 12));
+    }
+
+    #[test]
+    fn input_buffer_out_of_order_motion_does_not_match() {
+        let mut buffer = InputBuffer::new();
+        push_directions(&mut buffer, &[Direction::DownForward, Direction::Down]);
+        buffer.push(FightingInput::new(
+            Direction::Forward,
+            &[Button::HeavyPunch],
+        ));
+
+        assert_eq!(false, buffer.matches(&quarter_circle_forward(), 12));
+    }
+
+    #[test]
+    fn input_buffer_matches_dragon_punch() {
+        let dragon_punch = vec![
+            step(&[Direction::Forward], &[], 0),
+            step(&[Direction::Down], &[], 0),
+            step(&[Direction::DownForward], &[Button::LightPunch], 0),
+        ];
+        let mut buffer = InputBuffer::new();
+        push_directions(&mut buffer, &[Direction::Forward, Direction::Down]);
+        buffer.push(FightingInput::new(
+            Direction::DownForward,
+            &[Button::LightPunch],
+        ));
+
+        assert_eq!(true, buffer.matches(&dragon_punch, 12));
+    }
+
+    #[test]
+    fn input_buffer_charge_requires_holding_for_charge_frames() {
+        let charge = vec![
+            step(&[Direction::Back], &[], 30),
+            step(&[Direction::Forward], &[Button::HeavyKick], 0),
+        ];
+
+        let mut charged = InputBuffer::new();
+        push_directions(&mut charged, &[Direction::Back; 20]);
+        push_directions(&mut charged, &[Direction::DownBack; 10]);
+        charged.push(FightingInput::new(Direction::Forward, &[Button::HeavyKick]));
+
+        let mut uncharged = InputBuffer::new();
+        push_directions(&mut uncharged, &[Direction::Back; 29]);
+        uncharged.push(FightingInput::new(Direction::Forward, &[Button::HeavyKick]));
+
+        assert_eq!(true, charged.matches(&charge, 10));
+        assert_eq!(false, uncharged.matches(&charge, 10));
+    }
+
+    #[test]
+    fn input_buffer_button_not_pressed_on_latest_frame_does_not_match() {
+        let jab = vec![step(&[], &[Button::LightPunch], 0)];
+        let mut buffer = InputBuffer::new();
+        buffer.push(FightingInput::new(
+            Direction::Neutral,
+            &[Button::LightPunch],
+        ));
+        buffer.push(FightingInput::neutral());
+
+        assert_eq!(false, buffer.matches(&jab, 12));
+    }
+}
@@ -0,0 +1,679 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Two dimensional fighting on a single stage. Each frame fighters read their buffered inputs to start or cancel into
+    moves, then every active hit box is checked against the other fighters' hurt boxes at once, so trades are possible.
+    Fighters that are hit take hitstun and build combos; fighters holding back while idle block and take blockstun.
+*/
+
+pub mod fighter_definitions;
+pub mod input_buffer;
+
+use fighter_definitions::{FighterBox, FighterDefinition, FrameData, MoveId, IDLE_MOVE};
+use input_buffer::{Button, Direction, FightingInput, InputBuffer};
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, GameUnit};
+
+/// How far fighters may move from the center of the stage, in game units.
+pub const STAGE_HALF_WIDTH: i32 = 4500;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Facing {
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// 1 when facing towards positive x, otherwise -1.
+    pub fn sign(&self) -> i32 {
+        match self {
+            Facing::Left => -1,
+            Facing::Right => 1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FighterState {
+    /// X is along the stage and y is the height of the fighter's feet above the floor.
+    pub position: Coordinate2d,
+    pub velocity: Coordinate2d,
+    pub facing: Facing,
+    pub current_move: MoveId,
+    pub move_frame: u32,
+    /// Whether the current move has already hit or been blocked; moves only connect once.
+    pub move_connected: bool,
+    pub hitstun: u32,
+    pub blockstun: u32,
+    /// Hits taken in a row without leaving hitstun, and their total damage.
+    pub combo_count: u32,
+    pub combo_damage: u32,
+    pub input_buffer: InputBuffer,
+}
+
+impl FighterState {
+    pub fn new(x: GameUnit, facing: Facing) -> Self {
+        return Self {
+            position: Coordinate2d::new(x, GameUnit::from_num(0)),
+            velocity: Coordinate2d::zero(),
+            facing: facing,
+            current_move: IDLE_MOVE,
+            move_frame: 0,
+            move_connected: false,
+            hitstun: 0,
+            blockstun: 0,
+            combo_count: 0,
+            combo_damage: 0,
+            input_buffer: InputBuffer::new(),
+        };
+    }
+
+    pub fn is_airborne(&self) -> bool {
+        return self.position.y > 0;
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        return self.hitstun > 0 || self.blockstun > 0;
+    }
+
+    /// Fighters block while holding back on the ground, when idle or already blocking.
+    pub fn is_blocking(&self) -> bool {
+        let holding_back = self.input_buffer.latest().direction.axes().0 < 0;
+
+        return holding_back
+            && !self.is_airborne()
+            && self.hitstun == 0
+            && (self.current_move == IDLE_MOVE || self.blockstun > 0);
+    }
+
+    fn start_move(&mut self, definition: &FighterDefinition, id: MoveId) {
+        self.current_move = id;
+        self.move_frame = 0;
+        self.move_connected = false;
+
+        let jump_velocity = definition.get_move(id).map_or(0, |m| m.jump_velocity);
+        if jump_velocity > 0 && !self.is_airborne() {
+            let horizontal = self.input_buffer.latest().direction.axes().0;
+
+            self.velocity.x =
+                GameUnit::from_num(horizontal * definition.walk_speed * self.facing.sign());
+            self.velocity.y = GameUnit::from_num(jump_velocity);
+        }
+    }
+}
+
+/// What happened when a hit box connected.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FightHit {
+    /// Indexes of the fighters.
+    pub attacker: usize,
+    pub defender: usize,
+    /// Damage dealt; blocked hits deal none.
+    pub damage: u32,
+    pub blocked: bool,
+}
+
+/// Build a fighter's input from the held directions and pressed buttons. Opposing directions are cleaned the standard
+/// way, where left and right is neutral and up and down is up.
+pub fn to_fighting_input(
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+    buttons: &[Button],
+    facing: Facing,
+) -> FightingInput {
+    let horizontal = (right as i32) - (left as i32);
+    let vertical = if up {
+        1
+    } else if down {
+        -1
+    } else {
+        0
+    };
+
+    return FightingInput::new(
+        Direction::from_axes(horizontal * facing.sign(), vertical),
+        buttons,
+    );
+}
+
+/// Advance the fighter by a frame with the given input.
+pub fn step_fighter(
+    state: &mut FighterState,
+    definition: &FighterDefinition,
+    input: FightingInput,
+) {
+    state.input_buffer.push(input);
+
+    if state.is_stunned() {
+        state.blockstun = state.blockstun.saturating_sub(1);
+
+        if state.hitstun > 0 {
+            state.hitstun -= 1;
+
+            if state.hitstun == 0 {
+                state.combo_count = 0;
+                state.combo_damage = 0;
+            }
+        }
+    } else {
+        match next_move(state, definition) {
+            Some(id) => state.start_move(definition, id),
+            None => {
+                state.move_frame += 1;
+
+                let current = definition.get_move(state.current_move).unwrap();
+                if state.move_frame >= current.total_frames() {
+                    let next = current.transition_when_complete;
+
+                    state.current_move = next;
+                    state.move_frame = 0;
+                    state.move_connected = false;
+                }
+            }
+        }
+    }
+
+    // Walking
+    if !state.is_airborne() {
+        let mut speed = 0;
+
+        if state.current_move == IDLE_MOVE && !state.is_stunned() {
+            speed = match input.direction {
+                Direction::Forward => definition.walk_speed,
+                Direction::Back => -definition.back_walk_speed,
+                _ => 0,
+            };
+        }
+
+        if state.velocity.y <= 0 {
+            state.velocity.x = GameUnit::from_num(speed * state.facing.sign());
+        }
+    }
+
+    // Jumping and falling
+    if state.is_airborne() || state.velocity.y > 0 {
+        state.position.y += state.velocity.y;
+        state.velocity.y -= GameUnit::from_num(definition.gravity);
+
+        if state.position.y <= 0 {
+            state.position.y = GameUnit::from_num(0);
+            state.velocity = Coordinate2d::zero();
+
+            let jumping = definition
+                .get_move(state.current_move)
+                .map_or(false, |m| m.jump_velocity > 0);
+
+            if jumping {
+                state.current_move = IDLE_MOVE;
+                state.move_frame = 0;
+            }
+        }
+    }
+
+    state.position.x += state.velocity.x;
+    clamp_to_stage(state);
+}
+
+/// Returns the move the fighter should start this frame, if any. Only moves the current frame may cancel into are
+/// considered; of those, the one with the longest input string wins, so specials come out over normals.
+fn next_move(state: &FighterState, definition: &FighterDefinition) -> Option<MoveId> {
+    if state.is_airborne() || !current_frame(state, definition).is_cancel_window {
+        return None;
+    }
+
+    let current = definition.get_move(state.current_move).unwrap();
+    let mut best: Option<(usize, MoveId)> = None;
+
+    for id in current.cancels.iter() {
+        let candidate = match definition.get_move(*id) {
+            Some(candidate) => candidate,
+            None => {
+                continue;
+            }
+        };
+
+        let length = candidate.input_string.len();
+        if length == 0 || best.map_or(false, |(best_length, _)| best_length >= length) {
+            continue;
+        }
+
+        if state
+            .input_buffer
+            .matches(&candidate.input_string, candidate.input_window)
+        {
+            best = Some((length, *id));
+        }
+    }
+
+    return best.map(|(_, id)| id);
+}
+
+/// Check every fighter's active hit boxes against the others' hurt boxes, then apply hitstun, blockstun and combos.
+/// Hits are found before any are applied so simultaneous hits trade. Afterwards fighters are pushed apart and turned
+/// to face each other.
+pub fn resolve_fight(
+    fighters: &mut [FighterState],
+    definitions: &[&FighterDefinition],
+) -> Vec<FightHit> {
+    let blocking: Vec<bool> = fighters.iter().map(|f| f.is_blocking()).collect();
+
+    let mut connections: Vec<(usize, usize, FrameData)> = vec![];
+
+    for (a, attacker) in fighters.iter().enumerate() {
+        if attacker.is_stunned() || attacker.move_connected {
+            continue;
+        }
+
+        let frame = current_frame(attacker, definitions[a]);
+        if frame.hit_boxes.is_empty() {
+            continue;
+        }
+
+        for (d, defender) in fighters.iter().enumerate() {
+            if d == a {
+                continue;
+            }
+
+            let hurt_boxes = boxes_or_idle(defender, definitions[d], |f| &f.hurt_boxes);
+
+            if boxes_overlap(attacker, &frame.hit_boxes, defender, hurt_boxes) {
+                connections.push((a, d, frame.clone()));
+                break;
+            }
+        }
+    }
+
+    let mut hits = vec![];
+
+    for (a, d, frame) in connections.iter() {
+        let remaining = {
+            let attacker = &mut fighters[*a];
+            attacker.move_connected = true;
+
+            let total = definitions[*a]
+                .get_move(attacker.current_move)
+                .map_or(0, |m| m.total_frames());
+
+            total as i32 - attacker.move_frame as i32 - 1
+        };
+
+        let defender = &mut fighters[*d];
+
+        if blocking[*d] {
+            defender.blockstun = (remaining + frame.advantage_on_block).max(1) as u32;
+
+            hits.push(FightHit {
+                attacker: *a,
+                defender: *d,
+                damage: 0,
+                blocked: true,
+            });
+        } else {
+            if defender.hitstun > 0 {
+                defender.combo_count += 1;
+            } else {
+                defender.combo_count = 1;
+                defender.combo_damage = 0;
+            }
+
+            defender.combo_damage += frame.damage;
+            defender.hitstun = frame.stun_frames.max(1);
+            defender.blockstun = 0;
+            defender.current_move = IDLE_MOVE;
+            defender.move_frame = 0;
+            defender.move_connected = false;
+
+            hits.push(FightHit {
+                attacker: *a,
+                defender: *d,
+                damage: frame.damage,
+                blocked: false,
+            });
+        }
+    }
+
+    push_apart(fighters, definitions);
+    face_opponents(fighters);
+
+    return hits;
+}
+
+fn current_frame<'a>(state: &FighterState, definition: &'a FighterDefinition) -> &'a FrameData {
+    return definition
+        .get_move(state.current_move)
+        .and_then(|m| m.frame_at(state.move_frame))
+        .unwrap_or(&definition.idle().frame_data[0]);
+}
+
+/// The boxes of the fighter's current frame, or the idle move's when the frame has none.
+fn boxes_or_idle<'a>(
+    state: &FighterState,
+    definition: &'a FighterDefinition,
+    select: fn(&FrameData) -> &Vec<FighterBox>,
+) -> &'a Vec<FighterBox> {
+    let boxes = select(current_frame(state, definition));
+
+    if boxes.is_empty() {
+        return select(&definition.idle().frame_data[0]);
+    }
+
+    return boxes;
+}
+
+/// Returns the box on the stage, as (min x, min y, max x, max y).
+fn to_stage(state: &FighterState, fighter_box: &FighterBox) -> (i32, i32, i32, i32) {
+    let x = state.position.x.to_num::<i32>();
+    let y = state.position.y.to_num::<i32>();
+
+    let (min_x, max_x) = match state.facing {
+        Facing::Right => (x + fighter_box.start_x, x + fighter_box.end_x),
+        Facing::Left => (x - fighter_box.end_x, x - fighter_box.start_x),
+    };
+
+    return (min_x, y + fighter_box.start_y, max_x, y + fighter_box.end_y);
+}
+
+fn boxes_overlap(
+    a: &FighterState,
+    a_boxes: &[FighterBox],
+    b: &FighterState,
+    b_boxes: &[FighterBox],
+) -> bool {
+    for a_box in a_boxes.iter().map(|bx| to_stage(a, bx)) {
+        for b_box in b_boxes.iter().map(|bx| to_stage(b, bx)) {
+            if a_box.0 < b_box.2 && b_box.0 < a_box.2 && a_box.1 < b_box.3 && b_box.1 < a_box.3 {
+                return true;
+            }
+        }
+    }
+
+    return false;
+}
+
+/// Separate fighters whose push boxes overlap, moving each half of the way.
+fn push_apart(fighters: &mut [FighterState], definitions: &[&FighterDefinition]) {
+    for a in 0..fighters.len() {
+        for b in a + 1..fighters.len() {
+            let overlap = {
+                let a_boxes = boxes_or_idle(&fighters[a], definitions[a], |f| &f.push_boxes);
+                let b_boxes = boxes_or_idle(&fighters[b], definitions[b], |f| &f.push_boxes);
+
+                let mut overlap = 0;
+                for a_box in a_boxes.iter().map(|bx| to_stage(&fighters[a], bx)) {
+                    for b_box in b_boxes.iter().map(|bx| to_stage(&fighters[b], bx)) {
+                        if a_box.1 < b_box.3 && b_box.1 < a_box.3 {
+                            let amount = a_box.2.min(b_box.2) - a_box.0.max(b_box.0);
+                            overlap = overlap.max(amount);
+                        }
+                    }
+                }
+
+                overlap
+            };
+
+            if overlap <= 0 {
+                continue;
+            }
+
+            // The fighter on the left moves left; ties go to the first fighter
+            let direction = if fighters[a].position.x <= fighters[b].position.x {
+                -1
+            } else {
+                1
+            };
+            let half = GameUnit::from_num(overlap / 2 + overlap % 2);
+
+            fighters[a].position.x += half * direction;
+            fighters[b].position.x -= half * direction;
+
+            clamp_to_stage(&mut fighters[a]);
+            clamp_to_stage(&mut fighters[b]);
+        }
+    }
+}
+
+/// Turn grounded, idle fighters towards the nearest opponent.
+fn face_opponents(fighters: &mut [FighterState]) {
+    let positions: Vec<GameUnit> = fighters.iter().map(|f| f.position.x).collect();
+
+    for (i, fighter) in fighters.iter_mut().enumerate() {
+        if fighter.is_airborne() || fighter.is_stunned() || fighter.current_move != IDLE_MOVE {
+            continue;
+        }
+
+        let x = fighter.position.x;
+        let nearest = positions
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, p)| *p)
+            .min_by_key(|p| (*p - x).abs());
+
+        if let Some(opponent) = nearest {
+            if opponent > x {
+                fighter.facing = Facing::Right;
+            } else if opponent < x {
+                fighter.facing = Facing::Left;
+            }
+        }
+    }
+}
+
+fn clamp_to_stage(state: &mut FighterState) {
+    let limit = GameUnit::from_num(STAGE_HALF_WIDTH);
+
+    state.position.x = state.position.x.max(-limit).min(limit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fighter_definitions::FighterDefinitions;
+    use std::path::Path;
+
+    const PALM_STRIKE: MoveId = 6;
+    const JUMP: MoveId = 1;
+
+    fn faceless_one() -> FighterDefinition {
+        let definitions =
+            FighterDefinitions::from_directory(Path::new(fighter_definitions::FIGHTERS_PATH))
+                .unwrap();
+        let index = definitions.index_of("The Faceless One").unwrap();
+
+        return definitions.get(index).unwrap().clone();
+    }
+
+    /// Two fighters facing each other, the given distance apart.
+    fn fighters_apart(distance: i32) -> [FighterState; 2] {
+        return [
+            FighterState::new(GameUnit::from_num(-distance / 2), Facing::Right),
+            FighterState::new(GameUnit::from_num(distance / 2), Facing::Left),
+        ];
+    }
+
+    fn input(direction: Direction, buttons: &[Button]) -> FightingInput {
+        return FightingInput::new(direction, buttons);
+    }
+
+    /// Step both fighters with the given inputs and resolve the frame, returning any hits.
+    fn frame(
+        fighters: &mut [FighterState; 2],
+        definition: &FighterDefinition,
+        inputs: [FightingInput; 2],
+    ) -> Vec<FightHit> {
+        for (fighter, input) in fighters.iter_mut().zip(inputs.iter()) {
+            step_fighter(fighter, definition, *input);
+        }
+
+        return resolve_fight(fighters, &[definition, definition]);
+    }
+
+    /// Run frames until a hit, or give up after the limit.
+    fn until_hit(
+        fighters: &mut [FighterState; 2],
+        definition: &FighterDefinition,
+        inputs: [FightingInput; 2],
+    ) -> Vec<FightHit> {
+        for _ in 0..30 {
+            let hits = frame(fighters, definition, inputs);
+            if !hits.is_empty() {
+                return hits;
+            }
+        }
+
+        return vec![];
+    }
+
+    #[test]
+    fn to_fighting_input_left_and_right_is_neutral_and_relative_to_facing() {
+        let both = to_fighting_input(false, false, true, true, &[], Facing::Right);
+        let right_facing_left = to_fighting_input(false, true, false, true, &[], Facing::Left);
+
+        assert_eq!(Direction::Neutral, both.direction);
+        assert_eq!(Direction::DownBack, right_facing_left.direction);
+    }
+
+    #[test]
+    fn step_fighter_walks_forward_towards_facing() {
+        let definition = faceless_one();
+        let mut fighter = FighterState::new(GameUnit::from_num(1000), Facing::Left);
+
+        step_fighter(&mut fighter, &definition, input(Direction::Forward, &[]));
+
+        assert_eq!(
+            GameUnit::from_num(1000 - definition.walk_speed),
+            fighter.position.x
+        );
+    }
+
+    #[test]
+    fn step_fighter_jump_rises_then_lands_idle() {
+        let definition = faceless_one();
+        let mut fighter = FighterState::new(GameUnit::from_num(0), Facing::Right);
+
+        step_fighter(&mut fighter, &definition, input(Direction::Up, &[]));
+        assert_eq!(JUMP, fighter.current_move);
+        assert_eq!(true, fighter.is_airborne());
+
+        for _ in 0..60 {
+            step_fighter(&mut fighter, &definition, FightingInput::neutral());
+        }
+        assert_eq!(IDLE_MOVE, fighter.current_move);
+        assert_eq!(false, fighter.is_airborne());
+    }
+
+    #[test]
+    fn step_fighter_quarter_circle_forward_starts_palm_strike() {
+        let definition = faceless_one();
+        let mut fighter = FighterState::new(GameUnit::from_num(0), Facing::Right);
+
+        step_fighter(&mut fighter, &definition, input(Direction::Down, &[]));
+        step_fighter(
+            &mut fighter,
+            &definition,
+            input(Direction::DownForward, &[]),
+        );
+        step_fighter(
+            &mut fighter,
+            &definition,
+            input(Direction::Forward, &[Button::HeavyPunch]),
+        );
+
+        assert_eq!(PALM_STRIKE, fighter.current_move);
+    }
+
+    #[test]
+    fn resolve_fight_jab_in_range_hits_once_with_hitstun() {
+        let definition = faceless_one();
+        let mut fighters = fighters_apart(700);
+        let idle = FightingInput::neutral();
+
+        frame(
+            &mut fighters,
+            &definition,
+            [input(Direction::Neutral, &[Button::LightPunch]), idle],
+        );
+        let hits = until_hit(&mut fighters, &definition, [idle, idle]);
+
+        assert_eq!(
+            vec![FightHit {
+                attacker: 0,
+                defender: 1,
+                damage: 110,
+                blocked: false
+            }],
+            hits
+        );
+        assert_eq!(1, fighters[1].combo_count);
+        assert!(fighters[1].hitstun > 0);
+        assert_eq!(
+            Vec::<FightHit>::new(),
+            until_hit(&mut fighters, &definition, [idle, idle])
+        );
+    }
+
+    #[test]
+    fn resolve_fight_holding_back_blocks() {
+        let definition = faceless_one();
+        let mut fighters = fighters_apart(700);
+        let idle = FightingInput::neutral();
+        let back = input(Direction::Back, &[]);
+
+        frame(
+            &mut fighters,
+            &definition,
+            [input(Direction::Neutral, &[Button::LightPunch]), back],
+        );
+        let hits = until_hit(&mut fighters, &definition, [idle, back]);
+
+        assert_eq!(1, hits.len());
+        assert_eq!(true, hits[0].blocked);
+        assert_eq!(0, hits[0].damage);
+        assert!(fighters[1].blockstun > 0);
+        assert_eq!(0, fighters[1].hitstun);
+    }
+
+    #[test]
+    fn resolve_fight_cancel_into_second_hit_builds_combo() {
+        let definition = faceless_one();
+        let mut fighters = fighters_apart(700);
+        let idle = FightingInput::neutral();
+
+        frame(
+            &mut fighters,
+            &definition,
+            [input(Direction::Neutral, &[Button::LightPunch]), idle],
+        );
+        until_hit(&mut fighters, &definition, [idle, idle]);
+
+        frame(
+            &mut fighters,
+            &definition,
+            [input(Direction::Neutral, &[Button::HeavyPunch]), idle],
+        );
+        let hits = until_hit(&mut fighters, &definition, [idle, idle]);
+
+        assert_eq!(1, hits.len());
+        assert_eq!(2, fighters[1].combo_count);
+        assert_eq!(240, fighters[1].combo_damage);
+    }
+
+    #[test]
+    fn resolve_fight_out_of_range_misses() {
+        let definition = faceless_one();
+        let mut fighters = fighters_apart(2000);
+        let idle = FightingInput::neutral();
+
+        frame(
+            &mut fighters,
+            &definition,
+            [input(Direction::Neutral, &[Button::LightPunch]), idle],
+        );
+        let hits = until_hit(&mut fighters, &definition, [idle, idle]);
+
+        assert_eq!(Vec::<FightHit>::new(), hits);
+    }
+}
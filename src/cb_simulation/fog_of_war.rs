@@ -0,0 +1,377 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Per team visibility. The map is split into one cell per voxel column; each tick every unit reveals the cells within its
+    sight range that it has line of sight to, walking across the voxel height map. Only integer math is used so that all
+    peers agree on what is visible.
+*/
+
+use crate::cb_voxels;
+use cb_voxels::CbChunkManager;
+
+use super::teams::{CbTeams, TeamId};
+
+pub const FOG_GRID_SIZE: usize = cb_voxels::CHUNKS * cb_voxels::CHUNK_SIZE;
+
+/// How far above the terrain a unit's eyes are, in voxels.
+const EYE_HEIGHT: i32 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FogState {
+    Unexplored,
+    Explored,
+    Visible,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TeamVisibility {
+    team_id: TeamId,
+    cells: Vec<FogState>,
+}
+
+/// The fog of war grids for each team. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CbVisibility {
+    teams: Vec<TeamVisibility>,
+}
+
+impl CbVisibility {
+    pub fn new() -> Self {
+        return Self { teams: vec![] };
+    }
+
+    /// Demote all visible cells to explored; call before revealing cells for the current tick.
+    pub fn begin_tick(&mut self) {
+        for team in self.teams.iter_mut() {
+            for cell in team.cells.iter_mut() {
+                if *cell == FogState::Visible {
+                    *cell = FogState::Explored;
+                }
+            }
+        }
+    }
+
+    pub fn reveal(&mut self, team_id: TeamId, x: i32, y: i32) {
+        if !in_bounds(x, y) {
+            return;
+        }
+
+        let index = cell_index(x, y);
+
+        match self.teams.iter_mut().find(|t| t.team_id == team_id) {
+            Some(team) => {
+                team.cells[index] = FogState::Visible;
+            }
+            None => {
+                let mut cells = vec![FogState::Unexplored; FOG_GRID_SIZE * FOG_GRID_SIZE];
+                cells[index] = FogState::Visible;
+
+                self.teams.push(TeamVisibility {
+                    team_id: team_id,
+                    cells: cells,
+                });
+                self.teams.sort_by_key(|t| t.team_id);
+            }
+        }
+    }
+
+    /// The state of the cell for the team alone, ignoring allies.
+    pub fn state(&self, team_id: TeamId, x: i32, y: i32) -> FogState {
+        if !in_bounds(x, y) {
+            return FogState::Unexplored;
+        }
+
+        match self.teams.iter().find(|t| t.team_id == team_id) {
+            Some(team) => team.cells[cell_index(x, y)],
+            None => FogState::Unexplored,
+        }
+    }
+
+    /// Returns whether the team, or any of its allies, can currently see the cell.
+    pub fn is_visible(&self, teams: &CbTeams, team_id: TeamId, x: i32, y: i32) -> bool {
+        return self.teams.iter().any(|t| {
+            teams.are_teams_allied(team_id, t.team_id)
+                && self.state(t.team_id, x, y) == FogState::Visible
+        });
+    }
+
+    /// Returns whether the player's team, or any of its allies, can currently see the cell.
+    pub fn is_visible_to_player(&self, teams: &CbTeams, player_id: usize, x: i32, y: i32) -> bool {
+        match teams.team_of(player_id) {
+            Some(team_id) => self.is_visible(teams, team_id, x, y),
+            None => false,
+        }
+    }
+}
+
+/// Terrain heights, in voxels, for each x/y column of the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeightMap {
+    heights: Vec<i32>,
+}
+
+impl HeightMap {
+    pub fn flat() -> Self {
+        return Self {
+            heights: vec![0; FOG_GRID_SIZE * FOG_GRID_SIZE],
+        };
+    }
+
+    /// Build a height map where each column's height is one above its highest active voxel.
+    pub fn from_chunk_manager(chunk_manager: &CbChunkManager) -> Self {
+        let mut map = Self::flat();
+        let width = chunk_manager.get_voxel_width().min(FOG_GRID_SIZE);
+
+        for x in 0..width {
+            for y in 0..width {
+                map.heights[cell_index(x as i32, y as i32)] =
+                    chunk_manager.get_column_height(x, y) as i32;
+            }
+        }
+
+        return map;
+    }
+
+    pub fn set_height(&mut self, x: i32, y: i32, height: i32) {
+        if in_bounds(x, y) {
+            self.heights[cell_index(x, y)] = height;
+        }
+    }
+
+    pub fn height(&self, x: i32, y: i32) -> i32 {
+        if !in_bounds(x, y) {
+            return 0;
+        }
+
+        return self.heights[cell_index(x, y)];
+    }
+}
+
+/// The height map visibility is revealed over, kept between ticks as a world resource. Building it walks every voxel
+/// column, so it's only rebuilt when a chunk was updated since it was last built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CbHeightMapCache {
+    height_map: HeightMap,
+    /// The frame the height map was built at and whether it was built from voxels.
+    built: Option<(usize, bool)>,
+}
+
+impl CbHeightMapCache {
+    pub fn new() -> Self {
+        return Self {
+            height_map: HeightMap::flat(),
+            built: None,
+        };
+    }
+
+    /// The height map of the voxels at the frame, rebuilding it if they changed. Without a voxel map everything is
+    /// flat ground.
+    pub fn get(&mut self, chunk_manager: Option<&CbChunkManager>, frame: usize) -> &HeightMap {
+        let stale = match (self.built, chunk_manager) {
+            (None, _) => true,
+            (Some((_, from_voxels)), None) => from_voxels,
+            (Some((built_at, from_voxels)), Some(chunk_manager)) => {
+                !from_voxels || updated_since(chunk_manager, built_at)
+            }
+        };
+
+        if stale {
+            self.height_map = match chunk_manager {
+                Some(chunk_manager) => HeightMap::from_chunk_manager(chunk_manager),
+                None => HeightMap::flat(),
+            };
+            self.built = Some((frame, chunk_manager.is_some()));
+        }
+
+        return &self.height_map;
+    }
+}
+
+impl Default for CbHeightMapCache {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Returns whether any chunk was updated at or after the frame. Chunks updated on the frame a height map was built
+/// may have changed after it, so they count too.
+fn updated_since(chunk_manager: &CbChunkManager, frame: usize) -> bool {
+    return chunk_manager
+        .chunk_array
+        .iter()
+        .flatten()
+        .flatten()
+        .any(|chunk| chunk.frame_updated_at >= frame);
+}
+
+/// Convert a position in game units to the fog cell that contains it.
+pub fn world_to_cell(x: i32, y: i32) -> (i32, i32) {
+    return (
+        x.div_euclid(cb_voxels::VOXEL_GAME_SIZE),
+        y.div_euclid(cb_voxels::VOXEL_GAME_SIZE),
+    );
+}
+
+/// Returns whether something standing on the target cell can be seen from the origin cell.
+pub fn has_line_of_sight(height_map: &HeightMap, from: (i32, i32), to: (i32, i32)) -> bool {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs());
+
+    if steps <= 1 {
+        return true;
+    }
+
+    let eye = height_map.height(from.0, from.1) + EYE_HEIGHT;
+    let target = height_map.height(to.0, to.1) + EYE_HEIGHT;
+
+    // Walk the cells between the two points, excluding the end points, and check the ray is above the terrain.
+    // Heights are compared scaled by the number of steps to avoid division.
+    for i in 1..steps {
+        let x = from.0 + round_div(dx * i, steps);
+        let y = from.1 + round_div(dy * i, steps);
+
+        let ray_height_scaled = eye * steps + (target - eye) * i;
+        let terrain_height_scaled = height_map.height(x, y) * steps;
+
+        if terrain_height_scaled > ray_height_scaled {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+/// Reveal all cells within the sight range of the origin that are in line of sight.
+pub fn reveal_from(
+    visibility: &mut CbVisibility,
+    height_map: &HeightMap,
+    team_id: TeamId,
+    origin: (i32, i32),
+    sight_range_cells: i32,
+) {
+    let r = sight_range_cells.max(0);
+
+    for dx in -r..=r {
+        for dy in -r..=r {
+            if dx * dx + dy * dy > r * r {
+                continue;
+            }
+
+            let cell = (origin.0 + dx, origin.1 + dy);
+
+            if in_bounds(cell.0, cell.1) && has_line_of_sight(height_map, origin, cell) {
+                visibility.reveal(team_id, cell.0, cell.1);
+            }
+        }
+    }
+}
+
+fn in_bounds(x: i32, y: i32) -> bool {
+    let size = FOG_GRID_SIZE as i32;
+    return x >= 0 && y >= 0 && x < size && y < size;
+}
+
+fn cell_index(x: i32, y: i32) -> usize {
+    return x as usize + y as usize * FOG_GRID_SIZE;
+}
+
+/// Integer division rounding to the nearest whole number, with halves rounded away from zero. Divisor must be positive.
+fn round_div(value: i32, divisor: i32) -> i32 {
+    if value >= 0 {
+        return (value + divisor / 2) / divisor;
+    }
+
+    return -((-value + divisor / 2) / divisor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn has_line_of_sight_flat_terrain_returns_true() {
+        let map = HeightMap::flat();
+
+        assert_eq!(true, has_line_of_sight(&map, (0, 0), (10, 3)));
+    }
+
+    #[test]
+    fn has_line_of_sight_wall_between_returns_false() {
+        let mut map = HeightMap::flat();
+        map.set_height(5, 0, 4);
+
+        assert_eq!(false, has_line_of_sight(&map, (0, 0), (10, 0)));
+    }
+
+    #[test]
+    fn has_line_of_sight_from_hill_over_low_wall_returns_true() {
+        let mut map = HeightMap::flat();
+        map.set_height(0, 0, 6);
+        map.set_height(2, 0, 2);
+
+        assert_eq!(true, has_line_of_sight(&map, (0, 0), (10, 0)));
+    }
+
+    #[test]
+    fn height_map_cache_rebuilds_only_when_voxels_change() {
+        let mut chunk_manager = CbChunkManager::empty(0);
+        let mut cache = CbHeightMapCache::new();
+        assert_eq!(0, cache.get(Some(&chunk_manager), 1).height(3, 3));
+
+        // Changes on the frame the height map was built at may have come after it
+        chunk_manager.add_top_voxel(3, 3, cb_voxels::VOXEL_TYPE_DIRT, 1);
+        assert_eq!(1, cache.get(Some(&chunk_manager), 2).height(3, 3));
+
+        chunk_manager.add_top_voxel(3, 3, cb_voxels::VOXEL_TYPE_DIRT, 3);
+        assert_eq!(2, cache.get(Some(&chunk_manager), 3).height(3, 3));
+        assert_eq!(2, cache.get(Some(&chunk_manager), 4).height(3, 3));
+
+        assert_eq!(0, cache.get(None, 5).height(3, 3));
+    }
+
+    #[test]
+    fn reveal_from_marks_cells_within_range_only() {
+        let map = HeightMap::flat();
+        let mut visibility = CbVisibility::new();
+
+        reveal_from(&mut visibility, &map, 1, (5, 5), 2);
+
+        assert_eq!(FogState::Visible, visibility.state(1, 7, 5));
+        assert_eq!(FogState::Unexplored, visibility.state(1, 8, 5));
+        assert_eq!(FogState::Unexplored, visibility.state(1, 7, 7));
+        assert_eq!(FogState::Unexplored, visibility.state(0, 5, 5));
+    }
+
+    #[test]
+    fn begin_tick_demotes_visible_to_explored() {
+        let mut visibility = CbVisibility::new();
+        visibility.reveal(0, 1, 1);
+
+        visibility.begin_tick();
+
+        assert_eq!(FogState::Explored, visibility.state(0, 1, 1));
+    }
+
+    #[test]
+    fn is_visible_shares_vision_with_allies() {
+        let mut teams = CbTeams::new();
+        teams.add_player(1, 0);
+        teams.add_player(2, 1);
+        teams.add_player(3, 2);
+        teams.add_alliance(0, 1);
+
+        let mut visibility = CbVisibility::new();
+        visibility.reveal(1, 3, 3);
+
+        assert_eq!(true, visibility.is_visible_to_player(&teams, 1, 3, 3));
+        assert_eq!(false, visibility.is_visible_to_player(&teams, 3, 3, 3));
+    }
+
+    #[test]
+    fn world_to_cell_negative_positions_round_down() {
+        let size = cb_voxels::VOXEL_GAME_SIZE;
+
+        assert_eq!((0, 1), world_to_cell(size - 1, size));
+        assert_eq!((-1, 0), world_to_cell(-1, 0));
+    }
+}
@@ -0,0 +1,478 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Procedural walking. A body's legs are IK chains hanging from hips placed around it. Each foot stays planted where it
+    last landed until the spot it wants to land on is more than a stride away, then steps there, lifting in an arc over
+    a few ticks. Landing spots lead the hip by how far the body will move during a step, so faster bodies take longer
+    strides, and are snapped onto the voxel surface with a raycast so feet follow the terrain.
+
+    The gait decides which legs may step together. Legs in the same group step at once and groups take turns, so bipeds
+    alternate feet, quadrupeds trot on diagonal pairs and six legged creatures walk on alternating tripods. A wave gait
+    moves one leg at a time, for slow crawlers with many legs. Everything is fixed point, so feet land in the same place
+    on every machine.
+*/
+
+use crate::cb_inverse_kinematics;
+use cb_inverse_kinematics::fixed_point::{fixed_fabrik, FixedIkRig};
+
+use crate::cb_math;
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+use cb_voxels::CbChunkManager;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gait {
+    /// Legs step in two alternating groups.
+    Alternating,
+    /// Legs step one at a time, front to back.
+    Wave,
+}
+
+impl Gait {
+    /// The group the leg steps with. Legs are numbered in left and right pairs from the front.
+    pub fn group(&self, leg: usize) -> usize {
+        match self {
+            Gait::Alternating => (leg / 2 + leg % 2) % 2,
+            Gait::Wave => leg,
+        }
+    }
+
+    pub fn group_count(&self, leg_count: usize) -> usize {
+        match self {
+            Gait::Alternating => leg_count.min(2),
+            Gait::Wave => leg_count,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Step {
+    from: Coordinate3d,
+    to: Coordinate3d,
+    ticks: u32,
+}
+
+#[derive(Clone)]
+pub struct Leg {
+    /// Where the leg attaches, relative to the ground under the body, with x forwards and y to the left.
+    pub hip_offset: Coordinate3d,
+    /// The hip, knee and foot, posed to reach the foot.
+    pub rig: FixedIkRig,
+    pub foot: Coordinate3d,
+    step: Option<Step>,
+}
+
+impl Leg {
+    pub fn is_stepping(&self) -> bool {
+        return self.step.is_some();
+    }
+}
+
+#[derive(Clone)]
+pub struct Legs {
+    pub legs: Vec<Leg>,
+    pub gait: Gait,
+    /// How far, in game units, a foot may be from where it wants to land before it steps.
+    pub stride: GameUnit,
+    pub step_ticks: u32,
+    /// How high feet lift at the middle of a step, in game units.
+    pub step_height: GameUnit,
+    /// The unit direction the body faces, which follows its velocity.
+    pub facing: Coordinate3d,
+    /// Furthest below a hip, in game units, a foot may land.
+    reach: GameUnit,
+    next_group: usize,
+    last_body: Option<Coordinate3d>,
+}
+
+impl Legs {
+    /// Legs in left and right pairs from front to back, each a hip, knee and foot. Lengths are in game units; pairs are
+    /// the stance width apart, as are the two hips of a pair.
+    pub fn new(
+        gait: Gait,
+        count: usize,
+        length: GameUnit,
+        hip_height: GameUnit,
+        stance_width: GameUnit,
+    ) -> Self {
+        let pairs = (count as i32 + 1) / 2;
+        let zero = GameUnit::from_num(0);
+
+        let legs = (0..count)
+            .map(|i| {
+                let pair = (i / 2) as i32;
+                let side = if i % 2 == 0 { 1 } else { -1 };
+
+                let mut rig = FixedIkRig::new();
+                rig.add_joint(Coordinate3d::new(zero, zero, zero), None);
+                rig.add_joint(Coordinate3d::new(zero, zero, -length / 2), None);
+                rig.add_joint(Coordinate3d::new(zero, zero, -length), None);
+
+                Leg {
+                    hip_offset: Coordinate3d::new(
+                        stance_width * (pairs - 1 - 2 * pair) / 2,
+                        stance_width * side / 2,
+                        hip_height,
+                    ),
+                    rig: rig,
+                    foot: Coordinate3d::new(zero, zero, zero),
+                    step: None,
+                }
+            })
+            .collect();
+
+        return Self {
+            legs: legs,
+            gait: gait,
+            stride: length * 6 / 10,
+            step_ticks: 10,
+            step_height: length / 5,
+            facing: Coordinate3d::new(GameUnit::from_num(1), zero, zero),
+            reach: length,
+            next_group: 0,
+            last_body: None,
+        };
+    }
+
+    pub fn biped(length: GameUnit, hip_height: GameUnit, stance_width: GameUnit) -> Self {
+        return Self::new(Gait::Alternating, 2, length, hip_height, stance_width);
+    }
+
+    pub fn is_stepping(&self) -> bool {
+        return self.legs.iter().any(|leg| leg.is_stepping());
+    }
+
+    /// Where the leg's hip is when the body is at the given position.
+    pub fn hip(&self, leg: usize, body: Coordinate3d) -> Coordinate3d {
+        let offset = self.legs[leg].hip_offset;
+        let (fx, fy) = (self.facing.x, self.facing.y);
+
+        return Coordinate3d::new(
+            body.x + offset.x * fx - offset.y * fy,
+            body.y + offset.x * fy + offset.y * fx,
+            body.z + offset.z,
+        );
+    }
+}
+
+/// Advance the legs a tick for a body standing at the position, which is on the ground under its center, then pose
+/// every leg with IK. Bodies without terrain walk on the plane they stand on.
+pub fn step_legs(legs: &mut Legs, body: Coordinate3d, terrain: Option<&CbChunkManager>) {
+    let zero = GameUnit::from_num(0);
+    let velocity = match legs.last_body {
+        Some(last) => Coordinate3d::new(body.x - last.x, body.y - last.y, body.z - last.z),
+        None => Coordinate3d::new(zero, zero, zero),
+    };
+    let first_tick = legs.last_body.is_none();
+    legs.last_body = Some(body);
+
+    if let Some(facing) = horizontal_direction(velocity) {
+        legs.facing = facing;
+    }
+
+    // Land where the hip will be once the step is over
+    let lead = legs.step_ticks as i32;
+    let landing_spots: Vec<Option<Coordinate3d>> = (0..legs.legs.len())
+        .map(|i| {
+            let hip = legs.hip(i, body);
+            let ahead =
+                Coordinate3d::new(hip.x + velocity.x * lead, hip.y + velocity.y * lead, hip.z);
+
+            landing_spot(
+                ahead,
+                legs.legs[i].hip_offset.z,
+                legs.reach,
+                body.z,
+                terrain,
+            )
+        })
+        .collect();
+
+    if first_tick {
+        for (leg, spot) in legs.legs.iter_mut().zip(landing_spots.iter()) {
+            if let Some(spot) = spot {
+                leg.foot = *spot;
+            }
+        }
+    } else {
+        advance_steps(legs);
+
+        if !legs.is_stepping() {
+            start_steps(legs, &landing_spots);
+        }
+    }
+
+    pose_legs(legs, body);
+}
+
+/// Where a foot below the point lands, or nothing if there is no ground within reach of the hip.
+fn landing_spot(
+    above: Coordinate3d,
+    hip_height: GameUnit,
+    reach: GameUnit,
+    ground: GameUnit,
+    terrain: Option<&CbChunkManager>,
+) -> Option<Coordinate3d> {
+    let terrain = match terrain {
+        Some(terrain) => terrain,
+        None => {
+            return Some(Coordinate3d::new(above.x, above.y, ground));
+        }
+    };
+
+    let down = Coordinate3d::new(
+        GameUnit::from_num(0),
+        GameUnit::from_num(0),
+        GameUnit::from_num(-1),
+    );
+
+    // Cast from a little above the hip, so feet can step up onto ledges as high as their hips
+    let mut origin = above;
+    origin.z += hip_height / 2;
+
+    return terrain
+        .raycast(origin, down, reach + hip_height / 2)
+        .map(|hit| Coordinate3d::new(above.x, above.y, origin.z - hit.distance));
+}
+
+fn advance_steps(legs: &mut Legs) {
+    let (step_ticks, step_height) = (legs.step_ticks as i32, legs.step_height);
+
+    for leg in legs.legs.iter_mut() {
+        let step = match &mut leg.step {
+            Some(step) => step,
+            None => {
+                continue;
+            }
+        };
+
+        step.ticks += 1;
+        let t = step.ticks as i32;
+
+        if t >= step_ticks {
+            leg.foot = step.to;
+            leg.step = None;
+            continue;
+        }
+
+        // Move along the ground between the spots, lifted by a parabola peaking halfway
+        let (from, to) = (step.from, step.to);
+        leg.foot = Coordinate3d::new(
+            from.x + (to.x - from.x) * t / step_ticks,
+            from.y + (to.y - from.y) * t / step_ticks,
+            from.z
+                + (to.z - from.z) * t / step_ticks
+                + step_height * (4 * t * (step_ticks - t)) / (step_ticks * step_ticks),
+        );
+    }
+}
+
+/// Start stepping every leg in the next group that has a leg too far from its landing spot.
+fn start_steps(legs: &mut Legs, landing_spots: &[Option<Coordinate3d>]) {
+    let group_count = legs.gait.group_count(legs.legs.len());
+
+    for offset in 0..group_count {
+        let group = (legs.next_group + offset) % group_count;
+        let mut started = false;
+
+        for (i, leg) in legs.legs.iter_mut().enumerate() {
+            if legs.gait.group(i) != group {
+                continue;
+            }
+
+            if let Some(spot) = landing_spots[i] {
+                if distance(leg.foot, spot) > legs.stride {
+                    leg.step = Some(Step {
+                        from: leg.foot,
+                        to: spot,
+                        ticks: 0,
+                    });
+                    started = true;
+                }
+            }
+        }
+
+        if started {
+            legs.next_group = (group + 1) % group_count;
+            return;
+        }
+    }
+}
+
+/// Move each leg's rig to its hip and reach for its foot, with knees bending the way the body faces.
+fn pose_legs(legs: &mut Legs, body: Coordinate3d) {
+    for i in 0..legs.legs.len() {
+        let hip = legs.hip(i, body);
+        let facing = legs.facing;
+        let reach = legs.reach;
+        let leg = &mut legs.legs[i];
+
+        let root = leg.rig.get_child_position(0);
+        leg.rig.translate(Coordinate3d::new(
+            hip.x - root.x,
+            hip.y - root.y,
+            hip.z - root.z,
+        ));
+
+        leg.rig.target = Some(leg.foot);
+        leg.rig.pole = Some(Coordinate3d::new(
+            hip.x + facing.x * reach,
+            hip.y + facing.y * reach,
+            hip.z,
+        ));
+
+        fixed_fabrik(&mut leg.rig);
+    }
+}
+
+fn horizontal_direction(v: Coordinate3d) -> Option<Coordinate3d> {
+    let (x, y) = (v.x.to_bits() as i64, v.y.to_bits() as i64);
+    let length = cb_math::isqrt((x * x + y * y) as u64) as i64;
+
+    if length == 0 {
+        return None;
+    }
+
+    let one = GameUnit::from_num(1).to_bits() as i64;
+
+    return Some(Coordinate3d::new(
+        GameUnit::from_bits((x * one / length) as i32),
+        GameUnit::from_bits((y * one / length) as i32),
+        GameUnit::from_num(0),
+    ));
+}
+
+fn distance(a: Coordinate3d, b: Coordinate3d) -> GameUnit {
+    let d = [
+        (a.x - b.x).to_bits() as i64,
+        (a.y - b.y).to_bits() as i64,
+        (a.z - b.z).to_bits() as i64,
+    ];
+
+    return GameUnit::from_bits(
+        cb_math::isqrt((d[0] * d[0] + d[1] * d[1] + d[2] * d[2]) as u64) as i32,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cb_voxels::{coordinate, flat_map};
+
+    const VOXEL: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+    fn units(value: i32) -> GameUnit {
+        return GameUnit::from_num(value);
+    }
+
+    fn biped() -> Legs {
+        return Legs::biped(units(600), units(500), units(250));
+    }
+
+    fn hexapod() -> Legs {
+        return Legs::new(Gait::Alternating, 6, units(400), units(300), units(300));
+    }
+
+    /// Walk the body along the x axis, returning which legs were stepping on each tick.
+    fn walk(legs: &mut Legs, terrain: &CbChunkManager, ticks: i32, speed: i32) -> Vec<Vec<bool>> {
+        return (0..ticks)
+            .map(|tick| {
+                step_legs(
+                    legs,
+                    coordinate(1050 + tick * speed, 2450, 2 * VOXEL),
+                    Some(terrain),
+                );
+                legs.legs.iter().map(|leg| leg.is_stepping()).collect()
+            })
+            .collect();
+    }
+
+    #[test]
+    fn gait_alternating_groups_alternate_feet_diagonals_and_tripods() {
+        let gait = Gait::Alternating;
+        let groups = |count: usize| {
+            (0..count)
+                .map(|leg| gait.group(leg))
+                .collect::<Vec<usize>>()
+        };
+
+        assert_eq!(vec![0, 1], groups(2));
+        assert_eq!(vec![0, 1, 1, 0], groups(4));
+        assert_eq!(vec![0, 1, 1, 0, 0, 1], groups(6));
+    }
+
+    #[test]
+    fn step_legs_standing_still_plants_feet_on_terrain() {
+        let terrain = flat_map(2);
+        let mut legs = biped();
+
+        let stepping = walk(&mut legs, &terrain, 30, 0);
+
+        assert_eq!(false, stepping.iter().flatten().any(|s| *s));
+        for leg in legs.legs.iter() {
+            assert_eq!(units(2 * VOXEL), leg.foot.z);
+        }
+    }
+
+    #[test]
+    fn step_legs_walking_steps_feet_forward_one_group_at_a_time() {
+        let terrain = flat_map(2);
+        let mut legs = hexapod();
+        let gait = legs.gait;
+
+        let stepping = walk(&mut legs, &terrain, 120, 15);
+
+        for tick in stepping.iter() {
+            let groups: Vec<usize> = (0..tick.len())
+                .filter(|i| tick[*i])
+                .map(|i| gait.group(i))
+                .collect();
+            assert_eq!(true, groups.windows(2).all(|g| g[0] == g[1]), "{:?}", tick);
+        }
+        for (i, leg) in legs.legs.iter().enumerate() {
+            let hip = legs.hip(i, coordinate(1050 + 119 * 15, 2450, 2 * VOXEL));
+            assert!(distance(leg.foot, Coordinate3d::new(hip.x, hip.y, leg.foot.z)) < units(600));
+        }
+        assert!(stepping.iter().any(|tick| tick[0]));
+        assert!(stepping.iter().any(|tick| tick[1]));
+    }
+
+    #[test]
+    fn step_legs_feet_land_on_raised_voxels() {
+        let mut terrain = flat_map(2);
+        for x in 5..10 {
+            for y in 6..10 {
+                terrain.add_top_voxel(x, y, cb_voxels::VOXEL_TYPE_DIRT, 0);
+            }
+        }
+        let mut legs = biped();
+
+        walk(&mut legs, &terrain, 80, 15);
+        for _ in 0..20 {
+            step_legs(
+                &mut legs,
+                coordinate(1050 + 79 * 15, 2450, 2 * VOXEL),
+                Some(&terrain),
+            );
+        }
+
+        for leg in legs.legs.iter() {
+            assert_eq!(units(3 * VOXEL), leg.foot.z);
+        }
+    }
+
+    #[test]
+    fn step_legs_rigs_reach_their_feet() {
+        let terrain = flat_map(2);
+        let mut legs = biped();
+
+        walk(&mut legs, &terrain, 40, 10);
+
+        for leg in legs.legs.iter() {
+            assert!(leg.rig.end_effector_error() <= units(1));
+        }
+    }
+}
@@ -0,0 +1,636 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    The lifecycle of a match: players ready up in the lobby, a countdown runs, the match plays until a victory condition
+    is met, then results are recorded for each player. Evaluated every tick from a snapshot of the world so that all
+    peers agree on when and how the match ends.
+*/
+
+use std::path::Path;
+
+use crate::cb_datasheets;
+use cb_datasheets::DataValue;
+
+use super::economy::Resources;
+use super::teams::{CbTeams, TeamId};
+
+pub const MATCH_SETTINGS_PATH: &'static str = "./src/cb_simulation/datasheets/match.hjson";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VictoryCondition {
+    /// The last team, along with its allies, with units remaining wins.
+    Elimination,
+    /// A team that controls every objective for the given number of ticks wins.
+    HoldObjectives { hold_ticks: u32 },
+    /// When time runs out the team with the most units wins.
+    TimeLimit { ticks: u32 },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MatchEndReason {
+    Elimination,
+    ObjectivesHeld,
+    TimeLimit,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MatchPhase {
+    Lobby,
+    Countdown {
+        remaining_ticks: u32,
+    },
+    InProgress,
+    Finished {
+        winner: Option<TeamId>,
+        reason: MatchEndReason,
+    },
+}
+
+impl Default for MatchPhase {
+    fn default() -> Self {
+        return MatchPhase::Lobby;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MatchOutcome {
+    Victory,
+    Defeat,
+    Draw,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerResult {
+    pub player_id: usize,
+    pub team_id: TeamId,
+    pub outcome: MatchOutcome,
+    pub units_remaining: u32,
+    pub stockpile: Resources,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchSettings {
+    pub countdown_ticks: u32,
+    pub victory_conditions: Vec<VictoryCondition>,
+    /// The players controlled by the computer. Every peer loads the same settings, so they all agree on these.
+    pub ai_players: Vec<usize>,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        return Self {
+            countdown_ticks: 0,
+            victory_conditions: vec![VictoryCondition::Elimination],
+            ai_players: vec![],
+        };
+    }
+}
+
+impl MatchSettings {
+    /// Load and validate the match settings at the given path.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let root = cb_datasheets::from_path(path)?;
+
+        return Self::from_data(&root).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    /// Parse and validate match settings from a datasheet string.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root = cb_datasheets::parse(source)?;
+
+        return Self::from_data(&root);
+    }
+
+    fn from_data(root: &DataValue) -> Result<Self, String> {
+        let countdown_ticks = match root.get("countdown_ticks") {
+            Some(value) => match value.as_u32() {
+                Some(v) => v,
+                None => {
+                    return Err("'countdown_ticks' must be a non-negative integer".to_string());
+                }
+            },
+            None => 0,
+        };
+
+        let conditions = match root.get("victory_conditions").and_then(|c| c.as_array()) {
+            Some(conditions) => conditions,
+            None => {
+                return Err("expected a 'victory_conditions' array".to_string());
+            }
+        };
+
+        let mut victory_conditions = vec![];
+
+        for condition in conditions.iter() {
+            let read_ticks = |field: &str| -> Result<u32, String> {
+                match condition.get(field).and_then(|t| t.as_u32()) {
+                    Some(ticks) if ticks > 0 => Ok(ticks),
+                    _ => Err(format!(
+                        "victory condition '{}' requires a positive '{}'",
+                        condition.get("type").and_then(|t| t.as_str()).unwrap_or(""),
+                        field
+                    )),
+                }
+            };
+
+            let victory_condition = match condition.get("type").and_then(|t| t.as_str()) {
+                Some("elimination") => VictoryCondition::Elimination,
+                Some("hold_objectives") => VictoryCondition::HoldObjectives {
+                    hold_ticks: read_ticks("hold_ticks")?,
+                },
+                Some("time_limit") => VictoryCondition::TimeLimit {
+                    ticks: read_ticks("ticks")?,
+                },
+                other => {
+                    return Err(format!(
+                        "unknown victory condition '{}'; expected one of elimination, hold_objectives or time_limit",
+                        other.unwrap_or("<none>")
+                    ));
+                }
+            };
+
+            victory_conditions.push(victory_condition);
+        }
+
+        if victory_conditions.is_empty() {
+            return Err("at least one victory condition is required".to_string());
+        }
+
+        let mut ai_players = vec![];
+        if let Some(players) = root.get("ai_players") {
+            let players = match players.as_array() {
+                Some(players) => players,
+                None => {
+                    return Err("'ai_players' must be an array of player ids".to_string());
+                }
+            };
+
+            for player in players.iter() {
+                match player.as_u32() {
+                    Some(player_id) => ai_players.push(player_id as usize),
+                    None => {
+                        return Err(
+                            "'ai_players' must only contain non-negative integers".to_string()
+                        );
+                    }
+                }
+            }
+        }
+
+        return Ok(Self {
+            countdown_ticks: countdown_ticks,
+            victory_conditions: victory_conditions,
+            ai_players: ai_players,
+        });
+    }
+}
+
+/// The state of the world the victory conditions are evaluated against.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MatchSnapshot {
+    /// The number of living units for each player, as (player id, units).
+    pub units: Vec<(usize, u32)>,
+    /// The team controlling each objective, if any.
+    pub objective_controllers: Vec<Option<TeamId>>,
+    /// The stockpile of each player, as (player id, stockpile). Only used for results.
+    pub stockpiles: Vec<(usize, Resources)>,
+}
+
+impl MatchSnapshot {
+    fn units_of_player(&self, player_id: usize) -> u32 {
+        return self
+            .units
+            .iter()
+            .filter(|(p, _)| *p == player_id)
+            .map(|(_, u)| *u)
+            .sum();
+    }
+
+    fn units_of_team(&self, teams: &CbTeams, team_id: TeamId) -> u32 {
+        return teams
+            .players_on_team(team_id)
+            .iter()
+            .map(|p| self.units_of_player(*p))
+            .sum();
+    }
+}
+
+/// The current match. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CbMatch {
+    settings: MatchSettings,
+    phase: MatchPhase,
+    ready_players: Vec<usize>,
+    elapsed_ticks: u32,
+    objective_hold: Option<(TeamId, u32)>,
+    results: Vec<PlayerResult>,
+}
+
+impl CbMatch {
+    pub fn new(settings: MatchSettings) -> Self {
+        return Self {
+            settings: settings,
+            phase: MatchPhase::Lobby,
+            ready_players: vec![],
+            elapsed_ticks: 0,
+            objective_hold: None,
+            results: vec![],
+        };
+    }
+
+    pub fn phase(&self) -> MatchPhase {
+        return self.phase;
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        return self.phase() == MatchPhase::InProgress;
+    }
+
+    /// How many ticks the match has been in progress for.
+    pub fn elapsed_ticks(&self) -> u32 {
+        return self.elapsed_ticks;
+    }
+
+    /// The result for each player, once the match has finished.
+    pub fn results(&self) -> &Vec<PlayerResult> {
+        return &self.results;
+    }
+
+    /// Mark the player as ready or not; only has an effect in the lobby.
+    pub fn set_ready(&mut self, player_id: usize, ready: bool) {
+        if self.phase() != MatchPhase::Lobby {
+            return;
+        }
+
+        self.ready_players.retain(|p| *p != player_id);
+        if ready {
+            self.ready_players.push(player_id);
+        }
+    }
+
+    /// Skip the lobby and begin counting down.
+    pub fn start_countdown(&mut self) {
+        if self.phase() == MatchPhase::Lobby {
+            self.phase = MatchPhase::Countdown {
+                remaining_ticks: self.settings.countdown_ticks,
+            };
+        }
+    }
+
+    /// Advance the match by one tick.
+    pub fn tick(&mut self, teams: &CbTeams, snapshot: &MatchSnapshot) {
+        match self.phase() {
+            MatchPhase::Lobby => {
+                let players = teams.players();
+                let all_ready =
+                    !players.is_empty() && players.iter().all(|p| self.ready_players.contains(p));
+
+                if all_ready {
+                    self.start_countdown();
+                }
+            }
+            MatchPhase::Countdown { remaining_ticks } => {
+                if remaining_ticks == 0 {
+                    self.phase = MatchPhase::InProgress;
+                } else {
+                    self.phase = MatchPhase::Countdown {
+                        remaining_ticks: remaining_ticks - 1,
+                    };
+                }
+            }
+            MatchPhase::InProgress => {
+                self.elapsed_ticks += 1;
+
+                if let Some((winner, reason)) = self.evaluate(teams, snapshot) {
+                    self.finish(teams, snapshot, winner, reason);
+                }
+            }
+            MatchPhase::Finished {
+                winner: _,
+                reason: _,
+            } => {}
+        }
+    }
+
+    /// Check each victory condition in order; the first that is met ends the match.
+    fn evaluate(
+        &mut self,
+        teams: &CbTeams,
+        snapshot: &MatchSnapshot,
+    ) -> Option<(Option<TeamId>, MatchEndReason)> {
+        let conditions = self.settings.victory_conditions.clone();
+
+        for condition in conditions.iter() {
+            match *condition {
+                VictoryCondition::Elimination => {
+                    let surviving: Vec<TeamId> = teams
+                        .teams()
+                        .into_iter()
+                        .filter(|t| snapshot.units_of_team(teams, *t) > 0)
+                        .collect();
+
+                    if surviving.is_empty() {
+                        return Some((None, MatchEndReason::Elimination));
+                    }
+
+                    let all_allied = surviving
+                        .iter()
+                        .all(|t| teams.are_teams_allied(surviving[0], *t));
+
+                    if all_allied && surviving.len() < teams.teams().len() {
+                        return Some((Some(surviving[0]), MatchEndReason::Elimination));
+                    }
+                }
+                VictoryCondition::HoldObjectives { hold_ticks } => {
+                    let controller = match snapshot.objective_controllers.first() {
+                        Some(Some(first)) => {
+                            let holds_all = snapshot
+                                .objective_controllers
+                                .iter()
+                                .all(|c| *c == Some(*first));
+
+                            if holds_all {
+                                Some(*first)
+                            } else {
+                                None
+                            }
+                        }
+                        _ => None,
+                    };
+
+                    self.objective_hold = match (controller, self.objective_hold) {
+                        (Some(team), Some((held_by, ticks))) if team == held_by => {
+                            Some((team, ticks + 1))
+                        }
+                        (Some(team), _) => Some((team, 1)),
+                        (None, _) => None,
+                    };
+
+                    if let Some((team, ticks)) = self.objective_hold {
+                        if ticks >= hold_ticks {
+                            return Some((Some(team), MatchEndReason::ObjectivesHeld));
+                        }
+                    }
+                }
+                VictoryCondition::TimeLimit { ticks } => {
+                    if self.elapsed_ticks >= ticks {
+                        let mut best: Option<(TeamId, u32)> = None;
+                        let mut tied = false;
+
+                        for team in teams.teams().iter() {
+                            let units = snapshot.units_of_team(teams, *team);
+
+                            match best {
+                                Some((_, best_units)) if units == best_units => {
+                                    tied = true;
+                                }
+                                Some((_, best_units)) if units < best_units => {}
+                                _ => {
+                                    best = Some((*team, units));
+                                    tied = false;
+                                }
+                            }
+                        }
+
+                        let winner = if tied { None } else { best.map(|(t, _)| t) };
+
+                        return Some((winner, MatchEndReason::TimeLimit));
+                    }
+                }
+            }
+        }
+
+        return None;
+    }
+
+    fn finish(
+        &mut self,
+        teams: &CbTeams,
+        snapshot: &MatchSnapshot,
+        winner: Option<TeamId>,
+        reason: MatchEndReason,
+    ) {
+        self.phase = MatchPhase::Finished {
+            winner: winner,
+            reason: reason,
+        };
+
+        self.results = teams
+            .players()
+            .iter()
+            .map(|player_id| {
+                let team_id = teams.team_of(*player_id).unwrap();
+
+                let outcome = match winner {
+                    Some(winner) if teams.are_teams_allied(winner, team_id) => {
+                        MatchOutcome::Victory
+                    }
+                    Some(_) => MatchOutcome::Defeat,
+                    None => MatchOutcome::Draw,
+                };
+
+                let stockpile = snapshot
+                    .stockpiles
+                    .iter()
+                    .find(|(p, _)| p == player_id)
+                    .map(|(_, s)| *s)
+                    .unwrap_or(Resources::zero());
+
+                PlayerResult {
+                    player_id: *player_id,
+                    team_id: team_id,
+                    outcome: outcome,
+                    units_remaining: snapshot.units_of_player(*player_id),
+                    stockpile: stockpile,
+                }
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_teams() -> CbTeams {
+        let mut teams = CbTeams::new();
+        teams.add_player(0, 0);
+        teams.add_player(1, 1);
+
+        return teams;
+    }
+
+    fn in_progress(conditions: Vec<VictoryCondition>) -> CbMatch {
+        let mut m = CbMatch::new(MatchSettings {
+            countdown_ticks: 0,
+            victory_conditions: conditions,
+            ai_players: vec![],
+        });
+        m.start_countdown();
+        m.tick(&CbTeams::new(), &MatchSnapshot::default());
+
+        return m;
+    }
+
+    fn snapshot(units: Vec<(usize, u32)>) -> MatchSnapshot {
+        return MatchSnapshot {
+            units: units,
+            objective_controllers: vec![],
+            stockpiles: vec![],
+        };
+    }
+
+    #[test]
+    fn cb_match_lobby_all_players_ready_starts_countdown() {
+        let teams = two_teams();
+        let mut m = CbMatch::new(MatchSettings {
+            countdown_ticks: 2,
+            victory_conditions: vec![VictoryCondition::Elimination],
+            ai_players: vec![],
+        });
+
+        m.set_ready(0, true);
+        m.tick(&teams, &MatchSnapshot::default());
+        assert_eq!(MatchPhase::Lobby, m.phase());
+
+        m.set_ready(1, true);
+        m.tick(&teams, &MatchSnapshot::default());
+        assert_eq!(MatchPhase::Countdown { remaining_ticks: 2 }, m.phase());
+
+        for _ in 0..3 {
+            m.tick(&teams, &MatchSnapshot::default());
+        }
+        assert_eq!(MatchPhase::InProgress, m.phase());
+    }
+
+    #[test]
+    fn cb_match_elimination_last_team_standing_wins() {
+        let teams = two_teams();
+        let mut m = in_progress(vec![VictoryCondition::Elimination]);
+
+        m.tick(&teams, &snapshot(vec![(0, 3), (1, 1)]));
+        assert_eq!(MatchPhase::InProgress, m.phase());
+
+        m.tick(&teams, &snapshot(vec![(0, 3), (1, 0)]));
+
+        assert_eq!(
+            MatchPhase::Finished {
+                winner: Some(0),
+                reason: MatchEndReason::Elimination
+            },
+            m.phase()
+        );
+        assert_eq!(MatchOutcome::Victory, m.results()[0].outcome);
+        assert_eq!(3, m.results()[0].units_remaining);
+        assert_eq!(MatchOutcome::Defeat, m.results()[1].outcome);
+    }
+
+    #[test]
+    fn cb_match_elimination_allies_win_together() {
+        let mut teams = two_teams();
+        teams.add_player(2, 2);
+        teams.add_alliance(0, 2);
+        let mut m = in_progress(vec![VictoryCondition::Elimination]);
+
+        m.tick(&teams, &snapshot(vec![(0, 1), (2, 1)]));
+
+        assert_eq!(MatchOutcome::Victory, m.results()[2].outcome);
+        assert_eq!(MatchOutcome::Defeat, m.results()[1].outcome);
+    }
+
+    #[test]
+    fn cb_match_hold_objectives_requires_consecutive_ticks() {
+        let teams = two_teams();
+        let mut m = in_progress(vec![VictoryCondition::HoldObjectives { hold_ticks: 2 }]);
+        let held = MatchSnapshot {
+            units: vec![],
+            objective_controllers: vec![Some(1), Some(1)],
+            stockpiles: vec![],
+        };
+        let contested = MatchSnapshot {
+            units: vec![],
+            objective_controllers: vec![Some(1), None],
+            stockpiles: vec![],
+        };
+
+        m.tick(&teams, &held);
+        m.tick(&teams, &contested);
+        m.tick(&teams, &held);
+        assert_eq!(MatchPhase::InProgress, m.phase());
+
+        m.tick(&teams, &held);
+        assert_eq!(
+            MatchPhase::Finished {
+                winner: Some(1),
+                reason: MatchEndReason::ObjectivesHeld
+            },
+            m.phase()
+        );
+    }
+
+    #[test]
+    fn cb_match_time_limit_tied_units_is_draw() {
+        let teams = two_teams();
+        let mut m = in_progress(vec![VictoryCondition::TimeLimit { ticks: 2 }]);
+
+        m.tick(&teams, &snapshot(vec![(0, 2), (1, 2)]));
+        m.tick(&teams, &snapshot(vec![(0, 2), (1, 2)]));
+
+        assert_eq!(
+            MatchPhase::Finished {
+                winner: None,
+                reason: MatchEndReason::TimeLimit
+            },
+            m.phase()
+        );
+        assert_eq!(MatchOutcome::Draw, m.results()[0].outcome);
+    }
+
+    #[test]
+    fn match_settings_parse_returns_conditions_in_order() {
+        let source = "countdown_ticks: 60
+            victory_conditions: [
+                { type: elimination }
+                { type: time_limit, ticks: 600 }
+            ]
+            ai_players: [1, 3]";
+
+        let actual = MatchSettings::parse(source).unwrap();
+
+        assert_eq!(
+            MatchSettings {
+                countdown_ticks: 60,
+                victory_conditions: vec![
+                    VictoryCondition::Elimination,
+                    VictoryCondition::TimeLimit { ticks: 600 }
+                ],
+                ai_players: vec![1, 3]
+            },
+            actual
+        );
+    }
+
+    #[test]
+    fn match_settings_parse_unknown_condition_returns_err() {
+        let actual = MatchSettings::parse("victory_conditions: [ { type: king_of_the_hill } ]");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn match_settings_parse_negative_ai_player_returns_err() {
+        let actual = MatchSettings::parse(
+            "victory_conditions: [ { type: elimination } ]
+            ai_players: [-1]",
+        );
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn match_settings_from_path_shipped_datasheet_is_valid() {
+        let actual = MatchSettings::from_path(Path::new(MATCH_SETTINGS_PATH));
+
+        assert!(actual.is_ok(), "{:?}", actual.err());
+    }
+}
@@ -7,9 +7,15 @@ use crate::cb_voxels;
 
 use crate::cb_graphics;
 
+use crate::cb_assets;
+use cb_assets::{AssetManager, Handle};
+
 mod systems;
 use systems::{
-    actor_input_system, audio, editor_system::EditorSystem, physics, voxel_editor_system,
+    ability_system, actor_input_system, ai_system, animation_system, audio, camera_system,
+    death_system, economy as economy_systems, editor_system::EditorSystem, fighting_system,
+    fog_of_war_system, match_system, physics, projectile_system, shooter,
+    transform_history_system, unit_order_system,
 };
 
 mod assemblages;
@@ -31,10 +37,30 @@ use cb_input::CbGameInput;
 use crate::cb_menu;
 use cb_menu::{menu_events, Form};
 
+pub mod abilities;
+pub mod actor_controller;
+pub mod ai;
+pub mod cameras;
+pub mod character_controller;
+pub mod economy;
+pub mod fighting;
+pub mod fog_of_war;
+pub mod locomotion;
+pub mod match_state;
+pub mod projectiles;
+pub mod rigid_body;
+pub mod rts_targeting;
+pub mod teams;
+pub mod tick_clock;
+pub mod unit_animations;
+pub mod unit_definitions;
 mod world_builder;
 
 // NOTE: GAME UNITS are 1 = 1mm, using i32s
 
+/// Simulation ticks in a second. Anything converting between seconds and ticks goes through this.
+pub const TICKS_PER_SECOND: u32 = 60;
+
 #[derive(Default)]
 pub struct CbSystemValues {
     pub events: Vec<(menu_events::EventId, menu_events::Events)>,
@@ -83,16 +109,22 @@ pub struct CbSimulationInterface<'a, 'b> {
     world: World,
     in_editor_mode: bool,
     current_player_id: usize,
+    ai_dispatcher: specs::Dispatcher<'a, 'b>,
+    match_dispatcher: specs::Dispatcher<'a, 'b>,
     sim_dispatcher: specs::Dispatcher<'a, 'b>,
     editor_dispatcher: specs::Dispatcher<'a, 'b>,
+    view_dispatcher: specs::Dispatcher<'a, 'b>,
     gfx_dispatcher: specs::Dispatcher<'a, 'b>,
     audio_dispatcher: specs::Dispatcher<'a, 'b>,
+    tick_clock: tick_clock::CbTickClock,
     pub gfx: cb_graphics::CbGfx,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum CbSimulationModes {
     RtsMode,
+    ShooterMode,
+    FightingMode,
 }
 
 impl<'a, 'b> CbSimulationInterface<'a, 'b> {
@@ -102,11 +134,78 @@ impl<'a, 'b> CbSimulationInterface<'a, 'b> {
         {
             game_system_dispatcher = DispatcherBuilder::new()
                 .with(actor_input_system::ActorInputSystem, "actor input", &[])
+                .with(economy_systems::SupplySystem, "supply", &[])
                 .with_barrier()
+                .with(
+                    economy_systems::EconomyCommandSystem,
+                    "economy commands",
+                    &[],
+                )
                 .with(physics::IkSystem, "inverse kinematics", &[])
+                .with(
+                    unit_order_system::UnitOrderSystem,
+                    "unit orders",
+                    &["economy commands"],
+                )
+                .with(
+                    economy_systems::HarvestSystem,
+                    "harvesting",
+                    &["economy commands"],
+                )
+                .with(
+                    economy_systems::ProductionSystem,
+                    "production",
+                    &["economy commands"],
+                )
+                .with(ability_system::AbilitySystem, "abilities", &["production"])
+                .with(
+                    shooter::CharacterControllerSystem,
+                    "character controller",
+                    &[],
+                )
+                .with(shooter::WeaponSystem, "weapons", &["character controller"])
+                .with(
+                    physics::RigidBodySystem,
+                    "rigid bodies",
+                    &["character controller"],
+                )
+                .with(
+                    projectile_system::ProjectileSystem,
+                    "projectiles",
+                    &["weapons", "rigid bodies", "unit orders"],
+                )
+                .with(
+                    physics::LocomotionSystem,
+                    "locomotion",
+                    &["unit orders", "harvesting"],
+                )
+                .with(fighting_system::FightingSystem, "fighting", &[])
+                .with(
+                    animation_system::AnimationSystem,
+                    "animation",
+                    &["locomotion", "fighting"],
+                )
+                .with(
+                    death_system::DeathSystem,
+                    "death",
+                    &["abilities", "unit orders", "projectiles", "fighting"],
+                )
+                .with(
+                    fog_of_war_system::FogOfWarSystem,
+                    "fog of war",
+                    &["harvesting", "abilities", "unit orders"],
+                )
                 .build();
         }
 
+        let ai_dispatcher = DispatcherBuilder::new()
+            .with(ai_system::AiSystem, "ai", &[])
+            .build();
+
+        let match_dispatcher = DispatcherBuilder::new()
+            .with(match_system::MatchSystem, "match", &[])
+            .build();
+
         let audio_system_dispatcher;
         {
             let mut dispatcher = DispatcherBuilder::new();
@@ -124,18 +223,36 @@ impl<'a, 'b> CbSimulationInterface<'a, 'b> {
             .with(EditorSystem, "editor system", &[])
             .build();
 
+        // Cameras move and transforms are recorded even while the match isn't in progress, so the map can be looked
+        // around and entities come to rest
+        let mut view_dispatcher = DispatcherBuilder::new()
+            .with(camera_system::CameraSystem, "cameras", &[])
+            .with(
+                transform_history_system::TransformHistorySystem,
+                "transform history",
+                &[],
+            )
+            .build();
+
         let mut gfx_dispatcher = cb_graphics::gfx_build_dispatcher();
         let mut world = world_builder::new(mode);
+        view_dispatcher.setup(&mut world);
+        gfx_dispatcher.setup(&mut world);
+        let gfx = cb_graphics::CbGfx::new(&world.read_resource::<AssetManager>());
 
         return Self {
             current_player_id: 0,
             game_state: CbGameState::new(),
+            ai_dispatcher: ai_dispatcher,
+            match_dispatcher: match_dispatcher,
             sim_dispatcher: game_system_dispatcher,
             editor_dispatcher: editor_dispatcher,
+            view_dispatcher: view_dispatcher,
             audio_dispatcher: audio_system_dispatcher,
             gfx_dispatcher: gfx_dispatcher,
             world: world,
-            gfx: cb_graphics::CbGfx::new(),
+            tick_clock: tick_clock::CbTickClock::new(TICKS_PER_SECOND, std::time::Instant::now()),
+            gfx: gfx,
             in_editor_mode: true,
         };
     }
@@ -148,9 +265,24 @@ impl<'a, 'b> CbSimulationInterface<'a, 'b> {
         return self.current_player_id;
     }
 
+    /// What the player's cursor points at, for their RTS presses to act on.
+    pub fn get_rts_target(
+        &mut self,
+        player_id: usize,
+    ) -> cb_input::contexts::rts_command_context::CbRtsTarget {
+        let events = vec![];
+        let hardware = cb_graphics::Sdl2HardwareInterface::from_gfx(&self.gfx, &events);
+        let (cursor_x, cursor_y) = cb_input::contexts::get_normalized_cursor_coordinates(&hardware);
+
+        let camera = *self.gfx.camera();
+
+        return rts_targeting::get_rts_target(&self.world, &camera, player_id, cursor_x, cursor_y);
+    }
+
     pub fn toggle_editor_mode(&mut self) {
         self.in_editor_mode = !self.in_editor_mode;
         self.gfx.toggle_editor_window();
+        cameras::switch_editor_camera(&self.world);
         println!("Editor Mode: {}", self.in_editor_mode);
     }
 
@@ -160,17 +292,76 @@ impl<'a, 'b> CbSimulationInterface<'a, 'b> {
         self.audio_dispatcher.dispatch(&self.world);
     }
 
+    /// Apply changes to asset files that were edited while the game runs. Graphics assets are picked up by the
+    /// renderer; unit definitions, map lighting and voxel maps are copied into the world here.
+    /// NOTE: this changes the simulation, so it's only done between ticks while editing.
+    fn reload_assets(&mut self) {
+        let reloaded = self.world.read_resource::<AssetManager>().poll();
+        if reloaded.is_empty() {
+            return;
+        }
+
+        // Unit definitions
+        {
+            let handle = self
+                .world
+                .read_resource::<Handle<unit_definitions::UnitDefinitions>>()
+                .clone();
+
+            if reloaded.iter().any(|path| path == handle.path()) {
+                if let Some(definitions) = handle.get() {
+                    *self
+                        .world
+                        .write_resource::<unit_definitions::UnitDefinitions>() =
+                        (*definitions).clone();
+                }
+            }
+        }
+
+        // Map lighting
+        {
+            let handle = self
+                .world
+                .read_resource::<Handle<cb_graphics::lighting::Lighting>>()
+                .clone();
+
+            if reloaded.iter().any(|path| path == handle.path()) {
+                if let Some(lighting) = handle.get() {
+                    *self
+                        .world
+                        .write_resource::<cb_graphics::lighting::Lighting>() = (*lighting).clone();
+                }
+            }
+        }
+
+        // Voxel maps
+        {
+            let frame = self.game_state.current_tick as usize;
+            let mut voxels = self.world.write_storage::<VoxelComponent>();
+            for voxel in (&mut voxels).join() {
+                if let Err(e) = voxel.reload_map(frame) {
+                    println!("Unable to reload voxel map: {}", e);
+                }
+            }
+        }
+    }
+
     /// Render the simulation; only updates the graphics systems
     pub fn render(&mut self) {
-        //TODO: maybe make delta based to allow for interpolation?
+        // Draw between the previous tick and the current one, so movement is smooth at any refresh rate
+        let alpha = self.tick_clock.alpha(std::time::Instant::now());
+        self.world.insert(tick_clock::CbFrameAlpha::new(alpha));
+
+        // Gather what to draw before drawing it
+        self.gfx_dispatcher.dispatch(&self.world);
+        self.world.maintain();
+
         self.gfx.render(
             &self.game_state,
             &self.world,
             self.game_state.current_tick as usize,
+            alpha,
         );
-
-        self.gfx_dispatcher.dispatch(&self.world);
-        self.world.maintain();
     }
 }
 
@@ -183,7 +374,20 @@ impl<'a, 'b> RMercuryGameInterface<CbGameState, CbGameInput> for CbSimulationInt
         //unimplemented!()
         return "hello world!".to_string();
     }
-    fn advance_frame(&mut self, inputs: std::vec::Vec<CbGameInput>) {
+    fn advance_frame(&mut self, mut inputs: std::vec::Vec<CbGameInput>) {
+        if self.in_editor_mode {
+            self.reload_assets();
+        }
+
+        // AI players decide on their inputs from the world as it is at the start of the frame, just as a remote peer would
+        {
+            self.ai_dispatcher.dispatch(&mut self.world);
+
+            let mut ai_players = self.world.write_resource::<ai::CbAiPlayers>();
+            inputs.retain(|i| !ai_players.is_ai_player(i.player_id));
+            inputs.append(&mut ai_players.pending_inputs);
+        }
+
         let mut sys_values = CbSystemValues::from(
             inputs,
             self.get_local_player_id(),
@@ -209,14 +413,25 @@ impl<'a, 'b> RMercuryGameInterface<CbGameState, CbGameInput> for CbSimulationInt
         }
         //else
         {
+            // Advance the match lifecycle; the world is only simulated while the match is in progress
+            self.match_dispatcher.dispatch(&mut self.world);
+            let in_progress = self
+                .world
+                .read_resource::<match_state::CbMatch>()
+                .is_in_progress();
+
             // Execute simulation systems
-            {
+            if in_progress {
                 // Execute world systems + maintain it
                 self.sim_dispatcher.dispatch(&mut self.world);
+                assemblages::rts_assemblages::spawn_queued_units(&mut self.world);
                 self.world.maintain();
             }
 
+            self.view_dispatcher.dispatch(&mut self.world);
+
             self.game_state.current_tick += 1;
+            self.tick_clock.tick(std::time::Instant::now());
         }
     }
 
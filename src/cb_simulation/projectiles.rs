@@ -0,0 +1,616 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Projectiles fired by weapons. Hitscan projectiles cross their whole range on the tick they are fired, linear
+    projectiles fly straight at a fixed speed and ballistic projectiles fall under gravity. Every tick a projectile sweeps
+    the segment it travels against the voxel terrain and its targets, stopping at whichever it reaches first, so fast
+    projectiles never pass through anything. Explosive projectiles detonate where they stop, or where they are when
+    their lifetime runs out.
+*/
+
+use crate::cb_math;
+use cb_math::{from_bits, length, normalize, to_bits, FUint, ONE};
+
+use crate::cb_simulation;
+use cb_simulation::rigid_body::{RigidBody, GRAVITY_MILLIS};
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+use cb_voxels::{CbChunkManager, VoxelHit};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProjectileKind {
+    Hitscan,
+    Linear,
+    Ballistic,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProjectileDefinition {
+    pub kind: ProjectileKind,
+    pub damage: FUint,
+    /// Game units per tick.
+    pub speed: GameUnit,
+    pub lifetime_ticks: u32,
+    /// Explosive projectiles damage everything within the radius, in game units, and destroy the voxels in it. Zero
+    /// for projectiles that only damage what they hit.
+    pub blast_radius: GameUnit,
+}
+
+impl ProjectileDefinition {
+    /// A projectile that hits the first thing within range on the tick it is fired.
+    pub fn hitscan(damage: FUint, range: GameUnit) -> Self {
+        return Self {
+            kind: ProjectileKind::Hitscan,
+            damage: damage,
+            speed: range,
+            lifetime_ticks: 1,
+            blast_radius: GameUnit::from_num(0),
+        };
+    }
+
+    pub fn linear(damage: FUint, speed: GameUnit, lifetime_ticks: u32) -> Self {
+        return Self {
+            kind: ProjectileKind::Linear,
+            damage: damage,
+            speed: speed,
+            lifetime_ticks: lifetime_ticks,
+            blast_radius: GameUnit::from_num(0),
+        };
+    }
+
+    pub fn ballistic(damage: FUint, speed: GameUnit, lifetime_ticks: u32) -> Self {
+        return Self {
+            kind: ProjectileKind::Ballistic,
+            ..Self::linear(damage, speed, lifetime_ticks)
+        };
+    }
+
+    pub fn with_blast(mut self, radius: GameUnit) -> Self {
+        self.blast_radius = radius;
+        return self;
+    }
+
+    pub fn is_explosive(&self) -> bool {
+        return self.blast_radius > 0;
+    }
+}
+
+/// The half width of the box units without a character controller are shot at, in game units.
+pub const UNIT_RADIUS: i32 = 250;
+/// The height of the box units without a character controller are shot at, in game units.
+pub const UNIT_HEIGHT: i32 = 1800;
+
+/// Something a projectile may hit, as a box in game units.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProjectileTarget {
+    pub min: Coordinate3d,
+    pub max: Coordinate3d,
+}
+
+impl ProjectileTarget {
+    pub fn new(min: Coordinate3d, max: Coordinate3d) -> Self {
+        return Self { min: min, max: max };
+    }
+
+    /// A unit sized box standing on the ground at the position.
+    pub fn unit(position: Coordinate2d, ground: GameUnit) -> Self {
+        let radius = GameUnit::from_num(UNIT_RADIUS);
+
+        return Self {
+            min: Coordinate3d::new(position.x - radius, position.y - radius, ground),
+            max: Coordinate3d::new(
+                position.x + radius,
+                position.y + radius,
+                ground + GameUnit::from_num(UNIT_HEIGHT),
+            ),
+        };
+    }
+
+    pub fn center(&self) -> Coordinate3d {
+        return Coordinate3d::new(
+            (self.min.x + self.max.x) / 2,
+            (self.min.y + self.max.y) / 2,
+            (self.min.z + self.max.z) / 2,
+        );
+    }
+}
+
+/// The height of the top of the terrain under the position, in game units. Positions off of the map are on the floor.
+pub fn ground_height(terrain: &CbChunkManager, position: Coordinate2d) -> GameUnit {
+    let voxel = cb_voxels::VOXEL_GAME_SIZE;
+    let width = terrain.get_voxel_width() as i32;
+    let (x, y) = (
+        position.x.to_num::<i32>().div_euclid(voxel),
+        position.y.to_num::<i32>().div_euclid(voxel),
+    );
+
+    if x < 0 || y < 0 || x >= width || y >= width {
+        return GameUnit::from_num(0);
+    }
+
+    let height = terrain.get_column_height(x as usize, y as usize) as i32;
+
+    return GameUnit::from_num(height * voxel);
+}
+
+/// The direction from one point to another, of unit length. Identical points have no direction.
+pub fn aim(from: Coordinate3d, to: Coordinate3d) -> Option<Coordinate3d> {
+    let (f, t) = (to_bits(from), to_bits(to));
+
+    return normalize([t[0] - f[0], t[1] - f[1], t[2] - f[2]]).map(from_bits);
+}
+
+/// What a projectile stopped at.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Impact {
+    Terrain(VoxelHit),
+    /// The index of the target that was hit.
+    Target(usize),
+    /// The projectile's lifetime ran out.
+    Expired,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProjectileHit {
+    pub position: Coordinate3d,
+    pub impact: Impact,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Projectile {
+    pub definition: ProjectileDefinition,
+    pub position: Coordinate3d,
+    /// In game units per tick.
+    pub velocity: Coordinate3d,
+    pub ticks_alive: u32,
+}
+
+impl Projectile {
+    /// Fire from the origin along the direction, which should be of unit length.
+    pub fn new(
+        definition: ProjectileDefinition,
+        origin: Coordinate3d,
+        direction: Coordinate3d,
+    ) -> Self {
+        let speed = definition.speed;
+
+        return Self {
+            definition: definition,
+            position: origin,
+            velocity: Coordinate3d::new(
+                direction.x * speed,
+                direction.y * speed,
+                direction.z * speed,
+            ),
+            ticks_alive: 0,
+        };
+    }
+}
+
+/// Returns the distance along the ray to where it enters the box, if it does. Rays starting inside the box hit it at
+/// zero. The direction should be of unit length.
+pub fn raycast_box(
+    origin: Coordinate3d,
+    direction: Coordinate3d,
+    min: Coordinate3d,
+    max: Coordinate3d,
+) -> Option<GameUnit> {
+    let (o, d) = (to_bits(origin), to_bits(direction));
+    let (min, max) = (to_bits(min), to_bits(max));
+
+    // Slab test, done on the raw bits of the fixed point values to avoid overflowing during the division
+    let mut t_enter = i64::MIN;
+    let mut t_exit = i64::MAX;
+
+    for axis in 0..3 {
+        if d[axis] == 0 {
+            if o[axis] < min[axis] || o[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let t1 = (min[axis] - o[axis]) * ONE / d[axis];
+        let t2 = (max[axis] - o[axis]) * ONE / d[axis];
+
+        t_enter = t_enter.max(t1.min(t2));
+        t_exit = t_exit.min(t1.max(t2));
+    }
+
+    if t_enter > t_exit || t_exit < 0 {
+        return None;
+    }
+
+    return Some(GameUnit::from_bits(t_enter.max(0) as i32));
+}
+
+/// Returns the distance to the nearest voxel or target along the ray, within the max distance, and what was hit. A
+/// target only counts when strictly nearer than anything before it, so ties always resolve the same way. Without
+/// terrain only the targets may be hit.
+pub fn sweep(
+    origin: Coordinate3d,
+    direction: Coordinate3d,
+    max_distance: GameUnit,
+    terrain: Option<&CbChunkManager>,
+    targets: &[ProjectileTarget],
+) -> Option<(GameUnit, Impact)> {
+    let mut nearest = terrain
+        .and_then(|terrain| terrain.raycast(origin, direction, max_distance))
+        .map(|hit| (hit.distance, Impact::Terrain(hit)));
+
+    for (i, target) in targets.iter().enumerate() {
+        let distance = match raycast_box(origin, direction, target.min, target.max) {
+            Some(distance) if distance <= max_distance => distance,
+            _ => {
+                continue;
+            }
+        };
+
+        if nearest.map_or(true, |(d, _)| distance < d) {
+            nearest = Some((distance, Impact::Target(i)));
+        }
+    }
+
+    return nearest;
+}
+
+/// Advance the projectile by a tick. Returns where it stopped and what at, once it hits something or expires.
+pub fn step_projectile(
+    projectile: &mut Projectile,
+    terrain: Option<&CbChunkManager>,
+    targets: &[ProjectileTarget],
+) -> Option<ProjectileHit> {
+    if projectile.definition.kind == ProjectileKind::Ballistic {
+        projectile.velocity.z -= GameUnit::from_num(GRAVITY_MILLIS) / 1000;
+    }
+
+    let velocity = to_bits(projectile.velocity);
+    if let Some(direction) = normalize(velocity) {
+        let length = length(velocity);
+        let direction = from_bits(direction);
+        let length = GameUnit::from_bits(length as i32);
+
+        if let Some((distance, impact)) =
+            sweep(projectile.position, direction, length, terrain, targets)
+        {
+            let p = projectile.position;
+            projectile.position = Coordinate3d::new(
+                p.x + direction.x * distance,
+                p.y + direction.y * distance,
+                p.z + direction.z * distance,
+            );
+
+            return Some(ProjectileHit {
+                position: projectile.position,
+                impact: impact,
+            });
+        }
+
+        projectile.position.x += projectile.velocity.x;
+        projectile.position.y += projectile.velocity.y;
+        projectile.position.z += projectile.velocity.z;
+    }
+
+    projectile.ticks_alive += 1;
+
+    if projectile.ticks_alive >= projectile.definition.lifetime_ticks {
+        return Some(ProjectileHit {
+            position: projectile.position,
+            impact: Impact::Expired,
+        });
+    }
+
+    return None;
+}
+
+/// How strongly a blast reaches the box, from 1 at the center of the blast to nothing at the edge of its radius.
+pub fn blast_falloff(
+    center: Coordinate3d,
+    radius: GameUnit,
+    min: Coordinate3d,
+    max: Coordinate3d,
+) -> Option<GameUnit> {
+    let (c, min, max) = (to_bits(center), to_bits(min), to_bits(max));
+    let r = radius.to_bits() as i64;
+
+    // Distance to the closest point of the box
+    let mut squared = 0;
+    for axis in 0..3 {
+        let d = c[axis] - c[axis].max(min[axis]).min(max[axis]);
+        squared += d * d;
+    }
+    let distance = cb_math::isqrt(squared as u64) as i64;
+
+    if distance >= r {
+        return None;
+    }
+
+    return Some(GameUnit::from_bits(((r - distance) * ONE / r) as i32));
+}
+
+/// The impulse a blast gives a rigid body, pushing it away from the center of the blast. Strength is the impulse at
+/// the center, in kilogram game units per tick.
+pub fn blast_impulse(
+    center: Coordinate3d,
+    radius: GameUnit,
+    strength: GameUnit,
+    body: &RigidBody,
+) -> Option<Coordinate3d> {
+    let (min, max) = body.bounds();
+    let falloff = blast_falloff(center, radius, min, max)?;
+
+    let (c, p) = (to_bits(center), to_bits(body.position));
+    let direction = match normalize([p[0] - c[0], p[1] - c[1], p[2] - c[2]]) {
+        Some(direction) => from_bits(direction),
+        None => Coordinate3d::new(
+            GameUnit::from_num(0),
+            GameUnit::from_num(0),
+            GameUnit::from_num(1),
+        ),
+    };
+    let impulse = strength * falloff;
+
+    return Some(Coordinate3d::new(
+        direction.x * impulse,
+        direction.y * impulse,
+        direction.z * impulse,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cb_voxels::{coordinate, flat_map};
+
+    const VOXEL: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+    /// A unit sized target standing on the flat map, centered on the coordinates.
+    fn unit_at(x: i32, y: i32) -> ProjectileTarget {
+        return ProjectileTarget::new(
+            coordinate(x - 250, y - 250, 2 * VOXEL),
+            coordinate(x + 250, y + 250, 2 * VOXEL + 1800),
+        );
+    }
+
+    fn damage() -> FUint {
+        return FUint::from_num(10);
+    }
+
+    /// Step until the projectile stops, returning the hit and how many ticks it took.
+    fn fly(
+        projectile: &mut Projectile,
+        terrain: &CbChunkManager,
+        targets: &[ProjectileTarget],
+    ) -> (ProjectileHit, u32) {
+        for tick in 1..1000 {
+            if let Some(hit) = step_projectile(projectile, Some(terrain), targets) {
+                return (hit, tick);
+            }
+        }
+
+        panic!("projectile never stopped");
+    }
+
+    #[test]
+    fn raycast_box_hits_box_in_front_and_misses_behind() {
+        let (min, max) = (coordinate(2750, 750, 0), coordinate(3250, 1250, 1800));
+        let forward = coordinate(1, 0, 0);
+
+        let hit = raycast_box(coordinate(1000, 1000, 1000), forward, min, max);
+        let miss = raycast_box(coordinate(4000, 1000, 1000), forward, min, max);
+
+        assert_eq!(Some(GameUnit::from_num(1750)), hit);
+        assert_eq!(None, miss);
+    }
+
+    #[test]
+    fn sweep_hits_nearest_unit_before_voxel() {
+        let mut terrain = flat_map(2);
+        terrain.add_top_voxel(10, 3, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        let targets = [unit_at(2550, 1050), unit_at(1950, 1050)];
+
+        let hit = sweep(
+            coordinate(450, 1050, 1200),
+            coordinate(1, 0, 0),
+            GameUnit::from_num(9000),
+            Some(&terrain),
+            &targets,
+        );
+
+        assert_eq!(Some((GameUnit::from_num(1250), Impact::Target(1))), hit);
+    }
+
+    #[test]
+    fn sweep_voxel_blocks_unit_behind_it() {
+        let mut terrain = flat_map(2);
+        for _ in 0..4 {
+            terrain.add_top_voxel(5, 3, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        }
+        let targets = [unit_at(2550, 1050)];
+
+        let hit = sweep(
+            coordinate(450, 1050, 1200),
+            coordinate(1, 0, 0),
+            GameUnit::from_num(9000),
+            Some(&terrain),
+            &targets,
+        );
+
+        match hit {
+            Some((distance, Impact::Terrain(voxel))) => {
+                assert_eq!(GameUnit::from_num(5 * VOXEL - 450), distance);
+                assert_eq!((5, 3, 4), voxel.voxel);
+            }
+            _ => panic!("expected to hit the voxel, got {:?}", hit),
+        }
+    }
+
+    #[test]
+    fn hitscan_projectile_hits_on_first_tick_or_expires() {
+        let terrain = flat_map(2);
+        let definition = ProjectileDefinition::hitscan(damage(), GameUnit::from_num(3000));
+        let targets = [unit_at(2550, 1050)];
+
+        let mut in_range =
+            Projectile::new(definition, coordinate(450, 1050, 1200), coordinate(1, 0, 0));
+        let mut out_of_range =
+            Projectile::new(definition, coordinate(450, 1050, 1200), coordinate(0, 1, 0));
+
+        assert_eq!(
+            Some(Impact::Target(0)),
+            step_projectile(&mut in_range, Some(&terrain), &targets).map(|h| h.impact)
+        );
+        assert_eq!(
+            Some(Impact::Expired),
+            step_projectile(&mut out_of_range, Some(&terrain), &targets).map(|h| h.impact)
+        );
+    }
+
+    #[test]
+    fn linear_projectile_hits_unit_on_tick_it_reaches_it() {
+        let terrain = flat_map(2);
+        let definition = ProjectileDefinition::linear(damage(), GameUnit::from_num(100), 100);
+        let mut projectile =
+            Projectile::new(definition, coordinate(450, 1050, 1200), coordinate(1, 0, 0));
+
+        let (hit, ticks) = fly(&mut projectile, &terrain, &[unit_at(2550, 1050)]);
+
+        // The unit's near face is 1850 away, reached during the nineteenth tick
+        assert_eq!(Impact::Target(0), hit.impact);
+        assert_eq!(19, ticks);
+        assert_eq!(GameUnit::from_num(2300), hit.position.x);
+    }
+
+    #[test]
+    fn fast_projectile_does_not_pass_through_thin_unit() {
+        let terrain = flat_map(2);
+        let definition = ProjectileDefinition::linear(damage(), GameUnit::from_num(1200), 100);
+        let mut projectile =
+            Projectile::new(definition, coordinate(450, 1050, 1200), coordinate(1, 0, 0));
+
+        let (hit, ticks) = fly(&mut projectile, &terrain, &[unit_at(1950, 1050)]);
+
+        assert_eq!(Impact::Target(0), hit.impact);
+        assert_eq!(2, ticks);
+    }
+
+    #[test]
+    fn projectile_without_terrain_only_hits_targets() {
+        let definition = ProjectileDefinition::linear(damage(), GameUnit::from_num(100), 100);
+        let mut projectile = Projectile::new(
+            definition,
+            coordinate(450, 1050, -1200),
+            coordinate(1, 0, 0),
+        );
+        let target = ProjectileTarget::new(coordinate(2300, 800, -2000), coordinate(2800, 1300, 0));
+
+        let mut hit = None;
+        while hit.is_none() {
+            hit = step_projectile(&mut projectile, None, &[target]);
+        }
+
+        assert_eq!(Some(Impact::Target(0)), hit.map(|h| h.impact));
+    }
+
+    #[test]
+    fn ballistic_projectile_arcs_onto_terrain() {
+        let terrain = flat_map(2);
+        let definition = ProjectileDefinition::ballistic(damage(), GameUnit::from_num(40), 1000);
+        let mut projectile =
+            Projectile::new(definition, coordinate(450, 450, 1200), coordinate(1, 0, 0));
+
+        let (hit, _) = fly(&mut projectile, &terrain, &[]);
+
+        match hit.impact {
+            Impact::Terrain(voxel) => assert_eq!((0, 0, 1), voxel.face),
+            _ => panic!("expected to land on the terrain, got {:?}", hit.impact),
+        }
+        assert_eq!(GameUnit::from_num(2 * VOXEL), hit.position.z.round());
+        assert_eq!(true, hit.position.x > GameUnit::from_num(1000));
+    }
+
+    #[test]
+    fn projectile_expires_at_end_of_lifetime() {
+        let terrain = flat_map(2);
+        let definition = ProjectileDefinition::linear(damage(), GameUnit::from_num(10), 30);
+        let mut projectile =
+            Projectile::new(definition, coordinate(450, 450, 1200), coordinate(0, 0, 1));
+
+        let (hit, ticks) = fly(&mut projectile, &terrain, &[]);
+
+        assert_eq!(Impact::Expired, hit.impact);
+        assert_eq!(30, ticks);
+        assert_eq!(GameUnit::from_num(1500), hit.position.z);
+    }
+
+    #[test]
+    fn identical_projectiles_stop_at_identical_positions() {
+        let terrain = flat_map(2);
+        let definition = ProjectileDefinition::ballistic(damage(), GameUnit::from_num(73), 1000);
+        let direction = Coordinate3d::new(
+            GameUnit::from_num(0.6),
+            GameUnit::from_num(0.48),
+            GameUnit::from_num(0.64),
+        );
+
+        let mut first = Projectile::new(definition, coordinate(450, 450, 1200), direction);
+        let mut second = first;
+
+        assert_eq!(
+            fly(&mut first, &terrain, &[unit_at(3000, 2500)]),
+            fly(&mut second, &terrain, &[unit_at(3000, 2500)])
+        );
+    }
+
+    #[test]
+    fn unit_target_stands_on_ground_height() {
+        let mut terrain = flat_map(2);
+        terrain.add_top_voxel(3, 3, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        let position = Coordinate2d::new(GameUnit::from_num(1050), GameUnit::from_num(1050));
+
+        let target = ProjectileTarget::unit(position, ground_height(&terrain, position));
+        let off_map = Coordinate2d::new(GameUnit::from_num(-10), GameUnit::from_num(1050));
+
+        assert_eq!(
+            unit_at(1050, 1050).max.z + GameUnit::from_num(VOXEL),
+            target.max.z
+        );
+        assert_eq!(
+            coordinate(1050, 1050, 3 * VOXEL + UNIT_HEIGHT / 2),
+            target.center()
+        );
+        assert_eq!(GameUnit::from_num(0), ground_height(&terrain, off_map));
+    }
+
+    #[test]
+    fn aimed_projectile_hits_unit_it_was_aimed_at() {
+        let terrain = flat_map(2);
+        let shooter = unit_at(450, 450).center();
+        let target = unit_at(1950, 1650);
+        let definition = ProjectileDefinition::linear(damage(), GameUnit::from_num(150), 100);
+
+        let direction = aim(shooter, target.center()).unwrap();
+        let mut projectile = Projectile::new(definition, shooter, direction);
+        let (hit, _) = fly(&mut projectile, &terrain, &[target]);
+
+        assert_eq!(Impact::Target(0), hit.impact);
+        assert_eq!(None, aim(shooter, shooter));
+    }
+
+    #[test]
+    fn blast_falloff_is_full_at_center_and_none_outside_radius() {
+        let target = unit_at(1050, 1050);
+        let radius = GameUnit::from_num(500);
+
+        let inside = blast_falloff(coordinate(1050, 1050, 1000), radius, target.min, target.max);
+        let halfway = blast_falloff(coordinate(1550, 1050, 1000), radius, target.min, target.max);
+        let outside = blast_falloff(coordinate(2050, 1050, 1000), radius, target.min, target.max);
+
+        assert_eq!(Some(GameUnit::from_num(1)), inside);
+        assert_eq!(Some(GameUnit::from_num(0.5)), halfway);
+        assert_eq!(None, outside);
+    }
+}
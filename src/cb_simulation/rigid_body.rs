@@ -0,0 +1,742 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Rigid bodies that fall, bounce off voxel terrain and push each other apart. Bodies move one axis at a time and
+    stop at the first voxel they touch, then every pair of bodies is checked for overlap and separated with an impulse.
+    Bodies that stay still for a while are put to sleep and skipped until something disturbs them. All math is done in
+    fixed point, or on the raw bits of fixed point values where products would overflow, so every peer agrees.
+*/
+
+use crate::cb_math;
+use cb_math::{from_bits, length, to_bits, ONE};
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+use cb_voxels::CbChunkManager;
+
+/// Downwards acceleration, in thousandths of a game unit per tick per tick. About 9.8m/s² at 60 ticks a second.
+pub const GRAVITY_MILLIS: i32 = 2722;
+
+/// Fastest a body can move along each axis, in game units per tick. Kept under a voxel so bodies never skip through one.
+const MAX_SPEED: i32 = 250;
+
+/// Bounces slower than this, in game units per tick, come to rest instead, so bodies settle rather than jitter.
+const REST_SPEED: i32 = 6;
+
+/// Bodies moving slower than this along every axis, in game units per tick, for SLEEP_TICKS ticks in a row fall asleep.
+const SLEEP_SPEED: i32 = 3;
+const SLEEP_TICKS: u32 = 30;
+
+/// Sleeping bodies overlapped by more than this, in game units, wake up.
+const WAKE_DEPTH: i32 = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Collider {
+    /// An axis aligned box, given by half its size along each axis.
+    Aabb {
+        half_extents: Coordinate3d,
+    },
+    Sphere {
+        radius: GameUnit,
+    },
+}
+
+impl Collider {
+    /// Half the size of the box around the collider, in raw bits.
+    fn half_extents(&self) -> [i64; 3] {
+        match self {
+            Collider::Aabb { half_extents } => to_bits(*half_extents),
+            Collider::Sphere { radius } => {
+                let r = radius.to_bits() as i64;
+                [r, r, r]
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RigidBody {
+    /// The center of the collider.
+    pub position: Coordinate3d,
+    /// In game units per tick.
+    pub velocity: Coordinate3d,
+    pub collider: Collider,
+    /// In kilograms. Bodies without mass are static; they never move but others collide with them.
+    pub mass: u32,
+    /// Percentage of speed kept when bouncing.
+    pub restitution: i32,
+    /// Percentage of sliding speed lost each tick while resting on something.
+    pub friction: i32,
+    pub uses_gravity: bool,
+    pub on_ground: bool,
+    pub sleeping: bool,
+    still_ticks: u32,
+}
+
+impl RigidBody {
+    pub fn new(position: Coordinate3d, collider: Collider, mass: u32) -> Self {
+        return Self {
+            position: position,
+            velocity: Coordinate3d::zero(),
+            collider: collider,
+            mass: mass,
+            restitution: 30,
+            friction: 20,
+            uses_gravity: true,
+            on_ground: false,
+            sleeping: false,
+            still_ticks: 0,
+        };
+    }
+
+    pub fn is_static(&self) -> bool {
+        return self.mass == 0;
+    }
+
+    /// Whether the body is simulated this tick.
+    pub fn is_awake(&self) -> bool {
+        return !self.is_static() && !self.sleeping;
+    }
+
+    /// The corners of the box around the body's collider.
+    pub fn bounds(&self) -> (Coordinate3d, Coordinate3d) {
+        let (c, h) = (to_bits(self.position), self.collider.half_extents());
+
+        return (
+            from_bits([c[0] - h[0], c[1] - h[1], c[2] - h[2]]),
+            from_bits([c[0] + h[0], c[1] + h[1], c[2] + h[2]]),
+        );
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.still_ticks = 0;
+    }
+
+    /// Change the body's velocity by the impulse, in kilogram game units per tick, waking it up.
+    pub fn apply_impulse(&mut self, impulse: Coordinate3d) {
+        if self.is_static() {
+            return;
+        }
+
+        let mass = GameUnit::from_num(self.mass);
+        self.velocity.x += impulse.x / mass;
+        self.velocity.y += impulse.y / mass;
+        self.velocity.z += impulse.z / mass;
+
+        self.wake();
+    }
+}
+
+/// Returns whether the collider at the position overlaps an active voxel or leaves the map. The map has walls around
+/// its edges and a floor below it, so bodies can never fall out.
+fn collides_with_terrain(terrain: &CbChunkManager, collider: &Collider, center: [i64; 3]) -> bool {
+    let half = collider.half_extents();
+    let min = [
+        center[0] - half[0],
+        center[1] - half[1],
+        center[2] - half[2],
+    ];
+    let max = [
+        center[0] + half[0],
+        center[1] + half[1],
+        center[2] + half[2],
+    ];
+
+    let map_size = terrain.get_voxel_width() as i64 * cb_voxels::VOXEL_GAME_SIZE as i64 * ONE;
+    if min[0] < 0 || min[1] < 0 || min[2] < 0 || max[0] > map_size || max[1] > map_size {
+        return true;
+    }
+
+    match collider {
+        Collider::Aabb { half_extents: _ } => {
+            let floor = |v: i64| v.div_euclid(ONE) as i32;
+            let ceil = |v: i64| (v + ONE - 1).div_euclid(ONE) as i32;
+
+            return terrain.box_collides(
+                (floor(min[0]), floor(min[1]), floor(min[2])),
+                (ceil(max[0]), ceil(max[1]), ceil(max[2])),
+            );
+        }
+        Collider::Sphere { radius } => {
+            let r = radius.to_bits() as i64;
+            let size = cb_voxels::VOXEL_GAME_SIZE as i64 * ONE;
+
+            for x in min[0].div_euclid(size)..=(max[0] - 1).div_euclid(size) {
+                for y in min[1].div_euclid(size)..=(max[1] - 1).div_euclid(size) {
+                    for z in min[2].div_euclid(size)..=(max[2] - 1).div_euclid(size) {
+                        if !terrain.is_solid(x as i32, y as i32, z as i32) {
+                            continue;
+                        }
+
+                        // Distance from the center to the closest point of the voxel
+                        let voxel = [x, y, z];
+                        let mut squared = 0;
+                        for axis in 0..3 {
+                            let closest = center[axis]
+                                .max(voxel[axis] * size)
+                                .min((voxel[axis] + 1) * size);
+                            let d = center[axis] - closest;
+                            squared += d * d;
+                        }
+
+                        if squared < r * r {
+                            return true;
+                        }
+                    }
+                }
+            }
+
+            return false;
+        }
+    }
+}
+
+/// Move the body along the axis as far as it can go without entering the terrain. Returns whether it was blocked.
+fn sweep_axis(body: &mut RigidBody, terrain: &CbChunkManager, axis: usize, distance: i64) -> bool {
+    let start = to_bits(body.position);
+    let moved_by = |offset: i64| {
+        let mut moved = start;
+        moved[axis] += offset;
+        return moved;
+    };
+
+    if !collides_with_terrain(terrain, &body.collider, moved_by(distance)) {
+        body.position = from_bits(moved_by(distance));
+        return false;
+    }
+
+    // Search for the furthest free position between where the body is and where it was blocked
+    let mut free = 0;
+    let mut blocked = distance;
+    while (blocked - free).abs() > 1 {
+        let middle = free + (blocked - free) / 2;
+
+        if collides_with_terrain(terrain, &body.collider, moved_by(middle)) {
+            blocked = middle;
+        } else {
+            free = middle;
+        }
+    }
+
+    body.position = from_bits(moved_by(free));
+
+    return true;
+}
+
+/// Reverse the velocity along the axis, losing speed based on the body's restitution.
+fn bounce(body: &mut RigidBody, axis: usize) {
+    let mut velocity = to_bits(body.velocity);
+    velocity[axis] = -velocity[axis] * body.restitution as i64 / 100;
+
+    if velocity[axis].abs() < REST_SPEED as i64 * ONE {
+        velocity[axis] = 0;
+    }
+
+    body.velocity = from_bits(velocity);
+}
+
+/// Apply gravity and move the body by its velocity, colliding with the terrain.
+fn integrate(body: &mut RigidBody, terrain: &CbChunkManager) {
+    if body.uses_gravity {
+        body.velocity.z -= GameUnit::from_num(GRAVITY_MILLIS) / 1000;
+    }
+
+    let max_speed = MAX_SPEED as i64 * ONE;
+    let mut velocity = to_bits(body.velocity);
+    for axis in 0..3 {
+        velocity[axis] = velocity[axis].max(-max_speed).min(max_speed);
+    }
+    body.velocity = from_bits(velocity);
+
+    body.on_ground = false;
+
+    for axis in 0..3 {
+        if velocity[axis] == 0 {
+            continue;
+        }
+
+        if sweep_axis(body, terrain, axis, velocity[axis]) {
+            if axis == 2 && velocity[axis] < 0 {
+                body.on_ground = true;
+            }
+
+            bounce(body, axis);
+        }
+    }
+}
+
+/// A contact between two overlapping bodies. The normal is of unit length, in raw bits, and points from the first
+/// body towards the second; the depth is how far they overlap along it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Contact {
+    normal: [i64; 3],
+    depth: i64,
+}
+
+/// Returns the contact between the bodies, if they overlap.
+fn find_contact(a: &RigidBody, b: &RigidBody) -> Option<Contact> {
+    let (pa, pb) = (to_bits(a.position), to_bits(b.position));
+    let d = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+
+    match (a.collider, b.collider) {
+        (Collider::Sphere { radius: ra }, Collider::Sphere { radius: rb }) => {
+            let radii = ra.to_bits() as i64 + rb.to_bits() as i64;
+            let distance = length(d);
+
+            if distance >= radii {
+                return None;
+            }
+
+            let normal = if distance == 0 {
+                [0, 0, ONE]
+            } else {
+                [
+                    d[0] * ONE / distance,
+                    d[1] * ONE / distance,
+                    d[2] * ONE / distance,
+                ]
+            };
+
+            return Some(Contact {
+                normal: normal,
+                depth: radii - distance,
+            });
+        }
+        (Collider::Aabb { half_extents: _ }, Collider::Sphere { radius }) => {
+            let half = a.collider.half_extents();
+            let r = radius.to_bits() as i64;
+
+            // From the closest point of the box to the sphere's center
+            let mut offset = [0; 3];
+            for axis in 0..3 {
+                offset[axis] = d[axis] - d[axis].max(-half[axis]).min(half[axis]);
+            }
+            let distance = length(offset);
+
+            if distance >= r {
+                return None;
+            }
+
+            if distance > 0 {
+                return Some(Contact {
+                    normal: [
+                        offset[0] * ONE / distance,
+                        offset[1] * ONE / distance,
+                        offset[2] * ONE / distance,
+                    ],
+                    depth: r - distance,
+                });
+            }
+
+            // The center is inside the box, so push out along the shallowest axis
+            return shallowest_axis_contact(d, half, [r, r, r]);
+        }
+        (Collider::Sphere { radius: _ }, Collider::Aabb { half_extents: _ }) => {
+            return find_contact(b, a).map(|contact| Contact {
+                normal: [-contact.normal[0], -contact.normal[1], -contact.normal[2]],
+                depth: contact.depth,
+            });
+        }
+        (Collider::Aabb { half_extents: _ }, Collider::Aabb { half_extents: _ }) => {
+            return shallowest_axis_contact(
+                d,
+                a.collider.half_extents(),
+                b.collider.half_extents(),
+            );
+        }
+    }
+}
+
+/// The contact between two boxes with the given offset between their centers, along the axis they overlap least on.
+fn shallowest_axis_contact(d: [i64; 3], half_a: [i64; 3], half_b: [i64; 3]) -> Option<Contact> {
+    let mut contact: Option<Contact> = None;
+
+    for axis in 0..3 {
+        let overlap = half_a[axis] + half_b[axis] - d[axis].abs();
+
+        if overlap <= 0 {
+            return None;
+        }
+
+        if contact.map_or(true, |c| overlap < c.depth) {
+            let mut normal = [0; 3];
+            normal[axis] = if d[axis] < 0 { -ONE } else { ONE };
+
+            contact = Some(Contact {
+                normal: normal,
+                depth: overlap,
+            });
+        }
+    }
+
+    return contact;
+}
+
+/// Push two overlapping bodies apart and stop them moving into each other. Heavier bodies are moved less; static
+/// bodies are not moved at all. Bodies are never pushed into the terrain.
+fn resolve_contact(
+    a: &mut RigidBody,
+    b: &mut RigidBody,
+    contact: &Contact,
+    terrain: &CbChunkManager,
+) {
+    let n = contact.normal;
+
+    // How much of the correction each body takes, over the denominator
+    let (share_a, share_b, denominator) = match (a.is_static(), b.is_static()) {
+        (true, _) => (0, 1, 1),
+        (_, true) => (1, 0, 1),
+        _ => (b.mass as i64, a.mass as i64, (a.mass + b.mass) as i64),
+    };
+
+    // Separate. A body that cannot move without entering the terrain leaves its share to the other.
+    let depth = contact.depth;
+    let moved_a = share_a > 0 && push(a, terrain, n, -depth * share_a / denominator);
+    let moved_b = share_b > 0 && push(b, terrain, n, depth * share_b / denominator);
+
+    if share_a > 0 && !moved_a && moved_b {
+        push(b, terrain, n, depth * share_a / denominator);
+    } else if share_b > 0 && !moved_b && moved_a {
+        push(a, terrain, n, -depth * share_b / denominator);
+    }
+
+    // Bodies resting on top of others are on the ground, so they feel friction and may fall asleep
+    if n[2] > ONE / 2 {
+        b.on_ground = true;
+    } else if n[2] < -ONE / 2 {
+        a.on_ground = true;
+    }
+
+    // Bounce, if they are moving towards each other
+    let (va, vb) = (to_bits(a.velocity), to_bits(b.velocity));
+    let approach = ((vb[0] - va[0]) * n[0] + (vb[1] - va[1]) * n[1] + (vb[2] - va[2]) * n[2]) / ONE;
+
+    if approach >= 0 {
+        return;
+    }
+
+    let restitution = a.restitution.min(b.restitution) as i64;
+    let change = -approach * (100 + restitution) / 100;
+
+    let mut va = va;
+    let mut vb = vb;
+    for axis in 0..3 {
+        va[axis] -= n[axis] * change / ONE * share_a / denominator;
+        vb[axis] += n[axis] * change / ONE * share_b / denominator;
+    }
+
+    a.velocity = from_bits(va);
+    b.velocity = from_bits(vb);
+}
+
+/// Move the body the distance along the normal, unless that would put it in the terrain. Returns whether it moved.
+fn push(body: &mut RigidBody, terrain: &CbChunkManager, normal: [i64; 3], distance: i64) -> bool {
+    let mut position = to_bits(body.position);
+    for axis in 0..3 {
+        position[axis] += normal[axis] * distance / ONE;
+    }
+
+    if collides_with_terrain(terrain, &body.collider, position) {
+        return false;
+    }
+
+    body.position = from_bits(position);
+
+    return true;
+}
+
+/// Returns whether the body is resting on the terrain or another body.
+fn is_supported(index: usize, bodies: &[RigidBody], terrain: &CbChunkManager) -> bool {
+    let mut lowered = bodies[index];
+    lowered.position.z -= GameUnit::from_num(1);
+
+    if collides_with_terrain(terrain, &lowered.collider, to_bits(lowered.position)) {
+        return true;
+    }
+
+    return bodies
+        .iter()
+        .enumerate()
+        .any(|(i, other)| i != index && find_contact(&lowered, other).is_some());
+}
+
+/// Advance the bodies by a tick. Bodies are processed in order, so the same bodies in the same order always end up in
+/// the same place.
+pub fn step_bodies(bodies: &mut [RigidBody], terrain: &CbChunkManager) {
+    for body in bodies.iter_mut() {
+        if body.is_awake() {
+            integrate(body, terrain);
+        }
+    }
+
+    // Collide every pair with a body awake. Sleeping bodies are also checked against static ones, since characters
+    // are static bodies that move by other means and should still wake what they walk into.
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let (left, right) = bodies.split_at_mut(j);
+            let (a, b) = (&mut left[i], &mut right[0]);
+
+            if !a.is_awake() && !b.is_awake() && !(a.is_static() ^ b.is_static()) {
+                continue;
+            }
+
+            let contact = match find_contact(a, b) {
+                Some(contact) => contact,
+                None => {
+                    continue;
+                }
+            };
+
+            // Sleeping bodies are woken when something runs into them or overlaps them noticeably
+            let (va, vb) = (to_bits(a.velocity), to_bits(b.velocity));
+            let n = contact.normal;
+            let approach =
+                ((vb[0] - va[0]) * n[0] + (vb[1] - va[1]) * n[1] + (vb[2] - va[2]) * n[2]) / ONE;
+            let disturbed =
+                approach < -(SLEEP_SPEED as i64) * ONE || contact.depth > WAKE_DEPTH as i64 * ONE;
+
+            for body in [&mut *a, &mut *b].iter_mut() {
+                if body.sleeping && disturbed {
+                    body.wake();
+                }
+            }
+
+            if a.is_awake() || b.is_awake() {
+                resolve_contact(a, b, &contact, terrain);
+            }
+        }
+    }
+
+    // Friction and sleeping
+    for i in 0..bodies.len() {
+        if bodies[i].is_static() {
+            continue;
+        }
+
+        if bodies[i].sleeping {
+            if bodies[i].uses_gravity && !is_supported(i, bodies, terrain) {
+                bodies[i].wake();
+            }
+            continue;
+        }
+
+        let body = &mut bodies[i];
+
+        if body.on_ground {
+            let kept = GameUnit::from_num(100 - body.friction) / 100;
+            body.velocity.x *= kept;
+            body.velocity.y *= kept;
+        }
+
+        let slow = to_bits(body.velocity)
+            .iter()
+            .all(|v| v.abs() <= SLEEP_SPEED as i64 * ONE);
+
+        if slow && (body.on_ground || !body.uses_gravity) {
+            body.still_ticks += 1;
+        } else {
+            body.still_ticks = 0;
+        }
+
+        if body.still_ticks >= SLEEP_TICKS {
+            body.sleeping = true;
+            body.velocity = Coordinate3d::zero();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cb_voxels::{coordinate, flat_map};
+
+    const VOXEL: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+    fn cube(position: Coordinate3d, half_size: i32) -> RigidBody {
+        let half_extents = coordinate(half_size, half_size, half_size);
+
+        return RigidBody::new(position, Collider::Aabb { half_extents }, 10);
+    }
+
+    fn ball(position: Coordinate3d, radius: i32) -> RigidBody {
+        let radius = GameUnit::from_num(radius);
+
+        return RigidBody::new(position, Collider::Sphere { radius }, 10);
+    }
+
+    fn weightless(mut body: RigidBody) -> RigidBody {
+        body.uses_gravity = false;
+        return body;
+    }
+
+    fn run(bodies: &mut [RigidBody], terrain: &CbChunkManager, ticks: usize) {
+        for _ in 0..ticks {
+            step_bodies(bodies, terrain);
+        }
+    }
+
+    #[test]
+    fn step_bodies_falling_box_lands_on_terrain() {
+        let terrain = flat_map(2);
+        let mut bodies = [cube(coordinate(1500, 1500, 2000), 100)];
+
+        run(&mut bodies, &terrain, 120);
+
+        assert_eq!(true, bodies[0].on_ground || bodies[0].sleeping);
+        assert_eq!(
+            GameUnit::from_num(2 * VOXEL + 100),
+            bodies[0].position.z.round()
+        );
+    }
+
+    #[test]
+    fn step_bodies_bouncy_ball_bounces_lower_each_time() {
+        let terrain = flat_map(2);
+        let mut body = ball(coordinate(1500, 1500, 2000), 100);
+        body.restitution = 60;
+        let mut bodies = [body];
+
+        // Record the highest point between each bounce
+        let mut peaks = vec![];
+        let mut highest = GameUnit::from_num(0);
+        let mut rising = false;
+        for _ in 0..400 {
+            step_bodies(&mut bodies, &terrain);
+            let z = bodies[0].position.z;
+
+            if bodies[0].velocity.z > 0 {
+                rising = true;
+                highest = highest.max(z);
+            } else if rising {
+                peaks.push(highest);
+                rising = false;
+                highest = GameUnit::from_num(0);
+            }
+        }
+
+        assert_eq!(true, peaks.len() >= 2);
+        assert_eq!(true, peaks[1] < peaks[0]);
+        assert_eq!(true, peaks[0] < GameUnit::from_num(2000));
+    }
+
+    #[test]
+    fn step_bodies_fast_box_stops_at_voxel_wall() {
+        let mut terrain = flat_map(2);
+        for y in 0..terrain.get_voxel_width() {
+            terrain.add_top_voxel(8, y, cb_voxels::VOXEL_TYPE_DIRT, 0);
+        }
+        let mut body = weightless(cube(coordinate(1500, 1500, 2 * VOXEL + 150), 100));
+        body.restitution = 0;
+        body.velocity.x = GameUnit::from_num(1000);
+        let mut bodies = [body];
+
+        run(&mut bodies, &terrain, 30);
+
+        assert_eq!(
+            GameUnit::from_num(8 * VOXEL - 100),
+            bodies[0].position.x.round()
+        );
+    }
+
+    #[test]
+    fn step_bodies_resting_body_falls_asleep_and_wakes_on_impulse() {
+        let terrain = flat_map(2);
+        let mut bodies = [cube(coordinate(1500, 1500, 2 * VOXEL + 100), 100)];
+
+        run(&mut bodies, &terrain, SLEEP_TICKS as usize + 5);
+        assert_eq!(true, bodies[0].sleeping);
+
+        bodies[0].apply_impulse(coordinate(0, 0, 500));
+        run(&mut bodies, &terrain, 1);
+
+        assert_eq!(false, bodies[0].sleeping);
+        assert_eq!(
+            true,
+            bodies[0].position.z > GameUnit::from_num(2 * VOXEL + 100)
+        );
+    }
+
+    #[test]
+    fn step_bodies_sleeping_body_wakes_when_ground_is_removed() {
+        let mut terrain = flat_map(2);
+        let mut bodies = [cube(coordinate(1650, 1650, 2 * VOXEL + 100), 100)];
+        run(&mut bodies, &terrain, SLEEP_TICKS as usize + 5);
+        assert_eq!(true, bodies[0].sleeping);
+
+        terrain.remove_top_voxel(5, 5, 0);
+        run(&mut bodies, &terrain, 60);
+
+        assert_eq!(
+            GameUnit::from_num(VOXEL + 100),
+            bodies[0].position.z.round()
+        );
+    }
+
+    #[test]
+    fn step_bodies_equal_balls_exchange_velocities_head_on() {
+        let terrain = flat_map(2);
+        let mut a = weightless(ball(coordinate(1000, 1500, 2000), 100));
+        let mut b = weightless(ball(coordinate(1300, 1500, 2000), 100));
+        a.restitution = 100;
+        b.restitution = 100;
+        a.velocity.x = GameUnit::from_num(20);
+        let mut bodies = [a, b];
+
+        run(&mut bodies, &terrain, 10);
+
+        assert_eq!(true, bodies[0].velocity.x.abs() < GameUnit::from_num(1));
+        assert_eq!(true, bodies[1].velocity.x > GameUnit::from_num(19));
+    }
+
+    #[test]
+    fn step_bodies_ball_bounces_off_static_box() {
+        let terrain = flat_map(2);
+        let mut wall = cube(coordinate(1500, 1500, 2000), 100);
+        wall.mass = 0;
+        let mut thrown = weightless(ball(coordinate(1500, 1500, 2400), 100));
+        thrown.restitution = 100;
+        thrown.velocity.z = GameUnit::from_num(-50);
+        let mut bodies = [wall, thrown];
+
+        run(&mut bodies, &terrain, 10);
+
+        assert_eq!(coordinate(1500, 1500, 2000), bodies[0].position);
+        assert_eq!(true, bodies[1].velocity.z > 0);
+        assert_eq!(true, bodies[1].position.z >= GameUnit::from_num(2200));
+    }
+
+    #[test]
+    fn step_bodies_box_stacks_on_box() {
+        let terrain = flat_map(2);
+        let mut bodies = [
+            cube(coordinate(1500, 1500, 2 * VOXEL + 100), 100),
+            cube(coordinate(1500, 1500, 1200), 100),
+        ];
+
+        run(&mut bodies, &terrain, 200);
+
+        assert_eq!(
+            GameUnit::from_num(2 * VOXEL + 300),
+            bodies[1].position.z.round()
+        );
+        assert_eq!(true, bodies[0].sleeping && bodies[1].sleeping);
+    }
+
+    #[test]
+    fn find_contact_sphere_touching_box_face_pushes_along_face_normal() {
+        let box_body = cube(coordinate(0, 0, 0), 100);
+        let sphere = ball(coordinate(0, 0, 150), 100);
+
+        let contact = find_contact(&box_body, &sphere).unwrap();
+
+        assert_eq!([0, 0, ONE], contact.normal);
+        assert_eq!(50 * ONE, contact.depth);
+        assert_eq!(
+            [0, 0, -ONE],
+            find_contact(&sphere, &box_body).unwrap().normal
+        );
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Finding what the local player's cursor points at, so their RTS presses can be turned into commands. This only reads
+    the world from the last rendered camera and isn't part of a tick; just the commands it leads to are networked.
+*/
+
+extern crate nalgebra as na;
+use na::{Point3, Vector3};
+
+use specs::prelude::*;
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+use cb_voxels::CbChunkManager;
+
+use crate::cb_graphics;
+use cb_graphics::CbCamera;
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::{CbRtsTarget, CbRtsTargetUnit, UnitId};
+use cb_input::input_type::Range;
+
+use super::components;
+use super::fog_of_war::{self, CbVisibility};
+use super::match_state::{CbMatch, MatchPhase};
+use super::teams::CbTeams;
+
+/// How far from a unit's position the cursor may point on the ground and still point at it, in game units.
+pub const PICK_RADIUS: i32 = cb_voxels::VOXEL_GAME_SIZE;
+
+/// How far the cursor's ray looks for the ground, in game units.
+const MAX_GROUND_DISTANCE: i32 = 100000;
+
+/// The ray from the camera through the cursor, as (origin, direction) in game units.
+pub fn cursor_ray(
+    camera: &CbCamera,
+    cursor_x: Range,
+    cursor_y: Range,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let normalize = |cursor: Range| {
+        let span = (cursor.max() - cursor.min()).max(1) as f32;
+
+        return (cursor.value() - cursor.min()) as f32 / span * 2.0 - 1.0;
+    };
+
+    // The cursor's y goes down the screen
+    let (x, y) = (normalize(cursor_x), -normalize(cursor_y));
+
+    let (proj, view) = camera.proj_view();
+    let inverse = (proj * view).try_inverse()?;
+
+    let near = inverse.transform_point(&Point3::new(x, y, -1.0)).coords;
+    let far = inverse.transform_point(&Point3::new(x, y, 1.0)).coords;
+
+    let to_game_units = cb_voxels::VOXEL_GAME_SIZE as f32 / cb_voxels::VOXEL_SIZE;
+    let direction = (far - near).try_normalize(std::f32::EPSILON)?;
+
+    return Some((near * to_game_units, direction));
+}
+
+/// The nearest of the units, as (unit, position), within the pick radius of the point on the ground.
+pub fn pick_unit(units: &[(UnitId, (i32, i32))], point: (i32, i32)) -> Option<UnitId> {
+    let mut nearest: Option<(i64, UnitId)> = None;
+
+    for (unit, position) in units.iter() {
+        let dx = (position.0 - point.0) as i64;
+        let dy = (position.1 - point.1) as i64;
+        let distance_squared = dx * dx + dy * dy;
+
+        if distance_squared > PICK_RADIUS as i64 * PICK_RADIUS as i64 {
+            continue;
+        }
+
+        if nearest.is_none() || distance_squared < nearest.unwrap().0 {
+            nearest = Some((distance_squared, *unit));
+        }
+    }
+
+    return nearest.map(|(_, unit)| unit);
+}
+
+/// Where the ray meets the voxels, or the ground plane without any, in game units.
+pub fn ground_point(
+    chunk_manager: Option<&CbChunkManager>,
+    origin: &Vector3<f32>,
+    direction: &Vector3<f32>,
+) -> Option<(i32, i32)> {
+    if let Some(chunk_manager) = chunk_manager {
+        let to_coordinate = |v: &Vector3<f32>| {
+            Coordinate3d::new(
+                GameUnit::from_num(v.x),
+                GameUnit::from_num(v.y),
+                GameUnit::from_num(v.z),
+            )
+        };
+
+        let hit = chunk_manager.raycast(
+            to_coordinate(origin),
+            to_coordinate(direction),
+            GameUnit::from_num(MAX_GROUND_DISTANCE),
+        );
+
+        if let Some(hit) = hit {
+            let point = origin + direction * hit.distance.to_num::<f32>();
+
+            return Some((point.x.round() as i32, point.y.round() as i32));
+        }
+    }
+
+    if direction.z >= 0.0 {
+        return None;
+    }
+
+    let point = origin + direction * (-origin.z / direction.z);
+
+    return Some((point.x.round() as i32, point.y.round() as i32));
+}
+
+/// What the player's cursor points at through the camera. Units the player's team can't see aren't pointed at.
+pub fn get_rts_target(
+    world: &World,
+    camera: &CbCamera,
+    player_id: usize,
+    cursor_x: Range,
+    cursor_y: Range,
+) -> CbRtsTarget {
+    let in_lobby = world.read_resource::<CbMatch>().phase() == MatchPhase::Lobby;
+
+    let (origin, direction) = match cursor_ray(camera, cursor_x, cursor_y) {
+        Some(ray) => ray,
+        None => {
+            return CbRtsTarget {
+                in_lobby: in_lobby,
+                point: None,
+                unit: None,
+            };
+        }
+    };
+
+    let entities = world.entities();
+    let teams = world.read_resource::<CbTeams>();
+    let visibility = world.read_resource::<CbVisibility>();
+    let transforms = world.read_storage::<components::physics_components::TransformComponent>();
+    let ownerships = world.read_storage::<components::ownership_components::OwnershipComponent>();
+    let nodes = world.read_storage::<components::economy_components::ResourceNodeComponent>();
+    let voxels = world.read_storage::<components::voxel_components::VoxelComponent>();
+
+    let mut units = vec![];
+    for (entity, transform, ownership, node) in
+        (&entities, &transforms, ownerships.maybe(), nodes.maybe()).join()
+    {
+        let owned = ownership.map_or(false, |o| o.player_id == player_id);
+        if !owned && ownership.is_none() && node.is_none() {
+            continue;
+        }
+
+        let position = transform.world_position;
+        let (cell_x, cell_y) =
+            fog_of_war::world_to_cell(position.x.to_num::<i32>(), position.y.to_num::<i32>());
+        if !owned && !visibility.is_visible_to_player(&teams, player_id, cell_x, cell_y) {
+            continue;
+        }
+
+        units.push((
+            entity.id(),
+            (position.x.to_num::<i32>(), position.y.to_num::<i32>()),
+            owned,
+            node.is_some(),
+        ));
+    }
+
+    let chunk_manager = (&voxels).join().next().map(|voxel| &voxel.chunk_manager);
+    let point = ground_point(chunk_manager, &origin, &direction);
+
+    let positions: Vec<(UnitId, (i32, i32))> = units.iter().map(|u| (u.0, u.1)).collect();
+    let unit = point
+        .and_then(|point| pick_unit(&positions, point))
+        .and_then(|id| units.iter().find(|u| u.0 == id))
+        .map(|u| CbRtsTargetUnit {
+            id: u.0,
+            owned: u.2,
+            is_resource_node: u.3,
+        });
+
+    return CbRtsTarget {
+        in_lobby: in_lobby,
+        point: point,
+        unit: unit,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_unit_returns_nearest_unit_within_radius() {
+        let units = vec![(1, (0, 0)), (2, (1000, 0)), (3, (1200, 100))];
+
+        assert_eq!(Some(3), pick_unit(&units, (1250, 50)));
+        assert_eq!(Some(1), pick_unit(&units, (0, PICK_RADIUS)));
+        assert_eq!(None, pick_unit(&units, (500, 0)));
+    }
+
+    #[test]
+    fn ground_point_without_voxels_hits_ground_plane() {
+        let origin = Vector3::new(0.0, 0.0, 1000.0);
+        let direction = Vector3::new(1.0, 0.0, -1.0).normalize();
+
+        assert_eq!(Some((1000, 0)), ground_point(None, &origin, &direction));
+        assert_eq!(None, ground_point(None, &origin, &-direction));
+    }
+}
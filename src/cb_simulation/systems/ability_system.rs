@@ -0,0 +1,259 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::abilities::{
+    affects_hit_points, tick_abilities, try_activate, units_in_radius, voxel_columns,
+    AbilityDefinitions, AbilityEffect, AbilityId, AbilityTargeting,
+};
+use cb_simulation::components;
+use cb_simulation::economy::{CbSpawnQueue, SpawnRequest};
+use cb_simulation::teams::CbTeams;
+use cb_simulation::unit_definitions::UnitDefinitions;
+use cb_simulation::CbSystemValues;
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::{get_rts_commands, CbAbilityTarget, CbRtsCommand};
+use cb_input::contexts::RTS_COMMAND_CONTEXT_ID;
+
+use crate::cb_math;
+use cb_math::FUint;
+
+use crate::cb_system;
+use cb_system::Coordinate2d;
+
+use specs::prelude::*;
+
+/// How often units regain energy.
+pub const ENERGY_REGEN_TICKS: usize = 60;
+
+struct Activation {
+    ability: AbilityId,
+    player_id: usize,
+    center: Coordinate2d,
+    /// The targeted unit for unit abilities, or the caster for self and passive abilities.
+    target: Option<Entity>,
+}
+
+/// Tick cooldowns and energy, activate passive and commanded abilities, then apply their effects.
+pub struct AbilitySystem;
+
+impl<'a> System<'a> for AbilitySystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, CbSystemValues>,
+        Read<'a, AbilityDefinitions>,
+        Read<'a, UnitDefinitions>,
+        Read<'a, CbTeams>,
+        Write<'a, CbSpawnQueue>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        WriteStorage<'a, components::ability_components::AbilitiesComponent>,
+        WriteStorage<'a, components::character_components::HitPointsComponent>,
+        WriteStorage<'a, components::voxel_components::VoxelComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            sys_values,
+            ability_definitions,
+            unit_definitions,
+            teams,
+            mut spawn_queue,
+            ownerships,
+            transforms,
+            mut abilities,
+            mut hit_points,
+            mut voxels,
+        ): Self::SystemData,
+    ) {
+        let mut activations: Vec<Activation> = vec![];
+
+        // Cooldowns, energy and passives
+        let regen_energy = sys_values.frame % ENERGY_REGEN_TICKS == 0;
+
+        for (entity, ownership, transform, unit_abilities) in
+            (&entities, &ownerships, &transforms, &mut abilities).join()
+        {
+            let passives = tick_abilities(unit_abilities, &ability_definitions, regen_energy);
+
+            for ability in passives.iter() {
+                activations.push(Activation {
+                    ability: *ability,
+                    player_id: ownership.player_id,
+                    center: transform.world_position,
+                    target: Some(entity),
+                });
+            }
+        }
+
+        // Commanded abilities, processing players in a fixed order
+        let mut inputs: Vec<&cb_input::CbGameInput> = sys_values.world_inputs.iter().collect();
+        inputs.sort_by_key(|i| i.player_id);
+
+        for input in inputs.iter() {
+            let context = match input.context_manager.get_context(RTS_COMMAND_CONTEXT_ID) {
+                Some(context) => context,
+                None => {
+                    continue;
+                }
+            };
+
+            for command in get_rts_commands(&context).iter() {
+                if let CbRtsCommand::ActivateAbility { unit, slot, target } = *command {
+                    let caster = entities.entity(unit);
+                    if !entities.is_alive(caster) {
+                        continue;
+                    }
+
+                    match ownerships.get(caster) {
+                        Some(ownership) if ownership.player_id == input.player_id => {}
+                        _ => {
+                            continue;
+                        }
+                    }
+
+                    let caster_position = match transforms.get(caster) {
+                        Some(transform) => transform.world_position,
+                        None => {
+                            continue;
+                        }
+                    };
+
+                    let target_unit = match target {
+                        CbAbilityTarget::Unit(target_unit) => {
+                            Some(entities.entity(target_unit)).filter(|e| entities.is_alive(*e))
+                        }
+                        _ => None,
+                    };
+                    let target_unit_position =
+                        target_unit.and_then(|e| transforms.get(e).map(|t| t.world_position));
+
+                    let unit_abilities = match abilities.get_mut(caster) {
+                        Some(unit_abilities) => unit_abilities,
+                        None => {
+                            continue;
+                        }
+                    };
+
+                    let activated = try_activate(
+                        unit_abilities,
+                        slot as usize,
+                        &ability_definitions,
+                        caster_position,
+                        target,
+                        target_unit_position,
+                    );
+
+                    if let Some((ability, center)) = activated {
+                        // Effects without a radius apply to the targeted unit, or the caster
+                        let target_entity = match ability_definitions.get_by_id(ability) {
+                            Some(d) if d.targeting == AbilityTargeting::SelfTarget => Some(caster),
+                            Some(d) if d.targeting == AbilityTargeting::Unit => target_unit,
+                            _ => None,
+                        };
+
+                        activations.push(Activation {
+                            ability: ability,
+                            player_id: input.player_id,
+                            center: center,
+                            target: target_entity,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Effects
+        for activation in activations.iter() {
+            let definition = ability_definitions.get_by_id(activation.ability).unwrap();
+
+            for effect in definition.effects.iter() {
+                match effect {
+                    AbilityEffect::Damage { amount, radius }
+                    | AbilityEffect::Heal { amount, radius } => {
+                        let units: Vec<(Entity, Coordinate2d)> = (&entities, &transforms)
+                            .join()
+                            .map(|(e, t)| (e, t.world_position))
+                            .collect();
+
+                        let affected =
+                            units_in_radius(&units, activation.center, *radius, activation.target);
+
+                        for entity in affected.iter() {
+                            let allied = match ownerships.get(*entity) {
+                                Some(o) => {
+                                    teams.are_players_allied(activation.player_id, o.player_id)
+                                }
+                                None => false,
+                            };
+
+                            if !affects_hit_points(effect, allied) {
+                                continue;
+                            }
+
+                            if let Some(hp) = hit_points.get_mut(*entity) {
+                                match effect {
+                                    AbilityEffect::Heal {
+                                        amount: _,
+                                        radius: _,
+                                    } => hp.heal(FUint::from_num(*amount)),
+                                    _ => hp.apply_damage(FUint::from_num(*amount)),
+                                }
+                            }
+                        }
+                    }
+                    AbilityEffect::Spawn { unit, count } => {
+                        let unit_type = match unit_definitions.id_of(unit) {
+                            Some(unit_type) => unit_type,
+                            None => {
+                                continue;
+                            }
+                        };
+
+                        for _ in 0..*count {
+                            spawn_queue.requests.push(SpawnRequest {
+                                unit_type: unit_type,
+                                position: activation.center,
+                                player_id: activation.player_id,
+                            });
+                        }
+                    }
+                    AbilityEffect::VoxelDig { radius }
+                    | AbilityEffect::VoxelBuild {
+                        radius,
+                        voxel_type: _,
+                    } => {
+                        for voxel in (&mut voxels).join() {
+                            let width = voxel.chunk_manager.get_voxel_width() as i32;
+
+                            for (x, y) in voxel_columns(activation.center, *radius, width).iter() {
+                                match effect {
+                                    AbilityEffect::VoxelBuild {
+                                        radius: _,
+                                        voxel_type,
+                                    } => {
+                                        voxel.chunk_manager.add_top_voxel(
+                                            *x,
+                                            *y,
+                                            *voxel_type,
+                                            sys_values.frame,
+                                        );
+                                    }
+                                    _ => {
+                                        voxel.chunk_manager.remove_top_voxel(
+                                            *x,
+                                            *y,
+                                            sys_values.frame,
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
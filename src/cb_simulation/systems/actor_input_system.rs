@@ -3,9 +3,9 @@ use cb_simulation::components;
 use cb_simulation::CbSystemValues;
 
 use specs::prelude::*;
-pub struct ActorInputSystem;
 
-use crate::cb_menu;
+/// Record each actor's player inputs for the tick, so later systems read them from the actor's controller.
+pub struct ActorInputSystem;
 
 use crate::cb_input;
 
@@ -24,10 +24,10 @@ impl<'a> System<'a> for ActorInputSystem {
                 .filter(|i| actor_component.player_id == i.player_id)
                 .collect();
 
-            // Add inputs to actors
-            for input in actor_inputs.iter() {
-                actor_component.inputs.push(**input);
-            }
+            // Every tick is recorded, even without inputs, so the controller's history is measured in ticks
+            actor_component
+                .controller
+                .push(sys_values.frame, &actor_inputs);
         }
     }
 }
@@ -0,0 +1,181 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::ai::{self, AiUnit, AiView, CbAiPlayers, AI_THINK_TICKS};
+use cb_simulation::components;
+use cb_simulation::economy::CbEconomy;
+use cb_simulation::fog_of_war::{self, CbVisibility, FogState};
+use cb_simulation::match_state::{CbMatch, MatchPhase};
+use cb_simulation::teams::CbTeams;
+use cb_simulation::unit_definitions::UnitDefinitions;
+
+use components::economy_components::HarvestOrder;
+use components::order_components::UnitOrder;
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::CbRtsCommand;
+
+use specs::prelude::*;
+
+/// Build what each AI player can see of the world and have it decide on its inputs for the tick.
+pub struct AiSystem;
+
+impl<'a> System<'a> for AiSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, CbMatch>,
+        Read<'a, CbTeams>,
+        Read<'a, CbVisibility>,
+        Read<'a, CbEconomy>,
+        Read<'a, UnitDefinitions>,
+        Write<'a, CbAiPlayers>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        ReadStorage<'a, components::character_components::HitPointsComponent>,
+        ReadStorage<'a, components::character_components::MoveSpeedComponent>,
+        ReadStorage<'a, components::character_components::RangedAttackComponent>,
+        ReadStorage<'a, components::economy_components::WorkerComponent>,
+        ReadStorage<'a, components::economy_components::ProductionComponent>,
+        ReadStorage<'a, components::economy_components::ResourceNodeComponent>,
+        ReadStorage<'a, components::order_components::UnitOrderComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            cb_match,
+            teams,
+            visibility,
+            economy,
+            definitions,
+            mut ai_players,
+            ownerships,
+            transforms,
+            hit_points,
+            move_speeds,
+            ranged_attacks,
+            workers,
+            productions,
+            nodes,
+            orders,
+        ): Self::SystemData,
+    ) {
+        let ai_players = &mut *ai_players;
+        ai_players.pending_inputs.clear();
+
+        for ai_player in ai_players.players.iter_mut() {
+            let player_id = ai_player.player_id();
+
+            let commands = match cb_match.phase() {
+                MatchPhase::Lobby => vec![CbRtsCommand::SetReady { ready: true }],
+                MatchPhase::InProgress => {
+                    // Stagger AI players so they do not all think on the same tick
+                    let think = (cb_match.elapsed_ticks() as usize + player_id)
+                        % AI_THINK_TICKS as usize
+                        == 0;
+
+                    let team_id = match teams.team_of(player_id) {
+                        Some(team_id) if think => team_id,
+                        _ => {
+                            continue;
+                        }
+                    };
+
+                    let has_explored = |x: i32, y: i32| {
+                        let (cell_x, cell_y) = fog_of_war::world_to_cell(x, y);
+                        visibility.state(team_id, cell_x, cell_y) != FogState::Unexplored
+                    };
+
+                    let mut view = AiView::default();
+
+                    for (entity, ownership, transform, hp) in
+                        (&entities, &ownerships, &transforms, &hit_points).join()
+                    {
+                        if hp.is_dead() {
+                            continue;
+                        }
+
+                        let position = transform.world_position;
+
+                        if ownership.player_id != player_id {
+                            let (cell_x, cell_y) = fog_of_war::world_to_cell(
+                                position.x.to_num::<i32>(),
+                                position.y.to_num::<i32>(),
+                            );
+
+                            if teams.are_players_enemies(player_id, ownership.player_id)
+                                && visibility.is_visible(&teams, team_id, cell_x, cell_y)
+                            {
+                                view.visible_enemies.push((
+                                    entity.id(),
+                                    position,
+                                    move_speeds.get(entity).is_none(),
+                                ));
+                            }
+
+                            continue;
+                        }
+
+                        let order = orders.get(entity).map(|o| o.order);
+                        let worker = workers.get(entity);
+
+                        let is_idle = order.map_or(true, |o| o == UnitOrder::Idle)
+                            && worker.map_or(true, |w| w.order == HarvestOrder::Idle);
+
+                        let attack_target = match order {
+                            Some(UnitOrder::AttackMove { target }) => Some(target),
+                            _ => None,
+                        };
+
+                        view.units.push(AiUnit {
+                            id: entity.id(),
+                            position: position,
+                            move_speed: move_speeds
+                                .get(entity)
+                                .map_or(0, |s| s.value.to_num::<u32>()),
+                            can_attack: ranged_attacks.get(entity).is_some(),
+                            is_worker: worker.is_some(),
+                            is_idle: is_idle,
+                            attack_target: attack_target,
+                            produces: productions
+                                .get(entity)
+                                .map_or(vec![], |p| p.produces.clone()),
+                            production_full: productions.get(entity).map_or(true, |p| p.is_full()),
+                        });
+                    }
+
+                    for (entity, node, transform) in (&entities, &nodes, &transforms).join() {
+                        let position = transform.world_position;
+
+                        if node.remaining > 0
+                            && has_explored(position.x.to_num::<i32>(), position.y.to_num::<i32>())
+                        {
+                            view.resource_nodes
+                                .push((entity.id(), node.resource, position));
+                        }
+                    }
+
+                    if let Some(player) = economy.player(player_id) {
+                        view.stockpile = player.stockpile;
+                        view.supply_available = player.supply_available();
+                    }
+
+                    view.explored_waypoints = ai::scouting_waypoints()
+                        .iter()
+                        .map(|(x, y)| visibility.state(team_id, *x, *y) != FogState::Unexplored)
+                        .collect();
+
+                    ai_player.think(&view, &definitions)
+                }
+                _ => {
+                    continue;
+                }
+            };
+
+            ai_players
+                .pending_inputs
+                .push(ai::build_input(player_id, &commands));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::{components, unit_animations};
+
+use crate::cb_animation;
+use cb_animation::AnimationLibrary;
+
+use specs::prelude::*;
+
+/// Set each animator's speed from how far its entity moved since the last tick, advance it by a tick, then sample its
+/// pose and apply its IK layers.
+pub struct AnimationSystem;
+
+impl<'a> System<'a> for AnimationSystem {
+    type SystemData = (
+        Read<'a, AnimationLibrary>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        WriteStorage<'a, components::animation_components::AnimationComponent>,
+    );
+
+    fn run(&mut self, (library, transforms, mut animations): Self::SystemData) {
+        for (animation, transform) in (&mut animations, transforms.maybe()).join() {
+            let set = match library.get(animation.set) {
+                Some(set) => set,
+                None => {
+                    continue;
+                }
+            };
+
+            if let Some(transform) = transform {
+                let position = transform.world_position;
+
+                // Poses are only for drawing, so the speed may be a float
+                let speed = match animation.previous_position {
+                    Some(previous) => (previous.distance_squared(&position) as f32).sqrt(),
+                    None => 0.0,
+                };
+
+                animation
+                    .animator
+                    .set_parameter(unit_animations::SPEED_PARAMETER, speed);
+                animation.previous_position = Some(position);
+            }
+
+            animation.animator.tick(set);
+
+            let mut pose = animation.animator.sample(set);
+            for layer in animation.ik_layers.iter() {
+                layer.apply(&set.skeleton, &mut pose);
+            }
+
+            animation.pose = Some(pose);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use components::animation_components::{AnimationComponent, AnimationComponentsLinker};
+    use components::physics_components::TransformComponent;
+    use components::ComponentLinker;
+
+    use crate::cb_system;
+    use cb_system::{Coordinate2d, GameUnit};
+
+    /// A world with a walker standing at the origin.
+    fn world() -> (World, Entity) {
+        let mut world = World::new();
+        components::physics_components::PhysicsComponentsLinker::register_components(&mut world);
+        AnimationComponentsLinker::register_components(&mut world);
+
+        let library = unit_animations::load_library().unwrap();
+        let set = library.index_of(unit_animations::WALKER_SET).unwrap();
+        world.insert(library);
+
+        let entity = world
+            .create_entity()
+            .with(TransformComponent::new())
+            .with(AnimationComponent::new(set))
+            .build();
+
+        return (world, entity);
+    }
+
+    /// Run the system for the ticks, moving the entity by the step along x before each one.
+    fn run(world: &mut World, entity: Entity, ticks: u32, step: i32) {
+        for _ in 0..ticks {
+            {
+                let mut transforms = world.write_storage::<TransformComponent>();
+                let transform = transforms.get_mut(entity).unwrap();
+                transform.world_position = Coordinate2d::new(
+                    transform.world_position.x + GameUnit::from_num(step),
+                    transform.world_position.y,
+                );
+            }
+
+            AnimationSystem.run_now(world);
+        }
+    }
+
+    fn pose(world: &World, entity: Entity) -> cb_animation::skeleton::Pose {
+        return world
+            .read_storage::<AnimationComponent>()
+            .get(entity)
+            .unwrap()
+            .pose
+            .clone()
+            .unwrap();
+    }
+
+    fn speed(world: &World, entity: Entity) -> f32 {
+        return world
+            .read_storage::<AnimationComponent>()
+            .get(entity)
+            .unwrap()
+            .animator
+            .parameter(unit_animations::SPEED_PARAMETER);
+    }
+
+    #[test]
+    fn standing_still_holds_idle_pose() {
+        let (mut world, entity) = world();
+
+        run(&mut world, entity, 1, 0);
+        let first = pose(&world, entity);
+        run(&mut world, entity, 5, 0);
+
+        assert_eq!(0.0, speed(&world, entity));
+        assert_eq!(first, pose(&world, entity));
+    }
+
+    #[test]
+    fn walking_sets_speed_and_swings_legs() {
+        let (mut world, entity) = world();
+        run(&mut world, entity, 1, 0);
+        let idle = pose(&world, entity);
+
+        run(&mut world, entity, 5, 10);
+        let walking = pose(&world, entity);
+        run(&mut world, entity, 5, 10);
+
+        assert_eq!(10.0, speed(&world, entity));
+        assert_ne!(idle, walking);
+        assert_ne!(walking, pose(&world, entity));
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_input;
+use cb_input::contexts::CbInputContexts;
+use cb_input::input_type::Press;
+
+use crate::cb_simulation;
+use cb_simulation::cameras::{self, CbActiveCamera};
+use cb_simulation::character_controller::CharacterState;
+use cb_simulation::components::{actor_components, gfx_components, shooter_components};
+use cb_simulation::CbSystemValues;
+use gfx_components::{CameraComponent, CameraController};
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use specs::prelude::*;
+
+/// Move every camera by its controller, with the local player's inputs steering the active one.
+pub struct CameraSystem;
+
+impl<'a> System<'a> for CameraSystem {
+    type SystemData = (
+        Read<'a, CbSystemValues>,
+        Write<'a, CbActiveCamera>,
+        Entities<'a>,
+        WriteStorage<'a, CameraComponent>,
+        ReadStorage<'a, actor_components::ActorComponent>,
+        ReadStorage<'a, shooter_components::CharacterControllerComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (sys_values, mut active_camera, entities, mut cameras, actors, controllers): Self::SystemData,
+    ) {
+        for camera in (&mut cameras).join() {
+            camera.begin_tick();
+        }
+
+        // Fall back to the first camera when there is no active one
+        let active = match active_camera.get() {
+            Some(entity) if cameras.contains(entity) => Some(entity),
+            _ => (&entities, &cameras)
+                .join()
+                .next()
+                .map(|(entity, _)| entity),
+        };
+        active_camera.set(active);
+
+        let local_player_id = sys_values.get_current_player_id();
+
+        // Only the active camera takes input
+        if let Some(camera) = active.and_then(|entity| cameras.get_mut(entity)) {
+            let local_inputs = sys_values
+                .world_inputs
+                .iter()
+                .filter(|input| input.player_id == local_player_id);
+
+            for input in local_inputs {
+                for context in input.context_manager.get_contexts().iter() {
+                    if let Some(context) = context {
+                        apply_input(camera, context);
+                    }
+                }
+            }
+        }
+
+        let character = (&actors, &controllers)
+            .join()
+            .find(|(actor, _)| actor.player_id == local_player_id)
+            .map(|(_, controller)| controller.state);
+
+        for camera in (&mut cameras).join() {
+            update_pose(camera, character.as_ref());
+        }
+    }
+}
+
+fn pressed(press: Press) -> i32 {
+    return if press == Press::Pressed { 1 } else { 0 };
+}
+
+/// Steer the camera's controller with the context's inputs.
+fn apply_input(camera: &mut CameraComponent, context: &CbInputContexts) {
+    match *context {
+        CbInputContexts::VoxelEditorContext {
+            toggle_orthographic_view,
+            front_view,
+            top_view,
+            right_view,
+            left_view,
+            rotate_camera_up,
+            rotate_camera_down,
+            rotate_camera_left,
+            rotate_camera_right,
+            pan_camera_forward,
+            pan_camera_backward,
+            pan_camera_left,
+            pan_camera_right,
+            zoom_camera_in,
+            zoom_camera_out,
+            ..
+        } => {
+            if toggle_orthographic_view == Press::Pressed {
+                camera.orthographic_view = !camera.orthographic_view;
+            }
+
+            if let CameraController::Orbit {
+                mut yaw,
+                mut pitch,
+                mut distance,
+            } = camera.controller
+            {
+                // Views along an axis
+                if front_view == Press::Pressed {
+                    yaw = -900;
+                    pitch = 0;
+                } else if left_view == Press::Pressed {
+                    yaw = 1800;
+                    pitch = 0;
+                } else if right_view == Press::Pressed {
+                    yaw = 0;
+                    pitch = 0;
+                } else if top_view == Press::Pressed {
+                    pitch = cameras::MAX_ORBIT_PITCH;
+                }
+
+                // Orbit, pan and zoom
+                yaw += (pressed(rotate_camera_right) - pressed(rotate_camera_left))
+                    * cameras::ORBIT_STEP;
+                yaw = yaw.rem_euclid(3600);
+
+                pitch +=
+                    (pressed(rotate_camera_up) - pressed(rotate_camera_down)) * cameras::ORBIT_STEP;
+                pitch = pitch.clamp(-cameras::MAX_ORBIT_PITCH, cameras::MAX_ORBIT_PITCH);
+
+                camera.focus = cameras::pan(
+                    camera.focus,
+                    yaw,
+                    pressed(pan_camera_forward) - pressed(pan_camera_backward),
+                    pressed(pan_camera_right) - pressed(pan_camera_left),
+                );
+
+                distance =
+                    cameras::zoom(distance, pressed(zoom_camera_in) - pressed(zoom_camera_out));
+
+                camera.controller = CameraController::Orbit {
+                    yaw: yaw,
+                    pitch: pitch,
+                    distance: distance,
+                };
+            }
+        }
+        CbInputContexts::RtsContext {
+            cursor_x, cursor_y, ..
+        } => {
+            if let CameraController::TopDown { .. } = camera.controller {
+                let (right, up) = cameras::edge_scroll(cursor_x, cursor_y);
+                let speed = GameUnit::from_num(cameras::EDGE_SCROLL_SPEED);
+
+                camera.focus.x += speed * right;
+                camera.focus.y += speed * up;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Place the camera where its controller puts it.
+fn update_pose(camera: &mut CameraComponent, character: Option<&CharacterState>) {
+    let zero = GameUnit::from_num(0);
+    let z_up = Coordinate3d::new(zero, zero, GameUnit::from_num(1));
+
+    match camera.controller {
+        CameraController::Fixed => {}
+        CameraController::Orbit {
+            yaw,
+            pitch,
+            distance,
+        } => {
+            camera.position = cameras::orbit_position(camera.focus, yaw, pitch, distance);
+            camera.target = camera.focus;
+            camera.up = z_up;
+        }
+        CameraController::TopDown { height } => {
+            camera.position = cameras::top_down_position(camera.focus, height);
+            camera.target = camera.focus;
+            camera.up = z_up;
+        }
+        CameraController::FirstPerson => {
+            if let Some(state) = character {
+                let (eye, target) = cameras::first_person_pose(state);
+
+                camera.orthographic_view = false;
+                camera.position = eye;
+                camera.target = target;
+                camera.up = z_up;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cb_input::contexts::CbContextManager;
+    use crate::cb_input::CbGameInput;
+    use cb_simulation::components::ComponentLinker;
+
+    fn world() -> World {
+        let mut world = World::new();
+        gfx_components::GfxComponentsLinker::register_components(&mut world);
+        actor_components::ActorComponentsLinker::register_components(&mut world);
+        shooter_components::ShooterComponentsLinker::register_components(&mut world);
+        world.insert(CbSystemValues::new());
+        world.insert(CbActiveCamera::new(None));
+
+        return world;
+    }
+
+    fn orbit_camera() -> CameraComponent {
+        return CameraComponent::new(CameraController::Orbit {
+            yaw: 0,
+            pitch: 0,
+            distance: GameUnit::from_num(3000),
+        });
+    }
+
+    fn editor_input(rotate_camera_left: Press, zoom_camera_in: Press) -> CbGameInput {
+        let mut context_manager = CbContextManager::new();
+        context_manager.add_context(CbInputContexts::VoxelEditorContext {
+            networked: cb_input::contexts::Networked::Off,
+            open_console: Press::NotPressed,
+            cursor_x: cb_input::input_type::Range::default(),
+            cursor_y: cb_input::input_type::Range::default(),
+            toggle_orthographic_view: Press::NotPressed,
+            front_view: Press::NotPressed,
+            top_view: Press::NotPressed,
+            right_view: Press::NotPressed,
+            left_view: Press::NotPressed,
+            rotate_camera_up: Press::NotPressed,
+            rotate_camera_down: Press::NotPressed,
+            rotate_camera_left: rotate_camera_left,
+            rotate_camera_right: Press::NotPressed,
+            pan_camera_forward: Press::NotPressed,
+            pan_camera_backward: Press::NotPressed,
+            pan_camera_left: Press::NotPressed,
+            pan_camera_right: Press::NotPressed,
+            zoom_camera_in: zoom_camera_in,
+            zoom_camera_out: Press::NotPressed,
+            add_voxel: Press::NotPressed,
+            remove_voxel: Press::NotPressed,
+        });
+
+        return CbGameInput::new(0, context_manager);
+    }
+
+    #[test]
+    fn camera_system_activates_first_camera_and_places_orbit() {
+        let mut world = world();
+        let camera = world.create_entity().with(orbit_camera()).build();
+
+        CameraSystem.run_now(&world);
+
+        assert_eq!(Some(camera), world.read_resource::<CbActiveCamera>().get());
+
+        let cameras = world.read_storage::<CameraComponent>();
+        let camera = cameras.get(camera).unwrap();
+        assert_eq!(GameUnit::from_num(3000), camera.position.x);
+        assert_eq!(Coordinate3d::zero(), camera.target);
+    }
+
+    #[test]
+    fn camera_system_steers_only_active_camera() {
+        let mut world = world();
+        let active = world.create_entity().with(orbit_camera()).build();
+        let inactive = world.create_entity().with(orbit_camera()).build();
+        world.insert(CbActiveCamera::new(Some(active)));
+        world.insert(CbSystemValues::from(
+            vec![editor_input(Press::Pressed, Press::Pressed)],
+            0,
+            0,
+        ));
+
+        CameraSystem.run_now(&world);
+
+        let cameras = world.read_storage::<CameraComponent>();
+        assert_eq!(
+            CameraController::Orbit {
+                yaw: 3600 - cameras::ORBIT_STEP,
+                pitch: 0,
+                distance: GameUnit::from_num(2400),
+            },
+            cameras.get(active).unwrap().controller
+        );
+        assert_eq!(
+            orbit_camera().controller,
+            cameras.get(inactive).unwrap().controller
+        );
+    }
+
+    #[test]
+    fn camera_system_keeps_previous_pose() {
+        let mut world = world();
+        let camera = world.create_entity().with(orbit_camera()).build();
+
+        CameraSystem.run_now(&world);
+        world.insert(CbSystemValues::from(
+            vec![editor_input(Press::NotPressed, Press::Pressed)],
+            0,
+            1,
+        ));
+        CameraSystem.run_now(&world);
+
+        let cameras = world.read_storage::<CameraComponent>();
+        let camera = cameras.get(camera).unwrap();
+        assert_eq!(GameUnit::from_num(3000), camera.previous_position.x);
+        assert_eq!(GameUnit::from_num(2400), camera.position.x);
+    }
+
+    #[test]
+    fn camera_system_edge_scrolls_top_down_camera() {
+        let mut world = world();
+        world
+            .create_entity()
+            .with(CameraComponent::new(CameraController::TopDown {
+                height: GameUnit::from_num(3000),
+            }))
+            .build();
+
+        let mut context_manager = CbContextManager::new();
+        context_manager.add_context(CbInputContexts::RtsContext {
+            networked: cb_input::contexts::Networked::On,
+            select: Press::NotPressed,
+            target: Press::NotPressed,
+            cancel: Press::NotPressed,
+            move_unit: Press::NotPressed,
+            attack_move_unit: Press::NotPressed,
+            activate_ability: Press::NotPressed,
+            cursor_x: cb_input::input_type::Range::new(1000, 0, 1000),
+            cursor_y: cb_input::input_type::Range::new(500, 0, 1000),
+        });
+        world.insert(CbSystemValues::from(
+            vec![CbGameInput::new(0, context_manager)],
+            0,
+            0,
+        ));
+
+        CameraSystem.run_now(&world);
+
+        let cameras = world.read_storage::<CameraComponent>();
+        let camera = (&cameras).join().next().unwrap();
+        assert_eq!(
+            GameUnit::from_num(cameras::EDGE_SCROLL_SPEED),
+            camera.focus.x
+        );
+        assert_eq!(GameUnit::from_num(0), camera.focus.y);
+        assert_eq!(camera.focus.x, camera.position.x);
+    }
+}
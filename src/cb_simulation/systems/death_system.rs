@@ -0,0 +1,24 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+
+use specs::prelude::*;
+
+/// Remove units that have run out of hit points.
+pub struct DeathSystem;
+
+impl<'a> System<'a> for DeathSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, components::character_components::HitPointsComponent>,
+    );
+
+    fn run(&mut self, (entities, hit_points): Self::SystemData) {
+        for (entity, hp) in (&entities, &hit_points).join() {
+            if hp.is_dead() {
+                entities.delete(entity).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::economy::CbEconomy;
+use cb_simulation::unit_definitions::UnitDefinitions;
+use cb_simulation::CbSystemValues;
+
+use components::economy_components::{HarvestOrder, QueuedUnit};
+use components::order_components::UnitOrder;
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::{get_rts_commands, CbRtsCommand, UnitId};
+use cb_input::contexts::RTS_COMMAND_CONTEXT_ID;
+
+use specs::prelude::*;
+
+/// Apply the economy commands each player issued this frame.
+pub struct EconomyCommandSystem;
+
+impl<'a> System<'a> for EconomyCommandSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, CbSystemValues>,
+        Read<'a, UnitDefinitions>,
+        Write<'a, CbEconomy>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::economy_components::ResourceNodeComponent>,
+        WriteStorage<'a, components::economy_components::WorkerComponent>,
+        WriteStorage<'a, components::economy_components::ProductionComponent>,
+        WriteStorage<'a, components::order_components::UnitOrderComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            sys_values,
+            definitions,
+            mut economy,
+            ownerships,
+            nodes,
+            mut workers,
+            mut productions,
+            mut orders,
+        ): Self::SystemData,
+    ) {
+        // Process players in a fixed order so every peer spends resources the same way
+        let mut inputs: Vec<&cb_input::CbGameInput> = sys_values.world_inputs.iter().collect();
+        inputs.sort_by_key(|i| i.player_id);
+
+        for input in inputs.iter() {
+            let context = match input.context_manager.get_context(RTS_COMMAND_CONTEXT_ID) {
+                Some(context) => context,
+                None => {
+                    continue;
+                }
+            };
+
+            let player_id = input.player_id;
+
+            // Returns the entity if it is alive and owned by the player issuing the command
+            let owned_entity = |unit: UnitId| -> Option<Entity> {
+                let entity = entities.entity(unit);
+
+                if !entities.is_alive(entity) {
+                    return None;
+                }
+
+                match ownerships.get(entity) {
+                    Some(ownership) if ownership.player_id == player_id => Some(entity),
+                    _ => None,
+                }
+            };
+
+            for command in get_rts_commands(&context).iter() {
+                match *command {
+                    CbRtsCommand::Harvest { worker, node } => {
+                        let worker = match owned_entity(worker) {
+                            Some(worker) => worker,
+                            None => {
+                                continue;
+                            }
+                        };
+
+                        let node = entities.entity(node);
+                        if !entities.is_alive(node) || nodes.get(node).is_none() {
+                            continue;
+                        }
+
+                        if let Some(worker) = workers.get_mut(worker) {
+                            worker.order = HarvestOrder::Harvesting { node: node };
+                            worker.gather_progress = 0;
+                        }
+
+                        // Harvesting replaces any move order
+                        if let Some(unit_order) = orders.get_mut(worker) {
+                            unit_order.order = UnitOrder::Idle;
+                        }
+                    }
+                    CbRtsCommand::Train {
+                        structure,
+                        unit_type,
+                    } => {
+                        let production = match owned_entity(structure)
+                            .and_then(|structure| productions.get_mut(structure))
+                        {
+                            Some(production) => production,
+                            None => {
+                                continue;
+                            }
+                        };
+
+                        if production.is_full() || !production.produces.contains(&unit_type) {
+                            continue;
+                        }
+
+                        let cost = match definitions.get_by_id(unit_type) {
+                            Some(definition) => definition.cost,
+                            None => {
+                                continue;
+                            }
+                        };
+
+                        if economy.try_spend(player_id, &cost.resources, cost.supply) {
+                            production.queue.push(QueuedUnit {
+                                unit_type: unit_type,
+                                remaining_ticks: cost.build_ticks,
+                                cost: cost.resources,
+                                supply: cost.supply,
+                            });
+                        }
+                    }
+                    CbRtsCommand::CancelTraining { structure } => {
+                        let production = match owned_entity(structure)
+                            .and_then(|structure| productions.get_mut(structure))
+                        {
+                            Some(production) => production,
+                            None => {
+                                continue;
+                            }
+                        };
+
+                        if let Some(cancelled) = production.queue.pop() {
+                            economy.refund(player_id, &cancelled.cost, cancelled.supply);
+                        }
+                    }
+                    CbRtsCommand::ActivateAbility {
+                        unit: _,
+                        slot: _,
+                        target: _,
+                    } => {}
+                    CbRtsCommand::Move {
+                        unit: _,
+                        x: _,
+                        y: _,
+                    } => {}
+                    CbRtsCommand::AttackMove {
+                        unit: _,
+                        x: _,
+                        y: _,
+                    } => {}
+                    CbRtsCommand::SetReady { ready: _ } => {}
+                    CbRtsCommand::None => {}
+                }
+            }
+        }
+    }
+}
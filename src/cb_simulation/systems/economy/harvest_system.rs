@@ -0,0 +1,171 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::economy::CbEconomy;
+
+use components::economy_components::HarvestOrder;
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, GameUnit};
+
+use crate::cb_voxels;
+
+use specs::prelude::*;
+
+/// How close, in game units, a worker must be to a node or drop off to use it.
+const HARVEST_REACH: i64 = 2 * cb_voxels::VOXEL_GAME_SIZE as i64;
+
+/// Move workers between resource nodes and drop offs, gathering and depositing resources.
+pub struct HarvestSystem;
+
+impl<'a> System<'a> for HarvestSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, CbEconomy>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::character_components::MoveSpeedComponent>,
+        ReadStorage<'a, components::economy_components::DropOffComponent>,
+        WriteStorage<'a, components::economy_components::ResourceNodeComponent>,
+        WriteStorage<'a, components::economy_components::WorkerComponent>,
+        WriteStorage<'a, components::physics_components::TransformComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut economy,
+            ownerships,
+            move_speeds,
+            drop_offs,
+            mut nodes,
+            mut workers,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        let drop_off_positions: Vec<(usize, Coordinate2d)> = (&ownerships, &drop_offs, &transforms)
+            .join()
+            .map(|(ownership, _, transform)| (ownership.player_id, transform.world_position))
+            .collect();
+
+        for (entity, ownership, worker) in (&entities, &ownerships, &mut workers).join() {
+            let position = match transforms.get(entity) {
+                Some(transform) => transform.world_position,
+                None => {
+                    continue;
+                }
+            };
+
+            let speed = match move_speeds.get(entity) {
+                Some(speed) => GameUnit::from_num(speed.value),
+                None => GameUnit::from_num(0),
+            };
+
+            let mut destination = None;
+
+            match worker.order {
+                HarvestOrder::Idle => {}
+                HarvestOrder::Harvesting { node } => {
+                    let node_position = transforms.get(node).map(|t| t.world_position);
+
+                    match (nodes.get_mut(node), node_position) {
+                        (Some(node_component), Some(node_position))
+                            if entities.is_alive(node) && node_component.remaining > 0 =>
+                        {
+                            if position.distance_squared(&node_position)
+                                > HARVEST_REACH * HARVEST_REACH
+                            {
+                                destination = Some(node_position);
+                                worker.gather_progress = 0;
+                            } else {
+                                worker.gather_progress += 1;
+
+                                if worker.gather_progress >= worker.gather_ticks {
+                                    worker.gather_progress = 0;
+                                    node_component.remaining -= 1;
+
+                                    // Switching resource types drops whatever was carried
+                                    let carried = match worker.carrying {
+                                        Some((resource, amount))
+                                            if resource == node_component.resource =>
+                                        {
+                                            amount
+                                        }
+                                        _ => 0,
+                                    };
+                                    worker.carrying = Some((node_component.resource, carried + 1));
+
+                                    if node_component.remaining == 0 {
+                                        entities.delete(node).unwrap();
+                                    }
+
+                                    if carried + 1 >= worker.carry_capacity
+                                        || node_component.remaining == 0
+                                    {
+                                        worker.order = HarvestOrder::Returning { node: node };
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            // The node is gone; bring back anything already gathered
+                            if worker.carried_amount() > 0 {
+                                worker.order = HarvestOrder::Returning { node: node };
+                            } else {
+                                worker.order = HarvestOrder::Idle;
+                            }
+                        }
+                    }
+                }
+                HarvestOrder::Returning { node } => {
+                    // Nearest drop off owned by the player; ties go to the first found, so results are deterministic
+                    let mut nearest: Option<(i64, Coordinate2d)> = None;
+
+                    for (player_id, drop_off_position) in drop_off_positions.iter() {
+                        if *player_id != ownership.player_id {
+                            continue;
+                        }
+
+                        let distance = position.distance_squared(drop_off_position);
+
+                        if nearest.is_none() || distance < nearest.unwrap().0 {
+                            nearest = Some((distance, *drop_off_position));
+                        }
+                    }
+
+                    match nearest {
+                        Some((distance, drop_off_position)) => {
+                            if distance > HARVEST_REACH * HARVEST_REACH {
+                                destination = Some(drop_off_position);
+                            } else {
+                                if let Some((resource, amount)) = worker.carrying {
+                                    economy.deposit(ownership.player_id, resource, amount);
+                                }
+                                worker.carrying = None;
+
+                                let node_available = entities.is_alive(node)
+                                    && nodes.get(node).map_or(false, |n| n.remaining > 0);
+
+                                if node_available {
+                                    worker.order = HarvestOrder::Harvesting { node: node };
+                                } else {
+                                    worker.order = HarvestOrder::Idle;
+                                }
+                            }
+                        }
+                        None => {
+                            // Nowhere to return to; wait until a drop off exists
+                        }
+                    }
+                }
+            }
+
+            if let Some(destination) = destination {
+                if let Some(transform) = transforms.get_mut(entity) {
+                    transform.world_position = position.move_towards(&destination, speed);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod command_system;
+mod harvest_system;
+mod production_system;
+mod supply_system;
+pub use command_system::EconomyCommandSystem;
+pub use harvest_system::HarvestSystem;
+pub use production_system::ProductionSystem;
+pub use supply_system::SupplySystem;
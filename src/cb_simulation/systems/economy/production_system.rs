@@ -0,0 +1,63 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::economy::{CbSpawnQueue, SpawnRequest};
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use specs::prelude::*;
+
+/// Advance the front of each build queue, requesting a spawn when a unit finishes.
+pub struct ProductionSystem;
+
+impl<'a> System<'a> for ProductionSystem {
+    type SystemData = (
+        Write<'a, CbSpawnQueue>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        ReadStorage<'a, components::character_components::UnitBaseComponent>,
+        WriteStorage<'a, components::economy_components::ProductionComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (mut spawn_queue, ownerships, transforms, unit_bases, mut productions): Self::SystemData,
+    ) {
+        for (ownership, transform, production, unit_base) in (
+            &ownerships,
+            &transforms,
+            &mut productions,
+            unit_bases.maybe(),
+        )
+            .join()
+        {
+            let finished = match production.queue.first_mut() {
+                Some(queued) => {
+                    queued.remaining_ticks = queued.remaining_ticks.saturating_sub(1);
+                    queued.remaining_ticks == 0
+                }
+                None => false,
+            };
+
+            if !finished {
+                continue;
+            }
+
+            let queued = production.queue.remove(0);
+
+            // Spawn just outside the structure's base
+            let mut position = transform.world_position;
+            if let Some(unit_base) = unit_base {
+                position.y += GameUnit::from_num(unit_base.base_size);
+            }
+
+            spawn_queue.requests.push(SpawnRequest {
+                unit_type: queued.unit_type,
+                position: position,
+                player_id: ownership.player_id,
+            });
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::economy::CbEconomy;
+
+use specs::prelude::*;
+
+/// Recount the supply each player uses and provides, including units waiting in build queues.
+pub struct SupplySystem;
+
+impl<'a> System<'a> for SupplySystem {
+    type SystemData = (
+        Write<'a, CbEconomy>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::economy_components::SupplyComponent>,
+        ReadStorage<'a, components::economy_components::SupplyProviderComponent>,
+        ReadStorage<'a, components::economy_components::ProductionComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (mut economy, ownerships, supplies, supply_providers, productions): Self::SystemData,
+    ) {
+        economy.reset_supply();
+
+        for (ownership, supply) in (&ownerships, &supplies).join() {
+            economy.add_supply_used(ownership.player_id, supply.amount);
+        }
+
+        for (ownership, production) in (&ownerships, &productions).join() {
+            for queued in production.queue.iter() {
+                economy.add_supply_used(ownership.player_id, queued.supply);
+            }
+        }
+
+        for (ownership, provider) in (&ownerships, &supply_providers).join() {
+            economy.add_supply_cap(ownership.player_id, provider.amount);
+        }
+    }
+}
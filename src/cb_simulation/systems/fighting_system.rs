@@ -0,0 +1,114 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::actor_controller::ActorController;
+use cb_simulation::components;
+use cb_simulation::fighting::fighter_definitions::{FighterDefinition, FighterDefinitions};
+use cb_simulation::fighting::input_buffer::{Button, FightingInput};
+use cb_simulation::fighting::{self, Facing, FighterState};
+
+use crate::cb_input;
+use cb_input::contexts::{CbInputContexts, FIGHTING_CONTEXT_ID};
+use cb_input::input_type::{Press, State};
+
+use crate::cb_math;
+use cb_math::FUint;
+
+use specs::prelude::*;
+
+/// Step every fighter with its player's inputs, then resolve hits between them.
+pub struct FightingSystem;
+
+impl<'a> System<'a> for FightingSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, FighterDefinitions>,
+        ReadStorage<'a, components::actor_components::ActorComponent>,
+        WriteStorage<'a, components::fighting_components::FighterComponent>,
+        WriteStorage<'a, components::character_components::HitPointsComponent>,
+        WriteStorage<'a, components::physics_components::TransformComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, definitions, actors, mut fighters, mut hit_points, mut transforms): Self::SystemData,
+    ) {
+        let mut fighting: Vec<(Entity, &FighterDefinition, FighterState)> = vec![];
+
+        for (entity, actor, fighter) in (&entities, &actors, &mut fighters).join() {
+            let definition = match definitions.get(fighter.fighter) {
+                Some(definition) => definition,
+                None => {
+                    continue;
+                }
+            };
+
+            let input = get_fighting_input(&actor.controller, fighter.state.facing);
+            fighting::step_fighter(&mut fighter.state, definition, input);
+
+            fighting.push((entity, definition, fighter.state));
+        }
+
+        let fighter_definitions: Vec<&FighterDefinition> = fighting.iter().map(|f| f.1).collect();
+        let mut states: Vec<FighterState> = fighting.iter().map(|f| f.2).collect();
+
+        let hits = fighting::resolve_fight(&mut states, &fighter_definitions);
+
+        for ((entity, _, _), state) in fighting.iter().zip(states.iter()) {
+            if let Some(fighter) = fighters.get_mut(*entity) {
+                fighter.state = *state;
+            }
+
+            if let Some(transform) = transforms.get_mut(*entity) {
+                transform.world_position = state.position;
+            }
+        }
+
+        for hit in hits.iter() {
+            if let Some(hp) = hit_points.get_mut(fighting[hit.defender].0) {
+                hp.apply_damage(FUint::from_num(hit.damage));
+            }
+        }
+    }
+}
+
+/// The player's fighting input this frame, or neutral if none was sent.
+fn get_fighting_input(controller: &ActorController, facing: Facing) -> FightingInput {
+    match controller.current(FIGHTING_CONTEXT_ID) {
+        Some(CbInputContexts::FightingContext {
+            networked: _,
+            up,
+            down,
+            left,
+            right,
+            punch_light,
+            punch_heavy,
+            kick_light,
+            kick_heavy,
+        }) => {
+            let presses = [
+                (punch_light, Button::LightPunch),
+                (punch_heavy, Button::HeavyPunch),
+                (kick_light, Button::LightKick),
+                (kick_heavy, Button::HeavyKick),
+            ];
+            let buttons: Vec<Button> = presses
+                .iter()
+                .filter(|(press, _)| *press == Press::Pressed)
+                .map(|(_, button)| *button)
+                .collect();
+
+            return fighting::to_fighting_input(
+                up == State::On,
+                down == State::On,
+                left == State::On,
+                right == State::On,
+                &buttons,
+                facing,
+            );
+        }
+        _ => {
+            return FightingInput::neutral();
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::fog_of_war::{self, CbHeightMapCache, CbVisibility};
+use cb_simulation::teams::CbTeams;
+use cb_simulation::CbSystemValues;
+
+use crate::cb_voxels;
+
+use specs::prelude::*;
+pub struct FogOfWarSystem;
+
+impl<'a> System<'a> for FogOfWarSystem {
+    type SystemData = (
+        Read<'a, CbSystemValues>,
+        Read<'a, CbTeams>,
+        Write<'a, CbVisibility>,
+        Write<'a, CbHeightMapCache>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::character_components::SightComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        ReadStorage<'a, components::voxel_components::VoxelComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            system_values,
+            teams,
+            mut visibility,
+            mut height_maps,
+            ownerships,
+            sights,
+            transforms,
+            voxels,
+        ): Self::SystemData,
+    ) {
+        let chunk_manager = (&voxels).join().next().map(|voxel| &voxel.chunk_manager);
+        let height_map = height_maps.get(chunk_manager, system_values.frame);
+
+        visibility.begin_tick();
+
+        for (ownership, sight, transform) in (&ownerships, &sights, &transforms).join() {
+            let team_id = match teams.team_of(ownership.player_id) {
+                Some(team_id) => team_id,
+                None => {
+                    continue;
+                }
+            };
+
+            let origin = fog_of_war::world_to_cell(
+                transform.world_position.x.to_num::<i32>(),
+                transform.world_position.y.to_num::<i32>(),
+            );
+            let range = sight.range.to_num::<i32>() / cb_voxels::VOXEL_GAME_SIZE;
+
+            fog_of_war::reveal_from(&mut visibility, height_map, team_id, origin, range);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::economy::CbEconomy;
+use cb_simulation::match_state::{CbMatch, MatchSnapshot};
+use cb_simulation::teams::{CbTeams, TeamId};
+use cb_simulation::CbSystemValues;
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::{get_rts_commands, CbRtsCommand};
+use cb_input::contexts::RTS_COMMAND_CONTEXT_ID;
+
+use specs::prelude::*;
+
+/// Apply ready commands, then advance the match lifecycle and evaluate the victory conditions. Runs every tick,
+/// regardless of whether the match is in progress.
+pub struct MatchSystem;
+
+impl<'a> System<'a> for MatchSystem {
+    type SystemData = (
+        Read<'a, CbSystemValues>,
+        Read<'a, CbTeams>,
+        Read<'a, CbEconomy>,
+        Write<'a, CbMatch>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::character_components::HitPointsComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        ReadStorage<'a, components::match_components::ObjectiveComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            sys_values,
+            teams,
+            economy,
+            mut cb_match,
+            ownerships,
+            hit_points,
+            transforms,
+            objectives,
+        ): Self::SystemData,
+    ) {
+        let mut inputs: Vec<&cb_input::CbGameInput> = sys_values.world_inputs.iter().collect();
+        inputs.sort_by_key(|i| i.player_id);
+
+        for input in inputs.iter() {
+            if let Some(context) = input.context_manager.get_context(RTS_COMMAND_CONTEXT_ID) {
+                for command in get_rts_commands(&context).iter() {
+                    if let CbRtsCommand::SetReady { ready } = *command {
+                        cb_match.set_ready(input.player_id, ready);
+                    }
+                }
+            }
+        }
+
+        let mut snapshot = MatchSnapshot::default();
+
+        for player_id in teams.players().iter() {
+            let units = (&ownerships, &hit_points)
+                .join()
+                .filter(|(ownership, hp)| ownership.player_id == *player_id && !hp.is_dead())
+                .count() as u32;
+
+            snapshot.units.push((*player_id, units));
+
+            if let Some(player) = economy.player(*player_id) {
+                snapshot.stockpiles.push((*player_id, player.stockpile));
+            }
+        }
+
+        for (objective, objective_transform) in (&objectives, &transforms).join() {
+            let radius = objective.radius.to_num::<i64>();
+
+            let mut present: Vec<TeamId> = vec![];
+            for (ownership, hp, transform) in (&ownerships, &hit_points, &transforms).join() {
+                if hp.is_dead() {
+                    continue;
+                }
+
+                let distance_squared = transform
+                    .world_position
+                    .distance_squared(&objective_transform.world_position);
+
+                if distance_squared > radius * radius {
+                    continue;
+                }
+
+                if let Some(team_id) = teams.team_of(ownership.player_id) {
+                    if !present.contains(&team_id) {
+                        present.push(team_id);
+                    }
+                }
+            }
+            present.sort();
+
+            // Contested objectives are held by no one
+            let controller = match present.first() {
+                Some(first) if present.iter().all(|t| teams.are_teams_allied(*first, *t)) => {
+                    Some(*first)
+                }
+                _ => None,
+            };
+
+            snapshot.objective_controllers.push(controller);
+        }
+
+        cb_match.tick(&teams, &snapshot);
+    }
+}
@@ -1,5 +1,17 @@
+pub mod ability_system;
 pub mod actor_input_system;
+pub mod ai_system;
+pub mod animation_system;
 pub mod audio;
+pub mod camera_system;
+pub mod death_system;
+pub mod economy;
 pub mod editor_system;
+pub mod fighting_system;
+pub mod fog_of_war_system;
+pub mod match_system;
 pub mod physics;
-pub mod voxel_editor_system;
+pub mod projectile_system;
+pub mod shooter;
+pub mod transform_history_system;
+pub mod unit_order_system;
@@ -1,17 +1,14 @@
 use crate::cb_simulation;
 use cb_simulation::components;
+use cb_simulation::components::ik_components::IkSolver;
 use cb_simulation::CbSystemValues;
 
-use crate::cb_inverse_kinematics;
-use cb_inverse_kinematics::{fabrik, CbMatrix, IkRig};
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
 
 use specs::prelude::*;
 pub struct IkSystem;
 
-use crate::cb_menu;
-
-use crate::cb_input;
-
 impl<'a> System<'a> for IkSystem {
     type SystemData = (
         Read<'a, CbSystemValues>,
@@ -19,17 +16,20 @@ impl<'a> System<'a> for IkSystem {
     );
 
     fn run(&mut self, (sys_values, mut ik_components): Self::SystemData) {
-        let world_inputs = &sys_values.world_inputs;
-
         for ik_component in (&mut ik_components).join() {
-            // Target the mouse coordinates
-            let mouse_x = sys_values.editor_x;
-            let mouse_y = sys_values.editor_y;
-
-            let target = Some(CbMatrix::new(mouse_x as f32, mouse_y as f32));
-            ik_component.rig.target = target;
-
-            fabrik(&mut ik_component.rig);
+            // Cosmetic rigs follow the editor's mouse. Deterministic rigs reach for the targets the simulation gives
+            // them, as the mouse isn't networked
+            if let IkSolver::Cosmetic(_) = ik_component.solver {
+                let target = Coordinate3d::new(
+                    GameUnit::from_num(sys_values.editor_x),
+                    GameUnit::from_num(sys_values.editor_y),
+                    GameUnit::from_num(0),
+                );
+
+                ik_component.set_target(target);
+            }
+
+            ik_component.solve();
         }
     }
 }
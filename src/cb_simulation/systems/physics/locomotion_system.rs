@@ -0,0 +1,48 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::locomotion;
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use crate::cb_voxels;
+use cb_voxels::VOXEL_GAME_SIZE;
+
+use specs::prelude::*;
+
+/// Walk every unit's legs along under it, stepping onto the voxel terrain when there is any.
+pub struct LocomotionSystem;
+
+impl<'a> System<'a> for LocomotionSystem {
+    type SystemData = (
+        ReadStorage<'a, components::voxel_components::VoxelComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        WriteStorage<'a, components::ik_components::LegsComponent>,
+    );
+
+    fn run(&mut self, (voxels, transforms, mut legs): Self::SystemData) {
+        let terrain = (&voxels).join().next().map(|voxel| &voxel.chunk_manager);
+
+        for (transform, legs) in (&transforms, &mut legs).join() {
+            let (x, y) = (transform.world_position.x, transform.world_position.y);
+
+            // Units move in two dimensions, standing on top of whatever column they are over
+            let ground = match terrain {
+                Some(terrain) => {
+                    let width = terrain.get_voxel_width() as i32;
+                    let column =
+                        |v: GameUnit| (v.to_num::<i32>() / VOXEL_GAME_SIZE).max(0).min(width - 1);
+
+                    terrain.get_column_height(column(x) as usize, column(y) as usize) as i32
+                        * VOXEL_GAME_SIZE
+                }
+                None => 0,
+            };
+
+            let body = Coordinate3d::new(x, y, GameUnit::from_num(ground));
+            locomotion::step_legs(&mut legs.legs, body, terrain);
+        }
+    }
+}
@@ -1,2 +1,6 @@
 mod ik_system;
+mod locomotion_system;
+mod rigid_body_system;
 pub use ik_system::IkSystem;
+pub use locomotion_system::LocomotionSystem;
+pub use rigid_body_system::RigidBodySystem;
@@ -0,0 +1,65 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::character_controller::{CharacterState, CHARACTER_RADIUS};
+use cb_simulation::components;
+use cb_simulation::rigid_body::{self, Collider, RigidBody};
+
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
+use specs::prelude::*;
+
+/// Step every rigid body against the voxel terrain and each other.
+pub struct RigidBodySystem;
+
+impl<'a> System<'a> for RigidBodySystem {
+    type SystemData = (
+        ReadStorage<'a, components::voxel_components::VoxelComponent>,
+        ReadStorage<'a, components::shooter_components::CharacterControllerComponent>,
+        WriteStorage<'a, components::physics_components::RigidBodyComponent>,
+        WriteStorage<'a, components::physics_components::TransformComponent>,
+    );
+
+    fn run(&mut self, (voxels, characters, mut rigid_bodies, mut transforms): Self::SystemData) {
+        let terrain = match (&voxels).join().next() {
+            Some(voxel) => &voxel.chunk_manager,
+            None => {
+                return;
+            }
+        };
+
+        let mut bodies: Vec<RigidBody> = (&rigid_bodies).join().map(|r| r.body).collect();
+
+        // Characters shove bodies out of their way, but are moved by their own controller rather than the bodies
+        for character in (&characters).join() {
+            bodies.push(character_body(&character.state));
+        }
+
+        rigid_body::step_bodies(&mut bodies, terrain);
+
+        for (rigid_body, body) in (&mut rigid_bodies).join().zip(bodies.iter()) {
+            rigid_body.body = *body;
+        }
+
+        for (rigid_body, transform) in (&rigid_bodies, &mut transforms).join() {
+            transform.world_position.x = rigid_body.body.position.x;
+            transform.world_position.y = rigid_body.body.position.y;
+        }
+    }
+}
+
+/// A static body filling the character's collision box.
+fn character_body(state: &CharacterState) -> RigidBody {
+    let half_height = GameUnit::from_num(state.stance.height() / 2);
+    let radius = GameUnit::from_num(CHARACTER_RADIUS);
+
+    let mut center = state.position;
+    center.z += half_height;
+
+    let collider = Collider::Aabb {
+        half_extents: Coordinate3d::new(radius, radius, half_height),
+    };
+
+    return RigidBody::new(center, collider, 0);
+}
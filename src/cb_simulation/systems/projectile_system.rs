@@ -0,0 +1,168 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::projectiles::{
+    self, Impact, ProjectileDefinition, ProjectileHit, ProjectileTarget,
+};
+use cb_simulation::teams::CbTeams;
+use cb_simulation::CbSystemValues;
+
+use crate::cb_math;
+use cb_math::FUint;
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use specs::prelude::*;
+
+/// Impulse given to rigid bodies at the center of a blast, in kilogram game units per tick.
+const BLAST_IMPULSE: i32 = 1200;
+
+/// Move every projectile, damaging the enemies they hit and detonating the explosive ones. Anything with hit points and
+/// a transform may be shot; characters are hit by their collision box and everything else by a unit sized box standing
+/// on the terrain. Targets without an owner may be shot by anyone.
+pub struct ProjectileSystem;
+
+impl<'a> System<'a> for ProjectileSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, CbSystemValues>,
+        Read<'a, CbTeams>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::physics_components::TransformComponent>,
+        ReadStorage<'a, components::shooter_components::CharacterControllerComponent>,
+        WriteStorage<'a, components::projectile_components::ProjectileComponent>,
+        WriteStorage<'a, components::voxel_components::VoxelComponent>,
+        WriteStorage<'a, components::character_components::HitPointsComponent>,
+        WriteStorage<'a, components::physics_components::RigidBodyComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            system_values,
+            teams,
+            ownerships,
+            transforms,
+            controllers,
+            mut projectiles,
+            mut voxels,
+            mut hit_points,
+            mut rigid_bodies,
+        ): Self::SystemData,
+    ) {
+        // Without a voxel map projectiles only stop at what they hit or when they expire
+        let mut terrain = (&mut voxels)
+            .join()
+            .next()
+            .map(|voxel| &mut voxel.chunk_manager);
+
+        // Everything that may be shot
+        let targets: Vec<(Entity, Option<usize>, ProjectileTarget)> = (
+            &entities,
+            &hit_points,
+            &transforms,
+            ownerships.maybe(),
+            controllers.maybe(),
+        )
+            .join()
+            .filter(|(_, hp, _, _, _)| !hp.is_dead())
+            .map(|(entity, _, transform, ownership, controller)| {
+                let target = match controller {
+                    Some(controller) => {
+                        let (min, max) = controller.state.bounds();
+                        ProjectileTarget::new(min, max)
+                    }
+                    None => {
+                        let position = transform.world_position;
+                        let ground = terrain.as_ref().map_or(GameUnit::from_num(0), |terrain| {
+                            projectiles::ground_height(terrain, position)
+                        });
+                        ProjectileTarget::unit(position, ground)
+                    }
+                };
+
+                (entity, ownership.map(|o| o.player_id), target)
+            })
+            .collect();
+
+        let is_enemy = |player_id: usize, target_player_id: Option<usize>| {
+            target_player_id.map_or(true, |p| teams.are_players_enemies(player_id, p))
+        };
+
+        let mut stopped: Vec<(usize, ProjectileDefinition, ProjectileHit, Option<Entity>)> = vec![];
+
+        for (entity, projectile) in (&entities, &mut projectiles).join() {
+            // Projectiles only hit their player's enemies
+            let enemies: Vec<&(Entity, Option<usize>, ProjectileTarget)> = targets
+                .iter()
+                .filter(|(_, player_id, _)| is_enemy(projectile.player_id, *player_id))
+                .collect();
+            let boxes: Vec<ProjectileTarget> =
+                enemies.iter().map(|(_, _, target)| *target).collect();
+
+            if let Some(hit) =
+                projectiles::step_projectile(&mut projectile.projectile, terrain.as_deref(), &boxes)
+            {
+                let struck = match hit.impact {
+                    Impact::Target(i) => Some(enemies[i].0),
+                    _ => None,
+                };
+
+                stopped.push((
+                    projectile.player_id,
+                    projectile.projectile.definition,
+                    hit,
+                    struck,
+                ));
+                entities.delete(entity).unwrap();
+            }
+        }
+
+        let mut damaged: Vec<(Entity, FUint)> = vec![];
+
+        for (player_id, definition, hit, struck) in stopped.iter() {
+            if !definition.is_explosive() {
+                if let Some(target) = struck {
+                    damaged.push((*target, definition.damage));
+                }
+                continue;
+            }
+
+            let radius = definition.blast_radius;
+            if let Some(terrain) = terrain.as_mut() {
+                terrain.remove_voxels_within(hit.position, radius, system_values.frame);
+            }
+
+            for (target, target_player_id, bounds) in targets.iter() {
+                if !is_enemy(*player_id, *target_player_id) {
+                    continue;
+                }
+
+                if let Some(falloff) =
+                    projectiles::blast_falloff(hit.position, radius, bounds.min, bounds.max)
+                {
+                    damaged.push((*target, definition.damage * FUint::from_num(falloff)));
+                }
+            }
+
+            for rigid_body in (&mut rigid_bodies).join() {
+                let strength = GameUnit::from_num(BLAST_IMPULSE);
+
+                if let Some(impulse) =
+                    projectiles::blast_impulse(hit.position, radius, strength, &rigid_body.body)
+                {
+                    rigid_body.body.apply_impulse(impulse);
+                }
+            }
+        }
+
+        for (target, damage) in damaged.iter() {
+            if let Some(hp) = hit_points.get_mut(*target) {
+                hp.apply_damage(*damage);
+            }
+        }
+    }
+}
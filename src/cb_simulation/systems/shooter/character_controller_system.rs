@@ -0,0 +1,106 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::character_controller::{self, CharacterIntent, Stance};
+use cb_simulation::components;
+
+use crate::cb_input;
+use cb_input::contexts::{CbInputContexts, SHOOTER_CONTEXT_ID};
+use cb_input::input_type::{Press, State};
+
+use specs::prelude::*;
+
+/// How far the view turns, in tenths of a degree, when the cursor is moved from the center to the edge of the window.
+const LOOK_SENSITIVITY: i32 = 900;
+
+/// Move first person characters from their player's shooter inputs.
+pub struct CharacterControllerSystem;
+
+impl<'a> System<'a> for CharacterControllerSystem {
+    type SystemData = (
+        ReadStorage<'a, components::actor_components::ActorComponent>,
+        ReadStorage<'a, components::voxel_components::VoxelComponent>,
+        WriteStorage<'a, components::shooter_components::CharacterControllerComponent>,
+        WriteStorage<'a, components::physics_components::TransformComponent>,
+    );
+
+    fn run(&mut self, (actors, voxels, mut controllers, mut transforms): Self::SystemData) {
+        let terrain = match (&voxels).join().next() {
+            Some(voxel) => &voxel.chunk_manager,
+            None => {
+                return;
+            }
+        };
+
+        for (actor, controller) in (&actors, &mut controllers).join() {
+            let intent = match actor.controller.current(SHOOTER_CONTEXT_ID) {
+                Some(context) => get_intent(&context),
+                None => CharacterIntent {
+                    stance: controller.state.stance,
+                    ..CharacterIntent::default()
+                },
+            };
+
+            character_controller::step(&mut controller.state, &intent, terrain);
+        }
+
+        // Keep the transforms in sync, so everything else sees where the characters are
+        for (controller, transform) in (&controllers, &mut transforms).join() {
+            transform.world_position.x = controller.state.position.x;
+            transform.world_position.y = controller.state.position.y;
+        }
+    }
+}
+
+fn get_intent(context: &CbInputContexts) -> CharacterIntent {
+    match *context {
+        CbInputContexts::ShooterContext {
+            networked: _,
+            jump,
+            crouching,
+            running,
+            prone,
+            move_forward,
+            move_backward,
+            move_left,
+            move_right,
+            look_x,
+            look_y,
+            fire: _,
+            fire_secondary: _,
+        } => {
+            let axis = |positive: State, negative: State| {
+                let mut value = 0;
+                if positive == State::On {
+                    value += 1;
+                }
+                if negative == State::On {
+                    value -= 1;
+                }
+                return value;
+            };
+
+            let stance = if prone == State::On {
+                Stance::Prone
+            } else if crouching == State::On {
+                Stance::Crouching
+            } else {
+                Stance::Standing
+            };
+
+            // Moving the cursor right turns clockwise, and down looks down
+            return CharacterIntent {
+                forward: axis(move_forward, move_backward),
+                strafe: axis(move_right, move_left),
+                running: running == State::On,
+                jump: jump == Press::Pressed,
+                stance: stance,
+                look_yaw: -look_x.value() * LOOK_SENSITIVITY / look_x.max(),
+                look_pitch: -look_y.value() * LOOK_SENSITIVITY / look_y.max(),
+            };
+        }
+        _ => {
+            return CharacterIntent::default();
+        }
+    }
+}
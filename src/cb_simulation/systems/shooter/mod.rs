@@ -0,0 +1,6 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+mod character_controller_system;
+mod weapon_system;
+pub use character_controller_system::CharacterControllerSystem;
+pub use weapon_system::WeaponSystem;
@@ -0,0 +1,105 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::actor_controller::{ActionId, ActorController};
+use cb_simulation::components;
+use cb_simulation::projectiles::Projectile;
+use components::projectile_components::ProjectileComponent;
+use components::shooter_components::Weapon;
+
+use crate::cb_input;
+use cb_input::contexts::{CbInputContexts, SHOOTER_CONTEXT_ID};
+use cb_input::input_type::Press;
+
+use specs::prelude::*;
+
+/// How many ticks a fire press is remembered while the weapon is cooling down.
+const FIRE_BUFFER_TICKS: usize = 4;
+
+const FIRE_PRIMARY: ActionId = 0;
+const FIRE_SECONDARY: ActionId = 1;
+
+/// Fire each character's weapons along its view, spawning a projectile for every shot.
+pub struct WeaponSystem;
+
+impl<'a> System<'a> for WeaponSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, components::actor_components::ActorComponent>,
+        ReadStorage<'a, components::shooter_components::CharacterControllerComponent>,
+        WriteStorage<'a, components::shooter_components::WeaponComponent>,
+        WriteStorage<'a, ProjectileComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut actors, controllers, mut weapons, mut projectiles): Self::SystemData,
+    ) {
+        let mut fired: Vec<ProjectileComponent> = vec![];
+
+        for (actor, controller, weapons) in (&mut actors, &controllers, &mut weapons).join() {
+            let origin = controller.state.eye_position();
+            let direction = controller.state.look_direction();
+
+            let mut shots = vec![];
+
+            if fire(&mut actor.controller, &mut weapons.primary, FIRE_PRIMARY) {
+                shots.push(weapons.primary.definition());
+            }
+
+            if let Some(secondary) = &mut weapons.secondary {
+                if fire(&mut actor.controller, secondary, FIRE_SECONDARY) {
+                    shots.push(secondary.definition());
+                }
+            }
+
+            for definition in shots.into_iter() {
+                let projectile = Projectile::new(definition, origin, direction);
+                fired.push(ProjectileComponent::new(projectile, actor.player_id));
+            }
+        }
+
+        for projectile in fired.into_iter() {
+            let entity = entities.create();
+            projectiles.insert(entity, projectile).unwrap();
+        }
+    }
+}
+
+/// Cool the weapon down, then return whether the action's button was pressed once it was ready. Presses made just
+/// before the weapon is ready are kept until it is, and each press fires once.
+fn fire(controller: &mut ActorController, weapon: &mut Weapon, action: ActionId) -> bool {
+    weapon.cooldown_remaining = weapon.cooldown_remaining.saturating_sub(1);
+
+    if weapon.cooldown_remaining > 0 {
+        return false;
+    }
+
+    let pressed = controller.consume_pressed_within(
+        SHOOTER_CONTEXT_ID,
+        action,
+        FIRE_BUFFER_TICKS,
+        |context| match context {
+            CbInputContexts::ShooterContext {
+                fire,
+                fire_secondary,
+                ..
+            } => {
+                let press = if action == FIRE_PRIMARY {
+                    fire
+                } else {
+                    fire_secondary
+                };
+
+                *press == Press::Pressed
+            }
+            _ => false,
+        },
+    );
+
+    if pressed {
+        weapon.cooldown_remaining = weapon.cooldown_ticks();
+    }
+
+    return pressed;
+}
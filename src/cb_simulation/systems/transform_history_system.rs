@@ -0,0 +1,117 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components::{gfx_components, physics_components};
+use gfx_components::{InterpolationComponent, TransformState};
+
+use crate::cb_system;
+use cb_system::GameUnit;
+
+use specs::prelude::*;
+
+/// Record each transform at the end of the tick, keeping the previous tick's for the renderer to move from.
+pub struct TransformHistorySystem;
+
+impl<'a> System<'a> for TransformHistorySystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, physics_components::TransformComponent>,
+        ReadStorage<'a, physics_components::RigidBodyComponent>,
+        WriteStorage<'a, InterpolationComponent>,
+    );
+
+    fn run(&mut self, (entities, transforms, rigid_bodies, mut interpolations): Self::SystemData) {
+        let zero = GameUnit::from_num(0);
+
+        let states: Vec<(Entity, TransformState)> = (&entities, &transforms, rigid_bodies.maybe())
+            .join()
+            .map(|(entity, transform, rigid_body)| {
+                let state = TransformState {
+                    world_position: transform.world_position,
+                    height: rigid_body.map(|r| r.body.position.z).unwrap_or(zero),
+                    rotation: transform.rotation,
+                    scale: transform.scale,
+                };
+
+                return (entity, state);
+            })
+            .collect();
+
+        for (entity, state) in states {
+            match interpolations.get_mut(entity) {
+                Some(interpolation) => interpolation.push(state),
+                None => {
+                    // Newly created entities appear where they are, rather than moving from somewhere
+                    if let Err(e) =
+                        interpolations.insert(entity, InterpolationComponent::new(state))
+                    {
+                        println!("Unable to interpolate entity: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cb_simulation::components::ComponentLinker;
+
+    fn world() -> World {
+        let mut world = World::new();
+        gfx_components::GfxComponentsLinker::register_components(&mut world);
+        physics_components::PhysicsComponentsLinker::register_components(&mut world);
+
+        return world;
+    }
+
+    #[test]
+    fn transform_history_system_keeps_previous_and_current_ticks() {
+        let mut world = world();
+        let entity = world
+            .create_entity()
+            .with(physics_components::TransformComponent::new())
+            .build();
+
+        TransformHistorySystem.run_now(&world);
+        {
+            let interpolations = world.read_storage::<InterpolationComponent>();
+            let interpolation = interpolations.get(entity).unwrap();
+            assert_eq!(interpolation.previous, interpolation.current);
+        }
+
+        world
+            .write_storage::<physics_components::TransformComponent>()
+            .get_mut(entity)
+            .unwrap()
+            .world_position
+            .x = GameUnit::from_num(300);
+        TransformHistorySystem.run_now(&world);
+
+        let interpolations = world.read_storage::<InterpolationComponent>();
+        let interpolation = interpolations.get(entity).unwrap();
+        assert_eq!(
+            GameUnit::from_num(0),
+            interpolation.previous.world_position.x
+        );
+        assert_eq!(
+            GameUnit::from_num(300),
+            interpolation.current.world_position.x
+        );
+    }
+
+    #[test]
+    fn transform_history_system_ignores_entities_without_transforms() {
+        let mut world = world();
+        let entity = world.create_entity().build();
+
+        TransformHistorySystem.run_now(&world);
+
+        assert!(world
+            .read_storage::<InterpolationComponent>()
+            .get(entity)
+            .is_none());
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use crate::cb_simulation;
+use cb_simulation::components;
+use cb_simulation::teams::CbTeams;
+use cb_simulation::{CbSystemValues, TICKS_PER_SECOND};
+
+use components::economy_components::HarvestOrder;
+use components::order_components::UnitOrder;
+use components::projectile_components::ProjectileComponent;
+
+use cb_simulation::projectiles::{self, Projectile, ProjectileDefinition, ProjectileTarget};
+
+use crate::cb_input;
+use cb_input::contexts::rts_command_context::{get_rts_commands, CbRtsCommand};
+use cb_input::contexts::RTS_COMMAND_CONTEXT_ID;
+
+use crate::cb_system;
+use cb_system::{Coordinate2d, GameUnit};
+
+use specs::prelude::*;
+
+/// How fast units' shots fly, in game units per tick.
+const SHOT_SPEED: i32 = 150;
+
+/// Apply move orders, then move units and have them fire projectiles at enemies within range.
+pub struct UnitOrderSystem;
+
+impl<'a> System<'a> for UnitOrderSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, CbSystemValues>,
+        Read<'a, CbTeams>,
+        ReadStorage<'a, components::ownership_components::OwnershipComponent>,
+        ReadStorage<'a, components::character_components::MoveSpeedComponent>,
+        WriteStorage<'a, components::character_components::RangedAttackComponent>,
+        ReadStorage<'a, components::character_components::HitPointsComponent>,
+        ReadStorage<'a, components::voxel_components::VoxelComponent>,
+        WriteStorage<'a, components::economy_components::WorkerComponent>,
+        WriteStorage<'a, components::order_components::UnitOrderComponent>,
+        WriteStorage<'a, components::physics_components::TransformComponent>,
+        WriteStorage<'a, ProjectileComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            sys_values,
+            teams,
+            ownerships,
+            move_speeds,
+            mut ranged_attacks,
+            hit_points,
+            voxels,
+            mut workers,
+            mut orders,
+            mut transforms,
+            mut projectiles,
+        ): Self::SystemData,
+    ) {
+        // Commanded orders, processing players in a fixed order
+        let mut inputs: Vec<&cb_input::CbGameInput> = sys_values.world_inputs.iter().collect();
+        inputs.sort_by_key(|i| i.player_id);
+
+        for input in inputs.iter() {
+            let context = match input.context_manager.get_context(RTS_COMMAND_CONTEXT_ID) {
+                Some(context) => context,
+                None => {
+                    continue;
+                }
+            };
+
+            for command in get_rts_commands(&context).iter() {
+                // Targets outside of what a game unit can hold are skipped
+                let (unit, order) = match *command {
+                    CbRtsCommand::Move { unit, x, y } => match Coordinate2d::checked_from_num(x, y)
+                    {
+                        Some(target) => (unit, UnitOrder::Move { target: target }),
+                        None => {
+                            continue;
+                        }
+                    },
+                    CbRtsCommand::AttackMove { unit, x, y } => {
+                        match Coordinate2d::checked_from_num(x, y) {
+                            Some(target) => (unit, UnitOrder::AttackMove { target: target }),
+                            None => {
+                                continue;
+                            }
+                        }
+                    }
+                    _ => {
+                        continue;
+                    }
+                };
+
+                let entity = entities.entity(unit);
+                let owned = entities.is_alive(entity)
+                    && ownerships
+                        .get(entity)
+                        .map_or(false, |o| o.player_id == input.player_id);
+
+                if !owned || move_speeds.get(entity).is_none() {
+                    continue;
+                }
+
+                if let Some(unit_order) = orders.get_mut(entity) {
+                    unit_order.order = order;
+                }
+
+                // Moving a worker takes it off of harvesting
+                if let Some(worker) = workers.get_mut(entity) {
+                    worker.order = HarvestOrder::Idle;
+                }
+            }
+        }
+
+        // Everything that may be fired upon
+        let targets: Vec<(usize, Coordinate2d)> = (&ownerships, &hit_points, &transforms)
+            .join()
+            .filter(|(_, hp, _)| !hp.is_dead())
+            .map(|(ownership, _, transform)| (ownership.player_id, transform.world_position))
+            .collect();
+
+        // Shots leave from the middle of the unit and are aimed at the middle of the target
+        let terrain = (&voxels).join().next().map(|voxel| &voxel.chunk_manager);
+        let center = |position: Coordinate2d| {
+            let ground = terrain.map_or(GameUnit::from_num(0), |terrain| {
+                projectiles::ground_height(terrain, position)
+            });
+
+            return ProjectileTarget::unit(position, ground).center();
+        };
+
+        let mut fired: Vec<ProjectileComponent> = vec![];
+
+        for (entity, ownership, unit_order, transform) in
+            (&entities, &ownerships, &mut orders, &mut transforms).join()
+        {
+            let position = transform.world_position;
+
+            // Fire on the nearest enemy in range; ties go to the first found, so results are deterministic
+            let mut attacking = false;
+            if let Some(ranged_attack) = ranged_attacks.get_mut(entity) {
+                ranged_attack.cooldown_remaining =
+                    ranged_attack.cooldown_remaining.saturating_sub(1);
+
+                let can_attack = match unit_order.order {
+                    UnitOrder::Move { target: _ } => false,
+                    _ => true,
+                };
+
+                if can_attack {
+                    let range = ranged_attack.range().to_num::<i64>();
+                    let mut nearest: Option<(i64, Coordinate2d)> = None;
+
+                    for (player_id, target_position) in targets.iter() {
+                        if !teams.are_players_enemies(ownership.player_id, *player_id) {
+                            continue;
+                        }
+
+                        let distance = position.distance_squared(target_position);
+
+                        if distance <= range * range
+                            && (nearest.is_none() || distance < nearest.unwrap().0)
+                        {
+                            nearest = Some((distance, *target_position));
+                        }
+                    }
+
+                    if let Some((_, target_position)) = nearest {
+                        attacking = true;
+
+                        if ranged_attack.cooldown_remaining == 0 {
+                            let rate_of_fire = ranged_attack.rate_of_fire().to_num::<u32>().max(1);
+                            ranged_attack.cooldown_remaining = TICKS_PER_SECOND / rate_of_fire;
+
+                            let origin = center(position);
+                            if let Some(direction) =
+                                projectiles::aim(origin, center(target_position))
+                            {
+                                // Shots fly twice the range, so targets above or below are still reached
+                                let lifetime = (range as u32 * 2) / SHOT_SPEED as u32 + 1;
+                                let definition = ProjectileDefinition::linear(
+                                    ranged_attack.damage(),
+                                    GameUnit::from_num(SHOT_SPEED),
+                                    lifetime,
+                                );
+
+                                fired.push(ProjectileComponent::new(
+                                    Projectile::new(definition, origin, direction),
+                                    ownership.player_id,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if attacking {
+                continue;
+            }
+
+            let speed = match move_speeds.get(entity) {
+                Some(speed) => GameUnit::from_num(speed.value),
+                None => GameUnit::from_num(0),
+            };
+
+            match unit_order.order {
+                UnitOrder::Idle => {}
+                UnitOrder::Move { target } | UnitOrder::AttackMove { target } => {
+                    transform.world_position = position.move_towards(&target, speed);
+
+                    if transform.world_position == target {
+                        unit_order.order = UnitOrder::Idle;
+                    }
+                }
+            }
+        }
+
+        for projectile in fired.into_iter() {
+            let entity = entities.create();
+            projectiles.insert(entity, projectile).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cb_simulation::ai;
+    use components::ComponentLinker;
+
+    use crate::cb_math;
+    use cb_math::FUint;
+
+    fn world() -> World {
+        let mut world = World::new();
+        components::character_components::ComponentsLinker::register_components(&mut world);
+        components::economy_components::EconomyComponentsLinker::register_components(&mut world);
+        components::order_components::OrderComponentsLinker::register_components(&mut world);
+        components::ownership_components::OwnershipComponentsLinker::register_components(
+            &mut world,
+        );
+        components::physics_components::PhysicsComponentsLinker::register_components(&mut world);
+        components::projectile_components::ProjectileComponentsLinker::register_components(
+            &mut world,
+        );
+        components::voxel_components::VoxelComponentsLinker::register_components(&mut world);
+        world.insert(CbTeams::new());
+
+        return world;
+    }
+
+    fn order_of(world: &World, entity: Entity) -> UnitOrder {
+        return world
+            .read_storage::<components::order_components::UnitOrderComponent>()
+            .get(entity)
+            .unwrap()
+            .order;
+    }
+
+    #[test]
+    fn unit_order_system_skips_move_outside_game_unit_range() {
+        let mut world = world();
+        let unit = world
+            .create_entity()
+            .with(components::ownership_components::OwnershipComponent::new(0))
+            .with(components::character_components::MoveSpeedComponent::new(
+                FUint::from_num(0),
+            ))
+            .with(components::order_components::UnitOrderComponent::new())
+            .with(components::physics_components::TransformComponent::new())
+            .build();
+
+        let out_of_range = vec![
+            CbRtsCommand::Move {
+                unit: unit.id(),
+                x: i32::MAX,
+                y: 0,
+            },
+            CbRtsCommand::AttackMove {
+                unit: unit.id(),
+                x: 0,
+                y: i32::MIN,
+            },
+        ];
+        world.insert(CbSystemValues::from(
+            vec![ai::build_input(0, &out_of_range)],
+            0,
+            0,
+        ));
+        UnitOrderSystem.run_now(&world);
+
+        assert_eq!(UnitOrder::Idle, order_of(&world, unit));
+
+        let in_range = vec![CbRtsCommand::Move {
+            unit: unit.id(),
+            x: 3000,
+            y: 0,
+        }];
+        world.insert(CbSystemValues::from(
+            vec![ai::build_input(0, &in_range)],
+            0,
+            0,
+        ));
+        UnitOrderSystem.run_now(&world);
+
+        assert_eq!(
+            UnitOrder::Move {
+                target: Coordinate2d::new(GameUnit::from_num(3000), GameUnit::from_num(0))
+            },
+            order_of(&world, unit)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+pub type TeamId = usize;
+
+/// The team and alliance tables for a match. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CbTeams {
+    players: Vec<(usize, TeamId)>,
+    alliances: Vec<(TeamId, TeamId)>,
+}
+
+impl CbTeams {
+    pub fn new() -> Self {
+        return Self {
+            players: vec![],
+            alliances: vec![],
+        };
+    }
+
+    /// Assign the player to the team, replacing any previous assignment.
+    pub fn add_player(&mut self, player_id: usize, team_id: TeamId) {
+        self.players.retain(|(p, _)| *p != player_id);
+        self.players.push((player_id, team_id));
+        self.players.sort();
+    }
+
+    pub fn team_of(&self, player_id: usize) -> Option<TeamId> {
+        return self
+            .players
+            .iter()
+            .find(|(p, _)| *p == player_id)
+            .map(|(_, t)| *t);
+    }
+
+    pub fn players(&self) -> Vec<usize> {
+        return self.players.iter().map(|(p, _)| *p).collect();
+    }
+
+    pub fn players_on_team(&self, team_id: TeamId) -> Vec<usize> {
+        return self
+            .players
+            .iter()
+            .filter(|(_, t)| *t == team_id)
+            .map(|(p, _)| *p)
+            .collect();
+    }
+
+    /// Returns all teams with at least one player, in ascending order.
+    pub fn teams(&self) -> Vec<TeamId> {
+        let mut teams: Vec<TeamId> = self.players.iter().map(|(_, t)| *t).collect();
+        teams.sort();
+        teams.dedup();
+
+        return teams;
+    }
+
+    pub fn add_alliance(&mut self, team_a: TeamId, team_b: TeamId) {
+        if !self.are_teams_allied(team_a, team_b) {
+            self.alliances.push((team_a, team_b));
+        }
+    }
+
+    pub fn remove_alliance(&mut self, team_a: TeamId, team_b: TeamId) {
+        self.alliances
+            .retain(|(a, b)| !((*a == team_a && *b == team_b) || (*a == team_b && *b == team_a)));
+    }
+
+    /// Teams are always allied with themselves.
+    pub fn are_teams_allied(&self, team_a: TeamId, team_b: TeamId) -> bool {
+        if team_a == team_b {
+            return true;
+        }
+
+        return self
+            .alliances
+            .iter()
+            .any(|(a, b)| (*a == team_a && *b == team_b) || (*a == team_b && *b == team_a));
+    }
+
+    /// Players without a team are not allied with anyone but themselves.
+    pub fn are_players_allied(&self, player_a: usize, player_b: usize) -> bool {
+        if player_a == player_b {
+            return true;
+        }
+
+        match (self.team_of(player_a), self.team_of(player_b)) {
+            (Some(a), Some(b)) => self.are_teams_allied(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn are_players_enemies(&self, player_a: usize, player_b: usize) -> bool {
+        return !self.are_players_allied(player_a, player_b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cb_teams_add_player_twice_replaces_team() {
+        let mut teams = CbTeams::new();
+        teams.add_player(1, 0);
+        teams.add_player(1, 2);
+
+        assert_eq!(Some(2), teams.team_of(1));
+        assert_eq!(vec![2], teams.teams());
+    }
+
+    #[test]
+    fn cb_teams_are_players_allied_same_team_returns_true() {
+        let mut teams = CbTeams::new();
+        teams.add_player(1, 0);
+        teams.add_player(2, 0);
+        teams.add_player(3, 1);
+
+        assert_eq!(true, teams.are_players_allied(1, 2));
+        assert_eq!(false, teams.are_players_allied(1, 3));
+    }
+
+    #[test]
+    fn cb_teams_are_players_allied_with_alliance_returns_true() {
+        let mut teams = CbTeams::new();
+        teams.add_player(1, 0);
+        teams.add_player(2, 1);
+        teams.add_alliance(1, 0);
+
+        assert_eq!(true, teams.are_players_allied(1, 2));
+
+        teams.remove_alliance(0, 1);
+
+        assert_eq!(true, teams.are_players_enemies(1, 2));
+    }
+
+    #[test]
+    fn cb_teams_unassigned_player_is_enemy() {
+        let mut teams = CbTeams::new();
+        teams.add_player(1, 0);
+
+        assert_eq!(true, teams.are_players_enemies(1, 5));
+    }
+}
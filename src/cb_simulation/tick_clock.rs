@@ -0,0 +1,92 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    The simulation advances at a fixed tick rate, while frames are drawn as often as the display allows. Entities and
+    cameras keep their state from the previous tick as well as the current one, and each frame is drawn the alpha of
+    the way between the two, where the alpha is how much of a tick has passed on the wall clock since the last one.
+
+    This means what's drawn trails the simulation by up to a tick, in exchange for moving smoothly at any refresh rate.
+*/
+
+use std::time::{Duration, Instant};
+
+/// Tracks when ticks were simulated, to tell how far a frame is between the previous tick and the current one.
+pub struct CbTickClock {
+    tick_duration: Duration,
+    last_tick_at: Instant,
+}
+
+impl CbTickClock {
+    pub fn new(ticks_per_second: u32, now: Instant) -> Self {
+        return Self {
+            tick_duration: Duration::from_secs(1) / ticks_per_second,
+            last_tick_at: now,
+        };
+    }
+
+    /// Record that a tick was simulated.
+    pub fn tick(&mut self, now: Instant) {
+        self.last_tick_at = now;
+    }
+
+    /// How far the frame drawn now is from the previous tick towards the current one, from 0 to 1. Holds at 1 when
+    /// the next tick is late, rather than guessing where things are heading.
+    pub fn alpha(&self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.last_tick_at);
+        let alpha = elapsed.as_secs_f32() / self.tick_duration.as_secs_f32();
+
+        return alpha.min(1.0);
+    }
+}
+
+/// The alpha of the frame being drawn. Stored as a world resource for the graphics systems.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct CbFrameAlpha {
+    alpha: f32,
+}
+
+impl CbFrameAlpha {
+    pub fn new(alpha: f32) -> Self {
+        return Self { alpha: alpha };
+    }
+
+    pub fn get(&self) -> f32 {
+        return self.alpha;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_clock_alpha_is_fraction_of_tick_since_last() {
+        let start = Instant::now();
+        let clock = CbTickClock::new(50, start);
+
+        assert_eq!(0.0, clock.alpha(start));
+        assert!((clock.alpha(start + Duration::from_millis(5)) - 0.25).abs() < 0.001);
+        assert!((clock.alpha(start + Duration::from_millis(15)) - 0.75).abs() < 0.001);
+    }
+
+    #[test]
+    fn tick_clock_alpha_holds_when_tick_is_late() {
+        let start = Instant::now();
+        let clock = CbTickClock::new(50, start);
+
+        assert_eq!(1.0, clock.alpha(start + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn tick_clock_restarts_alpha_on_tick() {
+        let start = Instant::now();
+        let mut clock = CbTickClock::new(50, start);
+
+        clock.tick(start + Duration::from_millis(30));
+
+        assert_eq!(0.0, clock.alpha(start + Duration::from_millis(30)));
+        // A frame from before the tick doesn't go backwards past it
+        assert_eq!(0.0, clock.alpha(start + Duration::from_millis(10)));
+        assert!((clock.alpha(start + Duration::from_millis(40)) - 0.5).abs() < 0.001);
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    Animation sets for RTS units, imported from models. Walkers blend from their idle clip to their walk cycle by how
+    far they moved on the last tick, so a unit's legs only swing while it is moving and swing fully at walking pace.
+*/
+
+use std::path::Path;
+
+use crate::cb_animation;
+use cb_animation::state_machine::{AnimationStateMachine, BlendNode};
+use cb_animation::{AnimationLibrary, AnimationSet};
+
+use crate::cb_graphics;
+use cb_graphics::models;
+
+pub const WALKER_MODEL_PATH: &'static str = "./src/assets/walker.gltf";
+
+/// The walker's set is named after its model file.
+pub const WALKER_SET: &'static str = "walker";
+
+/// The animator parameter holding the game units moved on the last tick.
+pub const SPEED_PARAMETER: &'static str = "speed";
+
+/// The speed, in game units per tick, at which the walk cycle plays without any idle mixed in.
+pub const WALK_SPEED: f32 = 10.0;
+
+/// Load every unit animation set.
+pub fn load_library() -> Result<AnimationLibrary, String> {
+    let mut library = AnimationLibrary::new();
+
+    let model = models::from_path(Path::new(WALKER_MODEL_PATH))?;
+    library.add(walker_set(model.animation_set()?)?)?;
+
+    return Ok(library);
+}
+
+/// Replace an imported set's states with a single state that blends its 'Idle' and 'Walk' clips by speed.
+pub fn walker_set(mut set: AnimationSet) -> Result<AnimationSet, String> {
+    let clip = |name: &str| match set.find_clip(name) {
+        Some(clip) => Ok(BlendNode::Clip(clip)),
+        None => Err(format!(
+            "Animation set '{}' has no '{}' clip to walk with.",
+            set.name, name
+        )),
+    };
+
+    let idle = clip("Idle")?;
+    let walk = clip("Walk")?;
+
+    set.state_machine = AnimationStateMachine::new();
+    set.state_machine.add_state(
+        "Locomotion",
+        BlendNode::blend_1d(SPEED_PARAMETER, vec![(0.0, idle), (WALK_SPEED, walk)]),
+    );
+
+    return Ok(set);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_library_imports_walker() {
+        let library = load_library().unwrap();
+
+        let set = library.get(library.index_of(WALKER_SET).unwrap()).unwrap();
+        assert_eq!(2, set.skeleton.len());
+        assert_eq!(1, set.state_machine.states.len());
+    }
+
+    #[test]
+    fn walker_set_without_walk_clip_errors() {
+        let mut set = load_library().unwrap().get(0).unwrap().clone();
+        set.clips.retain(|clip| clip.name != "Walk");
+
+        assert!(walker_set(set).is_err());
+    }
+}
@@ -0,0 +1,642 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use std::path::Path;
+
+use crate::cb_assets::Asset;
+
+use crate::cb_datasheets;
+use cb_datasheets::DataValue;
+
+use crate::cb_math;
+use cb_math::FUint;
+
+use super::economy::Resources;
+
+pub const UNIT_DEFINITIONS_PATH: &'static str = "./src/cb_simulation/datasheets/units.hjson";
+
+/// The largest integer a stat may have; FUint only has 20 integer bits.
+const MAX_STAT_VALUE: u32 = (1 << 20) - 1;
+
+/// The index of a unit type in the UnitDefinitions. Stable for a given datasheet, so it may be sent over the network.
+pub type UnitTypeId = usize;
+
+/// A component and its stats, as described in the unit datasheet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitComponentDefinition {
+    Armor {
+        value: FUint,
+        max: FUint,
+    },
+    HitPoints {
+        value: FUint,
+        max: FUint,
+    },
+    MoveSpeed {
+        value: FUint,
+    },
+    RangedAttack {
+        rate_of_fire: FUint,
+        range: FUint,
+        damage: FUint,
+    },
+    UnitBase {
+        base_size: FUint,
+    },
+    Sight {
+        range: FUint,
+    },
+    Worker {
+        carry_capacity: u32,
+        gather_ticks: u32,
+    },
+    DropOff,
+    Production {
+        queue_size: u32,
+        units: Vec<String>,
+    },
+    SupplyProvider {
+        amount: u32,
+    },
+    Abilities {
+        max_energy: u32,
+        energy_regen: u32,
+        names: Vec<String>,
+    },
+    Sprite,
+    /// Lengths are in game units.
+    Legs {
+        count: u32,
+        length: FUint,
+        hip_height: FUint,
+        stance_width: FUint,
+    },
+}
+
+/// What it takes to produce a unit.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct UnitCost {
+    pub resources: Resources,
+    pub supply: u32,
+    pub build_ticks: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitDefinition {
+    pub name: String,
+    pub cost: UnitCost,
+    pub components: Vec<UnitComponentDefinition>,
+}
+
+/// The set of unit types that may be spawned. Stored as a world resource.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UnitDefinitions {
+    definitions: Vec<UnitDefinition>,
+}
+
+impl UnitDefinitions {
+    pub fn new() -> Self {
+        return Self {
+            definitions: vec![],
+        };
+    }
+
+    /// Load and validate the unit definitions at the given path.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let root = cb_datasheets::from_path(path)?;
+
+        return Self::from_data(&root).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    /// Parse and validate unit definitions from a datasheet string.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root = cb_datasheets::parse(source)?;
+
+        return Self::from_data(&root);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&UnitDefinition> {
+        return self.definitions.iter().find(|d| d.name == name);
+    }
+
+    pub fn get_by_id(&self, unit_type: UnitTypeId) -> Option<&UnitDefinition> {
+        return self.definitions.get(unit_type);
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<UnitTypeId> {
+        return self.definitions.iter().position(|d| d.name == name);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        return self.definitions.iter().map(|d| d.name.as_str()).collect();
+    }
+
+    fn from_data(root: &DataValue) -> Result<Self, String> {
+        let units = match root.get("units").and_then(|u| u.as_array()) {
+            Some(units) => units,
+            None => {
+                return Err("expected a 'units' array".to_string());
+            }
+        };
+
+        let mut definitions = Self::new();
+
+        for unit in units.iter() {
+            let definition = parse_unit(unit)?;
+
+            if definitions.get(&definition.name).is_some() {
+                return Err(format!("duplicate unit '{}'", definition.name));
+            }
+
+            definitions.definitions.push(definition);
+        }
+
+        // Production may only reference units that exist
+        for definition in definitions.definitions.iter() {
+            for component in definition.components.iter() {
+                if let UnitComponentDefinition::Production {
+                    queue_size: _,
+                    units,
+                } = component
+                {
+                    for unit in units.iter() {
+                        if definitions.get(unit).is_none() {
+                            return Err(format!(
+                                "unit '{}': production references unknown unit '{}'",
+                                definition.name, unit
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        return Ok(definitions);
+    }
+}
+
+impl Asset for UnitDefinitions {
+    fn load(path: &Path) -> Result<Self, String> {
+        return Self::from_path(path);
+    }
+}
+
+fn parse_unit(unit: &DataValue) -> Result<UnitDefinition, String> {
+    let name = match unit.get("name").and_then(|n| n.as_str()) {
+        Some(name) => name.to_string(),
+        None => {
+            return Err("each unit requires a 'name'".to_string());
+        }
+    };
+
+    for (key, _) in unit.as_object().unwrap().iter() {
+        if key != "name" && key != "cost" && key != "components" {
+            return Err(format!("unit '{}': unknown field '{}'", name, key));
+        }
+    }
+
+    let components = match unit.get("components").and_then(|c| c.as_object()) {
+        Some(c) => c,
+        None => {
+            return Err(format!("unit '{}': expected a 'components' object", name));
+        }
+    };
+
+    let cost = match unit.get("cost") {
+        Some(cost) => {
+            let f = read_fields(
+                &name,
+                "cost",
+                cost,
+                &["ore", "crystal", "supply", "build_ticks"],
+            )?;
+
+            UnitCost {
+                resources: Resources::new(f[0].to_num::<u32>(), f[1].to_num::<u32>()),
+                supply: f[2].to_num::<u32>(),
+                build_ticks: f[3].to_num::<u32>(),
+            }
+        }
+        None => UnitCost::default(),
+    };
+
+    let mut definition = UnitDefinition {
+        name: name,
+        cost: cost,
+        components: vec![],
+    };
+
+    for (component_name, value) in components.iter() {
+        let component = parse_component(&definition.name, component_name, value)?;
+        definition.components.push(component);
+    }
+
+    return Ok(definition);
+}
+
+fn parse_component(
+    unit: &str,
+    component: &str,
+    value: &DataValue,
+) -> Result<UnitComponentDefinition, String> {
+    match component {
+        "armor" => {
+            let f = read_fields(unit, component, value, &["value", "max"])?;
+            validate_not_above_max(unit, component, f[0], f[1])?;
+
+            return Ok(UnitComponentDefinition::Armor {
+                value: f[0],
+                max: f[1],
+            });
+        }
+        "hit_points" => {
+            let f = read_fields(unit, component, value, &["value", "max"])?;
+            validate_not_above_max(unit, component, f[0], f[1])?;
+
+            return Ok(UnitComponentDefinition::HitPoints {
+                value: f[0],
+                max: f[1],
+            });
+        }
+        "move_speed" => {
+            let f = read_fields(unit, component, value, &["value"])?;
+
+            return Ok(UnitComponentDefinition::MoveSpeed { value: f[0] });
+        }
+        "ranged_attack" => {
+            let f = read_fields(unit, component, value, &["rate_of_fire", "range", "damage"])?;
+
+            return Ok(UnitComponentDefinition::RangedAttack {
+                rate_of_fire: f[0],
+                range: f[1],
+                damage: f[2],
+            });
+        }
+        "unit_base" => {
+            let f = read_fields(unit, component, value, &["base_size"])?;
+
+            return Ok(UnitComponentDefinition::UnitBase { base_size: f[0] });
+        }
+        "sight" => {
+            let f = read_fields(unit, component, value, &["range"])?;
+
+            return Ok(UnitComponentDefinition::Sight { range: f[0] });
+        }
+        "worker" => {
+            let f = read_fields(unit, component, value, &["carry_capacity", "gather_ticks"])?;
+
+            if f[1] == FUint::from_num(0) {
+                return Err(format!(
+                    "unit '{}': component '{}' field 'gather_ticks' must be above 0",
+                    unit, component
+                ));
+            }
+
+            return Ok(UnitComponentDefinition::Worker {
+                carry_capacity: f[0].to_num::<u32>(),
+                gather_ticks: f[1].to_num::<u32>(),
+            });
+        }
+        "drop_off" => {
+            read_fields(unit, component, value, &[])?;
+
+            return Ok(UnitComponentDefinition::DropOff);
+        }
+        "production" => {
+            let (f, units) =
+                read_fields_with_names(unit, component, value, &["queue_size"], "units")?;
+
+            return Ok(UnitComponentDefinition::Production {
+                queue_size: f[0].to_num::<u32>(),
+                units: units,
+            });
+        }
+        "abilities" => {
+            let (f, names) = read_fields_with_names(
+                unit,
+                component,
+                value,
+                &["max_energy", "energy_regen"],
+                "names",
+            )?;
+
+            return Ok(UnitComponentDefinition::Abilities {
+                max_energy: f[0].to_num::<u32>(),
+                energy_regen: f[1].to_num::<u32>(),
+                names: names,
+            });
+        }
+        "supply_provider" => {
+            let f = read_fields(unit, component, value, &["amount"])?;
+
+            return Ok(UnitComponentDefinition::SupplyProvider {
+                amount: f[0].to_num::<u32>(),
+            });
+        }
+        "sprite" => {
+            read_fields(unit, component, value, &[])?;
+
+            return Ok(UnitComponentDefinition::Sprite);
+        }
+        "legs" => {
+            let f = read_fields(
+                unit,
+                component,
+                value,
+                &["count", "length", "hip_height", "stance_width"],
+            )?;
+
+            if f[0] < FUint::from_num(2) || f[0].to_num::<u32>() % 2 != 0 {
+                return Err(format!(
+                    "unit '{}': component '{}' field 'count' must be an even number of at least 2",
+                    unit, component
+                ));
+            }
+
+            if f[2] >= f[1] {
+                return Err(format!(
+                    "unit '{}': component '{}' field 'hip_height' must be below 'length'",
+                    unit, component
+                ));
+            }
+
+            return Ok(UnitComponentDefinition::Legs {
+                count: f[0].to_num::<u32>(),
+                length: f[1],
+                hip_height: f[2],
+                stance_width: f[3],
+            });
+        }
+        _ => {
+            return Err(format!(
+                "unit '{}': unknown component '{}'",
+                unit, component
+            ));
+        }
+    }
+}
+
+/// Read the given numeric fields, plus a list of names, from a component object.
+fn read_fields_with_names(
+    unit: &str,
+    component: &str,
+    value: &DataValue,
+    fields: &[&str],
+    names_field: &str,
+) -> Result<(Vec<FUint>, Vec<String>), String> {
+    let list = match value.get(names_field).and_then(|u| u.as_array()) {
+        Some(list) => list,
+        None => {
+            return Err(format!(
+                "unit '{}': component '{}' requires a '{}' array",
+                unit, component, names_field
+            ));
+        }
+    };
+
+    let mut names = vec![];
+    for name in list.iter() {
+        match name.as_str() {
+            Some(name) => names.push(name.to_string()),
+            None => {
+                return Err(format!(
+                    "unit '{}': component '{}' field '{}' must only contain names",
+                    unit, component, names_field
+                ));
+            }
+        }
+    }
+
+    // Validate the remaining fields without the list
+    let mut members = value.as_object().unwrap().clone();
+    members.retain(|(k, _)| k != names_field);
+    let values = read_fields(unit, component, &DataValue::Object(members), fields)?;
+
+    return Ok((values, names));
+}
+
+/// Read the given fields from a component object, in order. Errors on missing, unknown or invalid fields.
+fn read_fields(
+    unit: &str,
+    component: &str,
+    value: &DataValue,
+    fields: &[&str],
+) -> Result<Vec<FUint>, String> {
+    let members = match value.as_object() {
+        Some(m) => m,
+        None => {
+            return Err(format!(
+                "unit '{}': component '{}' must be an object",
+                unit, component
+            ));
+        }
+    };
+
+    for (key, _) in members.iter() {
+        if !fields.iter().any(|f| f == key) {
+            return Err(format!(
+                "unit '{}': component '{}' has unknown field '{}'",
+                unit, component, key
+            ));
+        }
+    }
+
+    let mut values = vec![];
+
+    for field in fields.iter() {
+        let raw = match value.get(field) {
+            Some(raw) => raw,
+            None => {
+                return Err(format!(
+                    "unit '{}': component '{}' is missing field '{}'",
+                    unit, component, field
+                ));
+            }
+        };
+
+        let v = match raw.as_u32() {
+            Some(v) if v <= MAX_STAT_VALUE => v,
+            _ => {
+                return Err(format!(
+                    "unit '{}': component '{}' field '{}' must be an integer between 0 and {}, found '{}'",
+                    unit,
+                    component,
+                    field,
+                    MAX_STAT_VALUE,
+                    raw.as_str().unwrap_or("<non-scalar>")
+                ));
+            }
+        };
+
+        values.push(FUint::from_num(v));
+    }
+
+    return Ok(values);
+}
+
+fn validate_not_above_max(
+    unit: &str,
+    component: &str,
+    value: FUint,
+    max: FUint,
+) -> Result<(), String> {
+    if value > max {
+        return Err(format!(
+            "unit '{}': component '{}' value {} exceeds max {}",
+            unit, component, value, max
+        ));
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_definitions_parse_valid_unit_returns_components() {
+        let source = "units: [
+            {
+                name: infantry
+                components: {
+                    armor: { value: 20, max: 20 }
+                    move_speed: { value: 10 }
+                    sprite: {}
+                }
+            }
+        ]";
+
+        let actual = UnitDefinitions::parse(source).unwrap();
+        let infantry = actual.get("infantry").unwrap();
+
+        assert_eq!(
+            vec![
+                UnitComponentDefinition::Armor {
+                    value: FUint::from_num(20),
+                    max: FUint::from_num(20)
+                },
+                UnitComponentDefinition::MoveSpeed {
+                    value: FUint::from_num(10)
+                },
+                UnitComponentDefinition::Sprite
+            ],
+            infantry.components
+        );
+    }
+
+    #[test]
+    fn unit_definitions_parse_odd_leg_count_returns_err() {
+        let source = "units: [ { name: crawler, components: {
+            legs: { count: 5, length: 400, hip_height: 300, stance_width: 300 }
+        } } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert_eq!(
+            Err("unit 'crawler': component 'legs' field 'count' must be an even number of at least 2".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn unit_definitions_parse_unknown_component_returns_err() {
+        let source = "units: [ { name: infantry, components: { armour: { value: 1, max: 1 } } } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert_eq!(
+            Err("unit 'infantry': unknown component 'armour'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn unit_definitions_parse_negative_value_returns_err() {
+        let source = "units: [ { name: infantry, components: { move_speed: { value: -1 } } } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn unit_definitions_parse_missing_field_returns_err() {
+        let source = "units: [ { name: infantry, components: { hit_points: { value: 1 } } } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert_eq!(
+            Err("unit 'infantry': component 'hit_points' is missing field 'max'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn unit_definitions_parse_value_above_max_returns_err() {
+        let source = "units: [ { name: infantry, components: { armor: { value: 30, max: 20 } } } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn unit_definitions_parse_duplicate_unit_returns_err() {
+        let source = "units: [ { name: a, components: {} }, { name: a, components: {} } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert_eq!(Err("duplicate unit 'a'".to_string()), actual);
+    }
+
+    #[test]
+    fn unit_definitions_parse_cost_and_production_returns_expected() {
+        let source = "units: [
+            { name: worker, cost: { ore: 50, crystal: 5, supply: 1, build_ticks: 60 }, components: {} }
+            { name: hq, components: { production: { queue_size: 5, units: [worker] } } }
+        ]";
+
+        let actual = UnitDefinitions::parse(source).unwrap();
+
+        assert_eq!(
+            UnitCost {
+                resources: Resources::new(50, 5),
+                supply: 1,
+                build_ticks: 60
+            },
+            actual.get("worker").unwrap().cost
+        );
+        assert_eq!(UnitCost::default(), actual.get("hq").unwrap().cost);
+        assert_eq!(
+            vec![UnitComponentDefinition::Production {
+                queue_size: 5,
+                units: vec!["worker".to_string()]
+            }],
+            actual.get("hq").unwrap().components
+        );
+        assert_eq!(Some(1), actual.id_of("hq"));
+    }
+
+    #[test]
+    fn unit_definitions_parse_production_of_unknown_unit_returns_err() {
+        let source =
+            "units: [ { name: hq, components: { production: { queue_size: 5, units: [tank] } } } ]";
+
+        let actual = UnitDefinitions::parse(source);
+
+        assert_eq!(
+            Err("unit 'hq': production references unknown unit 'tank'".to_string()),
+            actual
+        );
+    }
+
+    #[test]
+    fn unit_definitions_from_path_shipped_datasheet_is_valid() {
+        let actual = UnitDefinitions::from_path(Path::new(UNIT_DEFINITIONS_PATH));
+
+        assert!(actual.is_ok(), "{:?}", actual.err());
+        let definitions = actual.unwrap();
+        assert!(definitions.get("infantry").is_some());
+        assert!(definitions.get("headquarters").is_some());
+    }
+}
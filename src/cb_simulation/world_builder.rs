@@ -3,12 +3,49 @@ use specs::prelude::*;
 
 use super::*;
 
+use std::path::Path;
+
 use components;
 use components::{
-    actor_components, audio, character_components, editor_components, gfx_components,
-    ik_components, menu_components, physics_components, voxel_components, ComponentLinker,
+    ability_components, actor_components, animation_components, audio, character_components,
+    economy_components, editor_components, fighting_components, gfx_components, ik_components,
+    match_components, menu_components, order_components, ownership_components, physics_components,
+    projectile_components, shooter_components, voxel_components, ComponentLinker,
 };
 
+/// The players in a default match, as (player id, team id).
+const DEFAULT_PLAYERS: [(usize, teams::TeamId); 2] = [(0, 0), (1, 1)];
+
+/// The resources each player starts a match with, as (ore, crystal).
+const STARTING_RESOURCES: (u32, u32) = (200, 0);
+
+/// The resource nodes placed next to each player's start, as (resource, amount, x offset, y offset) in voxels.
+const START_RESOURCE_NODES: [(economy::ResourceType, u32, i32, i32); 3] = [
+    (economy::ResourceType::Ore, 1500, 3, 0),
+    (economy::ResourceType::Ore, 1500, 3, 1),
+    (economy::ResourceType::Crystal, 500, 0, 3),
+];
+
+/// The radius of the objective placed at the center of the map, in voxels.
+const CENTER_OBJECTIVE_RADIUS: i32 = 3;
+
+/// The fighter every player picks, until there is a character select.
+const DEFAULT_FIGHTER: &'static str = "The Faceless One";
+
+/// How far apart fighters start, in game units.
+const FIGHTER_START_DISTANCE: i32 = 2000;
+
+/// How high the RTS camera looks down on the map from, in voxels.
+const TOP_DOWN_CAMERA_HEIGHT: i32 = 12;
+
+/// The datasheets of the maps each mode is played on. The editor uses the RTS map.
+const SKIRMISH_MAP_PATH: &'static str = "./src/cb_simulation/datasheets/maps/skirmish.hjson";
+const ARENA_MAP_PATH: &'static str = "./src/cb_simulation/datasheets/maps/arena.hjson";
+const STAGE_MAP_PATH: &'static str = "./src/cb_simulation/datasheets/maps/stage.hjson";
+
+/// Where each of the shooter arena's lamps stand, as (x, y) in voxels.
+const ARENA_LAMPS: [(i32, i32); 2] = [(4, 4), (11, 11)];
+
 pub fn new(mode: CbSimulationModes) -> specs::World {
     let mut world = World::new();
 
@@ -20,10 +57,28 @@ pub fn new(mode: CbSimulationModes) -> specs::World {
     gfx_components::GfxComponentsLinker::register_components(&mut world);
     // IK components
     ik_components::IkComponentsLinker::register_components(&mut world);
+    // Animation components
+    animation_components::AnimationComponentsLinker::register_components(&mut world);
     // Actor components
     actor_components::ActorComponentsLinker::register_components(&mut world);
     // Voxel components
     voxel_components::VoxelComponentsLinker::register_components(&mut world);
+    // Ability components
+    ability_components::AbilityComponentsLinker::register_components(&mut world);
+    // Economy components
+    economy_components::EconomyComponentsLinker::register_components(&mut world);
+    // Ownership components
+    ownership_components::OwnershipComponentsLinker::register_components(&mut world);
+    // Order components
+    order_components::OrderComponentsLinker::register_components(&mut world);
+    // Shooter components
+    shooter_components::ShooterComponentsLinker::register_components(&mut world);
+    // Projectile components
+    projectile_components::ProjectileComponentsLinker::register_components(&mut world);
+    // Fighting components
+    fighting_components::FightingComponentsLinker::register_components(&mut world);
+    // Match components
+    match_components::MatchComponentsLinker::register_components(&mut world);
     // Editor components
     editor_components::EditorComponentsLinker::register_components(&mut world);
 
@@ -35,13 +90,267 @@ pub fn new(mode: CbSimulationModes) -> specs::World {
     // Resources
     {
         world.insert(CbSystemValues::new());
+
+        let mut match_teams = teams::CbTeams::new();
+        for (player_id, team_id) in DEFAULT_PLAYERS.iter() {
+            match_teams.add_player(*player_id, *team_id);
+        }
+        world.insert(match_teams);
+        world.insert(fog_of_war::CbVisibility::new());
+        world.insert(fog_of_war::CbHeightMapCache::new());
+
+        match unit_animations::load_library() {
+            Ok(library) => world.insert(library),
+            Err(e) => panic!("Unable to load unit animations: {}", e),
+        }
+
+        let mut match_economy = economy::CbEconomy::new();
+        for (player_id, _) in DEFAULT_PLAYERS.iter() {
+            match_economy.add_player(
+                *player_id,
+                economy::Resources::new(STARTING_RESOURCES.0, STARTING_RESOURCES.1),
+            );
+        }
+        world.insert(match_economy);
+        world.insert(economy::CbSpawnQueue::new());
+
+        let assets = AssetManager::new(Path::new(cb_assets::ASSET_ROOT));
+
+        // Unit definitions are hot reloaded through their handle
+        let unit_definitions_handle = match assets.load::<unit_definitions::UnitDefinitions>(
+            Path::new(unit_definitions::UNIT_DEFINITIONS_PATH),
+        ) {
+            Ok(handle) => handle,
+            Err(e) => panic!("Unable to load unit definitions: {}", e),
+        };
+
+        let unit_definitions = match unit_definitions_handle.get() {
+            Some(definitions) => (*definitions).clone(),
+            None => panic!("Unable to load unit definitions"),
+        };
+
+        let ability_definitions = abilities::AbilityDefinitions::from_path(Path::new(
+            abilities::ABILITY_DEFINITIONS_PATH,
+        ))
+        .and_then(|abilities| abilities.validate(&unit_definitions).map(|_| abilities));
+
+        match ability_definitions {
+            Ok(definitions) => world.insert(definitions),
+            Err(e) => panic!("Unable to load ability definitions: {}", e),
+        }
+
+        world.insert(unit_definitions);
+        world.insert(unit_definitions_handle);
+
+        // The map's lighting is hot reloaded through its handle too
+        let map_path = match mode {
+            CbSimulationModes::ShooterMode => ARENA_MAP_PATH,
+            CbSimulationModes::FightingMode => STAGE_MAP_PATH,
+            _ => SKIRMISH_MAP_PATH,
+        };
+
+        let lighting_handle =
+            match assets.load::<cb_graphics::lighting::Lighting>(Path::new(map_path)) {
+                Ok(handle) => handle,
+                Err(e) => panic!("Unable to load map lighting: {}", e),
+            };
+
+        match lighting_handle.get() {
+            Some(lighting) => world.insert((*lighting).clone()),
+            None => panic!("Unable to load map lighting"),
+        }
+
+        world.insert(lighting_handle);
+        world.insert(assets);
+
+        if mode == CbSimulationModes::FightingMode {
+            match fighting::fighter_definitions::FighterDefinitions::from_directory(Path::new(
+                fighting::fighter_definitions::FIGHTERS_PATH,
+            )) {
+                Ok(definitions) => world.insert(definitions),
+                Err(e) => panic!("Unable to load fighter definitions: {}", e),
+            }
+        }
+
+        let match_settings = match match_state::MatchSettings::from_path(Path::new(
+            match_state::MATCH_SETTINGS_PATH,
+        )) {
+            Ok(settings) => settings,
+            Err(e) => panic!("Unable to load match settings: {}", e),
+        };
+
+        // Computer players come from the shared match settings rather than from which player is local, so every peer
+        // drops and replaces the same players' inputs. Only RTS matches have an AI to play them
+        let mut ai_players = ai::CbAiPlayers::new();
+        if mode == CbSimulationModes::RtsMode {
+            let definitions = world.read_resource::<unit_definitions::UnitDefinitions>();
+
+            for player_id in match_settings.ai_players.iter() {
+                if !DEFAULT_PLAYERS.iter().any(|(p, _)| p == player_id) {
+                    panic!("Unable to add AI player {}: no such player", player_id);
+                }
+
+                ai_players.add_player(ai::AiPlayer::new(
+                    *player_id,
+                    &ai::DEFAULT_BUILD_ORDER,
+                    &definitions,
+                ));
+            }
+        }
+
+        // RTS players ready up from the lobby. The other modes have no RTS commands to ready up with, so they go
+        // straight to the countdown
+        let mut current_match = match_state::CbMatch::new(match_settings);
+        if mode != CbSimulationModes::RtsMode {
+            current_match.start_countdown();
+        }
+        world.insert(current_match);
+        world.insert(ai_players);
     }
 
     // Setup entities
     {
+        // Mark the origin of the world
+        let origin_model = world
+            .read_resource::<AssetManager>()
+            .load_in_background(Path::new(cb_graphics::DEFAULT_MODEL_PATH));
+        world
+            .create_entity()
+            .with(physics_components::TransformComponent::new())
+            .with(gfx_components::RenderComponent::new(origin_model))
+            .build();
+
+        // Every mode can be edited, looking around the middle of the terrain
+        let terrain_middle =
+            cb_system::GameUnit::from_num(cb_voxels::CHUNKS * cb_voxels::CHUNK_SIZE) / 2
+                * cb_voxels::VOXEL_GAME_SIZE;
+        let editor_camera = assemblages::voxel_editor_assemblages::new_editor_camera(
+            &mut world,
+            cb_system::Coordinate3d::new(
+                terrain_middle,
+                terrain_middle,
+                cb_system::GameUnit::from_num(0),
+            ),
+        );
+
+        let game_camera;
+
         if mode == CbSimulationModes::RtsMode {
-            assemblages::rts_assemblages::new_unit(&mut world)
+            // Start each player in opposite corners of the map, with resources heading towards the center
+            let voxel = cb_voxels::VOXEL_GAME_SIZE;
+            let map_size = (fog_of_war::FOG_GRID_SIZE as i32 - 1) * voxel;
+
+            for (i, (player_id, _)) in DEFAULT_PLAYERS.iter().enumerate() {
+                let (start, direction) = if i % 2 == 0 { (0, 1) } else { (map_size, -1) };
+
+                let at = |x_voxels: i32, y_voxels: i32| {
+                    cb_system::Coordinate2d::new(
+                        cb_system::GameUnit::from_num(start + direction * x_voxels * voxel),
+                        cb_system::GameUnit::from_num(start + direction * y_voxels * voxel),
+                    )
+                };
+
+                let units = [
+                    ("headquarters", at(0, 0)),
+                    ("barracks", at(0, 5)),
+                    ("worker", at(1, 1)),
+                    ("worker", at(2, 1)),
+                    ("infantry", at(1, 2)),
+                ];
+
+                for (unit_type, position) in units.iter() {
+                    let unit = assemblages::rts_assemblages::new_unit(
+                        &mut world, unit_type, *position, *player_id,
+                    );
+
+                    if unit.is_err() {
+                        panic!("Unable to spawn unit: {}", unit.unwrap_err());
+                    }
+                }
+
+                for (resource, amount, x, y) in START_RESOURCE_NODES.iter() {
+                    assemblages::rts_assemblages::new_resource_node(
+                        &mut world,
+                        *resource,
+                        *amount,
+                        at(*x, *y),
+                    );
+                }
+            }
+
+            let center = cb_system::GameUnit::from_num(map_size / 2);
+            assemblages::rts_assemblages::new_objective(
+                &mut world,
+                cb_system::Coordinate2d::new(center, center),
+                cb_system::GameUnit::from_num(CENTER_OBJECTIVE_RADIUS * voxel),
+            );
+
+            game_camera = assemblages::rts_assemblages::new_top_down_camera(
+                &mut world,
+                cb_system::Coordinate2d::new(center, center),
+                cb_system::GameUnit::from_num(TOP_DOWN_CAMERA_HEIGHT * voxel),
+            );
+        } else if mode == CbSimulationModes::ShooterMode {
+            assemblages::shooter_assemblages::new_arena(&mut world);
+            for (x, y) in ARENA_LAMPS.iter() {
+                assemblages::shooter_assemblages::new_lamp(&mut world, *x, *y);
+            }
+            game_camera = assemblages::shooter_assemblages::new_first_person_camera(&mut world);
+
+            // A few crates dropped around the middle, to be knocked about
+            let middle = (cb_voxels::CHUNKS * cb_voxels::CHUNK_SIZE) as i32 / 2;
+            for (x, y, height) in [
+                (middle - 2, middle + 2, 6),
+                (middle + 2, middle - 2, 8),
+                (middle + 2, middle - 2, 10),
+            ]
+            .iter()
+            {
+                assemblages::shooter_assemblages::new_crate(&mut world, *x, *y, *height);
+            }
+
+            // Start each player in opposite corners of the arena, facing each other
+            let far_corner = (cb_voxels::CHUNKS * cb_voxels::CHUNK_SIZE) as i32 - 2;
+
+            for (i, (player_id, _)) in DEFAULT_PLAYERS.iter().enumerate() {
+                let (corner, yaw) = if i % 2 == 0 {
+                    (1, 450)
+                } else {
+                    (far_corner, 2250)
+                };
+
+                assemblages::shooter_assemblages::new_character(
+                    &mut world, *player_id, corner, corner, yaw,
+                );
+            }
+        } else if mode == CbSimulationModes::FightingMode {
+            game_camera = assemblages::fighting_assemblages::new_side_camera(&mut world);
+
+            // Start the players on either side of the center of the stage, facing each other
+            for (i, (player_id, _)) in DEFAULT_PLAYERS.iter().enumerate() {
+                let (x, facing) = if i % 2 == 0 {
+                    (-FIGHTER_START_DISTANCE / 2, fighting::Facing::Right)
+                } else {
+                    (FIGHTER_START_DISTANCE / 2, fighting::Facing::Left)
+                };
+
+                let fighter = assemblages::fighting_assemblages::new_fighter(
+                    &mut world,
+                    *player_id,
+                    DEFAULT_FIGHTER,
+                    x,
+                    facing,
+                );
+
+                if fighter.is_err() {
+                    panic!("Unable to spawn fighter: {}", fighter.unwrap_err());
+                }
+            }
+        } else {
+            game_camera = editor_camera;
         }
+
+        world.insert(cameras::CbActiveCamera::new(Some(game_camera)));
     }
 
     return world;
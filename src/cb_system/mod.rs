@@ -10,6 +10,7 @@ use cb_math::FInt;
 
 pub const FRAMEDELAY: GameTick = 3;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Coordinate3d {
     pub x: GameUnit,
     pub y: GameUnit,
@@ -32,6 +33,7 @@ impl Coordinate3d {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Coordinate2d {
     pub x: GameUnit,
     pub y: GameUnit,
@@ -51,6 +53,41 @@ impl Coordinate2d {
         let one = FInt::from_num(1);
         return Self::new(one, one);
     }
+
+    /// The coordinate at the whole game units, or None if either is too large for a GameUnit. Use this for
+    /// coordinates received from other peers, which may be anything.
+    pub fn checked_from_num(x: i32, y: i32) -> Option<Self> {
+        return Some(Self::new(
+            GameUnit::checked_from_num(x)?,
+            GameUnit::checked_from_num(y)?,
+        ));
+    }
+
+    /// The squared distance to the other coordinate, in whole game units.
+    pub fn distance_squared(&self, other: &Self) -> i64 {
+        let dx = other.x.to_num::<i64>() - self.x.to_num::<i64>();
+        let dy = other.y.to_num::<i64>() - self.y.to_num::<i64>();
+
+        return dx * dx + dy * dy;
+    }
+
+    /// Returns the coordinate moved up to the given distance towards the target, without overshooting it.
+    pub fn move_towards(&self, target: &Self, distance: GameUnit) -> Self {
+        let distance_to_target = cb_math::isqrt(self.distance_squared(target) as u64) as i64;
+        let step = distance.to_num::<i64>();
+
+        if distance_to_target <= step {
+            return *target;
+        }
+
+        let dx = target.x.to_num::<i64>() - self.x.to_num::<i64>();
+        let dy = target.y.to_num::<i64>() - self.y.to_num::<i64>();
+
+        return Self::new(
+            self.x + GameUnit::from_num(dx * step / distance_to_target),
+            self.y + GameUnit::from_num(dy * step / distance_to_target),
+        );
+    }
 }
 
 pub struct CbEvent<T> {
@@ -1,8 +1,17 @@
 // Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
 
+pub mod palette;
+pub mod vox;
+pub mod voxel_model;
+
+use palette::VoxelPalette;
+
 use crate::cb_math;
 use cb_math::{index_1d_to_3d, index_3d_to_1d};
 
+use crate::cb_system;
+use cb_system::{Coordinate3d, GameUnit};
+
 extern crate rayon;
 use rayon::prelude::*;
 
@@ -19,6 +28,9 @@ pub const MAX_CHUNK_INDEX: usize = CHUNK_SIZE - 1;
 
 pub const VOXEL_SIZE: f32 = 1.0; //TODO: change from float
 
+/// The width of a voxel in game units (1 = 1mm).
+pub const VOXEL_GAME_SIZE: i32 = 300;
+
 pub const CHUNKS: usize = 4;
 pub const CHUNKS_SQUARED: usize = CHUNKS * CHUNKS;
 pub const CHUNKS_CUBED: usize = CHUNKS * CHUNKS * CHUNKS;
@@ -36,6 +48,9 @@ pub struct CbChunkManager {
     dirty: bool,
     randomizer_index: usize,
 
+    /// The materials of the map's voxel types.
+    pub palette: VoxelPalette,
+
     // End goal: Convert to using a 1d array, right now it's just too annoying and bug prone
     pub chunk_array: [[[CbVoxelChunk; CHUNKS]; CHUNKS]; CHUNKS],
 }
@@ -47,6 +62,20 @@ impl CbChunkManager {
         return Self {
             chunk_array: [[[CbVoxelChunk::new(); CHUNKS]; CHUNKS]; CHUNKS],
             randomizer_index: 0,
+            palette: VoxelPalette::new(),
+            dirty: true,
+        };
+    }
+
+    /// A map without any active voxels, with every chunk marked as updated at the frame.
+    pub fn empty(frame: usize) -> Self {
+        let mut chunk = CbVoxelChunk::empty();
+        chunk.frame_updated_at = frame;
+
+        return Self {
+            chunk_array: [[[chunk; CHUNKS]; CHUNKS]; CHUNKS],
+            randomizer_index: 0,
+            palette: VoxelPalette::new(),
             dirty: true,
         };
     }
@@ -76,6 +105,236 @@ impl CbChunkManager {
 
         return &mut self.chunk_array[cx][cy][cz].voxels[vx][vy][vz];
     }
+
+    /// Returns one above the highest active voxel in the column, or 0 if the column is empty.
+    pub fn get_column_height(&self, x: usize, y: usize) -> usize {
+        for z in (0..self.get_voxel_width()).rev() {
+            if voxel_active(self.get_voxel(x, y, z)) {
+                return z + 1;
+            }
+        }
+
+        return 0;
+    }
+
+    /// Deactivate the highest active voxel in the column. Returns false if the column was already empty.
+    pub fn remove_top_voxel(&mut self, x: usize, y: usize, frame: usize) -> bool {
+        let height = self.get_column_height(x, y);
+        if height == 0 {
+            return false;
+        }
+
+        let voxel = self.get_voxel_mut(x, y, height - 1, frame);
+        voxel.0 = false;
+        voxel.1 = false;
+
+        return true;
+    }
+
+    /// Activate a voxel of the given type on top of the column. Returns false if the column is already full.
+    pub fn add_top_voxel(&mut self, x: usize, y: usize, voxel_type: u8, frame: usize) -> bool {
+        let height = self.get_column_height(x, y);
+        if height >= self.get_voxel_width() {
+            return false;
+        }
+
+        let voxel = self.get_voxel_mut(x, y, height, frame);
+        *voxel = (true, true, voxel_type, 0);
+
+        return true;
+    }
+
+    /// Returns whether the voxel at the coordinates is active. Coordinates outside of the map are never active.
+    pub fn is_solid(&self, x: i32, y: i32, z: i32) -> bool {
+        let width = self.get_voxel_width() as i32;
+
+        if x < 0 || y < 0 || z < 0 || x >= width || y >= width || z >= width {
+            return false;
+        }
+
+        return voxel_active(self.get_voxel(x as usize, y as usize, z as usize));
+    }
+
+    /// Returns whether any active voxel overlaps the box. The box is in game units, with the max corner exclusive.
+    pub fn box_collides(&self, min: (i32, i32, i32), max: (i32, i32, i32)) -> bool {
+        let size = VOXEL_GAME_SIZE;
+
+        for x in min.0.div_euclid(size)..=(max.0 - 1).div_euclid(size) {
+            for y in min.1.div_euclid(size)..=(max.1 - 1).div_euclid(size) {
+                for z in min.2.div_euclid(size)..=(max.2 - 1).div_euclid(size) {
+                    if self.is_solid(x, y, z) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        return false;
+    }
+
+    /// Deactivate every voxel whose center is within the radius of the point, in game units. Returns how many were
+    /// removed.
+    pub fn remove_voxels_within(
+        &mut self,
+        center: Coordinate3d,
+        radius: GameUnit,
+        frame: usize,
+    ) -> usize {
+        let size = VOXEL_GAME_SIZE as i64;
+        let width = self.get_voxel_width() as i64;
+        let c = [
+            center.x.to_num::<i64>(),
+            center.y.to_num::<i64>(),
+            center.z.to_num::<i64>(),
+        ];
+        let r = radius.to_num::<i64>();
+
+        let range = |axis: usize| {
+            let min = ((c[axis] - r).div_euclid(size)).max(0);
+            let max = ((c[axis] + r).div_euclid(size)).min(width - 1);
+            return min..=max;
+        };
+
+        let mut removed = 0;
+
+        for x in range(0) {
+            for y in range(1) {
+                for z in range(2) {
+                    if !self.is_solid(x as i32, y as i32, z as i32) {
+                        continue;
+                    }
+
+                    let voxel_center = [
+                        x * size + size / 2,
+                        y * size + size / 2,
+                        z * size + size / 2,
+                    ];
+                    let squared: i64 = (0..3).map(|i| (voxel_center[i] - c[i]).pow(2)).sum();
+
+                    if squared <= r * r {
+                        let voxel = self.get_voxel_mut(x as usize, y as usize, z as usize, frame);
+                        voxel.0 = false;
+                        voxel.1 = false;
+
+                        removed += 1;
+                    }
+                }
+            }
+        }
+
+        return removed;
+    }
+
+    /// Walk the voxels along the ray, returning the first active voxel within the max distance. The direction should be
+    /// of unit length, with the origin and distances in game units. Only integer math is used so every peer agrees.
+    pub fn raycast(
+        &self,
+        origin: Coordinate3d,
+        direction: Coordinate3d,
+        max_distance: GameUnit,
+    ) -> Option<VoxelHit> {
+        // Work in the raw bits of the fixed point values
+        let one = GameUnit::from_num(1).to_bits() as i64;
+        let size = VOXEL_GAME_SIZE as i64 * one;
+        let width = self.get_voxel_width() as i64;
+
+        let o = [
+            origin.x.to_bits() as i64,
+            origin.y.to_bits() as i64,
+            origin.z.to_bits() as i64,
+        ];
+        let d = [
+            direction.x.to_bits() as i64,
+            direction.y.to_bits() as i64,
+            direction.z.to_bits() as i64,
+        ];
+
+        let mut cell = [
+            o[0].div_euclid(size),
+            o[1].div_euclid(size),
+            o[2].div_euclid(size),
+        ];
+        let mut step = [0; 3];
+
+        // The distance along the ray to the next boundary on each axis, as a fraction of (numerator, denominator)
+        let mut next_boundary = [(0, 0); 3];
+
+        for axis in 0..3 {
+            if d[axis] > 0 {
+                step[axis] = 1;
+                next_boundary[axis] = ((cell[axis] + 1) * size - o[axis], d[axis]);
+            } else if d[axis] < 0 {
+                step[axis] = -1;
+                next_boundary[axis] = (o[axis] - cell[axis] * size, -d[axis]);
+            }
+        }
+
+        let mut entered = (0, 1);
+        let mut face = (0, 0, 0);
+        let max_distance = max_distance.to_bits() as i64;
+
+        loop {
+            let distance = entered.0 * one / entered.1;
+            if distance > max_distance {
+                return None;
+            }
+
+            if self.is_solid(cell[0] as i32, cell[1] as i32, cell[2] as i32) {
+                return Some(VoxelHit {
+                    voxel: (cell[0] as usize, cell[1] as usize, cell[2] as usize),
+                    distance: GameUnit::from_bits(distance as i32),
+                    face: face,
+                });
+            }
+
+            // Cross multiply to find the nearest boundary without dividing
+            let mut nearest: Option<usize> = None;
+            for axis in 0..3 {
+                if step[axis] == 0 {
+                    continue;
+                }
+
+                let closer = match nearest {
+                    Some(n) => {
+                        next_boundary[axis].0 * next_boundary[n].1
+                            < next_boundary[n].0 * next_boundary[axis].1
+                    }
+                    None => true,
+                };
+
+                if closer {
+                    nearest = Some(axis);
+                }
+            }
+
+            let axis = nearest?;
+
+            entered = next_boundary[axis];
+            next_boundary[axis].0 += size;
+            cell[axis] += step[axis];
+
+            face = match axis {
+                0 => (-step[0] as i32, 0, 0),
+                1 => (0, -step[1] as i32, 0),
+                _ => (0, 0, -step[2] as i32),
+            };
+
+            // Once past the edge of the map and moving away from it, nothing more can be hit
+            if (step[axis] > 0 && cell[axis] >= width) || (step[axis] < 0 && cell[axis] < 0) {
+                return None;
+            }
+        }
+    }
+}
+
+/// Where a ray hit a voxel.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VoxelHit {
+    pub voxel: (usize, usize, usize),
+    /// The distance along the ray, in game units.
+    pub distance: GameUnit,
+    /// The normal of the face that was hit, or all zeros if the ray started inside the voxel.
+    pub face: (i32, i32, i32),
 }
 
 pub const VOXEL_TYPE_DEFAULT: u8 = 0;
@@ -97,6 +356,14 @@ impl CbVoxelChunk {
         };
         return chunk;
     }
+
+    /// A chunk without any active voxels.
+    pub fn empty() -> Self {
+        return Self {
+            voxels: [[[(false, false, VOXEL_TYPE_DEFAULT, 0); CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE],
+            frame_updated_at: 0,
+        };
+    }
 }
 
 fn get_chunk_and_voxel_index(i: usize, num_chunks: usize, chunk_size: usize) -> (usize, usize) {
@@ -123,10 +390,148 @@ fn get_chunk_and_voxel_indexes(
     return ((chunk_x, chunk_y, chunk_z), (voxel_x, voxel_y, voxel_z));
 }
 
+/// A map with every column the given number of voxels high, for tests that need terrain.
+#[cfg(test)]
+pub fn flat_map(height: usize) -> CbChunkManager {
+    let mut chunk_manager = CbChunkManager::new();
+    let width = chunk_manager.get_voxel_width();
+
+    for x in 0..width {
+        for y in 0..width {
+            while chunk_manager.get_column_height(x, y) > height {
+                chunk_manager.remove_top_voxel(x, y, 0);
+            }
+        }
+    }
+
+    return chunk_manager;
+}
+
+/// The position in whole game units, for tests.
+#[cfg(test)]
+pub fn coordinate(x: i32, y: i32, z: i32) -> Coordinate3d {
+    return Coordinate3d::new(
+        GameUnit::from_num(x),
+        GameUnit::from_num(y),
+        GameUnit::from_num(z),
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn remove_voxels_within_removes_only_voxels_with_centers_in_radius() {
+        let mut chunk_manager = flat_map(2);
+
+        // Centered on the top face of voxel (5, 5, 1); its center is 150 below, its neighbors' centers further
+        let removed = chunk_manager.remove_voxels_within(
+            coordinate(1650, 1650, 600),
+            GameUnit::from_num(200),
+            7,
+        );
+
+        assert_eq!(1, removed);
+        assert_eq!(false, chunk_manager.is_solid(5, 5, 1));
+        assert_eq!(true, chunk_manager.is_solid(5, 5, 0));
+        assert_eq!(true, chunk_manager.is_solid(6, 5, 1));
+        assert_eq!(7, chunk_manager.chunk_array[1][1][0].frame_updated_at);
+    }
+
+    #[test]
+    fn raycast_straight_down_hits_top_voxel() {
+        let chunk_manager = flat_map(2);
+        let origin = coordinate(450, 450, 1500);
+
+        let actual = chunk_manager.raycast(origin, coordinate(0, 0, -1), GameUnit::from_num(5000));
+
+        assert_eq!(
+            Some(VoxelHit {
+                voxel: (1, 1, 1),
+                distance: GameUnit::from_num(900),
+                face: (0, 0, 1)
+            }),
+            actual
+        );
+    }
+
+    #[test]
+    fn raycast_horizontal_blocked_by_wall() {
+        let mut chunk_manager = flat_map(2);
+        chunk_manager.add_top_voxel(5, 1, VOXEL_TYPE_DIRT, 0);
+        let origin = coordinate(150, 450, 750);
+
+        let actual = chunk_manager.raycast(origin, coordinate(1, 0, 0), GameUnit::from_num(5000));
+
+        assert_eq!((5, 1, 2), actual.unwrap().voxel);
+        assert_eq!(GameUnit::from_num(1350), actual.unwrap().distance);
+    }
+
+    #[test]
+    fn raycast_beyond_max_distance_returns_none() {
+        let chunk_manager = flat_map(2);
+        let origin = coordinate(450, 450, 1500);
+
+        let actual = chunk_manager.raycast(origin, coordinate(0, 0, -1), GameUnit::from_num(800));
+
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn raycast_leaving_map_returns_none() {
+        let chunk_manager = flat_map(2);
+        let origin = coordinate(450, 450, 1500);
+
+        let actual = chunk_manager.raycast(origin, coordinate(0, 0, 1), GameUnit::from_num(50000));
+
+        assert_eq!(None, actual);
+    }
+
+    #[test]
+    fn box_collides_box_resting_on_surface_returns_false() {
+        let chunk_manager = flat_map(2);
+
+        assert_eq!(
+            false,
+            chunk_manager.box_collides((100, 100, 600), (400, 400, 900))
+        );
+        assert_eq!(
+            true,
+            chunk_manager.box_collides((100, 100, 599), (400, 400, 900))
+        );
+    }
+
+    #[test]
+    fn remove_top_voxel_full_column_lowers_height() {
+        let mut chunk_manager = CbChunkManager::new();
+        let width = chunk_manager.get_voxel_width();
+
+        assert_eq!(true, chunk_manager.remove_top_voxel(1, 2, 7));
+
+        assert_eq!(width - 1, chunk_manager.get_column_height(1, 2));
+        assert_eq!(width, chunk_manager.get_column_height(2, 1));
+    }
+
+    #[test]
+    fn add_top_voxel_full_column_returns_false() {
+        let mut chunk_manager = CbChunkManager::new();
+
+        assert_eq!(false, chunk_manager.add_top_voxel(0, 0, VOXEL_TYPE_DIRT, 0));
+    }
+
+    #[test]
+    fn add_top_voxel_after_remove_restores_height_with_type() {
+        let mut chunk_manager = CbChunkManager::new();
+        let width = chunk_manager.get_voxel_width();
+        chunk_manager.remove_top_voxel(3, 3, 0);
+
+        assert_eq!(true, chunk_manager.add_top_voxel(3, 3, VOXEL_TYPE_DIRT, 0));
+
+        assert_eq!(width, chunk_manager.get_column_height(3, 3));
+        assert_eq!(VOXEL_TYPE_DIRT, chunk_manager.get_voxel(3, 3, width - 1).2);
+    }
+
     #[test]
     fn get_chunk_and_voxel_indexes_x0y4z0_n2c4_returns_returns_expected() {
         let x = 0;
@@ -0,0 +1,133 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use super::{VOXEL_TYPE_DEFAULT, VOXEL_TYPE_DIRT, VOXEL_TYPE_GRASS};
+
+/// The most voxel types a palette can hold, as types are a u8.
+pub const MAX_VOXEL_TYPES: usize = 256;
+
+/// The color of voxel types without one.
+const UNASSIGNED_COLOR: [u8; 3] = [0, 0, 0];
+
+/// The material of each voxel type, where a voxel's type is its index in the palette.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelPalette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl VoxelPalette {
+    /// A palette with the game's own voxel types.
+    pub fn new() -> Self {
+        let mut colors = vec![UNASSIGNED_COLOR; 3];
+        colors[VOXEL_TYPE_DEFAULT as usize] = [255, 0, 0];
+        colors[VOXEL_TYPE_GRASS as usize] = [0, 255, 0];
+        colors[VOXEL_TYPE_DIRT as usize] = [59, 43, 22];
+
+        return Self { colors: colors };
+    }
+
+    /// A palette without any types.
+    pub fn empty() -> Self {
+        return Self { colors: vec![] };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.colors.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.colors.is_empty();
+    }
+
+    /// The color of the type, if it is in the palette.
+    pub fn rgb(&self, voxel_type: u8) -> Option<[u8; 3]> {
+        return self.colors.get(voxel_type as usize).copied();
+    }
+
+    /// The color of the type for meshing, or black if it is not in the palette.
+    pub fn color(&self, voxel_type: u8) -> [f32; 3] {
+        let rgb = self.rgb(voxel_type).unwrap_or(UNASSIGNED_COLOR);
+
+        return [
+            rgb[0] as f32 / 255.0,
+            rgb[1] as f32 / 255.0,
+            rgb[2] as f32 / 255.0,
+        ];
+    }
+
+    /// Add a type with the color, even if another type has it. Returns none if the palette is full.
+    pub fn add(&mut self, rgb: [u8; 3]) -> Option<u8> {
+        if self.colors.len() >= MAX_VOXEL_TYPES {
+            return None;
+        }
+
+        self.colors.push(rgb);
+        return Some((self.colors.len() - 1) as u8);
+    }
+
+    /// The type with exactly the color, adding a type if none has it. Once every type is taken, the type with the
+    /// nearest color.
+    pub fn find_or_add(&mut self, rgb: [u8; 3]) -> u8 {
+        if let Some(voxel_type) = self.colors.iter().position(|color| *color == rgb) {
+            return voxel_type as u8;
+        }
+
+        match self.add(rgb) {
+            Some(voxel_type) => voxel_type,
+            None => self.nearest(rgb),
+        }
+    }
+
+    /// The type with the nearest color. Empty palettes return the default type.
+    pub fn nearest(&self, rgb: [u8; 3]) -> u8 {
+        let distance = |color: &[u8; 3]| -> i32 {
+            return (0..3)
+                .map(|i| {
+                    let d = color[i] as i32 - rgb[i] as i32;
+                    d * d
+                })
+                .sum();
+        };
+
+        return self
+            .colors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, color)| distance(color))
+            .map(|(voxel_type, _)| voxel_type as u8)
+            .unwrap_or(VOXEL_TYPE_DEFAULT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voxel_palette_new_colors_game_types() {
+        let palette = VoxelPalette::new();
+
+        assert_eq!([0.0, 1.0, 0.0], palette.color(VOXEL_TYPE_GRASS));
+        assert_eq!([0.0, 0.0, 0.0], palette.color(200));
+    }
+
+    #[test]
+    fn voxel_palette_find_or_add_reuses_matching_color() {
+        let mut palette = VoxelPalette::new();
+
+        assert_eq!(VOXEL_TYPE_GRASS, palette.find_or_add([0, 255, 0]));
+        assert_eq!(3, palette.find_or_add([10, 20, 30]));
+        assert_eq!(3, palette.find_or_add([10, 20, 30]));
+        assert_eq!(4, palette.len());
+    }
+
+    #[test]
+    fn voxel_palette_find_or_add_full_returns_nearest() {
+        let mut palette = VoxelPalette::empty();
+        for i in 0..MAX_VOXEL_TYPES {
+            palette.find_or_add([i as u8, 0, 0]);
+        }
+
+        assert_eq!(20, palette.find_or_add([20, 1, 1]));
+        assert_eq!(MAX_VOXEL_TYPES, palette.len());
+    }
+}
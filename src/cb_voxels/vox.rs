@@ -0,0 +1,559 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+/*
+    MagicaVoxel .vox files. A file holds any number of models, each a size and a list of voxels that point into a
+    palette of 255 colors. Only the models and palette are read; the scene graph that places models, layers and
+    materials beyond color are skipped, so each model is loaded at its own origin. Files are written as version 150
+    with just the models and palette.
+
+    Colors become voxel types through a palette: a standalone voxel model gets the file's palette, with each color
+    index one above its type, while models loaded into a map reuse any of the map's types with the same color and add
+    types for the rest. MagicaVoxel is z up like the game, so no axes are swapped.
+*/
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cb_assets::Asset;
+
+use super::palette::{VoxelPalette, MAX_VOXEL_TYPES};
+use super::voxel_model::VoxelModel;
+use super::{voxel_active, CbChunkManager};
+
+const VOX_VERSION: u32 = 150;
+
+/// The largest a model may be along any axis, as voxel positions are a u8.
+pub const MAX_VOX_SIZE: usize = 256;
+
+/// A model in a .vox file, with the position and color index of each voxel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxModel {
+    pub size: (usize, usize, usize),
+    pub voxels: Vec<((u8, u8, u8), u8)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxFile {
+    pub models: Vec<VoxModel>,
+    /// The RGBA color of each color index. Index 0 is empty space, so no voxel uses it.
+    pub palette: Vec<[u8; 4]>,
+}
+
+impl VoxFile {
+    /// A file without models, using MagicaVoxel's default palette.
+    pub fn new() -> Self {
+        return Self {
+            models: vec![],
+            palette: default_palette(),
+        };
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+
+        return Self::parse(&bytes)
+            .map_err(|e| format!("Unable to import '{}': {}", path.display(), e));
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.get(0..4) != Some(b"VOX ") {
+            return Err("The file does not start with 'VOX '.".to_string());
+        }
+
+        let main = read_chunks(&bytes[8.min(bytes.len())..])?;
+        let main = match main.first() {
+            Some(main) if main.id == b"MAIN" => main,
+            _ => {
+                return Err("The file has no MAIN chunk.".to_string());
+            }
+        };
+
+        let mut file = VoxFile::new();
+        let mut size = None;
+
+        for chunk in read_chunks(main.children)? {
+            match chunk.id {
+                b"SIZE" => {
+                    let (x, y, z) = (
+                        read_u32(chunk.content, 0)? as usize,
+                        read_u32(chunk.content, 4)? as usize,
+                        read_u32(chunk.content, 8)? as usize,
+                    );
+
+                    if x > MAX_VOX_SIZE || y > MAX_VOX_SIZE || z > MAX_VOX_SIZE {
+                        return Err(format!(
+                            "A model is {}x{}x{}, but models can be at most {} voxels along each axis.",
+                            x, y, z, MAX_VOX_SIZE
+                        ));
+                    }
+
+                    size = Some((x, y, z));
+                }
+                b"XYZI" => {
+                    let size = size
+                        .take()
+                        .ok_or("A model's voxels come before its size.")?;
+                    let model = read_voxels(chunk.content, size)
+                        .map_err(|e| format!("Model {}: {}", file.models.len(), e))?;
+
+                    file.models.push(model);
+                }
+                b"RGBA" => {
+                    // The colors are stored from index 1, so the chunk's last color is unused
+                    for i in 1..MAX_VOXEL_TYPES {
+                        let at = (i - 1) * 4;
+                        let color = chunk
+                            .content
+                            .get(at..at + 4)
+                            .ok_or("The palette has fewer than 255 colors.")?;
+
+                        file.palette[i] = [color[0], color[1], color[2], color[3]];
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return Ok(file);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut children = vec![];
+
+        if self.models.len() > 1 {
+            write_chunk(
+                &mut children,
+                b"PACK",
+                &(self.models.len() as u32).to_le_bytes(),
+                &[],
+            );
+        }
+
+        for model in self.models.iter() {
+            let mut size = vec![];
+            for length in [model.size.0, model.size.1, model.size.2].iter() {
+                size.extend(&(*length as u32).to_le_bytes());
+            }
+            write_chunk(&mut children, b"SIZE", &size, &[]);
+
+            let mut voxels = (model.voxels.len() as u32).to_le_bytes().to_vec();
+            for ((x, y, z), color) in model.voxels.iter() {
+                voxels.extend(&[*x, *y, *z, *color]);
+            }
+            write_chunk(&mut children, b"XYZI", &voxels, &[]);
+        }
+
+        let mut colors = vec![];
+        for i in 1..MAX_VOXEL_TYPES {
+            colors.extend(&self.palette[i]);
+        }
+        colors.extend(&[0, 0, 0, 0]);
+        write_chunk(&mut children, b"RGBA", &colors, &[]);
+
+        let mut bytes = b"VOX ".to_vec();
+        bytes.extend(&VOX_VERSION.to_le_bytes());
+        write_chunk(&mut bytes, b"MAIN", &[], &children);
+
+        return bytes;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        return std::fs::write(path, self.to_bytes())
+            .map_err(|e| format!("Unable to write '{}': {}", path.display(), e));
+    }
+
+    fn model(&self, model: usize) -> Result<&VoxModel, String> {
+        return self.models.get(model).ok_or(format!(
+            "There is no model {}; the file has {}.",
+            model,
+            self.models.len()
+        ));
+    }
+
+    fn rgb(&self, color: u8) -> [u8; 3] {
+        let rgba = self.palette[color as usize];
+        return [rgba[0], rgba[1], rgba[2]];
+    }
+
+    /// Convert one of the file's models into a standalone voxel model, with a type for each of the file's colors.
+    pub fn to_voxel_model(&self, model: usize) -> Result<VoxelModel, String> {
+        let vox = self.model(model)?;
+
+        let mut palette = VoxelPalette::empty();
+        for color in 1..MAX_VOXEL_TYPES {
+            palette.add(self.rgb(color as u8));
+        }
+
+        let mut voxel_model = VoxelModel::new(vox.size, palette);
+        for ((x, y, z), color) in vox.voxels.iter() {
+            voxel_model.set_voxel(
+                *x as usize,
+                *y as usize,
+                *z as usize,
+                (true, true, *color - 1, 0),
+                0,
+            );
+        }
+
+        return Ok(voxel_model);
+    }
+
+    /// Place one of the file's models in a map with its lowest corner at the offset, replacing the map's voxels
+    /// wherever the model has one. Colors become the map's types, adding types to its palette where needed.
+    pub fn load_into(
+        &self,
+        model: usize,
+        chunk_manager: &mut CbChunkManager,
+        offset: (usize, usize, usize),
+        frame: usize,
+    ) -> Result<(), String> {
+        let vox = self.model(model)?;
+
+        let width = chunk_manager.get_voxel_width();
+        if offset.0 + vox.size.0 > width
+            || offset.1 + vox.size.1 > width
+            || offset.2 + vox.size.2 > width
+        {
+            return Err(format!(
+                "A {}x{}x{} model at ({}, {}, {}) does not fit in a map {} voxels wide.",
+                vox.size.0, vox.size.1, vox.size.2, offset.0, offset.1, offset.2, width
+            ));
+        }
+
+        let mut types = HashMap::new();
+        for ((x, y, z), color) in vox.voxels.iter() {
+            let rgb = self.rgb(*color);
+            let voxel_type = *types
+                .entry(*color)
+                .or_insert_with(|| chunk_manager.palette.find_or_add(rgb));
+
+            let voxel = chunk_manager.get_voxel_mut(
+                offset.0 + *x as usize,
+                offset.1 + *y as usize,
+                offset.2 + *z as usize,
+                frame,
+            );
+            *voxel = (true, true, voxel_type, 0);
+        }
+
+        return Ok(());
+    }
+
+    /// A file with the voxel model, where each type is saved as its own color.
+    pub fn from_voxel_model(model: &VoxelModel) -> Result<Self, String> {
+        return Self::from_voxels(model.size(), model.active_voxels(), &model.palette);
+    }
+
+    /// A file with the whole map as a single model.
+    pub fn from_chunk_manager(chunk_manager: &CbChunkManager) -> Result<Self, String> {
+        let width = chunk_manager.get_voxel_width();
+
+        let mut voxels = vec![];
+        for z in 0..width {
+            for y in 0..width {
+                for x in 0..width {
+                    let voxel = chunk_manager.get_voxel(x, y, z);
+                    if voxel_active(voxel) {
+                        voxels.push(((x, y, z), voxel.2));
+                    }
+                }
+            }
+        }
+
+        return Self::from_voxels((width, width, width), voxels, &chunk_manager.palette);
+    }
+
+    fn from_voxels(
+        size: (usize, usize, usize),
+        voxels: Vec<((usize, usize, usize), u8)>,
+        palette: &VoxelPalette,
+    ) -> Result<Self, String> {
+        if size.0 > MAX_VOX_SIZE || size.1 > MAX_VOX_SIZE || size.2 > MAX_VOX_SIZE {
+            return Err(format!(
+                "A {}x{}x{} model is larger than the {} voxels a .vox model can be along each axis.",
+                size.0, size.1, size.2, MAX_VOX_SIZE
+            ));
+        }
+
+        let mut file = VoxFile::new();
+
+        // Color indices start at 1, so the last type has no color to be saved as
+        for voxel_type in 0..palette.len().min(MAX_VOXEL_TYPES - 1) {
+            if let Some(rgb) = palette.rgb(voxel_type as u8) {
+                file.palette[voxel_type + 1] = [rgb[0], rgb[1], rgb[2], 255];
+            }
+        }
+
+        let mut model = VoxModel {
+            size: size,
+            voxels: vec![],
+        };
+
+        for ((x, y, z), voxel_type) in voxels {
+            if voxel_type as usize >= MAX_VOXEL_TYPES - 1 {
+                return Err(format!(
+                    "Voxel type {} can't be saved, as .vox files only have {} colors.",
+                    voxel_type,
+                    MAX_VOXEL_TYPES - 1
+                ));
+            }
+
+            model
+                .voxels
+                .push(((x as u8, y as u8, z as u8), voxel_type + 1));
+        }
+
+        file.models.push(model);
+
+        return Ok(file);
+    }
+}
+
+impl Asset for VoxFile {
+    fn load(path: &Path) -> Result<Self, String> {
+        return Self::from_path(path);
+    }
+}
+
+/// MagicaVoxel's palette for files without one: a cube of web safe colors, then ramps of red, green, blue and gray.
+fn default_palette() -> Vec<[u8; 4]> {
+    let steps = [255, 204, 153, 102, 51, 0];
+    let ramp = [238, 221, 187, 170, 136, 119, 85, 68, 34, 17];
+
+    let mut palette = vec![[0, 0, 0, 0]];
+
+    for r in steps.iter() {
+        for g in steps.iter() {
+            for b in steps.iter() {
+                if *r != 0 || *g != 0 || *b != 0 {
+                    palette.push([*r, *g, *b, 255]);
+                }
+            }
+        }
+    }
+
+    for v in ramp.iter() {
+        palette.push([*v, 0, 0, 255]);
+    }
+    for v in ramp.iter() {
+        palette.push([0, *v, 0, 255]);
+    }
+    for v in ramp.iter() {
+        palette.push([0, 0, *v, 255]);
+    }
+    for v in ramp.iter() {
+        palette.push([*v, *v, *v, 255]);
+    }
+
+    return palette;
+}
+
+struct Chunk<'a> {
+    id: &'a [u8],
+    content: &'a [u8],
+    children: &'a [u8],
+}
+
+/// Split bytes into the chunks they hold, one after another.
+fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    let mut chunks = vec![];
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let id = bytes
+            .get(offset..offset + 4)
+            .ok_or("A chunk's id is cut short.")?;
+        let content_length = read_u32(bytes, offset + 4)? as usize;
+        let children_length = read_u32(bytes, offset + 8)? as usize;
+
+        let content_start = offset + 12;
+        let children_start = content_start + content_length;
+        let end = children_start + children_length;
+
+        if end > bytes.len() {
+            return Err(format!(
+                "The {} chunk runs past the end of the file.",
+                String::from_utf8_lossy(id)
+            ));
+        }
+
+        chunks.push(Chunk {
+            id: id,
+            content: &bytes[content_start..children_start],
+            children: &bytes[children_start..end],
+        });
+
+        offset = end;
+    }
+
+    return Ok(chunks);
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    return bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or("A chunk ends early.".to_string());
+}
+
+fn read_voxels(content: &[u8], size: (usize, usize, usize)) -> Result<VoxModel, String> {
+    let count = read_u32(content, 0)? as usize;
+    if content.len() < 4 + count * 4 {
+        return Err(format!(
+            "The model should have {} voxels, but ends early.",
+            count
+        ));
+    }
+
+    let mut voxels = Vec::with_capacity(count);
+    for voxel in content[4..4 + count * 4].chunks(4) {
+        let (x, y, z, color) = (voxel[0], voxel[1], voxel[2], voxel[3]);
+
+        if x as usize >= size.0 || y as usize >= size.1 || z as usize >= size.2 {
+            return Err(format!(
+                "The voxel at ({}, {}, {}) is outside of the model's {}x{}x{} size.",
+                x, y, z, size.0, size.1, size.2
+            ));
+        }
+
+        if color == 0 {
+            return Err(format!(
+                "The voxel at ({}, {}, {}) uses color 0, which is empty space.",
+                x, y, z
+            ));
+        }
+
+        voxels.push(((x, y, z), color));
+    }
+
+    return Ok(VoxModel {
+        size: size,
+        voxels: voxels,
+    });
+}
+
+fn write_chunk(bytes: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+    bytes.extend(id);
+    bytes.extend(&(content.len() as u32).to_le_bytes());
+    bytes.extend(&(children.len() as u32).to_le_bytes());
+    bytes.extend(content);
+    bytes.extend(children);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_voxels() -> VoxFile {
+        let mut file = VoxFile::new();
+        file.palette[9] = [10, 20, 30, 255];
+        file.models.push(VoxModel {
+            size: (3, 2, 2),
+            voxels: vec![((0, 0, 0), 9), ((2, 1, 1), 9), ((1, 0, 0), 1)],
+        });
+
+        return file;
+    }
+
+    #[test]
+    fn from_path_sword_imports_model_and_palette() {
+        let file = VoxFile::from_path(Path::new("./src/assets/chr_sword.vox")).unwrap();
+
+        assert_eq!(1, file.models.len());
+        assert_eq!((20, 21, 20), file.models[0].size);
+        assert_eq!(334, file.models[0].voxels.len());
+        assert_eq!(MAX_VOXEL_TYPES, file.palette.len());
+
+        let model = file.to_voxel_model(0).unwrap();
+        assert_eq!(334, model.active_voxels().len());
+    }
+
+    #[test]
+    fn to_bytes_parse_round_trips() {
+        let mut file = two_voxels();
+        file.models.push(VoxModel {
+            size: (1, 1, 1),
+            voxels: vec![((0, 0, 0), 255)],
+        });
+
+        assert_eq!(file, VoxFile::parse(&file.to_bytes()).unwrap());
+    }
+
+    #[test]
+    fn default_palette_matches_magica_voxel() {
+        let palette = default_palette();
+
+        assert_eq!(MAX_VOXEL_TYPES, palette.len());
+        assert_eq!([255, 255, 255, 255], palette[1]);
+        assert_eq!([255, 255, 204, 255], palette[2]);
+        assert_eq!([238, 0, 0, 255], palette[216]);
+        assert_eq!([17, 17, 17, 255], palette[255]);
+    }
+
+    #[test]
+    fn to_voxel_model_uses_file_palette() {
+        let model = two_voxels().to_voxel_model(0).unwrap();
+
+        assert_eq!(MAX_VOXEL_TYPES - 1, model.palette.len());
+        assert_eq!(Some([10, 20, 30]), model.palette.rgb(8));
+        assert_eq!(Some(&(true, true, 8, 0)), model.get_voxel(2, 1, 1));
+        assert_eq!(Some(&(true, true, 0, 0)), model.get_voxel(1, 0, 0));
+    }
+
+    #[test]
+    fn load_into_maps_colors_to_map_types() {
+        let mut chunk_manager = CbChunkManager::new();
+        let types = chunk_manager.palette.len();
+
+        let mut file = two_voxels();
+        file.palette[1] = [0, 255, 0, 255];
+        file.load_into(0, &mut chunk_manager, (4, 5, 6), 3).unwrap();
+
+        assert_eq!(types + 1, chunk_manager.palette.len());
+        assert_eq!(
+            &(true, true, types as u8, 0),
+            chunk_manager.get_voxel(6, 6, 7)
+        );
+        assert_eq!(
+            &(true, true, super::super::VOXEL_TYPE_GRASS, 0),
+            chunk_manager.get_voxel(5, 5, 6)
+        );
+    }
+
+    #[test]
+    fn load_into_too_large_returns_err() {
+        let mut chunk_manager = CbChunkManager::new();
+        let width = chunk_manager.get_voxel_width();
+
+        let result = two_voxels().load_into(0, &mut chunk_manager, (width - 2, 0, 0), 0);
+
+        assert!(result.unwrap_err().contains("does not fit"));
+    }
+
+    #[test]
+    fn from_voxel_model_round_trips_colors() {
+        let model = two_voxels().to_voxel_model(0).unwrap();
+
+        let file = VoxFile::from_voxel_model(&model).unwrap();
+        let reloaded = file.to_voxel_model(0).unwrap();
+
+        assert_eq!(model.active_voxels(), reloaded.active_voxels());
+        assert_eq!(model.palette, reloaded.palette);
+    }
+
+    #[test]
+    fn parse_voxel_outside_size_returns_err() {
+        let mut file = two_voxels();
+        file.models[0].voxels.push(((3, 0, 0), 1));
+
+        let result = VoxFile::parse(&file.to_bytes());
+
+        assert!(result.unwrap_err().contains("outside"));
+    }
+
+    #[test]
+    fn parse_not_vox_returns_err() {
+        assert!(VoxFile::parse(b"glTF").is_err());
+    }
+}
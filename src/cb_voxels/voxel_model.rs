@@ -0,0 +1,122 @@
+// Copyright 2020, Eric Olson, All rights reserved. Contact eric.rob.olson@gmail.com for questions regarding use.
+
+use super::palette::VoxelPalette;
+use super::{voxel_active, CbVoxel, CbVoxelChunk, CHUNK_SIZE};
+
+/// Voxels that aren't part of the map, such as a unit or prop. They are kept in chunks so they can be meshed the same
+/// way as the map, and have their own palette.
+#[derive(Debug, Clone)]
+pub struct VoxelModel {
+    size: (usize, usize, usize),
+    chunk_counts: (usize, usize, usize),
+    chunks: Vec<CbVoxelChunk>,
+    pub palette: VoxelPalette,
+}
+
+impl VoxelModel {
+    /// An empty model with the size in voxels.
+    pub fn new(size: (usize, usize, usize), palette: VoxelPalette) -> Self {
+        let chunks_for = |voxels: usize| (voxels + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let chunk_counts = (chunks_for(size.0), chunks_for(size.1), chunks_for(size.2));
+
+        return Self {
+            size: size,
+            chunk_counts: chunk_counts,
+            chunks: vec![CbVoxelChunk::empty(); chunk_counts.0 * chunk_counts.1 * chunk_counts.2],
+            palette: palette,
+        };
+    }
+
+    pub fn size(&self) -> (usize, usize, usize) {
+        return self.size;
+    }
+
+    /// The number of chunks along each axis.
+    pub fn chunk_counts(&self) -> (usize, usize, usize) {
+        return self.chunk_counts;
+    }
+
+    pub fn chunk(&self, x: usize, y: usize, z: usize) -> &CbVoxelChunk {
+        let (width, depth, _) = self.chunk_counts;
+        return &self.chunks[x + y * width + z * width * depth];
+    }
+
+    fn chunk_mut(&mut self, x: usize, y: usize, z: usize) -> &mut CbVoxelChunk {
+        let (width, depth, _) = self.chunk_counts;
+        return &mut self.chunks[x + y * width + z * width * depth];
+    }
+
+    /// The voxel at the coordinates, or none if they are outside of the model.
+    pub fn get_voxel(&self, x: usize, y: usize, z: usize) -> Option<&CbVoxel> {
+        if x >= self.size.0 || y >= self.size.1 || z >= self.size.2 {
+            return None;
+        }
+
+        let chunk = self.chunk(x / CHUNK_SIZE, y / CHUNK_SIZE, z / CHUNK_SIZE);
+        return Some(&chunk.voxels[x % CHUNK_SIZE][y % CHUNK_SIZE][z % CHUNK_SIZE]);
+    }
+
+    /// Replace the voxel at the coordinates. Returns false if they are outside of the model.
+    pub fn set_voxel(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        voxel: CbVoxel,
+        frame: usize,
+    ) -> bool {
+        if x >= self.size.0 || y >= self.size.1 || z >= self.size.2 {
+            return false;
+        }
+
+        let chunk = self.chunk_mut(x / CHUNK_SIZE, y / CHUNK_SIZE, z / CHUNK_SIZE);
+        chunk.frame_updated_at = frame;
+        chunk.voxels[x % CHUNK_SIZE][y % CHUNK_SIZE][z % CHUNK_SIZE] = voxel;
+
+        return true;
+    }
+
+    /// The coordinates and type of every active voxel.
+    pub fn active_voxels(&self) -> Vec<((usize, usize, usize), u8)> {
+        let mut voxels = vec![];
+
+        for z in 0..self.size.2 {
+            for y in 0..self.size.1 {
+                for x in 0..self.size.0 {
+                    if let Some(voxel) = self.get_voxel(x, y, z) {
+                        if voxel_active(voxel) {
+                            voxels.push(((x, y, z), voxel.2));
+                        }
+                    }
+                }
+            }
+        }
+
+        return voxels;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voxel_model_new_rounds_chunks_up() {
+        let model = VoxelModel::new((5, 4, 1), VoxelPalette::empty());
+
+        assert_eq!((2, 1, 1), model.chunk_counts());
+        assert!(model.active_voxels().is_empty());
+    }
+
+    #[test]
+    fn voxel_model_set_voxel_outside_returns_false() {
+        let mut model = VoxelModel::new((5, 4, 1), VoxelPalette::empty());
+
+        assert_eq!(true, model.set_voxel(4, 3, 0, (true, true, 7, 0), 2));
+        assert_eq!(false, model.set_voxel(5, 0, 0, (true, true, 7, 0), 2));
+
+        assert_eq!(vec![((4, 3, 0), 7)], model.active_voxels());
+        assert_eq!(2, model.chunk(1, 0, 0).frame_updated_at);
+        assert_eq!(None, model.get_voxel(0, 0, 1));
+    }
+}
@@ -8,7 +8,10 @@ use rmercury::{MercuryType, RMercuryBuilder, RMercuryExecutionResults};
 #[macro_use]
 pub mod cb_utility;
 // Non-macro Internal Crates
+pub mod cb_animation;
+pub mod cb_assets;
 pub mod cb_cmd_line;
+pub mod cb_datasheets;
 pub mod cb_graphics;
 pub mod cb_input;
 pub mod cb_inverse_kinematics;
@@ -31,10 +34,34 @@ fn main() {
     let mut game_interface;
     let mut builder;
     {
-        game_interface = CbSimulationInterface::new(CbSimulationModes::RtsMode);
-        game_interface.gfx.reset_cursor = false;
+        let mode = if std::env::args().any(|arg| arg == "--shooter") {
+            CbSimulationModes::ShooterMode
+        } else if std::env::args().any(|arg| arg == "--fighting") {
+            CbSimulationModes::FightingMode
+        } else {
+            CbSimulationModes::RtsMode
+        };
+
+        game_interface = CbSimulationInterface::new(mode);
+
+        if mode == CbSimulationModes::ShooterMode {
+            // Looking around is driven by how far the cursor moved from the center each frame
+            game_interface.gfx.reset_cursor = true;
+
+            input_context_manager.add_context(cb_input::contexts::SHOOTER_CONTEXT_ID);
+        } else if mode == CbSimulationModes::FightingMode {
+            game_interface.gfx.reset_cursor = false;
+
+            input_context_manager.add_context(cb_input::contexts::FIGHTING_CONTEXT_ID);
+        } else {
+            game_interface.gfx.reset_cursor = false;
+
+            input_context_manager.add_context(cb_input::contexts::RTS_CONTEXT_ID);
+            input_context_manager.add_context(cb_input::contexts::RTS_COMMAND_CONTEXT_ID);
+        }
 
-        input_context_manager.add_context(cb_input::contexts::RTS_CONTEXT_ID);
+        // The editor's camera only takes input while it's the active camera
+        input_context_manager.add_context(cb_input::contexts::VOXEL_EDITOR_CONTEXT_ID);
 
         builder = RMercuryBuilder::<CbSimulationInterface, CbGameInput, CbGameState>::new(
             &mut game_interface,
@@ -75,12 +102,21 @@ fn main() {
                     }
                 }
 
+                let local_player_id = r_mercury.get_local_player_id();
+
+                // RTS presses act on whatever is under the cursor
+                let rts_target = r_mercury
+                    .get_game_interface_mut()
+                    .get_rts_target(local_player_id);
+                input_context_manager.set_rts_target(rts_target);
+
                 let hardware_interface = cb_graphics::Sdl2HardwareInterface::from_gfx(
                     &r_mercury.get_game_interface_mut().gfx,
                     &current_frame_inputs,
                 );
 
-                let local_input = input_context_manager.get_rmercury_inputs(&hardware_interface);
+                let local_input =
+                    input_context_manager.get_rmercury_inputs(local_player_id, &hardware_interface);
                 r_mercury.add_local_input(&mut vec![local_input]);
 
                 let center_mouse = r_mercury.get_game_interface_mut().gfx.reset_cursor;